@@ -1 +1,857 @@
 \echo Use "ALTER EXTENSION pg_search UPDATE TO '0.18.0-libnare.0'" to load this file. \quit
+
+DROP FUNCTION IF EXISTS aggregate(index regclass, query SearchQueryInput, agg json, solve_mvcc bool, memory_limit bigint, bucket_limit bigint);
+/* <begin connected objects> */
+-- pg_search/src/api/aggregate.rs:28
+-- pg_search::api::aggregate::aggregate
+CREATE  FUNCTION "aggregate"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "agg" json, /* pgrx::datum::json::Json */
+    "solve_mvcc" bool DEFAULT true, /* bool */
+    "memory_limit" bigint DEFAULT 500000000, /* i64 */
+    "bucket_limit" bigint DEFAULT 65000, /* i64 */
+    "format" TEXT DEFAULT 'tantivy' /* alloc::string::String */
+) RETURNS jsonb /* core::result::Result<pgrx::datum::json::JsonB, alloc::boxed::Box<dyn core::error::Error>> */
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'aggregate_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/aggregate.rs:80
+-- pg_search::api::aggregate::timeseries
+CREATE  FUNCTION "timeseries"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "field" TEXT, /* alloc::string::String */
+    "interval" TEXT, /* alloc::string::String */
+    "metric" TEXT DEFAULT 'count', /* alloc::string::String */
+    "metric_field" TEXT DEFAULT NULL, /* core::option::Option<alloc::string::String> */
+    "solve_mvcc" bool DEFAULT true /* bool */
+) RETURNS TABLE (
+    "bucket" timestamp with time zone,  /* pgrx::datum::time_stamp_with_timezone::TimestampWithTimeZone */
+    "doc_count" bigint,  /* i64 */
+    "value" double precision  /* core::option::Option<f64> */
+)
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'timeseries_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/admin.rs:494
+-- pg_search::api::admin::force_merge_segments
+CREATE  FUNCTION "force_merge"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "segment_count" INT, /* i32 */
+    "min_deleted_percent" double precision DEFAULT NULL /* core::option::Option<f64> */
+) RETURNS TABLE (
+    "new_segments" bigint,  /* i64 */
+    "merged_segments" bigint  /* i64 */
+)
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'force_merge_segments_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/admin.rs:317
+-- pg_search::api::admin::index_segments
+CREATE  FUNCTION "index_segments"(
+    "index" regclass /* pgrx::rel::PgRelation */
+) RETURNS TABLE (
+    "index_name" TEXT,  /* alloc::string::String */
+    "segno" TEXT,  /* alloc::string::String */
+    "num_docs" bigint,  /* i64 */
+    "deleted_docs" bigint,  /* i64 */
+    "byte_size" bigint,  /* i64 */
+    "termdict_bytes" bigint,  /* i64 */
+    "postings_bytes" bigint,  /* i64 */
+    "positions_bytes" bigint,  /* i64 */
+    "fast_fields_bytes" bigint,  /* i64 */
+    "fieldnorms_bytes" bigint,  /* i64 */
+    "store_bytes" bigint,  /* i64 */
+    "deletes_bytes" bigint,  /* i64 */
+    "created_at" timestamp with time zone  /* core::option::Option<pgrx::datum::time_stamp_with_timezone::TimestampWithTimeZone> */
+)
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'index_segments_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/admin.rs:382
+-- pg_search::api::admin::index_field_sizes
+CREATE  FUNCTION "index_field_sizes"(
+    "index" regclass /* pgrx::rel::PgRelation */
+) RETURNS TABLE (
+    "field_name" TEXT,  /* core::option::Option<alloc::string::String> */
+    "total_bytes" bigint,  /* i64 */
+    "termdict_bytes" bigint,  /* i64 */
+    "postings_bytes" bigint,  /* i64 */
+    "positions_bytes" bigint,  /* i64 */
+    "fast_fields_bytes" bigint,  /* i64 */
+    "fieldnorms_bytes" bigint,  /* i64 */
+    "store_bytes" bigint  /* i64 */
+)
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'index_field_sizes_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/check.rs:96
+-- pg_search::api::check::validate_index
+CREATE  FUNCTION "validate_index"(
+    "index" regclass /* pgrx::rel::PgRelation */
+) RETURNS TABLE (
+    "index_name" TEXT,  /* alloc::string::String */
+    "check" TEXT,  /* alloc::string::String */
+    "segno" TEXT,  /* core::option::Option<alloc::string::String> */
+    "detail" TEXT  /* alloc::string::String */
+)
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'validate_index_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/builder_fns/paradedb.rs:85
+-- pg_search::api::builder_fns::paradedb::element
+CREATE  FUNCTION "element"(
+    "field" FieldName, /* pg_search::api::FieldName */
+    "query" SearchQueryInput /* pg_search::query::SearchQueryInput */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'element_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/query/element.rs:120
+-- pg_search::query::element::matching_elements
+CREATE  FUNCTION "matching_elements"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "field" FieldName, /* pg_search::api::FieldName */
+    "value" anyarray, /* pgrx::datum::anyarray::AnyArray */
+    "query" SearchQueryInput /* pg_search::query::SearchQueryInput */
+) RETURNS INT[] /* core::result::Result<alloc::vec::Vec<i32>, anyhow::Error> */
+    STABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'matching_elements_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/admin.rs:380
+-- pg_search::api::admin::prewarm_segments
+CREATE  FUNCTION "prewarm_segments"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "segments" TEXT[] DEFAULT NULL /* core::option::Option<alloc::vec::Vec<alloc::string::String>> */
+) RETURNS bigint /* core::result::Result<i64, anyhow::Error> */
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'prewarm_segments_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/reindex.rs:48
+-- pg_search::api::reindex::reindex_where
+CREATE  FUNCTION "reindex_where"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "predicate" TEXT /* &str */
+) RETURNS bigint /* core::result::Result<i64, anyhow::Error> */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'reindex_where_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/builder_fns/pdb.rs:516
+-- pg_search::api::builder_fns::pdb::pdb::_9b41d7::ip_range
+CREATE  FUNCTION "ip_range"(
+    "field" FieldName, /* pg_search::api::FieldName */
+    "cidr" inet /* pgrx::datum::inet::Inet */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'ip_range_bfn_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/builder_fns/pdb.rs:516
+-- pg_search::api::builder_fns::pdb::pdb::ip_range
+CREATE  FUNCTION pdb."ip_range"(
+    "cidr" inet /* pgrx::datum::inet::Inet */
+) RETURNS pdb.Query /* pg_search::query::pdb_query::pdb::Query */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'ip_range_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/builder_fns/paradedb.rs:362
+-- pg_search::api::builder_fns::paradedb::range_term_with_operator
+CREATE  FUNCTION "range_term_with_operator"(
+    "field" FieldName, /* pg_search::api::FieldName */
+    "operator" TEXT, /* alloc::string::String */
+    "value" anyelement /* pgrx::datum::anyelement::AnyElement */
+) RETURNS SearchQueryInput /* core::result::Result<pg_search::query::SearchQueryInput, anyhow::Error> */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'range_term_with_operator_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/multi_search.rs:46
+-- pg_search::api::multi_search::multi_search
+CREATE  FUNCTION "multi_search"(
+    "indexes" oid[], /* alloc::vec::Vec<pgrx_pg_sys::submodules::oids::Oid> */
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "limit" bigint DEFAULT 10 /* i64 */
+) RETURNS TABLE (
+    "tableoid" oid,  /* pgrx_pg_sys::submodules::oids::Oid */
+    "ctid" tid,  /* pgrx_pg_sys::include::pg17::ItemPointerData */
+    "score" real  /* f32 */
+)
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'multi_search_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/alias.rs:35
+-- index_aliases
+CREATE TABLE paradedb.index_aliases (
+    alias TEXT PRIMARY KEY,
+    indexrelid regclass NOT NULL,
+    -- when the alias was created or last swapped
+    updated_at timestamptz NOT NULL DEFAULT now()
+);
+SELECT pg_catalog.pg_extension_config_dump('paradedb.index_aliases', '');
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/alias.rs:58
+-- pg_search::api::alias::create_alias
+CREATE  FUNCTION "create_alias"(
+    "alias" TEXT, /* &str */
+    "index" regclass /* pgrx::rel::PgRelation */
+) RETURNS void /* core::result::Result<(), anyhow::Error> */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'create_alias_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/alias.rs:73
+-- pg_search::api::alias::swap_alias
+CREATE  FUNCTION "swap_alias"(
+    "alias" TEXT, /* &str */
+    "index" regclass /* pgrx::rel::PgRelation */
+) RETURNS TEXT /* core::result::Result<alloc::string::String, anyhow::Error> */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'swap_alias_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/alias.rs:89
+-- pg_search::api::alias::drop_alias
+CREATE  FUNCTION "drop_alias"(
+    "alias" TEXT /* &str */
+) RETURNS void /* core::result::Result<(), anyhow::Error> */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'drop_alias_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/alias.rs:101
+-- pg_search::api::alias::resolve_alias
+CREATE  FUNCTION "resolve_alias"(
+    "alias" TEXT /* &str */
+) RETURNS oid /* core::result::Result<pgrx_pg_sys::submodules::oids::Oid, anyhow::Error> */
+STABLE STRICT PARALLEL SAFE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'resolve_alias_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/rollover.rs:37
+-- pg_search::api::rollover::rollover
+CREATE  FUNCTION "rollover"(
+    "alias" TEXT, /* &str */
+    "max_size" bigint DEFAULT NULL, /* core::option::Option<i64> */
+    "max_age" interval DEFAULT NULL /* core::option::Option<pgrx::datum::interval::Interval> */
+) RETURNS TEXT /* core::result::Result<core::option::Option<alloc::string::String>, anyhow::Error> */
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'rollover_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/snapshot.rs:82
+-- pg_search::api::snapshot::snapshot_index
+CREATE  FUNCTION "snapshot_index"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "path" TEXT /* &str */
+) RETURNS bigint /* core::result::Result<i64, anyhow::Error> */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'snapshot_index_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/snapshot.rs:165
+-- pg_search::api::snapshot::restore_index
+CREATE  FUNCTION "restore_index"(
+    "path" TEXT /* &str */
+) RETURNS TEXT /* core::result::Result<alloc::string::String, anyhow::Error> */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'restore_index_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/publication.rs:30
+-- index_definitions
+CREATE TABLE paradedb.index_definitions (
+    -- the schema-qualified name of the index
+    index_name TEXT PRIMARY KEY,
+    -- the `CREATE INDEX` statement that recreates it
+    definition TEXT NOT NULL
+);
+SELECT pg_catalog.pg_extension_config_dump('paradedb.index_definitions', '');
+
+CREATE FUNCTION paradedb.capture_index_definitions() RETURNS event_trigger
+LANGUAGE plpgsql SECURITY DEFINER SET search_path = pg_catalog, pg_temp AS $$
+DECLARE
+    command record;
+BEGIN
+    -- a renamed index is recorded under its new name
+    DELETE FROM paradedb.index_definitions WHERE to_regclass(index_name) IS NULL;
+
+    FOR command IN
+        SELECT objid, object_identity FROM pg_event_trigger_ddl_commands()
+        WHERE object_type = 'index'
+    LOOP
+        IF EXISTS (
+            SELECT 1 FROM pg_class c JOIN pg_am am ON am.oid = c.relam
+            WHERE c.oid = command.objid AND am.amname = 'bm25'
+        ) THEN
+            INSERT INTO paradedb.index_definitions (index_name, definition)
+            VALUES (command.object_identity, pg_get_indexdef(command.objid))
+            ON CONFLICT (index_name) DO UPDATE SET definition = EXCLUDED.definition
+            WHERE index_definitions.definition IS DISTINCT FROM EXCLUDED.definition;
+        END IF;
+    END LOOP;
+END;
+$$;
+
+CREATE FUNCTION paradedb.capture_dropped_indexes() RETURNS event_trigger
+LANGUAGE plpgsql SECURITY DEFINER SET search_path = pg_catalog, pg_temp AS $$
+BEGIN
+    DELETE FROM paradedb.index_definitions
+    WHERE index_name IN (
+        SELECT object_identity FROM pg_event_trigger_dropped_objects()
+        WHERE object_type = 'index'
+    );
+END;
+$$;
+
+CREATE FUNCTION paradedb.apply_index_definition() RETURNS trigger
+LANGUAGE plpgsql SET search_path = pg_catalog, pg_temp AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        EXECUTE format('DROP INDEX IF EXISTS %s', OLD.index_name);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') AND to_regclass(NEW.index_name) IS NULL THEN
+        EXECUTE NEW.definition;
+    END IF;
+    RETURN NULL;
+END;
+$$;
+
+-- only fires for changes applied by logical replication
+CREATE TRIGGER apply_index_definition
+AFTER INSERT OR UPDATE OR DELETE ON paradedb.index_definitions
+FOR EACH ROW EXECUTE FUNCTION paradedb.apply_index_definition();
+ALTER TABLE paradedb.index_definitions ENABLE REPLICA TRIGGER apply_index_definition;
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/publication.rs:101
+-- pg_search::api::publication::publish_index_definitions
+CREATE  FUNCTION "publish_index_definitions"(
+    "publication" TEXT /* &str */
+) RETURNS void /* core::result::Result<(), anyhow::Error> */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'publish_index_definitions_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/admin.rs:777
+-- pg_search::api::admin::upgrade_index
+CREATE  FUNCTION "upgrade_index"(
+    "index" regclass /* pgrx::rel::PgRelation */
+) RETURNS TABLE (
+    "index_name" TEXT,  /* alloc::string::String */
+    "from_version" INT,  /* i32 */
+    "to_version" INT  /* i32 */
+)
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'upgrade_index_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/admin.rs:382
+-- pg_search::api::admin::index_dead_docs
+CREATE  FUNCTION "index_dead_docs"(
+    "index" regclass /* pgrx::rel::PgRelation */
+) RETURNS TABLE (
+    "index_name" TEXT,  /* alloc::string::String */
+    "live_docs" bigint,  /* i64 */
+    "dead_docs" bigint,  /* i64 */
+    "dead_percent" double precision,  /* f64 */
+    "reclaimable_segments" bigint  /* i64 */
+)
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'index_dead_docs_wrapper';
+/* </end connected objects> */
+
+DROP FUNCTION IF EXISTS snippet(field anyelement, start_tag text, end_tag text, max_num_chars int);
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/snippet.rs:84
+-- pg_search::postgres::customscan::pdbscan::projections::snippet::snippet_from_relation
+CREATE  FUNCTION "snippet"(
+    "field" anyelement, /* pgrx::datum::anyelement::AnyElement */
+    "start_tag" TEXT DEFAULT '<b>', /* alloc::string::String */
+    "end_tag" TEXT DEFAULT '</b>', /* alloc::string::String */
+    "max_num_chars" INT DEFAULT 150, /* i32 */
+    "fragment_size" INT DEFAULT NULL, /* core::option::Option<i32> */
+    "number_of_fragments" INT DEFAULT 1, /* i32 */
+    "fragment_separator" TEXT DEFAULT ' ... ', /* alloc::string::String */
+    "escape_html" bool DEFAULT true, /* bool */
+    "count_graphemes" bool DEFAULT false /* bool */
+) RETURNS TEXT /* core::option::Option<alloc::string::String> */
+STABLE PARALLEL SAFE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'snippet_from_relation_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/snippet.rs:102
+-- requires:
+--   snippet_from_relation
+--   placeholder_support
+ALTER FUNCTION snippet SUPPORT placeholder_support;
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/snippet.rs:114
+-- pg_search::postgres::customscan::pdbscan::projections::snippet::match_positions_from_relation
+CREATE  FUNCTION "match_positions"(
+    "field" anyelement /* pgrx::datum::anyelement::AnyElement */
+) RETURNS jsonb /* core::option::Option<pgrx::datum::json::JsonB> */
+STRICT STABLE PARALLEL SAFE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'match_positions_from_relation_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/snippet.rs:135
+-- requires:
+--   match_positions_from_relation
+--   placeholder_support
+ALTER FUNCTION match_positions SUPPORT placeholder_support;
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/snippet.rs:110
+-- pg_search::postgres::customscan::pdbscan::projections::snippet::highlights_from_relation
+CREATE  FUNCTION "highlights"(
+    "fields" anyarray, /* pgrx::datum::anyarray::AnyArray */
+    "start_tag" TEXT DEFAULT '<b>', /* alloc::string::String */
+    "end_tag" TEXT DEFAULT '</b>', /* alloc::string::String */
+    "max_num_chars" INT DEFAULT 150, /* i32 */
+    "fragment_size" INT DEFAULT NULL, /* core::option::Option<i32> */
+    "number_of_fragments" INT DEFAULT 1, /* i32 */
+    "fragment_separator" TEXT DEFAULT ' ... ', /* alloc::string::String */
+    "escape_html" bool DEFAULT true, /* bool */
+    "count_graphemes" bool DEFAULT false /* bool */
+) RETURNS jsonb /* core::option::Option<pgrx::datum::json::JsonB> */
+STABLE PARALLEL SAFE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'highlights_from_relation_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/snippet.rs:154
+-- requires:
+--   highlights_from_relation
+--   placeholder_support
+ALTER FUNCTION highlights SUPPORT placeholder_support;
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/snippet.rs:149
+-- pg_search::postgres::customscan::pdbscan::projections::snippet::snippet_fragments_from_relation
+CREATE  FUNCTION "snippet_fragments"(
+    "field" anyelement, /* pgrx::datum::anyelement::AnyElement */
+    "start_tag" TEXT DEFAULT '<b>', /* alloc::string::String */
+    "end_tag" TEXT DEFAULT '</b>', /* alloc::string::String */
+    "fragment_size" INT DEFAULT 150, /* i32 */
+    "number_of_fragments" INT DEFAULT 3, /* i32 */
+    "escape_html" bool DEFAULT true, /* bool */
+    "count_graphemes" bool DEFAULT false /* bool */
+) RETURNS jsonb /* core::option::Option<pgrx::datum::json::JsonB> */
+STRICT STABLE PARALLEL SAFE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'snippet_fragments_from_relation_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/snippet.rs:193
+-- requires:
+--   snippet_fragments_from_relation
+--   placeholder_support
+ALTER FUNCTION snippet_fragments SUPPORT placeholder_support;
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/admin.rs:436
+-- pg_search::api::admin::index_stats_data
+CREATE  FUNCTION "index_stats_data"() RETURNS TABLE (
+    "index_oid" oid,  /* pgrx_pg_sys::submodules::oids::Oid */
+    "queries" bigint,  /* i64 */
+    "mean_time_ms" double precision,  /* core::option::Option<f64> */
+    "p95_time_ms" double precision,  /* core::option::Option<f64> */
+    "docs_scanned" bigint,  /* i64 */
+    "blocks_hit" bigint,  /* i64 */
+    "blocks_read" bigint,  /* i64 */
+    "hit_ratio" double precision,  /* core::option::Option<f64> */
+    "index_blocks_hit" bigint,  /* i64 */
+    "index_blocks_read" bigint,  /* i64 */
+    "index_hit_ratio" double precision  /* core::option::Option<f64> */
+)
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'index_stats_data_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/admin.rs:472
+-- pg_search::api::admin::index_stats_reset
+CREATE  FUNCTION "index_stats_reset"(
+    "index" regclass DEFAULT NULL /* core::option::Option<pgrx::rel::PgRelation> */
+) RETURNS void
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'index_stats_reset_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/admin.rs:1081
+-- requires:
+--   index_stats_data
+--   index_stats_reset
+create view paradedb.index_stats as
+select index_oid::regclass as index,
+       queries,
+       mean_time_ms,
+       p95_time_ms,
+       docs_scanned,
+       blocks_hit,
+       blocks_read,
+       hit_ratio,
+       index_blocks_hit,
+       index_blocks_read,
+       index_hit_ratio
+from paradedb.index_stats_data();
+
+GRANT SELECT ON paradedb.index_stats TO PUBLIC;
+REVOKE EXECUTE ON FUNCTION paradedb.index_stats_reset(regclass) FROM PUBLIC;
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/admin.rs:486
+-- pg_search::api::admin::metrics
+CREATE  FUNCTION "metrics"() RETURNS TABLE (
+    "metric" TEXT,  /* alloc::string::String */
+    "kind" TEXT,  /* alloc::string::String */
+    "index" TEXT,  /* alloc::string::String */
+    "le" double precision,  /* core::option::Option<f64> */
+    "value" double precision  /* f64 */
+)
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'metrics_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/admin.rs:586
+-- pg_search::api::admin::metrics_text
+CREATE  FUNCTION "metrics_text"() RETURNS TEXT /* alloc::string::String */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'metrics_text_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/hybrid.rs:38
+-- pg_search::postgres::customscan::pdbscan::projections::hybrid::hybrid_from_relation
+CREATE  FUNCTION "hybrid"(
+    "key" anyelement, /* pgrx::datum::anyelement::AnyElement */
+    "distance" double precision, /* f64 */
+    "fusion" TEXT DEFAULT 'rrf', /* alloc::string::String */
+    "weight" double precision DEFAULT 0.5, /* f64 */
+    "k" INT DEFAULT 60 /* i32 */
+) RETURNS double precision /* core::option::Option<f64> */
+STRICT STABLE PARALLEL SAFE COST 1
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'hybrid_from_relation_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/es_search.rs:48
+-- pg_search::api::es_search::es_search
+CREATE  FUNCTION "es_search"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "request" jsonb /* pgrx::datum::json::JsonB */
+) RETURNS jsonb /* core::result::Result<pgrx::datum::json::JsonB, anyhow::Error> */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'es_search_wrapper';
+/* </end connected objects> */
+
+DROP FUNCTION IF EXISTS parse(query_string text, lenient bool, conjunction_mode bool);
+/* <begin connected objects> */
+-- pg_search/src/api/builder_fns/paradedb.rs:170
+-- pg_search::api::builder_fns::paradedb::parse
+CREATE  FUNCTION "parse"(
+    "query_string" TEXT, /* alloc::string::String */
+    "lenient" bool DEFAULT NULL, /* core::option::Option<bool> */
+    "conjunction_mode" bool DEFAULT NULL, /* core::option::Option<bool> */
+    "syntax" TEXT DEFAULT NULL /* core::option::Option<alloc::string::String> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+IMMUTABLE PARALLEL SAFE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'parse_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/bulk_index.rs:43
+-- pg_search::api::bulk_index::bulk_index
+CREATE  FUNCTION "bulk_index"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "path" TEXT, /* &str */
+    "batch_size" INT DEFAULT 10000 /* i32 */
+) RETURNS bigint /* core::result::Result<i64, anyhow::Error> */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'bulk_index_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/export_parquet.rs:101
+-- pg_search::api::export_parquet::export_parquet
+CREATE  FUNCTION "export_parquet"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "path" TEXT, /* &str */
+    "fields" TEXT[] DEFAULT NULL /* core::option::Option<alloc::vec::Vec<alloc::string::String>> */
+) RETURNS bigint /* core::result::Result<i64, anyhow::Error> */
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'export_parquet_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/postgres/fdw.rs:46
+-- pg_search::postgres::fdw::tantivy_fdw_handler
+
+CREATE FUNCTION tantivy_fdw_handler() RETURNS fdw_handler STRICT LANGUAGE c AS 'MODULE_PATHNAME', 'tantivy_fdw_handler_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/postgres/fdw.rs:65
+-- pg_search::postgres::fdw::tantivy_fdw_validator
+CREATE  FUNCTION "tantivy_fdw_validator"(
+    "options" TEXT[], /* alloc::vec::Vec<alloc::string::String> */
+    "catalog" oid /* pgrx_pg_sys::submodules::oids::Oid */
+) RETURNS void /* core::result::Result<(), anyhow::Error> */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'tantivy_fdw_validator_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/postgres/fdw.rs:83
+-- tantivy_fdw
+CREATE FOREIGN DATA WRAPPER tantivy_fdw HANDLER tantivy_fdw_handler VALIDATOR tantivy_fdw_validator;
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/maintain.rs:48
+-- pg_search::api::maintain::maintain
+CREATE  FUNCTION "maintain"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "time_budget" interval DEFAULT '1 minute', /* pgrx::datum::interval::Interval */
+    "io_budget" bigint DEFAULT NULL /* core::option::Option<i64> */
+) RETURNS TABLE (
+    "index_name" TEXT,  /* alloc::string::String */
+    "merged_segments" bigint,  /* i64 */
+    "rewritten_segments" bigint,  /* i64 */
+    "bytes_rewritten" bigint,  /* i64 */
+    "live_docs" bigint,  /* i64 */
+    "finished" bool  /* bool */
+)
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'maintain_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/builder_fns/paradedb.rs:192
+-- pg_search::api::builder_fns::paradedb::tsquery_field
+CREATE  FUNCTION "tsquery"(
+    "field" FieldName, /* pg_search::api::FieldName */
+    "query" tsquery /* pg_search::query::tsquery::TsQuery */
+) RETURNS SearchQueryInput /* core::result::Result<pg_search::query::SearchQueryInput, anyhow::Error> */
+IMMUTABLE STRICT PARALLEL SAFE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'tsquery_field_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/builder_fns/paradedb.rs:204
+-- pg_search::api::builder_fns::paradedb::tsquery_fields
+CREATE  FUNCTION "tsquery"(
+    "fields" TEXT[], /* alloc::vec::Vec<alloc::string::String> */
+    "query" tsquery, /* pg_search::query::tsquery::TsQuery */
+    "weights" TEXT /* core::option::Option<alloc::string::String> */
+) RETURNS SearchQueryInput /* core::result::Result<pg_search::query::SearchQueryInput, anyhow::Error> */
+IMMUTABLE PARALLEL SAFE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'tsquery_fields_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/operator/atatat.rs:62
+-- pg_search::api::operator::atatat::search_with_tsquery
+CREATE  FUNCTION "search_with_tsquery"(
+    "_element" anyelement, /* pgrx::datum::anyelement::AnyElement */
+    "query" tsquery /* pg_search::query::tsquery::TsQuery */
+) RETURNS bool /* bool */
+IMMUTABLE STRICT PARALLEL SAFE COST 1000000000
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'search_with_tsquery_wrapper';
+-- pg_search/src/api/operator/atatat.rs:62
+-- pg_search::api::operator::atatat::search_with_tsquery
+CREATE OPERATOR pg_catalog.@@@ (
+    PROCEDURE="search_with_tsquery",
+    LEFTARG=anyelement, /* pgrx::datum::anyelement::AnyElement */
+    RIGHTARG=tsquery /* pg_search::query::tsquery::TsQuery */
+    );
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/operator/atatat.rs:180
+-- pg_search::api::operator::atatat::atatat_tsquery_support
+CREATE  FUNCTION "atatat_tsquery_support"(
+    "arg" internal /* pgrx::datum::internal::Internal */
+) RETURNS internal /* pg_search::api::operator::ReturnedNodePointer */
+IMMUTABLE PARALLEL SAFE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'atatat_tsquery_support_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/operator/atatat.rs:289
+-- requires:
+--   search_with_tsquery
+--   atatat_tsquery_support
+ALTER FUNCTION paradedb.search_with_tsquery SUPPORT paradedb.atatat_tsquery_support;
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/index_config.rs:52
+-- pg_search::api::index_config::dump_index_config
+CREATE  FUNCTION "dump_index_config"(
+    "index" regclass /* pgrx::rel::PgRelation */
+) RETURNS jsonb /* core::result::Result<pgrx::datum::json::JsonB, anyhow::Error> */
+STRICT STABLE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'dump_index_config_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/index_config.rs:109
+-- pg_search::api::index_config::create_index_from_config
+CREATE  FUNCTION "create_index_from_config"(
+    "config" jsonb /* pgrx::datum::json::JsonB */
+) RETURNS TEXT /* core::result::Result<alloc::string::String, anyhow::Error> */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'create_index_from_config_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/index_config.rs:131
+-- pg_search::api::index_config::create_index_from_config_on
+CREATE  FUNCTION "create_index_from_config"(
+    "config" TEXT, /* &str */
+    "table_name" regclass /* pgrx::rel::PgRelation */
+) RETURNS TEXT /* core::result::Result<alloc::string::String, anyhow::Error> */
+STRICT
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'create_index_from_config_on_wrapper';
+/* </end connected objects> */
+
+DROP FUNCTION IF EXISTS "match"(field FieldName, value text, tokenizer jsonb, distance int, transposition_cost_one bool, prefix bool, conjunction_mode bool);
+/* <begin connected objects> */
+-- pg_search/src/api/builder_fns/pdb.rs:90
+-- pg_search::api::builder_fns::pdb::pdb::_607293::match
+CREATE  FUNCTION "match"(
+    "field" FieldName, /* pg_search::api::FieldName */
+    "value" TEXT, /* alloc::string::String */
+    "tokenizer" jsonb DEFAULT NULL, /* core::option::Option<pgrx::datum::json::JsonB> */
+    "distance" INT DEFAULT NULL, /* core::option::Option<i32> */
+    "transposition_cost_one" bool DEFAULT NULL, /* core::option::Option<bool> */
+    "prefix" bool DEFAULT NULL, /* core::option::Option<bool> */
+    "conjunction_mode" bool DEFAULT NULL, /* core::option::Option<bool> */
+    "stopwords" bool DEFAULT NULL /* core::option::Option<bool> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'match_query_bfn_wrapper';
+/* </end connected objects> */
+
+DROP FUNCTION IF EXISTS pdb."match"(value text, tokenizer jsonb, distance int, transposition_cost_one bool, prefix bool, conjunction_mode bool);
+/* <begin connected objects> */
+-- pg_search/src/api/builder_fns/pdb.rs:90
+-- pg_search::api::builder_fns::pdb::pdb::match
+CREATE  FUNCTION pdb."match"(
+    "value" TEXT, /* alloc::string::String */
+    "tokenizer" jsonb DEFAULT NULL, /* core::option::Option<pgrx::datum::json::JsonB> */
+    "distance" INT DEFAULT NULL, /* core::option::Option<i32> */
+    "transposition_cost_one" bool DEFAULT NULL, /* core::option::Option<bool> */
+    "prefix" bool DEFAULT NULL, /* core::option::Option<bool> */
+    "conjunction_mode" bool DEFAULT NULL, /* core::option::Option<bool> */
+    "stopwords" bool DEFAULT NULL /* core::option::Option<bool> */
+) RETURNS pdb.Query /* pg_search::query::pdb_query::pdb::Query */
+    IMMUTABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'match_query_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/validate_query.rs:47
+-- pg_search::api::validate_query::validate_query
+CREATE  FUNCTION "validate_query"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "query" SearchQueryInput /* pg_search::query::SearchQueryInput */
+) RETURNS TABLE (
+    "path" TEXT,  /* alloc::string::String */
+    "field" TEXT,  /* core::option::Option<alloc::string::String> */
+    "error" TEXT  /* alloc::string::String */
+)
+STRICT STABLE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'validate_query_wrapper';
+/* </end connected objects> */
+/* <begin connected objects> */
+-- pg_search/src/api/validate_query.rs:80
+-- pg_search::api::validate_query::validate_query_jsonb
+CREATE  FUNCTION "validate_query"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "query" jsonb /* pgrx::datum::json::JsonB */
+) RETURNS TABLE (
+    "path" TEXT,  /* alloc::string::String */
+    "field" TEXT,  /* core::option::Option<alloc::string::String> */
+    "error" TEXT  /* alloc::string::String */
+)
+STRICT STABLE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'validate_query_jsonb_wrapper';
+/* </end connected objects> */
+DROP FUNCTION IF EXISTS timeseries(index regclass, query SearchQueryInput, field TEXT, interval TEXT, metric TEXT, metric_field TEXT, solve_mvcc bool);
+/* <begin connected objects> */
+-- pg_search/src/api/aggregate.rs:87
+-- pg_search::api::aggregate::timeseries
+CREATE  FUNCTION "timeseries"(
+    "index" regclass, /* pgrx::rel::PgRelation */
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "field" TEXT, /* alloc::string::String */
+    "interval" TEXT, /* alloc::string::String */
+    "metric" TEXT DEFAULT 'count', /* alloc::string::String */
+    "metric_field" TEXT DEFAULT NULL, /* core::option::Option<alloc::string::String> */
+    "solve_mvcc" bool DEFAULT true, /* bool */
+    "time_zone" TEXT DEFAULT NULL /* core::option::Option<alloc::string::String> */
+) RETURNS TABLE (
+    "bucket" timestamp with time zone,  /* pgrx::datum::time_stamp_with_timezone::TimestampWithTimeZone */
+    "doc_count" bigint,  /* i64 */
+    "value" double precision  /* core::option::Option<f64> */
+)
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'timeseries_wrapper';
+/* </end connected objects> */
@@ -0,0 +1,91 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! A per-backend cache of aggregation results.
+//!
+//! Dashboards tend to re-run the same heavy aggregations every few seconds against an index that
+//! rarely changes in between.  Results are keyed by the request and by the index's visible
+//! segments, along with how many deleted documents each one has, so inserts, merges, and vacuums
+//! all make previous results unreachable without any explicit invalidation.
+//!
+//! Only aggregations that don't solve MVCC are cached.  Which rows those that do see depends on
+//! the heap and on the caller's snapshot, and a `DELETE` changes neither the segments nor their
+//! deleted documents until the table is vacuumed.
+
+use crate::index::lru::LruCache;
+use crate::index::mvcc::MvccSatisfies;
+use crate::index::reader::index::SearchIndexReader;
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::stats;
+use crate::query::SearchQueryInput;
+use parking_lot::Mutex;
+use pgrx::pg_sys;
+use std::sync::LazyLock;
+use tantivy::index::SegmentId;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AggregateCacheKey {
+    indexrelid: pg_sys::Oid,
+    query: String,
+    aggregations: String,
+    memory_limit: u64,
+    bucket_limit: u32,
+    segments: Vec<(SegmentId, u32)>,
+}
+
+impl AggregateCacheKey {
+    pub fn new(
+        index: &PgSearchRelation,
+        query: &SearchQueryInput,
+        aggregations: &serde_json::Value,
+        memory_limit: u64,
+        bucket_limit: u32,
+    ) -> anyhow::Result<Self> {
+        let reader =
+            SearchIndexReader::open(index, SearchQueryInput::All, false, MvccSatisfies::Snapshot)?;
+        let mut segments = reader
+            .segment_readers()
+            .iter()
+            .map(|segment| (segment.segment_id(), segment.num_deleted_docs()))
+            .collect::<Vec<_>>();
+        segments.sort_unstable();
+
+        Ok(Self {
+            indexrelid: index.oid(),
+            query: serde_json::to_string(query)?,
+            aggregations: serde_json::to_string(aggregations)?,
+            memory_limit,
+            bucket_limit,
+            segments,
+        })
+    }
+}
+
+static CACHE: LazyLock<Mutex<LruCache<AggregateCacheKey, serde_json::Value>>> =
+    LazyLock::new(Default::default);
+
+/// Look up a cached result, marking it as the most recently used.
+pub fn get(key: &AggregateCacheKey) -> Option<serde_json::Value> {
+    let result = CACHE.lock().get(key).cloned();
+    stats::record_cache_lookup(key.indexrelid, result.is_some());
+    result
+}
+
+/// Cache a result, evicting the least recently used ones beyond `capacity`.
+pub fn insert(key: AggregateCacheKey, result: serde_json::Value, capacity: usize) {
+    CACHE.lock().insert(key, result, capacity);
+}
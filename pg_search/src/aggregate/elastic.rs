@@ -0,0 +1,291 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Elasticsearch compatibility for `paradedb.aggregate()`.
+//!
+//! Tantivy's aggregation JSON is already modeled on Elasticsearch's, so most requests pass through
+//! untouched.  This module accepts an Elasticsearch search body (`{"size": 0, "aggs": {...}}`),
+//! translates the handful of request options that tantivy spells differently, and wraps the result
+//! in the shape of an Elasticsearch search response.
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Map, Value};
+
+const SUB_AGGREGATION_KEYS: [&str; 2] = ["aggs", "aggregations"];
+
+/// Calendar intervals that are also a fixed length of time.  Weeks, months, quarters and years
+/// all need calendar-aware bucketing, which tantivy doesn't do.
+const FIXED_CALENDAR_INTERVALS: [(&str, &str); 8] = [
+    ("second", "1s"),
+    ("1s", "1s"),
+    ("minute", "1m"),
+    ("1m", "1m"),
+    ("hour", "1h"),
+    ("1h", "1h"),
+    ("day", "1d"),
+    ("1d", "1d"),
+];
+
+/// Which dialect `paradedb.aggregate()` speaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateFormat {
+    Tantivy,
+    Elasticsearch,
+}
+
+impl AggregateFormat {
+    pub fn parse(format: &str) -> Result<Self> {
+        match format {
+            "tantivy" => Ok(AggregateFormat::Tantivy),
+            "elasticsearch" | "es" => Ok(AggregateFormat::Elasticsearch),
+            other => {
+                bail!("unknown aggregate format `{other}`, expected `tantivy` or `elasticsearch`")
+            }
+        }
+    }
+}
+
+/// The `meta` objects of an Elasticsearch request, which are echoed back in the response.
+#[derive(Debug, Default, PartialEq)]
+pub struct MetaTree {
+    meta: Vec<(String, Value)>,
+    children: Vec<(String, MetaTree)>,
+}
+
+/// Translate an Elasticsearch search body into a tantivy aggregation request.
+pub fn translate_request(body: Value) -> Result<(Value, MetaTree)> {
+    let Value::Object(mut body) = body else {
+        bail!("an Elasticsearch search body must be a JSON object");
+    };
+    if body.contains_key("query") {
+        bail!("`query` is not supported in an Elasticsearch search body, pass it as the search query argument instead");
+    }
+    match body.remove("size") {
+        None => {}
+        Some(size) if size.as_u64() == Some(0) => {}
+        Some(_) => bail!("only `\"size\": 0` is supported, as no hits are returned"),
+    }
+    body.remove("track_total_hits");
+
+    let mut aggs = match (body.remove("aggs"), body.remove("aggregations")) {
+        (Some(_), Some(_)) => bail!("only one of `aggs` and `aggregations` may be specified"),
+        (Some(aggs), None) | (None, Some(aggs)) => aggs,
+        (None, None) => Value::Object(Map::new()),
+    };
+    if let Some(key) = body.keys().next() {
+        bail!("`{key}` is not supported in an Elasticsearch search body");
+    }
+    let Some(aggs_map) = aggs.as_object_mut() else {
+        bail!("`aggs` must be a JSON object");
+    };
+    let meta = translate_level(aggs_map)?;
+    Ok((aggs, meta))
+}
+
+fn translate_level(aggs: &mut Map<String, Value>) -> Result<MetaTree> {
+    let mut tree = MetaTree::default();
+    for (name, def) in aggs.iter_mut() {
+        let Some(def) = def.as_object_mut() else {
+            bail!("aggregation `{name}` must be a JSON object");
+        };
+        if let Some(meta) = def.remove("meta") {
+            tree.meta.push((name.clone(), meta));
+        }
+        for key in SUB_AGGREGATION_KEYS {
+            if let Some(Value::Object(sub_aggs)) = def.get_mut(key) {
+                let subtree = translate_level(sub_aggs)?;
+                if subtree != MetaTree::default() {
+                    tree.children.push((name.clone(), subtree));
+                }
+            }
+        }
+
+        if let Some(Value::Object(date_histogram)) = def.get_mut("date_histogram") {
+            translate_date_histogram(name, date_histogram)?;
+        }
+        if let Some(Value::Object(terms)) = def.get_mut("terms") {
+            translate_terms(terms);
+        }
+    }
+    Ok(tree)
+}
+
+fn translate_date_histogram(name: &str, date_histogram: &mut Map<String, Value>) -> Result<()> {
+    if let Some(interval) = date_histogram.remove("calendar_interval") {
+        let interval = interval.as_str().ok_or_else(|| {
+            anyhow!("date_histogram `{name}`: `calendar_interval` must be a string")
+        })?;
+        let fixed = FIXED_CALENDAR_INTERVALS
+            .iter()
+            .find(|(calendar, _)| *calendar == interval)
+            .map(|(_, fixed)| *fixed)
+            .ok_or_else(|| {
+                anyhow!("date_histogram `{name}`: `calendar_interval` `{interval}` is not supported, use a `fixed_interval` instead")
+            })?;
+        date_histogram.insert("fixed_interval".into(), fixed.into());
+    }
+    // the pre-7.2 spelling
+    if let Some(interval) = date_histogram.remove("interval") {
+        date_histogram.entry("fixed_interval").or_insert(interval);
+    }
+    Ok(())
+}
+
+fn translate_terms(terms: &mut Map<String, Value>) {
+    // the pre-6.0 spelling of `_key`
+    if let Some(Value::Object(order)) = terms.get_mut("order") {
+        if let Some(direction) = order.remove("_term") {
+            order.insert("_key".into(), direction);
+        }
+    }
+}
+
+/// Wrap a tantivy aggregation result in an Elasticsearch search response.
+pub fn translate_response(mut result: Value, total_hits: u64, meta: &MetaTree) -> Value {
+    if result.is_null() {
+        result = Value::Object(Map::new());
+    }
+    if let Some(aggs) = result.as_object_mut() {
+        meta.apply(aggs);
+    }
+    json!({
+        "timed_out": false,
+        "hits": {
+            "total": {"value": total_hits, "relation": "eq"},
+            "max_score": null,
+            "hits": []
+        },
+        "aggregations": result
+    })
+}
+
+impl MetaTree {
+    fn apply(&self, result: &mut Map<String, Value>) {
+        for (name, meta) in &self.meta {
+            if let Some(Value::Object(agg)) = result.get_mut(name) {
+                agg.insert("meta".into(), meta.clone());
+            }
+        }
+        for (name, subtree) in &self.children {
+            let Some(agg) = result.get_mut(name) else {
+                continue;
+            };
+            match agg.get_mut("buckets") {
+                Some(Value::Array(buckets)) => buckets
+                    .iter_mut()
+                    .filter_map(Value::as_object_mut)
+                    .for_each(|bucket| subtree.apply(bucket)),
+                Some(Value::Object(buckets)) => buckets
+                    .values_mut()
+                    .filter_map(Value::as_object_mut)
+                    .for_each(|bucket| subtree.apply(bucket)),
+                // a single-bucket aggregation, like a sampler
+                None => {
+                    if let Some(agg) = agg.as_object_mut() {
+                        subtree.apply(agg);
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_translate_request() {
+        let (aggs, meta) = translate_request(json!({
+            "size": 0,
+            "track_total_hits": true,
+            "aggs": {
+                "per_hour": {
+                    "date_histogram": {"field": "created_at", "calendar_interval": "hour"},
+                    "meta": {"color": "blue"},
+                    "aggs": {
+                        "top": {"terms": {"field": "category", "order": {"_term": "asc"}}}
+                    }
+                },
+                "per_day": {"date_histogram": {"field": "created_at", "interval": "1d"}}
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            aggs,
+            json!({
+                "per_hour": {
+                    "date_histogram": {"field": "created_at", "fixed_interval": "1h"},
+                    "aggs": {
+                        "top": {"terms": {"field": "category", "order": {"_key": "asc"}}}
+                    }
+                },
+                "per_day": {"date_histogram": {"field": "created_at", "fixed_interval": "1d"}}
+            })
+        );
+        assert_eq!(
+            meta,
+            MetaTree {
+                meta: vec![("per_hour".into(), json!({"color": "blue"}))],
+                children: vec![],
+            }
+        );
+    }
+
+    #[rstest]
+    #[case(json!({"query": {"match_all": {}}, "aggs": {}}))]
+    #[case(json!({"size": 10, "aggs": {}}))]
+    #[case(json!({"aggs": {}, "aggregations": {}}))]
+    #[case(json!({"aggs": {}, "sort": ["rating"]}))]
+    #[case(json!({"aggs": {"h": {"date_histogram": {"field": "d", "calendar_interval": "month"}}}}))]
+    fn test_translate_request_invalid(#[case] body: Value) {
+        assert!(translate_request(body).is_err());
+    }
+
+    #[rstest]
+    fn test_translate_response() {
+        let (_, meta) = translate_request(json!({
+            "aggs": {
+                "ratings": {
+                    "terms": {"field": "rating"},
+                    "aggs": {"avg": {"avg": {"field": "price"}, "meta": {"unit": "usd"}}}
+                }
+            }
+        }))
+        .unwrap();
+        let response = translate_response(
+            json!({"ratings": {"buckets": [{"key": 5.0, "doc_count": 2, "avg": {"value": 3.5}}]}}),
+            7,
+            &meta,
+        );
+        assert_eq!(
+            response,
+            json!({
+                "timed_out": false,
+                "hits": {"total": {"value": 7, "relation": "eq"}, "max_score": null, "hits": []},
+                "aggregations": {
+                    "ratings": {"buckets": [
+                        {"key": 5.0, "doc_count": 2, "avg": {"value": 3.5, "meta": {"unit": "usd"}}}
+                    ]}
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,432 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Geo grid aggregations.
+//!
+//! `geohash_grid` and `geotile_grid` bucket documents by the map cell their latitude/longitude
+//! falls in, which is what a heatmap needs.  The coordinates are read from a pair of numeric fast
+//! fields.  Tantivy has no such aggregation, so like samplers they're pulled out of the request
+//! and executed with their own [`GeoGridCollector`].
+
+use crate::aggregate::sampler::SamplerKind;
+use crate::index::fast_fields_helper::FFType;
+use anyhow::{anyhow, bail, Result};
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::f64::consts::PI;
+use tantivy::collector::{Collector, SegmentCollector};
+use tantivy::{DocAddress, DocId, Score, SegmentOrdinal, SegmentReader};
+
+const NAMES: [&str; 2] = ["geohash_grid", "geotile_grid"];
+
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// The furthest north or south a Web Mercator tile reaches.
+const MAX_TILE_LATITUDE: f64 = 85.051_128_779_806_6;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GeoGridRequest {
+    lat_field: String,
+    lon_field: String,
+    #[serde(default)]
+    precision: Option<u8>,
+    #[serde(default = "default_size")]
+    size: usize,
+}
+
+fn default_size() -> usize {
+    10000
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum GeoGridKind {
+    GeohashGrid(GeoGridRequest),
+    GeotileGrid(GeoGridRequest),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeoGridEncoding {
+    /// Cells are geohashes of `precision` characters.
+    Geohash,
+    /// Cells are Web Mercator map tiles at zoom level `precision`, keyed as `zoom/x/y`.
+    Geotile,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoGrid {
+    pub encoding: GeoGridEncoding,
+    pub precision: u8,
+    pub lat_field: String,
+    pub lon_field: String,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoGridAggregation {
+    pub grid: GeoGrid,
+    pub aggs: Value,
+}
+
+impl GeoGridAggregation {
+    /// Remove all top-level geo grid aggregations from `request`, keyed by their name.
+    pub fn extract(request: &mut Value) -> Result<Vec<(String, GeoGridAggregation)>> {
+        let Some(aggs) = request.as_object_mut() else {
+            return Ok(vec![]);
+        };
+
+        let names = aggs
+            .iter()
+            .filter(|(_, def)| is_named(def, &NAMES))
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+
+        let mut grids = Vec::with_capacity(names.len());
+        for name in names {
+            let Some(Value::Object(mut def)) = aggs.remove(&name) else {
+                unreachable!("geo grid aggregation should be an object");
+            };
+            let sub_aggs = def
+                .remove("aggs")
+                .or_else(|| def.remove("aggregations"))
+                .unwrap_or_else(|| Value::Object(Map::new()));
+            let Some(sub_aggs_map) = sub_aggs.as_object() else {
+                bail!("geo grid aggregation `{name}`: `aggs` must be an object");
+            };
+            if let Some((nested, _)) = sub_aggs_map.iter().find(|(_, def)| {
+                contains_named(def, &NAMES) || contains_named(def, &SamplerKind::NAMES)
+            }) {
+                bail!(
+                    "geo grid aggregation `{name}` cannot contain a geo grid or sampler aggregation `{nested}`"
+                );
+            }
+
+            let kind = serde_json::from_value::<GeoGridKind>(Value::Object(def))
+                .map_err(|e| anyhow!("invalid geo grid aggregation `{name}`: {e}"))?;
+            let grid = match kind {
+                GeoGridKind::GeohashGrid(request) => {
+                    GeoGrid::new(&name, GeoGridEncoding::Geohash, request)?
+                }
+                GeoGridKind::GeotileGrid(request) => {
+                    GeoGrid::new(&name, GeoGridEncoding::Geotile, request)?
+                }
+            };
+            grids.push((
+                name,
+                GeoGridAggregation {
+                    grid,
+                    aggs: sub_aggs,
+                },
+            ));
+        }
+
+        if let Some((nested, _)) = aggs.iter().find(|(_, def)| contains_named(def, &NAMES)) {
+            bail!("geo grid aggregations must be declared at the top level, but `{nested}` contains one");
+        }
+
+        Ok(grids)
+    }
+}
+
+fn is_named(def: &Value, names: &[&str]) -> bool {
+    def.as_object()
+        .is_some_and(|def| def.keys().any(|key| names.contains(&key.as_str())))
+}
+
+fn contains_named(def: &Value, names: &[&str]) -> bool {
+    is_named(def, names)
+        || def.as_object().is_some_and(|def| {
+            ["aggs", "aggregations"].iter().any(|key| {
+                def.get(*key)
+                    .and_then(Value::as_object)
+                    .is_some_and(|aggs| aggs.values().any(|def| contains_named(def, names)))
+            })
+        })
+}
+
+impl GeoGrid {
+    fn new(name: &str, encoding: GeoGridEncoding, request: GeoGridRequest) -> Result<Self> {
+        let (default, range) = match encoding {
+            GeoGridEncoding::Geohash => (5, 1..=12),
+            GeoGridEncoding::Geotile => (7, 0..=29),
+        };
+        let precision = request.precision.unwrap_or(default);
+        if !range.contains(&precision) {
+            bail!(
+                "geo grid aggregation `{name}`: `precision` must be between {} and {}",
+                range.start(),
+                range.end()
+            );
+        }
+        if request.size == 0 {
+            bail!("geo grid aggregation `{name}`: `size` must be greater than zero");
+        }
+        Ok(Self {
+            encoding,
+            precision,
+            lat_field: request.lat_field,
+            lon_field: request.lon_field,
+            size: request.size,
+        })
+    }
+
+    /// The cell containing the given point, packed into a u64.  Points that aren't valid
+    /// coordinates don't belong to any cell.
+    #[inline]
+    fn cell(&self, lat: f64, lon: f64) -> Option<u64> {
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return None;
+        }
+        match self.encoding {
+            GeoGridEncoding::Geohash => Some(geohash_bits(lat, lon, self.precision)),
+            GeoGridEncoding::Geotile => {
+                let (x, y) = geotile(lat, lon, self.precision);
+                Some(((x as u64) << 32) | y as u64)
+            }
+        }
+    }
+
+    /// Render a cell returned by [`GeoGrid::cell`] as its key.
+    pub fn cell_key(&self, cell: u64) -> String {
+        match self.encoding {
+            GeoGridEncoding::Geohash => (0..self.precision)
+                .rev()
+                .map(|i| GEOHASH_ALPHABET[((cell >> (i * 5)) & 0b11111) as usize] as char)
+                .collect(),
+            GeoGridEncoding::Geotile => {
+                format!("{}/{}/{}", self.precision, cell >> 32, cell & 0xffff_ffff)
+            }
+        }
+    }
+}
+
+/// The geohash of a point, as `precision * 5` bits alternating between longitude and latitude.
+fn geohash_bits(lat: f64, lon: f64, precision: u8) -> u64 {
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut bits = 0u64;
+    for i in 0..(precision as u32 * 5) {
+        let (range, value) = if i % 2 == 0 {
+            (&mut lon_range, lon)
+        } else {
+            (&mut lat_range, lat)
+        };
+        let mid = (range.0 + range.1) / 2.0;
+        bits <<= 1;
+        if value >= mid {
+            bits |= 1;
+            range.0 = mid;
+        } else {
+            range.1 = mid;
+        }
+    }
+    bits
+}
+
+/// The Web Mercator tile containing a point at the given zoom level.
+fn geotile(lat: f64, lon: f64, zoom: u8) -> (u32, u32) {
+    let tiles = (1u64 << zoom) as f64;
+    let max = (1u64 << zoom) - 1;
+    let lat = lat
+        .clamp(-MAX_TILE_LATITUDE, MAX_TILE_LATITUDE)
+        .to_radians();
+    let x = ((lon + 180.0) / 360.0 * tiles).floor();
+    let y = ((1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * tiles).floor();
+    let clamp = |v: f64| (v.max(0.0) as u64).min(max) as u32;
+    (clamp(x), clamp(y))
+}
+
+#[derive(Debug, Default)]
+pub struct GeoCell {
+    pub doc_count: u64,
+    /// The documents in this cell, if they're needed to compute sub-aggregations.
+    pub docs: Vec<DocAddress>,
+}
+
+pub struct GeoGridCollector {
+    grid: GeoGrid,
+    keep_docs: bool,
+}
+
+impl GeoGridCollector {
+    pub fn new(grid: GeoGrid, keep_docs: bool) -> Self {
+        Self { grid, keep_docs }
+    }
+}
+
+impl Collector for GeoGridCollector {
+    type Fruit = FxHashMap<u64, GeoCell>;
+    type Child = GeoGridSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        Ok(GeoGridSegmentCollector {
+            grid: self.grid.clone(),
+            keep_docs: self.keep_docs,
+            segment_ord: segment_local_id,
+            lat_ff: FFType::new(segment.fast_fields(), &self.grid.lat_field),
+            lon_ff: FFType::new(segment.fast_fields(), &self.grid.lon_field),
+            cells: Default::default(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> tantivy::Result<Self::Fruit> {
+        let mut merged = FxHashMap::<u64, GeoCell>::default();
+        for fruit in segment_fruits {
+            for (cell, segment_cell) in fruit {
+                let merged_cell = merged.entry(cell).or_default();
+                merged_cell.doc_count += segment_cell.doc_count;
+                merged_cell.docs.extend(segment_cell.docs);
+            }
+        }
+        Ok(merged)
+    }
+}
+
+pub struct GeoGridSegmentCollector {
+    grid: GeoGrid,
+    keep_docs: bool,
+    segment_ord: SegmentOrdinal,
+    lat_ff: FFType,
+    lon_ff: FFType,
+    cells: FxHashMap<u64, GeoCell>,
+}
+
+impl SegmentCollector for GeoGridSegmentCollector {
+    type Fruit = FxHashMap<u64, GeoCell>;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        let (Some(lat), Some(lon)) = (self.lat_ff.as_f64(doc), self.lon_ff.as_f64(doc)) else {
+            return;
+        };
+        let Some(cell) = self.grid.cell(lat, lon) else {
+            return;
+        };
+        let cell = self.cells.entry(cell).or_default();
+        cell.doc_count += 1;
+        if self.keep_docs {
+            cell.docs.push(DocAddress::new(self.segment_ord, doc));
+        }
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        self.cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    fn grid(encoding: GeoGridEncoding, precision: u8) -> GeoGrid {
+        GeoGrid {
+            encoding,
+            precision,
+            lat_field: "lat".into(),
+            lon_field: "lon".into(),
+            size: 10,
+        }
+    }
+
+    #[rstest]
+    #[case(57.64911, 10.40744, 11, "u4pruydqqvj")]
+    #[case(48.8584, 2.2945, 5, "u09tu")]
+    #[case(-33.8568, 151.2153, 6, "r3gx2u")]
+    fn test_geohash(
+        #[case] lat: f64,
+        #[case] lon: f64,
+        #[case] precision: u8,
+        #[case] expected: &str,
+    ) {
+        let grid = grid(GeoGridEncoding::Geohash, precision);
+        assert_eq!(grid.cell_key(grid.cell(lat, lon).unwrap()), expected);
+    }
+
+    #[rstest]
+    #[case(0.0, 0.0, 1, "1/1/1")]
+    #[case(48.8584, 2.2945, 10, "10/518/352")]
+    #[case(90.0, -180.0, 3, "3/0/0")]
+    #[case(-90.0, 180.0, 3, "3/7/7")]
+    fn test_geotile(
+        #[case] lat: f64,
+        #[case] lon: f64,
+        #[case] precision: u8,
+        #[case] expected: &str,
+    ) {
+        let grid = grid(GeoGridEncoding::Geotile, precision);
+        assert_eq!(grid.cell_key(grid.cell(lat, lon).unwrap()), expected);
+    }
+
+    #[rstest]
+    fn test_invalid_point() {
+        assert_eq!(grid(GeoGridEncoding::Geohash, 5).cell(91.0, 0.0), None);
+        assert_eq!(grid(GeoGridEncoding::Geotile, 5).cell(0.0, -181.0), None);
+    }
+
+    #[rstest]
+    fn test_extract_geo_grids() {
+        let mut request = json!({
+            "heatmap": {
+                "geotile_grid": {"lat_field": "lat", "lon_field": "lon", "precision": 4},
+                "aggs": {"avg_rating": {"avg": {"field": "rating"}}}
+            },
+            "ratings": {"avg": {"field": "rating"}}
+        });
+        let grids = GeoGridAggregation::extract(&mut request).unwrap();
+        assert_eq!(request, json!({"ratings": {"avg": {"field": "rating"}}}));
+        assert_eq!(
+            grids,
+            vec![(
+                "heatmap".to_string(),
+                GeoGridAggregation {
+                    grid: GeoGrid {
+                        encoding: GeoGridEncoding::Geotile,
+                        precision: 4,
+                        lat_field: "lat".into(),
+                        lon_field: "lon".into(),
+                        size: 10000,
+                    },
+                    aggs: json!({"avg_rating": {"avg": {"field": "rating"}}}),
+                }
+            )]
+        );
+    }
+
+    #[rstest]
+    #[case(json!({"g": {"geohash_grid": {"lat_field": "lat"}}}))]
+    #[case(json!({"g": {"geohash_grid": {"lat_field": "lat", "lon_field": "lon", "precision": 13}}}))]
+    #[case(json!({"g": {"geotile_grid": {"lat_field": "lat", "lon_field": "lon", "precision": 30}}}))]
+    #[case(json!({"g": {"geotile_grid": {"lat_field": "lat", "lon_field": "lon", "size": 0}}}))]
+    #[case(json!({"g": {"geotile_grid": {"lat_field": "lat", "lon_field": "lon"}, "aggs": {"s": {"sampler": {}}}}}))]
+    #[case(json!({"t": {"terms": {"field": "f"}, "aggs": {"g": {"geohash_grid": {"lat_field": "lat", "lon_field": "lon"}}}}}))]
+    fn test_extract_invalid(#[case] mut request: Value) {
+        assert!(GeoGridAggregation::extract(&mut request).is_err());
+    }
+}
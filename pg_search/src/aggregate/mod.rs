@@ -15,8 +15,25 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+pub mod cache;
+pub mod elastic;
+pub mod geo_grid;
+pub mod multi_terms;
+pub mod nested;
+pub mod pipeline;
+pub mod sampler;
+pub mod time_zone;
+
+use crate::aggregate::cache::AggregateCacheKey;
+use crate::aggregate::geo_grid::{GeoGridAggregation, GeoGridCollector};
+use crate::aggregate::multi_terms::MultiTermsTree;
 use crate::aggregate::mvcc_collector::MVCCFilterCollector;
+use crate::aggregate::nested::{CtidCollector, HeapJsonReader, NestedAggregation};
+use crate::aggregate::pipeline::PipelineTree;
+use crate::aggregate::sampler::{Sample, SampleFilterCollector, SamplerAggregation, SamplerKind};
 use crate::aggregate::vischeck::TSVisibilityChecker;
+use crate::gucs;
+use crate::index::fast_fields_helper::FFType;
 use crate::index::mvcc::MvccSatisfies;
 use crate::index::reader::index::SearchIndexReader;
 use crate::launch_parallel_process;
@@ -24,17 +41,24 @@ use crate::parallel_worker::mqueue::MessageQueueSender;
 use crate::parallel_worker::ParallelStateManager;
 use crate::parallel_worker::{chunk_range, QueryWorkerStyle, WorkerStyle};
 use crate::parallel_worker::{ParallelProcess, ParallelState, ParallelStateType, ParallelWorker};
+use crate::postgres::customscan::pdbscan::is_block_all_visible;
 use crate::postgres::rel::PgSearchRelation;
 use crate::postgres::spinlock::Spinlock;
 use crate::query::SearchQueryInput;
-use pgrx::{check_for_interrupts, pg_sys};
+use pgrx::pg_sys::panic::ErrorReport;
+use pgrx::{check_for_interrupts, function_name, pg_sys, PgLogLevel, PgSqlErrorCode};
 use rustc_hash::FxHashSet;
 use std::error::Error;
+use std::ops::Bound;
 use tantivy::aggregation::agg_req::Aggregations;
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
-use tantivy::aggregation::{AggregationLimitsGuard, DistributedAggregationCollector};
-use tantivy::collector::Collector;
+use tantivy::aggregation::{
+    AggregationError, AggregationLimitsGuard, DistributedAggregationCollector,
+};
+use tantivy::collector::{Collector, Count, TopDocs};
 use tantivy::index::SegmentId;
+use tantivy::query::{BooleanQuery, EnableScoring, Query, RangeQuery, Weight};
+use tantivy::{DocSet, TantivyError, Term, TERMINATED};
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
@@ -219,10 +243,11 @@ impl<'a> ParallelAggregationWorker<'a> {
                     pg_sys::GetActiveSnapshot()
                 }),
             );
-            reader.collect(mvcc_collector)
+            reader.try_collect(mvcc_collector)
         } else {
-            reader.collect(base_collector)
+            reader.try_collect(base_collector)
         };
+        let intermediate_results = check_bucket_limit(intermediate_results)?;
         pgrx::debug1!(
             "Worker #{}: collected {segment_ids:?} in {:?}",
             unsafe { pg_sys::ParallelWorkerNumber },
@@ -293,6 +318,296 @@ pub fn execute_aggregate(
     solve_mvcc: bool,
     memory_limit: u64,
     bucket_limit: u32,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+    // the buckets are the bulk of what an aggregation holds onto, so `paradedb.work_mem` caps them
+    let memory_limit =
+        gucs::search_work_mem().map_or(memory_limit, |work_mem| memory_limit.min(work_mem as u64));
+
+    // which rows are visible depends on the heap and on our snapshot, neither of which the cache
+    // can tell apart, so only results that count every document in the index are cached
+    let cache_entries = gucs::aggregate_cache_entries();
+    if cache_entries == 0 || solve_mvcc {
+        return compute_aggregate(index, query, agg, solve_mvcc, memory_limit, bucket_limit);
+    }
+
+    let key = AggregateCacheKey::new(index, &query, &agg, memory_limit, bucket_limit)?;
+    if let Some(result) = cache::get(&key) {
+        return Ok(result);
+    }
+    let result = compute_aggregate(index, query, agg, solve_mvcc, memory_limit, bucket_limit)?;
+    cache::insert(key, result.clone(), cache_entries);
+    Ok(result)
+}
+
+fn compute_aggregate(
+    index: &PgSearchRelation,
+    query: SearchQueryInput,
+    mut agg: serde_json::Value,
+    solve_mvcc: bool,
+    memory_limit: u64,
+    bucket_limit: u32,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+    // tantivy buckets dates by UTC, so a date_histogram's `time_zone` becomes an offset from it
+    time_zone::translate(&mut agg)?;
+
+    // tantivy doesn't understand pipeline aggregations, so we compute them from its final result
+    let pipelines = PipelineTree::extract(&mut agg)?;
+
+    // multi_terms is executed as nested terms aggregations, which are flattened back out afterwards
+    let multi_terms = MultiTermsTree::rewrite(&mut agg)?;
+
+    // nor geo grids, which bucket on a pair of coordinate fields
+    let geo_grids = GeoGridAggregation::extract(&mut agg)?;
+
+    // nor nested aggregations, which treat each element of a JSON array as its own document
+    let nested = NestedAggregation::extract(&mut agg)?;
+
+    // nor samplers, which need to run over a different set of documents than everything else
+    let samplers = SamplerAggregation::extract(&mut agg)?;
+
+    let only_extracted = !(samplers.is_empty() && geo_grids.is_empty() && nested.is_empty());
+    let mut result = if !only_extracted || agg.as_object().is_some_and(|aggs| !aggs.is_empty()) {
+        execute_tantivy_aggregate(
+            index,
+            query.clone(),
+            agg,
+            solve_mvcc,
+            memory_limit,
+            bucket_limit,
+        )?
+    } else {
+        serde_json::Value::Object(Default::default())
+    };
+
+    if only_extracted && result.is_null() {
+        result = serde_json::Value::Object(Default::default());
+    }
+    for (name, nested) in nested {
+        let elements =
+            execute_nested_aggregate(index, query.clone(), nested, memory_limit, bucket_limit)?;
+        if let Some(result) = result.as_object_mut() {
+            result.insert(name, elements);
+        }
+    }
+    for (name, geo_grid) in geo_grids {
+        let cells = execute_geo_grid_aggregate(
+            index,
+            query.clone(),
+            geo_grid,
+            solve_mvcc,
+            memory_limit,
+            bucket_limit,
+        )?;
+        if let Some(result) = result.as_object_mut() {
+            result.insert(name, cells);
+        }
+    }
+    for (name, sampler) in samplers {
+        let sampled = execute_sampled_aggregate(
+            index,
+            query.clone(),
+            sampler,
+            solve_mvcc,
+            memory_limit,
+            bucket_limit,
+        )?;
+        if let Some(result) = result.as_object_mut() {
+            result.insert(name, sampled);
+        }
+    }
+
+    multi_terms.apply(&mut result);
+    pipelines.apply(&mut result)?;
+    Ok(result)
+}
+
+/// Execute the sub-aggregations of a sampler over just the sampled documents.
+///
+/// Sampling is meant to keep latency down on huge result sets, so this runs entirely in this
+/// backend rather than launching parallel workers.
+fn execute_sampled_aggregate(
+    index: &PgSearchRelation,
+    query: SearchQueryInput,
+    sampler: SamplerAggregation,
+    solve_mvcc: bool,
+    memory_limit: u64,
+    bucket_limit: u32,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+    let reader = SearchIndexReader::open(
+        index,
+        query,
+        sampler.kind.needs_scores(),
+        MvccSatisfies::Snapshot,
+    )?;
+    let agg_req = serde_json::from_value::<Aggregations>(sampler.aggs)?;
+    let heaprel = index
+        .heap_relation()
+        .expect("index should belong to a heap relation");
+    let vischeck = || {
+        TSVisibilityChecker::with_rel_and_snap(heaprel.as_ptr(), unsafe {
+            pg_sys::GetActiveSnapshot()
+        })
+    };
+    let limits = || AggregationLimitsGuard::new(Some(memory_limit), Some(bucket_limit));
+
+    let (sample, solve_mvcc) = match sampler.kind {
+        SamplerKind::Sampler { shard_size } => {
+            let collector = TopDocs::with_limit(shard_size);
+            let top_docs = if solve_mvcc {
+                reader.collect(MVCCFilterCollector::new(collector, vischeck()))
+            } else {
+                reader.collect(collector)
+            };
+            // the top docs have already been checked for visibility
+            (Sample::from_top_docs(top_docs), false)
+        }
+        SamplerKind::RandomSampler { probability, seed } => {
+            (Sample::random(probability, seed), solve_mvcc)
+        }
+    };
+
+    let collector = (
+        Count,
+        DistributedAggregationCollector::from_aggs(agg_req.clone(), limits()),
+    );
+    let (doc_count, intermediate_results) = check_bucket_limit(if solve_mvcc {
+        // sample first so that we only check the visibility of the documents we keep
+        reader.try_collect(SampleFilterCollector::new(
+            MVCCFilterCollector::new(collector, vischeck()),
+            sample,
+        ))
+    } else {
+        reader.try_collect(SampleFilterCollector::new(collector, sample))
+    })?;
+
+    let mut result = serde_json::Map::new();
+    result.insert("doc_count".into(), doc_count.into());
+    if let serde_json::Value::Object(sub_results) = serde_json::to_value(check_bucket_limit(
+        intermediate_results.into_final_result(agg_req, limits()),
+    )?)? {
+        result.extend(sub_results);
+    }
+    Ok(serde_json::Value::Object(result))
+}
+
+/// Bucket the matching documents by geo grid cell, computing any sub-aggregations for each of the
+/// returned cells.
+///
+/// Like samplers, this runs entirely in this backend.  Sub-aggregations take an extra pass over
+/// the documents in each returned cell.
+fn execute_geo_grid_aggregate(
+    index: &PgSearchRelation,
+    query: SearchQueryInput,
+    geo_grid: GeoGridAggregation,
+    solve_mvcc: bool,
+    memory_limit: u64,
+    bucket_limit: u32,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+    let reader = SearchIndexReader::open(index, query, false, MvccSatisfies::Snapshot)?;
+    let agg_req = serde_json::from_value::<Aggregations>(geo_grid.aggs)?;
+    let heaprel = index
+        .heap_relation()
+        .expect("index should belong to a heap relation");
+    let limits = || AggregationLimitsGuard::new(Some(memory_limit), Some(bucket_limit));
+
+    let grid = geo_grid.grid;
+    let collector = GeoGridCollector::new(grid.clone(), !agg_req.is_empty());
+    let cells = if solve_mvcc {
+        reader.collect(MVCCFilterCollector::new(
+            collector,
+            TSVisibilityChecker::with_rel_and_snap(heaprel.as_ptr(), unsafe {
+                pg_sys::GetActiveSnapshot()
+            }),
+        ))
+    } else {
+        reader.collect(collector)
+    };
+
+    let mut cells = cells.into_iter().collect::<Vec<_>>();
+    cells.sort_unstable_by(|(a_cell, a), (b_cell, b)| {
+        b.doc_count.cmp(&a.doc_count).then(a_cell.cmp(b_cell))
+    });
+    cells.truncate(grid.size);
+    if cells.len() > bucket_limit as usize {
+        return Err(format!(
+            "geo grid aggregation produced {} buckets, which is more than the limit of {bucket_limit}",
+            cells.len()
+        )
+        .into());
+    }
+
+    let mut buckets = Vec::with_capacity(cells.len());
+    for (cell, geo_cell) in cells {
+        let mut bucket = serde_json::Map::new();
+        bucket.insert("key".into(), grid.cell_key(cell).into());
+        bucket.insert("doc_count".into(), geo_cell.doc_count.into());
+        if !agg_req.is_empty() {
+            // the cell's documents have already been checked for visibility
+            let intermediate_results =
+                check_bucket_limit(reader.try_collect(SampleFilterCollector::new(
+                    DistributedAggregationCollector::from_aggs(agg_req.clone(), limits()),
+                    Sample::from_docs(geo_cell.docs),
+                )))?;
+            if let serde_json::Value::Object(sub_results) =
+                serde_json::to_value(check_bucket_limit(
+                    intermediate_results.into_final_result(agg_req.clone(), limits()),
+                )?)?
+            {
+                bucket.extend(sub_results);
+            }
+        }
+        buckets.push(serde_json::Value::Object(bucket));
+    }
+    Ok(serde_json::json!({ "buckets": buckets }))
+}
+
+/// Compute the sub-aggregations of a nested aggregation over the array elements of every
+/// matching row.
+///
+/// The elements are read from the heap, so only rows visible to the current snapshot are
+/// included regardless of `solve_mvcc`.
+fn execute_nested_aggregate(
+    index: &PgSearchRelation,
+    query: SearchQueryInput,
+    nested: NestedAggregation,
+    memory_limit: u64,
+    bucket_limit: u32,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+    let reader = SearchIndexReader::open(index, query, false, MvccSatisfies::Snapshot)?;
+    let heaprel = index
+        .heap_relation()
+        .expect("index should belong to a heap relation");
+    let ctids = reader.collect(CtidCollector);
+
+    let mut heap = HeapJsonReader::new(&heaprel, &nested.column, unsafe {
+        pg_sys::GetActiveSnapshot()
+    })?;
+    let elements = ctids.into_iter().flat_map(|ctid| {
+        check_for_interrupts!();
+        heap.read(ctid)
+            .map(|value| nested.elements(value))
+            .unwrap_or_default()
+    });
+    let (doc_count, sub_results) = nested.aggregate(
+        elements,
+        AggregationLimitsGuard::new(Some(memory_limit), Some(bucket_limit)),
+    )?;
+
+    let mut result = serde_json::Map::new();
+    result.insert("doc_count".into(), doc_count.into());
+    if let serde_json::Value::Object(sub_results) = sub_results {
+        result.extend(sub_results);
+    }
+    Ok(serde_json::Value::Object(result))
+}
+
+fn execute_tantivy_aggregate(
+    index: &PgSearchRelation,
+    query: SearchQueryInput,
+    agg: serde_json::Value,
+    solve_mvcc: bool,
+    memory_limit: u64,
+    bucket_limit: u32,
 ) -> Result<serde_json::Value, Box<dyn Error>> {
     unsafe {
         let reader = SearchIndexReader::open(index, query.clone(), false, MvccSatisfies::Snapshot)?;
@@ -369,9 +684,11 @@ pub fn execute_aggregate(
                     agg_req.clone(),
                     AggregationLimitsGuard::new(Some(memory_limit), Some(bucket_limit)),
                 );
-                collector.merge_fruits(agg_results)?.into_final_result(
-                    agg_req,
-                    AggregationLimitsGuard::new(Some(memory_limit), Some(bucket_limit)),
+                check_bucket_limit(
+                    check_bucket_limit(collector.merge_fruits(agg_results))?.into_final_result(
+                        agg_req,
+                        AggregationLimitsGuard::new(Some(memory_limit), Some(bucket_limit)),
+                    ),
                 )?
             };
 
@@ -395,10 +712,10 @@ pub fn execute_aggregate(
                 &mut state,
             );
             if let Some(agg_results) = worker.execute_aggregate(QueryWorkerStyle::NonParallel)? {
-                let result = agg_results.into_final_result(
+                let result = check_bucket_limit(agg_results.into_final_result(
                     agg_req,
                     AggregationLimitsGuard::new(Some(memory_limit), Some(bucket_limit)),
-                )?;
+                ))?;
                 Ok(serde_json::to_value(result)?)
             } else {
                 Ok(serde_json::Value::Null)
@@ -407,6 +724,139 @@ pub fn execute_aggregate(
     }
 }
 
+/// Raise an aggregation that created more buckets than its limit allows as Postgres' error for
+/// exceeding a limit, rather than as an internal error.  This happens where the buckets are
+/// created, as a parallel worker's error keeps its code when the leader rethrows it.
+fn check_bucket_limit<T>(result: tantivy::Result<T>) -> tantivy::Result<T> {
+    if let Err(TantivyError::AggregationError(AggregationError::BucketLimitExceeded {
+        limit,
+        ..
+    })) = &result
+    {
+        ErrorReport::new(
+            PgSqlErrorCode::ERRCODE_PROGRAM_LIMIT_EXCEEDED,
+            format!("aggregation created more than {limit} buckets"),
+            function_name!(),
+        )
+        .set_hint("GROUP BY is limited by `paradedb.max_term_agg_buckets` groups per column, and `paradedb.aggregate()` by its `bucket_limit`")
+        .report(PgLogLevel::ERROR);
+    }
+    result
+}
+
+/// Count the documents matching `query` without building any aggregation structures.
+///
+/// The count is taken from each segment's [`Weight::count`], which skips deleted documents with
+/// the segment's alive bitset and doesn't visit each match for queries that know their count,
+/// like `paradedb.all()` and terms in segments without deletes.  When `solve_mvcc` is true, only
+/// the matches on heap pages that aren't marked all-visible are then checked against the heap,
+/// and the ones our snapshot can't see are taken back off the count.  If those pages are spread
+/// over more than [`MAX_NOT_ALL_VISIBLE_RANGES`] runs, searching for the matches on each of them
+/// would cost more than it saves, so every match is checked instead.
+pub fn execute_count(
+    index: &PgSearchRelation,
+    query: SearchQueryInput,
+    solve_mvcc: bool,
+) -> anyhow::Result<u64> {
+    let reader = SearchIndexReader::open(index, query, false, MvccSatisfies::Snapshot)?;
+    let weight = reader.weight();
+    let mut count = 0;
+    for segment_reader in reader.segment_readers() {
+        check_for_interrupts!();
+        count += weight.count(segment_reader)? as u64;
+    }
+    if !solve_mvcc || count == 0 {
+        return Ok(count);
+    }
+
+    let heaprel = index
+        .heap_relation()
+        .expect("index should belong to a heap relation");
+    let uncertain = match not_all_visible_blocks(&heaprel) {
+        Some(blocks) if blocks.is_empty() => return Ok(count),
+
+        // only the matches whose ctids are on one of those blocks could be invisible
+        Some(blocks) => {
+            let ctid_field = reader.schema().ctid_field();
+            let ctid_of_block = |blockno: pg_sys::BlockNumber| {
+                Term::from_field_u64(ctid_field, u64::from(blockno) << 16)
+            };
+            let on_blocks = BooleanQuery::union(
+                blocks
+                    .into_iter()
+                    .map(|(start, end)| {
+                        Box::new(RangeQuery::new(
+                            Bound::Included(ctid_of_block(start)),
+                            Bound::Excluded(ctid_of_block(end)),
+                        )) as Box<dyn Query>
+                    })
+                    .collect(),
+            );
+            BooleanQuery::intersection(vec![reader.query().box_clone(), Box::new(on_blocks)])
+                .weight(EnableScoring::disabled_from_searcher(reader.searcher()))?
+        }
+
+        None => reader
+            .query()
+            .weight(EnableScoring::disabled_from_searcher(reader.searcher()))?,
+    };
+
+    let mut vischeck = TSVisibilityChecker::with_rel_and_snap(heaprel.as_ptr(), unsafe {
+        pg_sys::GetActiveSnapshot()
+    });
+    for segment_reader in reader.segment_readers() {
+        let ctid_ff = FFType::new_ctid(segment_reader.fast_fields());
+        let alive_bitset = segment_reader.alive_bitset();
+        let mut scorer = uncertain.scorer(segment_reader, 1.0)?;
+        let mut doc = scorer.doc();
+        while doc != TERMINATED {
+            check_for_interrupts!();
+            let is_alive = alive_bitset.is_none_or(|alive_bitset| alive_bitset.is_alive(doc));
+            let ctid = ctid_ff.as_u64(doc).expect("ctid should be present");
+            if is_alive && !vischeck.is_visible(ctid) {
+                count -= 1;
+            }
+            doc = scorer.advance();
+        }
+    }
+    Ok(count)
+}
+
+/// The most runs of blocks that aren't all-visible that [`execute_count`] searches for the matches
+/// on, rather than checking the visibility of all of them.
+const MAX_NOT_ALL_VISIBLE_RANGES: usize = 1024;
+
+/// The ranges of the blocks of `heaprel`, as the first block and the one after the last, that the
+/// visibility map doesn't mark all-visible, or `None` if there are more than
+/// [`MAX_NOT_ALL_VISIBLE_RANGES`] of them.
+fn not_all_visible_blocks(
+    heaprel: &PgSearchRelation,
+) -> Option<Vec<(pg_sys::BlockNumber, pg_sys::BlockNumber)>> {
+    let nblocks = unsafe {
+        pg_sys::RelationGetNumberOfBlocksInFork(heaprel.as_ptr(), pg_sys::ForkNumber::MAIN_FORKNUM)
+    };
+    let mut vmbuff = pg_sys::InvalidBuffer as pg_sys::Buffer;
+    let mut ranges: Vec<(pg_sys::BlockNumber, pg_sys::BlockNumber)> = Vec::new();
+    let mut too_many = false;
+    for blockno in 0..nblocks {
+        if is_block_all_visible(heaprel, &mut vmbuff, blockno) {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some((_, end)) if *end == blockno => *end = blockno + 1,
+            _ if ranges.len() == MAX_NOT_ALL_VISIBLE_RANGES => {
+                too_many = true;
+                break;
+            }
+            _ => ranges.push((blockno, blockno + 1)),
+        }
+    }
+    if vmbuff != pg_sys::InvalidBuffer as pg_sys::Buffer {
+        unsafe { pg_sys::ReleaseBuffer(vmbuff) };
+    }
+    (!too_many).then_some(ranges)
+}
+
 pub mod mvcc_collector {
     use parking_lot::Mutex;
     use std::sync::Arc;
@@ -512,7 +962,7 @@ pub mod mvcc_collector {
     }
 }
 
-mod vischeck {
+pub mod vischeck {
     use crate::postgres::utils;
     use pgrx::itemptr::item_pointer_get_block_number;
     use pgrx::pg_sys;
@@ -523,6 +973,11 @@ mod vischeck {
         snapshot: pg_sys::Snapshot,
         tid: pg_sys::ItemPointerData,
         vmbuf: pg_sys::Buffer,
+
+        // matching documents tend to arrive in ctid order, so remember the visibility map status
+        // of the last block we looked at
+        last_blockno: pg_sys::BlockNumber,
+        last_block_all_visible: bool,
     }
 
     impl Clone for TSVisibilityChecker {
@@ -562,6 +1017,8 @@ mod vischeck {
                     snapshot,
                     tid: pg_sys::ItemPointerData::default(),
                     vmbuf: pg_sys::InvalidBuffer as _,
+                    last_blockno: pg_sys::InvalidBlockNumber,
+                    last_block_all_visible: false,
                 }
             }
         }
@@ -570,12 +1027,18 @@ mod vischeck {
             unsafe {
                 utils::u64_to_item_pointer(ctid, &mut self.tid);
 
-                if pg_sys::visibilitymap_get_status(
-                    (*self.scan).rel,
-                    item_pointer_get_block_number(&self.tid),
-                    &mut self.vmbuf,
-                ) != 0
-                {
+                // a page that was all-visible when we first checked it stays visible to our
+                // snapshot even if it's concurrently modified, so the cached status is safe to use
+                let blockno = item_pointer_get_block_number(&self.tid);
+                if blockno != self.last_blockno {
+                    self.last_blockno = blockno;
+                    self.last_block_all_visible = pg_sys::visibilitymap_get_status(
+                        (*self.scan).rel,
+                        blockno,
+                        &mut self.vmbuf,
+                    ) != 0;
+                }
+                if self.last_block_all_visible {
                     return true;
                 }
 
@@ -0,0 +1,457 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! The `multi_terms` aggregation.
+//!
+//! Buckets documents by the combination of values of two or more fields.  Tantivy only knows how
+//! to bucket on a single field, so a `multi_terms` aggregation is rewritten into a chain of nested
+//! `terms` aggregations, one per field, and the nested result is flattened back into one bucket
+//! per combination of values.
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+
+const SUB_AGGREGATION_KEYS: [&str; 2] = ["aggs", "aggregations"];
+
+/// The name given to each nested `terms` aggregation produced by the rewrite.
+const NESTED_TERMS_PREFIX: &str = "_multi_terms_";
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MultiTermsRequest {
+    terms: Vec<Map<String, Value>>,
+    #[serde(default = "default_size")]
+    size: usize,
+    /// How many values of each field to consider before the combinations are ranked and
+    /// truncated to `size`.
+    #[serde(default)]
+    shard_size: Option<usize>,
+    #[serde(default)]
+    order: Option<Map<String, Value>>,
+}
+
+fn default_size() -> usize {
+    10
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OrderTarget {
+    Count,
+    Key,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct MultiTerms {
+    depth: usize,
+    size: usize,
+    order: (OrderTarget, bool), // (target, ascending)
+}
+
+/// Every `multi_terms` aggregation in a request, arranged like the request itself.
+#[derive(Debug, Default, PartialEq)]
+pub struct MultiTermsTree {
+    multi_terms: Vec<(String, MultiTerms)>,
+    children: Vec<(String, MultiTermsTree)>,
+}
+
+impl MultiTermsTree {
+    /// Rewrite every `multi_terms` aggregation in `request` into nested `terms` aggregations,
+    /// returning a [`MultiTermsTree`] that can flatten the result back out with
+    /// [`MultiTermsTree::apply`].
+    pub fn rewrite(request: &mut Value) -> Result<Self> {
+        match request.as_object_mut() {
+            Some(aggs) => Self::rewrite_level(aggs),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.multi_terms.is_empty() && self.children.is_empty()
+    }
+
+    fn rewrite_level(aggs: &mut Map<String, Value>) -> Result<Self> {
+        let mut tree = MultiTermsTree::default();
+        for (name, def) in aggs.iter_mut() {
+            let Some(def) = def.as_object_mut() else {
+                continue;
+            };
+
+            // rewrite the sub-aggregations first, as they end up inside the deepest nested `terms`
+            let sub_aggs_key = SUB_AGGREGATION_KEYS
+                .into_iter()
+                .find(|key| def.get(*key).is_some_and(Value::is_object));
+            if let Some(key) = sub_aggs_key {
+                let sub_aggs = def
+                    .get_mut(key)
+                    .and_then(Value::as_object_mut)
+                    .expect("sub-aggregations should be an object");
+                let subtree = Self::rewrite_level(sub_aggs)?;
+                if !subtree.is_empty() {
+                    tree.children.push((name.clone(), subtree));
+                }
+            }
+
+            let Some(request) = def.remove("multi_terms") else {
+                continue;
+            };
+            let request = serde_json::from_value::<MultiTermsRequest>(request)
+                .map_err(|e| anyhow!("invalid multi_terms aggregation `{name}`: {e}"))?;
+            if request.terms.len() < 2 {
+                bail!("multi_terms aggregation `{name}` requires at least two `terms`");
+            }
+            let order = parse_order(name, request.order.as_ref())?;
+            let shard_size = request
+                .shard_size
+                .unwrap_or_else(|| (request.size * 10).max(10000));
+
+            let sub_aggs = sub_aggs_key.and_then(|key| def.remove(key));
+            let nested = nest_terms(&request.terms, shard_size, sub_aggs)?;
+            let Value::Object(nested) = nested else {
+                unreachable!("nested terms should be an object");
+            };
+            def.extend(nested);
+
+            tree.multi_terms.push((
+                name.clone(),
+                MultiTerms {
+                    depth: request.terms.len(),
+                    size: request.size,
+                    order,
+                },
+            ));
+        }
+        Ok(tree)
+    }
+
+    /// Flatten the nested `terms` results of every `multi_terms` aggregation in `result`.
+    pub fn apply(&self, result: &mut Value) {
+        if let Some(result) = result.as_object_mut() {
+            self.apply_level(result);
+        }
+    }
+
+    fn apply_level(&self, result: &mut Map<String, Value>) {
+        for (name, multi_terms) in &self.multi_terms {
+            if let Some(agg) = result.get_mut(name) {
+                *agg = multi_terms.flatten(std::mem::take(agg));
+            }
+        }
+
+        for (name, subtree) in &self.children {
+            let Some(agg) = result.get_mut(name) else {
+                continue;
+            };
+            match agg.get_mut("buckets") {
+                Some(Value::Array(buckets)) => buckets
+                    .iter_mut()
+                    .filter_map(Value::as_object_mut)
+                    .for_each(|bucket| subtree.apply_level(bucket)),
+                Some(Value::Object(buckets)) => buckets
+                    .values_mut()
+                    .filter_map(Value::as_object_mut)
+                    .for_each(|bucket| subtree.apply_level(bucket)),
+                // a single-bucket aggregation, like a sampler
+                None => {
+                    if let Some(agg) = agg.as_object_mut() {
+                        subtree.apply_level(agg);
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+fn parse_order(name: &str, order: Option<&Map<String, Value>>) -> Result<(OrderTarget, bool)> {
+    let Some(order) = order else {
+        return Ok((OrderTarget::Count, false));
+    };
+    let mut entries = order.iter();
+    let (Some((target, direction)), None) = (entries.next(), entries.next()) else {
+        bail!("multi_terms aggregation `{name}`: `order` must have exactly one entry");
+    };
+    let target = match target.as_str() {
+        "_count" => OrderTarget::Count,
+        "_key" => OrderTarget::Key,
+        other => bail!(
+            "multi_terms aggregation `{name}`: can only order by `_count` or `_key`, not `{other}`"
+        ),
+    };
+    let ascending = match direction.as_str() {
+        Some("asc") => true,
+        Some("desc") => false,
+        _ => bail!("multi_terms aggregation `{name}`: order direction must be `asc` or `desc`"),
+    };
+    Ok((target, ascending))
+}
+
+fn nest_terms(
+    terms: &[Map<String, Value>],
+    shard_size: usize,
+    sub_aggs: Option<Value>,
+) -> Result<Value> {
+    let (term, rest) = terms.split_first().expect("terms should not be empty");
+    if !term.contains_key("field") {
+        bail!("multi_terms: every entry in `terms` requires a `field`");
+    }
+
+    let mut terms_def = term.clone();
+    terms_def.insert("size".into(), shard_size.into());
+    terms_def.insert("segment_size".into(), shard_size.into());
+
+    let mut def = Map::new();
+    def.insert("terms".into(), Value::Object(terms_def));
+    if rest.is_empty() {
+        if let Some(sub_aggs) = sub_aggs {
+            def.insert("aggs".into(), sub_aggs);
+        }
+    } else {
+        let mut aggs = Map::new();
+        aggs.insert(
+            format!("{NESTED_TERMS_PREFIX}{}", terms.len() - rest.len()),
+            nest_terms(rest, shard_size, sub_aggs)?,
+        );
+        def.insert("aggs".into(), Value::Object(aggs));
+    }
+    Ok(Value::Object(def))
+}
+
+impl MultiTerms {
+    fn flatten(&self, nested: Value) -> Value {
+        let mut combinations = Vec::new();
+        collect_combinations(&nested, 1, self.depth, &mut Vec::new(), &mut combinations);
+
+        combinations.sort_by(|(a_key, a), (b_key, b)| {
+            let by_count = || {
+                let count = |bucket: &Map<String, Value>| {
+                    bucket.get("doc_count").and_then(Value::as_u64).unwrap_or(0)
+                };
+                count(a).cmp(&count(b))
+            };
+            let by_key = || compare_keys(a_key, b_key);
+            match self.order {
+                (OrderTarget::Count, true) => by_count().then_with(by_key),
+                (OrderTarget::Count, false) => by_count().reverse().then_with(by_key),
+                (OrderTarget::Key, true) => by_key(),
+                (OrderTarget::Key, false) => by_key().reverse(),
+            }
+        });
+
+        let sum_other_doc_count = combinations
+            .iter()
+            .skip(self.size)
+            .filter_map(|(_, bucket)| bucket.get("doc_count").and_then(Value::as_u64))
+            .sum::<u64>();
+
+        let buckets = combinations
+            .into_iter()
+            .take(self.size)
+            .map(|(key, mut bucket)| {
+                let key_as_string = key
+                    .iter()
+                    .map(|value| match value {
+                        Value::String(s) => s.clone(),
+                        // tantivy reports every numeric term as an f64
+                        Value::Number(n)
+                            if n.as_f64().is_some_and(|f| {
+                                f.fract() == 0.0 && f.abs() < 9007199254740992.0
+                            }) =>
+                        {
+                            (n.as_f64().unwrap() as i64).to_string()
+                        }
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("|");
+                bucket.remove("key");
+                bucket.remove("key_as_string");
+
+                let mut flattened = Map::new();
+                flattened.insert("key".into(), Value::Array(key));
+                flattened.insert("key_as_string".into(), key_as_string.into());
+                flattened.extend(bucket);
+                Value::Object(flattened)
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::json!({
+            "buckets": buckets,
+            "doc_count_error_upper_bound": 0,
+            "sum_other_doc_count": sum_other_doc_count,
+        })
+    }
+}
+
+/// Walk the nested `terms` results down to `depth`, collecting the key of every level along with
+/// the deepest bucket (which holds the doc count and any sub-aggregation results).
+fn collect_combinations(
+    terms_result: &Value,
+    level: usize,
+    depth: usize,
+    prefix: &mut Vec<Value>,
+    combinations: &mut Vec<(Vec<Value>, Map<String, Value>)>,
+) {
+    let Some(buckets) = terms_result.get("buckets").and_then(Value::as_array) else {
+        return;
+    };
+    for bucket in buckets {
+        let Some(bucket) = bucket.as_object() else {
+            continue;
+        };
+        prefix.push(bucket.get("key").cloned().unwrap_or(Value::Null));
+        if level == depth {
+            combinations.push((prefix.clone(), bucket.clone()));
+        } else if let Some(nested) = bucket.get(&format!("{NESTED_TERMS_PREFIX}{level}")) {
+            collect_combinations(nested, level + 1, depth, prefix, combinations);
+        }
+        prefix.pop();
+    }
+}
+
+fn compare_keys(a: &[Value], b: &[Value]) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+
+    for (a, b) in a.iter().zip(b) {
+        let ordering = match (a, b) {
+            (Value::Number(a), Value::Number(b)) => a
+                .as_f64()
+                .partial_cmp(&b.as_f64())
+                .unwrap_or(Ordering::Equal),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (a, b) => rank(a).cmp(&rank(b)),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    fn test_rewrite() {
+        let mut request = json!({
+            "combos": {
+                "multi_terms": {
+                    "terms": [{"field": "brand"}, {"field": "color", "missing": "none"}],
+                    "shard_size": 50
+                },
+                "aggs": {"avg_price": {"avg": {"field": "price"}}}
+            }
+        });
+        let tree = MultiTermsTree::rewrite(&mut request).unwrap();
+        assert!(!tree.is_empty());
+        assert_eq!(
+            request,
+            json!({
+                "combos": {
+                    "terms": {"field": "brand", "size": 50, "segment_size": 50},
+                    "aggs": {
+                        "_multi_terms_1": {
+                            "terms": {"field": "color", "missing": "none", "size": 50, "segment_size": 50},
+                            "aggs": {"avg_price": {"avg": {"field": "price"}}}
+                        }
+                    }
+                }
+            })
+        );
+    }
+
+    #[rstest]
+    #[case(json!({"m": {"multi_terms": {"terms": [{"field": "a"}]}}}))]
+    #[case(json!({"m": {"multi_terms": {"terms": [{"field": "a"}, {"size": 1}]}}}))]
+    #[case(json!({"m": {"multi_terms": {"terms": [{"field": "a"}, {"field": "b"}], "order": {"avg": "asc"}}}}))]
+    #[case(json!({"m": {"multi_terms": {"terms": [{"field": "a"}, {"field": "b"}], "colour": "red"}}}))]
+    fn test_rewrite_invalid(#[case] mut request: Value) {
+        assert!(MultiTermsTree::rewrite(&mut request).is_err());
+    }
+
+    fn nested_result() -> Value {
+        json!({
+            "combos": {
+                "buckets": [
+                    {"key": "acme", "doc_count": 5, "_multi_terms_1": {"buckets": [
+                        {"key": "red", "doc_count": 3, "avg_price": {"value": 10.0}},
+                        {"key": "blue", "doc_count": 2, "avg_price": {"value": 20.0}}
+                    ]}},
+                    {"key": "zeta", "doc_count": 4, "_multi_terms_1": {"buckets": [
+                        {"key": "red", "doc_count": 4, "avg_price": {"value": 30.0}}
+                    ]}}
+                ],
+                "sum_other_doc_count": 0
+            }
+        })
+    }
+
+    fn flatten(multi_terms: Value) -> Value {
+        let mut request = json!({"combos": {"multi_terms": multi_terms}});
+        let tree = MultiTermsTree::rewrite(&mut request).unwrap();
+        let mut result = nested_result();
+        tree.apply(&mut result);
+        result
+    }
+
+    #[rstest]
+    fn test_flatten() {
+        let result = flatten(json!({"terms": [{"field": "brand"}, {"field": "color"}], "size": 2}));
+        assert_eq!(
+            result,
+            json!({
+                "combos": {
+                    "buckets": [
+                        {"key": ["zeta", "red"], "key_as_string": "zeta|red", "doc_count": 4, "avg_price": {"value": 30.0}},
+                        {"key": ["acme", "red"], "key_as_string": "acme|red", "doc_count": 3, "avg_price": {"value": 10.0}}
+                    ],
+                    "doc_count_error_upper_bound": 0,
+                    "sum_other_doc_count": 2
+                }
+            })
+        );
+    }
+
+    #[rstest]
+    fn test_flatten_order_by_key() {
+        let result = flatten(json!({
+            "terms": [{"field": "brand"}, {"field": "color"}],
+            "order": {"_key": "asc"}
+        }));
+        let keys = result["combos"]["buckets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| bucket["key_as_string"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["acme|blue", "acme|red", "zeta|red"]);
+    }
+}
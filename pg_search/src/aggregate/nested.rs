@@ -0,0 +1,381 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! The `nested` aggregation.
+//!
+//! The index flattens JSON arrays, so `metadata.items.category` and `metadata.items.price` are
+//! just two unrelated lists of values on each document.  A `nested` aggregation instead treats
+//! every element of the array at `path` as its own document, so that its sub-aggregations only
+//! ever combine values from the same element.
+//!
+//! The association between values of the same element only exists in the heap, so the elements
+//! of every matching row are read from the heap and indexed into a throwaway in-memory index,
+//! over which the sub-aggregations are then computed.
+
+use crate::aggregate::sampler::SamplerKind;
+use crate::index::fast_fields_helper::FFType;
+use crate::postgres::heap::HeapColumnReader;
+use crate::postgres::rel::PgSearchRelation;
+use anyhow::{anyhow, bail, Result};
+use pgrx::{pg_sys, FromDatum, Json, JsonB};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tantivy::aggregation::agg_req::Aggregations;
+use tantivy::aggregation::{AggregationCollector, AggregationLimitsGuard};
+use tantivy::collector::{Collector, SegmentCollector};
+use tantivy::query::AllQuery;
+use tantivy::schema::{JsonObjectOptions, Schema};
+use tantivy::{DocId, Index, IndexWriter, Score, SegmentOrdinal, SegmentReader, TantivyDocument};
+
+const NESTED: &str = "nested";
+
+/// The smallest memory budget tantivy accepts for an index writer.
+const WRITER_MEMORY_BUDGET: usize = 15_000_000;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NestedRequest {
+    path: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestedAggregation {
+    /// The JSON or JSONB column holding the array.
+    pub column: String,
+    /// The keys leading from the column to the array, if it isn't the column itself.
+    pub path: Vec<String>,
+    pub aggs: Value,
+}
+
+impl NestedAggregation {
+    /// Remove all top-level nested aggregations from `request`, keyed by their name.
+    pub fn extract(request: &mut Value) -> Result<Vec<(String, NestedAggregation)>> {
+        let Some(aggs) = request.as_object_mut() else {
+            return Ok(vec![]);
+        };
+
+        let names = aggs
+            .iter()
+            .filter(|(_, def)| def.get(NESTED).is_some())
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+
+        let mut nested = Vec::with_capacity(names.len());
+        for name in names {
+            let Some(Value::Object(mut def)) = aggs.remove(&name) else {
+                unreachable!("nested aggregation should be an object");
+            };
+            let sub_aggs = def
+                .remove("aggs")
+                .or_else(|| def.remove("aggregations"))
+                .unwrap_or_else(|| Value::Object(Map::new()));
+            let Some(sub_aggs_map) = sub_aggs.as_object() else {
+                bail!("nested aggregation `{name}`: `aggs` must be an object");
+            };
+            if let Some((inner, _)) = sub_aggs_map.iter().find(|(_, def)| {
+                contains_named(def, &[NESTED]) || contains_named(def, &SamplerKind::NAMES)
+            }) {
+                bail!(
+                    "nested aggregation `{name}` cannot contain a nested or sampler aggregation `{inner}`"
+                );
+            }
+
+            let request = def
+                .remove(NESTED)
+                .expect("nested aggregation should have a `nested` key");
+            if let Some(key) = def.keys().next() {
+                bail!("nested aggregation `{name}`: unexpected key `{key}`");
+            }
+            let request = serde_json::from_value::<NestedRequest>(request)
+                .map_err(|e| anyhow!("invalid nested aggregation `{name}`: {e}"))?;
+            let mut path = request.path.split('.').map(String::from);
+            let column = path
+                .next()
+                .filter(|column| !column.is_empty())
+                .ok_or_else(|| anyhow!("nested aggregation `{name}`: `path` must not be empty"))?;
+
+            nested.push((
+                name,
+                NestedAggregation {
+                    column,
+                    path: path.collect(),
+                    aggs: sub_aggs,
+                },
+            ));
+        }
+
+        if let Some((inner, _)) = aggs.iter().find(|(_, def)| contains_named(def, &[NESTED])) {
+            bail!(
+                "nested aggregations must be declared at the top level, but `{inner}` contains one"
+            );
+        }
+
+        Ok(nested)
+    }
+
+    /// The elements of the array at this aggregation's path within `value`.  A lone object is
+    /// treated as an array of one element.
+    pub fn elements(&self, value: Value) -> Vec<Value> {
+        let mut value = value;
+        for key in &self.path {
+            value = match value {
+                Value::Object(mut object) => object.remove(key).unwrap_or(Value::Null),
+                _ => return vec![],
+            };
+        }
+        match value {
+            Value::Array(elements) => elements.into_iter().filter(Value::is_object).collect(),
+            object @ Value::Object(_) => vec![object],
+            _ => vec![],
+        }
+    }
+
+    /// Wrap an element back in its path, so that fields keep their full name in the in-memory
+    /// index and sub-aggregations can refer to `column.path.field` like any other.
+    fn wrap(&self, element: Value) -> Value {
+        let mut value = element;
+        for key in self.path.iter().rev() {
+            let mut object = Map::new();
+            object.insert(key.clone(), value);
+            value = Value::Object(object);
+        }
+        let mut document = Map::new();
+        document.insert(self.column.clone(), value);
+        Value::Object(document)
+    }
+
+    /// Compute the sub-aggregations over `elements`, returning the number of elements along with
+    /// the sub-aggregation results.
+    pub fn aggregate(
+        &self,
+        elements: impl IntoIterator<Item = Value>,
+        limits: AggregationLimitsGuard,
+    ) -> Result<(u64, Value)> {
+        let mut schema = Schema::builder();
+        schema.add_json_field(
+            &self.column,
+            JsonObjectOptions::default().set_fast(Some("raw")),
+        );
+        let schema = schema.build();
+        let index = Index::create_in_ram(schema.clone());
+        let mut writer: IndexWriter = index.writer_with_num_threads(1, WRITER_MEMORY_BUDGET)?;
+
+        let mut doc_count = 0u64;
+        for element in elements {
+            let document = TantivyDocument::parse_json(&schema, &self.wrap(element).to_string())?;
+            writer.add_document(document)?;
+            doc_count += 1;
+        }
+        writer.commit()?;
+
+        let agg_req = serde_json::from_value::<Aggregations>(self.aggs.clone())?;
+        let searcher = index.reader()?.searcher();
+        let results =
+            searcher.search(&AllQuery, &AggregationCollector::from_aggs(agg_req, limits))?;
+        Ok((doc_count, serde_json::to_value(results)?))
+    }
+}
+
+fn contains_named(def: &Value, names: &[&str]) -> bool {
+    def.as_object().is_some_and(|def| {
+        def.keys().any(|key| names.contains(&key.as_str()))
+            || ["aggs", "aggregations"].iter().any(|key| {
+                def.get(*key)
+                    .and_then(Value::as_object)
+                    .is_some_and(|aggs| aggs.values().any(|def| contains_named(def, names)))
+            })
+    })
+}
+
+/// Reads the JSON column of the heap tuples identified by ctid, skipping tuples that aren't
+/// visible to the snapshot.
+pub struct HeapJsonReader(HeapColumnReader);
+
+impl HeapJsonReader {
+    pub fn new(
+        heaprel: &PgSearchRelation,
+        column: &str,
+        snapshot: pg_sys::Snapshot,
+    ) -> Result<Self> {
+        let reader = HeapColumnReader::new(heaprel, column, snapshot)?;
+        let typoid = reader.typoid();
+        if typoid != pg_sys::JSONOID && typoid != pg_sys::JSONBOID {
+            bail!("nested aggregations require a json or jsonb column, but `{column}` is not");
+        }
+        Ok(Self(reader))
+    }
+
+    pub fn read(&mut self, ctid: u64) -> Option<Value> {
+        let typoid = self.0.typoid();
+        self.0.read(ctid, |datum, is_null| unsafe {
+            if typoid == pg_sys::JSONBOID {
+                JsonB::from_datum(datum, is_null).map(|json| json.0)
+            } else {
+                Json::from_datum(datum, is_null).map(|json| json.0)
+            }
+        })
+    }
+}
+
+/// Collects the ctid of every matching document.
+pub struct CtidCollector;
+
+impl Collector for CtidCollector {
+    type Fruit = Vec<u64>;
+    type Child = CtidSegmentCollector;
+
+    fn for_segment(
+        &self,
+        _segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        Ok(CtidSegmentCollector {
+            ctid_ff: FFType::new_ctid(segment.fast_fields()),
+            ctids: Vec::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(&self, segment_fruits: Vec<Vec<u64>>) -> tantivy::Result<Self::Fruit> {
+        let mut ctids = segment_fruits.concat();
+        // visit the heap in physical order
+        ctids.sort_unstable();
+        Ok(ctids)
+    }
+}
+
+pub struct CtidSegmentCollector {
+    ctid_ff: FFType,
+    ctids: Vec<u64>,
+}
+
+impl SegmentCollector for CtidSegmentCollector {
+    type Fruit = Vec<u64>;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        self.ctids
+            .push(self.ctid_ff.as_u64(doc).expect("ctid should be present"));
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        self.ctids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    fn test_extract_nested() {
+        let mut request = json!({
+            "items": {
+                "nested": {"path": "metadata.items"},
+                "aggs": {"categories": {"terms": {"field": "metadata.items.category"}}}
+            },
+            "ratings": {"avg": {"field": "rating"}}
+        });
+        let nested = NestedAggregation::extract(&mut request).unwrap();
+        assert_eq!(request, json!({"ratings": {"avg": {"field": "rating"}}}));
+        assert_eq!(
+            nested,
+            vec![(
+                "items".to_string(),
+                NestedAggregation {
+                    column: "metadata".into(),
+                    path: vec!["items".into()],
+                    aggs: json!({"categories": {"terms": {"field": "metadata.items.category"}}}),
+                }
+            )]
+        );
+    }
+
+    #[rstest]
+    #[case(json!({"n": {"nested": {}}}))]
+    #[case(json!({"n": {"nested": {"path": ""}}}))]
+    #[case(json!({"n": {"nested": {"path": "a"}, "terms": {"field": "b"}}}))]
+    #[case(json!({"n": {"nested": {"path": "a"}, "aggs": {"m": {"nested": {"path": "b"}}}}}))]
+    #[case(json!({"t": {"terms": {"field": "f"}, "aggs": {"n": {"nested": {"path": "a"}}}}}))]
+    fn test_extract_invalid(#[case] mut request: Value) {
+        assert!(NestedAggregation::extract(&mut request).is_err());
+    }
+
+    #[rstest]
+    fn test_elements() {
+        let nested = NestedAggregation {
+            column: "metadata".into(),
+            path: vec!["order".into(), "items".into()],
+            aggs: json!({}),
+        };
+        assert_eq!(
+            nested.elements(json!({"order": {"items": [{"a": 1}, 2, {"a": 3}]}})),
+            vec![json!({"a": 1}), json!({"a": 3})]
+        );
+        assert_eq!(
+            nested.elements(json!({"order": {"items": {"a": 1}}})),
+            vec![json!({"a": 1})]
+        );
+        assert!(nested.elements(json!({"order": 5})).is_empty());
+        assert_eq!(
+            nested.wrap(json!({"a": 1})),
+            json!({"metadata": {"order": {"items": {"a": 1}}}})
+        );
+    }
+
+    #[rstest]
+    fn test_aggregate_scopes_elements() {
+        let nested = NestedAggregation {
+            column: "metadata".into(),
+            path: vec!["items".into()],
+            aggs: json!({
+                "categories": {
+                    "terms": {"field": "metadata.items.category", "order": {"_key": "asc"}},
+                    "aggs": {"price": {"sum": {"field": "metadata.items.price"}}}
+                }
+            }),
+        };
+        let rows = [
+            json!({"items": [{"category": "a", "price": 1}, {"category": "b", "price": 10}]}),
+            json!({"items": [{"category": "b", "price": 100}]}),
+        ];
+        let (doc_count, results) = nested
+            .aggregate(
+                rows.into_iter().flat_map(|row| nested.elements(row)),
+                AggregationLimitsGuard::new(None, None),
+            )
+            .unwrap();
+        assert_eq!(doc_count, 3);
+
+        let buckets = results["categories"]["buckets"].as_array().unwrap();
+        let sums = buckets
+            .iter()
+            .map(|bucket| {
+                (
+                    bucket["key"].as_str().unwrap(),
+                    bucket["doc_count"].as_u64().unwrap(),
+                    bucket["price"]["value"].as_f64().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sums, vec![("a", 1, 1.0), ("b", 2, 110.0)]);
+    }
+}
@@ -0,0 +1,784 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Pipeline aggregations.
+//!
+//! Tantivy has no notion of aggregations that operate on the output of other aggregations, so we
+//! strip them out of the request before handing it to tantivy and then compute them ourselves
+//! over the finalized JSON result.  Only "parent" pipelines are supported: they're declared as a
+//! sub-aggregation of a multi-bucket aggregation and add one value to each of its buckets.
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+const SUB_AGGREGATION_KEYS: [&str; 2] = ["aggs", "aggregations"];
+const ORDERED_BUCKET_AGGREGATIONS: [&str; 2] = ["histogram", "date_histogram"];
+const BUCKET_AGGREGATIONS: [&str; 7] = [
+    "histogram",
+    "date_histogram",
+    "terms",
+    "range",
+    "multi_terms",
+    "geohash_grid",
+    "geotile_grid",
+];
+
+/// How a pipeline aggregation treats buckets where its input value is missing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapPolicy {
+    /// Missing values produce a `null` output and are ignored by running computations.
+    #[default]
+    Skip,
+    /// Missing values are treated as zero.
+    InsertZeros,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovingAvgModel {
+    #[default]
+    Simple,
+    Linear,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Script {
+    Source { source: String },
+    Inline(String),
+}
+
+impl Script {
+    fn source(&self) -> &str {
+        match self {
+            Script::Source { source } => source,
+            Script::Inline(source) => source,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineAggregation {
+    Derivative {
+        buckets_path: String,
+        #[serde(default)]
+        gap_policy: GapPolicy,
+    },
+    CumulativeSum {
+        buckets_path: String,
+    },
+    MovingAvg {
+        buckets_path: String,
+        #[serde(default = "default_window")]
+        window: usize,
+        #[serde(default)]
+        shift: i64,
+        #[serde(default)]
+        model: MovingAvgModel,
+        #[serde(default)]
+        gap_policy: GapPolicy,
+    },
+    BucketScript {
+        buckets_path: Map<String, Value>,
+        script: Script,
+        #[serde(default)]
+        gap_policy: GapPolicy,
+    },
+}
+
+fn default_window() -> usize {
+    5
+}
+
+impl PipelineAggregation {
+    const NAMES: [&'static str; 4] = [
+        "derivative",
+        "cumulative_sum",
+        "moving_avg",
+        "bucket_script",
+    ];
+
+    fn requires_ordered_parent(&self) -> bool {
+        !matches!(self, PipelineAggregation::BucketScript { .. })
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            PipelineAggregation::Derivative { .. } => "derivative",
+            PipelineAggregation::CumulativeSum { .. } => "cumulative_sum",
+            PipelineAggregation::MovingAvg { .. } => "moving_avg",
+            PipelineAggregation::BucketScript { .. } => "bucket_script",
+        }
+    }
+
+    /// Compute this pipeline's output for every bucket, in bucket order.
+    fn compute(&self, buckets: &[&Map<String, Value>]) -> Result<Vec<Option<f64>>> {
+        match self {
+            PipelineAggregation::Derivative {
+                buckets_path,
+                gap_policy,
+            } => {
+                let values = resolve_all(buckets, buckets_path, *gap_policy)?;
+                let mut previous = None;
+                Ok(values
+                    .into_iter()
+                    .map(|current| {
+                        let derivative = match (previous, current) {
+                            (Some(previous), Some(current)) => Some(current - previous),
+                            _ => None,
+                        };
+                        if current.is_some() {
+                            previous = current;
+                        }
+                        derivative
+                    })
+                    .collect())
+            }
+            PipelineAggregation::CumulativeSum { buckets_path } => {
+                let values = resolve_all(buckets, buckets_path, GapPolicy::InsertZeros)?;
+                let mut sum = 0.0;
+                Ok(values
+                    .into_iter()
+                    .map(|value| {
+                        sum += value.unwrap_or(0.0);
+                        Some(sum)
+                    })
+                    .collect())
+            }
+            PipelineAggregation::MovingAvg {
+                buckets_path,
+                window,
+                shift,
+                model,
+                gap_policy,
+            } => {
+                if *window == 0 {
+                    bail!("moving_avg: `window` must be greater than zero");
+                }
+                let values = resolve_all(buckets, buckets_path, *gap_policy)?;
+                Ok((0..values.len())
+                    .map(|idx| {
+                        // the window covers the `window` buckets preceding the current one, moved
+                        // to the right by `shift`
+                        let end = (idx as i64 + shift).clamp(0, values.len() as i64) as usize;
+                        let start = end.saturating_sub(*window);
+                        let window_values = values[start..end].iter().flatten().copied();
+                        moving_average(window_values, *model)
+                    })
+                    .collect())
+            }
+            PipelineAggregation::BucketScript {
+                buckets_path,
+                script,
+                gap_policy,
+            } => {
+                let expr = Expr::parse(script.source())?;
+                buckets
+                    .iter()
+                    .map(|bucket| {
+                        let mut vars = Vec::with_capacity(buckets_path.len());
+                        for (var, path) in buckets_path {
+                            let path = path.as_str().ok_or_else(|| {
+                                anyhow!("bucket_script: buckets_path `{var}` must be a string")
+                            })?;
+                            match (resolve(bucket, path)?, gap_policy) {
+                                (Some(value), _) => vars.push((var.as_str(), value)),
+                                (None, GapPolicy::InsertZeros) => vars.push((var.as_str(), 0.0)),
+                                (None, GapPolicy::Skip) => return Ok(None),
+                            }
+                        }
+                        expr.eval(&vars)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+fn moving_average(values: impl Iterator<Item = f64>, model: MovingAvgModel) -> Option<f64> {
+    let (mut sum, mut weights, mut count) = (0.0, 0.0, 0);
+    for (idx, value) in values.enumerate() {
+        let weight = match model {
+            MovingAvgModel::Simple => 1.0,
+            // older values are linearly less important than newer ones
+            MovingAvgModel::Linear => (idx + 1) as f64,
+        };
+        sum += value * weight;
+        weights += weight;
+        count += 1;
+    }
+    (count > 0).then(|| sum / weights)
+}
+
+/// The pipeline aggregations declared at one level of an aggregation request, along with those
+/// declared further down inside each bucket aggregation at this level.
+#[derive(Debug, Default, PartialEq)]
+pub struct PipelineTree {
+    pipelines: Vec<(String, PipelineAggregation)>,
+    children: Vec<(String, PipelineTree)>,
+}
+
+impl PipelineTree {
+    /// Remove all pipeline aggregations from `request`, returning them as a [`PipelineTree`] that
+    /// can later be applied to the aggregation result with [`PipelineTree::apply`].
+    pub fn extract(request: &mut Value) -> Result<Self> {
+        let Some(aggs) = request.as_object_mut() else {
+            return Ok(Self::default());
+        };
+        let tree = Self::extract_level(aggs)?;
+        if let Some((name, pipeline)) = tree.pipelines.first() {
+            bail!(
+                "{} aggregation `{name}` must be declared within a bucket aggregation",
+                pipeline.name()
+            );
+        }
+        Ok(tree)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pipelines.is_empty() && self.children.is_empty()
+    }
+
+    fn extract_level(aggs: &mut Map<String, Value>) -> Result<Self> {
+        let mut tree = PipelineTree::default();
+
+        let pipeline_names = aggs
+            .iter()
+            .filter(|(_, def)| is_pipeline(def))
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        for name in pipeline_names {
+            let def = aggs
+                .remove(&name)
+                .expect("pipeline aggregation should exist");
+            let pipeline = serde_json::from_value::<PipelineAggregation>(def)
+                .map_err(|e| anyhow!("invalid pipeline aggregation `{name}`: {e}"))?;
+            tree.pipelines.push((name, pipeline));
+        }
+
+        for (name, def) in aggs.iter_mut() {
+            let Some(def) = def.as_object_mut() else {
+                continue;
+            };
+            let kind = def
+                .keys()
+                .find(|key| !SUB_AGGREGATION_KEYS.contains(&key.as_str()))
+                .cloned();
+
+            for key in SUB_AGGREGATION_KEYS {
+                let Some(sub_aggs) = def.get_mut(key).and_then(Value::as_object_mut) else {
+                    continue;
+                };
+                let subtree = Self::extract_level(sub_aggs)?;
+                for (pipeline_name, pipeline) in &subtree.pipelines {
+                    let kind = kind.as_deref().unwrap_or_default();
+                    let valid_parent = if pipeline.requires_ordered_parent() {
+                        ORDERED_BUCKET_AGGREGATIONS.contains(&kind)
+                    } else {
+                        BUCKET_AGGREGATIONS.contains(&kind)
+                    };
+                    if !valid_parent {
+                        bail!(
+                            "{} aggregation `{pipeline_name}` cannot be declared within `{name}`, which is a `{kind}` aggregation",
+                            pipeline.name()
+                        );
+                    }
+                }
+                if sub_aggs.is_empty() {
+                    def.remove(key);
+                }
+                if !subtree.is_empty() {
+                    tree.children.push((name.clone(), subtree));
+                }
+                break;
+            }
+        }
+
+        Ok(tree)
+    }
+
+    /// Compute every pipeline aggregation in this tree and add its output to the matching
+    /// buckets of `result`.
+    pub fn apply(&self, result: &mut Value) -> Result<()> {
+        let Some(result) = result.as_object_mut() else {
+            return Ok(());
+        };
+        self.apply_level(result)
+    }
+
+    fn apply_level(&self, result: &mut Map<String, Value>) -> Result<()> {
+        for (name, subtree) in &self.children {
+            let Some(buckets) = result.get_mut(name).and_then(|agg| agg.get_mut("buckets")) else {
+                continue;
+            };
+
+            // tantivy returns buckets as an array, or as an object when `keyed` is requested
+            let mut buckets = match buckets {
+                Value::Array(buckets) => buckets
+                    .iter_mut()
+                    .filter_map(Value::as_object_mut)
+                    .collect::<Vec<_>>(),
+                Value::Object(buckets) => buckets
+                    .values_mut()
+                    .filter_map(Value::as_object_mut)
+                    .collect::<Vec<_>>(),
+                _ => continue,
+            };
+
+            // apply nested pipelines first so that ours can reference their output
+            for bucket in buckets.iter_mut() {
+                subtree.apply_level(bucket)?;
+            }
+
+            for (pipeline_name, pipeline) in &subtree.pipelines {
+                let values = {
+                    let readonly = buckets.iter().map(|bucket| &**bucket).collect::<Vec<_>>();
+                    pipeline
+                        .compute(&readonly)
+                        .map_err(|e| anyhow!("{pipeline_name}: {e}"))?
+                };
+                for (bucket, value) in buckets.iter_mut().zip(values) {
+                    bucket.insert(pipeline_name.clone(), serde_json::json!({ "value": value }));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_pipeline(def: &Value) -> bool {
+    def.as_object().is_some_and(|def| {
+        def.keys()
+            .any(|key| PipelineAggregation::NAMES.contains(&key.as_str()))
+    })
+}
+
+fn resolve_all(
+    buckets: &[&Map<String, Value>],
+    path: &str,
+    gap_policy: GapPolicy,
+) -> Result<Vec<Option<f64>>> {
+    buckets
+        .iter()
+        .map(|bucket| {
+            let value = resolve(bucket, path)?;
+            Ok(match gap_policy {
+                GapPolicy::Skip => value,
+                GapPolicy::InsertZeros => Some(value.unwrap_or(0.0)),
+            })
+        })
+        .collect()
+}
+
+/// Resolve a `buckets_path` against a single bucket.
+///
+/// The path is either `_count` (the bucket's document count), the name of a single-value metric
+/// aggregation, or `name.stat` for a multi-value metric aggregation like `stats`.  `>` separates
+/// the names of nested single-bucket aggregations.
+fn resolve(bucket: &Map<String, Value>, path: &str) -> Result<Option<f64>> {
+    if path == "_count" {
+        return Ok(bucket.get("doc_count").and_then(Value::as_f64));
+    }
+
+    let mut current = bucket;
+    let mut segments = path.split('>').peekable();
+    while let Some(segment) = segments.next() {
+        if segments.peek().is_some() {
+            current = current
+                .get(segment)
+                .and_then(Value::as_object)
+                .ok_or_else(|| {
+                    anyhow!("buckets_path `{path}`: no aggregation named `{segment}`")
+                })?;
+            continue;
+        }
+
+        let (name, stat) = match segment.split_once('.') {
+            Some((name, stat)) => (name, stat),
+            None => (segment, "value"),
+        };
+        if name == "_count" {
+            return Ok(current.get("doc_count").and_then(Value::as_f64));
+        }
+        let agg = current
+            .get(name)
+            .ok_or_else(|| anyhow!("buckets_path `{path}`: no aggregation named `{name}`"))?;
+        return match agg.get(stat) {
+            Some(Value::Null) => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .map(Some)
+                .ok_or_else(|| anyhow!("buckets_path `{path}` does not refer to a number")),
+            None if stat == "value" => Ok(agg.as_f64()),
+            None => bail!("buckets_path `{path}`: `{name}` has no value named `{stat}`"),
+        };
+    }
+
+    bail!("buckets_path must not be empty")
+}
+
+/// A tiny arithmetic expression language for `bucket_script`.
+///
+/// Supports numeric literals, variables (optionally prefixed with `params.`), parentheses, unary
+/// minus, and the binary operators `+ - * / %`.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, char, Box<Expr>),
+}
+
+impl Expr {
+    fn parse(source: &str) -> Result<Expr> {
+        let tokens = tokenize(source)?;
+        let mut pos = 0;
+        let expr = parse_sum(&tokens, &mut pos)?;
+        if pos != tokens.len() {
+            bail!(
+                "bucket_script: unexpected `{:?}` in script `{source}`",
+                tokens[pos]
+            );
+        }
+        Ok(expr)
+    }
+
+    fn eval(&self, vars: &[(&str, f64)]) -> Result<Option<f64>> {
+        Ok(match self {
+            Expr::Number(n) => Some(*n),
+            Expr::Var(name) => Some(
+                vars.iter()
+                    .find(|(var, _)| *var == name.as_str())
+                    .map(|(_, value)| *value)
+                    .ok_or_else(|| anyhow!("bucket_script: unknown variable `{name}`"))?,
+            ),
+            Expr::Neg(expr) => expr.eval(vars)?.map(|value| -value),
+            Expr::Binary(left, op, right) => {
+                let (Some(left), Some(right)) = (left.eval(vars)?, right.eval(vars)?) else {
+                    return Ok(None);
+                };
+                let value = match op {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    '/' => left / right,
+                    '%' => left % right,
+                    _ => unreachable!("unknown operator `{op}`"),
+                };
+                // dividing by zero doesn't produce a meaningful bucket value
+                value.is_finite().then_some(value)
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '0'..='9' | '.' => {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                    number.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Number(
+                    number
+                        .parse()
+                        .map_err(|_| anyhow!("bucket_script: invalid number `{number}`"))?,
+                ));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&c) = chars
+                    .peek()
+                    .filter(|c| c.is_alphanumeric() || **c == '_' || **c == '.')
+                {
+                    ident.push(c);
+                    chars.next();
+                }
+                let ident = ident.strip_prefix("params.").unwrap_or(&ident).to_string();
+                tokens.push(Token::Ident(ident));
+            }
+            '+' | '-' | '*' | '/' | '%' => {
+                tokens.push(Token::Op(c));
+                chars.next();
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                chars.next();
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                chars.next();
+            }
+            _ => bail!("bucket_script: unexpected character `{c}` in script `{source}`"),
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_sum(tokens: &[Token], pos: &mut usize) -> Result<Expr> {
+    let mut left = parse_product(tokens, pos)?;
+    while let Some(Token::Op(op @ ('+' | '-'))) = tokens.get(*pos) {
+        *pos += 1;
+        let right = parse_product(tokens, pos)?;
+        left = Expr::Binary(Box::new(left), *op, Box::new(right));
+    }
+    Ok(left)
+}
+
+fn parse_product(tokens: &[Token], pos: &mut usize) -> Result<Expr> {
+    let mut left = parse_unary(tokens, pos)?;
+    while let Some(Token::Op(op @ ('*' | '/' | '%'))) = tokens.get(*pos) {
+        *pos += 1;
+        let right = parse_unary(tokens, pos)?;
+        left = Expr::Binary(Box::new(left), *op, Box::new(right));
+    }
+    Ok(left)
+}
+
+fn parse_unary(tokens: &[Token], pos: &mut usize) -> Result<Expr> {
+    match tokens.get(*pos) {
+        Some(Token::Op('-')) => {
+            *pos += 1;
+            Ok(Expr::Neg(Box::new(parse_unary(tokens, pos)?)))
+        }
+        Some(Token::Number(n)) => {
+            *pos += 1;
+            Ok(Expr::Number(*n))
+        }
+        Some(Token::Ident(name)) => {
+            *pos += 1;
+            Ok(Expr::Var(name.clone()))
+        }
+        Some(Token::LParen) => {
+            *pos += 1;
+            let expr = parse_sum(tokens, pos)?;
+            if tokens.get(*pos) != Some(&Token::RParen) {
+                bail!("bucket_script: unbalanced parentheses");
+            }
+            *pos += 1;
+            Ok(expr)
+        }
+        Some(token) => bail!("bucket_script: unexpected `{token:?}`"),
+        None => bail!("bucket_script: unexpected end of script"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    fn histogram_result() -> Value {
+        json!({
+            "by_day": {
+                "buckets": [
+                    {"key": 0.0, "doc_count": 2, "sales": {"value": 10.0}},
+                    {"key": 1.0, "doc_count": 4, "sales": {"value": 30.0}},
+                    {"key": 2.0, "doc_count": 0, "sales": {"value": null}},
+                    {"key": 3.0, "doc_count": 5, "sales": {"value": 25.0}}
+                ]
+            }
+        })
+    }
+
+    fn outputs(result: &Value, name: &str) -> Vec<Value> {
+        result["by_day"]["buckets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| bucket[name]["value"].clone())
+            .collect()
+    }
+
+    #[rstest]
+    fn test_extract_removes_pipelines() {
+        let mut request = json!({
+            "by_day": {
+                "histogram": {"field": "day", "interval": 1},
+                "aggs": {
+                    "sales": {"sum": {"field": "price"}},
+                    "sales_deriv": {"derivative": {"buckets_path": "sales"}}
+                }
+            }
+        });
+        let tree = PipelineTree::extract(&mut request).unwrap();
+        assert!(!tree.is_empty());
+        assert_eq!(
+            request,
+            json!({
+                "by_day": {
+                    "histogram": {"field": "day", "interval": 1},
+                    "aggs": {"sales": {"sum": {"field": "price"}}}
+                }
+            })
+        );
+    }
+
+    #[rstest]
+    fn test_extract_drops_empty_sub_aggregations() {
+        let mut request = json!({
+            "by_day": {
+                "histogram": {"field": "day", "interval": 1},
+                "aggs": {"count_sum": {"cumulative_sum": {"buckets_path": "_count"}}}
+            }
+        });
+        PipelineTree::extract(&mut request).unwrap();
+        assert_eq!(
+            request,
+            json!({"by_day": {"histogram": {"field": "day", "interval": 1}}})
+        );
+    }
+
+    #[rstest]
+    fn test_extract_rejects_top_level_pipeline() {
+        let mut request = json!({"d": {"derivative": {"buckets_path": "x"}}});
+        assert!(PipelineTree::extract(&mut request).is_err());
+    }
+
+    #[rstest]
+    fn test_extract_rejects_unordered_parent() {
+        let mut request = json!({
+            "by_category": {
+                "terms": {"field": "category"},
+                "aggs": {"d": {"derivative": {"buckets_path": "_count"}}}
+            }
+        });
+        assert!(PipelineTree::extract(&mut request).is_err());
+    }
+
+    fn apply(pipelines: Value) -> Value {
+        let mut request = json!({
+            "by_day": {
+                "histogram": {"field": "day", "interval": 1},
+                "aggs": pipelines
+            }
+        });
+        let tree = PipelineTree::extract(&mut request).unwrap();
+        let mut result = histogram_result();
+        tree.apply(&mut result).unwrap();
+        result
+    }
+
+    #[rstest]
+    fn test_derivative() {
+        let result = apply(json!({"d": {"derivative": {"buckets_path": "sales"}}}));
+        assert_eq!(
+            outputs(&result, "d"),
+            vec![json!(null), json!(20.0), json!(null), json!(-5.0)]
+        );
+
+        let result = apply(json!({
+            "d": {"derivative": {"buckets_path": "sales", "gap_policy": "insert_zeros"}}
+        }));
+        assert_eq!(
+            outputs(&result, "d"),
+            vec![json!(null), json!(20.0), json!(-30.0), json!(25.0)]
+        );
+    }
+
+    #[rstest]
+    fn test_cumulative_sum() {
+        let result = apply(json!({"c": {"cumulative_sum": {"buckets_path": "_count"}}}));
+        assert_eq!(
+            outputs(&result, "c"),
+            vec![json!(2.0), json!(6.0), json!(6.0), json!(11.0)]
+        );
+    }
+
+    #[rstest]
+    fn test_moving_avg() {
+        let result = apply(json!({"m": {"moving_avg": {"buckets_path": "sales", "window": 2}}}));
+        assert_eq!(
+            outputs(&result, "m"),
+            vec![json!(null), json!(10.0), json!(20.0), json!(30.0)]
+        );
+
+        let result = apply(json!({
+            "m": {"moving_avg": {"buckets_path": "_count", "window": 2, "shift": 1}}
+        }));
+        assert_eq!(
+            outputs(&result, "m"),
+            vec![json!(2.0), json!(3.0), json!(2.0), json!(2.5)]
+        );
+    }
+
+    #[rstest]
+    fn test_bucket_script() {
+        let result = apply(json!({
+            "avg_sale": {
+                "bucket_script": {
+                    "buckets_path": {"total": "sales", "count": "_count"},
+                    "script": "params.total / params.count"
+                }
+            }
+        }));
+        assert_eq!(
+            outputs(&result, "avg_sale"),
+            vec![json!(5.0), json!(7.5), json!(null), json!(5.0)]
+        );
+    }
+
+    #[rstest]
+    fn test_pipeline_of_pipeline() {
+        let result = apply(json!({
+            "c": {"cumulative_sum": {"buckets_path": "sales"}},
+            "d": {"derivative": {"buckets_path": "c"}}
+        }));
+        assert_eq!(
+            outputs(&result, "d"),
+            vec![json!(null), json!(30.0), json!(0.0), json!(25.0)]
+        );
+    }
+
+    #[rstest]
+    #[case("1 + 2 * 3", 7.0)]
+    #[case("(1 + 2) * 3", 9.0)]
+    #[case("-a + 10 % 4", 0.0)]
+    #[case("params.a * 2 - 1", 3.0)]
+    fn test_expr(#[case] source: &str, #[case] expected: f64) {
+        let expr = Expr::parse(source).unwrap();
+        assert_eq!(expr.eval(&[("a", 2.0)]).unwrap(), Some(expected));
+    }
+
+    #[rstest]
+    #[case("1 +")]
+    #[case("(1 + 2")]
+    #[case("a ^ 2")]
+    fn test_expr_invalid(#[case] source: &str) {
+        assert!(Expr::parse(source).is_err());
+    }
+}
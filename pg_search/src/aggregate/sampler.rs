@@ -0,0 +1,356 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Sampler aggregations.
+//!
+//! A sampler computes its sub-aggregations over a subset of the matching documents: either the
+//! top `shard_size` documents by score (`sampler`), or a random `probability` fraction of them
+//! (`random_sampler`).  Tantivy has no such aggregation, so they're pulled out of the request and
+//! executed separately, with a [`SampleFilterCollector`] restricting the documents that reach the
+//! sub-aggregations.
+
+use anyhow::{anyhow, bail, Result};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use tantivy::collector::{Collector, SegmentCollector};
+use tantivy::{DocAddress, DocId, Score, SegmentOrdinal, SegmentReader};
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum SamplerKind {
+    /// Only the `shard_size` highest scoring documents are sampled.
+    Sampler {
+        #[serde(default = "default_shard_size")]
+        shard_size: usize,
+    },
+    /// Each matching document is sampled with the given `probability`.  The same `seed` always
+    /// selects the same documents from an unchanged index.
+    RandomSampler {
+        probability: f64,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+fn default_shard_size() -> usize {
+    100
+}
+
+impl SamplerKind {
+    pub const NAMES: [&'static str; 2] = ["sampler", "random_sampler"];
+
+    pub fn needs_scores(&self) -> bool {
+        matches!(self, SamplerKind::Sampler { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplerAggregation {
+    pub kind: SamplerKind,
+    pub aggs: Value,
+}
+
+impl SamplerAggregation {
+    /// Remove all top-level sampler aggregations from `request`, keyed by their name.
+    pub fn extract(request: &mut Value) -> Result<Vec<(String, SamplerAggregation)>> {
+        let Some(aggs) = request.as_object_mut() else {
+            return Ok(vec![]);
+        };
+
+        let names = aggs
+            .iter()
+            .filter(|(_, def)| is_sampler(def))
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+
+        let mut samplers = Vec::with_capacity(names.len());
+        for name in names {
+            let Some(Value::Object(mut def)) = aggs.remove(&name) else {
+                unreachable!("sampler aggregation should be an object");
+            };
+            let sub_aggs = def
+                .remove("aggs")
+                .or_else(|| def.remove("aggregations"))
+                .unwrap_or_else(|| Value::Object(Map::new()));
+            if !sub_aggs.is_object() {
+                bail!("sampler aggregation `{name}`: `aggs` must be an object");
+            }
+            if let Some(nested) = sub_aggs
+                .as_object()
+                .and_then(|aggs| aggs.iter().find(|(_, def)| contains_sampler(def)))
+            {
+                bail!(
+                    "sampler aggregation `{name}` cannot contain another sampler aggregation `{}`",
+                    nested.0
+                );
+            }
+
+            let kind = serde_json::from_value::<SamplerKind>(Value::Object(def))
+                .map_err(|e| anyhow!("invalid sampler aggregation `{name}`: {e}"))?;
+            match kind {
+                SamplerKind::Sampler { shard_size: 0 } => {
+                    bail!("sampler aggregation `{name}`: `shard_size` must be greater than zero");
+                }
+                SamplerKind::RandomSampler { probability, .. }
+                    if probability.is_nan() || probability <= 0.0 || probability > 1.0 =>
+                {
+                    bail!("random_sampler aggregation `{name}`: `probability` must be in (0, 1]");
+                }
+                _ => {}
+            }
+            samplers.push((
+                name,
+                SamplerAggregation {
+                    kind,
+                    aggs: sub_aggs,
+                },
+            ));
+        }
+
+        if let Some(nested) = aggs.iter().find(|(_, def)| contains_sampler(def)) {
+            bail!(
+                "sampler aggregations must be declared at the top level, but `{}` contains one",
+                nested.0
+            );
+        }
+
+        Ok(samplers)
+    }
+}
+
+fn is_sampler(def: &Value) -> bool {
+    def.as_object().is_some_and(|def| {
+        def.keys()
+            .any(|key| SamplerKind::NAMES.contains(&key.as_str()))
+    })
+}
+
+fn contains_sampler(def: &Value) -> bool {
+    is_sampler(def)
+        || def.as_object().is_some_and(|def| {
+            ["aggs", "aggregations"].iter().any(|key| {
+                def.get(*key)
+                    .and_then(Value::as_object)
+                    .is_some_and(|aggs| aggs.values().any(contains_sampler))
+            })
+        })
+}
+
+/// The set of documents a sampler lets through to its sub-aggregations.
+#[derive(Clone)]
+pub enum Sample {
+    /// Exactly these documents, grouped by segment.
+    Docs(Arc<FxHashMap<SegmentOrdinal, FxHashSet<DocId>>>),
+    Random {
+        threshold: u64,
+        seed: u64,
+    },
+}
+
+impl Sample {
+    pub fn from_top_docs(top_docs: impl IntoIterator<Item = (Score, DocAddress)>) -> Self {
+        Self::from_docs(top_docs.into_iter().map(|(_, doc_address)| doc_address))
+    }
+
+    pub fn from_docs(doc_addresses: impl IntoIterator<Item = DocAddress>) -> Self {
+        let mut docs = FxHashMap::<SegmentOrdinal, FxHashSet<DocId>>::default();
+        for doc_address in doc_addresses {
+            docs.entry(doc_address.segment_ord)
+                .or_default()
+                .insert(doc_address.doc_id);
+        }
+        Sample::Docs(Arc::new(docs))
+    }
+
+    pub fn random(probability: f64, seed: u64) -> Self {
+        Sample::Random {
+            threshold: (probability * u64::MAX as f64) as u64,
+            seed,
+        }
+    }
+}
+
+pub struct SampleFilterCollector<C: Collector> {
+    inner: C,
+    sample: Sample,
+}
+
+impl<C: Collector> SampleFilterCollector<C> {
+    pub fn new(inner: C, sample: Sample) -> Self {
+        Self { inner, sample }
+    }
+}
+
+impl<C: Collector> Collector for SampleFilterCollector<C> {
+    type Fruit = C::Fruit;
+    type Child = SampleFilterSegmentCollector<C::Child>;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        let filter = match &self.sample {
+            Sample::Docs(docs) => {
+                SegmentSample::Docs(docs.get(&segment_local_id).cloned().unwrap_or_default())
+            }
+            Sample::Random { threshold, seed } => SegmentSample::Random {
+                threshold: *threshold,
+                // mixing in the segment id keeps doc ids from different segments independent
+                seed: seed ^ mix(segment_local_id as u64),
+            },
+        };
+        Ok(SampleFilterSegmentCollector {
+            inner: self.inner.for_segment(segment_local_id, segment)?,
+            filter,
+            filtered_buffer: Vec::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.inner.requires_scoring()
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> tantivy::Result<Self::Fruit> {
+        self.inner.merge_fruits(segment_fruits)
+    }
+}
+
+enum SegmentSample {
+    Docs(FxHashSet<DocId>),
+    Random { threshold: u64, seed: u64 },
+}
+
+impl SegmentSample {
+    #[inline]
+    fn contains(&self, doc: DocId) -> bool {
+        match self {
+            SegmentSample::Docs(docs) => docs.contains(&doc),
+            SegmentSample::Random { threshold, seed } => mix(seed ^ doc as u64) <= *threshold,
+        }
+    }
+}
+
+pub struct SampleFilterSegmentCollector<SC: SegmentCollector> {
+    inner: SC,
+    filter: SegmentSample,
+    filtered_buffer: Vec<DocId>,
+}
+
+impl<SC: SegmentCollector> SegmentCollector for SampleFilterSegmentCollector<SC> {
+    type Fruit = SC::Fruit;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        if self.filter.contains(doc) {
+            self.inner.collect(doc, score);
+        }
+    }
+
+    fn collect_block(&mut self, docs: &[DocId]) {
+        self.filtered_buffer.clear();
+        self.filtered_buffer.extend(
+            docs.iter()
+                .copied()
+                .filter(|doc| self.filter.contains(*doc)),
+        );
+        self.inner.collect_block(&self.filtered_buffer);
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        self.inner.harvest()
+    }
+}
+
+/// The splitmix64 finalizer, which is plenty for spreading doc ids uniformly.
+#[inline]
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    fn test_extract_samplers() {
+        let mut request = json!({
+            "sample": {
+                "sampler": {"shard_size": 10},
+                "aggs": {"categories": {"terms": {"field": "category"}}}
+            },
+            "random": {
+                "random_sampler": {"probability": 0.5, "seed": 42}
+            },
+            "ratings": {"avg": {"field": "rating"}}
+        });
+        let samplers = SamplerAggregation::extract(&mut request).unwrap();
+        assert_eq!(request, json!({"ratings": {"avg": {"field": "rating"}}}));
+        assert_eq!(
+            samplers,
+            vec![
+                (
+                    "sample".to_string(),
+                    SamplerAggregation {
+                        kind: SamplerKind::Sampler { shard_size: 10 },
+                        aggs: json!({"categories": {"terms": {"field": "category"}}}),
+                    }
+                ),
+                (
+                    "random".to_string(),
+                    SamplerAggregation {
+                        kind: SamplerKind::RandomSampler {
+                            probability: 0.5,
+                            seed: 42
+                        },
+                        aggs: json!({}),
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(json!({"s": {"random_sampler": {"probability": 0.0}}}))]
+    #[case(json!({"s": {"random_sampler": {"probability": 1.5}}}))]
+    #[case(json!({"s": {"sampler": {"size": 10}}}))]
+    #[case(json!({"s": {"sampler": {"shard_size": 0}}}))]
+    #[case(json!({"s": {"sampler": {}, "aggs": {"t": {"sampler": {}}}}}))]
+    #[case(json!({"t": {"terms": {"field": "f"}, "aggs": {"s": {"sampler": {}}}}}))]
+    fn test_extract_invalid(#[case] mut request: Value) {
+        assert!(SamplerAggregation::extract(&mut request).is_err());
+    }
+
+    #[rstest]
+    fn test_random_sample_rate() {
+        let sample = SegmentSample::Random {
+            threshold: (0.25 * u64::MAX as f64) as u64,
+            seed: 7,
+        };
+        let sampled = (0..100_000).filter(|doc| sample.contains(*doc)).count();
+        assert!((24_000..26_000).contains(&sampled), "sampled {sampled}");
+    }
+}
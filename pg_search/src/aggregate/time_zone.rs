@@ -0,0 +1,279 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Time zones for `date_histogram` aggregations.
+//!
+//! Tantivy buckets dates by fixed-length intervals counted from the unix epoch, which it can shift
+//! by a fixed `offset`, so without one a day runs from midnight to midnight UTC.  The `time_zone`
+//! of a `date_histogram` that's a UTC offset, like `+09:00`, is translated to the `offset` that
+//! starts its buckets at that zone's midnight instead.
+//!
+//! A named time zone like `America/New_York` changes its offset for daylight saving time, which no
+//! single `offset` can follow.  `paradedb.timeseries()` buckets by one by splitting the dates into
+//! the [`periods`] over which the zone's offset doesn't change, bucketing each period by its own
+//! offset, and merging the buckets of the same local time that a transition cut in two.
+//!
+//! Only bucketing is done in a time zone.  Range aggregations and range queries compare dates as
+//! the instants they're written as, which is in UTC unless they carry an offset of their own.
+
+use anyhow::{anyhow, bail, ensure, Result};
+use pgrx::datum::TimestampWithTimeZone;
+use pgrx::Spi;
+use serde_json::Value;
+
+const SUB_AGGREGATION_KEYS: [&str; 2] = ["aggs", "aggregations"];
+
+/// Translate the `time_zone` of every `date_histogram` in `request` to the `offset` that buckets
+/// by that zone's local time.
+pub fn translate(request: &mut Value) -> Result<()> {
+    let Some(aggs) = request.as_object_mut() else {
+        return Ok(());
+    };
+    for (name, def) in aggs.iter_mut() {
+        let Some(def) = def.as_object_mut() else {
+            continue;
+        };
+        for key in SUB_AGGREGATION_KEYS {
+            if let Some(sub_aggs) = def.get_mut(key) {
+                translate(sub_aggs)?;
+            }
+        }
+
+        let Some(Value::Object(date_histogram)) = def.get_mut("date_histogram") else {
+            continue;
+        };
+        let Some(time_zone) = date_histogram.remove("time_zone") else {
+            continue;
+        };
+        let time_zone = time_zone
+            .as_str()
+            .ok_or_else(|| anyhow!("date_histogram `{name}`: `time_zone` must be a string"))?;
+        let offset = utc_offset(time_zone).ok_or_else(|| {
+            anyhow!("date_histogram `{name}`: `time_zone` `{time_zone}` must be a UTC offset like `+09:00`, use paradedb.timeseries() to bucket by a named time zone")
+        })?;
+        ensure!(
+            !date_histogram.contains_key("offset"),
+            "date_histogram `{name}`: `time_zone` can't be combined with `offset`"
+        );
+        if offset != 0 {
+            date_histogram.insert("offset".into(), tantivy_offset(offset).into());
+        }
+    }
+    Ok(())
+}
+
+/// The seconds east of UTC of a time zone that's written as a UTC offset, like `+09:00`, `-0330`,
+/// `+02` or `UTC`, or [`None`] if it's a named time zone.
+pub fn utc_offset(time_zone: &str) -> Option<i64> {
+    if ["UTC", "Z", "GMT"]
+        .iter()
+        .any(|utc| time_zone.eq_ignore_ascii_case(utc))
+    {
+        return Some(0);
+    }
+
+    let (sign, rest) = match time_zone.as_bytes().first()? {
+        b'+' => (1, &time_zone[1..]),
+        b'-' => (-1, &time_zone[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some(parts) => parts,
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "00"),
+    };
+    let is_number =
+        |part: &str| (1..=2).contains(&part.len()) && part.bytes().all(|b| b.is_ascii_digit());
+    if !is_number(hours) || !is_number(minutes) {
+        return None;
+    }
+    let (hours, minutes) = (hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?);
+    (hours <= 14 && minutes < 60).then_some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// The `offset` that starts tantivy's buckets at the local midnight of a zone `offset` seconds
+/// east of UTC, which is that many seconds before midnight UTC.
+pub fn tantivy_offset(offset: i64) -> String {
+    format!("{:+}s", -offset)
+}
+
+/// The length of a tantivy `fixed_interval`, like `90m` or `1d`, in milliseconds.
+pub fn interval_millis(interval: &str) -> Result<i64> {
+    let unit_start = interval
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(interval.len());
+    let (count, unit) = interval.split_at(unit_start);
+    let unit = match unit {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        _ => bail!("interval `{interval}` must be a number of `ms`, `s`, `m`, `h` or `d`"),
+    };
+    match count.parse::<i64>() {
+        Ok(count) if count > 0 => Ok(count * unit),
+        _ => bail!("interval `{interval}` must be a positive length of time"),
+    }
+}
+
+/// A span of time, in milliseconds from the unix epoch, over which a time zone is `offset`
+/// seconds east of UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    pub start: i64,
+    pub end: i64,
+    pub offset: i64,
+}
+
+/// The periods from `start` to `end` over which the offset of `time_zone` doesn't change.
+///
+/// The zone's offset is looked up every 15 minutes from `start` rounded down to a quarter of an
+/// hour, which every transition in the time zone database falls on.
+pub fn periods(time_zone: &str, start: i64, end: i64) -> Result<Vec<Period>> {
+    const QUARTER_HOUR: i64 = 900_000;
+    let start = start.div_euclid(QUARTER_HOUR) * QUARTER_HOUR;
+
+    let transitions = Spi::connect(|client| {
+        client
+            .select(
+                "SELECT (extract(epoch FROM t) * 1000)::int8, offset_secs FROM (
+                     SELECT t, offset_secs, lag(offset_secs) OVER (ORDER BY t) AS previous FROM (
+                         SELECT t, extract(epoch FROM (t AT TIME ZONE $3) - (t AT TIME ZONE 'UTC'))::int8 AS offset_secs
+                         FROM generate_series(to_timestamp($1 / 1000), to_timestamp($2 / 1000), interval '15 minutes') t
+                     ) offsets
+                 ) transitions
+                 WHERE previous IS DISTINCT FROM offset_secs
+                 ORDER BY t",
+                None,
+                &[(start as f64).into(), (end as f64).into(), time_zone.into()],
+            )?
+            .map(|row| Ok((row.get::<i64>(1)?, row.get::<i64>(2)?)))
+            .collect::<Result<Vec<_>, pgrx::spi::Error>>()
+    })?;
+
+    let transitions = transitions
+        .into_iter()
+        .filter_map(|(at, offset)| Some((at?, offset?)))
+        .collect::<Vec<_>>();
+    Ok(transitions
+        .iter()
+        .enumerate()
+        .map(|(i, &(at, offset))| Period {
+            start: at,
+            end: transitions.get(i + 1).map_or(end, |&(next, _)| next),
+            offset,
+        })
+        .collect())
+}
+
+/// The start of the bucket of `interval` milliseconds that the bucket `key` of a period `offset`
+/// seconds east of UTC is in, as milliseconds from the unix epoch of local time.
+pub fn local_bucket(key: i64, offset: i64, interval: i64) -> i64 {
+    (key + offset * 1000).div_euclid(interval) * interval
+}
+
+/// The instants at which `time_zone`'s clocks show each of `local` milliseconds from the unix
+/// epoch of local time.
+pub fn to_instants(time_zone: &str, local: &[i64]) -> Result<Vec<TimestampWithTimeZone>> {
+    let local = local
+        .iter()
+        .map(|&millis| millis as f64)
+        .collect::<Vec<_>>();
+    let instants = Spi::get_one_with_args::<Vec<Option<TimestampWithTimeZone>>>(
+        "SELECT array_agg((to_timestamp(m / 1000) AT TIME ZONE 'UTC') AT TIME ZONE $2 ORDER BY i)
+         FROM unnest($1::float8[]) WITH ORDINALITY AS u(m, i)",
+        &[local.into(), time_zone.into()],
+    )?
+    .unwrap_or_default();
+    instants
+        .into_iter()
+        .map(|instant| instant.ok_or_else(|| anyhow!("local time should have an instant")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    #[case("UTC", Some(0))]
+    #[case("z", Some(0))]
+    #[case("+09:00", Some(32400))]
+    #[case("-0330", Some(-12600))]
+    #[case("+02", Some(7200))]
+    #[case("+5:45", Some(20700))]
+    #[case("-15:00", None)]
+    #[case("+09:60", None)]
+    #[case("+-9", None)]
+    #[case("America/New_York", None)]
+    fn test_utc_offset(#[case] time_zone: &str, #[case] expected: Option<i64>) {
+        assert_eq!(utc_offset(time_zone), expected);
+    }
+
+    #[rstest]
+    #[case("100ms", Some(100))]
+    #[case("90m", Some(5_400_000))]
+    #[case("1d", Some(86_400_000))]
+    #[case("0h", None)]
+    #[case("1w", None)]
+    #[case("h", None)]
+    fn test_interval_millis(#[case] interval: &str, #[case] expected: Option<i64>) {
+        assert_eq!(interval_millis(interval).ok(), expected);
+    }
+
+    #[rstest]
+    fn test_translate() {
+        let mut request = json!({
+            "tokyo": {
+                "date_histogram": {"field": "d", "fixed_interval": "1d", "time_zone": "+09:00"},
+                "aggs": {
+                    "utc": {"date_histogram": {"field": "d", "fixed_interval": "1h", "time_zone": "UTC"}}
+                }
+            }
+        });
+        translate(&mut request).unwrap();
+        assert_eq!(
+            request,
+            json!({
+                "tokyo": {
+                    "date_histogram": {"field": "d", "fixed_interval": "1d", "offset": "-32400s"},
+                    "aggs": {
+                        "utc": {"date_histogram": {"field": "d", "fixed_interval": "1h"}}
+                    }
+                }
+            })
+        );
+
+        let mut request = json!({
+            "h": {"date_histogram": {"field": "d", "fixed_interval": "1d", "time_zone": "Europe/Paris"}}
+        });
+        assert!(translate(&mut request).is_err());
+    }
+
+    #[rstest]
+    // 2024-03-10 starts at 05:00Z in New York, and the rest of it after the clocks spring forward
+    // at 07:00Z is bucketed from its midnight in EDT, 04:00Z.  Both are the same local day
+    #[case(1_710_046_800_000, -18_000, 1_710_028_800_000)]
+    #[case(1_710_043_200_000, -14_400, 1_710_028_800_000)]
+    #[case(1_710_129_600_000, -14_400, 1_710_115_200_000)]
+    fn test_local_bucket(#[case] key: i64, #[case] offset: i64, #[case] expected: i64) {
+        assert_eq!(local_bucket(key, offset, 86_400_000), expected);
+    }
+}
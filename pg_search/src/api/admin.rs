@@ -19,15 +19,19 @@ use crate::api::FieldName;
 use crate::api::{HashMap, HashSet};
 use crate::index::mvcc::MvccSatisfies;
 use crate::index::reader::index::SearchIndexReader;
+use crate::postgres::datetime::unix_millis_to_timestamptz;
 use crate::postgres::index::IndexKind;
 use crate::postgres::rel::PgSearchRelation;
-use crate::postgres::storage::block::{LinkedList, MVCCEntry, SegmentMetaEntry};
-use crate::postgres::storage::metadata::MetaPage;
+use crate::postgres::stats;
+use crate::postgres::storage::block::{FileEntry, LinkedList, MVCCEntry, SegmentMetaEntry};
+use crate::postgres::storage::linked_bytes::LinkedBytesList;
+use crate::postgres::storage::metadata::{MetaPage, INDEX_FORMAT_VERSION};
 use crate::postgres::utils::item_pointer_to_u64;
 use crate::query::pdb_query::pdb;
 use crate::query::SearchQueryInput;
 use crate::schema::IndexRecordOption;
 use anyhow::Result;
+use pgrx::datum::TimestampWithTimeZone;
 use pgrx::prelude::*;
 use pgrx::JsonB;
 use pgrx::PgRelation;
@@ -308,6 +312,437 @@ fn index_info(
     Ok(TableIterator::new(results))
 }
 
+/// Per-segment statistics for diagnosing merge and bloat behavior: how many documents each
+/// visible segment holds, how many of those are deleted, how its bytes are split across its
+/// components, and when it was created.
+#[allow(clippy::type_complexity)]
+#[pg_extern]
+fn index_segments(
+    index: PgRelation,
+) -> anyhow::Result<
+    TableIterator<
+        'static,
+        (
+            name!(index_name, String),
+            name!(segno, String),
+            name!(num_docs, i64),
+            name!(deleted_docs, i64),
+            name!(byte_size, i64),
+            name!(termdict_bytes, i64),
+            name!(postings_bytes, i64),
+            name!(positions_bytes, i64),
+            name!(fast_fields_bytes, i64),
+            name!(fieldnorms_bytes, i64),
+            name!(store_bytes, i64),
+            name!(deletes_bytes, i64),
+            name!(created_at, Option<TimestampWithTimeZone>),
+        ),
+    >,
+> {
+    let index = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _);
+    let index_kind = IndexKind::for_index(index)?;
+
+    let bytes = |file: Option<FileEntry>| file.map(|file| file.total_bytes as i64).unwrap_or(0);
+    let mut results = Vec::new();
+    for index in index_kind.partitions() {
+        let segment_components = MetaPage::open(&index).segment_metas();
+        let all_entries = unsafe { segment_components.list() };
+
+        for entry in all_entries {
+            if unsafe { !entry.visible() } {
+                continue;
+            }
+            results.push((
+                index.name().to_owned(),
+                entry.segment_id.short_uuid_string(),
+                entry.num_docs() as i64,
+                entry.num_deleted_docs() as i64,
+                entry.byte_size() as i64,
+                bytes(entry.terms),
+                bytes(entry.postings),
+                bytes(entry.positions),
+                bytes(entry.fast_fields),
+                bytes(entry.field_norms),
+                bytes(entry.store),
+                bytes(entry.delete.map(|delete| delete.file_entry)),
+                entry
+                    .created_at()
+                    .and_then(|secs| unix_millis_to_timestamptz(secs as f64 * 1000.0)),
+            ));
+        }
+    }
+
+    Ok(TableIterator::new(results))
+}
+
+/// How many live and deleted documents the visible segments of `index`, or of each of its
+/// partitions, hold, and how many of those segments are deleted enough for `VACUUM` to rewrite,
+/// per the `vacuum_deleted_percent` index option.  Deleted documents take up space, and slow
+/// searches down, until their segments are merged or rewritten.
+#[allow(clippy::type_complexity)]
+#[pg_extern]
+fn index_dead_docs(
+    index: PgRelation,
+) -> anyhow::Result<
+    TableIterator<
+        'static,
+        (
+            name!(index_name, String),
+            name!(live_docs, i64),
+            name!(dead_docs, i64),
+            name!(dead_percent, f64),
+            name!(reclaimable_segments, i64),
+        ),
+    >,
+> {
+    let index = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _);
+    let index_kind = IndexKind::for_index(index)?;
+
+    let mut results = Vec::new();
+    for index in index_kind.partitions() {
+        let min_deleted_fraction = index.options().vacuum_deleted_fraction();
+        let all_entries = unsafe { MetaPage::open(&index).segment_metas().list() };
+
+        let (mut live_docs, mut dead_docs, mut reclaimable_segments) = (0, 0, 0);
+        for entry in all_entries {
+            if unsafe { !entry.visible() } {
+                continue;
+            }
+            live_docs += entry.num_docs() as i64;
+            dead_docs += entry.num_deleted_docs() as i64;
+            if min_deleted_fraction.is_some_and(|fraction| entry.deleted_fraction() > fraction) {
+                reclaimable_segments += 1;
+            }
+        }
+        let dead_percent = match live_docs + dead_docs {
+            0 => 0.0,
+            total => dead_docs as f64 * 100.0 / total as f64,
+        };
+        results.push((
+            index.name().to_owned(),
+            live_docs,
+            dead_docs,
+            dead_percent,
+            reclaimable_segments,
+        ));
+    }
+
+    Ok(TableIterator::new(results))
+}
+
+/// The search statistics of every BM25 index in the current database, since they were last reset
+/// with [`index_stats_reset`].  `paradedb.index_stats` shows them by index name.
+#[allow(clippy::type_complexity)]
+#[pg_extern]
+fn index_stats_data() -> TableIterator<
+    'static,
+    (
+        name!(index_oid, pg_sys::Oid),
+        name!(queries, i64),
+        name!(mean_time_ms, Option<f64>),
+        name!(p95_time_ms, Option<f64>),
+        name!(docs_scanned, i64),
+        name!(blocks_hit, i64),
+        name!(blocks_read, i64),
+        name!(hit_ratio, Option<f64>),
+        name!(index_blocks_hit, i64),
+        name!(index_blocks_read, i64),
+        name!(index_hit_ratio, Option<f64>),
+    ),
+> {
+    let as_millis = |duration: std::time::Duration| duration.as_secs_f64() * 1000.0;
+    TableIterator::new(stats::database_stats().into_iter().map(|index_stats| {
+        (
+            index_stats.index_oid,
+            index_stats.queries as i64,
+            index_stats.mean_time().map(as_millis),
+            index_stats.percentile_time(0.95).map(as_millis),
+            index_stats.docs_scanned as i64,
+            index_stats.blocks_hit as i64,
+            index_stats.blocks_read as i64,
+            index_stats.hit_ratio(),
+            index_stats.index_blocks_hit as i64,
+            index_stats.index_blocks_read as i64,
+            index_stats.index_hit_ratio(),
+        )
+    }))
+}
+
+/// Forget the search statistics of `index`, or of every index in every database if it's `NULL`.
+#[pg_extern]
+fn index_stats_reset(index: default!(Option<PgRelation>, "NULL")) {
+    stats::reset(index.map(|index| index.oid()));
+}
+
+/// The upper bounds, in seconds, of the buckets of the `paradedb_query_duration_seconds` histogram.
+const METRICS_LATENCY_BOUNDS: [f64; 13] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The statistics of [`index_stats_data`], and the counts of writes, merges, and cache lookups,
+/// as one row per metric and index, named and typed the way Prometheus expects.  A histogram's
+/// buckets are cumulative, and the row for its last bucket has an `le` of `Infinity`.
+#[allow(clippy::type_complexity)]
+#[pg_extern]
+fn metrics() -> TableIterator<
+    'static,
+    (
+        name!(metric, String),
+        name!(kind, String),
+        name!(index, String),
+        name!(le, Option<f64>),
+        name!(value, f64),
+    ),
+> {
+    let mut rows = vec![];
+    for index_stats in stats::database_stats() {
+        // an index that has since been dropped has no name to label its metrics with
+        let name = unsafe { pg_sys::get_rel_name(index_stats.index_oid) };
+        if name.is_null() {
+            continue;
+        }
+        let index = unsafe { std::ffi::CStr::from_ptr(name) }
+            .to_string_lossy()
+            .into_owned();
+        let mut row = |metric: &str, kind: &str, le: Option<f64>, value: f64| {
+            rows.push((
+                metric.to_string(),
+                kind.to_string(),
+                index.clone(),
+                le,
+                value,
+            ));
+        };
+
+        let bounds = METRICS_LATENCY_BOUNDS
+            .iter()
+            .map(|seconds| std::time::Duration::from_secs_f64(*seconds))
+            .collect::<Vec<_>>();
+        for (le, count) in METRICS_LATENCY_BOUNDS
+            .iter()
+            .zip(index_stats.cumulative_latencies(&bounds))
+        {
+            row(
+                "paradedb_query_duration_seconds_bucket",
+                "histogram",
+                Some(*le),
+                count as f64,
+            );
+        }
+        let queries = index_stats.queries as f64;
+        row(
+            "paradedb_query_duration_seconds_bucket",
+            "histogram",
+            Some(f64::INFINITY),
+            queries,
+        );
+        row(
+            "paradedb_query_duration_seconds_sum",
+            "histogram",
+            None,
+            index_stats.total_time.as_secs_f64(),
+        );
+        row(
+            "paradedb_query_duration_seconds_count",
+            "histogram",
+            None,
+            queries,
+        );
+
+        for (metric, value) in [
+            ("paradedb_queries_total", index_stats.queries),
+            ("paradedb_docs_scanned_total", index_stats.docs_scanned),
+            ("paradedb_blocks_hit_total", index_stats.blocks_hit),
+            ("paradedb_blocks_read_total", index_stats.blocks_read),
+            (
+                "paradedb_index_blocks_hit_total",
+                index_stats.index_blocks_hit,
+            ),
+            (
+                "paradedb_index_blocks_read_total",
+                index_stats.index_blocks_read,
+            ),
+            ("paradedb_docs_indexed_total", index_stats.docs_indexed),
+            (
+                "paradedb_segments_written_total",
+                index_stats.segments_written,
+            ),
+            ("paradedb_merges_total", index_stats.merges),
+            (
+                "paradedb_segments_merged_total",
+                index_stats.segments_merged,
+            ),
+            ("paradedb_cache_hits_total", index_stats.cache_hits),
+            ("paradedb_cache_misses_total", index_stats.cache_misses),
+        ] {
+            row(metric, "counter", None, value as f64);
+        }
+    }
+    TableIterator::new(rows)
+}
+
+/// The rows of [`metrics`] in Prometheus' text exposition format, for exporters that pass a
+/// scraped query's text through as is.
+#[pg_extern]
+fn metrics_text() -> String {
+    // the samples of a histogram share the name of its family, without their suffix, and each
+    // family's samples must all be together
+    let mut rows = metrics()
+        .map(|row| {
+            let family = match row.1.as_str() {
+                "histogram" => row
+                    .0
+                    .trim_end_matches("_bucket")
+                    .trim_end_matches("_sum")
+                    .trim_end_matches("_count")
+                    .to_string(),
+                _ => row.0.clone(),
+            };
+            (family, row)
+        })
+        .collect::<Vec<_>>();
+    rows.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut text = String::new();
+    let mut previous = None;
+    for (family, (metric, kind, index, le, value)) in rows {
+        if previous.as_ref() != Some(&family) {
+            text.push_str(&format!("# TYPE {family} {kind}\n"));
+            previous = Some(family);
+        }
+        let index = index.replace('\\', "\\\\").replace('"', "\\\"");
+        match le {
+            Some(le) if le.is_infinite() => text.push_str(&format!(
+                "{metric}{{index=\"{index}\",le=\"+Inf\"}} {value}\n"
+            )),
+            Some(le) => text.push_str(&format!(
+                "{metric}{{index=\"{index}\",le=\"{le}\"}} {value}\n"
+            )),
+            None => text.push_str(&format!("{metric}{{index=\"{index}\"}} {value}\n")),
+        }
+    }
+    text
+}
+
+/// Load the blocks of the given segments, or of every visible segment if `segments` is `NULL`,
+/// into the buffer cache, returning how many blocks were read.  Segments are named by the `segno`
+/// that [`index_segments`] reports for them.
+#[pg_extern]
+fn prewarm_segments(
+    index: PgRelation,
+    segments: default!(Option<Vec<String>>, "NULL"),
+) -> anyhow::Result<i64> {
+    let index = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _);
+    let index_kind = IndexKind::for_index(index)?;
+
+    let mut unmatched = segments.clone().unwrap_or_default();
+    let mut nblocks = 0;
+    for index in index_kind.partitions() {
+        let segment_components = MetaPage::open(&index).segment_metas();
+        let all_entries = unsafe { segment_components.list() };
+
+        for entry in all_entries {
+            if unsafe { !entry.visible() } {
+                continue;
+            }
+            let segno = entry.segment_id.short_uuid_string();
+            if let Some(segments) = &segments {
+                if !segments.contains(&segno) {
+                    continue;
+                }
+                unmatched.retain(|unmatched| unmatched != &segno);
+            }
+            for (file_entry, _) in entry.file_entries() {
+                nblocks +=
+                    unsafe { LinkedBytesList::open(&index, file_entry.starting_block).prewarm() }
+                        as i64;
+            }
+        }
+    }
+
+    if !unmatched.is_empty() {
+        anyhow::bail!("segment(s) not found: {}", unmatched.join(", "));
+    }
+    Ok(nblocks)
+}
+
+/// How many bytes each field takes up across the index's visible segments, summed over its term
+/// dictionary, postings, positions, fast fields, and fieldnorms and sorted largest first.
+///
+/// The document store compresses whole documents together, so its size can't be split across
+/// fields and is reported on its own row, with a `NULL` field name.
+#[allow(clippy::type_complexity)]
+#[pg_extern]
+fn index_field_sizes(
+    index: PgRelation,
+) -> anyhow::Result<
+    TableIterator<
+        'static,
+        (
+            name!(field_name, Option<String>),
+            name!(total_bytes, i64),
+            name!(termdict_bytes, i64),
+            name!(postings_bytes, i64),
+            name!(positions_bytes, i64),
+            name!(fast_fields_bytes, i64),
+            name!(fieldnorms_bytes, i64),
+            name!(store_bytes, i64),
+        ),
+    >,
+> {
+    let index = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _);
+    let index_kind = IndexKind::for_index(index)?;
+
+    // termdict, postings, positions, fast fields, and fieldnorms bytes, per field
+    let mut field_sizes = HashMap::<String, [u64; 5]>::default();
+    let mut store_bytes = 0;
+    for index in index_kind.partitions() {
+        let reader = SearchIndexReader::open(
+            &index,
+            SearchQueryInput::All,
+            false,
+            MvccSatisfies::Snapshot,
+        )?;
+        let schema = reader.schema().tantivy_schema();
+        for segment in reader.searcher().space_usage()?.segments() {
+            let components = [
+                segment.termdict(),
+                segment.postings(),
+                segment.positions(),
+                segment.fast_fields(),
+                segment.fieldnorms(),
+            ];
+            for (i, component) in components.into_iter().enumerate() {
+                for (field, usage) in component.fields() {
+                    let field_name = schema.get_field_name(*field).to_owned();
+                    field_sizes.entry(field_name).or_default()[i] += usage.total().get_bytes();
+                }
+            }
+            store_bytes += segment.store().total().get_bytes();
+        }
+    }
+
+    let mut results = field_sizes
+        .into_iter()
+        .map(|(field_name, sizes)| {
+            (
+                Some(field_name),
+                sizes.iter().sum::<u64>() as i64,
+                sizes[0] as i64,
+                sizes[1] as i64,
+                sizes[2] as i64,
+                sizes[3] as i64,
+                sizes[4] as i64,
+                0,
+            )
+        })
+        .collect::<Vec<_>>();
+    results.push((None, store_bytes as i64, 0, 0, 0, 0, 0, store_bytes as i64));
+    results.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    Ok(TableIterator::new(results))
+}
+
 /// Returns the list of segments that contain the specified [`pg_sys::ItemPointerData]` heap tuple
 /// identifier.
 ///
@@ -488,6 +923,86 @@ fn force_merge_raw_bytes(
     anyhow::bail!("force_merge is deprecated, run `VACUUM` instead");
 }
 
+/// Merge the index down to `segment_count` segments.  If `min_deleted_percent` is given, only
+/// segments with more than that percentage of their documents deleted are merged, and even a
+/// lone such segment is rewritten to reclaim the space they take up.
+#[pg_extern(name = "force_merge")]
+fn force_merge_segments(
+    index: PgRelation,
+    segment_count: i32,
+    min_deleted_percent: default!(Option<f64>, "NULL"),
+) -> anyhow::Result<TableIterator<'static, (name!(new_segments, i64), name!(merged_segments, i64))>>
+{
+    if segment_count < 1 {
+        anyhow::bail!("segment_count must be at least 1");
+    }
+    if let Some(min_deleted_percent) = min_deleted_percent {
+        if !(0.0..100.0).contains(&min_deleted_percent) {
+            anyhow::bail!("min_deleted_percent must be at least 0 and less than 100");
+        }
+    }
+
+    let index = {
+        let oid = index.oid();
+        drop(index);
+        // reopen the index with a RowExclusiveLock b/c we are going to be changing its physical structure
+        PgSearchRelation::with_lock(oid, pg_sys::RowExclusiveLock as _)
+    };
+    let index_kind = IndexKind::for_index(index)?;
+
+    let (mut new_segments, mut merged_segments) = (0, 0);
+    for index in index_kind.partitions() {
+        let (new, merged) = unsafe {
+            crate::postgres::merge::force_merge(
+                &index,
+                segment_count as usize,
+                min_deleted_percent.map(|percent| percent / 100.0),
+            )
+        };
+        new_segments += new as i64;
+        merged_segments += merged as i64;
+    }
+    Ok(TableIterator::once((new_segments, merged_segments)))
+}
+
+/// Migrate `index`, or each of its partitions, from an older on-disk format to the current one in
+/// place, such as after a `pg_upgrade` that carried it over from an older version of pg_search.
+/// Returns the format version each was in before and after.
+#[allow(clippy::type_complexity)]
+#[pg_extern]
+fn upgrade_index(
+    index: PgRelation,
+) -> Result<
+    TableIterator<
+        'static,
+        (
+            name!(index_name, String),
+            name!(from_version, i32),
+            name!(to_version, i32),
+        ),
+    >,
+> {
+    let index = {
+        let oid = index.oid();
+        drop(index);
+        // keep writers, but not readers, out while the metadata is rewritten
+        PgSearchRelation::with_lock(oid, pg_sys::ShareLock as _)
+    };
+    let index_kind = IndexKind::for_index(index)?;
+
+    let mut results = Vec::new();
+    for index in index_kind.partitions() {
+        let mut metadata = MetaPage::open(&index);
+        let from_version = unsafe { metadata.upgrade()? };
+        results.push((
+            index.name().to_owned(),
+            from_version as i32,
+            INDEX_FORMAT_VERSION as i32,
+        ));
+    }
+    Ok(TableIterator::new(results))
+}
+
 #[pg_extern]
 fn merge_lock_garbage_collect(index: PgRelation) -> SetOfIterator<'static, i32> {
     unsafe {
@@ -562,3 +1077,25 @@ GRANT SELECT ON paradedb.index_layer_info TO PUBLIC;
     name = "index_layer_info",
     requires = [index_info, layer_sizes]
 );
+
+extension_sql!(
+    r#"create view paradedb.index_stats as
+select index_oid::regclass as index,
+       queries,
+       mean_time_ms,
+       p95_time_ms,
+       docs_scanned,
+       blocks_hit,
+       blocks_read,
+       hit_ratio,
+       index_blocks_hit,
+       index_blocks_read,
+       index_hit_ratio
+from paradedb.index_stats_data();
+
+GRANT SELECT ON paradedb.index_stats TO PUBLIC;
+REVOKE EXECUTE ON FUNCTION paradedb.index_stats_reset(regclass) FROM PUBLIC;
+"#,
+    name = "index_stats",
+    requires = [index_stats_data, index_stats_reset]
+);
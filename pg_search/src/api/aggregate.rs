@@ -15,11 +15,17 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use std::error::Error;
 
-use pgrx::{default, pg_extern, Json, JsonB, PgRelation};
+use pgrx::datum::TimestampWithTimeZone;
+use pgrx::{default, name, pg_extern, Json, JsonB, PgRelation, TableIterator};
 
-use crate::aggregate::execute_aggregate;
+use crate::aggregate::elastic::{self, AggregateFormat};
+use crate::aggregate::time_zone;
+use crate::aggregate::{execute_aggregate, execute_count};
+use crate::api::ensure_no_rls;
+use crate::postgres::datetime::unix_millis_to_timestamptz;
 use crate::postgres::rel::PgSearchRelation;
 use crate::query::SearchQueryInput;
 
@@ -31,14 +37,282 @@ pub fn aggregate(
     solve_mvcc: default!(bool, true),
     memory_limit: default!(i64, 500000000),
     bucket_limit: default!(i64, 65000),
+    format: default!(String, "'tantivy'"),
 ) -> Result<JsonB, Box<dyn Error>> {
     let relation = unsafe { PgSearchRelation::from_pg(index.as_ptr()) };
-    Ok(JsonB(execute_aggregate(
-        &relation,
+    if let Some(heaprel) = relation.heap_relation() {
+        ensure_no_rls(&heaprel)?;
+    }
+    let memory_limit = memory_limit.try_into()?;
+    let bucket_limit = bucket_limit.try_into()?;
+
+    match AggregateFormat::parse(&format)? {
+        AggregateFormat::Tantivy => Ok(JsonB(execute_aggregate(
+            &relation,
+            query,
+            agg.0,
+            solve_mvcc,
+            memory_limit,
+            bucket_limit,
+        )?)),
+        AggregateFormat::Elasticsearch => {
+            let (aggs, meta) = elastic::translate_request(agg.0)?;
+            let total_hits = execute_count(&relation, query.clone(), solve_mvcc)?;
+            let result = if aggs.as_object().is_some_and(|aggs| aggs.is_empty()) {
+                aggs
+            } else {
+                execute_aggregate(
+                    &relation,
+                    query,
+                    aggs,
+                    solve_mvcc,
+                    memory_limit,
+                    bucket_limit,
+                )?
+            };
+            Ok(JsonB(elastic::translate_response(
+                result, total_hits, &meta,
+            )))
+        }
+    }
+}
+
+/// Bucket the documents matching `query` by `interval` over the date fast field `field`, and
+/// compute `metric` for each bucket.
+///
+/// `metric` is one of `count`, `avg`, `sum`, `min`, or `max`.  Every metric but `count` is
+/// computed over the numeric fast field `metric_field`.
+///
+/// Buckets start at midnight UTC unless `time_zone` is given, which is either an offset from UTC
+/// like `+09:00` or the name of a time zone like `America/New_York`, whose buckets follow its
+/// daylight saving time.
+#[allow(clippy::type_complexity)]
+#[allow(clippy::too_many_arguments)]
+#[pg_extern]
+pub fn timeseries(
+    index: PgRelation,
+    query: SearchQueryInput,
+    field: String,
+    interval: String,
+    metric: default!(String, "'count'"),
+    metric_field: default!(Option<String>, "NULL"),
+    solve_mvcc: default!(bool, true),
+    time_zone: default!(Option<String>, "NULL"),
+) -> Result<
+    TableIterator<
+        'static,
+        (
+            name!(bucket, TimestampWithTimeZone),
+            name!(doc_count, i64),
+            name!(value, Option<f64>),
+        ),
+    >,
+    Box<dyn Error>,
+> {
+    match (metric.as_str(), &metric_field) {
+        ("count", _) | ("avg" | "sum" | "min" | "max", Some(_)) => {}
+        ("avg" | "sum" | "min" | "max", None) => {
+            return Err(format!("the `{metric}` metric requires a `metric_field`").into());
+        }
+        (other, _) => {
+            return Err(format!(
+                "unknown metric `{other}`, expected one of `count`, `avg`, `sum`, `min`, or `max`"
+            )
+            .into());
+        }
+    }
+    let metric_field = metric_field.filter(|_| metric != "count");
+
+    let relation = unsafe { PgSearchRelation::from_pg(index.as_ptr()) };
+    if let Some(heaprel) = relation.heap_relation() {
+        ensure_no_rls(&heaprel)?;
+    }
+    if let Some(time_zone) = time_zone.filter(|zone| time_zone::utc_offset(zone).is_none()) {
+        let rows = zoned_timeseries(
+            &relation,
+            query,
+            &field,
+            &interval,
+            &metric,
+            metric_field.as_deref(),
+            solve_mvcc,
+            &time_zone,
+        )?;
+        return Ok(TableIterator::new(rows));
+    }
+
+    let mut histogram = serde_json::json!({
+        "date_histogram": {"field": field, "fixed_interval": interval}
+    });
+    if let Some(time_zone) = time_zone {
+        histogram["date_histogram"]["time_zone"] = time_zone.into();
+    }
+    if let Some(metric_field) = metric_field {
+        histogram["aggs"] =
+            serde_json::json!({ "metric": { metric.as_str(): { "field": metric_field } } });
+    }
+    let buckets = timeseries_buckets(&relation, query, histogram, solve_mvcc)?;
+
+    let mut rows = Vec::with_capacity(buckets.len());
+    for bucket in buckets {
+        let key = bucket["key"]
+            .as_f64()
+            .and_then(unix_millis_to_timestamptz)
+            .ok_or("date_histogram bucket should have a timestamp key")?;
+        let doc_count = bucket["doc_count"].as_i64().unwrap_or(0);
+        let value = if metric == "count" {
+            Some(doc_count as f64)
+        } else {
+            bucket
+                .pointer("/metric/value")
+                .and_then(serde_json::Value::as_f64)
+        };
+        rows.push((key, doc_count, value));
+    }
+    Ok(TableIterator::new(rows))
+}
+
+/// The buckets of the date histogram `histogram` of the documents matching `query`.
+fn timeseries_buckets(
+    relation: &PgSearchRelation,
+    query: SearchQueryInput,
+    histogram: serde_json::Value,
+    solve_mvcc: bool,
+) -> Result<Vec<serde_json::Value>, Box<dyn Error>> {
+    const MEMORY_LIMIT: u64 = 500000000;
+    const BUCKET_LIMIT: u32 = 65000;
+
+    let result = execute_aggregate(
+        relation,
         query,
-        agg.0,
+        serde_json::json!({ "timeseries": histogram }),
         solve_mvcc,
-        memory_limit.try_into()?,
-        bucket_limit.try_into()?,
-    )?))
+        MEMORY_LIMIT,
+        BUCKET_LIMIT,
+    )?;
+    Ok(result
+        .pointer("/timeseries/buckets")
+        .and_then(serde_json::Value::as_array)
+        .cloned()
+        .unwrap_or_default())
+}
+
+/// A bucket of a time zone's local time, merged from the buckets of the periods it spans.
+#[derive(Default)]
+struct LocalBucket {
+    doc_count: i64,
+    count: u64,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+/// `paradedb.timeseries()` in the named time zone `time_zone`, whose offset from UTC can change.
+#[allow(clippy::too_many_arguments)]
+fn zoned_timeseries(
+    relation: &PgSearchRelation,
+    query: SearchQueryInput,
+    field: &str,
+    interval: &str,
+    metric: &str,
+    metric_field: Option<&str>,
+    solve_mvcc: bool,
+    time_zone: &str,
+) -> Result<Vec<(TimestampWithTimeZone, i64, Option<f64>)>, Box<dyn Error>> {
+    let interval_millis = time_zone::interval_millis(interval)?;
+    let bucket_key = |bucket: &serde_json::Value| {
+        bucket["key"]
+            .as_f64()
+            .map(|key| key as i64)
+            .ok_or("date_histogram bucket should have a timestamp key")
+    };
+
+    // the UTC buckets of the documents tell when the zone's transitions have to be looked up
+    let utc_buckets = timeseries_buckets(
+        relation,
+        query.clone(),
+        serde_json::json!({
+            "date_histogram": {"field": field, "fixed_interval": interval, "min_doc_count": 1}
+        }),
+        solve_mvcc,
+    )?;
+    let (Some(first), Some(last)) = (utc_buckets.first(), utc_buckets.last()) else {
+        return Ok(vec![]);
+    };
+    let periods = time_zone::periods(
+        time_zone,
+        bucket_key(first)?,
+        bucket_key(last)? + interval_millis,
+    )?;
+
+    let mut local_buckets = BTreeMap::<i64, LocalBucket>::new();
+    for period in periods {
+        let mut histogram = serde_json::json!({
+            "date_histogram": {
+                "field": field,
+                "fixed_interval": interval,
+                "min_doc_count": 1,
+                "hard_bounds": {"min": period.start, "max": period.end - 1}
+            }
+        });
+        if period.offset != 0 {
+            histogram["date_histogram"]["offset"] = time_zone::tantivy_offset(period.offset).into();
+        }
+        if let Some(metric_field) = metric_field {
+            histogram["aggs"] =
+                serde_json::json!({ "metric": { "stats": { "field": metric_field } } });
+        }
+
+        for bucket in timeseries_buckets(relation, query.clone(), histogram, solve_mvcc)? {
+            let local =
+                time_zone::local_bucket(bucket_key(&bucket)?, period.offset, interval_millis);
+            let local_bucket = local_buckets.entry(local).or_default();
+            local_bucket.doc_count += bucket["doc_count"].as_i64().unwrap_or(0);
+            let stats = &bucket["metric"];
+            local_bucket.count += stats["count"].as_u64().unwrap_or(0);
+            local_bucket.sum += stats["sum"].as_f64().unwrap_or(0.0);
+            if let Some(min) = stats["min"].as_f64() {
+                local_bucket.min = Some(local_bucket.min.map_or(min, |m| m.min(min)));
+            }
+            if let Some(max) = stats["max"].as_f64() {
+                local_bucket.max = Some(local_bucket.max.map_or(max, |m| m.max(max)));
+            }
+        }
+    }
+
+    // like UTC buckets, the empty ones between the first and the last are returned too
+    if let (Some(&first), Some(&last)) = (
+        local_buckets.keys().next(),
+        local_buckets.keys().next_back(),
+    ) {
+        for local in (first..last).step_by(interval_millis as usize) {
+            local_buckets.entry(local).or_default();
+        }
+    }
+
+    let locals = local_buckets.keys().copied().collect::<Vec<_>>();
+    let instants = time_zone::to_instants(time_zone, &locals)?;
+    let mut rows: Vec<(TimestampWithTimeZone, i64, Option<f64>)> =
+        Vec::with_capacity(instants.len());
+    for (instant, bucket) in instants.into_iter().zip(local_buckets.into_values()) {
+        // a local time that the clocks skipped over is the instant of the one after it, and has
+        // no documents of its own
+        if bucket.doc_count == 0
+            && rows
+                .last()
+                .is_some_and(|(previous, ..)| *previous == instant)
+        {
+            continue;
+        }
+        let value = match metric {
+            "count" => Some(bucket.doc_count as f64),
+            "sum" => (bucket.count > 0).then_some(bucket.sum),
+            "avg" => (bucket.count > 0).then(|| bucket.sum / bucket.count as f64),
+            "min" => bucket.min,
+            "max" => bucket.max,
+            _ => unreachable!("metric should have been validated"),
+        };
+        rows.push((instant, bucket.doc_count, value));
+    }
+    Ok(rows)
 }
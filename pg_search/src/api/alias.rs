@@ -0,0 +1,128 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Named aliases for BM25 indexes.
+//!
+//! An alias is a stable name that applications use in place of an index's own name, so that a
+//! rebuilt index can be swapped in without changing any queries: build the new index, perhaps
+//! with `CREATE INDEX CONCURRENTLY`, repoint the alias at it with `paradedb.swap_alias()`, and
+//! drop the old one.  Aliases live in the `paradedb.index_aliases` table, so a swap is as atomic
+//! as the transaction that makes it.
+//!
+//! While a table has more than one BM25 index, which is only the case mid-rebuild, searches of
+//! that table use the one an alias points at.
+
+use crate::postgres::build::is_bm25_index;
+use crate::postgres::rel::PgSearchRelation;
+use anyhow::{anyhow, ensure, Result};
+use pgrx::prelude::*;
+use pgrx::PgRelation;
+
+extension_sql!(
+    r#"
+CREATE TABLE paradedb.index_aliases (
+    alias TEXT PRIMARY KEY,
+    indexrelid regclass NOT NULL,
+    -- when the alias was created or last swapped
+    updated_at timestamptz NOT NULL DEFAULT now()
+);
+SELECT pg_catalog.pg_extension_config_dump('paradedb.index_aliases', '');
+"#,
+    name = "index_aliases"
+);
+
+fn ensure_bm25_index(index: &PgRelation) -> Result<()> {
+    let relation = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _);
+    ensure!(
+        is_bm25_index(&relation),
+        "`{}` is not a BM25 index",
+        relation.name()
+    );
+    Ok(())
+}
+
+/// Create a new alias named `alias` for `index`.
+#[pg_extern]
+fn create_alias(alias: &str, index: PgRelation) -> Result<()> {
+    ensure_bm25_index(&index)?;
+    let created = Spi::get_one_with_args::<bool>(
+        "INSERT INTO paradedb.index_aliases (alias, indexrelid) VALUES ($1, $2)
+         ON CONFLICT (alias) DO NOTHING
+         RETURNING true",
+        &[alias.into(), index.oid().into()],
+    )?;
+    ensure!(created.is_some(), "alias `{alias}` already exists");
+    Ok(())
+}
+
+/// Repoint the existing alias `alias` at `index`, returning the name of the index it pointed at
+/// before.  Concurrent swaps of the same alias wait for each other.
+#[pg_extern]
+fn swap_alias(alias: &str, index: PgRelation) -> Result<String> {
+    ensure_bm25_index(&index)?;
+    repoint_alias(alias, index.oid())
+}
+
+/// Point `alias` at the BM25 index `indexrelid`, returning the name of the index it pointed at
+/// before.
+pub fn repoint_alias(alias: &str, indexrelid: pg_sys::Oid) -> Result<String> {
+    let previous = Spi::get_one_with_args::<String>(
+        "SELECT indexrelid::text FROM paradedb.index_aliases WHERE alias = $1 FOR UPDATE",
+        &[alias.into()],
+    )?
+    .ok_or_else(|| anyhow!("alias `{alias}` does not exist"))?;
+    Spi::run_with_args(
+        "UPDATE paradedb.index_aliases SET indexrelid = $2, updated_at = now() WHERE alias = $1",
+        &[alias.into(), indexrelid.into()],
+    )?;
+    Ok(previous)
+}
+
+/// Remove the alias `alias`.  The index it points at is left alone.
+#[pg_extern]
+fn drop_alias(alias: &str) -> Result<()> {
+    let dropped = Spi::get_one_with_args::<bool>(
+        "DELETE FROM paradedb.index_aliases WHERE alias = $1 RETURNING true",
+        &[alias.into()],
+    )?;
+    ensure!(dropped.is_some(), "alias `{alias}` does not exist");
+    Ok(())
+}
+
+/// The OID of the index that `alias` points at, for passing to the functions that take an
+/// index, such as `paradedb.aggregate()`.
+#[pg_extern(stable, parallel_safe)]
+fn resolve_alias(alias: &str) -> Result<pg_sys::Oid> {
+    let indexrelid = Spi::get_one_with_args::<pg_sys::Oid>(
+        "SELECT a.indexrelid::oid FROM paradedb.index_aliases a
+         JOIN pg_catalog.pg_class c ON c.oid = a.indexrelid
+         WHERE a.alias = $1",
+        &[alias.into()],
+    )?;
+    indexrelid.ok_or_else(|| anyhow!("alias `{alias}` does not exist or its index was dropped"))
+}
+
+/// Of the `candidates`, which are all BM25 indexes on the same table, the one with the highest
+/// OID that an alias points at, if any.
+pub fn aliased_index(candidates: &[pg_sys::Oid]) -> Option<pg_sys::Oid> {
+    Spi::get_one_with_args::<pg_sys::Oid>(
+        "SELECT max(indexrelid::oid) FROM paradedb.index_aliases WHERE indexrelid::oid = ANY($1)",
+        &[candidates.to_vec().into()],
+    )
+    .ok()
+    .flatten()
+}
@@ -20,7 +20,8 @@ use pgrx::*;
 use crate::api::{FieldName, HashMap};
 use crate::postgres::types::{TantivyValue, TantivyValueError};
 use crate::query::pdb_query::pdb;
-use crate::query::{SearchQueryInput, TermInput};
+use crate::query::tsquery::{self, TsQuery, TsVectorField};
+use crate::query::{QuerySyntax, SearchQueryInput, TermInput};
 use pgrx::nullable::IntoNullableIterator;
 use std::ops::Bound;
 
@@ -82,6 +83,14 @@ pub fn disjunction_max(
     }
 }
 
+#[pg_extern(immutable, parallel_safe)]
+pub fn element(field: FieldName, query: SearchQueryInput) -> SearchQueryInput {
+    SearchQueryInput::Element {
+        field,
+        query: Box::new(query),
+    }
+}
+
 #[pg_extern(immutable, parallel_safe)]
 pub fn empty() -> SearchQueryInput {
     SearchQueryInput::Empty
@@ -163,14 +172,68 @@ pub fn parse(
     query_string: String,
     lenient: default!(Option<bool>, "NULL"),
     conjunction_mode: default!(Option<bool>, "NULL"),
+    syntax: default!(Option<String>, "NULL"),
 ) -> SearchQueryInput {
+    let syntax = syntax.map(|syntax| match syntax.to_lowercase().as_str() {
+        "tantivy" => QuerySyntax::Tantivy,
+        "lucene" => QuerySyntax::Lucene,
+        _ => panic!("parse: syntax must be 'tantivy' or 'lucene', not '{syntax}'"),
+    });
     SearchQueryInput::Parse {
         query_string,
         lenient,
         conjunction_mode,
+        syntax,
     }
 }
 
+/// Search `field` for the rows whose `tsvector` of it would match the `tsquery`.
+#[pg_extern(name = "tsquery", immutable, parallel_safe)]
+pub fn tsquery_field(field: FieldName, query: TsQuery) -> anyhow::Result<SearchQueryInput> {
+    let fields = [TsVectorField {
+        field,
+        weight: None,
+    }];
+    Ok(tsquery::to_search_query_input(&query.0, &fields)?)
+}
+
+/// Search the `fields` for the rows whose `tsvector` made from them would match the `tsquery`.
+/// The letters of `weights`, if it's given, are the weights that the lexemes of each of the
+/// fields have in the `tsvector`, as `setweight()` gives them.
+#[pg_extern(name = "tsquery", immutable, parallel_safe)]
+pub fn tsquery_fields(
+    fields: Vec<String>,
+    query: TsQuery,
+    weights: Option<String>,
+) -> anyhow::Result<SearchQueryInput> {
+    let weights = match weights {
+        None => vec![None; fields.len()],
+        Some(weights) => {
+            let weights = weights
+                .chars()
+                .map(|weight| match weight.to_ascii_uppercase() {
+                    weight @ 'A'..='D' => Ok(Some(weight)),
+                    _ => Err(anyhow::anyhow!("`{weight}` is not a weight")),
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            anyhow::ensure!(
+                weights.len() == fields.len(),
+                "there must be a weight for each of the fields"
+            );
+            weights
+        }
+    };
+    let fields = fields
+        .into_iter()
+        .zip(weights)
+        .map(|(field, weight)| TsVectorField {
+            field: field.into(),
+            weight,
+        })
+        .collect::<Vec<_>>();
+    Ok(tsquery::to_search_query_input(&query.0, &fields)?)
+}
+
 #[rustfmt::skip]
 #[pg_extern(immutable, parallel_safe)]
 pub unsafe fn term_with_operator(
@@ -214,6 +277,7 @@ pub unsafe fn term_with_operator(
         pg_sys::TEXTOID => make_query!(operator, field, term_str, String, value, false),
         pg_sys::VARCHAROID => make_query!(operator, field, term_str, String, value, false),
         pg_sys::UUIDOID => make_query!(operator, field, uuid, pgrx::datum::Uuid, value, false),
+        pg_sys::BYTEAOID => make_query!(operator, field, term_bytes, Vec<u8>, value, false),
 
         pg_sys::DATEOID => make_query!(operator, field, date, pgrx::datum::Date, value, true),
         pg_sys::TIMEOID => make_query!(operator, field, time, pgrx::datum::Time, value, true),
@@ -333,6 +397,71 @@ pub unsafe fn terms_with_operator(
     }
 }
 
+/// An internal function, not intended to be used directly by end users
+/// but instead used by our pushdown code.
+///
+/// Our pushdown code will rewrite the former into the latter.
+///
+/// ```sql
+/// SELECT * FROM mock_items WHERE weight_range @> 5;
+/// ```
+///
+/// is equivalent to:
+///
+/// ```sql
+/// SELECT * FROM mock_items WHERE id @@@ paradedb.range_term_with_operator('weight_range', '@>', 5);
+/// ```
+///
+#[rustfmt::skip]
+#[pg_extern(immutable, parallel_safe)]
+pub unsafe fn range_term_with_operator(
+    field: FieldName,
+    operator: String,
+    value: AnyElement,
+) -> anyhow::Result<SearchQueryInput> {
+    macro_rules! make_query {
+        ($func_name:ident, $value_type:ty, $anyelement:expr) => {
+            Ok(SearchQueryInput::FieldedQuery { field, query: $func_name(<$value_type>::from_datum($anyelement.datum(), false).unwrap()) })
+        };
+        ($func_name:ident, $value_type:ty, $anyelement:expr, $relation:expr) => {
+            Ok(SearchQueryInput::FieldedQuery { field, query: $func_name(<$value_type>::from_datum($anyelement.datum(), false).unwrap(), $relation) })
+        };
+    }
+
+    use crate::api::builder_fns::*;
+    let relation = match operator.as_str() {
+        "&&" => RangeRelation::Intersects,
+        // the indexed range contains the value
+        "@>" => RangeRelation::Within,
+        // the indexed range is contained by the value
+        "<@" => RangeRelation::Contains,
+        other => panic!("unsupported range operator: {other}"),
+    };
+
+    match value.oid() {
+        pg_sys::INT4RANGEOID => make_query!(range_term_range_int4range, Range<i32>, value, relation),
+        pg_sys::INT8RANGEOID => make_query!(range_term_range_int8range, Range<i64>, value, relation),
+        pg_sys::NUMRANGEOID => make_query!(range_term_range_numrange, Range<AnyNumeric>, value, relation),
+        pg_sys::DATERANGEOID => make_query!(range_term_range_daterange, Range<pgrx::datum::Date>, value, relation),
+        pg_sys::TSRANGEOID => make_query!(range_term_range_tsrange, Range<pgrx::datum::Timestamp>, value, relation),
+        pg_sys::TSTZRANGEOID => make_query!(range_term_range_tstzrange, Range<pgrx::datum::TimestampWithTimeZone>, value, relation),
+
+        // a single element can only be contained by the indexed range
+        _ if !matches!(relation, RangeRelation::Within) => panic!("unsupported range operator: {operator}"),
+        pg_sys::INT2OID => make_query!(range_term_i16, i16, value),
+        pg_sys::INT4OID => make_query!(range_term_i32, i32, value),
+        pg_sys::INT8OID => make_query!(range_term_i64, i64, value),
+        pg_sys::FLOAT4OID => make_query!(range_term_f32, f32, value),
+        pg_sys::FLOAT8OID => make_query!(range_term_f64, f64, value),
+        pg_sys::NUMERICOID => make_query!(range_term_numeric, AnyNumeric, value),
+        pg_sys::DATEOID => make_query!(range_term_date, pgrx::datum::Date, value),
+        pg_sys::TIMESTAMPOID => make_query!(range_term_timestamp, pgrx::datum::Timestamp, value),
+        pg_sys::TIMESTAMPTZOID => make_query!(range_term_timestamp_with_time_zone, pgrx::datum::TimestampWithTimeZone, value),
+
+        other => panic!("unsupported type: {other:?}"),
+    }
+}
+
 macro_rules! term_fn_unsupported {
     ($func_name:ident, $value_type:ty, $term_type:literal) => {
         #[pg_extern(name = "term", immutable, parallel_safe)]
@@ -395,15 +524,15 @@ pub fn term_set(terms: Vec<SearchQueryInput>) -> SearchQueryInput {
 
 #[pg_cast(implicit)]
 fn jsonb_to_searchqueryinput(query: JsonB) -> SearchQueryInput {
-    serde_path_to_error::deserialize(query.0).unwrap_or_else(|err| {
+    crate::query::json::parse(query.0).unwrap_or_else(|err| {
         panic!(
             r#"error parsing search query input json at "{}": {}"#,
-            err.path(),
-            match err.inner().to_string() {
+            err.path,
+            match err.message {
                 msg if msg.contains("expected unit") => {
                     format!(
                         r#"invalid type: map, pass null as value for "{}""#,
-                        err.path()
+                        err.path
                     )
                 }
                 msg => msg,
@@ -26,6 +26,7 @@ mod pdb {
     use pgrx::datum::RangeBound;
     use pgrx::{default, pg_extern, AnyElement, AnyNumeric, Range};
     use std::collections::Bound;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
     use tantivy::schema::{OwnedValue, Value};
 
     #[builder_fn]
@@ -38,6 +39,7 @@ mod pdb {
             distance: None,
             transposition_cost_one: None,
             prefix: None,
+            stopwords: None,
         }
     }
 
@@ -51,6 +53,7 @@ mod pdb {
             distance: None,
             transposition_cost_one: None,
             prefix: None,
+            stopwords: None,
         }
     }
 
@@ -91,6 +94,7 @@ mod pdb {
         transposition_cost_one: default!(Option<bool>, "NULL"),
         prefix: default!(Option<bool>, "NULL"),
         conjunction_mode: default!(Option<bool>, "NULL"),
+        stopwords: default!(Option<bool>, "NULL"),
     ) -> pdb::Query {
         pdb::Query::Match {
             value,
@@ -99,6 +103,7 @@ mod pdb {
             transposition_cost_one,
             prefix,
             conjunction_mode,
+            stopwords,
         }
     }
 
@@ -510,6 +515,46 @@ mod pdb {
         true
     );
 
+    #[builder_fn]
+    #[pg_extern(immutable, parallel_safe, name = "ip_range")]
+    pub fn ip_range(cidr: pgrx::Inet) -> pdb::Query {
+        let (first, last) = cidr_bounds(&cidr).unwrap_or_else(|e| panic!("{e}"));
+        pdb::Query::Range {
+            lower_bound: Bound::Included(OwnedValue::IpAddr(first)),
+            upper_bound: Bound::Included(OwnedValue::IpAddr(last)),
+            is_datetime: false,
+        }
+    }
+
+    /// Returns the first and last address of the network block described by `cidr`, mapped
+    /// to IPv6 the same way indexed inet values are.
+    fn cidr_bounds(cidr: &str) -> anyhow::Result<(Ipv6Addr, Ipv6Addr)> {
+        let (addr, prefix) = match cidr.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>()?, Some(prefix.parse::<u32>()?)),
+            None => (cidr.parse::<IpAddr>()?, None),
+        };
+
+        match addr {
+            IpAddr::V4(addr) => {
+                let prefix = prefix.unwrap_or(32);
+                anyhow::ensure!(prefix <= 32, "invalid IPv4 prefix length: {prefix}");
+                let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+                let first = u32::from(addr) & mask;
+                Ok((
+                    Ipv4Addr::from(first).to_ipv6_mapped(),
+                    Ipv4Addr::from(first | !mask).to_ipv6_mapped(),
+                ))
+            }
+            IpAddr::V6(addr) => {
+                let prefix = prefix.unwrap_or(128);
+                anyhow::ensure!(prefix <= 128, "invalid IPv6 prefix length: {prefix}");
+                let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+                let first = u128::from(addr) & mask;
+                Ok((Ipv6Addr::from(first), Ipv6Addr::from(first | !mask)))
+            }
+        }
+    }
+
     pub use paradedb::RangeRelation;
     mod paradedb {
         use pgrx::PostgresEnum;
@@ -0,0 +1,196 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.bulk_index()`, which loads a file of newline-delimited JSON documents, such as one
+//! exported from a search cluster, into the table of a BM25 index.
+//!
+//! The documents are inserted in batches, but the rows of all of them are written by one index
+//! writer with `maintenance_work_mem`, like that of `paradedb.bulk_load_mode`, which is committed
+//! with the transaction.  A load writes a few large segments rather than some for every batch.
+
+use crate::api::ensure_role;
+use crate::postgres::build::is_bm25_index;
+use crate::postgres::insert::with_bulk_indexing;
+use crate::postgres::rel::PgSearchRelation;
+use anyhow::{anyhow, ensure, Context, Result};
+use pgrx::prelude::*;
+use pgrx::spi::quote_identifier;
+use pgrx::{JsonB, PgRelation};
+use serde_json::{Map, Value};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// Insert the documents of the newline-delimited JSON file `path` on the database server into the
+/// table of `index`, `batch_size` of them at a time.  Returns the number of rows inserted.
+///
+/// Each line is an object whose keys are the columns of the table, or an Elasticsearch hit whose
+/// `_source` is, and whose `_id` is the key field's value if the `_source` doesn't have one.
+#[pg_extern]
+fn bulk_index(index: PgRelation, path: &str, batch_size: default!(i32, 10000)) -> Result<i64> {
+    ensure_role("pg_read_server_files")?;
+    ensure!(batch_size > 0, "`batch_size` must be greater than 0");
+    let index = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _);
+    ensure!(
+        is_bm25_index(&index)
+            && unsafe { pg_sys::get_rel_relkind(index.oid()) as u8 == pg_sys::RELKIND_INDEX },
+        "`{}` is not a BM25 index",
+        index.name()
+    );
+    let heaprel = index
+        .heap_relation()
+        .ok_or_else(|| anyhow!("`{}` does not belong to a table", index.name()))?;
+    let table = format!(
+        "{}.{}",
+        quote_identifier(heaprel.namespace()),
+        quote_identifier(heaprel.name())
+    );
+    let columns = insertable_columns(heaprel.oid())?;
+    let key_field = index.options().key_field_name();
+
+    let file = File::open(path).with_context(|| format!("could not open `{path}`"))?;
+    with_bulk_indexing(|| {
+        let batch_size = batch_size as usize;
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut inserted = 0;
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.with_context(|| format!("could not read `{path}`"))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let document = document(&line, key_field.root())
+                .map_err(|err| anyhow!("line {} of `{path}`: {err}", number + 1))?;
+            ensure!(
+                columns.iter().any(|column| document.contains_key(column)),
+                "line {} of `{path}` has none of the columns of `{table}`",
+                number + 1
+            );
+            batch.push(document);
+
+            if batch.len() == batch_size {
+                inserted += insert_batch(&table, &columns, std::mem::take(&mut batch))?;
+            }
+        }
+        if !batch.is_empty() {
+            inserted += insert_batch(&table, &columns, batch)?;
+        }
+        Ok(inserted)
+    })
+}
+
+/// The document of a line, which is the `_source` of an Elasticsearch hit.
+fn document(line: &str, key_field: &str) -> Result<Map<String, Value>> {
+    let Value::Object(mut object) = serde_json::from_str::<Value>(line)? else {
+        return Err(anyhow!("not a JSON object"));
+    };
+    match object.remove("_source") {
+        None => Ok(object),
+        Some(Value::Object(mut source)) => {
+            if let Some(id) = object.remove("_id") {
+                source.entry(key_field).or_insert(id);
+            }
+            Ok(source)
+        }
+        Some(_) => Err(anyhow!("`_source` is not an object")),
+    }
+}
+
+/// The columns of the table `relid` that rows can be inserted with values for.
+fn insertable_columns(relid: pg_sys::Oid) -> Result<Vec<String>> {
+    Spi::connect(|client| {
+        client
+            .select(
+                "SELECT attname::text FROM pg_attribute
+                 WHERE attrelid = $1 AND attnum > 0 AND NOT attisdropped AND attgenerated = ''
+                 ORDER BY attnum",
+                None,
+                &[relid.into()],
+            )?
+            .map(|row| row.get::<String>(1))
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .map(|columns| columns.into_iter().flatten().collect())
+    .map_err(Into::into)
+}
+
+/// Insert the `documents` into the `table` with an `INSERT` for each set of the `columns` they
+/// have, so that the columns a document doesn't have get their defaults.
+fn insert_batch(
+    table: &str,
+    columns: &[String],
+    documents: Vec<Map<String, Value>>,
+) -> Result<i64> {
+    let mut groups: Vec<(Vec<&str>, Vec<Value>)> = vec![];
+    for document in documents {
+        let present = columns
+            .iter()
+            .map(String::as_str)
+            .filter(|column| document.contains_key(*column))
+            .collect::<Vec<_>>();
+        match groups.iter_mut().find(|(columns, _)| *columns == present) {
+            Some((_, group)) => group.push(Value::Object(document)),
+            None => groups.push((present, vec![Value::Object(document)])),
+        }
+    }
+
+    let mut inserted = 0;
+    for (columns, group) in groups {
+        let columns = columns
+            .into_iter()
+            .map(quote_identifier)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "INSERT INTO {table} ({columns}) SELECT {columns} FROM jsonb_populate_recordset(NULL::{table}, $1)"
+        );
+        inserted += Spi::connect_mut(|client| -> Result<i64> {
+            let inserted = client.update(&sql, None, &[JsonB(Value::Array(group)).into()])?;
+            Ok(inserted.len() as i64)
+        })?;
+    }
+    Ok(inserted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    fn test_document() {
+        assert_eq!(
+            Value::Object(document(r#"{"id": 1, "description": "shoes"}"#, "id").unwrap()),
+            json!({"id": 1, "description": "shoes"})
+        );
+        assert_eq!(
+            Value::Object(
+                document(
+                    r#"{"_index": "products", "_id": "7", "_source": {"description": "shoes"}}"#,
+                    "id"
+                )
+                .unwrap()
+            ),
+            json!({"id": "7", "description": "shoes"})
+        );
+        assert_eq!(
+            Value::Object(document(r#"{"_id": "7", "_source": {"id": 8}}"#, "id").unwrap()),
+            json!({"id": 8})
+        );
+        assert!(document("[1, 2]", "id").is_err());
+        assert!(document(r#"{"_source": "shoes"}"#, "id").is_err());
+    }
+}
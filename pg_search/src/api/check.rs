@@ -0,0 +1,314 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.validate_index()`, an `amcheck`-style integrity check of a BM25 index.
+//!
+//! Each check reports the problems it finds as rows rather than raising an error, so that a
+//! single run describes everything that's wrong with an index.
+
+use crate::api::{HashMap, HashSet};
+use crate::index::fast_fields_helper::FFType;
+use crate::index::mvcc::MvccSatisfies;
+use crate::index::reader::index::SearchIndexReader;
+use crate::postgres::index::IndexKind;
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::storage::block::MVCCEntry;
+use crate::postgres::storage::metadata::MetaPage;
+use crate::postgres::utils::{item_pointer_to_u64, u64_to_item_pointer};
+use crate::query::SearchQueryInput;
+use pgrx::prelude::*;
+use pgrx::{check_for_interrupts, PgRelation};
+use std::ptr::addr_of_mut;
+use tantivy::index::SegmentId;
+
+/// The most problems each check reports, beyond which they're only counted.
+const MAX_REPORTED: usize = 100;
+
+type Problem = (String, String, Option<String>, String);
+
+struct Problems<'a> {
+    index_name: &'a str,
+    check: &'static str,
+    problems: &'a mut Vec<Problem>,
+    nproblems: usize,
+}
+
+impl<'a> Problems<'a> {
+    fn new(index_name: &'a str, check: &'static str, problems: &'a mut Vec<Problem>) -> Self {
+        Self {
+            index_name,
+            check,
+            problems,
+            nproblems: 0,
+        }
+    }
+
+    fn report(&mut self, segment_id: Option<SegmentId>, detail: impl Into<String>) {
+        self.nproblems += 1;
+        if self.nproblems <= MAX_REPORTED {
+            self.problems.push((
+                self.index_name.to_owned(),
+                self.check.to_owned(),
+                segment_id.map(|segment_id| segment_id.short_uuid_string()),
+                detail.into(),
+            ));
+        }
+    }
+}
+
+impl Drop for Problems<'_> {
+    fn drop(&mut self) {
+        if self.nproblems > MAX_REPORTED {
+            self.problems.push((
+                self.index_name.to_owned(),
+                self.check.to_owned(),
+                None,
+                format!("{} more problems", self.nproblems - MAX_REPORTED),
+            ));
+        }
+    }
+}
+
+/// Verify the structure and contents of a BM25 index, returning one row per problem found.  An
+/// index that passes every check returns no rows.
+///
+/// The checks are:
+///   - `segment_meta`: each visible segment's metadata is self-consistent and points at blocks
+///     that exist in the index
+///   - `checksum`: every segment file's contents match its checksum
+///   - `ctid`: no heap tuple is indexed by more than one live document
+///   - `heap`: every heap tuple visible to the current snapshot is indexed
+#[allow(clippy::type_complexity)]
+#[pg_extern]
+fn validate_index(
+    index: PgRelation,
+) -> anyhow::Result<
+    TableIterator<
+        'static,
+        (
+            name!(index_name, String),
+            name!(check, String),
+            name!(segno, Option<String>),
+            name!(detail, String),
+        ),
+    >,
+> {
+    let index = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _);
+    let index_kind = IndexKind::for_index(index)?;
+
+    let mut problems = Vec::new();
+    for index in index_kind.partitions() {
+        let index_name = index.name().to_owned();
+        check_segment_metas(
+            &index,
+            &mut Problems::new(&index_name, "segment_meta", &mut problems),
+        );
+
+        let reader = match SearchIndexReader::open(
+            &index,
+            SearchQueryInput::All,
+            false,
+            MvccSatisfies::Snapshot,
+        ) {
+            Ok(reader) => reader,
+            Err(e) => {
+                // nothing past here can be checked without a reader
+                Problems::new(&index_name, "open", &mut problems)
+                    .report(None, format!("failed to open the index: {e}"));
+                continue;
+            }
+        };
+
+        let mut checksums = Problems::new(&index_name, "checksum", &mut problems);
+        match reader.validate_checksum() {
+            Ok(failed) => {
+                for path in failed {
+                    let segment_id = path
+                        .file_name()
+                        .and_then(|name| name.to_str()?.split('.').next())
+                        .and_then(|uuid| SegmentId::from_uuid_string(uuid).ok());
+                    checksums.report(
+                        segment_id,
+                        format!("checksum mismatch in {}", path.display()),
+                    );
+                }
+            }
+            Err(e) => checksums.report(None, format!("failed to validate checksums: {e}")),
+        }
+        drop(checksums);
+
+        let indexed = check_ctids(
+            &reader,
+            &mut Problems::new(&index_name, "ctid", &mut problems),
+        );
+        if let Some(heaprel) = index.heap_relation() {
+            unsafe {
+                check_heap(
+                    &heaprel,
+                    &index,
+                    &indexed,
+                    &mut Problems::new(&index_name, "heap", &mut problems),
+                );
+            }
+        }
+    }
+
+    Ok(TableIterator::new(problems))
+}
+
+fn check_segment_metas(index: &PgSearchRelation, problems: &mut Problems) {
+    let nblocks = unsafe {
+        pg_sys::RelationGetNumberOfBlocksInFork(index.as_ptr(), pg_sys::ForkNumber::MAIN_FORKNUM)
+    };
+    let segment_metas = MetaPage::open(index).segment_metas();
+    let entries = unsafe { segment_metas.list() };
+
+    let mut seen = HashSet::default();
+    for entry in entries {
+        if unsafe { !entry.visible() } || entry.is_orphaned_delete() {
+            continue;
+        }
+        let segment_id = Some(entry.segment_id);
+
+        if !seen.insert(entry.segment_id) {
+            problems.report(segment_id, "segment is listed more than once");
+        }
+        if entry.num_deleted_docs() > entry.max_doc as usize {
+            problems.report(
+                segment_id,
+                format!(
+                    "{} deleted documents, but only {} documents",
+                    entry.num_deleted_docs(),
+                    entry.max_doc
+                ),
+            );
+        }
+        for (file_entry, component) in entry.file_entries() {
+            if file_entry.starting_block >= nblocks {
+                problems.report(
+                    segment_id,
+                    format!(
+                        "{component} starts at block {}, past the end of the index's {nblocks} blocks",
+                        file_entry.starting_block
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// Collect the ctid of every live document, reporting any that are indexed more than once.
+fn check_ctids(reader: &SearchIndexReader, problems: &mut Problems) -> HashMap<u64, SegmentId> {
+    let mut indexed = HashMap::default();
+    for segment_reader in reader.segment_readers() {
+        let segment_id = segment_reader.segment_id();
+        let ctid_ff = FFType::new_ctid(segment_reader.fast_fields());
+        for doc_id in 0..segment_reader.max_doc() {
+            if doc_id % 10_000 == 0 {
+                check_for_interrupts!();
+            }
+            if segment_reader.is_deleted(doc_id) {
+                continue;
+            }
+            let Some(ctid) = ctid_ff.as_u64(doc_id) else {
+                problems.report(Some(segment_id), format!("document {doc_id} has no ctid"));
+                continue;
+            };
+            if let Some(other) = indexed.insert(ctid, segment_id) {
+                let mut tid = pg_sys::ItemPointerData::default();
+                u64_to_item_pointer(ctid, &mut tid);
+                problems.report(
+                    Some(segment_id),
+                    format!(
+                        "ctid {:?} is also indexed by segment {}",
+                        pgrx::itemptr::item_pointer_get_both(tid),
+                        other.short_uuid_string()
+                    ),
+                );
+            }
+        }
+    }
+    indexed
+}
+
+struct HeapCheckState<'a, 'b> {
+    indexed: &'a HashMap<u64, SegmentId>,
+    problems: &'a mut Problems<'b>,
+}
+
+/// Report every heap tuple visible to the current snapshot that isn't indexed.
+///
+/// Like `amcheck`'s `heapallindexed` option, this uses a concurrent index build's heap scan, which
+/// visits only the tuples visible to the snapshot and identifies HOT-updated ones by the root of
+/// their chain, just as they were indexed.
+unsafe fn check_heap(
+    heaprel: &PgSearchRelation,
+    indexrel: &PgSearchRelation,
+    indexed: &HashMap<u64, SegmentId>,
+    problems: &mut Problems,
+) {
+    let snapshot = pg_sys::RegisterSnapshot(pg_sys::GetTransactionSnapshot());
+    // what `table_beginscan_strat(heaprel, snapshot, 0, NULL, true, false)` does
+    let scan = (*(*heaprel.as_ptr()).rd_tableam).scan_begin.unwrap()(
+        heaprel.as_ptr(),
+        snapshot,
+        0,
+        std::ptr::null_mut(),
+        std::ptr::null_mut(),
+        pg_sys::ScanOptions::SO_TYPE_SEQSCAN
+            | pg_sys::ScanOptions::SO_ALLOW_PAGEMODE
+            | pg_sys::ScanOptions::SO_ALLOW_STRAT,
+    );
+    let index_info = pg_sys::BuildIndexInfo(indexrel.as_ptr());
+    (*index_info).ii_Concurrent = true;
+
+    let mut state = HeapCheckState { indexed, problems };
+    pg_sys::table_index_build_scan(
+        heaprel.as_ptr(),
+        indexrel.as_ptr(),
+        index_info,
+        true,
+        false,
+        Some(heap_check_callback),
+        addr_of_mut!(state).cast(),
+        scan,
+    );
+    pg_sys::UnregisterSnapshot(snapshot);
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn heap_check_callback(
+    _indexrel: pg_sys::Relation,
+    ctid: pg_sys::ItemPointer,
+    _values: *mut pg_sys::Datum,
+    _isnull: *mut bool,
+    _tuple_is_alive: bool,
+    state: *mut std::os::raw::c_void,
+) {
+    check_for_interrupts!();
+
+    let state = &mut *state.cast::<HeapCheckState>();
+    if !state.indexed.contains_key(&item_pointer_to_u64(*ctid)) {
+        state.problems.report(
+            None,
+            format!(
+                "heap tuple {:?} is not indexed",
+                pgrx::itemptr::item_pointer_get_both(*ctid)
+            ),
+        );
+    }
+}
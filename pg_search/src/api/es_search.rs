@@ -0,0 +1,1002 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.es_search()`, which answers an Elasticsearch `_search` request body with a response
+//! shaped like Elasticsearch's, so that applications can be moved over without rewriting their
+//! searches first.
+//!
+//! The query DSL is translated into a [`SearchQueryInput`], and the hits are found with an
+//! ordinary `SELECT` of the index's table, so they're scored, sorted, and highlighted by the
+//! custom scan like any other search.  Aggregations are run by `paradedb.aggregate()`'s
+//! Elasticsearch dialect.
+
+use crate::aggregate::{elastic, execute_aggregate, execute_count};
+use crate::api::{ensure_no_rls, FieldName};
+use crate::postgres::rel::PgSearchRelation;
+use crate::query::pdb_query::pdb;
+use crate::query::SearchQueryInput;
+use anyhow::{anyhow, bail, ensure, Result};
+use pgrx::prelude::*;
+use pgrx::spi::{quote_identifier, quote_literal};
+use pgrx::{JsonB, PgRelation};
+use serde_json::{json, Map, Value};
+use std::ops::Bound;
+use std::time::Instant;
+use tantivy::schema::OwnedValue;
+
+const DEFAULT_SIZE: u64 = 10;
+const MEMORY_LIMIT: u64 = 500000000;
+const BUCKET_LIMIT: u32 = 65000;
+
+/// The queries of a single field, like `{"match": {"description": "shoes"}}`.
+const FIELD_QUERIES: [&str; 10] = [
+    "match",
+    "match_phrase",
+    "term",
+    "terms",
+    "range",
+    "exists",
+    "prefix",
+    "fuzzy",
+    "regexp",
+    "wildcard",
+];
+
+/// Run the Elasticsearch `_search` request body `request` against `index`.
+///
+/// `query`, `from`, `size`, `sort`, `_source`, `highlight`, and `aggs` are supported.
+#[pg_extern]
+fn es_search(index: PgRelation, request: JsonB) -> Result<JsonB> {
+    let started = Instant::now();
+    let relation = unsafe { PgSearchRelation::from_pg(index.as_ptr()) };
+    let heaprel = relation
+        .heap_relation()
+        .ok_or_else(|| anyhow!("`{}` does not belong to a table", relation.name()))?;
+    // the hit count and aggregations are computed from the index alone
+    ensure_no_rls(&heaprel)?;
+    let key_field = relation.options().key_field_name();
+    let request = EsRequest::parse(request.0, &key_field)?;
+
+    let total_hits = execute_count(&relation, request.query.clone(), true)?;
+    let hits = if request.size == 0 {
+        vec![]
+    } else {
+        search_hits(&relation, &heaprel, &key_field, &request)?
+    };
+    let max_score = hits
+        .iter()
+        .filter_map(|hit| hit["_score"].as_f64())
+        .reduce(f64::max);
+
+    let mut response = match &request.aggs {
+        Some(aggs) => {
+            let (aggs, meta) = elastic::translate_request(json!({ "aggs": aggs }))?;
+            let result = if aggs.as_object().is_some_and(|aggs| aggs.is_empty()) {
+                aggs
+            } else {
+                execute_aggregate(
+                    &relation,
+                    request.query.clone(),
+                    aggs,
+                    true,
+                    MEMORY_LIMIT,
+                    BUCKET_LIMIT,
+                )
+                .map_err(|e| anyhow!("{e}"))?
+            };
+            elastic::translate_response(result, total_hits, &meta)
+        }
+        None => json!({
+            "timed_out": false,
+            "hits": {"total": {"value": total_hits, "relation": "eq"}}
+        }),
+    };
+    response["took"] = json!(started.elapsed().as_millis() as u64);
+    response["hits"]["max_score"] = json!(max_score);
+    response["hits"]["hits"] = Value::Array(hits);
+    Ok(JsonB(response))
+}
+
+/// The parts of a `_search` request body that `paradedb.es_search()` understands.
+#[derive(Debug, PartialEq)]
+struct EsRequest {
+    query: SearchQueryInput,
+    from: u64,
+    size: u64,
+    sort: Vec<(EsSortKey, bool)>,
+    source: EsSource,
+    highlight: Vec<EsHighlight>,
+    aggs: Option<Value>,
+}
+
+#[derive(Debug, PartialEq)]
+enum EsSortKey {
+    Score,
+    Field(String),
+}
+
+#[derive(Debug, PartialEq)]
+enum EsSource {
+    All,
+    None,
+    Fields(Vec<String>),
+}
+
+#[derive(Debug, PartialEq)]
+struct EsHighlight {
+    field: String,
+    pre_tag: String,
+    post_tag: String,
+    fragment_size: i64,
+    number_of_fragments: i64,
+    escape_html: bool,
+}
+
+impl EsRequest {
+    fn parse(body: Value, key_field: &FieldName) -> Result<Self> {
+        let Value::Object(mut body) = body else {
+            bail!("an Elasticsearch search body must be a JSON object");
+        };
+
+        let query = match body.remove("query") {
+            Some(query) => translate_query(&query, key_field)?,
+            None => SearchQueryInput::All,
+        };
+        let from = match body.remove("from") {
+            Some(from) => from
+                .as_u64()
+                .ok_or_else(|| anyhow!("`from` must be a non-negative integer"))?,
+            None => 0,
+        };
+        let size = match body.remove("size") {
+            Some(size) => size
+                .as_u64()
+                .ok_or_else(|| anyhow!("`size` must be a non-negative integer"))?,
+            None => DEFAULT_SIZE,
+        };
+        let sort = match body.remove("sort") {
+            Some(sort) => parse_sort(sort)?,
+            None => vec![],
+        };
+        let source = match body.remove("_source") {
+            None | Some(Value::Bool(true)) => EsSource::All,
+            Some(Value::Bool(false)) => EsSource::None,
+            Some(Value::String(field)) => EsSource::Fields(vec![field]),
+            Some(Value::Array(fields)) => EsSource::Fields(
+                fields
+                    .into_iter()
+                    .map(|field| match field {
+                        Value::String(field) => Ok(field),
+                        _ => Err(anyhow!("`_source` fields must be strings")),
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            Some(_) => bail!("`_source` must be a boolean, a field name, or an array of them"),
+        };
+        let highlight = match body.remove("highlight") {
+            Some(highlight) => parse_highlight(highlight)?,
+            None => vec![],
+        };
+        let aggs = match (body.remove("aggs"), body.remove("aggregations")) {
+            (Some(_), Some(_)) => bail!("only one of `aggs` and `aggregations` may be specified"),
+            (Some(aggs), None) | (None, Some(aggs)) => Some(aggs),
+            (None, None) => None,
+        };
+        // always reported exactly
+        body.remove("track_total_hits");
+        if let Some(key) = body.keys().next() {
+            bail!("`{key}` is not supported in an Elasticsearch search body");
+        }
+
+        Ok(Self {
+            query,
+            from,
+            size,
+            sort,
+            source,
+            highlight,
+            aggs,
+        })
+    }
+}
+
+fn parse_sort(sort: Value) -> Result<Vec<(EsSortKey, bool)>> {
+    let sorts = match sort {
+        Value::Array(sorts) => sorts,
+        sort => vec![sort],
+    };
+    sorts
+        .into_iter()
+        .map(|sort| {
+            let (field, order) = match sort {
+                Value::String(field) => (field, None),
+                Value::Object(sort) if sort.len() == 1 => {
+                    let (field, order) = sort.into_iter().next().unwrap();
+                    let order = match order {
+                        Value::String(order) => order,
+                        Value::Object(mut options) => match options.remove("order") {
+                            Some(Value::String(order)) => order,
+                            _ => bail!("sort `{field}` must have an `order`"),
+                        },
+                        _ => bail!("sort `{field}` must be `asc` or `desc`"),
+                    };
+                    (field, Some(order))
+                }
+                _ => bail!("each sort must be a field name or an object of one field"),
+            };
+
+            let key = match field.as_str() {
+                "_score" => EsSortKey::Score,
+                _ => EsSortKey::Field(field.clone()),
+            };
+            // like Elasticsearch, scores sort from the best by default, and fields from the least
+            let descending = match order.as_deref() {
+                None => key == EsSortKey::Score,
+                Some("asc") => false,
+                Some("desc") => true,
+                Some(other) => bail!("sort `{field}` must be `asc` or `desc`, not `{other}`"),
+            };
+            Ok((key, descending))
+        })
+        .collect()
+}
+
+fn parse_highlight(highlight: Value) -> Result<Vec<EsHighlight>> {
+    let Value::Object(highlight) = highlight else {
+        bail!("`highlight` must be a JSON object");
+    };
+    let tag = |options: &Map<String, Value>, key: &str, default: &str| -> Result<String> {
+        match options.get(key) {
+            None => Ok(default.into()),
+            Some(Value::Array(tags)) => match tags.as_slice() {
+                [Value::String(tag)] => Ok(tag.clone()),
+                _ => bail!("`highlight.{key}` must have exactly one tag"),
+            },
+            Some(Value::String(tag)) => Ok(tag.clone()),
+            Some(_) => bail!("`highlight.{key}` must be an array of strings"),
+        }
+    };
+    let number = |options: &Map<String, Value>, key: &str, default: i64| -> Result<i64> {
+        match options.get(key) {
+            None => Ok(default),
+            Some(value) => value
+                .as_i64()
+                .filter(|value| *value > 0)
+                .ok_or_else(|| anyhow!("`highlight.{key}` must be a positive integer")),
+        }
+    };
+
+    let pre_tag = tag(&highlight, "pre_tags", "<em>")?;
+    let post_tag = tag(&highlight, "post_tags", "</em>")?;
+    let fragment_size = number(&highlight, "fragment_size", 100)?;
+    let number_of_fragments = number(&highlight, "number_of_fragments", 5)?;
+    let escape_html = highlight.get("encoder").and_then(Value::as_str) == Some("html");
+
+    let fields = match highlight.get("fields") {
+        Some(Value::Object(fields)) => fields
+            .iter()
+            .map(|(field, options)| (field.clone(), options.clone()))
+            .collect::<Vec<_>>(),
+        // the array form keeps the fields in order
+        Some(Value::Array(fields)) => fields
+            .iter()
+            .map(|field| match field.as_object() {
+                Some(field) if field.len() == 1 => {
+                    let (field, options) = field.iter().next().unwrap();
+                    Ok((field.clone(), options.clone()))
+                }
+                _ => bail!("each of `highlight.fields` must be an object of one field"),
+            })
+            .collect::<Result<_>>()?,
+        _ => bail!("`highlight.fields` must be a JSON object"),
+    };
+    fields
+        .into_iter()
+        .map(|(field, options)| {
+            let options = options.as_object().cloned().unwrap_or_default();
+            Ok(EsHighlight {
+                pre_tag: tag(&options, "pre_tags", &pre_tag)?,
+                post_tag: tag(&options, "post_tags", &post_tag)?,
+                fragment_size: number(&options, "fragment_size", fragment_size)?,
+                number_of_fragments: number(&options, "number_of_fragments", number_of_fragments)?,
+                escape_html,
+                field,
+            })
+        })
+        .collect()
+}
+
+/// Translate an Elasticsearch query into a [`SearchQueryInput`].
+fn translate_query(query: &Value, key_field: &FieldName) -> Result<SearchQueryInput> {
+    let Some((kind, body)) = query
+        .as_object()
+        .filter(|query| query.len() == 1)
+        .and_then(|query| query.iter().next())
+    else {
+        bail!("a query must be a JSON object of one query type, like `{{\"match\": ...}}`, not `{query}`");
+    };
+    let kind = kind.as_str();
+
+    match kind {
+        "match_all" => return boosted(SearchQueryInput::All, object(kind, body)?),
+        "match_none" => return Ok(SearchQueryInput::Empty),
+        "bool" => return translate_bool(object(kind, body)?, key_field),
+        "constant_score" => {
+            let body = object(kind, body)?;
+            let filter = body
+                .get("filter")
+                .ok_or_else(|| anyhow!("`constant_score` must have a `filter`"))?;
+            return Ok(SearchQueryInput::ConstScore {
+                query: Box::new(translate_query(filter, key_field)?),
+                score: float(kind, body, "boost")?.unwrap_or(1.0),
+            });
+        }
+        "dis_max" => {
+            let body = object(kind, body)?;
+            let queries = body
+                .get("queries")
+                .ok_or_else(|| anyhow!("`dis_max` must have `queries`"))?;
+            return boosted(
+                SearchQueryInput::DisjunctionMax {
+                    disjuncts: translate_clauses(queries, key_field)?,
+                    tie_breaker: float(kind, body, "tie_breaker")?,
+                },
+                body,
+            );
+        }
+        "ids" => {
+            let body = object(kind, body)?;
+            let Some(Value::Array(values)) = body.get("values") else {
+                bail!("`ids` must have an array of `values`");
+            };
+            return Ok(SearchQueryInput::FieldedQuery {
+                field: key_field.clone(),
+                query: pdb::Query::TermSet {
+                    terms: values
+                        .iter()
+                        .map(|value| scalar(kind, value))
+                        .collect::<Result<_>>()?,
+                },
+            });
+        }
+        "query_string" | "simple_query_string" => {
+            let body = object(kind, body)?;
+            let query_string = string(kind, body, "query")?;
+            let conjunction_mode = match body.get("default_operator").and_then(Value::as_str) {
+                None => None,
+                Some(operator) => Some(is_and(kind, operator)?),
+            };
+            let lenient = Some(kind == "simple_query_string");
+            let query = match body.get("default_field").and_then(Value::as_str) {
+                Some(field) => fielded(
+                    field,
+                    pdb::Query::ParseWithField {
+                        query_string,
+                        lenient,
+                        conjunction_mode,
+                        fuzzy_data: None,
+                    },
+                ),
+                None => SearchQueryInput::Parse {
+                    query_string,
+                    lenient,
+                    conjunction_mode,
+                    syntax: None,
+                },
+            };
+            return boosted(query, body);
+        }
+        "multi_match" => {
+            let body = object(kind, body)?;
+            let value = string(kind, body, "query")?;
+            let Some(Value::Array(fields)) = body.get("fields") else {
+                bail!("`multi_match` must have an array of `fields`");
+            };
+            let conjunction_mode = operator(kind, body)?;
+            let disjuncts = fields
+                .iter()
+                .map(|field| {
+                    let field = field
+                        .as_str()
+                        .ok_or_else(|| anyhow!("`multi_match` fields must be strings"))?;
+                    // a field can be boosted like `title^2`
+                    let (field, boost) = match field.split_once('^') {
+                        Some((field, boost)) => (field, Some(boost.parse::<f32>()?)),
+                        None => (field, None),
+                    };
+                    let query = fielded(
+                        field,
+                        pdb::Query::Match {
+                            value: value.clone(),
+                            tokenizer: None,
+                            distance: fuzziness(kind, body, &value)?,
+                            transposition_cost_one: None,
+                            prefix: None,
+                            conjunction_mode,
+                            stopwords: None,
+                        },
+                    );
+                    Ok(match boost {
+                        Some(factor) => SearchQueryInput::Boost {
+                            query: Box::new(query),
+                            factor,
+                        },
+                        None => query,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return boosted(
+                SearchQueryInput::DisjunctionMax {
+                    disjuncts,
+                    tie_breaker: float(kind, body, "tie_breaker")?,
+                },
+                body,
+            );
+        }
+        _ => {}
+    }
+
+    // the rest of the queries are of one field, given as the only key of their body
+    if !FIELD_QUERIES.contains(&kind) {
+        bail!("the `{kind}` query is not supported");
+    }
+    let (field, params) = field_params(kind, body)?;
+    let params = &params;
+    let query = match kind {
+        "match" => {
+            let value = string(kind, params, "query")?;
+            pdb::Query::Match {
+                distance: fuzziness(kind, params, &value)?,
+                value,
+                tokenizer: None,
+                transposition_cost_one: None,
+                prefix: None,
+                conjunction_mode: operator(kind, params)?,
+                stopwords: None,
+            }
+        }
+        "match_phrase" => pdb::Query::TokenizedPhrase {
+            phrase: string(kind, params, "query")?,
+            slop: float(kind, params, "slop")?.map(|slop| slop as u32),
+        },
+        "term" => pdb::Query::Term {
+            value: scalar(
+                kind,
+                params
+                    .get("value")
+                    .ok_or_else(|| anyhow!("`term` must have a `value`"))?,
+            )?,
+            is_datetime: false,
+        },
+        "terms" => {
+            let Some(Value::Array(values)) = params.get("value") else {
+                bail!("`terms` must have an array of values");
+            };
+            pdb::Query::TermSet {
+                terms: values
+                    .iter()
+                    .map(|value| scalar(kind, value))
+                    .collect::<Result<_>>()?,
+            }
+        }
+        "range" => {
+            // bounds aren't interpreted as dates, so a zone for them would silently do nothing
+            ensure!(
+                !params.contains_key("time_zone"),
+                "`range` doesn't support `time_zone`, write its dates with a UTC offset instead"
+            );
+            let bound = |inclusive: &str, exclusive: &str| -> Result<Bound<OwnedValue>> {
+                match (params.get(inclusive), params.get(exclusive)) {
+                    (Some(_), Some(_)) => {
+                        bail!("`range` must not have both `{inclusive}` and `{exclusive}`")
+                    }
+                    (Some(value), None) => Ok(Bound::Included(scalar(kind, value)?)),
+                    (None, Some(value)) => Ok(Bound::Excluded(scalar(kind, value)?)),
+                    (None, None) => Ok(Bound::Unbounded),
+                }
+            };
+            pdb::Query::Range {
+                lower_bound: bound("gte", "gt")?,
+                upper_bound: bound("lte", "lt")?,
+                is_datetime: false,
+            }
+        }
+        "exists" => pdb::Query::Exists,
+        "prefix" => pdb::Query::FuzzyTerm {
+            value: string(kind, params, "value")?,
+            distance: Some(0),
+            transposition_cost_one: None,
+            prefix: Some(true),
+        },
+        "fuzzy" => {
+            let value = string(kind, params, "value")?;
+            pdb::Query::FuzzyTerm {
+                distance: Some(fuzziness(kind, params, &value)?.unwrap_or(2)),
+                value,
+                transposition_cost_one: None,
+                prefix: None,
+            }
+        }
+        "regexp" => pdb::Query::Regex {
+            pattern: string(kind, params, "value")?,
+        },
+        "wildcard" => pdb::Query::Regex {
+            pattern: wildcard_to_regex(&string(kind, params, "value")?),
+        },
+        _ => unreachable!("`{kind}` should be one of the field queries"),
+    };
+    boosted(fielded(&field, query), params)
+}
+
+fn translate_bool(body: &Map<String, Value>, key_field: &FieldName) -> Result<SearchQueryInput> {
+    let clauses = |occur: &str| -> Result<Vec<SearchQueryInput>> {
+        match body.get(occur) {
+            Some(clauses) => translate_clauses(clauses, key_field),
+            None => Ok(vec![]),
+        }
+    };
+    let mut must = clauses("must")?;
+    // filters must match, but don't count toward the score
+    must.extend(
+        clauses("filter")?
+            .into_iter()
+            .map(|query| SearchQueryInput::ConstScore {
+                query: Box::new(query),
+                score: 0.0,
+            }),
+    );
+    let should = clauses("should")?;
+    let must_not = clauses("must_not")?;
+
+    // tantivy only requires a `should` clause to match when there are no `must` clauses, which
+    // is also Elasticsearch's default
+    if let Some(minimum) = body.get("minimum_should_match") {
+        let minimum = match minimum {
+            Value::Number(number) => number.as_u64(),
+            Value::String(number) => number.parse().ok(),
+            _ => None,
+        };
+        match minimum {
+            Some(0) if must.is_empty() && !should.is_empty() => {
+                bail!("`minimum_should_match` of 0 is only supported alongside `must` or `filter` clauses")
+            }
+            Some(0) => {}
+            Some(1) if must.is_empty() => {}
+            _ => bail!("only the default `minimum_should_match` is supported"),
+        }
+    }
+
+    boosted(
+        SearchQueryInput::Boolean {
+            must,
+            should,
+            must_not,
+        },
+        body,
+    )
+}
+
+/// The clauses of a compound query, which can be given as one query or as an array of them.
+fn translate_clauses(clauses: &Value, key_field: &FieldName) -> Result<Vec<SearchQueryInput>> {
+    match clauses {
+        Value::Array(clauses) => clauses
+            .iter()
+            .map(|clause| translate_query(clause, key_field))
+            .collect(),
+        clause => Ok(vec![translate_query(clause, key_field)?]),
+    }
+}
+
+fn fielded(field: &str, query: pdb::Query) -> SearchQueryInput {
+    SearchQueryInput::FieldedQuery {
+        field: field.into(),
+        query,
+    }
+}
+
+fn boosted(query: SearchQueryInput, params: &Map<String, Value>) -> Result<SearchQueryInput> {
+    Ok(match params.get("boost") {
+        Some(boost) => SearchQueryInput::Boost {
+            query: Box::new(query),
+            factor: boost
+                .as_f64()
+                .ok_or_else(|| anyhow!("`boost` must be a number"))? as f32,
+        },
+        None => query,
+    })
+}
+
+fn object<'a>(kind: &str, body: &'a Value) -> Result<&'a Map<String, Value>> {
+    body.as_object()
+        .ok_or_else(|| anyhow!("`{kind}` must be a JSON object"))
+}
+
+/// The field of a one-field query and its parameters.  Its value can be an object of parameters,
+/// or the query's main parameter on its own, like the text of `{"match": {"body": "text"}}`.
+fn field_params(kind: &str, body: &Value) -> Result<(String, Map<String, Value>)> {
+    let body = object(kind, body)?;
+    if kind == "exists" {
+        let field = string(kind, body, "field")?;
+        return Ok((field, Map::new()));
+    }
+    let mut fields = body.iter();
+    let (Some((field, params)), None) = (fields.next(), fields.next()) else {
+        bail!("`{kind}` must be an object of exactly one field");
+    };
+
+    let main = match kind {
+        "match" | "match_phrase" => "query",
+        _ => "value",
+    };
+    let params = match params {
+        Value::Object(params) if kind != "terms" => params.clone(),
+        value => Map::from_iter([(main.to_string(), value.clone())]),
+    };
+    Ok((field.clone(), params))
+}
+
+fn string(kind: &str, params: &Map<String, Value>, key: &str) -> Result<String> {
+    match params.get(key) {
+        Some(Value::String(value)) => Ok(value.clone()),
+        // numbers and booleans are searched for as text, as Elasticsearch would
+        Some(value @ (Value::Number(_) | Value::Bool(_))) => Ok(value.to_string()),
+        _ => bail!("`{kind}` must have a string `{key}`"),
+    }
+}
+
+fn float(kind: &str, params: &Map<String, Value>, key: &str) -> Result<Option<f32>> {
+    match params.get(key) {
+        None => Ok(None),
+        Some(value) => Ok(Some(
+            value
+                .as_f64()
+                .ok_or_else(|| anyhow!("`{kind}`: `{key}` must be a number"))? as f32,
+        )),
+    }
+}
+
+fn scalar(kind: &str, value: &Value) -> Result<OwnedValue> {
+    ensure!(
+        matches!(value, Value::String(_) | Value::Number(_) | Value::Bool(_)),
+        "`{kind}` values must be strings, numbers, or booleans, not `{value}`"
+    );
+    Ok(serde_json::from_value(value.clone())?)
+}
+
+fn is_and(kind: &str, operator: &str) -> Result<bool> {
+    match operator.to_ascii_lowercase().as_str() {
+        "and" => Ok(true),
+        "or" => Ok(false),
+        _ => bail!("`{kind}`: the operator must be `and` or `or`, not `{operator}`"),
+    }
+}
+
+fn operator(kind: &str, params: &Map<String, Value>) -> Result<Option<bool>> {
+    match params.get("operator").and_then(Value::as_str) {
+        None => Ok(None),
+        Some(operator) => Ok(Some(is_and(kind, operator)?)),
+    }
+}
+
+/// The edit distance of a `fuzziness`, which is either a number of edits, or `AUTO` to choose
+/// one by the length of the query's shortest word, like Elasticsearch does for each term.
+fn fuzziness(kind: &str, params: &Map<String, Value>, value: &str) -> Result<Option<u8>> {
+    match params.get("fuzziness") {
+        None => Ok(None),
+        Some(Value::String(auto)) if auto.eq_ignore_ascii_case("auto") => {
+            let shortest = value
+                .split_whitespace()
+                .map(|word| word.chars().count())
+                .min()
+                .unwrap_or(0);
+            Ok(Some(match shortest {
+                0..=2 => 0,
+                3..=5 => 1,
+                _ => 2,
+            }))
+        }
+        Some(distance) => {
+            let distance = match distance {
+                Value::Number(distance) => distance.as_u64(),
+                Value::String(distance) => distance.parse().ok(),
+                _ => None,
+            };
+            match distance {
+                Some(distance @ 0..=2) => Ok(Some(distance as u8)),
+                _ => bail!("`{kind}`: `fuzziness` must be 0, 1, 2, or `AUTO`"),
+            }
+        }
+    }
+}
+
+/// A `wildcard` pattern, where `*` is any characters and `?` is any one, as a regex.
+fn wildcard_to_regex(pattern: &str) -> String {
+    let mut regex = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c if r"\.+()[]{}|^$#&~-".contains(c) => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            c => regex.push(c),
+        }
+    }
+    regex
+}
+
+/// Find the hits of `request` with a `SELECT` of the table, as Elasticsearch hits.
+fn search_hits(
+    relation: &PgSearchRelation,
+    heaprel: &PgSearchRelation,
+    key_field: &FieldName,
+    request: &EsRequest,
+) -> Result<Vec<Value>> {
+    let key = format!("t.{}", quote_identifier(key_field.root()));
+    let score = format!("paradedb.score({key})");
+
+    let mut columns = vec![format!("{key}::text"), score.clone(), "to_jsonb(t)".into()];
+    for (sort, _) in &request.sort {
+        columns.push(match sort {
+            EsSortKey::Score => format!("to_jsonb({score})"),
+            EsSortKey::Field(field) => format!("to_jsonb(t.{})", quote_identifier(field)),
+        });
+    }
+    for highlight in &request.highlight {
+        columns.push(format!(
+            "paradedb.snippet_fragments(t.{}, start_tag => {}, end_tag => {}, fragment_size => {}, number_of_fragments => {}, escape_html => {})",
+            quote_identifier(&highlight.field),
+            quote_literal(&highlight.pre_tag),
+            quote_literal(&highlight.post_tag),
+            highlight.fragment_size,
+            highlight.number_of_fragments,
+            highlight.escape_html,
+        ));
+    }
+
+    let order_by = if request.sort.is_empty() {
+        format!("{score} DESC")
+    } else {
+        request
+            .sort
+            .iter()
+            .map(|(sort, descending)| {
+                let sort = match sort {
+                    EsSortKey::Score => score.clone(),
+                    EsSortKey::Field(field) => format!("t.{}", quote_identifier(field)),
+                };
+                format!("{sort} {}", if *descending { "DESC" } else { "ASC" })
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let sql = format!(
+        "SELECT {} FROM {}.{} t WHERE {key} @@@ $1 ORDER BY {order_by} LIMIT {} OFFSET {}",
+        columns.join(", "),
+        quote_identifier(heaprel.namespace()),
+        quote_identifier(heaprel.name()),
+        request.size,
+        request.from,
+    );
+
+    let index_name = relation.name().to_string();
+    Spi::connect(|client| {
+        let mut hits = Vec::new();
+        for row in client.select(&sql, None, &[request.query.clone().into()])? {
+            let id = row.get::<String>(1)?;
+            let score = row.get::<f32>(2)?;
+            let mut hit = Map::new();
+            hit.insert("_index".into(), json!(index_name));
+            hit.insert("_id".into(), json!(id));
+            hit.insert("_score".into(), json!(score));
+
+            let source = row.get::<JsonB>(3)?.map(|source| source.0);
+            match (&request.source, source) {
+                (EsSource::None, _) | (_, None) => {}
+                (EsSource::All, Some(source)) => {
+                    hit.insert("_source".into(), source);
+                }
+                (EsSource::Fields(fields), Some(Value::Object(mut source))) => {
+                    source.retain(|field, _| fields.contains(field));
+                    hit.insert("_source".into(), Value::Object(source));
+                }
+                (EsSource::Fields(_), Some(_)) => {}
+            }
+
+            let mut ordinal = 4;
+            if !request.sort.is_empty() {
+                let mut sort = Vec::with_capacity(request.sort.len());
+                for _ in &request.sort {
+                    sort.push(
+                        row.get::<JsonB>(ordinal)?
+                            .map_or(Value::Null, |value| value.0),
+                    );
+                    ordinal += 1;
+                }
+                hit.insert("sort".into(), Value::Array(sort));
+            }
+
+            let mut highlights = Map::new();
+            for highlight in &request.highlight {
+                let fragments = row
+                    .get::<JsonB>(ordinal)?
+                    .and_then(|fragments| fragments.0.as_array().cloned())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|fragment| fragment.get("fragment").cloned())
+                    .collect::<Vec<_>>();
+                ordinal += 1;
+                if !fragments.is_empty() {
+                    highlights.insert(highlight.field.clone(), Value::Array(fragments));
+                }
+            }
+            if !highlights.is_empty() {
+                hit.insert("highlight".into(), Value::Object(highlights));
+            }
+
+            hits.push(Value::Object(hit));
+        }
+        Ok(hits)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn translate(query: Value) -> Result<SearchQueryInput> {
+        translate_query(&query, &FieldName::from("id"))
+    }
+
+    #[rstest]
+    fn test_translate_match() {
+        assert_eq!(
+            translate(
+                json!({"match": {"description": {"query": "running shoes", "operator": "and"}}})
+            )
+            .unwrap(),
+            fielded(
+                "description",
+                pdb::Query::Match {
+                    value: "running shoes".into(),
+                    tokenizer: None,
+                    distance: None,
+                    transposition_cost_one: None,
+                    prefix: None,
+                    conjunction_mode: Some(true),
+                    stopwords: None,
+                }
+            )
+        );
+    }
+
+    #[rstest]
+    fn test_translate_bool() {
+        assert_eq!(
+            translate(json!({"bool": {
+                "must": {"match": {"description": "shoes"}},
+                "filter": [{"range": {"rating": {"gte": 4}}}],
+                "must_not": [{"term": {"in_stock": false}}]
+            }}))
+            .unwrap(),
+            SearchQueryInput::Boolean {
+                must: vec![
+                    fielded(
+                        "description",
+                        pdb::Query::Match {
+                            value: "shoes".into(),
+                            tokenizer: None,
+                            distance: None,
+                            transposition_cost_one: None,
+                            prefix: None,
+                            conjunction_mode: None,
+                            stopwords: None,
+                        }
+                    ),
+                    SearchQueryInput::ConstScore {
+                        query: Box::new(fielded(
+                            "rating",
+                            pdb::Query::Range {
+                                lower_bound: Bound::Included(OwnedValue::U64(4)),
+                                upper_bound: Bound::Unbounded,
+                                is_datetime: false,
+                            }
+                        )),
+                        score: 0.0,
+                    }
+                ],
+                should: vec![],
+                must_not: vec![fielded(
+                    "in_stock",
+                    pdb::Query::Term {
+                        value: OwnedValue::Bool(false),
+                        is_datetime: false,
+                    }
+                )],
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_translate_ids_and_boost() {
+        assert_eq!(
+            translate(json!({"ids": {"values": [1, 2]}})).unwrap(),
+            fielded(
+                "id",
+                pdb::Query::TermSet {
+                    terms: vec![OwnedValue::U64(1), OwnedValue::U64(2)]
+                }
+            )
+        );
+        assert_eq!(
+            translate(json!({"match_all": {"boost": 2.0}})).unwrap(),
+            SearchQueryInput::Boost {
+                query: Box::new(SearchQueryInput::All),
+                factor: 2.0
+            }
+        );
+    }
+
+    #[rstest]
+    #[case("a*b?.c", r"a.*b.\.c")]
+    #[case("plain", "plain")]
+    fn test_wildcard_to_regex(#[case] pattern: &str, #[case] regex: &str) {
+        assert_eq!(wildcard_to_regex(pattern), regex);
+    }
+
+    #[rstest]
+    #[case(json!({"match": {"a": "x", "b": "y"}}))]
+    #[case(json!({"geo_distance": {"location": [0, 0]}}))]
+    #[case(json!({"match": {"a": "x"}, "term": {"b": "y"}}))]
+    #[case(json!({"bool": {"should": [{"match_all": {}}], "minimum_should_match": 2}}))]
+    #[case(json!({"range": {"created_at": {"gte": "2024-01-01", "time_zone": "+09:00"}}}))]
+    fn test_translate_query_invalid(#[case] query: Value) {
+        assert!(translate(query).is_err());
+    }
+
+    #[rstest]
+    fn test_parse_request() {
+        let request = EsRequest::parse(
+            json!({
+                "from": 5,
+                "size": 2,
+                "sort": [{"rating": {"order": "desc"}}, "_score"],
+                "_source": ["description"],
+                "highlight": {"pre_tags": ["<b>"], "post_tags": ["</b>"], "fields": {"description": {}}}
+            }),
+            &FieldName::from("id"),
+        )
+        .unwrap();
+        assert_eq!(request.query, SearchQueryInput::All);
+        assert_eq!((request.from, request.size), (5, 2));
+        assert_eq!(
+            request.sort,
+            vec![
+                (EsSortKey::Field("rating".into()), true),
+                (EsSortKey::Score, true)
+            ]
+        );
+        assert_eq!(request.source, EsSource::Fields(vec!["description".into()]));
+        assert_eq!(request.highlight[0].pre_tag, "<b>");
+        assert_eq!(request.highlight[0].number_of_fragments, 5);
+
+        assert!(EsRequest::parse(json!({"suggest": {}}), &FieldName::from("id")).is_err());
+    }
+}
@@ -0,0 +1,360 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.export_parquet()`, which writes the fast fields of the documents that match a query
+//! to a Parquet file on the database server, for analytics engines to read without going through
+//! a client.
+//!
+//! The values are read from each segment's fast fields a column and a batch of documents at a
+//! time, so the table's rows are only fetched to check that they're visible.
+
+use crate::api::{ensure_no_rls, ensure_role, ensure_select};
+use crate::index::mvcc::MvccSatisfies;
+use crate::index::reader::index::SearchIndexReader;
+use crate::postgres::build::is_bm25_index;
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::types_arrow::date_time_to_ts_nanos;
+use crate::postgres::visibility_checker::VisibilityChecker;
+use crate::query::SearchQueryInput;
+use crate::schema::SearchIndexSchema;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use arrow_array::builder::{BinaryBuilder, StringBuilder};
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, TimestampNanosecondArray,
+    UInt64Array,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use pgrx::prelude::*;
+use pgrx::PgRelation;
+use std::fmt::Debug;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use tantivy::columnar::{DynamicColumn, HasAssociatedColumnType};
+use tantivy::fastfield::{Column, FastFieldReaders};
+use tantivy::schema::FieldType;
+use tantivy::DocId;
+
+/// How many documents of a segment are written to the file at a time.
+const BATCH_SIZE: usize = 65_536;
+
+/// The types of fast fields that can be exported.
+#[derive(Debug, Clone, Copy)]
+enum ColumnKind {
+    Str,
+    Bytes,
+    I64,
+    U64,
+    F64,
+    Bool,
+    Date,
+}
+
+impl ColumnKind {
+    fn of(field_type: &FieldType) -> Option<Self> {
+        match field_type {
+            FieldType::Str(_) => Some(ColumnKind::Str),
+            FieldType::Bytes(_) => Some(ColumnKind::Bytes),
+            FieldType::I64(_) => Some(ColumnKind::I64),
+            FieldType::U64(_) => Some(ColumnKind::U64),
+            FieldType::F64(_) => Some(ColumnKind::F64),
+            FieldType::Bool(_) => Some(ColumnKind::Bool),
+            FieldType::Date(_) => Some(ColumnKind::Date),
+            _ => None,
+        }
+    }
+
+    fn data_type(self) -> DataType {
+        match self {
+            ColumnKind::Str => DataType::Utf8,
+            ColumnKind::Bytes => DataType::Binary,
+            ColumnKind::I64 => DataType::Int64,
+            ColumnKind::U64 => DataType::UInt64,
+            ColumnKind::F64 => DataType::Float64,
+            ColumnKind::Bool => DataType::Boolean,
+            ColumnKind::Date => DataType::Timestamp(TimeUnit::Nanosecond, None),
+        }
+    }
+}
+
+/// Write the `fields` of the visible documents of `index` that match `query` to a new Parquet file
+/// at `path` on the database server, or all of the index's fast fields if `fields` is `NULL`.
+/// Returns the number of documents written.
+#[pg_extern]
+fn export_parquet(
+    index: PgRelation,
+    query: SearchQueryInput,
+    path: &str,
+    fields: default!(Option<Vec<String>>, "NULL"),
+) -> Result<i64> {
+    ensure_role("pg_write_server_files")?;
+    let index = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _);
+    ensure!(
+        is_bm25_index(&index)
+            && unsafe { pg_sys::get_rel_relkind(index.oid()) as u8 == pg_sys::RELKIND_INDEX },
+        "`{}` is not a BM25 index",
+        index.name()
+    );
+    let heaprel = index
+        .heap_relation()
+        .ok_or_else(|| anyhow!("`{}` does not belong to a table", index.name()))?;
+    let path = Path::new(path);
+    ensure!(
+        path.is_absolute(),
+        "`{}` is not an absolute path",
+        path.display()
+    );
+
+    let schema = index.schema()?;
+    let columns = export_columns(&schema, fields)?;
+    ensure_select_columns(&index, &heaprel, &schema, &columns)?;
+    ensure_no_rls(&heaprel)?;
+
+    let reader = SearchIndexReader::open(&index, query, false, MvccSatisfies::Snapshot)?;
+    let file =
+        File::create_new(path).with_context(|| format!("could not create `{}`", path.display()))?;
+    write_parquet(file, &reader, &heaprel, &columns).inspect_err(|_| {
+        // a file that was only partly written is of no use to anyone
+        let _ = std::fs::remove_file(path);
+    })
+}
+
+/// The columns to export, in their order in the index if they weren't named.
+fn export_columns(
+    schema: &SearchIndexSchema,
+    fields: Option<Vec<String>>,
+) -> Result<Vec<(String, ColumnKind)>> {
+    match fields {
+        None => Ok(schema
+            .fields()
+            .filter(|(_, entry)| entry.is_fast() && entry.name() != "ctid")
+            .filter_map(|(_, entry)| {
+                ColumnKind::of(entry.field_type()).map(|kind| (entry.name().to_string(), kind))
+            })
+            .collect()),
+        Some(fields) => fields
+            .into_iter()
+            .map(|name| {
+                let field = schema
+                    .search_field(&name)
+                    .ok_or_else(|| anyhow!("`{name}` is not a field of the index"))?;
+                ensure!(field.is_fast(), "`{name}` is not a fast field");
+                match ColumnKind::of(field.field_entry().field_type()) {
+                    Some(kind) => Ok((name, kind)),
+                    None => bail!("`{name}` is a field whose values can't be exported"),
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Fail unless the current user can read the table columns that `columns` are indexed from, with
+/// `SELECT` on either the whole table or each of those columns.  The values of an expression can
+/// only be read with `SELECT` on the whole table.
+fn ensure_select_columns(
+    index: &PgSearchRelation,
+    heaprel: &PgSearchRelation,
+    schema: &SearchIndexSchema,
+    columns: &[(String, ColumnKind)],
+) -> Result<()> {
+    if ensure_select(heaprel).is_ok() {
+        return Ok(());
+    }
+
+    let categorized = schema.categorized_fields();
+    let keys = unsafe {
+        let keys = &(*index.rd_index).indkey;
+        keys.values.as_slice(keys.dim1 as usize)
+    };
+    for (name, _) in columns {
+        let attnum = categorized
+            .iter()
+            .find(|(field, _)| field.field_name().as_ref() == name.as_str())
+            .map(|(_, data)| keys[data.attno])
+            .unwrap_or(0);
+        let allowed = attnum != 0
+            && unsafe {
+                pg_sys::pg_attribute_aclcheck(
+                    heaprel.oid(),
+                    attnum,
+                    pg_sys::GetUserId(),
+                    pg_sys::ACL_SELECT as pg_sys::AclMode,
+                )
+            } == pg_sys::AclResult::ACLCHECK_OK;
+        ensure!(
+            allowed,
+            "permission denied for column `{name}` of table `{}`",
+            heaprel.name()
+        );
+    }
+    Ok(())
+}
+
+fn write_parquet(
+    file: File,
+    reader: &SearchIndexReader,
+    heaprel: &PgSearchRelation,
+    columns: &[(String, ColumnKind)],
+) -> Result<i64> {
+    let schema = Arc::new(Schema::new(
+        columns
+            .iter()
+            .map(|(name, kind)| Field::new(name, kind.data_type(), true))
+            .collect::<Vec<_>>(),
+    ));
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(properties))?;
+
+    let mut visibility =
+        VisibilityChecker::with_rel_and_snap(heaprel, unsafe { pg_sys::GetActiveSnapshot() });
+    let slot = unsafe { pg_sys::table_slot_create(heaprel.as_ptr(), std::ptr::null_mut()) };
+
+    // the results are returned a segment at a time, so each batch is of one segment
+    let mut results = reader.search().peekable();
+    let mut exported = 0;
+    while let Some(segment_ord) = results
+        .peek()
+        .map(|(_, doc_address)| doc_address.segment_ord)
+    {
+        let mut ids = Vec::with_capacity(BATCH_SIZE);
+        while ids.len() < BATCH_SIZE {
+            let Some((scored, doc_address)) =
+                results.next_if(|(_, doc_address)| doc_address.segment_ord == segment_ord)
+            else {
+                break;
+            };
+            check_for_interrupts!();
+            if visibility
+                .exec_if_visible(scored.ctid, slot, |_| ())
+                .is_some()
+            {
+                ids.push(doc_address.doc_id);
+            }
+            unsafe { pg_sys::ExecClearTuple(slot) };
+        }
+        if ids.is_empty() {
+            continue;
+        }
+
+        let fast_fields = reader.segment_readers()[segment_ord as usize].fast_fields();
+        let arrays = columns
+            .iter()
+            .map(|(name, kind)| read_column(fast_fields, name, *kind, &ids))
+            .collect::<Result<Vec<_>>>()?;
+        writer.write(&RecordBatch::try_new(schema.clone(), arrays)?)?;
+        exported += ids.len() as i64;
+    }
+
+    unsafe { pg_sys::ExecDropSingleTupleTableSlot(slot) };
+    writer.close()?;
+    Ok(exported)
+}
+
+/// The values of the fast field `name` of the documents `ids`, which are `NULL` where a document
+/// doesn't have one.
+fn read_column(
+    fast_fields: &FastFieldReaders,
+    name: &str,
+    kind: ColumnKind,
+    ids: &[DocId],
+) -> Result<ArrayRef> {
+    Ok(match kind {
+        ColumnKind::I64 => Arc::new(Int64Array::from(first_vals::<i64>(fast_fields, name, ids)?)),
+        ColumnKind::U64 => Arc::new(UInt64Array::from(first_vals::<u64>(
+            fast_fields,
+            name,
+            ids,
+        )?)),
+        ColumnKind::F64 => Arc::new(Float64Array::from(first_vals::<f64>(
+            fast_fields,
+            name,
+            ids,
+        )?)),
+        ColumnKind::Bool => Arc::new(BooleanArray::from(first_vals::<bool>(
+            fast_fields,
+            name,
+            ids,
+        )?)),
+        ColumnKind::Date => Arc::new(TimestampNanosecondArray::from(
+            first_vals::<tantivy::DateTime>(fast_fields, name, ids)?
+                .into_iter()
+                .map(|value| value.map(date_time_to_ts_nanos))
+                .collect::<Vec<_>>(),
+        )),
+        ColumnKind::Str => {
+            let mut builder = StringBuilder::with_capacity(ids.len(), 0);
+            let column = fast_fields.str(name)?;
+            let mut text = String::new();
+            for &id in ids {
+                match column
+                    .as_ref()
+                    .and_then(|column| column.term_ords(id).next())
+                {
+                    Some(ord) => {
+                        text.clear();
+                        column.as_ref().unwrap().ord_to_str(ord, &mut text)?;
+                        builder.append_value(&text);
+                    }
+                    None => builder.append_null(),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnKind::Bytes => {
+            let mut builder = BinaryBuilder::with_capacity(ids.len(), 0);
+            let column = fast_fields.bytes(name)?;
+            let mut bytes = Vec::new();
+            for &id in ids {
+                match column
+                    .as_ref()
+                    .and_then(|column| column.term_ords(id).next())
+                {
+                    Some(ord) => {
+                        bytes.clear();
+                        column.as_ref().unwrap().ord_to_bytes(ord, &mut bytes)?;
+                        builder.append_value(&bytes);
+                    }
+                    None => builder.append_null(),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+    })
+}
+
+/// The first values of the numeric fast field `name` of the documents `ids`.  A segment none of
+/// whose documents have a value doesn't have the column at all.
+fn first_vals<T>(
+    fast_fields: &FastFieldReaders,
+    name: &str,
+    ids: &[DocId],
+) -> Result<Vec<Option<T>>>
+where
+    T: HasAssociatedColumnType + PartialOrd + Copy + Debug + Send + Sync + 'static,
+    DynamicColumn: Into<Option<Column<T>>>,
+{
+    let mut values = vec![None; ids.len()];
+    if let Some(column) = fast_fields.column_opt::<T>(name)? {
+        column.first_vals(ids, &mut values);
+    }
+    Ok(values)
+}
@@ -0,0 +1,211 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.dump_index_config()` and `paradedb.create_index_from_config()`, which describe a BM25
+//! index's definition as JSON and create an index from such a description, so that definitions
+//! can be kept in version control and applied to other databases.
+//!
+//! Options whose values are JSON, like `text_fields`, are dumped as JSON rather than as strings,
+//! so that a change to one field's tokenizer is a change of one line.  Quickwit index configs are
+//! accepted too, so that the indexes of logs moved from Quickwit keep their mappings.
+
+mod quickwit;
+
+use crate::postgres::build::is_bm25_index;
+use crate::postgres::rel::PgSearchRelation;
+use anyhow::{anyhow, ensure, Result};
+use pgrx::prelude::*;
+use pgrx::{JsonB, PgRelation};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IndexConfig {
+    index_name: String,
+    table_schema: String,
+    table_name: String,
+    /// The index's columns and expressions, as SQL.
+    columns: Vec<String>,
+    #[serde(default)]
+    options: Map<String, Value>,
+    /// The predicate of a partial index, as SQL.
+    #[serde(default, rename = "where", skip_serializing_if = "Option::is_none")]
+    predicate: Option<String>,
+}
+
+/// The definition of the BM25 index `index`: its name, table, columns, options and predicate.
+#[pg_extern(stable)]
+fn dump_index_config(index: PgRelation) -> Result<JsonB> {
+    let index = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _);
+    ensure!(
+        is_bm25_index(&index),
+        "`{}` is not a BM25 index",
+        index.name()
+    );
+
+    let config = Spi::get_one_with_args::<JsonB>(
+        "SELECT jsonb_strip_nulls(jsonb_build_object(
+             'index_name', c.relname,
+             'table_schema', n.nspname,
+             'table_name', t.relname,
+             'columns', (
+                 -- an expression has to be parenthesized to be a column of `CREATE INDEX` again
+                 SELECT jsonb_agg(
+                     CASE WHEN i.indkey[k - 1] = 0
+                          THEN '(' || pg_get_indexdef(i.indexrelid, k, true) || ')'
+                          ELSE pg_get_indexdef(i.indexrelid, k, true)
+                     END
+                     ORDER BY k
+                 )
+                 FROM generate_series(1, i.indnkeyatts) k
+             ),
+             'options', coalesce(
+                 (SELECT jsonb_object_agg(option_name, option_value) FROM pg_options_to_table(c.reloptions)),
+                 '{}'
+             ),
+             'where', pg_get_expr(i.indpred, i.indrelid, true)
+         ))
+         FROM pg_catalog.pg_index i
+         JOIN pg_catalog.pg_class c ON c.oid = i.indexrelid
+         JOIN pg_catalog.pg_class t ON t.oid = i.indrelid
+         JOIN pg_catalog.pg_namespace n ON n.oid = t.relnamespace
+         WHERE i.indexrelid = $1",
+        &[index.oid().into()],
+    )?
+    .ok_or_else(|| anyhow!("`{}` does not belong to a table", index.name()))?;
+
+    let mut config: IndexConfig = serde_json::from_value(config.0)?;
+    for value in config.options.values_mut() {
+        if let Value::String(s) = value {
+            if let Ok(json @ (Value::Object(_) | Value::Array(_) | Value::Number(_))) =
+                serde_json::from_str(s)
+            {
+                *value = json;
+            }
+        }
+    }
+    Ok(JsonB(serde_json::to_value(config)?))
+}
+
+/// Create the BM25 index described by `config`, in the form that `paradedb.dump_index_config()`
+/// returns or as a Quickwit index config, and return its name.
+///
+/// The index of a Quickwit config is created on the table named by its `index_id`.
+#[pg_extern]
+fn create_index_from_config(config: JsonB) -> Result<String> {
+    let config = if quickwit::is_quickwit_config(&config.0) {
+        let index_id = quickwit::index_id(&config.0)?;
+        let heaprelid = Spi::get_one_with_args::<pg_sys::Oid>(
+            "SELECT to_regclass(quote_ident($1))::oid",
+            &[index_id.into()],
+        )?
+        .ok_or_else(|| {
+            anyhow!(
+                "there is no table named `{index_id}` to create the Quickwit index `{index_id}` on"
+            )
+        })?;
+        quickwit_index_config(config.0, heaprelid)?
+    } else {
+        parse_config(config.0)?
+    };
+    create_index(config)
+}
+
+/// Create the BM25 index described by `config`, as YAML or JSON, on the table `table_name`
+/// instead of the one the config names, and return its name.
+#[pg_extern(name = "create_index_from_config")]
+fn create_index_from_config_on(config: &str, table_name: PgRelation) -> Result<String> {
+    let mut config: Value =
+        serde_yaml::from_str(config).map_err(|e| anyhow!("invalid index config: {e}"))?;
+    let heaprelid = table_name.oid();
+    drop(table_name);
+
+    let config = if quickwit::is_quickwit_config(&config) {
+        quickwit_index_config(config, heaprelid)?
+    } else {
+        let (table_schema, table_name) = Spi::get_two_with_args::<String, String>(
+            "SELECT n.nspname::text, c.relname::text
+             FROM pg_catalog.pg_class c
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+             WHERE c.oid = $1",
+            &[heaprelid.into()],
+        )?;
+        if let Some(config) = config.as_object_mut() {
+            config.insert("table_schema".into(), table_schema.into());
+            config.insert("table_name".into(), table_name.into());
+        }
+        parse_config(config)?
+    };
+    create_index(config)
+}
+
+fn parse_config(config: Value) -> Result<IndexConfig> {
+    serde_json::from_value(config).map_err(|e| anyhow!("invalid index config: {e}"))
+}
+
+/// The definition of the index described by the Quickwit config `config` of the table `heaprelid`,
+/// which is keyed by the table's primary key.
+fn quickwit_index_config(config: Value, heaprelid: pg_sys::Oid) -> Result<IndexConfig> {
+    let (table_schema, table_name, key_field) =
+        Spi::get_three_with_args::<String, String, String>(
+            "SELECT n.nspname::text, c.relname::text, (
+                 SELECT a.attname::text
+                 FROM pg_catalog.pg_index i
+                 JOIN pg_catalog.pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = i.indkey[0]
+                 WHERE i.indrelid = c.oid AND i.indisprimary AND i.indnkeyatts = 1
+             )
+             FROM pg_catalog.pg_class c
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+             WHERE c.oid = $1",
+            &[heaprelid.into()],
+        )?;
+    quickwit::to_index_config(
+        config,
+        table_schema.expect("table should have a schema"),
+        table_name.expect("table should have a name"),
+        key_field,
+    )
+}
+
+fn create_index(config: IndexConfig) -> Result<String> {
+    ensure!(
+        !config.columns.is_empty(),
+        "invalid index config: `columns` must not be empty"
+    );
+    ensure!(
+        config.options.contains_key("key_field"),
+        "invalid index config: `options` must include a `key_field`"
+    );
+
+    // identifiers are quoted by `format()`, and options that are JSON are written as JSON text
+    let ddl = Spi::get_one_with_args::<String>(
+        "SELECT format(
+                    'CREATE INDEX %I ON %I.%I USING bm25 (%s) WITH (%s)',
+                    $1->>'index_name',
+                    $1->>'table_schema',
+                    $1->>'table_name',
+                    (SELECT string_agg(expr, ', ' ORDER BY n) FROM jsonb_array_elements_text($1->'columns') WITH ORDINALITY AS e(expr, n)),
+                    (SELECT string_agg(format('%I = %L', key, value), ', ' ORDER BY key) FROM jsonb_each_text($1->'options'))
+                ) || coalesce(' WHERE ' || ($1->>'where'), '')",
+        &[JsonB(serde_json::to_value(&config)?).into()],
+    )?
+    .expect("DDL should not be NULL");
+    Spi::run(&ddl)?;
+
+    Ok(config.index_name)
+}
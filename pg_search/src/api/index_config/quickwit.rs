@@ -0,0 +1,413 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Translation of a Quickwit index config into the definition of a BM25 index.
+//!
+//! Quickwit's doc mapping becomes the index's columns and field options, its tokenizers become
+//! tokenizer configs, and its retention becomes a `ttl` on its timestamp field.  The settings that
+//! don't affect what's indexed, like `indexing_settings` and `search_settings`, are ignored.
+
+use super::IndexConfig;
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+#[derive(Debug, Deserialize)]
+struct QuickwitConfig {
+    index_id: String,
+    doc_mapping: DocMapping,
+    #[serde(default)]
+    retention: Option<Retention>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DocMapping {
+    field_mappings: Vec<FieldMapping>,
+    #[serde(default)]
+    tokenizers: Vec<TokenizerMapping>,
+    #[serde(default)]
+    timestamp_field: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Retention {
+    period: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FieldMapping {
+    name: String,
+    #[serde(rename = "type")]
+    field_type: String,
+    #[serde(default)]
+    indexed: Option<bool>,
+    #[serde(default)]
+    fast: Option<FastMapping>,
+    #[serde(default)]
+    fieldnorms: Option<bool>,
+    #[serde(default)]
+    tokenizer: Option<String>,
+    #[serde(default)]
+    record: Option<String>,
+    #[serde(default)]
+    expand_dots: Option<bool>,
+}
+
+/// A text field's `fast` is either a flag or the normalizer of its fast field.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum FastMapping {
+    Flag(bool),
+    Normalizer { normalizer: String },
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenizerMapping {
+    name: String,
+    #[serde(rename = "type")]
+    tokenizer_type: String,
+    #[serde(default)]
+    filters: Vec<String>,
+    #[serde(default)]
+    pattern: Option<String>,
+    #[serde(default)]
+    min_gram: Option<u64>,
+    #[serde(default)]
+    max_gram: Option<u64>,
+    #[serde(default)]
+    prefix_only: Option<bool>,
+}
+
+/// Whether `config` is a Quickwit index config rather than one of `paradedb.dump_index_config()`.
+pub fn is_quickwit_config(config: &Value) -> bool {
+    config.get("doc_mapping").is_some()
+}
+
+/// The Quickwit index that `config` describes, for which `paradedb.create_index_from_config()`
+/// looks for a table of the same name when it isn't given one.
+pub fn index_id(config: &Value) -> Result<&str> {
+    config
+        .get("index_id")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("invalid Quickwit index config: `index_id` is missing"))
+}
+
+/// The definition of a BM25 index of the table `table_schema.table_name`, whose primary key is
+/// `key_field`, with the fields of the Quickwit index config `config`.
+pub fn to_index_config(
+    config: Value,
+    table_schema: String,
+    table_name: String,
+    key_field: Option<String>,
+) -> Result<IndexConfig> {
+    let config: QuickwitConfig = serde_json::from_value(config)
+        .map_err(|e| anyhow!("invalid Quickwit index config: {e}"))?;
+    let key_field = key_field.ok_or_else(|| {
+        anyhow!("`{table_name}` needs a primary key of one column to be the index's `key_field`")
+    })?;
+
+    let mut columns = vec![key_field.clone()];
+    let mut options = Map::new();
+    for mapping in config.doc_mapping.field_mappings {
+        // the key field is indexed as such, whatever Quickwit mapped it as
+        if mapping.name == key_field {
+            continue;
+        }
+        let (option, field) = field_config(&mapping, &config.doc_mapping.tokenizers)?;
+        columns.push(mapping.name.clone());
+        options
+            .entry(option)
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .expect("field options should be an object")
+            .insert(mapping.name, field);
+    }
+    options.insert("key_field".into(), Value::String(key_field));
+
+    if let Some(retention) = config.retention {
+        let timestamp_field = config.doc_mapping.timestamp_field.ok_or_else(|| {
+            anyhow!("invalid Quickwit index config: `retention` needs a `timestamp_field`")
+        })?;
+        options.insert("ttl_field".into(), Value::String(timestamp_field));
+        options.insert("ttl".into(), Value::String(retention.period));
+    }
+
+    Ok(IndexConfig {
+        index_name: index_name(&config.index_id),
+        table_schema,
+        table_name,
+        columns,
+        options,
+        predicate: None,
+    })
+}
+
+/// The index is named after the Quickwit index, as a name that doesn't need quoting.
+fn index_name(index_id: &str) -> String {
+    let stem: String = index_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}_idx", stem.to_ascii_lowercase())
+}
+
+/// The `*_fields` option that configures a field of Quickwit's `mapping`, and its config.
+fn field_config(
+    mapping: &FieldMapping,
+    tokenizers: &[TokenizerMapping],
+) -> Result<(String, Value)> {
+    let mut field = Map::new();
+    if let Some(indexed) = mapping.indexed {
+        field.insert("indexed".into(), Value::Bool(indexed));
+    }
+    match &mapping.fast {
+        Some(FastMapping::Flag(fast)) => {
+            field.insert("fast".into(), Value::Bool(*fast));
+        }
+        Some(FastMapping::Normalizer { normalizer }) => {
+            field.insert("fast".into(), Value::Bool(true));
+            field.insert("normalizer".into(), Value::String(normalizer.clone()));
+        }
+        None => {}
+    }
+
+    // an array of a type is indexed like a single value of it
+    let field_type = mapping
+        .field_type
+        .strip_prefix("array<")
+        .and_then(|inner| inner.strip_suffix('>'))
+        .unwrap_or(&mapping.field_type);
+    let option = match field_type {
+        "text" | "json" | "object" => {
+            if let Some(tokenizer) = &mapping.tokenizer {
+                field.insert("tokenizer".into(), tokenizer_config(tokenizer, tokenizers)?);
+            }
+            if let Some(record) = &mapping.record {
+                field.insert("record".into(), Value::String(record.clone()));
+            }
+            if let Some(fieldnorms) = mapping.fieldnorms {
+                field.insert("fieldnorms".into(), Value::Bool(fieldnorms));
+            }
+            if field_type == "text" {
+                "text_fields"
+            } else {
+                if let Some(expand_dots) = mapping.expand_dots {
+                    field.insert("expand_dots".into(), Value::Bool(expand_dots));
+                }
+                "json_fields"
+            }
+        }
+        "i64" | "u64" | "f64" => "numeric_fields",
+        "bool" => "boolean_fields",
+        "datetime" => "datetime_fields",
+        "ip" => "inet_fields",
+        "bytes" => "bytes_fields",
+        other => bail!(
+            "field `{}` is of the Quickwit type `{other}`, which has no equivalent",
+            mapping.name
+        ),
+    };
+    Ok((option.into(), Value::Object(field)))
+}
+
+/// The tokenizer config of Quickwit's tokenizer `name`, which is either one of its own or one of
+/// the doc mapping's `tokenizers`.
+fn tokenizer_config(name: &str, tokenizers: &[TokenizerMapping]) -> Result<Value> {
+    Ok(match name {
+        "raw" => json!({"type": "keyword"}),
+        "default" => json!({"type": "default"}),
+        "en_stem" => json!({"type": "default", "stemmer": "English"}),
+        "lowercase" => json!({"type": "lowercase"}),
+        // Quickwit's whitespace tokenizer leaves the case of its tokens alone
+        "whitespace" => json!({"type": "whitespace", "lowercase": false}),
+        "chinese_compatible" => json!({"type": "chinese_compatible"}),
+        "source_code_default" => json!({"type": "source_code"}),
+        name => {
+            let custom = tokenizers
+                .iter()
+                .find(|tokenizer| tokenizer.name == name)
+                .ok_or_else(|| anyhow!("the tokenizer `{name}` is not defined"))?;
+            custom_tokenizer_config(custom)?
+        }
+    })
+}
+
+fn custom_tokenizer_config(tokenizer: &TokenizerMapping) -> Result<Value> {
+    let mut config = match tokenizer.tokenizer_type.as_str() {
+        "simple" => json!({"type": "default"}),
+        "source_code" => json!({"type": "source_code"}),
+        "regex" => {
+            let pattern = tokenizer.pattern.as_ref().ok_or_else(|| {
+                anyhow!("the regex tokenizer `{}` needs a `pattern`", tokenizer.name)
+            })?;
+            json!({"type": "regex", "pattern": pattern})
+        }
+        "ngram" => {
+            let (Some(min_gram), Some(max_gram)) = (tokenizer.min_gram, tokenizer.max_gram) else {
+                bail!(
+                    "the ngram tokenizer `{}` needs a `min_gram` and `max_gram`",
+                    tokenizer.name
+                );
+            };
+            json!({
+                "type": "ngram",
+                "min_gram": min_gram,
+                "max_gram": max_gram,
+                "prefix_only": tokenizer.prefix_only.unwrap_or(false),
+            })
+        }
+        other => bail!(
+            "the tokenizer `{}` is of the Quickwit type `{other}`, which has no equivalent",
+            tokenizer.name
+        ),
+    };
+
+    // a custom tokenizer only lowercases its tokens if it's told to
+    let mut lowercase = false;
+    for filter in &tokenizer.filters {
+        match filter.as_str() {
+            "lower_caser" => lowercase = true,
+            "remove_long" => {
+                config["remove_long"] = json!(255);
+            }
+            other => bail!(
+                "the tokenizer `{}` has the filter `{other}`, which has no equivalent",
+                tokenizer.name
+            ),
+        }
+    }
+    config["lowercase"] = Value::Bool(lowercase);
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn translate(config: Value) -> Result<IndexConfig> {
+        to_index_config(config, "public".into(), "logs".into(), Some("id".into()))
+    }
+
+    #[rstest]
+    fn test_doc_mapping() {
+        let config = translate(json!({
+            "version": "0.8",
+            "index_id": "hdfs-logs",
+            "doc_mapping": {
+                "field_mappings": [
+                    {"name": "id", "type": "u64", "fast": true},
+                    {"name": "timestamp", "type": "datetime", "fast": true, "input_formats": ["unix_timestamp"]},
+                    {"name": "severity_text", "type": "text", "tokenizer": "raw", "fast": {"normalizer": "lowercase"}},
+                    {"name": "body", "type": "text", "tokenizer": "en_stem", "record": "position", "fieldnorms": true},
+                    {"name": "tags", "type": "array<text>", "tokenizer": "raw"},
+                    {"name": "attributes", "type": "json", "tokenizer": "raw", "fast": true},
+                    {"name": "tenant_id", "type": "i64", "indexed": false, "fast": true}
+                ],
+                "timestamp_field": "timestamp"
+            },
+            "search_settings": {"default_search_fields": ["body"]},
+            "retention": {"period": "90 days", "schedule": "daily"}
+        }))
+        .unwrap();
+
+        assert_eq!(config.index_name, "hdfs_logs_idx");
+        assert_eq!(
+            config.columns,
+            vec![
+                "id",
+                "timestamp",
+                "severity_text",
+                "body",
+                "tags",
+                "attributes",
+                "tenant_id"
+            ]
+        );
+        assert_eq!(
+            Value::Object(config.options),
+            json!({
+                "datetime_fields": {"timestamp": {"fast": true}},
+                "text_fields": {
+                    "severity_text": {"fast": true, "normalizer": "lowercase", "tokenizer": {"type": "keyword"}},
+                    "body": {"tokenizer": {"type": "default", "stemmer": "English"}, "record": "position", "fieldnorms": true},
+                    "tags": {"tokenizer": {"type": "keyword"}}
+                },
+                "json_fields": {"attributes": {"fast": true, "tokenizer": {"type": "keyword"}}},
+                "numeric_fields": {"tenant_id": {"indexed": false, "fast": true}},
+                "key_field": "id",
+                "ttl_field": "timestamp",
+                "ttl": "90 days"
+            })
+        );
+    }
+
+    #[rstest]
+    fn test_custom_tokenizers() {
+        let config = translate(json!({
+            "index_id": "logs",
+            "doc_mapping": {
+                "field_mappings": [
+                    {"name": "path", "type": "text", "tokenizer": "path_parts"},
+                    {"name": "host", "type": "text", "tokenizer": "host_grams"}
+                ],
+                "tokenizers": [
+                    {"name": "path_parts", "type": "regex", "pattern": "[^/]+", "filters": ["lower_caser"]},
+                    {"name": "host_grams", "type": "ngram", "min_gram": 2, "max_gram": 3, "filters": ["remove_long"]}
+                ]
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            config.options["text_fields"],
+            json!({
+                "path": {"tokenizer": {"type": "regex", "pattern": "[^/]+", "lowercase": true}},
+                "host": {"tokenizer": {"type": "ngram", "min_gram": 2, "max_gram": 3, "prefix_only": false, "remove_long": 255, "lowercase": false}}
+            })
+        );
+    }
+
+    #[rstest]
+    #[case::unknown_type(
+        json!({"index_id": "logs", "doc_mapping": {"field_mappings": [{"name": "location", "type": "geo"}]}}),
+        "Quickwit type `geo`"
+    )]
+    #[case::undefined_tokenizer(
+        json!({"index_id": "logs", "doc_mapping": {"field_mappings": [{"name": "body", "type": "text", "tokenizer": "mine"}]}}),
+        "`mine` is not defined"
+    )]
+    #[case::unsupported_filter(
+        json!({
+            "index_id": "logs",
+            "doc_mapping": {
+                "field_mappings": [{"name": "body", "type": "text", "tokenizer": "folded"}],
+                "tokenizers": [{"name": "folded", "type": "simple", "filters": ["ascii_folding"]}]
+            }
+        }),
+        "filter `ascii_folding`"
+    )]
+    #[case::retention_without_timestamp(
+        json!({"index_id": "logs", "doc_mapping": {"field_mappings": []}, "retention": {"period": "1 day"}}),
+        "needs a `timestamp_field`"
+    )]
+    #[case::no_doc_mapping(json!({"index_id": "logs"}), "invalid Quickwit index config")]
+    fn test_errors(#[case] config: Value, #[case] message: &str) {
+        let err = translate(config).unwrap_err().to_string();
+        assert!(err.contains(message), "{err}");
+    }
+}
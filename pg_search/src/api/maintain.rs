@@ -0,0 +1,141 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.maintain()`, which does an index's merging and cleanup in the foreground within a
+//! time and I/O budget, so that it can be scheduled with pg_cron instead of being left to
+//! `VACUUM` and the background mergers.
+//!
+//! Every call starts from the index as it is, so running it again, or while another call is
+//! running, is always safe, and a call that runs out of budget is continued by the next one.
+
+use crate::index::mvcc::MvccSatisfies;
+use crate::index::reader::index::SearchIndexReader;
+use crate::postgres::delete::delete_expired;
+use crate::postgres::index::IndexKind;
+use crate::postgres::merge::{maintain as maintain_index, MaintenanceBudget};
+use crate::postgres::rel::PgSearchRelation;
+use crate::query::SearchQueryInput;
+use anyhow::{ensure, Result};
+use pgrx::datum::Interval;
+use pgrx::prelude::*;
+use pgrx::PgRelation;
+use std::time::Duration;
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// Delete `index`'s expired documents, free its unused segments, merge and rewrite segments until
+/// `time_budget` has passed or `io_budget` bytes of segments have been rewritten, and update the
+/// number of documents the planner estimates it has.  A partitioned index's partitions share the
+/// budget.
+///
+/// Returns a row for each index, whose `finished` is whether it was left with nothing more to do.
+#[allow(clippy::type_complexity)]
+#[pg_extern]
+fn maintain(
+    index: PgRelation,
+    time_budget: default!(Interval, "'1 minute'"),
+    io_budget: default!(Option<i64>, "NULL"),
+) -> Result<
+    TableIterator<
+        'static,
+        (
+            name!(index_name, String),
+            name!(merged_segments, i64),
+            name!(rewritten_segments, i64),
+            name!(bytes_rewritten, i64),
+            name!(live_docs, i64),
+            name!(finished, bool),
+        ),
+    >,
+> {
+    // an interval's months are taken to be 30 days, like `justify_days()` does
+    let micros = time_budget.micros()
+        + (time_budget.days() as i64 + time_budget.months() as i64 * 30) * MICROS_PER_DAY;
+    ensure!(micros > 0, "`time_budget` must be positive");
+    ensure!(
+        io_budget.is_none_or(|io_budget| io_budget > 0),
+        "`io_budget` must be positive"
+    );
+
+    let index = {
+        let oid = index.oid();
+        drop(index);
+        // reopen the index with a RowExclusiveLock b/c we are going to be changing its physical structure
+        PgSearchRelation::with_lock(oid, pg_sys::RowExclusiveLock as _)
+    };
+    let index_kind = IndexKind::for_index(index)?;
+
+    let mut budget = MaintenanceBudget::new(
+        Duration::from_micros(micros as u64),
+        io_budget.map(|io_budget| io_budget as u64),
+    );
+    let current_xid = unsafe { pg_sys::GetCurrentTransactionId() };
+    let mut results = Vec::new();
+    for index in index_kind.partitions() {
+        unsafe { delete_expired(&index)? };
+        let result = unsafe { maintain_index(&index, &mut budget, current_xid) };
+        let live_docs = refresh_reltuples(&index)?;
+        results.push((
+            index.name().to_owned(),
+            result.merged_segments as i64,
+            result.rewritten_segments as i64,
+            result.bytes_rewritten as i64,
+            live_docs as i64,
+            result.finished,
+        ));
+    }
+    Ok(TableIterator::new(results))
+}
+
+/// Record the index's size and number of live documents in `pg_class`, which the planner's cost
+/// estimates of BM25 index scans are based on, as `VACUUM` does for other indexes.
+fn refresh_reltuples(index: &PgSearchRelation) -> Result<u64> {
+    let reader =
+        SearchIndexReader::open(index, SearchQueryInput::All, false, MvccSatisfies::Snapshot)?;
+    let live_docs = reader.searcher().num_docs();
+    unsafe {
+        let num_pages = pg_sys::RelationGetNumberOfBlocksInFork(
+            index.as_ptr(),
+            pg_sys::ForkNumber::MAIN_FORKNUM,
+        );
+        #[cfg(feature = "pg14")]
+        pg_sys::vac_update_relstats(
+            index.as_ptr(),
+            num_pages,
+            live_docs as f64,
+            0,
+            false,
+            pg_sys::InvalidTransactionId,
+            pg_sys::InvalidMultiXactId,
+            true,
+        );
+        #[cfg(not(feature = "pg14"))]
+        pg_sys::vac_update_relstats(
+            index.as_ptr(),
+            num_pages,
+            live_docs as f64,
+            0,
+            false,
+            pg_sys::InvalidTransactionId,
+            pg_sys::InvalidMultiXactId,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            true,
+        );
+    }
+    Ok(live_docs)
+}
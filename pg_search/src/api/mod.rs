@@ -20,13 +20,28 @@
 
 mod admin;
 pub mod aggregate;
+pub mod alias;
 pub mod builder_fns;
+mod bulk_index;
+mod check;
 pub mod config;
+mod es_search;
+mod export_parquet;
+mod index_config;
+mod maintain;
+mod multi_search;
 pub mod operator;
+mod publication;
+mod reindex;
+mod rollover;
+mod snapshot;
 pub mod tokenize;
+mod validate_query;
 
+use crate::postgres::rel::PgSearchRelation;
+use anyhow::ensure;
 use pgrx::{
-    direct_function_call, pg_cast, pg_sys, InOutFuncs, IntoDatum, PostgresType, StringInfo,
+    direct_function_call, pg_cast, pg_sys, InOutFuncs, IntoDatum, PostgresType, Spi, StringInfo,
 };
 pub use rustc_hash::FxHashMap as HashMap;
 pub use rustc_hash::FxHashSet as HashSet;
@@ -295,3 +310,61 @@ pub struct OrderByInfo {
     pub feature: OrderByFeature,
     pub direction: SortDirection,
 }
+
+/// Fail unless the current user is a superuser or a member of `role`, like Postgres' own functions
+/// that read and write files on the database server.
+pub(crate) fn ensure_role(role: &str) -> anyhow::Result<()> {
+    let allowed =
+        Spi::get_one_with_args::<bool>("SELECT pg_has_role($1, 'USAGE')", &[role.into()])?;
+    ensure!(
+        allowed.unwrap_or(false),
+        "must be a superuser or a member of `{role}`"
+    );
+    Ok(())
+}
+
+/// Fail unless the current user can `SELECT` from the table `heaprel`.
+pub(crate) fn ensure_select(heaprel: &PgSearchRelation) -> anyhow::Result<()> {
+    let result = unsafe {
+        pg_sys::pg_class_aclcheck(
+            heaprel.oid(),
+            pg_sys::GetUserId(),
+            pg_sys::ACL_SELECT as pg_sys::AclMode,
+        )
+    };
+    ensure!(
+        result == pg_sys::AclResult::ACLCHECK_OK,
+        "permission denied for table `{}`",
+        heaprel.name()
+    );
+    Ok(())
+}
+
+/// Fail unless the current user is a superuser or owns the table `heaprel`.
+pub(crate) fn ensure_owner(heaprel: &PgSearchRelation) -> anyhow::Result<()> {
+    #[cfg(any(feature = "pg14", feature = "pg15"))]
+    let owner = unsafe { pg_sys::pg_class_ownercheck(heaprel.oid(), pg_sys::GetUserId()) };
+    #[cfg(any(feature = "pg16", feature = "pg17"))]
+    let owner = unsafe {
+        pg_sys::object_ownercheck(
+            pg_sys::Oid::from(pg_sys::RelationRelationId),
+            heaprel.oid(),
+            pg_sys::GetUserId(),
+        )
+    };
+    ensure!(owner, "must be owner of table `{}`", heaprel.name());
+    Ok(())
+}
+
+/// Fail if row-level security applies to the current user's reads of the table `heaprel`.  The
+/// index has all of the table's rows, and what's read straight out of it can't be filtered by the
+/// table's policies.
+pub(crate) fn ensure_no_rls(heaprel: &PgSearchRelation) -> anyhow::Result<()> {
+    let rls = unsafe { pg_sys::check_enable_rls(heaprel.oid(), pg_sys::InvalidOid, false) };
+    ensure!(
+        rls != pg_sys::CheckEnableRlsResult::RLS_ENABLED as i32,
+        "table `{}` has row-level security enabled, which this function can't apply",
+        heaprel.name()
+    );
+    Ok(())
+}
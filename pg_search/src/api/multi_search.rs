@@ -0,0 +1,136 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.multi_search()`, which runs one query against several BM25 indexes and returns a
+//! single top-k across all of them.
+//!
+//! Every index is scored with the BM25 statistics of all of the searched indexes summed together,
+//! so that a row's score doesn't depend on which index it happens to live in, and the scores of
+//! different indexes can be compared.
+
+use crate::aggregate::mvcc_collector::MVCCFilterCollector;
+use crate::aggregate::vischeck::TSVisibilityChecker;
+use crate::api::ensure_no_rls;
+use crate::index::fast_fields_helper::FFType;
+use crate::index::mvcc::MvccSatisfies;
+use crate::index::reader::index::SearchIndexReader;
+use crate::index::reader::statistics::PartitionStatistics;
+use crate::postgres::build::is_bm25_index;
+use crate::postgres::index::IndexKind;
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::utils::u64_to_item_pointer;
+use crate::query::SearchQueryInput;
+use anyhow::{ensure, Result};
+use pgrx::prelude::*;
+use tantivy::collector::TopDocs;
+
+/// Search each of `indexes` for `query`, returning the `limit` best scoring rows of all of them.
+///
+/// `indexes` is usually given as a `regclass[]`.  Partitioned indexes are searched across all of
+/// their partitions.  Rows are identified by the `tableoid` and `ctid` of the table they belong to.
+#[allow(clippy::type_complexity)]
+#[pg_extern]
+fn multi_search(
+    indexes: Vec<pg_sys::Oid>,
+    query: SearchQueryInput,
+    limit: default!(i64, 10),
+) -> Result<
+    TableIterator<
+        'static,
+        (
+            name!(tableoid, pg_sys::Oid),
+            name!(ctid, pg_sys::ItemPointerData),
+            name!(score, f32),
+        ),
+    >,
+> {
+    ensure!(limit > 0, "`limit` must be greater than zero, got {limit}");
+    let limit = limit as usize;
+
+    let mut relations = Vec::new();
+    for index in indexes {
+        let index = PgSearchRelation::with_lock(index, pg_sys::AccessShareLock as _);
+        // a partitioned table's policies apply to the rows of all of its partitions
+        if let Some(heaprel) = index.heap_relation() {
+            ensure_no_rls(&heaprel)?;
+        }
+        for partition in IndexKind::for_index(index)?.partitions() {
+            // a sub-partitioned partition's own partitions aren't searched
+            if unsafe { pg_sys::get_rel_relkind(partition.oid()) as u8 != pg_sys::RELKIND_INDEX } {
+                continue;
+            }
+            ensure!(
+                is_bm25_index(&partition),
+                "`{}` is not a BM25 index",
+                partition.name()
+            );
+            relations.push(partition);
+        }
+    }
+    relations.sort_by_key(|index| index.oid());
+    relations.dedup_by_key(|index| index.oid());
+
+    let mut hits = Vec::new();
+    for index in &relations {
+        let Some(heaprel) = index.heap_relation() else {
+            continue;
+        };
+
+        let mut reader =
+            SearchIndexReader::open(index, query.clone(), true, MvccSatisfies::Snapshot)?;
+        let siblings = relations
+            .iter()
+            .filter(|sibling| sibling.oid() != index.oid())
+            .cloned();
+        if let Some(statistics) = PartitionStatistics::with_siblings(reader.searcher(), siblings) {
+            reader = reader.with_statistics(statistics);
+        }
+
+        let vischeck = TSVisibilityChecker::with_rel_and_snap(heaprel.as_ptr(), unsafe {
+            pg_sys::GetActiveSnapshot()
+        });
+        let top_docs = reader.collect(MVCCFilterCollector::new(
+            TopDocs::with_limit(limit),
+            vischeck,
+        ));
+
+        for (score, address) in top_docs {
+            let segment_reader = reader.searcher().segment_reader(address.segment_ord);
+            let ctid = FFType::new_ctid(segment_reader.fast_fields())
+                .as_u64(address.doc_id)
+                .expect("ctid should be present");
+            hits.push((heaprel.oid(), ctid, score));
+        }
+    }
+
+    // the best scores first, with ties broken by where the rows are so the order is stable
+    hits.sort_by(|(a_rel, a_ctid, a_score), (b_rel, b_ctid, b_score)| {
+        b_score
+            .total_cmp(a_score)
+            .then(a_rel.cmp(b_rel))
+            .then(a_ctid.cmp(b_ctid))
+    });
+    hits.truncate(limit);
+
+    Ok(TableIterator::new(hits.into_iter().map(
+        |(tableoid, ctid, score)| {
+            let mut tid = pg_sys::ItemPointerData::default();
+            u64_to_item_pointer(ctid, &mut tid);
+            (tableoid, tid, score)
+        },
+    )))
+}
@@ -36,6 +36,7 @@ use crate::postgres::utils::{locate_bm25_index_from_heaprel, ToPalloc};
 use crate::postgres::var::{find_one_var_and_fieldname, find_var_relation, VarContext};
 use crate::query::pdb_query::pdb;
 use crate::query::proximity::ProximityClause;
+use crate::query::tsquery::TsQuery;
 use crate::query::SearchQueryInput;
 use pgrx::callconv::{BoxRet, FcInfo};
 use pgrx::datum::Datum;
@@ -50,6 +51,7 @@ enum RHSValue {
     TextArray(Vec<String>),
     PdbQuery(pdb::Query),
     ProximityClause(ProximityClause),
+    TsQuery(String),
 }
 
 #[derive(Debug)]
@@ -525,6 +527,13 @@ where
                 RHSValue::ProximityClause(prox)
             }
 
+            // this is specifically used for the `@@@(anyelement, tsquery)` operator
+            pg_sys::TSQUERYOID => RHSValue::TsQuery(
+                TsQuery::from_datum((*const_).constvalue, (*const_).constisnull)
+                    .expect("rhs tsquery value must not be NULL")
+                    .0,
+            ),
+
             other => panic!("operator does not support rhs type {other}"),
         };
 
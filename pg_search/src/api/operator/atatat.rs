@@ -19,8 +19,12 @@ use crate::api::builder_fns::{parse, parse_with_field, proximity};
 use crate::api::operator::{
     get_expr_result_type, request_simplify, searchqueryinput_typoid, RHSValue, ReturnedNodePointer,
 };
+use crate::api::FieldName;
+use crate::nodecast;
+use crate::postgres::var::find_var_relation;
 use crate::query::pdb_query::{pdb, to_search_query_input};
 use crate::query::proximity::ProximityClause;
+use crate::query::tsquery::{self, TsQuery, TsVectorField};
 use pgrx::{
     direct_function_call, extension_sql, opname, pg_extern, pg_operator, pg_sys, AnyElement,
     Internal, IntoDatum, PgList,
@@ -49,6 +53,19 @@ pub fn search_with_proximity_clause(_element: AnyElement, query: ProximityClause
     panic!("query is incompatible with pg_search's `@@@(field, pdb.ProximityClause)` operator: `{query:?}`")
 }
 
+/// This is the function behind the `@@@(anyelement, tsquery)` operator, whose left-hand side is a
+/// field of the index, or a generated `tsvector` column whose `tsquery`s are searched for in the
+/// fields it's made from.  Like `@@@(anyelement, text)`, it's always rewritten to use
+/// `@@@(anyelement, searchqueryinput)`.
+#[pg_operator(immutable, parallel_safe, cost = 1000000000)]
+#[opname(pg_catalog.@@@)]
+pub fn search_with_tsquery(_element: AnyElement, query: TsQuery) -> bool {
+    panic!(
+        "query is incompatible with pg_search's `@@@(field, tsquery)` operator: `{}`",
+        query.0
+    )
+}
+
 #[pg_extern(immutable, parallel_safe)]
 pub fn atatat_support(arg: Internal) -> ReturnedNodePointer {
     unsafe {
@@ -159,6 +176,124 @@ pub fn atatat_support(arg: Internal) -> ReturnedNodePointer {
     }
 }
 
+#[pg_extern(immutable, parallel_safe)]
+pub fn atatat_tsquery_support(arg: Internal) -> ReturnedNodePointer {
+    unsafe {
+        let arg = arg.unwrap().unwrap().cast_mut_ptr::<pg_sys::Node>();
+        let tsvector_fields = tsvector_fields_of_lhs(arg);
+        let fields = |field: Option<FieldName>| match &tsvector_fields {
+            Some(fields) => fields.clone(),
+            None => vec![TsVectorField {
+                field: field.expect(
+                    "The left-hand side of the `@@@(field, tsquery)` operator must be a field.",
+                ),
+                weight: None,
+            }],
+        };
+
+        request_simplify(
+            arg,
+            |field, query_value| match query_value {
+                RHSValue::TsQuery(query) => tsquery::to_search_query_input(&query, &fields(field))
+                    .unwrap_or_else(|err| panic!("{err}")),
+                _ => {
+                    unreachable!("atatat_tsquery_support should only ever be called with a tsquery")
+                }
+            },
+            |field, rhs| {
+                assert!(
+                    get_expr_result_type(rhs) == pg_sys::TSQUERYOID,
+                    "The right-hand side of the `@@@(field, tsquery)` operator must be a tsquery"
+                );
+
+                // here we call the `paradedb.tsquery(text[], tsquery, text)` function
+                let fields = fields(field);
+                let weights = fields
+                    .iter()
+                    .map(|field| field.weight)
+                    .collect::<Option<String>>();
+                let fields = fields
+                    .into_iter()
+                    .map(|field| field.field.into_inner())
+                    .collect::<Vec<_>>();
+                let mut args = PgList::<pg_sys::Node>::new();
+                args.push(
+                    pg_sys::makeConst(
+                        pg_sys::TEXTARRAYOID,
+                        -1,
+                        pg_sys::DEFAULT_COLLATION_OID,
+                        -1,
+                        fields.into_datum().unwrap(),
+                        false,
+                        false,
+                    )
+                    .cast(),
+                );
+                args.push(rhs.cast());
+                args.push(
+                    pg_sys::makeConst(
+                        pg_sys::TEXTOID,
+                        -1,
+                        pg_sys::DEFAULT_COLLATION_OID,
+                        -1,
+                        weights
+                            .clone()
+                            .into_datum()
+                            .unwrap_or(pg_sys::Datum::from(0)),
+                        weights.is_none(),
+                        false,
+                    )
+                    .cast(),
+                );
+
+                pg_sys::FuncExpr {
+                    xpr: pg_sys::Expr {
+                        type_: pg_sys::NodeTag::T_FuncExpr,
+                    },
+                    funcid: direct_function_call::<pg_sys::Oid>(
+                        pg_sys::regprocedurein,
+                        &[c"paradedb.tsquery(text[], tsquery, text)".into_datum()],
+                    )
+                    .expect("`paradedb.tsquery(text[], tsquery, text)` should exist"),
+                    funcresulttype: searchqueryinput_typoid(),
+                    funcretset: false,
+                    funcvariadic: false,
+                    funcformat: pg_sys::CoercionForm::COERCE_EXPLICIT_CALL,
+                    funccollid: pg_sys::Oid::INVALID,
+                    inputcollid: pg_sys::Oid::INVALID,
+                    args: args.into_pg(),
+                    location: -1,
+                }
+            },
+        )
+        .unwrap_or(ReturnedNodePointer(None))
+    }
+}
+
+/// The fields that the generated `tsvector` column on the left-hand side of `@@@` is made from,
+/// or [`None`] if the left-hand side isn't a `tsvector`.
+unsafe fn tsvector_fields_of_lhs(arg: *mut pg_sys::Node) -> Option<Vec<TsVectorField>> {
+    let srs = nodecast!(SupportRequestSimplify, T_SupportRequestSimplify, arg)?;
+    if (*srs).root.is_null() {
+        return None;
+    }
+    let args = PgList::<pg_sys::Node>::from_pg((*(*srs).fcall).args);
+    let var = nodecast!(Var, T_Var, args.get_ptr(0)?)?;
+    if (*var).vartype != pg_sys::TSVECTOROID {
+        return None;
+    }
+    let (heaprelid, attno, _) = find_var_relation(var, (*srs).root);
+    Some(tsquery::tsvector_fields(heaprelid, attno).unwrap_or_else(|err| panic!("{err}")))
+}
+
+extension_sql!(
+    r#"
+        ALTER FUNCTION paradedb.search_with_tsquery SUPPORT paradedb.atatat_tsquery_support;
+    "#,
+    name = "atatat_tsquery_support_fn",
+    requires = [search_with_tsquery, atatat_tsquery_support]
+);
+
 extension_sql!(
     r#"
         ALTER FUNCTION paradedb.search_with_parse SUPPORT paradedb.atatat_support;
@@ -25,7 +25,7 @@ use crate::postgres::rel::PgSearchRelation;
 use crate::postgres::types::TantivyValue;
 use crate::postgres::utils::locate_bm25_index;
 use crate::query::SearchQueryInput;
-use crate::{nodecast, UNKNOWN_SELECTIVITY};
+use crate::{nodecast, PARAMETERIZED_SELECTIVITY, UNKNOWN_SELECTIVITY};
 use pgrx::callconv::{Arg, ArgAbi};
 use pgrx::pgrx_sql_entity_graph::metadata::{
     ArgumentError, Returns, ReturnsError, SqlMapping, SqlTranslatable,
@@ -274,11 +274,16 @@ pub fn query_input_restrict(
                 PgList::<pg_sys::Node>::from_pg(args.unwrap()?.cast_mut_ptr::<pg_sys::List>());
 
             let var = nodecast!(Var, T_Var, args.get_ptr(0)?)?;
-            let const_ = nodecast!(Const, T_Const, args.get_ptr(1)?)?;
-
             let (heaprelid, _, _) = find_var_relation(var, info);
             let indexrel = locate_bm25_index(heaprelid)?;
 
+            // the query of a generic plan is a parameter, which we can't look at.  guess the same
+            // as the custom scan does, rather than `UNKNOWN_SELECTIVITY`, which would claim that
+            // next to nothing matches and put the scan at the outside of every nested loop
+            let Some(const_) = nodecast!(Const, T_Const, args.get_ptr(1)?) else {
+                return Some(PARAMETERIZED_SELECTIVITY);
+            };
+
             // create the search query from the rhs Const node
             let search_query_input =
                 SearchQueryInput::from_datum((*const_).constvalue, (*const_).constisnull)?;
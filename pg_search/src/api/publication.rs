@@ -0,0 +1,157 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Logical replication of BM25 index definitions.
+//!
+//! Logical replication doesn't replicate DDL, so on its own, a subscriber has none of the
+//! publisher's BM25 indexes.  `paradedb.publish_index_definitions()` installs event triggers that
+//! keep the `paradedb.index_definitions` table in step with the `CREATE INDEX` statement of
+//! every BM25 index, and adds the table to a publication.  On a subscriber, which has the same
+//! table, a trigger that only fires for replicated changes creates, recreates, and drops the
+//! indexes to match.
+
+use anyhow::{ensure, Result};
+use pgrx::prelude::*;
+
+extension_sql!(
+    r#"
+CREATE TABLE paradedb.index_definitions (
+    -- the schema-qualified name of the index
+    index_name TEXT PRIMARY KEY,
+    -- the `CREATE INDEX` statement that recreates it
+    definition TEXT NOT NULL
+);
+SELECT pg_catalog.pg_extension_config_dump('paradedb.index_definitions', '');
+
+CREATE FUNCTION paradedb.capture_index_definitions() RETURNS event_trigger
+LANGUAGE plpgsql SECURITY DEFINER SET search_path = pg_catalog, pg_temp AS $$
+DECLARE
+    command record;
+BEGIN
+    -- a renamed index is recorded under its new name
+    DELETE FROM paradedb.index_definitions WHERE to_regclass(index_name) IS NULL;
+
+    FOR command IN
+        SELECT objid, object_identity FROM pg_event_trigger_ddl_commands()
+        WHERE object_type = 'index'
+    LOOP
+        IF EXISTS (
+            SELECT 1 FROM pg_class c JOIN pg_am am ON am.oid = c.relam
+            WHERE c.oid = command.objid AND am.amname = 'bm25'
+        ) THEN
+            INSERT INTO paradedb.index_definitions (index_name, definition)
+            VALUES (command.object_identity, pg_get_indexdef(command.objid))
+            ON CONFLICT (index_name) DO UPDATE SET definition = EXCLUDED.definition
+            WHERE index_definitions.definition IS DISTINCT FROM EXCLUDED.definition;
+        END IF;
+    END LOOP;
+END;
+$$;
+
+CREATE FUNCTION paradedb.capture_dropped_indexes() RETURNS event_trigger
+LANGUAGE plpgsql SECURITY DEFINER SET search_path = pg_catalog, pg_temp AS $$
+BEGIN
+    DELETE FROM paradedb.index_definitions
+    WHERE index_name IN (
+        SELECT object_identity FROM pg_event_trigger_dropped_objects()
+        WHERE object_type = 'index'
+    );
+END;
+$$;
+
+CREATE FUNCTION paradedb.apply_index_definition() RETURNS trigger
+LANGUAGE plpgsql SET search_path = pg_catalog, pg_temp AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        EXECUTE format('DROP INDEX IF EXISTS %s', OLD.index_name);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') AND to_regclass(NEW.index_name) IS NULL THEN
+        EXECUTE NEW.definition;
+    END IF;
+    RETURN NULL;
+END;
+$$;
+
+-- only fires for changes applied by logical replication
+CREATE TRIGGER apply_index_definition
+AFTER INSERT OR UPDATE OR DELETE ON paradedb.index_definitions
+FOR EACH ROW EXECUTE FUNCTION paradedb.apply_index_definition();
+ALTER TABLE paradedb.index_definitions ENABLE REPLICA TRIGGER apply_index_definition;
+"#,
+    name = "index_definitions"
+);
+
+/// Start recording the definition of every BM25 index in this database in
+/// `paradedb.index_definitions`, and add that table to `publication`, so that subscribers create
+/// the same indexes.  It's safe to call more than once.
+#[pg_extern]
+fn publish_index_definitions(publication: &str) -> Result<()> {
+    ensure!(
+        unsafe { pg_sys::superuser() },
+        "must be a superuser to publish index definitions"
+    );
+
+    // the event triggers belong to the extension so that they're dropped along with it
+    let installed = Spi::get_one::<bool>(
+        "SELECT count(*) = 2 FROM pg_event_trigger
+         WHERE evtname IN ('paradedb_capture_index_definitions', 'paradedb_capture_dropped_indexes')",
+    )?
+    .unwrap_or(false);
+    if !installed {
+        Spi::run(
+            "CREATE EVENT TRIGGER paradedb_capture_index_definitions ON ddl_command_end
+             WHEN TAG IN ('CREATE INDEX', 'ALTER INDEX')
+             EXECUTE FUNCTION paradedb.capture_index_definitions();
+             CREATE EVENT TRIGGER paradedb_capture_dropped_indexes ON sql_drop
+             EXECUTE FUNCTION paradedb.capture_dropped_indexes();
+             ALTER EXTENSION pg_search ADD EVENT TRIGGER paradedb_capture_index_definitions;
+             ALTER EXTENSION pg_search ADD EVENT TRIGGER paradedb_capture_dropped_indexes;",
+        )?;
+    }
+
+    // the indexes that already exist
+    Spi::run(
+        "INSERT INTO paradedb.index_definitions (index_name, definition)
+         SELECT format('%I.%I', n.nspname, c.relname), pg_get_indexdef(c.oid)
+         FROM pg_class c
+         JOIN pg_namespace n ON n.oid = c.relnamespace
+         JOIN pg_am am ON am.oid = c.relam
+         JOIN pg_index i ON i.indexrelid = c.oid
+         WHERE am.amname = 'bm25' AND c.relkind IN ('i', 'I') AND NOT c.relispartition
+         ON CONFLICT (index_name) DO UPDATE SET definition = EXCLUDED.definition
+         WHERE index_definitions.definition IS DISTINCT FROM EXCLUDED.definition",
+    )?;
+
+    let published = Spi::get_one_with_args::<bool>(
+        "SELECT EXISTS (
+             SELECT 1 FROM pg_publication_tables
+             WHERE pubname = $1 AND schemaname = 'paradedb' AND tablename = 'index_definitions'
+         )",
+        &[publication.into()],
+    )?
+    .unwrap_or(false);
+    if !published {
+        let sql = Spi::get_one_with_args::<String>(
+            "SELECT format('ALTER PUBLICATION %I ADD TABLE paradedb.index_definitions', $1)",
+            &[publication.into()],
+        )?
+        .expect("format() should not return NULL");
+        Spi::run(&sql)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,195 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.reindex_where()`, which rebuilds the documents of only the rows matching a
+//! predicate, rather than the whole index.
+//!
+//! The new documents are written before the old ones are deleted.  Documents aren't tied to the
+//! transaction that wrote them, so if the transaction aborts or the server crashes in between,
+//! a row can be left indexed twice, but never not at all.
+
+use crate::api::{HashMap, HashSet};
+use crate::gucs;
+use crate::index::writer::index::{IndexWriterConfig, SerialIndexWriter};
+use crate::postgres::delete::bulk_delete;
+use crate::postgres::index::IndexKind;
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::utils::{item_pointer_to_u64, row_to_search_document, u64_to_item_pointer};
+use anyhow::{bail, Result};
+use pgrx::prelude::*;
+use pgrx::{check_for_interrupts, PgRelation};
+use std::mem::{offset_of, size_of};
+use tantivy::TantivyDocument;
+
+/// The most tuples that fit on a heap page, as Postgres' `MaxHeapTuplesPerPage`.
+const MAX_HEAP_TUPLES_PER_PAGE: usize = (pg_sys::BLCKSZ as usize
+    - offset_of!(pg_sys::PageHeaderData, pd_linp))
+    / (pg_sys::MAXALIGN(offset_of!(pg_sys::HeapTupleHeaderData, t_bits))
+        + size_of::<pg_sys::ItemIdData>());
+
+/// Re-tokenize and replace the documents of the rows of `index`'s table that match the SQL
+/// `predicate`, returning how many were reindexed.
+///
+/// Writes to the table and reads of the index wait until the transaction ends, as for `REINDEX`.
+#[pg_extern]
+fn reindex_where(index: PgRelation, predicate: &str) -> Result<i64> {
+    let index = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessExclusiveLock as _);
+    let mut nreindexed = 0;
+    for index in IndexKind::for_index(index)?.partitions() {
+        let Some(heaprel) = index.heap_relation() else {
+            continue;
+        };
+        unsafe {
+            pg_sys::LockRelationOid(heaprel.oid(), pg_sys::ShareLock as _);
+            pg_sys::LockRelationOid(index.oid(), pg_sys::AccessExclusiveLock as _);
+            nreindexed += reindex_rows(&heaprel, &index, predicate)?;
+        }
+    }
+    Ok(nreindexed as i64)
+}
+
+unsafe fn reindex_rows(
+    heaprel: &PgSearchRelation,
+    indexrel: &PgSearchRelation,
+    predicate: &str,
+) -> Result<usize> {
+    if (*heaprel.as_ptr()).rd_tableam != pg_sys::GetHeapamTableAmRoutine() {
+        bail!("`{}` is not a heap table", heaprel.name());
+    }
+
+    let query = format!(
+        "SELECT ctid FROM {}.{} WHERE {predicate}",
+        pgrx::spi::quote_identifier(heaprel.namespace()),
+        pgrx::spi::quote_identifier(heaprel.name()),
+    );
+    let ctids = Spi::connect(|client| {
+        client
+            .select(&query, None, &[])?
+            .map(|row| row.get::<pg_sys::ItemPointerData>(1))
+            .collect::<Result<Vec<_>, _>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    if ctids.is_empty() {
+        return Ok(0);
+    }
+
+    // the documents of heap-only tuples are indexed by the ctid at the root of their HOT chain
+    let root_ctids = root_ctids(heaprel, &ctids);
+    let replaced = root_ctids.values().copied().collect::<HashSet<_>>();
+
+    bulk_delete(
+        indexrel,
+        |ctid| replaced.contains(&ctid),
+        || index_rows(heaprel, indexrel, &root_ctids),
+    )?;
+    Ok(ctids.len())
+}
+
+/// The root ctid of the HOT chain that each of `ctids` belongs to.
+unsafe fn root_ctids(
+    heaprel: &PgSearchRelation,
+    ctids: &[pg_sys::ItemPointerData],
+) -> HashMap<u64, u64> {
+    let mut root_ctids = HashMap::default();
+    let mut root_offsets = [pg_sys::InvalidOffsetNumber; MAX_HEAP_TUPLES_PER_PAGE];
+    let mut current_block = pg_sys::InvalidBlockNumber;
+
+    let mut ctids = ctids.to_vec();
+    ctids.sort_by_key(|ctid| item_pointer_to_u64(*ctid));
+    for ctid in ctids {
+        let (blockno, offno) = pgrx::itemptr::item_pointer_get_both(ctid);
+        if blockno != current_block {
+            check_for_interrupts!();
+            let buffer = pg_sys::ReadBuffer(heaprel.as_ptr(), blockno);
+            pg_sys::LockBuffer(buffer, pg_sys::BUFFER_LOCK_SHARE as _);
+            pg_sys::heap_get_root_tuples(pg_sys::BufferGetPage(buffer), root_offsets.as_mut_ptr());
+            pg_sys::UnlockReleaseBuffer(buffer);
+            current_block = blockno;
+        }
+
+        let mut root_ctid = ctid;
+        let root_offno = root_offsets[offno as usize - 1];
+        if root_offno != pg_sys::InvalidOffsetNumber {
+            pgrx::itemptr::item_pointer_set_all(&mut root_ctid, blockno, root_offno);
+        }
+        root_ctids.insert(item_pointer_to_u64(ctid), item_pointer_to_u64(root_ctid));
+    }
+    root_ctids
+}
+
+/// Write new documents for the rows at the keys of `root_ctids`, indexed by their values.
+unsafe fn index_rows(
+    heaprel: &PgSearchRelation,
+    indexrel: &PgSearchRelation,
+    root_ctids: &HashMap<u64, u64>,
+) -> Result<()> {
+    let config = IndexWriterConfig {
+        memory_budget: gucs::adjust_maintenance_work_mem(1),
+        max_docs_per_segment: None,
+    };
+    let mut writer = SerialIndexWriter::open(indexrel, config, Default::default())?;
+    let categorized_fields = writer.schema().categorized_fields().clone();
+    let key_field_name = writer.schema().key_field_name();
+
+    let index_info = pg_sys::BuildIndexInfo(indexrel.as_ptr());
+    let estate = pg_sys::CreateExecutorState();
+    let econtext = pg_sys::MakePerTupleExprContext(estate);
+    let slot = pg_sys::table_slot_create(heaprel.as_ptr(), std::ptr::null_mut());
+    (*econtext).ecxt_scantuple = slot;
+
+    let snapshot = pg_sys::GetActiveSnapshot();
+    let fetch_row_version = (*(*heaprel.as_ptr()).rd_tableam)
+        .tuple_fetch_row_version
+        .expect("table access method should be able to fetch tuples");
+    let mut values = [pg_sys::Datum::null(); pg_sys::INDEX_MAX_KEYS as usize];
+    let mut isnull = [false; pg_sys::INDEX_MAX_KEYS as usize];
+
+    for (&ctid, &root_ctid) in root_ctids {
+        check_for_interrupts!();
+        let mut tid = pg_sys::ItemPointerData::default();
+        u64_to_item_pointer(ctid, &mut tid);
+        if !fetch_row_version(heaprel.as_ptr(), &mut tid, snapshot, slot) {
+            continue;
+        }
+
+        pg_sys::MemoryContextReset((*econtext).ecxt_per_tuple_memory);
+        pg_sys::FormIndexDatum(
+            index_info,
+            slot,
+            estate,
+            values.as_mut_ptr(),
+            isnull.as_mut_ptr(),
+        );
+
+        let mut document = TantivyDocument::new();
+        row_to_search_document(
+            values.as_mut_ptr(),
+            isnull.as_mut_ptr(),
+            &key_field_name,
+            &categorized_fields,
+            &mut document,
+        )?;
+        writer.insert(document, root_ctid, || {})?;
+    }
+
+    pg_sys::ExecDropSingleTupleTableSlot(slot);
+    pg_sys::FreeExecutorState(estate);
+    writer.commit()?;
+    Ok(())
+}
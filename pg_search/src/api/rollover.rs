@@ -0,0 +1,122 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.rollover()`, which moves an index alias on to a fresh table once the table it
+//! points at has grown too large or too old.
+//!
+//! This is meant for log-style ingestion, where new rows are written to the table behind an alias
+//! and old tables are dropped wholesale once they're no longer needed.  The new table is a copy of
+//! the old one's definition, indexes included, and inherits from the same parents, so a query of
+//! the parent keeps seeing every generation.
+
+use crate::api::alias::repoint_alias;
+use crate::postgres::utils::locate_bm25_index;
+use anyhow::{anyhow, ensure, Result};
+use pgrx::datum::Interval;
+use pgrx::prelude::*;
+
+/// If the index that `alias` points at is at least `max_size` bytes, or the alias was created or
+/// last rolled over at least `max_age` ago, create the next generation of its table and point
+/// `alias` at that table's BM25 index.
+///
+/// Returns the name of the new index, or `NULL` if neither threshold was reached.
+#[pg_extern]
+fn rollover(
+    alias: &str,
+    max_size: default!(Option<i64>, "NULL"),
+    max_age: default!(Option<Interval>, "NULL"),
+) -> Result<Option<String>> {
+    ensure!(
+        max_size.is_some() || max_age.is_some(),
+        "at least one of `max_size` and `max_age` must be given"
+    );
+
+    let (heaprelid, too_big, too_old) = Spi::get_three_with_args::<pg_sys::Oid, bool, bool>(
+        "SELECT i.indrelid,
+                coalesce(pg_relation_size(a.indexrelid) >= $2, false),
+                coalesce(now() - a.updated_at >= $3, false)
+         FROM paradedb.index_aliases a
+         JOIN pg_catalog.pg_index i ON i.indexrelid = a.indexrelid
+         WHERE a.alias = $1
+         FOR UPDATE OF a",
+        &[alias.into(), max_size.into(), max_age.into()],
+    )?;
+    let heaprelid = heaprelid
+        .ok_or_else(|| anyhow!("alias `{alias}` does not exist or its index was dropped"))?;
+    if !too_big.unwrap_or(false) && !too_old.unwrap_or(false) {
+        return Ok(None);
+    }
+
+    let (schema, table, is_partition) = Spi::get_three_with_args::<String, String, bool>(
+        "SELECT n.nspname::text, c.relname::text, c.relispartition
+         FROM pg_catalog.pg_class c
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+         WHERE c.oid = $1",
+        &[heaprelid.into()],
+    )?;
+    let schema = schema.expect("table should have a schema");
+    let table = table.expect("table should have a name");
+    ensure!(
+        !is_partition.unwrap_or(false),
+        "`{table}` is a declarative partition, which `paradedb.rollover()` doesn't manage"
+    );
+
+    let next = next_generation(&table);
+    let ddl = Spi::get_one_with_args::<String>(
+        "SELECT format('CREATE TABLE %I.%I (LIKE %I.%I INCLUDING ALL)', $1, $2, $1, $3)
+                || coalesce(
+                       ' INHERITS (' || (
+                           SELECT string_agg(inhparent::regclass::text, ', ' ORDER BY inhseqno)
+                           FROM pg_catalog.pg_inherits
+                           WHERE inhrelid = $4
+                       ) || ')',
+                       ''
+                   )",
+        &[
+            schema.as_str().into(),
+            next.as_str().into(),
+            table.as_str().into(),
+            heaprelid.into(),
+        ],
+    )?
+    .expect("DDL should not be NULL");
+    Spi::run(&ddl)?;
+
+    let next_relid = Spi::get_one_with_args::<pg_sys::Oid>(
+        "SELECT format('%I.%I', $1, $2)::regclass::oid",
+        &[schema.as_str().into(), next.as_str().into()],
+    )?
+    .expect("new table should exist");
+    let index = locate_bm25_index(next_relid)
+        .ok_or_else(|| anyhow!("`{next}` was created without a BM25 index"))?;
+    repoint_alias(alias, index.oid())?;
+
+    Ok(Some(index.name().to_string()))
+}
+
+/// The name of the table after `table`: a trailing `_<digits>` is incremented, keeping its
+/// width, and anything else is treated as the first generation.
+fn next_generation(table: &str) -> String {
+    if let Some((stem, digits)) = table.rsplit_once('_') {
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            if let Ok(generation) = digits.parse::<u64>() {
+                return format!("{stem}_{:0width$}", generation + 1, width = digits.len());
+            }
+        }
+    }
+    format!("{table}_000002")
+}
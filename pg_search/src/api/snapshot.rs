@@ -0,0 +1,289 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.snapshot_index()` and `paradedb.restore_index()`, which copy a BM25 index's segment
+//! files out to a directory on the database server and back in again.
+//!
+//! A snapshot is the set of segments visible to the snapshotting transaction, so it's consistent
+//! without blocking writers, plus a `manifest.json` that records which index it was taken of, its
+//! schema, and each segment's files.  Restoring replaces all of an index's segments with the
+//! snapshot's, under new segment ids so that a snapshot can be restored more than once.
+//!
+//! Documents find their rows by `ctid`, so a snapshot is only useful for a table whose rows are
+//! where they were when it was taken, such as a physical copy of the database.
+
+use crate::api::{ensure_owner, ensure_role, ensure_select};
+use crate::index::directory::mvcc::BUFWRITER_CAPACITY;
+use crate::index::mvcc::MvccSatisfies;
+use crate::postgres::build::is_bm25_index;
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::storage::block::SegmentFileDetails;
+use anyhow::{anyhow, ensure, Context, Result};
+use pgrx::prelude::*;
+use pgrx::PgRelation;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use tantivy::directory::TerminatingWrite;
+use tantivy::index::SegmentId;
+use tantivy::{Directory, HasLen, Index, IndexMeta};
+
+const MANIFEST: &str = "manifest.json";
+const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    schema_name: String,
+    index_name: String,
+    indexdef: String,
+    schema: serde_json::Value,
+    segments: Vec<SnapshotSegment>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotSegment {
+    segment_id: String,
+    max_doc: u32,
+    num_deleted_docs: Option<u32>,
+    files: Vec<String>,
+}
+
+/// The part of the name of the `file` of the segment `segment_id` that follows the segment id, like
+/// `.idx`.  A manifest's names are joined to the snapshot's directory, so only the names of the
+/// files that tantivy writes, which can't lead outside of it, are allowed.
+fn segment_file_suffix<'a>(segment_id: &str, file: &'a str) -> Result<&'a str> {
+    ensure!(
+        SegmentId::from_uuid_string(segment_id).is_ok(),
+        "`{segment_id}` is not a segment id"
+    );
+    let suffix = file
+        .strip_prefix(segment_id)
+        .ok_or_else(|| anyhow!("`{file}` does not belong to its segment"))?;
+    ensure!(
+        suffix.starts_with('.')
+            && !file.contains(['/', '\\'])
+            && !file.contains("..")
+            && Path::new(file).component_type().is_some(),
+        "`{file}` is not the name of a segment file"
+    );
+    Ok(suffix)
+}
+
+fn tantivy_schema_json(index: &PgSearchRelation) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(index.schema()?.tantivy_schema())?)
+}
+
+/// Copy the segments of `index` that are visible to this transaction into the directory `path`
+/// on the database server, which must be empty or not exist yet.  Returns the number of segments
+/// copied.
+#[pg_extern]
+fn snapshot_index(index: PgRelation, path: &str) -> Result<i64> {
+    ensure_role("pg_write_server_files")?;
+    let index = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _);
+    ensure!(
+        is_bm25_index(&index)
+            && unsafe { pg_sys::get_rel_relkind(index.oid()) as u8 == pg_sys::RELKIND_INDEX },
+        "`{}` is not a BM25 index",
+        index.name()
+    );
+    // the segments hold the table's indexed and stored values
+    let heaprel = index
+        .heap_relation()
+        .ok_or_else(|| anyhow!("`{}` does not belong to a table", index.name()))?;
+    ensure_select(&heaprel)?;
+
+    let path = Path::new(path);
+    ensure!(
+        path.is_absolute(),
+        "`{}` is not an absolute path",
+        path.display()
+    );
+    std::fs::create_dir_all(path)
+        .with_context(|| format!("could not create `{}`", path.display()))?;
+    ensure!(
+        std::fs::read_dir(path)?.next().is_none(),
+        "`{}` is not empty",
+        path.display()
+    );
+
+    // opening the index loads, and pins, the segments this transaction can see
+    let directory = MvccSatisfies::Snapshot.directory(&index);
+    let meta = Index::open(directory.clone())?.load_metas()?;
+
+    let entries = directory.all_entries();
+    let mut segments = Vec::with_capacity(meta.segments.len());
+    for segment in &meta.segments {
+        let entry = entries
+            .get(&segment.id())
+            .ok_or_else(|| anyhow!("segment {} has no metadata", segment.id()))?;
+
+        let mut files = Vec::new();
+        for component in entry.get_component_paths() {
+            check_for_interrupts!();
+            let handle = directory.get_file_handle(&component)?;
+            let mut file = BufWriter::new(File::create(path.join(&component))?);
+            let mut offset = 0;
+            while offset < handle.len() {
+                let end = handle.len().min(offset + BUFWRITER_CAPACITY);
+                file.write_all(handle.read_bytes(offset..end)?.as_slice())?;
+                offset = end;
+            }
+            file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+            files.push(component.display().to_string());
+        }
+
+        segments.push(SnapshotSegment {
+            segment_id: segment.id().uuid_string(),
+            max_doc: segment.max_doc(),
+            num_deleted_docs: segment.has_deletes().then(|| segment.num_deleted_docs()),
+            files,
+        });
+    }
+
+    let manifest = Manifest {
+        version: MANIFEST_VERSION,
+        schema_name: index.namespace().to_string(),
+        index_name: index.name().to_string(),
+        indexdef: Spi::get_one_with_args::<String>(
+            "SELECT pg_get_indexdef($1)",
+            &[index.oid().into()],
+        )?
+        .unwrap_or_default(),
+        schema: tantivy_schema_json(&index)?,
+        segments,
+    };
+    let mut file = File::create(path.join(MANIFEST))?;
+    serde_json::to_writer_pretty(&mut file, &manifest)?;
+    file.sync_all()?;
+
+    Ok(manifest.segments.len() as i64)
+}
+
+/// Replace all of the segments of the index that the snapshot in the directory `path` was taken
+/// of with the snapshot's segments, returning the name of the index.
+///
+/// The index must already exist, with the same fields and options as when the snapshot was taken,
+/// and belong to a table the current user owns.  It's locked against reads and writes until the
+/// transaction ends.
+#[pg_extern]
+fn restore_index(path: &str) -> Result<String> {
+    ensure_role("pg_read_server_files")?;
+    let path = Path::new(path);
+    ensure!(
+        path.is_absolute(),
+        "`{}` is not an absolute path",
+        path.display()
+    );
+    let manifest: Manifest = serde_json::from_reader(
+        File::open(path.join(MANIFEST))
+            .with_context(|| format!("`{}` does not contain a snapshot", path.display()))?,
+    )?;
+    ensure!(
+        manifest.version == MANIFEST_VERSION,
+        "snapshot version {} is not supported",
+        manifest.version
+    );
+    // saving a list of no segments doesn't remove the old ones
+    ensure!(
+        !manifest.segments.is_empty(),
+        "the snapshot has no segments to restore"
+    );
+
+    let indexrelid = Spi::get_one_with_args::<pg_sys::Oid>(
+        "SELECT to_regclass(format('%I.%I', $1, $2))::oid",
+        &[
+            manifest.schema_name.as_str().into(),
+            manifest.index_name.as_str().into(),
+        ],
+    )?
+    .ok_or_else(|| {
+        anyhow!(
+            "index `{}.{}` does not exist, create it with: {}",
+            manifest.schema_name,
+            manifest.index_name,
+            manifest.indexdef
+        )
+    })?;
+    // checked before locking, so that a caller who doesn't own the table can't block its readers
+    // and writers
+    let heaprelid = unsafe { pg_sys::IndexGetRelation(indexrelid, true) };
+    ensure!(
+        heaprelid != pg_sys::InvalidOid,
+        "`{}.{}` is not an index",
+        manifest.schema_name,
+        manifest.index_name
+    );
+    ensure_owner(&PgSearchRelation::with_lock(
+        heaprelid,
+        pg_sys::AccessShareLock as _,
+    ))?;
+    let index = PgSearchRelation::with_lock(indexrelid, pg_sys::AccessExclusiveLock as _);
+    ensure!(
+        is_bm25_index(&index),
+        "`{}` is not a BM25 index",
+        index.name()
+    );
+    ensure!(
+        tantivy_schema_json(&index)? == manifest.schema,
+        "the fields of `{}` don't match the snapshot's, which was taken of: {}",
+        index.name(),
+        manifest.indexdef
+    );
+
+    // every segment that isn't already on its way out is replaced
+    let directory = MvccSatisfies::Vacuum.directory(&index);
+    let tantivy_index = Index::open(directory.clone())?;
+    let previous_meta = tantivy_index.load_metas()?;
+
+    let mut segments = Vec::with_capacity(manifest.segments.len());
+    for segment in &manifest.segments {
+        let new_id = SegmentId::generate_random();
+        for file in &segment.files {
+            check_for_interrupts!();
+            let suffix = segment_file_suffix(&segment.segment_id, file)?;
+            let mut source =
+                File::open(path.join(file)).with_context(|| format!("could not open `{file}`"))?;
+            let mut writer = directory
+                .open_write(&PathBuf::from(format!("{}{suffix}", new_id.uuid_string())))?;
+            let mut buffer = vec![0; BUFWRITER_CAPACITY];
+            loop {
+                let nread = source.read(&mut buffer)?;
+                if nread == 0 {
+                    break;
+                }
+                writer.write_all(&buffer[..nread])?;
+            }
+            writer.terminate()?;
+        }
+
+        let mut meta = tantivy_index.new_segment_meta(new_id, segment.max_doc);
+        if let Some(num_deleted_docs) = segment.num_deleted_docs {
+            meta = meta.with_delete_meta(num_deleted_docs, previous_meta.opstamp);
+        }
+        segments.push(meta);
+    }
+
+    let new_meta = IndexMeta {
+        segments,
+        ..previous_meta.clone()
+    };
+    directory.save_metas(&new_meta, &previous_meta, &mut ())?;
+
+    Ok(index.name().to_string())
+}
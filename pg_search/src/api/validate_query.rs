@@ -0,0 +1,231 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.validate_query()`, which checks a query against the schema of a BM25 index without
+//! running it, for applications that build queries from user input.
+//!
+//! The queries of a boolean tree are checked one at a time, so that every query that can't search
+//! the index is reported instead of only the first, along with its path in the query's JSON.
+
+use crate::index::mvcc::MvccSatisfies;
+use crate::index::reader::index::SearchIndexReader;
+use crate::postgres::build::is_bm25_index;
+use crate::postgres::index::IndexKind;
+use crate::postgres::rel::PgSearchRelation;
+use crate::query::json::{self, Path};
+use crate::query::pdb_query::pdb;
+use crate::query::SearchQueryInput;
+use anyhow::{ensure, Result};
+use pgrx::prelude::*;
+use pgrx::{JsonB, PgRelation};
+use std::cmp::Ordering;
+use std::ops::Bound;
+use tantivy::schema::OwnedValue;
+
+type Problem = (String, Option<String>, String);
+
+/// Check that `query` can search the BM25 index `index` without running it: that its fields are
+/// fields of the index, that its values are of their fields' types and that its ranges aren't
+/// empty.  Returns one row per invalid query, with its path in the query's JSON and its field, or
+/// no rows if the whole query is valid.
+#[allow(clippy::type_complexity)]
+#[pg_extern(stable)]
+fn validate_query(
+    index: PgRelation,
+    query: SearchQueryInput,
+) -> Result<
+    TableIterator<
+        'static,
+        (
+            name!(path, String),
+            name!(field, Option<String>),
+            name!(error, String),
+        ),
+    >,
+> {
+    let index = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _);
+    ensure!(
+        is_bm25_index(&index),
+        "`{}` is not a BM25 index",
+        index.name()
+    );
+
+    let mut problems = Vec::new();
+    // the partitions of an index share its schema, so any of them can check the query
+    if let Some(index) = IndexKind::for_index(index)?.partitions().next() {
+        let reader = SearchIndexReader::empty(&index, MvccSatisfies::Snapshot)?;
+        check_query(&reader, query, &Path::root(), &mut problems);
+    }
+    Ok(TableIterator::new(problems))
+}
+
+/// Like `paradedb.validate_query(index, query)`, but a query whose JSON can't be parsed is a row
+/// that says why rather than an error.
+#[allow(clippy::type_complexity)]
+#[pg_extern(stable, name = "validate_query")]
+fn validate_query_jsonb(
+    index: PgRelation,
+    query: JsonB,
+) -> Result<
+    TableIterator<
+        'static,
+        (
+            name!(path, String),
+            name!(field, Option<String>),
+            name!(error, String),
+        ),
+    >,
+> {
+    match json::parse(query.0) {
+        Ok(query) => validate_query(index, query),
+        Err(err) => Ok(TableIterator::once((err.path, None, err.message))),
+    }
+}
+
+fn check_query(
+    reader: &SearchIndexReader,
+    query: SearchQueryInput,
+    path: &Path,
+    problems: &mut Vec<Problem>,
+) {
+    match query {
+        SearchQueryInput::Boolean {
+            must,
+            should,
+            must_not,
+        } => {
+            let path = path.key("boolean");
+            for (occur, queries) in [("must", must), ("should", should), ("must_not", must_not)] {
+                for (i, query) in queries.into_iter().enumerate() {
+                    check_query(reader, query, &path.key(occur).index(i), problems);
+                }
+            }
+        }
+        SearchQueryInput::DisjunctionMax { disjuncts, .. } => {
+            let path = path.key("disjunction_max").key("disjuncts");
+            for (i, query) in disjuncts.into_iter().enumerate() {
+                check_query(reader, query, &path.index(i), problems);
+            }
+        }
+        // a boost is reported as part of the query it boosts, as it is when it's written in that
+        // query's JSON
+        SearchQueryInput::Boost { query, .. }
+        | SearchQueryInput::ConstScore { query, .. }
+        | SearchQueryInput::WithIndex { query, .. } => check_query(reader, *query, path, problems),
+        query => {
+            let field = match &query {
+                SearchQueryInput::FieldedQuery { field, .. } => Some(field.to_string()),
+                _ => None,
+            };
+            let error = match &query {
+                SearchQueryInput::FieldedQuery {
+                    query:
+                        pdb::Query::Range {
+                            lower_bound,
+                            upper_bound,
+                            ..
+                        },
+                    ..
+                } if is_empty_range(lower_bound, upper_bound) => Some(
+                    "the range is empty, as its lower bound isn't below its upper bound"
+                        .to_string(),
+                ),
+                _ => reader.try_make_query(query).err().map(|e| e.to_string()),
+            };
+            if let Some(error) = error {
+                problems.push((path.to_string(), field, error));
+            }
+        }
+    }
+}
+
+/// Can no value be between `lower` and `upper`?  Bounds of different types aren't compared.
+fn is_empty_range(lower: &Bound<OwnedValue>, upper: &Bound<OwnedValue>) -> bool {
+    let (
+        Bound::Included(lower_value) | Bound::Excluded(lower_value),
+        Bound::Included(upper_value) | Bound::Excluded(upper_value),
+    ) = (lower, upper)
+    else {
+        return false;
+    };
+    let ordering = match (lower_value, upper_value) {
+        (OwnedValue::Str(lower), OwnedValue::Str(upper)) => lower.cmp(upper),
+        (lower, upper) => match (as_f64(lower), as_f64(upper)) {
+            (Some(lower), Some(upper)) => match lower.partial_cmp(&upper) {
+                Some(ordering) => ordering,
+                None => return false,
+            },
+            _ => return false,
+        },
+    };
+    match ordering {
+        Ordering::Greater => true,
+        Ordering::Equal => !matches!((lower, upper), (Bound::Included(_), Bound::Included(_))),
+        Ordering::Less => false,
+    }
+}
+
+fn as_f64(value: &OwnedValue) -> Option<f64> {
+    match value {
+        OwnedValue::I64(n) => Some(*n as f64),
+        OwnedValue::U64(n) => Some(*n as f64),
+        OwnedValue::F64(n) => Some(*n),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        Bound::Included(OwnedValue::I64(5)),
+        Bound::Excluded(OwnedValue::I64(1)),
+        true
+    )]
+    #[case(
+        Bound::Included(OwnedValue::I64(1)),
+        Bound::Excluded(OwnedValue::F64(1.0)),
+        true
+    )]
+    #[case(
+        Bound::Included(OwnedValue::U64(1)),
+        Bound::Included(OwnedValue::I64(1)),
+        false
+    )]
+    #[case(
+        Bound::Included(OwnedValue::I64(1)),
+        Bound::Excluded(OwnedValue::I64(5)),
+        false
+    )]
+    #[case(Bound::Unbounded, Bound::Excluded(OwnedValue::I64(-5)), false)]
+    #[case(
+        Bound::Included(OwnedValue::Str("b".into())),
+        Bound::Included(OwnedValue::Str("a".into())),
+        true
+    )]
+    #[case(Bound::Included(OwnedValue::Str("b".into())), Bound::Included(OwnedValue::I64(1)), false)]
+    fn test_is_empty_range(
+        #[case] lower: Bound<OwnedValue>,
+        #[case] upper: Bound<OwnedValue>,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(is_empty_range(&lower, &upper), expected);
+    }
+}
@@ -69,6 +69,81 @@ static MIXED_FAST_FIELD_EXEC_COLUMN_THRESHOLD_NAME: &CStr =
 /// it logically can.
 static PER_TUPLE_COST: GucSetting<f64> = GucSetting::<f64>::new(100_000_000.0);
 
+/// The maximum number of groups that the "ParadeDB Aggregate Scan" will produce for each level of
+/// a `GROUP BY`.  Queries that would produce more groups raise an error rather than silently
+/// returning a truncated result.
+static MAX_TERM_AGG_BUCKETS: GucSetting<i32> = GucSetting::<i32>::new(65000);
+
+/// How many `paradedb.aggregate()` results each backend keeps cached.  A cached result is reused
+/// when the same aggregation is run with the same query against an unchanged set of segments.
+/// Zero disables the cache.
+static AGGREGATE_CACHE_ENTRIES: GucSetting<i32> = GucSetting::<i32>::new(0);
+
+/// How many top-n search results each backend keeps cached.  Cached results are reused when the
+/// same search, with the same ORDER BY, LIMIT and OFFSET, is run against an unchanged set of
+/// segments.  Zero disables the cache.
+static SEARCH_CACHE_ENTRIES: GucSetting<i32> = GucSetting::<i32>::new(0);
+
+/// How many translated search queries each backend keeps cached, so that running the same
+/// prepared statement again doesn't parse its query strings and compile its regexes again.  Zero
+/// disables the cache.
+static QUERY_CACHE_ENTRIES: GucSetting<i32> = GucSetting::<i32>::new(0);
+
+/// Allows a top-n scan ordered by score that isn't itself parallel to collect the index's segments
+/// with parallel workers, up to `max_parallel_workers_per_gather` of them.
+static ENABLE_PARALLEL_COLLECTION: GucSetting<bool> = GucSetting::<bool>::new(false);
+
+/// The most keys that a search joined to another table on a fast field may be restricted to,
+/// where they're read from the other table's matches.  Zero disables the restriction.
+static MAX_JOIN_KEYS: GucSetting<i32> = GucSetting::<i32>::new(100_000);
+
+/// How many of the best BM25 matches a search ordered by `paradedb.hybrid()` ranks by their
+/// distance too, before fusing the two rankings.  At least the `LIMIT` are always ranked.
+static HYBRID_CANDIDATES: GucSetting<i32> = GucSetting::<i32>::new(100);
+
+/// The most background merge workers that may run at once.  A merge that's needed while this many
+/// are already running is deferred to the next time the index is written to or vacuumed.  Zero
+/// means there's no limit.
+static MAX_BACKGROUND_MERGERS: GucSetting<i32> = GucSetting::<i32>::new(0);
+
+/// The rate, in kilobytes per second, at which each background merge worker may write the merged
+/// segment.  Zero means the rate isn't limited.
+static BACKGROUND_MERGE_WRITE_LIMIT: GucSetting<i32> = GucSetting::<i32>::new(0);
+
+/// Whether BM25 scores of a partition's rows are computed from the statistics of all of the
+/// partitions of its table, so that they can be compared across partitions.
+static ENABLE_PARTITION_WIDE_SCORING: GucSetting<bool> = GucSetting::<bool>::new(true);
+
+/// Whether a phrase query of a field that doesn't record positions matches the documents that
+/// have all of its terms and whose stored values have the phrase, rather than being an error.
+static PHRASE_FALLBACK: GucSetting<bool> = GucSetting::<bool>::new(false);
+
+/// The memory, in kilobytes, that each statement writing to a BM25 index may use to buffer its
+/// documents before writing them out as a segment.  -1 means `work_mem` is used instead.
+static INSERT_WORK_MEM: GucSetting<i32> = GucSetting::<i32>::new(-1);
+
+/// How many rows a statement writing to a BM25 index converts to documents before writing them to
+/// its segment together.  1 writes each row as it's inserted.
+static INSERT_BATCH_SIZE: GucSetting<i32> = GucSetting::<i32>::new(1000);
+
+/// Whether the documents written by the statements of a transaction are buffered until the
+/// transaction commits, rather than written out as a segment at the end of each statement.
+static BULK_LOAD_MODE: GucSetting<bool> = GucSetting::<bool>::new(false);
+
+/// Whether the searches of each BM25 index are recorded for the `paradedb.index_stats` view.
+static TRACK_INDEX_STATS: GucSetting<bool> = GucSetting::<bool>::new(true);
+
+/// Searches that take at least this many milliseconds are logged, with where their time went.
+/// -1 disables the log.
+static LOG_SLOW_SEARCHES_MS: GucSetting<i32> = GucSetting::<i32>::new(-1);
+
+/// Whether each search of a BM25 index reports how it was planned and executed, as NOTICEs.
+static TRACE: GucSetting<bool> = GucSetting::<bool>::new(false);
+
+/// The memory, in kilobytes, that each search of a BM25 index may hold onto before it fails.
+/// -1 means it isn't limited.
+static WORK_MEM: GucSetting<i32> = GucSetting::<i32>::new(-1);
+
 pub fn init() {
     // Note that Postgres is very specific about the naming convention of variables.
     // They must be namespaced... we use 'paradedb.<variable>' below.
@@ -150,6 +225,192 @@ pub fn init() {
         GucContext::Userset,
         GucFlags::default(),
     );
+
+    GucRegistry::define_int_guc(
+        c"paradedb.max_term_agg_buckets",
+        c"The maximum number of groups the aggregate custom scan will produce for a GROUP BY column",
+        c"Default is 65000.  GROUP BY queries that produce more groups than this raise an error",
+        &MAX_TERM_AGG_BUCKETS,
+        1,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        c"paradedb.aggregate_cache_entries",
+        c"The number of paradedb.aggregate() results each connection caches",
+        c"Default is 0, which disables the cache.  Only aggregations with solve_mvcc => false are cached, and cached results are discarded whenever the index's segments change",
+        &AGGREGATE_CACHE_ENTRIES,
+        0,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        c"paradedb.search_cache_entries",
+        c"The number of top-n search results each connection caches",
+        c"Default is 0, which disables the cache.  Parallel scans, searches scored across partitions, and searches of indexes with a ttl are never cached",
+        &SEARCH_CACHE_ENTRIES,
+        0,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        c"paradedb.query_cache_entries",
+        c"The number of translated search queries each connection caches",
+        c"Default is 0, which disables the cache.  A cached query is reused when the same query is searched for in an unchanged set of segments",
+        &QUERY_CACHE_ENTRIES,
+        0,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_bool_guc(
+        c"paradedb.enable_parallel_collection",
+        c"Collect the segments of a top-n search by score with parallel workers",
+        c"Allow a top-n scan ordered by score that isn't parallel to launch up to max_parallel_workers_per_gather workers, which each collect the top matches of some of the index's segments",
+        &ENABLE_PARALLEL_COLLECTION,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        c"paradedb.max_join_keys",
+        c"The maximum number of join keys a search is restricted to when it's joined to another search",
+        c"Default is 100,000, and 0 disables the restriction.  When the other search's rows have more keys than this, every row is searched instead",
+        &MAX_JOIN_KEYS,
+        0,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        c"paradedb.hybrid_candidates",
+        c"The number of BM25 matches that a search ordered by paradedb.hybrid() fuses with their distances",
+        c"Default is 100.  At least as many as the LIMIT are always fused",
+        &HYBRID_CANDIDATES,
+        1,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        c"paradedb.max_background_mergers",
+        c"The maximum number of background merge workers that may run at once",
+        c"Default is 0, which means there's no limit.  Merges beyond the limit are deferred until the index is next written to",
+        &MAX_BACKGROUND_MERGERS,
+        0,
+        i32::MAX,
+        GucContext::Sighup,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        c"paradedb.background_merge_write_limit",
+        c"The rate at which each background merge worker may write merged segments",
+        c"Default is 0, which means the rate isn't limited",
+        &BACKGROUND_MERGE_WRITE_LIMIT,
+        0,
+        i32::MAX,
+        GucContext::Sighup,
+        GucFlags::UNIT_KB,
+    );
+
+    GucRegistry::define_bool_guc(
+        c"paradedb.enable_partition_wide_scoring",
+        c"Score the rows of a partitioned table using the statistics of all of its partitions",
+        c"When enabled, BM25 scores are comparable across the partitions of a table, at the cost of opening every partition's index when scoring one of them",
+        &ENABLE_PARTITION_WIDE_SCORING,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        c"paradedb.insert_work_mem",
+        c"The memory each INSERT/UPDATE/COPY statement may use to buffer documents for a BM25 index",
+        c"Default is -1, which means work_mem is used.  Values below 15MB are raised to 15MB",
+        &INSERT_WORK_MEM,
+        -1,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::UNIT_KB,
+    );
+
+    GucRegistry::define_int_guc(
+        c"paradedb.insert_batch_size",
+        c"How many rows each INSERT/UPDATE/COPY statement writes to a BM25 index at a time",
+        c"The rows of a statement are buffered as documents and written to its segment in batches of this many, and the rest at the end of the statement.  1 writes each row as it's inserted",
+        &INSERT_BATCH_SIZE,
+        1,
+        1_000_000,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_bool_guc(
+        c"paradedb.phrase_fallback",
+        c"Search fields without positions for phrases by checking their stored values",
+        c"When enabled, a phrase query of a stored text field that doesn't record positions matches the documents that have all of its terms, and then reads each of them to check that it has the phrase, instead of being an error",
+        &PHRASE_FALLBACK,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_bool_guc(
+        c"paradedb.bulk_load_mode",
+        c"Buffer the documents written to a BM25 index until the transaction commits",
+        c"When enabled, the statements of a transaction share one index writer, so a load made of many small statements creates few segments rather than one per statement",
+        &BULK_LOAD_MODE,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_bool_guc(
+        c"paradedb.track_index_stats",
+        c"Record the searches of each BM25 index for the paradedb.index_stats view",
+        c"Requires pg_search to be in shared_preload_libraries",
+        &TRACK_INDEX_STATS,
+        GucContext::Suset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        c"paradedb.log_slow_searches_ms",
+        c"Log the BM25 index searches that take at least this long",
+        c"Default is -1, which disables the log.  Each search is logged with its index, query, segment count and phase timings",
+        &LOG_SLOW_SEARCHES_MS,
+        -1,
+        i32::MAX,
+        GucContext::Suset,
+        GucFlags::UNIT_MS,
+    );
+
+    GucRegistry::define_bool_guc(
+        c"paradedb.trace",
+        c"Report how each BM25 index search is planned and executed",
+        c"Emits a NOTICE for each predicate pushdown decision, the final search query, the clauses that are checked against the heap, and fallbacks to slower execution methods",
+        &TRACE,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        c"paradedb.work_mem",
+        c"The memory each search of a BM25 index may use",
+        c"Counts the index data, collected matches and aggregation buckets a search holds onto, and fails the search when they exceed it.  Default is -1, which means searches aren't limited",
+        &WORK_MEM,
+        -1,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::UNIT_KB,
+    );
 }
 
 pub fn enable_custom_scan() -> bool {
@@ -194,6 +455,78 @@ pub fn per_tuple_cost() -> f64 {
     PER_TUPLE_COST.get()
 }
 
+pub fn max_term_agg_buckets() -> u32 {
+    MAX_TERM_AGG_BUCKETS.get() as u32
+}
+
+pub fn aggregate_cache_entries() -> usize {
+    AGGREGATE_CACHE_ENTRIES.get() as usize
+}
+
+pub fn search_cache_entries() -> usize {
+    SEARCH_CACHE_ENTRIES.get() as usize
+}
+
+pub fn query_cache_entries() -> usize {
+    QUERY_CACHE_ENTRIES.get() as usize
+}
+
+pub fn enable_parallel_collection() -> bool {
+    ENABLE_PARALLEL_COLLECTION.get()
+}
+
+pub fn max_join_keys() -> usize {
+    MAX_JOIN_KEYS.get() as usize
+}
+
+pub fn hybrid_candidates() -> usize {
+    HYBRID_CANDIDATES.get() as usize
+}
+
+pub fn max_background_mergers() -> usize {
+    MAX_BACKGROUND_MERGERS.get() as usize
+}
+
+/// In bytes per second.
+pub fn background_merge_write_limit() -> u64 {
+    BACKGROUND_MERGE_WRITE_LIMIT.get() as u64 * 1024
+}
+
+pub fn enable_partition_wide_scoring() -> bool {
+    ENABLE_PARTITION_WIDE_SCORING.get()
+}
+
+pub fn phrase_fallback() -> bool {
+    PHRASE_FALLBACK.get()
+}
+
+pub fn insert_batch_size() -> usize {
+    INSERT_BATCH_SIZE.get() as usize
+}
+
+pub fn bulk_load_mode() -> bool {
+    BULK_LOAD_MODE.get()
+}
+
+pub fn track_index_stats() -> bool {
+    TRACK_INDEX_STATS.get()
+}
+
+pub fn log_slow_searches() -> Option<std::time::Duration> {
+    let millis = LOG_SLOW_SEARCHES_MS.get();
+    (millis >= 0).then(|| std::time::Duration::from_millis(millis as u64))
+}
+
+pub fn trace() -> bool {
+    TRACE.get()
+}
+
+/// The number of bytes each search may use, from `paradedb.work_mem`.
+pub fn search_work_mem() -> Option<usize> {
+    let kb = WORK_MEM.get();
+    (kb >= 0).then(|| kb as usize * 1024)
+}
+
 // NB:  These limits come from [`tantivy::index_writer::MEMORY_BUDGET_NUM_BYTES_MAX`], which is not publicly exposed
 mod limits {
     const MARGIN_IN_BYTES: usize = 1_000_000;
@@ -236,13 +569,26 @@ pub fn adjust_maintenance_work_mem(nlaunched: usize) -> NonZeroUsize {
 }
 
 pub fn adjust_work_mem() -> NonZeroUsize {
-    let wm_as_bytes = unsafe { pg_sys::work_mem as usize * 1024 };
-    let wm_as_bytes = wm_as_bytes.clamp(
+    clamp_memory_budget(unsafe { pg_sys::work_mem as usize * 1024 })
+}
+
+/// The memory budget of a statement's index writer, `paradedb.insert_work_mem` if it's set, the
+/// index's own `insert_work_mem` if that is, and `work_mem` otherwise.
+pub fn adjust_insert_work_mem(index_budget: Option<usize>) -> NonZeroUsize {
+    match (INSERT_WORK_MEM.get(), index_budget) {
+        (-1, Some(bytes)) => clamp_memory_budget(bytes),
+        (-1, None) => adjust_work_mem(),
+        (kb, _) => clamp_memory_budget(kb as usize * 1024),
+    }
+}
+
+fn clamp_memory_budget(bytes: usize) -> NonZeroUsize {
+    let bytes = bytes.clamp(
         limits::MEMORY_BUDGET_NUM_BYTES_MIN,
         limits::MEMORY_BUDGET_NUM_BYTES_MAX - 1,
     );
 
-    NonZeroUsize::new(wm_as_bytes).unwrap()
+    NonZeroUsize::new(bytes).unwrap()
 }
 
 #[cfg(any(test, feature = "pg_test"))]
@@ -280,6 +626,32 @@ mod tests {
         assert_approx_eq!(adjust_work_mem().get(), 1024 * 1024 * 1024, 1.0);
     }
 
+    #[pg_test]
+    fn test_adjust_insert_work_mem() {
+        Spi::run("SET work_mem = '1GB';").unwrap();
+        assert_approx_eq!(adjust_insert_work_mem(None).get(), 1024 * 1024 * 1024, 1.0);
+
+        // the index's own budget is used instead of work_mem
+        let index_budget = Some(32 * 1024 * 1024);
+        assert_approx_eq!(
+            adjust_insert_work_mem(index_budget).get(),
+            32 * 1024 * 1024,
+            1.0
+        );
+
+        // and the session's is used instead of either
+        Spi::run("SET paradedb.insert_work_mem = '64MB';").unwrap();
+        assert_approx_eq!(adjust_insert_work_mem(None).get(), 64 * 1024 * 1024, 1.0);
+        assert_approx_eq!(
+            adjust_insert_work_mem(index_budget).get(),
+            64 * 1024 * 1024,
+            1.0
+        );
+
+        Spi::run("SET paradedb.insert_work_mem = '1MB';").unwrap();
+        assert_approx_eq!(adjust_insert_work_mem(None).get(), 15 * 1_000_000, 1.0);
+    }
+
     #[pg_test]
     fn test_adjust_maintenance_work_mem() {
         Spi::run("SET maintenance_work_mem = '16MB';").unwrap();
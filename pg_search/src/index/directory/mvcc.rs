@@ -17,11 +17,12 @@
 
 use super::utils::{load_metas, save_new_metas, save_schema, save_settings};
 use crate::api::{HashMap, HashSet};
+use crate::index::reader::memory::MemoryTracker;
 use crate::index::reader::segment_component::SegmentComponentReader;
 use crate::index::writer::segment_component::SegmentComponentWriter;
 use crate::postgres::rel::PgSearchRelation;
 use crate::postgres::storage::block::{
-    bm25_max_free_space, FileEntry, MVCCEntry, SegmentMetaEntry,
+    bm25_max_free_space, FileEntry, MVCCEntry, SegmentFileDetails, SegmentMetaEntry,
 };
 use crate::postgres::storage::buffer::{BufferManager, PinnedBuffer};
 use crate::postgres::storage::metadata::MetaPage;
@@ -98,6 +99,12 @@ pub struct MVCCDirectory {
     all_entries: Arc<Mutex<HashMap<SegmentId, SegmentMetaEntry>>>,
     pin_cushion: Arc<Mutex<Option<PinCushion>>>,
     total_segment_count: Arc<AtomicUsize>,
+
+    // if set, the segments that are known to belong to other tenants are left out
+    tenants: Arc<Option<HashSet<String>>>,
+
+    // if set, what's read from the index counts against it
+    memory: Option<Arc<MemoryTracker>>,
 }
 
 unsafe impl Send for MVCCDirectory {}
@@ -121,9 +128,25 @@ impl MVCCDirectory {
             pin_cushion: Default::default(),
             all_entries: Default::default(),
             total_segment_count: Default::default(),
+            tenants: Default::default(),
+            memory: Default::default(),
         }
     }
 
+    /// Only load the segments that may hold documents of `tenants`, when they're known.
+    ///
+    /// This only affects a [`MvccSatisfies::Snapshot`].  In particular, a
+    /// [`MvccSatisfies::ParallelWorker`] still loads exactly the segments its leader did.
+    pub fn for_tenants(mut self, tenants: Option<HashSet<String>>) -> Self {
+        self.tenants = Arc::new(tenants);
+        self
+    }
+
+    pub fn with_memory(mut self, memory: Arc<MemoryTracker>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
     pub unsafe fn directory_lookup(&self, path: &Path) -> tantivy::Result<FileEntry> {
         let file_name = path
             .file_name()
@@ -178,6 +201,10 @@ impl MVCCDirectory {
         Some(())
     }
 
+    pub fn indexrel(&self) -> &PgSearchRelation {
+        &self.indexrel
+    }
+
     pub(crate) fn all_entries(&self) -> HashMap<SegmentId, SegmentMetaEntry> {
         self.all_entries.lock().clone()
     }
@@ -221,6 +248,7 @@ impl Directory for MVCCDirectory {
                 Ok(vacant
                     .insert(Arc::new(unsafe {
                         SegmentComponentReader::new(&self.indexrel, file_entry)
+                            .with_memory(self.memory.clone())
                     }))
                     .clone())
             }
@@ -243,6 +271,15 @@ impl Directory for MVCCDirectory {
         path: &Path,
     ) -> result::Result<Box<dyn TerminatingWrite>, OpenWriteError> {
         let writer = unsafe { SegmentComponentWriter::new(&self.indexrel, path) };
+        let starting_block = writer.file_entry().starting_block;
+        if starting_block != pg_sys::InvalidBlockNumber {
+            // until the file belongs to a segment, it's only the journal that can free it
+            unsafe {
+                MetaPage::open(&self.indexrel)
+                    .write_journal()
+                    .record(starting_block);
+            }
+        }
         self.new_files.lock().insert(
             path.to_path_buf(),
             (writer.file_entry(), writer.total_bytes()),
@@ -345,6 +382,25 @@ impl Directory for MVCCDirectory {
         }
 
         unsafe {
+            // the new files are forgotten by the journal before the segments that reference them
+            // are saved, so that a crash in between can't free them out from under the segments
+            let segment_ids = meta
+                .segments
+                .iter()
+                .map(|segment| segment.id())
+                .collect::<HashSet<_>>();
+            let header_blocknos = payload
+                .iter()
+                .filter(|(path, _)| {
+                    path.segment_id()
+                        .is_some_and(|segment_id| segment_ids.contains(&segment_id))
+                })
+                .map(|(_, file_entry)| file_entry.starting_block)
+                .collect::<HashSet<_>>();
+            MetaPage::open(&self.indexrel)
+                .write_journal()
+                .forget(&header_blocknos);
+
             save_new_metas(&self.indexrel, meta, previous_meta, payload)
                 .map_err(|err| tantivy::TantivyError::InternalError(err.to_string()))?;
         }
@@ -358,6 +414,7 @@ impl Directory for MVCCDirectory {
                 &self.indexrel,
                 inventory,
                 &self.mvcc_style,
+                self.tenants.as_ref().as_ref(),
                 self.indexrel
                     .schema()
                     .unwrap_or_else(|e| panic!("{e}"))
@@ -2,9 +2,11 @@ use crate::api::{HashMap, HashSet};
 use crate::index::mvcc::{MvccSatisfies, PinCushion};
 use crate::postgres::rel::PgSearchRelation;
 use crate::postgres::storage::block::{
-    DeleteEntry, FileEntry, LinkedList, MVCCEntry, PgItem, SegmentFileDetails, SegmentMetaEntry,
+    segment_created_at_now, DeleteEntry, FileEntry, LinkedList, MVCCEntry, PgItem,
+    SegmentFileDetails, SegmentMetaEntry,
 };
 use crate::postgres::storage::metadata::MetaPage;
+use crate::postgres::storage::LinkedBytesList;
 use anyhow::Result;
 use pgrx::pg_sys;
 use std::path::PathBuf;
@@ -28,6 +30,36 @@ pub fn save_schema(indexrel: &PgSearchRelation, tantivy_schema: &Schema) -> Resu
     Ok(())
 }
 
+/// Overwrite the schema of an existing index, such as when `ALTER INDEX` adds a field.
+pub fn replace_schema(indexrel: &PgSearchRelation, tantivy_schema: &Schema) -> Result<()> {
+    let bytes = serde_json::to_vec(tantivy_schema)?;
+    let schema = LinkedBytesList::create_with_fsm(indexrel);
+    let mut writer = schema.writer();
+    unsafe {
+        writer.write(&bytes)?;
+    }
+    let schema = writer.finalize_and_write()?;
+    MetaPage::open(indexrel).replace_schema_bytes(&schema);
+    Ok(())
+}
+
+/// Overwrite the settings of an existing index, such as when `ALTER INDEX` changes how new
+/// segments are compressed.
+pub fn replace_settings(
+    indexrel: &PgSearchRelation,
+    tantivy_settings: &IndexSettings,
+) -> Result<()> {
+    let bytes = serde_json::to_vec(tantivy_settings)?;
+    let settings = LinkedBytesList::create_with_fsm(indexrel);
+    let mut writer = settings.writer();
+    unsafe {
+        writer.write(&bytes)?;
+    }
+    let settings = writer.finalize_and_write()?;
+    MetaPage::open(indexrel).replace_settings_bytes(&settings);
+    Ok(())
+}
+
 pub fn save_settings(indexrel: &PgSearchRelation, tantivy_settings: &IndexSettings) -> Result<()> {
     let settings = MetaPage::open(indexrel).settings_bytes();
     if settings.is_empty() {
@@ -118,7 +150,7 @@ pub unsafe fn save_new_metas(
             let meta_entry = SegmentMetaEntry {
                 segment_id: *id,
                 max_doc: created_segment.max_doc(),
-                _unused: pg_sys::InvalidTransactionId,
+                created_at: segment_created_at_now(),
                 xmax: pg_sys::InvalidTransactionId,
                 postings: files.remove(&SegmentComponent::Postings).map(|e| e.0),
                 positions: files.remove(&SegmentComponent::Positions).map(|e| e.0),
@@ -326,6 +358,7 @@ pub unsafe fn load_metas(
     indexrel: &PgSearchRelation,
     inventory: &SegmentMetaInventory,
     solve_mvcc: &MvccSatisfies,
+    tenants: Option<&HashSet<String>>,
     tantivy_schema: &Schema,
 ) -> tantivy::Result<LoadedMetas> {
     let mut total_segments = 0;
@@ -338,6 +371,12 @@ pub unsafe fn load_metas(
     let metapage = MetaPage::open(indexrel);
     let mut segment_metas = metapage.segment_metas();
 
+    // the tenant of each segment that belongs to one, if we're only after some tenants' segments
+    let segment_tenants = tenants
+        .filter(|_| matches!(solve_mvcc, MvccSatisfies::Snapshot))
+        .and_then(|_| metapage.segment_tenants())
+        .map(|segment_tenants| segment_tenants.list());
+
     let is_largest_only = &MvccSatisfies::LargestSegment == solve_mvcc;
     let mut largest_doc_count = 0;
     // Find all relevant segments in this list.
@@ -360,6 +399,16 @@ pub unsafe fn load_metas(
             return;
         };
 
+        if let (Some(tenants), Some(segment_tenants)) = (tenants, &segment_tenants) {
+            if segment_tenants
+                .get(&entry.segment_id)
+                .is_some_and(|tenant| !tenants.contains(tenant))
+            {
+                // the segment only holds another tenant's documents, so it can't match
+                return;
+            }
+        }
+
         total_segments += 1;
 
         let mut need_entry = true;
@@ -446,6 +495,15 @@ pub unsafe fn load_metas(
     })
 }
 
+pub fn load_index_settings(indexrel: &PgSearchRelation) -> tantivy::Result<Option<IndexSettings>> {
+    let metapage = MetaPage::open(indexrel);
+    let settings_bytes = unsafe { metapage.settings_bytes().read_all() };
+    if settings_bytes.is_empty() {
+        return Ok(None);
+    }
+    Ok(serde_json::from_slice(&settings_bytes)?)
+}
+
 pub fn load_index_schema(indexrel: &PgSearchRelation) -> tantivy::Result<Option<Schema>> {
     let metapage = MetaPage::open(indexrel);
     let schema_bytes = unsafe { metapage.schema_bytes().read_all() };
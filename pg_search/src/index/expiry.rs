@@ -0,0 +1,119 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Document expiry for indexes with a `ttl_field` and a `ttl`.
+//!
+//! A document expires once the value of its `ttl_field` is older than the start of the current
+//! transaction, less the `ttl`.  Expired documents are hidden from every search straight away, and
+//! VACUUM deletes them from the index, after which the next merge of their segment drops them for
+//! good.  Documents without a value in the `ttl_field` never expire.
+
+use std::ffi::CString;
+use std::ops::Bound;
+
+use crate::api::HashSet;
+use crate::index::fast_fields_helper::FFType;
+use crate::postgres::datetime::POSTGRES_EPOCH_UNIX_MICROS;
+use crate::postgres::options::parse_ttl;
+use crate::postgres::rel::PgSearchRelation;
+use crate::schema::SearchIndexSchema;
+use anyhow::Result;
+use pgrx::{direct_function_call, pg_sys, IntoDatum};
+use tantivy::query::{BooleanQuery, EnableScoring, Occur, Query, RangeQuery};
+use tantivy::schema::{Field, DATE_TIME_PRECISION_INDEXED};
+use tantivy::{DateTime, Searcher, Term};
+
+pub struct Expiry {
+    field: Field,
+    cutoff: DateTime,
+}
+
+impl Expiry {
+    /// The expiry of `index_relation`'s documents as of now, if it has a `ttl`.
+    pub fn for_index(
+        index_relation: &PgSearchRelation,
+        schema: &SearchIndexSchema,
+    ) -> Option<Self> {
+        let options = index_relation.options();
+        let (ttl_field, ttl) = (options.ttl_field_name()?, options.ttl()?);
+        let field = schema
+            .search_field(&ttl_field)
+            .unwrap_or_else(|| panic!("ttl_field '{ttl_field}' should be in the schema"))
+            .field();
+        let ttl = CString::new(ttl).expect("`ttl` should not contain NUL bytes");
+        let ttl = parse_ttl(&ttl);
+
+        // the transaction's start is what `now()` returns, and is shared with parallel workers,
+        // so every part of a statement agrees on which documents have expired
+        let cutoff = unsafe {
+            direct_function_call::<i64>(
+                pg_sys::timestamptz_mi_interval,
+                &[
+                    pg_sys::GetCurrentTransactionStartTimestamp().into_datum(),
+                    ttl.into_datum(),
+                ],
+            )
+            .expect("`timestamptz_mi_interval()` should not return NULL")
+        };
+
+        Some(Self {
+            field,
+            cutoff: DateTime::from_timestamp_micros(
+                cutoff.saturating_add(POSTGRES_EPOCH_UNIX_MICROS),
+            ),
+        })
+    }
+
+    /// A query that matches the expired documents.
+    pub fn expired(&self) -> Box<dyn Query> {
+        Box::new(RangeQuery::new(
+            Bound::Unbounded,
+            Bound::Excluded(Term::from_field_date(
+                self.field,
+                self.cutoff.truncate(DATE_TIME_PRECISION_INDEXED),
+            )),
+        ))
+    }
+
+    /// `query`, without the expired documents.  Scores are still those of `query`.
+    pub fn exclude(&self, query: Box<dyn Query>) -> Box<dyn Query> {
+        Box::new(BooleanQuery::new(vec![
+            (Occur::Must, query),
+            (Occur::MustNot, self.expired()),
+        ]))
+    }
+
+    /// The ctids of the live documents in `searcher` that have expired.
+    pub fn expired_ctids(&self, searcher: &Searcher) -> Result<HashSet<u64>> {
+        let weight = self
+            .expired()
+            .weight(EnableScoring::disabled_from_searcher(searcher))?;
+
+        let mut ctids = HashSet::default();
+        for segment_reader in searcher.segment_readers() {
+            let ctid_ff = FFType::new_ctid(segment_reader.fast_fields());
+            weight.for_each_no_score(segment_reader, &mut |doc_ids| {
+                for &doc_id in doc_ids {
+                    if !segment_reader.is_deleted(doc_id) {
+                        ctids.insert(ctid_ff.as_u64(doc_id).expect("ctid should be present"));
+                    }
+                }
+            })?;
+        }
+        Ok(ctids)
+    }
+}
@@ -22,7 +22,7 @@ use crate::postgres::types::TantivyValue;
 use crate::schema::SearchFieldType;
 
 use serde::{Deserialize, Serialize};
-use tantivy::columnar::StrColumn;
+use tantivy::columnar::{BytesColumn, StrColumn};
 use tantivy::fastfield::{Column, FastFieldReaders};
 use tantivy::schema::OwnedValue;
 use tantivy::SegmentOrdinal;
@@ -114,6 +114,7 @@ impl FFHelper {
 pub enum FFType {
     Junk,
     Text(StrColumn),
+    Bytes(BytesColumn),
     I64(Column<i64>),
     F64(Column<f64>),
     U64(Column<u64>),
@@ -136,6 +137,8 @@ impl FFType {
             Self::I64(ff)
         } else if let Ok(Some(ff)) = ffr.str(field_name) {
             Self::Text(ff)
+        } else if let Ok(Some(ff)) = ffr.bytes(field_name) {
+            Self::Bytes(ff)
         } else if let Ok(ff) = ffr.u64(field_name) {
             Self::U64(ff)
         } else if let Ok(ff) = ffr.f64(field_name) {
@@ -164,6 +167,17 @@ impl FFType {
                     .expect("string should be retrievable for term ord");
                 TantivyValue(s.into())
             }
+            FFType::Bytes(ff) => TantivyValue(
+                ff.term_ords(doc)
+                    .next()
+                    .map(|ord| {
+                        let mut bytes = Vec::new();
+                        ff.ord_to_bytes(ord, &mut bytes)
+                            .expect("bytes should be retrievable for term ord");
+                        OwnedValue::Bytes(bytes)
+                    })
+                    .unwrap_or(OwnedValue::Null),
+            ),
             FFType::I64(ff) => TantivyValue(
                 ff.first(doc)
                     .map(|first| first.into())
@@ -238,6 +252,19 @@ impl FFType {
         }
     }
 
+    /// Given a [`DocId`], what is its numeric "fast field" value as an f64?
+    ///
+    /// If this [`FFType`] isn't numeric, this function returns [`None`].
+    #[inline(always)]
+    pub fn as_f64(&self, doc: DocId) -> Option<f64> {
+        match self {
+            FFType::F64(ff) => ff.first(doc),
+            FFType::I64(ff) => ff.first(doc).map(|v| v as f64),
+            FFType::U64(ff) => ff.first(doc).map(|v| v as f64),
+            _ => None,
+        }
+    }
+
     /// Given [`DocId`]s, what are their u64 "fast field" values?
     ///
     /// The given `output` slice must be the same length as the docs slice.
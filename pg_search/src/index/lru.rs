@@ -0,0 +1,111 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! The least-recently-used map behind the per-backend caches of queries, search results, and
+//! aggregation results.
+
+use rustc_hash::FxHashMap;
+use std::collections::BTreeMap;
+use std::hash::Hash;
+
+pub struct LruCache<K, V> {
+    /// Each value, with the tick it was last used at.
+    entries: FxHashMap<K, (V, u64)>,
+    /// Keys by the tick they were last used at, from least to most recently used.
+    recency: BTreeMap<u64, K>,
+    tick: u64,
+}
+
+impl<K, V> Default for LruCache<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: Default::default(),
+            recency: Default::default(),
+            tick: 0,
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> LruCache<K, V> {
+    /// Look up a value, marking it as the most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let (value, last_used) = self.entries.get_mut(key)?;
+        let key = self
+            .recency
+            .remove(last_used)
+            .expect("a cached key should have a recency");
+        self.tick += 1;
+        *last_used = self.tick;
+        self.recency.insert(self.tick, key);
+        Some(value)
+    }
+
+    /// Insert a value as the most recently used, evicting the least recently used ones beyond
+    /// `capacity`.
+    pub fn insert(&mut self, key: K, value: V, capacity: usize) {
+        self.tick += 1;
+        if let Some((_, last_used)) = self.entries.insert(key.clone(), (value, self.tick)) {
+            self.recency.remove(&last_used);
+        }
+        self.recency.insert(self.tick, key);
+
+        while self.entries.len() > capacity {
+            let Some((_, evicted)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&evicted);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_evicts_least_recently_used() {
+        let mut cache = LruCache::default();
+        cache.insert("a", 1, 2);
+        cache.insert("b", 2, 2);
+        assert_eq!(cache.get(&"a"), Some(&1));
+
+        cache.insert("c", 3, 2);
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.get(&"c"), Some(&3));
+
+        // replacing a value makes it the most recently used
+        cache.insert("a", 4, 2);
+        cache.insert("d", 5, 2);
+        assert_eq!(cache.get(&"c"), None);
+        assert_eq!(cache.get(&"a"), Some(&4));
+        assert_eq!(cache.get(&"d"), Some(&5));
+    }
+
+    #[rstest]
+    fn test_shrinks_to_a_smaller_capacity() {
+        let mut cache = LruCache::default();
+        for i in 0..5 {
+            cache.insert(i, i, 5);
+        }
+        cache.insert(5, 5, 2);
+        assert_eq!(cache.get(&3), None);
+        assert_eq!(cache.get(&4), Some(&4));
+        assert_eq!(cache.get(&5), Some(&5));
+    }
+}
@@ -17,9 +17,11 @@ pub struct LayeredMergePolicy {
     n: usize,
     layer_sizes: Vec<u64>,
     min_merge_count: usize,
+    max_merge_count: Option<usize>,
     enable_logging: bool,
 
     mergeable_segments: HashMap<SegmentId, SegmentMetaEntry>,
+    segment_tenants: HashMap<SegmentId, String>,
     already_processed: AtomicBool,
 }
 
@@ -81,8 +83,10 @@ impl MergePolicy for LayeredMergePolicy {
             // collect the list of mergeable segments so that we can combine those that fit in the next layer
             let segments =
                 self.collect_mergeable_segments(original_segments, &merged_segments, avg_doc_size);
-            let mut candidate_byte_size = 0;
-            candidates.push((layer_size, MergeCandidate(vec![])));
+            // segments of different tenants are never merged together, so each tenant fills
+            // candidates of its own
+            let mut open_candidates: HashMap<Option<&String>, (u64, Vec<SegmentId>)> =
+                HashMap::default();
 
             for segment in segments {
                 if merged_segments.contains(&segment.id()) {
@@ -98,20 +102,25 @@ impl MergePolicy for LayeredMergePolicy {
                 // add this segment as a candidate
                 let segment_byte_size =
                     actual_byte_size(segment, &self.mergeable_segments, avg_doc_size);
-                candidate_byte_size += segment_byte_size;
-                candidates.last_mut().unwrap().1 .0.push(segment.id());
-
-                if candidate_byte_size >= extended_layer_size {
-                    // the candidate now exceeds the layer size so we start a new candidate
-                    candidate_byte_size = 0;
-                    candidates.push((layer_size, MergeCandidate(vec![])));
+                let (candidate_byte_size, candidate) = open_candidates
+                    .entry(self.segment_tenants.get(&segment.id()))
+                    .or_default();
+                *candidate_byte_size += segment_byte_size;
+                candidate.push(segment.id());
+
+                if *candidate_byte_size >= extended_layer_size
+                    || self
+                        .max_merge_count
+                        .is_some_and(|max_merge_count| candidate.len() >= max_merge_count)
+                {
+                    // the candidate now exceeds the layer size, or has as many segments as can be
+                    // merged at once, so we start a new candidate
+                    *candidate_byte_size = 0;
+                    candidates.push((layer_size, MergeCandidate(std::mem::take(candidate))));
                 }
             }
 
-            if candidate_byte_size < extended_layer_size {
-                // the last candidate isn't full, so throw it away
-                candidates.pop();
-            }
+            // the candidates that are left aren't full, so they're thrown away
 
             // remember the segments we have merged so we don't merge them again
             for candidate in &candidates {
@@ -180,13 +189,23 @@ impl LayeredMergePolicy {
                 .get(),
             layer_sizes,
             min_merge_count: 2,
+            max_merge_count: None,
             enable_logging: unsafe { pg_sys::message_level_is_interesting(pg_sys::DEBUG1 as _) },
 
             mergeable_segments: Default::default(),
+            segment_tenants: Default::default(),
             already_processed: Default::default(),
         }
     }
 
+    /// Bound how many segments are merged together at once.  Merging more segments at once makes
+    /// for fewer, but longer and larger, merges.
+    pub fn with_merge_counts(mut self, min: usize, max: Option<usize>) -> Self {
+        self.min_merge_count = min;
+        self.max_merge_count = max;
+        self
+    }
+
     pub fn set_mergeable_segment_entries(
         &mut self,
         metadata: &MetaPage,
@@ -209,6 +228,11 @@ impl LayeredMergePolicy {
                 !non_mergeable_segments.contains(segment_id)
             })
             .collect();
+
+        self.segment_tenants = metadata
+            .segment_tenants()
+            .map(|segment_tenants| unsafe { segment_tenants.list() })
+            .unwrap_or_default();
     }
 
     /// Run a simulation of what tantivy will do if it were to call our [`MergePolicy::compute_merge_candidates`]
@@ -313,3 +337,103 @@ fn adjusted_byte_size(
         .unwrap_or(meta.num_docs() as u64 * avg_doc_size)
         .max(avg_doc_size)
 }
+
+/// Group segments, given as their ids and byte sizes, into merge candidates for
+/// `paradedb.force_merge()`, such that together with the `nother` segments that aren't being
+/// merged, the index is left with `segment_count` segments, or as close to it as possible.
+///
+/// Segments are spread across the candidates largest first, each going to the candidate with the
+/// fewest bytes so far, so that the merged segments come out about the same size.  A candidate with
+/// a single segment is only kept if `allow_single` is set, as merging it is then worthwhile for
+/// the deleted documents it drops.
+pub fn force_merge_candidates(
+    mut segments: Vec<(SegmentId, u64)>,
+    nother: usize,
+    segment_count: usize,
+    allow_single: bool,
+) -> Vec<MergeCandidate> {
+    if segments.is_empty() {
+        return vec![];
+    }
+
+    let ncandidates = segment_count
+        .saturating_sub(nother)
+        .clamp(1, segments.len());
+    segments.sort_by_key(|(segment_id, byte_size)| (Reverse(*byte_size), *segment_id));
+
+    let mut candidates = vec![(0u64, Vec::new()); ncandidates];
+    for (segment_id, byte_size) in segments {
+        let (candidate_bytes, candidate) = candidates
+            .iter_mut()
+            .min_by_key(|(candidate_bytes, _)| *candidate_bytes)
+            .unwrap();
+        *candidate_bytes += byte_size;
+        candidate.push(segment_id);
+    }
+
+    candidates
+        .into_iter()
+        .map(|(_, candidate)| MergeCandidate(candidate))
+        .filter(|candidate| candidate.0.len() > 1 || allow_single)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn segments(sizes: &[u64]) -> Vec<(SegmentId, u64)> {
+        sizes
+            .iter()
+            .map(|size| (SegmentId::generate_random(), *size))
+            .collect()
+    }
+
+    fn candidate_sizes(segments: &[(SegmentId, u64)], candidates: &[MergeCandidate]) -> Vec<u64> {
+        let mut sizes = candidates
+            .iter()
+            .map(|candidate| {
+                candidate
+                    .0
+                    .iter()
+                    .map(|id| segments.iter().find(|(s, _)| s == id).unwrap().1)
+                    .sum()
+            })
+            .collect::<Vec<_>>();
+        sizes.sort_unstable();
+        sizes
+    }
+
+    #[rstest]
+    fn test_force_merge_to_one() {
+        let segments = segments(&[10, 20, 30, 40]);
+        let candidates = force_merge_candidates(segments.clone(), 0, 1, false);
+        assert_eq!(candidate_sizes(&segments, &candidates), vec![100]);
+    }
+
+    #[rstest]
+    fn test_force_merge_balances_candidates() {
+        let segments = segments(&[50, 40, 30, 20, 10, 10]);
+        let candidates = force_merge_candidates(segments.clone(), 0, 2, false);
+        assert_eq!(candidate_sizes(&segments, &candidates), vec![80, 80]);
+    }
+
+    #[rstest]
+    fn test_force_merge_accounts_for_other_segments() {
+        // two segments aren't being merged, so the rest merge down to one to make three
+        let segments = segments(&[10, 20, 30]);
+        let candidates = force_merge_candidates(segments.clone(), 2, 3, false);
+        assert_eq!(candidate_sizes(&segments, &candidates), vec![60]);
+    }
+
+    #[rstest]
+    fn test_force_merge_single_segments() {
+        let segments = segments(&[10, 20]);
+        // already at the target, so there's nothing to do...
+        assert!(force_merge_candidates(segments.clone(), 0, 2, false).is_empty());
+        // ...unless the segments are being rewritten to drop their deleted documents
+        let candidates = force_merge_candidates(segments.clone(), 0, 2, true);
+        assert_eq!(candidate_sizes(&segments, &candidates), vec![10, 20]);
+    }
+}
@@ -16,10 +16,13 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 pub mod directory;
+pub mod expiry;
 pub mod fast_fields_helper;
+pub mod lru;
 pub mod merge_policy;
 pub mod reader;
 pub mod search;
+pub mod term_vectors;
 pub mod writer;
 
 pub use directory::*;
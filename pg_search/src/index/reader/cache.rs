@@ -0,0 +1,94 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! A per-backend cache of top-n search results.
+//!
+//! Read-heavy applications tend to run the same handful of top-n searches over and over against
+//! an index that changes far less often.  Like the [aggregate cache](crate::aggregate::cache),
+//! results are keyed by the search and by the segments that the snapshot can see, in the order
+//! the searcher has them, along with how many deleted documents each one has.  Inserts, merges,
+//! and vacuums all change those, so previous results become unreachable without any explicit
+//! invalidation.  The results are the index's matches before they're checked against the heap,
+//! so they don't otherwise depend on the snapshot.
+
+use crate::api::OrderByInfo;
+use crate::index::lru::LruCache;
+use crate::index::reader::index::{SearchIndexReader, SearchIndexScore};
+use crate::postgres::stats;
+use crate::query::SearchQueryInput;
+use parking_lot::Mutex;
+use pgrx::pg_sys;
+use std::sync::LazyLock;
+use tantivy::index::SegmentId;
+use tantivy::DocAddress;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TopNCacheKey {
+    indexrelid: pg_sys::Oid,
+    query: String,
+    orderby_info: String,
+    n: usize,
+    offset: usize,
+    segments: Vec<(SegmentId, u32)>,
+}
+
+impl TopNCacheKey {
+    /// The key of a top-n search of every segment of `reader`, if its results can be cached.
+    pub fn new(
+        indexrelid: pg_sys::Oid,
+        reader: &SearchIndexReader,
+        query: &SearchQueryInput,
+        orderby_info: Option<&Vec<OrderByInfo>>,
+        n: usize,
+        offset: usize,
+    ) -> Option<Self> {
+        if !reader.is_repeatable() || query.depends_on_heap() || query.searches_other_index() {
+            return None;
+        }
+
+        Some(Self {
+            indexrelid,
+            query: serde_json::to_string(query).ok()?,
+            orderby_info: serde_json::to_string(&orderby_info).ok()?,
+            n,
+            offset,
+            // a `DocAddress` refers to its segment by position, so the order matters
+            segments: reader
+                .segment_readers()
+                .iter()
+                .map(|segment| (segment.segment_id(), segment.num_deleted_docs()))
+                .collect(),
+        })
+    }
+}
+
+type TopNResults = Vec<(SearchIndexScore, DocAddress)>;
+
+static CACHE: LazyLock<Mutex<LruCache<TopNCacheKey, TopNResults>>> =
+    LazyLock::new(Default::default);
+
+/// Look up cached results, marking them as the most recently used.
+pub fn get(key: &TopNCacheKey) -> Option<TopNResults> {
+    let results = CACHE.lock().get(key).cloned();
+    stats::record_cache_lookup(key.indexrelid, results.is_some());
+    results
+}
+
+/// Cache results, evicting the least recently used ones beyond `capacity`.
+pub fn insert(key: TopNCacheKey, results: TopNResults, capacity: usize) {
+    CACHE.lock().insert(key, results, capacity);
+}
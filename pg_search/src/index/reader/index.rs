@@ -21,9 +21,16 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use crate::api::{HashMap, OrderByFeature, OrderByInfo, SortDirection};
+use crate::gucs;
+use crate::index::expiry::Expiry;
 use crate::index::fast_fields_helper::FFType;
 use crate::index::mvcc::MvccSatisfies;
+use crate::index::reader::interrupt::interruptible;
+use crate::index::reader::memory::MemoryTracker;
+use crate::index::reader::query_cache::{self, QueryCacheKey};
 use crate::index::reader::scorer::{DeferredScorer, ScorerIter};
+use crate::index::reader::statistics::PartitionStatistics;
+use crate::index::reader::timings::SearchTimings;
 use crate::index::setup_tokenizers;
 use crate::postgres::rel::PgSearchRelation;
 use crate::postgres::storage::buffer::PinnedBuffer;
@@ -32,19 +39,25 @@ use crate::query::SearchQueryInput;
 use crate::schema::SearchIndexSchema;
 
 use anyhow::Result;
+use pgrx::pg_sys;
 use tantivy::collector::{Collector, Feature, FieldFeature, ScoreFeature, TopDocs, TopOrderable};
 use tantivy::index::{Index, SegmentId};
-use tantivy::query::{EnableScoring, QueryClone, QueryParser, Weight};
+use tantivy::query::{EnableScoring, QueryClone, QueryParser, Scorer, Weight};
 use tantivy::snippet::SnippetGenerator;
 use tantivy::{
-    query::Query, schema::OwnedValue, DocAddress, DocId, DocSet, Executor, IndexReader,
-    ReloadPolicy, Score, Searcher, SegmentOrdinal, SegmentReader, TantivyDocument,
+    query::Query, schema::OwnedValue, DocAddress, DocId, DocSet, IndexReader, ReloadPolicy, Score,
+    Searcher, SegmentOrdinal, SegmentReader, TantivyDocument,
 };
 
 /// The maximum number of sort-features/`OrderByInfo`s supported for
 /// `SearchIndexReader::search_top_n_in_segments`.
 pub const MAX_TOPN_FEATURES: usize = 3;
 
+/// How many doc ids of a segment [`SearchIndexReader::estimate_docs`] looks at, at most, in how
+/// many windows.
+const ESTIMATE_SAMPLE_DOCS: DocId = 65536;
+const ESTIMATE_SAMPLE_WINDOWS: DocId = 16;
+
 /// Represents a matching document from a tantivy search.  Typically, it is returned as an Iterator
 /// Item alongside the originating tantivy [`DocAddress`]
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -83,7 +96,8 @@ impl TopNSearchResults {
         Self::new(vec![])
     }
 
-    fn new(results: Vec<(SearchIndexScore, DocAddress)>) -> Self {
+    /// Results that were found before, like those of [`crate::index::reader::cache`].
+    pub fn new(results: Vec<(SearchIndexScore, DocAddress)>) -> Self {
         Self {
             results_original_len: results.len(),
             results: results.into_iter(),
@@ -134,6 +148,11 @@ impl TopNSearchResults {
     pub fn original_len(&self) -> usize {
         self.results_original_len
     }
+
+    /// The results that haven't been consumed yet.
+    pub fn remaining(&self) -> Vec<(SearchIndexScore, DocAddress)> {
+        self.results.as_slice().to_vec()
+    }
 }
 
 /// A set of search results across multiple segments.
@@ -213,6 +232,29 @@ impl Iterator for MultiSegmentSearchResults {
     }
 }
 
+/// A hot standby's searches don't pin the primary's copy of the segments they read, so the primary
+/// only knows not to reuse those segments' blocks while it's told about the standby's snapshots,
+/// which `hot_standby_feedback` does, and only takes them into account for indexes with
+/// `standby_searches` on.
+fn ensure_standby_searches(index_relation: &PgSearchRelation) -> Result<()> {
+    unsafe {
+        if !pg_sys::RecoveryInProgress() {
+            return Ok(());
+        }
+        anyhow::ensure!(
+            index_relation.options().standby_searches(),
+            "index `{}` can't be searched on a hot standby without `standby_searches = true`",
+            index_relation.name()
+        );
+        let feedback = pg_sys::GetConfigOption(c"hot_standby_feedback".as_ptr(), false, false);
+        anyhow::ensure!(
+            !feedback.is_null() && std::ffi::CStr::from_ptr(feedback) == c"on",
+            "searching a BM25 index on a hot standby requires `hot_standby_feedback = on`"
+        );
+    }
+    Ok(())
+}
+
 pub struct SearchIndexReader {
     index_rel: PgSearchRelation,
     searcher: Searcher,
@@ -221,6 +263,15 @@ pub struct SearchIndexReader {
     underlying_index: Index,
     query: Box<dyn Query>,
     need_scores: bool,
+    // if we're one partition of a partitioned index, the statistics of all the partitions, or
+    // of all the indexes being searched together
+    statistics: Option<Arc<PartitionStatistics>>,
+    // if the search is being explained with `ANALYZE`, where its time goes
+    timings: Option<Arc<SearchTimings>>,
+    // what the search holds onto, against `paradedb.work_mem`
+    memory: Arc<MemoryTracker>,
+    // whether the index has a `ttl`, so that which documents match changes with time
+    expires: bool,
 
     // [`PinnedBuffer`] has a Drop impl, so we hold onto it but don't otherwise use it
     //
@@ -239,6 +290,10 @@ impl Clone for SearchIndexReader {
             underlying_index: self.underlying_index.clone(),
             query: self.query.box_clone(),
             need_scores: self.need_scores,
+            statistics: self.statistics.clone(),
+            timings: self.timings.clone(),
+            memory: self.memory.clone(),
+            expires: self.expires,
             _cleanup_lock: self._cleanup_lock.clone(),
         }
     }
@@ -258,6 +313,8 @@ impl SearchIndexReader {
         need_scores: bool,
         mvcc_style: MvccSatisfies,
     ) -> Result<Self> {
+        ensure_standby_searches(index_relation)?;
+
         // It is possible for index only scans and custom scans, which only check the visibility map
         // and do not fetch tuples from the heap, to suffer from the concurrent TID recycling problem.
         // This problem occurs due to a race condition: after vacuum is called, a concurrent index only or custom scan
@@ -270,7 +327,16 @@ impl SearchIndexReader {
         // a pinned but unlocked buffer.
         let cleanup_lock = MetaPage::open(index_relation).cleanup_lock_pinned();
 
-        let directory = mvcc_style.directory(index_relation);
+        // a query that's restricted to some tenants needn't open the segments of any other
+        let tenants = index_relation
+            .options()
+            .tenant_field_name()
+            .and_then(|tenant_field| search_query_input.tenants(&tenant_field));
+        let memory = Arc::new(MemoryTracker::new(gucs::search_work_mem()));
+        let directory = mvcc_style
+            .directory(index_relation)
+            .for_tenants(tenants)
+            .with_memory(memory.clone());
         let mut index = Index::open(directory)?;
         let schema = index_relation.schema()?;
         setup_tokenizers(index_relation, &mut index)?;
@@ -282,22 +348,44 @@ impl SearchIndexReader {
         let searcher = reader.searcher();
 
         let need_scores = need_scores || search_query_input.need_scores();
-        let query = {
-            search_query_input
-                .into_tantivy_query(
-                    &schema,
-                    &|| {
-                        QueryParser::for_index(
-                            &index,
-                            schema.fields().map(|(field, _)| field).collect::<Vec<_>>(),
-                        )
-                    },
-                    &searcher,
-                    index_relation.oid(),
-                    index_relation.rel_oid(),
-                )
-                .unwrap_or_else(|e| panic!("{e}"))
+        let cache_entries = gucs::query_cache_entries();
+        let cache_key = (cache_entries > 0)
+            .then(|| QueryCacheKey::new(index_relation.oid(), &searcher, &search_query_input))
+            .flatten();
+        let query = match cache_key.as_ref().and_then(query_cache::get) {
+            Some(query) => query,
+            None => {
+                let query = search_query_input
+                    .into_tantivy_query(
+                        &schema,
+                        &|| {
+                            QueryParser::for_index(
+                                &index,
+                                schema.fields().map(|(field, _)| field).collect::<Vec<_>>(),
+                            )
+                        },
+                        &searcher,
+                        index_relation.oid(),
+                        index_relation.rel_oid(),
+                    )
+                    .unwrap_or_else(|e| panic!("{e}"));
+                if let Some(cache_key) = cache_key {
+                    query_cache::insert(cache_key, query.box_clone(), cache_entries);
+                }
+                query
+            }
         };
+        let expiry = Expiry::for_index(index_relation, &schema);
+        let expires = expiry.is_some();
+        let query = match expiry {
+            Some(expiry) => expiry.exclude(query),
+            None => query,
+        };
+
+        let statistics = need_scores
+            .then(|| PartitionStatistics::open(index_relation, &searcher))
+            .flatten()
+            .map(Arc::new);
 
         Ok(Self {
             index_rel: index_relation.clone(),
@@ -307,10 +395,42 @@ impl SearchIndexReader {
             underlying_index: index,
             query,
             need_scores,
+            statistics,
+            timings: None,
+            memory,
+            expires,
             _cleanup_lock: Arc::new(cleanup_lock),
         })
     }
 
+    /// Score with `statistics` instead of with the statistics of only this index.
+    pub fn with_statistics(mut self, statistics: PartitionStatistics) -> Self {
+        self.statistics = Some(Arc::new(statistics));
+        self
+    }
+
+    /// Record where the time of each search goes in `timings`.
+    pub fn with_timings(mut self, timings: Arc<SearchTimings>) -> Self {
+        self.timings = Some(timings);
+        self
+    }
+
+    pub fn timings(&self) -> Option<&SearchTimings> {
+        self.timings.as_deref()
+    }
+
+    /// What the search holds onto, of the index and of its matches.
+    pub fn memory(&self) -> &Arc<MemoryTracker> {
+        &self.memory
+    }
+
+    /// Whether searching the same segments again always finds the same documents with the same
+    /// scores, which it doesn't when they're scored with the statistics of other indexes, or
+    /// when documents expire.
+    pub fn is_repeatable(&self) -> bool {
+        self.statistics.is_none() && !self.expires
+    }
+
     pub fn segment_ids(&self) -> Vec<SegmentId> {
         self.searcher
             .segment_readers()
@@ -329,41 +449,38 @@ impl SearchIndexReader {
 
     pub fn weight(&self) -> Box<dyn Weight> {
         self.query
-            .weight(if self.need_scores {
-                tantivy::query::EnableScoring::Enabled {
-                    searcher: &self.searcher,
-                    statistics_provider: &self.searcher,
-                }
-            } else {
-                tantivy::query::EnableScoring::Disabled {
-                    schema: self.schema.tantivy_schema(),
-                    searcher_opt: Some(&self.searcher),
-                }
-            })
+            .weight(enable_scoring(
+                self.need_scores,
+                &self.searcher,
+                self.statistics.as_deref(),
+            ))
             .expect("weight should be constructable")
     }
 
     pub fn make_query(&self, search_query_input: SearchQueryInput) -> Box<dyn Query> {
-        search_query_input
-            .clone()
-            .into_tantivy_query(
-                &self.schema,
-                &|| {
-                    QueryParser::for_index(
-                        &self.underlying_index,
-                        self.schema
-                            .fields()
-                            .map(|(field, _)| field)
-                            .collect::<Vec<_>>(),
-                    )
-                },
-                &self.searcher,
-                self.index_rel.oid(),
-                self.index_rel.rel_oid(),
-            )
+        self.try_make_query(search_query_input)
             .unwrap_or_else(|e| panic!("{e}"))
     }
 
+    /// Build the tantivy query of `search_query_input`, or say why it can't search this index.
+    pub fn try_make_query(&self, search_query_input: SearchQueryInput) -> Result<Box<dyn Query>> {
+        search_query_input.into_tantivy_query(
+            &self.schema,
+            &|| {
+                QueryParser::for_index(
+                    &self.underlying_index,
+                    self.schema
+                        .fields()
+                        .map(|(field, _)| field)
+                        .collect::<Vec<_>>(),
+                )
+            },
+            &self.searcher,
+            self.index_rel.oid(),
+            self.index_rel.rel_oid(),
+        )
+    }
+
     pub fn get_doc(&self, doc_address: DocAddress) -> tantivy::Result<TantivyDocument> {
         self.searcher.doc(doc_address)
     }
@@ -449,6 +566,8 @@ impl SearchIndexReader {
                     self.need_scores,
                     segment_reader.clone(),
                     self.searcher.clone(),
+                    self.statistics.clone(),
+                    self.timings.clone(),
                 ),
                 segment_ord,
                 segment_reader.clone(),
@@ -485,7 +604,7 @@ impl SearchIndexReader {
                     .schema
                     .search_field(sort_field)
                     .expect("sort field should exist in index schema");
-                match field.field_entry().field_type().value_type() {
+                let results = match field.field_entry().field_type().value_type() {
                     tantivy::schema::Type::Str => TopNSearchResults::new_for_discarded_field(
                         &self.searcher,
                         self.top_in_segments(
@@ -557,7 +676,8 @@ impl SearchIndexReader {
                         // `SearchField::is_sortable`.
                         panic!("Unsupported order-by field type: {x:?}");
                     }
-                }
+                };
+                self.with_scores(results)
             }
             Some(OrderByInfo {
                 feature: OrderByFeature::Score,
@@ -683,15 +803,24 @@ impl SearchIndexReader {
             .and_offset(offset)
             .order_by(orderable);
         let query = self.query();
-        let weight = query
-            .weight(enable_scoring(self.need_scores, &self.searcher))
-            .expect("creating a Weight from a Query should not fail");
-
-        let top_docs = self.collect_segments(segment_ids, |segment_ord, segment_reader| {
-            collector
-                .collect_segment(weight.as_ref(), segment_ord, segment_reader)
-                .expect("should be able to collect top-n in segment")
-        });
+        let weight = interruptible(self.time_weight(|| {
+            query
+                .weight(enable_scoring(
+                    self.need_scores,
+                    &self.searcher,
+                    self.statistics.as_deref(),
+                ))
+                .expect("creating a Weight from a Query should not fail")
+        }));
+
+        let top_docs =
+            self.collect_top_n_segments(segment_ids, n + offset, |segment_ord, segment_reader| {
+                self.time_collect_segment(|| {
+                    collector
+                        .collect_segment(weight.as_ref(), segment_ord, segment_reader)
+                        .expect("should be able to collect top-n in segment")
+                })
+            });
 
         collector
             .merge_fruits(top_docs)
@@ -703,6 +832,44 @@ impl SearchIndexReader {
     /// TODO: This is a special case for a single score feature: the score-only codepath is highly
     /// specialized, and at least 50% faster than `TopDocs::order_by` when sorting on only the
     /// score. We should try to close that gap over time, but for now we special case it.
+    /// Score the documents that a search ordered by a field found, so that `paradedb.score()`
+    /// can be projected from them, if the scan needs scores.
+    ///
+    /// Only the top n documents are scored, each by seeking a scorer of its segment to it, rather
+    /// than collecting the score of every match alongside the field.
+    fn with_scores(&self, results: TopNSearchResults) -> TopNSearchResults {
+        if !self.need_scores {
+            return results;
+        }
+
+        let mut results = results.remaining();
+        let weight = self.weight();
+        let mut order = (0..results.len()).collect::<Vec<_>>();
+        order.sort_unstable_by_key(|i| results[*i].1);
+
+        let mut scorer: Option<(SegmentOrdinal, Box<dyn Scorer>)> = None;
+        for i in order {
+            let doc_address = results[i].1;
+            if scorer.as_ref().map(|(segment_ord, _)| *segment_ord) != Some(doc_address.segment_ord)
+            {
+                let segment_reader = self.searcher.segment_reader(doc_address.segment_ord);
+                let segment_scorer = weight
+                    .scorer(segment_reader, 1.0)
+                    .expect("creating a Scorer from a Weight should not fail");
+                scorer = Some((doc_address.segment_ord, segment_scorer));
+            }
+
+            // the documents are in order, and every one of them matches
+            let (_, scorer) = scorer.as_mut().unwrap();
+            if scorer.doc() <= doc_address.doc_id
+                && scorer.seek(doc_address.doc_id) == doc_address.doc_id
+            {
+                results[i].0.bm25 = scorer.score();
+            }
+        }
+        TopNSearchResults::new(results)
+    }
+
     fn top_by_score_in_segments(
         &self,
         segment_ids: impl Iterator<Item = SegmentId>,
@@ -713,13 +880,15 @@ impl SearchIndexReader {
         match sortdir {
             // requires tweaking the score, which is a bit slower
             SortDirection::Asc => {
-                let weight = self
-                    .query
-                    .weight(tantivy::query::EnableScoring::Enabled {
-                        searcher: &self.searcher,
-                        statistics_provider: &self.searcher,
-                    })
-                    .expect("creating a Weight from a Query should not fail");
+                let weight = interruptible(self.time_weight(|| {
+                    self.query
+                        .weight(enable_scoring(
+                            true,
+                            &self.searcher,
+                            self.statistics.as_deref(),
+                        ))
+                        .expect("creating a Weight from a Query should not fail")
+                }));
 
                 let collector = TopDocs::with_limit(n).and_offset(offset).tweak_score(
                     move |_segment_reader: &tantivy::SegmentReader| {
@@ -729,11 +898,17 @@ impl SearchIndexReader {
                     },
                 );
 
-                let top_docs = self.collect_segments(segment_ids, |segment_ord, segment_reader| {
-                    collector
-                        .collect_segment(weight.as_ref(), segment_ord, segment_reader)
-                        .expect("should be able to collect top-n in segment")
-                });
+                let top_docs = self.collect_top_n_segments(
+                    segment_ids,
+                    n + offset,
+                    |segment_ord, segment_reader| {
+                        self.time_collect_segment(|| {
+                            collector
+                                .collect_segment(weight.as_ref(), segment_ord, segment_reader)
+                                .expect("should be able to collect top-n in segment")
+                        })
+                    },
+                );
 
                 let top_docs = collector
                     .merge_fruits(top_docs)
@@ -749,21 +924,29 @@ impl SearchIndexReader {
 
             // can use tantivy's score directly
             SortDirection::Desc => {
-                let weight = self
-                    .query
-                    .weight(tantivy::query::EnableScoring::Enabled {
-                        searcher: &self.searcher,
-                        statistics_provider: &self.searcher,
-                    })
-                    .expect("creating a Weight from a Query should not fail");
+                let weight = interruptible(self.time_weight(|| {
+                    self.query
+                        .weight(enable_scoring(
+                            true,
+                            &self.searcher,
+                            self.statistics.as_deref(),
+                        ))
+                        .expect("creating a Weight from a Query should not fail")
+                }));
 
                 let collector = TopDocs::with_limit(n).and_offset(offset);
 
-                let top_docs = self.collect_segments(segment_ids, |segment_ord, segment_reader| {
-                    collector
-                        .collect_segment(weight.as_ref(), segment_ord, segment_reader)
-                        .expect("should be able to collect top-n in segment")
-                });
+                let top_docs = self.collect_top_n_segments(
+                    segment_ids,
+                    n + offset,
+                    |segment_ord, segment_reader| {
+                        self.time_collect_segment(|| {
+                            collector
+                                .collect_segment(weight.as_ref(), segment_ord, segment_reader)
+                                .expect("should be able to collect top-n in segment")
+                        })
+                    },
+                );
 
                 let top_docs = collector
                     .merge_fruits(top_docs)
@@ -774,6 +957,12 @@ impl SearchIndexReader {
         }
     }
 
+    /// Estimate how many of `total_docs` documents the query matches, from the largest segment.
+    ///
+    /// A scorer's `size_hint` is exact for one term, but for a boolean query it's only a bound,
+    /// like the smallest of its musts, which can be off by orders of magnitude.  Large segments
+    /// are sampled instead: the matches are counted in evenly spaced windows of doc ids, skipping
+    /// to each window with `seek`, so that the estimate costs about the same on any segment.
     pub fn estimate_docs(&self, total_docs: f64) -> Option<usize> {
         debug_assert!(self.searcher.segment_readers().len() == 1, "estimate_docs(): expected an index with only one segment, which is assumed to be the largest segment by num_docs");
         let largest_reader = self.searcher.segment_reader(0);
@@ -782,26 +971,78 @@ impl SearchIndexReader {
             .scorer(largest_reader, 1.0)
             .expect("counting docs in the largest segment should not fail");
 
-        // investigate the size_hint.  it will often give us a good enough value
-        let mut count = scorer.size_hint() as usize;
-        if count == 0 {
-            // but when it doesn't, we need to do a full count
-            count = scorer.count_including_deleted() as usize;
-        }
+        let max_doc = largest_reader.max_doc();
+        let count = if max_doc <= ESTIMATE_SAMPLE_DOCS {
+            scorer.count_including_deleted() as f64
+        } else {
+            let stride = max_doc / ESTIMATE_SAMPLE_WINDOWS;
+            let window = ESTIMATE_SAMPLE_DOCS / ESTIMATE_SAMPLE_WINDOWS;
+            let mut sampled = 0;
+            for start in (0..ESTIMATE_SAMPLE_WINDOWS).map(|i| i * stride) {
+                if scorer.doc() < start {
+                    scorer.seek(start);
+                }
+                while scorer.doc() < start + window {
+                    sampled += 1;
+                    scorer.advance();
+                }
+            }
+            sampled as f64 * max_doc as f64 / (window * ESTIMATE_SAMPLE_WINDOWS) as f64
+        };
         let segment_doc_proportion = largest_reader.num_docs() as f64 / total_docs;
 
-        Some((count as f64 / segment_doc_proportion).ceil() as usize)
+        Some((count / segment_doc_proportion).ceil() as usize)
     }
 
-    pub fn collect<C: Collector>(&self, collector: C) -> C::Fruit {
+    /// The number of live documents that `search_query_input` matches on its own.
+    pub fn count(&self, search_query_input: SearchQueryInput) -> u64 {
+        let weight = self
+            .make_query(search_query_input)
+            .weight(EnableScoring::disabled_from_searcher(&self.searcher))
+            .expect("creating a Weight from a Query should not fail");
         self.searcher
-            .search_with_executor(
-                &self.query,
-                &collector,
-                &Executor::SingleThread,
-                enable_scoring(self.need_scores, &self.searcher),
-            )
-            .expect("search should not fail")
+            .segment_readers()
+            .iter()
+            .map(|segment_reader| {
+                weight
+                    .count(segment_reader)
+                    .expect("counting the docs in a segment should not fail") as u64
+            })
+            .sum()
+    }
+
+    pub fn collect<C: Collector>(&self, collector: C) -> C::Fruit {
+        self.try_collect(collector).expect("search should not fail")
+    }
+
+    /// Like [`SearchIndexReader::collect`], but returns the errors of collectors that can fail,
+    /// like an aggregation that runs out of buckets.
+    pub fn try_collect<C: Collector>(&self, collector: C) -> tantivy::Result<C::Fruit> {
+        // this is what `Searcher::search_with_executor` does on a single thread, but with a
+        // weight that can be interrupted
+        let weight = interruptible(
+            self.query
+                .weight(enable_scoring(
+                    self.need_scores,
+                    &self.searcher,
+                    self.statistics.as_deref(),
+                ))
+                .expect("creating a Weight from a Query should not fail"),
+        );
+        let segment_fruits = self
+            .searcher
+            .segment_readers()
+            .iter()
+            .enumerate()
+            .map(|(segment_ord, segment_reader)| {
+                collector.collect_segment(
+                    weight.as_ref(),
+                    segment_ord as SegmentOrdinal,
+                    segment_reader,
+                )
+            })
+            .collect::<tantivy::Result<Vec<_>>>()?;
+        collector.merge_fruits(segment_fruits)
     }
 
     /// Create erased Features for the given OrderByInfo.
@@ -848,6 +1089,42 @@ impl SearchIndexReader {
             .collect()
     }
 
+    fn time_weight<T>(&self, f: impl FnOnce() -> T) -> T {
+        match &self.timings {
+            Some(timings) => timings.time_weight(f),
+            None => f(),
+        }
+    }
+
+    /// Collect the matches of one segment with `f`, counting the segment as visited.
+    fn time_collect_segment<T>(&self, f: impl FnOnce() -> T) -> T {
+        match &self.timings {
+            Some(timings) => {
+                timings.visit_segment();
+                timings.time_collect(f)
+            }
+            None => f(),
+        }
+    }
+
+    /// Like `collect_segments`, for a top-n `collect`, whose buffer of the top `limit` matches of
+    /// a segment counts against the search's memory while it's collected.
+    fn collect_top_n_segments<T>(
+        &self,
+        segment_ids: impl Iterator<Item = SegmentId>,
+        limit: usize,
+        mut collect: impl FnMut(SegmentOrdinal, &SegmentReader) -> Vec<T>,
+    ) -> Vec<Vec<T>> {
+        // tantivy keeps up to twice as many matches as it's asked for between truncations
+        let buffer = limit.saturating_mul(2 * std::mem::size_of::<T>());
+        self.collect_segments(segment_ids, |segment_ord, segment_reader| {
+            self.memory.reserve(buffer, "collecting the top matches");
+            let top_docs = collect(segment_ord, segment_reader);
+            self.memory.release(buffer);
+            top_docs
+        })
+    }
+
     fn collect_segments<T>(
         &self,
         segment_ids: impl Iterator<Item = SegmentId>,
@@ -868,9 +1145,20 @@ impl SearchIndexReader {
     }
 }
 
-pub(super) fn enable_scoring(need_scores: bool, searcher: &Searcher) -> EnableScoring {
+/// Scoring with the statistics of all of a partitioned index's partitions, if we have them, or
+/// otherwise with those of `searcher`.
+pub(super) fn enable_scoring<'a>(
+    need_scores: bool,
+    searcher: &'a Searcher,
+    statistics: Option<&'a PartitionStatistics>,
+) -> EnableScoring<'a> {
     if need_scores {
-        EnableScoring::enabled_from_searcher(searcher)
+        match statistics {
+            Some(statistics) => {
+                EnableScoring::enabled_from_statistics_provider(statistics, searcher)
+            }
+            None => EnableScoring::enabled_from_searcher(searcher),
+        }
     } else {
         EnableScoring::disabled_from_searcher(searcher)
     }
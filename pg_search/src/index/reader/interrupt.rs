@@ -0,0 +1,89 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Letting Postgres cancel a search while tantivy is collecting its matches.
+//!
+//! Tantivy collects a segment in one tight loop that never returns to Postgres, so a query that
+//! matches a great many documents, like a broad regex under a large aggregation, couldn't be
+//! cancelled, nor stopped by `statement_timeout`, until it had visited every one of them.  An
+//! [`InterruptibleWeight`] checks for a pending interrupt each time the loop hands it a match,
+//! which is no more than a read of `InterruptPending` until there is one.
+//!
+//! The checks raise the cancellation as a Postgres `ERROR`, so the weight must only be used from
+//! the backend's own thread, as [`Executor::SingleThread`](tantivy::Executor::SingleThread) does.
+
+use pgrx::check_for_interrupts;
+use tantivy::query::{Explanation, Scorer, Weight};
+use tantivy::{DocId, Score, SegmentReader};
+
+/// Wrap `weight` so that collecting its matches checks for interrupts.
+pub fn interruptible(weight: Box<dyn Weight>) -> Box<dyn Weight> {
+    Box::new(InterruptibleWeight(weight))
+}
+
+pub struct InterruptibleWeight(Box<dyn Weight>);
+
+impl Weight for InterruptibleWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        self.0.scorer(reader, boost)
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        self.0.explain(reader, doc)
+    }
+
+    fn count(&self, reader: &SegmentReader) -> tantivy::Result<u32> {
+        self.0.count(reader)
+    }
+
+    fn for_each(
+        &self,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score),
+    ) -> tantivy::Result<()> {
+        self.0.for_each(reader, &mut |doc, score| {
+            check_for_interrupts!();
+            callback(doc, score)
+        })
+    }
+
+    fn for_each_no_score(
+        &self,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(&[DocId]),
+    ) -> tantivy::Result<()> {
+        self.0.for_each_no_score(reader, &mut |docs| {
+            check_for_interrupts!();
+            callback(docs)
+        })
+    }
+
+    fn for_each_pruning(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+    ) -> tantivy::Result<()> {
+        // only the documents that could enter the top-n reach `callback`, so a cancellation may
+        // wait out a run of uncompetitive ones, which the inner weight skips over in blocks
+        self.0
+            .for_each_pruning(threshold, reader, &mut |doc, score| {
+                check_for_interrupts!();
+                callback(doc, score)
+            })
+    }
+}
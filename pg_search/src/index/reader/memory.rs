@@ -0,0 +1,140 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! How much memory a search holds onto, for `EXPLAIN ANALYZE` and `paradedb.work_mem`.
+//!
+//! Tantivy copies whatever it reads of an index, like a term dictionary or a block of postings,
+//! out of the buffer cache and into memory of its own, which it keeps for as long as it's using
+//! it.  Its collectors keep the matches they've collected so far.  A [`MemoryTracker`] adds both
+//! up, and raises an `ERROR` as soon as they exceed `paradedb.work_mem`, rather than letting the
+//! search grow until the backend is killed for running out of memory.
+
+use pgrx::pg_sys::panic::ErrorReport;
+use pgrx::{function_name, PgLogLevel, PgSqlErrorCode};
+use stable_deref_trait::StableDeref;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, Default)]
+pub struct MemoryTracker {
+    limit: Option<usize>,
+    used: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl MemoryTracker {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    /// Count `bytes` more as used, for `what`, failing the search if that's more than its limit.
+    pub fn reserve(&self, bytes: usize, what: &str) {
+        let used = self.used.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        self.peak.fetch_max(used, Ordering::Relaxed);
+
+        if let Some(limit) = self.limit.filter(|limit| used > *limit) {
+            self.used.fetch_sub(bytes, Ordering::Relaxed);
+            ErrorReport::new(
+                PgSqlErrorCode::ERRCODE_PROGRAM_LIMIT_EXCEEDED,
+                format!(
+                    "search exceeded `paradedb.work_mem` of {}",
+                    format_kb(limit)
+                ),
+                function_name!(),
+            )
+            .set_detail(format!(
+                "{what} needed another {} with {} already in use",
+                format_kb(bytes),
+                format_kb(used - bytes)
+            ))
+            .set_hint("increase `paradedb.work_mem`, or make the search more selective")
+            .report(PgLogLevel::ERROR);
+        }
+    }
+
+    pub fn release(&self, bytes: usize) {
+        self.used.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// The most that was ever in use at once.
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+}
+
+fn format_kb(bytes: usize) -> String {
+    format!("{}kB", bytes.div_ceil(1024))
+}
+
+/// Bytes read from the index that count against a [`MemoryTracker`] until they're dropped.
+pub struct TrackedBytes {
+    bytes: Vec<u8>,
+    memory: Arc<MemoryTracker>,
+}
+
+impl TrackedBytes {
+    pub fn new(bytes: Vec<u8>, memory: Arc<MemoryTracker>) -> Self {
+        memory.reserve(bytes.len(), "reading the index");
+        Self { bytes, memory }
+    }
+}
+
+impl Deref for TrackedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+// SAFETY: the bytes are on the heap, where they stay no matter where the `TrackedBytes` moves
+unsafe impl StableDeref for TrackedBytes {}
+
+impl Drop for TrackedBytes {
+    fn drop(&mut self) {
+        self.memory.release(self.bytes.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_tracked_bytes_are_released() {
+        let memory = Arc::new(MemoryTracker::new(None));
+        let first = TrackedBytes::new(vec![0; 100], memory.clone());
+        let second = TrackedBytes::new(vec![0; 50], memory.clone());
+        assert_eq!(memory.used(), 150);
+
+        drop(first);
+        assert_eq!(memory.used(), 50);
+        assert_eq!(memory.peak(), 150);
+
+        drop(second);
+        assert_eq!(memory.used(), 0);
+    }
+}
@@ -1,3 +1,10 @@
+pub mod cache;
 pub mod index;
+pub mod interrupt;
+pub mod memory;
+pub mod parallel;
+pub mod query_cache;
 pub mod scorer;
 pub mod segment_component;
+pub mod statistics;
+pub mod timings;
@@ -0,0 +1,352 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Collecting the top n matches by score of many segments with parallel workers.
+//!
+//! A scan that Postgres doesn't plan as parallel, like one that's expected to return few rows,
+//! still has to score every match in every segment of the index to find its top n, all in one
+//! backend.  With `paradedb.enable_parallel_collection`, the scan instead launches up to
+//! `max_parallel_workers_per_gather` workers, which check out segments the same way the workers
+//! of [`crate::aggregate`] do, and each sends back the top `n + offset` of its own segments.  The
+//! leader collects its share too, and merges them all into the overall top n.
+//!
+//! Tantivy can't read Postgres buffers from threads of its own, so the workers are processes.
+
+use crate::api::{HashSet, OrderByFeature, OrderByInfo, SortDirection};
+use crate::index::mvcc::MvccSatisfies;
+use crate::index::reader::index::{SearchIndexReader, SearchIndexScore, TopNSearchResults};
+use crate::launch_parallel_process;
+use crate::parallel_worker::mqueue::MessageQueueSender;
+use crate::parallel_worker::ParallelStateManager;
+use crate::parallel_worker::{chunk_range, QueryWorkerStyle, WorkerStyle};
+use crate::parallel_worker::{ParallelProcess, ParallelState, ParallelStateType, ParallelWorker};
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::spinlock::Spinlock;
+use crate::query::SearchQueryInput;
+use pgrx::{check_for_interrupts, pg_sys};
+use tantivy::index::SegmentId;
+use tantivy::{DocAddress, DocId, Score, SegmentOrdinal};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct State {
+    // these require the Spinlock mutex for atomic access (read and write)
+    mutex: Spinlock,
+    nlaunched: usize,
+    remaining_segments: usize,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct Config {
+    indexrelid: pg_sys::Oid,
+    total_segments: usize,
+    limit: usize,
+    sortdir: SortDirection,
+}
+
+impl State {
+    fn set_launched_workers(&mut self, nlaunched: usize) {
+        let _lock = self.mutex.acquire();
+        self.nlaunched = nlaunched;
+    }
+
+    fn launched_workers(&mut self) -> usize {
+        let _lock = self.mutex.acquire();
+        self.nlaunched
+    }
+}
+
+impl ParallelStateType for State {}
+impl ParallelStateType for Config {}
+
+struct ParallelTopN {
+    state: State,
+    config: Config,
+    query_bytes: Vec<u8>,
+    segment_ids: Vec<SegmentId>,
+}
+
+impl ParallelProcess for ParallelTopN {
+    fn state_values(&self) -> Vec<&dyn ParallelState> {
+        vec![
+            &self.state,
+            &self.config,
+            &self.query_bytes,
+            &self.segment_ids,
+        ]
+    }
+}
+
+/// The top matches of one worker's segments, as a worker sends them to the leader.  Segments
+/// are sent by id, because each process numbers its segments in its own order.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SegmentTopN {
+    segment_id: String,
+    docs: Vec<(DocId, Score, u64)>,
+}
+
+struct ParallelTopNWorker<'a> {
+    state: &'a mut State,
+    config: Config,
+    query: SearchQueryInput,
+    segment_ids: Vec<SegmentId>,
+}
+
+impl ParallelTopNWorker<'_> {
+    fn checkout_segments(&mut self, worker_number: i32) -> HashSet<SegmentId> {
+        let nworkers = self.state.launched_workers();
+        let nsegments = self.config.total_segments;
+
+        let mut segment_ids = HashSet::default();
+        let (_, many_segments) = chunk_range(nsegments, nworkers, worker_number as usize);
+        while let Some(segment_id) = self.checkout_segment() {
+            segment_ids.insert(segment_id);
+
+            if segment_ids.len() == many_segments {
+                // we have all the segments we need
+                break;
+            }
+        }
+        segment_ids
+    }
+
+    fn checkout_segment(&mut self) -> Option<SegmentId> {
+        let _lock = self.state.mutex.acquire();
+        if self.state.remaining_segments == 0 {
+            return None;
+        }
+        self.state.remaining_segments -= 1;
+        self.segment_ids.get(self.state.remaining_segments).cloned()
+    }
+
+    /// Collect the top matches of the segments this worker checks out with a reader of its own.
+    fn top_n(&mut self, worker_style: QueryWorkerStyle) -> anyhow::Result<Vec<SegmentTopN>> {
+        let segment_ids = self.checkout_segments(worker_style.worker_number());
+        if segment_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        // the reader sees every segment, which the leader holds pinned, so that the top
+        // matches of our segments are scored with the statistics of the whole index
+        let indexrel =
+            PgSearchRelation::with_lock(self.config.indexrelid, pg_sys::AccessShareLock as _);
+        let reader = SearchIndexReader::open(
+            &indexrel,
+            self.query.clone(),
+            true,
+            MvccSatisfies::ParallelWorker(self.segment_ids.iter().cloned().collect()),
+        )?;
+
+        let mut results = reader
+            .search_top_n_in_segments(
+                segment_ids.into_iter(),
+                Some(&orderby_score(self.config.sortdir)),
+                self.config.limit,
+                0,
+            )
+            .remaining();
+        results.sort_unstable_by_key(|(_, doc_address)| *doc_address);
+
+        let segment_readers = reader.segment_readers();
+        Ok(results
+            .chunk_by(|(_, a), (_, b)| a.segment_ord == b.segment_ord)
+            .map(|docs| SegmentTopN {
+                segment_id: segment_readers[docs[0].1.segment_ord as usize]
+                    .segment_id()
+                    .uuid_string(),
+                docs: docs
+                    .iter()
+                    .map(|(scored, doc_address)| (doc_address.doc_id, scored.bm25, scored.ctid))
+                    .collect(),
+            })
+            .collect())
+    }
+}
+
+impl ParallelWorker for ParallelTopNWorker<'_> {
+    fn new_parallel_worker(state_manager: ParallelStateManager) -> Self {
+        let state = state_manager
+            .object::<State>(0)
+            .expect("wrong type for state")
+            .expect("missing state value");
+        let config = state_manager
+            .object::<Config>(1)
+            .expect("wrong type for config")
+            .expect("missing config value");
+        let query_bytes = state_manager
+            .slice::<u8>(2)
+            .expect("wrong type for query_bytes")
+            .expect("missing query_bytes value");
+        let segment_ids = state_manager
+            .slice::<SegmentId>(3)
+            .expect("wrong type for segment_ids")
+            .expect("missing segment_ids value");
+
+        let query = serde_json::from_slice::<SearchQueryInput>(query_bytes)
+            .expect("query_bytes should deserialize into an SearchQueryInput");
+        Self {
+            state,
+            config: *config,
+            query,
+            segment_ids: segment_ids.to_vec(),
+        }
+    }
+
+    fn run(mut self, mq_sender: &MessageQueueSender, worker_number: i32) -> anyhow::Result<()> {
+        // wait for all workers to launch
+        while self.state.launched_workers() == 0 {
+            check_for_interrupts!();
+            std::thread::yield_now();
+        }
+
+        let results = self.top_n(QueryWorkerStyle::ParallelWorker(worker_number))?;
+        if results.is_empty() {
+            return Ok(());
+        }
+        Ok(mq_sender.send(postcard::to_allocvec(&results)?)?)
+    }
+}
+
+/// The top `n` matches of `query` in all of `reader`'s segments, ordered by score in
+/// `sortdir`, after the first `offset`, collected by parallel workers and this backend.
+/// Returns [`None`] if no workers could be launched.
+pub fn search_top_n_by_score(
+    reader: &SearchIndexReader,
+    indexrelid: pg_sys::Oid,
+    query: &SearchQueryInput,
+    sortdir: SortDirection,
+    n: usize,
+    offset: usize,
+) -> anyhow::Result<Option<TopNSearchResults>> {
+    let segment_ids = reader.segment_ids();
+    let limit = n + offset;
+    let process = ParallelTopN {
+        state: State {
+            mutex: Spinlock::new(),
+            nlaunched: 0,
+            remaining_segments: segment_ids.len(),
+        },
+        config: Config {
+            indexrelid,
+            total_segments: segment_ids.len(),
+            limit,
+            sortdir,
+        },
+        query_bytes: serde_json::to_vec(query)?,
+        segment_ids: segment_ids.clone(),
+    };
+
+    unsafe {
+        // limit number of workers to the number of segments, the leader being one of them
+        let mut nworkers =
+            (pg_sys::max_parallel_workers_per_gather as usize).min(segment_ids.len());
+        if nworkers > 0 && pg_sys::parallel_leader_participation {
+            nworkers -= 1;
+        }
+        if nworkers == 0 {
+            return Ok(None);
+        }
+
+        let Some(mut process) = launch_parallel_process!(
+            ParallelTopN<ParallelTopNWorker>,
+            process,
+            WorkerStyle::Query,
+            nworkers,
+            65536
+        ) else {
+            return Ok(None);
+        };
+
+        // signal our workers with the number of workers actually launched
+        // they need this before they can begin checking out the correct segment counts
+        let mut nlaunched = process.launched_workers();
+        if pg_sys::parallel_leader_participation {
+            nlaunched += 1;
+        }
+        process
+            .state_manager_mut()
+            .object::<State>(0)?
+            .unwrap()
+            .set_launched_workers(nlaunched);
+
+        // leader participation, with the scan's own reader
+        let mut results = vec![];
+        if pg_sys::parallel_leader_participation {
+            let mut worker = ParallelTopNWorker::new_parallel_worker(*process.state_manager());
+            let segment_ids =
+                worker.checkout_segments(QueryWorkerStyle::ParallelLeader.worker_number());
+            if !segment_ids.is_empty() {
+                results.extend(
+                    reader
+                        .search_top_n_in_segments(
+                            segment_ids.into_iter(),
+                            Some(&orderby_score(sortdir)),
+                            limit,
+                            0,
+                        )
+                        .remaining(),
+                );
+            }
+        }
+
+        // wait for workers to finish, placing their matches in the leader's segments
+        let segment_ords = reader
+            .segment_readers()
+            .iter()
+            .enumerate()
+            .map(|(segment_ord, segment_reader)| {
+                (
+                    segment_reader.segment_id().uuid_string(),
+                    segment_ord as SegmentOrdinal,
+                )
+            })
+            .collect::<crate::api::HashMap<_, _>>();
+        for (_worker_number, message) in process {
+            for segment in postcard::from_bytes::<Vec<SegmentTopN>>(&message)? {
+                let segment_ord = *segment_ords
+                    .get(&segment.segment_id)
+                    .unwrap_or_else(|| panic!("segment {} should exist", segment.segment_id));
+                results.extend(segment.docs.into_iter().map(|(doc_id, bm25, ctid)| {
+                    (
+                        SearchIndexScore { ctid, bm25 },
+                        DocAddress::new(segment_ord, doc_id),
+                    )
+                }));
+            }
+        }
+
+        // the same order as collecting every segment in this backend would give
+        results.sort_unstable_by(|(a, a_doc), (b, b_doc)| {
+            let by_score = if matches!(sortdir, SortDirection::Asc) {
+                a.bm25.total_cmp(&b.bm25)
+            } else {
+                b.bm25.total_cmp(&a.bm25)
+            };
+            by_score.then_with(|| a_doc.cmp(b_doc))
+        });
+        Ok(Some(TopNSearchResults::new(
+            results.into_iter().skip(offset).take(n).collect(),
+        )))
+    }
+}
+
+fn orderby_score(direction: SortDirection) -> Vec<OrderByInfo> {
+    vec![OrderByInfo {
+        feature: OrderByFeature::Score,
+        direction,
+    }]
+}
@@ -0,0 +1,89 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! A per-backend cache of translated tantivy queries.
+//!
+//! Every execution of a prepared statement translates its [`SearchQueryInput`] again, which
+//! means parsing any query strings and compiling any regexes, before the search itself can
+//! start.  For a point search that's run thousands of times a second that can be most of the
+//! work, so translated queries are cached by the input they were translated from.
+//!
+//! Some queries look at the index while they're translated, like `more_like_this` reading its
+//! document, so the key also has the segments that the searcher could see.  Weights aren't
+//! cached: they're created from the statistics of the searcher's segments, and their scorers
+//! hold onto that searcher's postings.
+
+use crate::gucs;
+use crate::index::lru::LruCache;
+use crate::postgres::stats;
+use crate::query::SearchQueryInput;
+use parking_lot::Mutex;
+use pgrx::pg_sys;
+use std::sync::LazyLock;
+use tantivy::index::SegmentId;
+use tantivy::query::{Query, QueryClone};
+use tantivy::Searcher;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryCacheKey {
+    indexrelid: pg_sys::Oid,
+    query: String,
+    segments: Vec<(SegmentId, u32)>,
+    /// Phrases of fields without positions are only queries while `paradedb.phrase_fallback` is on.
+    phrase_fallback: bool,
+}
+
+impl QueryCacheKey {
+    /// The key of `query`'s translation, if it can be cached.
+    pub fn new(
+        indexrelid: pg_sys::Oid,
+        searcher: &Searcher,
+        query: &SearchQueryInput,
+    ) -> Option<Self> {
+        // an expression is translated with the values it has in this execution, and another
+        // index's matches with the segments it has
+        if query.depends_on_heap() || query.searches_other_index() {
+            return None;
+        }
+
+        Some(Self {
+            indexrelid,
+            query: serde_json::to_string(query).ok()?,
+            segments: searcher
+                .segment_readers()
+                .iter()
+                .map(|segment| (segment.segment_id(), segment.num_deleted_docs()))
+                .collect(),
+            phrase_fallback: gucs::phrase_fallback(),
+        })
+    }
+}
+
+static CACHE: LazyLock<Mutex<LruCache<QueryCacheKey, Box<dyn Query>>>> =
+    LazyLock::new(Default::default);
+
+/// Look up a cached query, marking it as the most recently used.
+pub fn get(key: &QueryCacheKey) -> Option<Box<dyn Query>> {
+    let query = CACHE.lock().get(key).map(|query| query.box_clone());
+    stats::record_cache_lookup(key.indexrelid, query.is_some());
+    query
+}
+
+/// Cache a translated query, evicting the least recently used ones beyond `capacity`.
+pub fn insert(key: QueryCacheKey, query: Box<dyn Query>, capacity: usize) {
+    CACHE.lock().insert(key, query, capacity);
+}
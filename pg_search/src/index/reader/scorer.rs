@@ -16,6 +16,10 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use crate::index::reader::index::enable_scoring;
+use crate::index::reader::statistics::PartitionStatistics;
+use crate::index::reader::timings::SearchTimings;
+use pgrx::check_for_interrupts;
+use std::sync::Arc;
 use std::sync::OnceLock;
 use tantivy::query::{Query, Scorer};
 use tantivy::{DocAddress, DocId, DocSet, Score, Searcher, SegmentOrdinal, SegmentReader};
@@ -25,6 +29,8 @@ pub struct DeferredScorer {
     need_scores: bool,
     segment_reader: SegmentReader,
     searcher: Searcher,
+    statistics: Option<Arc<PartitionStatistics>>,
+    timings: Option<Arc<SearchTimings>>,
     scorer: OnceLock<Box<dyn Scorer>>,
 }
 
@@ -34,12 +40,16 @@ impl DeferredScorer {
         need_scores: bool,
         segment_reader: SegmentReader,
         searcher: Searcher,
+        statistics: Option<Arc<PartitionStatistics>>,
+        timings: Option<Arc<SearchTimings>>,
     ) -> Self {
         Self {
             query,
             need_scores,
             segment_reader,
             searcher,
+            statistics,
+            timings,
             scorer: Default::default(),
         }
     }
@@ -57,10 +67,22 @@ impl DeferredScorer {
     #[inline(always)]
     fn scorer(&self) -> &dyn Scorer {
         self.scorer.get_or_init(|| {
-            let weight = self
-                .query
-                .weight(enable_scoring(self.need_scores, &self.searcher))
-                .expect("weight should be constructable");
+            let make_weight = || {
+                self.query
+                    .weight(enable_scoring(
+                        self.need_scores,
+                        &self.searcher,
+                        self.statistics.as_deref(),
+                    ))
+                    .expect("weight should be constructable")
+            };
+            let weight = match &self.timings {
+                Some(timings) => {
+                    timings.visit_segment();
+                    timings.time_weight(make_weight)
+                }
+                None => make_weight(),
+            };
 
             weight
                 .scorer(&self.segment_reader, 1.0)
@@ -114,13 +136,12 @@ impl ScorerIter {
     pub fn segment_ord(&self) -> SegmentOrdinal {
         self.segment_ord
     }
-}
-
-impl Iterator for ScorerIter {
-    type Item = (Score, DocAddress);
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_alive(&mut self) -> Option<(Score, DocAddress)> {
         loop {
+            // a segment of mostly deleted docs could otherwise keep us here for a long time
+            check_for_interrupts!();
+
             let doc_id = self.deferred.doc();
 
             if doc_id == tantivy::TERMINATED {
@@ -148,6 +169,17 @@ impl Iterator for ScorerIter {
             self.deferred.advance();
         }
     }
+}
+
+impl Iterator for ScorerIter {
+    type Item = (Score, DocAddress);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.deferred.timings.clone() {
+            Some(timings) => timings.time_collect(|| self.next_alive()),
+            None => self.next_alive(),
+        }
+    }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
         // NOTE: We do not implement size_hint for `ScorerIter`, because the implementation of
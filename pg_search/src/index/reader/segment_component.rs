@@ -1,3 +1,4 @@
+use crate::index::reader::memory::{MemoryTracker, TrackedBytes};
 use crate::postgres::rel::PgSearchRelation;
 use crate::postgres::storage::block::FileEntry;
 use crate::postgres::storage::linked_bytes::RangeData;
@@ -5,6 +6,7 @@ use crate::postgres::storage::LinkedBytesList;
 use anyhow::Result;
 use std::io::Error;
 use std::ops::Range;
+use std::sync::Arc;
 use tantivy::directory::FileHandle;
 use tantivy::directory::OwnedBytes;
 use tantivy::HasLen;
@@ -13,13 +15,24 @@ use tantivy::HasLen;
 pub struct SegmentComponentReader {
     block_list: LinkedBytesList,
     entry: FileEntry,
+    memory: Option<Arc<MemoryTracker>>,
 }
 
 impl SegmentComponentReader {
     pub unsafe fn new(indexrel: &PgSearchRelation, entry: FileEntry) -> Self {
         let block_list = LinkedBytesList::open(indexrel, entry.starting_block);
 
-        Self { block_list, entry }
+        Self {
+            block_list,
+            entry,
+            memory: None,
+        }
+    }
+
+    /// Count the bytes that are read against `memory` for as long as they're kept.
+    pub fn with_memory(mut self, memory: Option<Arc<MemoryTracker>>) -> Self {
+        self.memory = memory;
+        self
     }
 
     fn read_bytes_raw(&self, range: Range<usize>) -> Result<RangeData, Error> {
@@ -38,7 +51,10 @@ impl FileHandle for SegmentComponentReader {
         let range_data = self.read_bytes_raw(range)?;
         let bytes =
             unsafe { std::slice::from_raw_parts(range_data.as_ptr(), range_data.len()).to_vec() };
-        Ok(OwnedBytes::new(bytes))
+        match &self.memory {
+            Some(memory) => Ok(OwnedBytes::new(TrackedBytes::new(bytes, memory.clone()))),
+            None => Ok(OwnedBytes::new(bytes)),
+        }
     }
 }
 
@@ -0,0 +1,147 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! BM25 statistics that span every partition of a partitioned table.
+//!
+//! Each partition of a partitioned table has its own BM25 index, so by default a row's score only
+//! reflects how common its terms are in its own partition.  When a query's results are merged
+//! across partitions, for instance by `ORDER BY paradedb.score(id) DESC LIMIT 10` over the whole
+//! table, scores that were computed against different statistics can't be meaningfully compared.
+//! [`PartitionStatistics`] sums the statistics of all of the partitions instead, so that each row
+//! is scored as if the table weren't partitioned.
+//!
+//! The same goes for `paradedb.multi_search`, which merges the results of several unrelated
+//! indexes into one ranking.
+
+use crate::gucs;
+use crate::index::mvcc::MvccSatisfies;
+use crate::postgres::index::IndexKind;
+use crate::postgres::rel::PgSearchRelation;
+
+use pgrx::{pg_sys, Spi};
+use tantivy::query::Bm25StatisticsProvider;
+use tantivy::schema::{Field, Schema};
+use tantivy::{Index, IndexReader, ReloadPolicy, Searcher, Term};
+
+struct Sibling {
+    searcher: Searcher,
+    schema: Schema,
+}
+
+pub struct PartitionStatistics {
+    searcher: Searcher,
+    schema: Schema,
+    siblings: Vec<Sibling>,
+}
+
+impl PartitionStatistics {
+    /// If `index_relation` is one partition of a partitioned BM25 index, collect the other
+    /// partitions' indexes, whose statistics are added to those of `searcher`.
+    pub fn open(index_relation: &PgSearchRelation, searcher: &Searcher) -> Option<Self> {
+        if !gucs::enable_partition_wide_scoring()
+            || unsafe { !pg_sys::get_rel_relispartition(index_relation.oid()) }
+        {
+            return None;
+        }
+
+        let parent = Spi::get_one_with_args::<pg_sys::Oid>(
+            "SELECT inhparent FROM pg_inherits WHERE inhrelid = $1",
+            &[index_relation.oid().into()],
+        )
+        .expect("failed to lookup the parent index")?;
+        let parent = PgSearchRelation::with_lock(parent, pg_sys::AccessShareLock as _);
+
+        let siblings = IndexKind::for_index(parent)
+            .ok()?
+            .partitions()
+            .filter(|sibling| sibling.oid() != index_relation.oid())
+            // the partitions of a sub-partitioned partition are its own, not our siblings
+            .filter(|sibling| unsafe {
+                pg_sys::get_rel_relkind(sibling.oid()) as u8 == pg_sys::RELKIND_INDEX
+            });
+
+        Self::with_siblings(searcher, siblings)
+    }
+
+    /// Add the statistics of each of the `siblings` indexes to those of `searcher`.  The
+    /// `siblings` shouldn't include the index that `searcher` belongs to.
+    pub fn with_siblings(
+        searcher: &Searcher,
+        siblings: impl IntoIterator<Item = PgSearchRelation>,
+    ) -> Option<Self> {
+        let siblings = siblings
+            .into_iter()
+            .filter_map(|sibling| {
+                let index = Index::open(MvccSatisfies::Snapshot.directory(&sibling)).ok()?;
+                let searcher = index
+                    .reader_builder()
+                    .reload_policy(ReloadPolicy::Manual)
+                    .try_into()
+                    .ok()
+                    .map(|reader: IndexReader| reader.searcher())?;
+                Some(Sibling {
+                    searcher,
+                    schema: index.schema(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        (!siblings.is_empty()).then(|| Self {
+            searcher: searcher.clone(),
+            schema: searcher.schema().clone(),
+            siblings,
+        })
+    }
+
+    /// Each sibling's field with the same name as our `field`.  Partitions are created with the
+    /// same configuration, but a field added with `ALTER INDEX` might not be in all of them yet.
+    fn sibling_fields(&self, field: Field) -> impl Iterator<Item = (&Sibling, Field)> {
+        let name = self.schema.get_field_name(field);
+        self.siblings.iter().filter_map(move |sibling| {
+            let field = sibling.schema.get_field(name).ok()?;
+            Some((sibling, field))
+        })
+    }
+}
+
+impl Bm25StatisticsProvider for PartitionStatistics {
+    fn total_num_tokens(&self, field: Field) -> tantivy::Result<u64> {
+        let mut total = self.searcher.total_num_tokens(field)?;
+        for (sibling, field) in self.sibling_fields(field) {
+            total += sibling.searcher.total_num_tokens(field)?;
+        }
+        Ok(total)
+    }
+
+    fn total_num_docs(&self) -> tantivy::Result<u64> {
+        let mut total = self.searcher.total_num_docs()?;
+        for sibling in &self.siblings {
+            total += sibling.searcher.total_num_docs()?;
+        }
+        Ok(total)
+    }
+
+    fn doc_freq(&self, term: &Term) -> tantivy::Result<u64> {
+        let mut total = self.searcher.doc_freq(term)?;
+        for (sibling, field) in self.sibling_fields(term.field()) {
+            let mut sibling_term = Term::with_type_and_field(term.typ(), field);
+            sibling_term.append_bytes(term.serialized_value_bytes());
+            total += sibling.searcher.doc_freq(&sibling_term)?;
+        }
+        Ok(total)
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Where the time of a search goes, for `EXPLAIN ANALYZE`.
+//!
+//! Timing every document that a search returns isn't free, so a [`SearchIndexReader`] only keeps
+//! [`SearchTimings`] when it's given some, which the custom scan does when its query is being
+//! explained with `ANALYZE` and `TIMING`.
+//!
+//! [`SearchIndexReader`]: crate::index::reader::index::SearchIndexReader
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default)]
+pub struct SearchTimings {
+    weight_nanos: AtomicU64,
+    collect_nanos: AtomicU64,
+    segments_visited: AtomicUsize,
+}
+
+impl SearchTimings {
+    /// Run `f`, which creates a query's [`Weight`](tantivy::query::Weight), and count its time as
+    /// such.
+    pub fn time_weight<T>(&self, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.weight_nanos
+            .fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        result
+    }
+
+    /// Run `f`, which collects matching documents, and count its time as such.  Any weight that
+    /// `f` creates along the way is only counted as weight creation.
+    pub fn time_collect<T>(&self, f: impl FnOnce() -> T) -> T {
+        let weight_before = self.weight_nanos.load(Ordering::Relaxed);
+        let start = Instant::now();
+        let result = f();
+        let elapsed = start.elapsed().as_nanos() as u64;
+        let weight = self.weight_nanos.load(Ordering::Relaxed) - weight_before;
+        self.collect_nanos
+            .fetch_add(elapsed.saturating_sub(weight), Ordering::Relaxed);
+        result
+    }
+
+    /// Count a segment that the search has started to read.
+    pub fn visit_segment(&self) {
+        self.segments_visited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn weight_time(&self) -> Duration {
+        Duration::from_nanos(self.weight_nanos.load(Ordering::Relaxed))
+    }
+
+    pub fn collect_time(&self) -> Duration {
+        Duration::from_nanos(self.collect_nanos.load(Ordering::Relaxed))
+    }
+
+    pub fn segments_visited(&self) -> usize {
+        self.segments_visited.load(Ordering::Relaxed)
+    }
+
+    pub fn reset(&self) {
+        self.weight_nanos.store(0, Ordering::Relaxed);
+        self.collect_nanos.store(0, Ordering::Relaxed);
+        self.segments_visited.store(0, Ordering::Relaxed);
+    }
+}
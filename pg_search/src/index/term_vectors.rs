@@ -0,0 +1,150 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! The stored tokens of text fields configured with `term_vectors`.
+//!
+//! When a document is indexed, the text of each such field is tokenized once more and its tokens
+//! are kept, with their positions and byte offsets, in a hidden stored field.  Highlighting a
+//! search result then reads them back instead of running the field's tokenizer over the text,
+//! which is what dominates the cost of highlighting large documents.
+
+use crate::PG_SEARCH_PREFIX;
+use std::ops::Range;
+use tantivy::tokenizer::TextAnalyzer;
+
+const TERM_VECTORS_PREFIX: &str = "term_vectors_";
+
+/// A token of a field's text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub text: String,
+    pub position: usize,
+    /// The byte offsets of the token in the text
+    pub offsets: Range<usize>,
+}
+
+/// The name of the hidden field that holds the tokens of the text field `field_name`.
+pub fn term_vectors_field_name(field_name: &str) -> String {
+    format!("{PG_SEARCH_PREFIX}{TERM_VECTORS_PREFIX}{field_name}")
+}
+
+/// Whether `field_name` names a field that holds the tokens of another.
+pub fn is_term_vectors_field(field_name: &str) -> bool {
+    field_name
+        .strip_prefix(PG_SEARCH_PREFIX)
+        .is_some_and(|rest| rest.starts_with(TERM_VECTORS_PREFIX))
+}
+
+/// Every token that `analyzer` finds in `text`.
+pub fn tokenize(analyzer: &mut TextAnalyzer, text: &str) -> Vec<Token> {
+    let mut stream = analyzer.token_stream(text);
+    let mut tokens = vec![];
+    while stream.advance() {
+        let token = stream.token();
+        tokens.push(Token {
+            text: token.text.clone(),
+            position: token.position,
+            offsets: token.offset_from..token.offset_to,
+        });
+    }
+    tokens
+}
+
+/// Each token is written as its position, its start and end offsets, and the length of its text,
+/// all little-endian `u32`s, followed by the text itself.
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for token in tokens {
+        for value in [
+            token.position,
+            token.offsets.start,
+            token.offsets.end,
+            token.text.len(),
+        ] {
+            bytes.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(token.text.as_bytes());
+    }
+    bytes
+}
+
+/// The tokens written by [`encode`], or [`None`] if `bytes` weren't.
+pub fn decode(mut bytes: &[u8]) -> Option<Vec<Token>> {
+    fn read_u32(bytes: &mut &[u8]) -> Option<usize> {
+        let (value, rest) = bytes.split_first_chunk::<4>()?;
+        *bytes = rest;
+        Some(u32::from_le_bytes(*value) as usize)
+    }
+
+    let mut tokens = vec![];
+    while !bytes.is_empty() {
+        let position = read_u32(&mut bytes)?;
+        let start = read_u32(&mut bytes)?;
+        let end = read_u32(&mut bytes)?;
+        let len = read_u32(&mut bytes)?;
+        let text = std::str::from_utf8(bytes.get(..len)?).ok()?.to_string();
+        bytes = &bytes[len..];
+        tokens.push(Token {
+            text,
+            position,
+            offsets: start..end,
+        });
+    }
+    Some(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tantivy::tokenizer::{LowerCaser, SimpleTokenizer};
+
+    #[rstest]
+    fn test_round_trip() {
+        let mut analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(LowerCaser)
+            .build();
+        let tokens = tokenize(&mut analyzer, "Crème brûlée, twice");
+        assert_eq!(
+            tokens.iter().map(|t| t.text.as_str()).collect::<Vec<_>>(),
+            vec!["crème", "brûlée", "twice"]
+        );
+        assert_eq!(tokens[1].position, 1);
+        assert_eq!(tokens[1].offsets, 7..15);
+        assert_eq!(decode(&encode(&tokens)), Some(tokens));
+    }
+
+    #[rstest]
+    fn test_decode_truncated() {
+        let tokens = vec![Token {
+            text: "keyboard".into(),
+            position: 0,
+            offsets: 0..8,
+        }];
+        let bytes = encode(&tokens);
+        assert_eq!(decode(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(decode(&[]), Some(vec![]));
+    }
+
+    #[rstest]
+    fn test_field_name() {
+        let name = term_vectors_field_name("description");
+        assert!(is_term_vectors_field(&name));
+        assert!(!is_term_vectors_field("description"));
+        assert!(!is_term_vectors_field(&format!("{PG_SEARCH_PREFIX}1")));
+    }
+}
@@ -20,7 +20,8 @@ use anyhow::Result;
 use std::num::NonZeroUsize;
 use tantivy::index::SegmentId;
 use tantivy::indexer::{AddOperation, IndexWriterOptions, SegmentWriter};
-use tantivy::schema::Field;
+use tantivy::schema::{Field, OwnedValue, Value};
+use tantivy::tokenizer::TextAnalyzer;
 use tantivy::{
     Directory, Index, IndexMeta, IndexWriter, Opstamp, Segment, SegmentMeta, TantivyDocument,
 };
@@ -28,8 +29,12 @@ use thiserror::Error;
 
 use crate::index::mvcc::{MVCCDirectory, MvccSatisfies};
 use crate::index::setup_tokenizers;
+use crate::index::term_vectors;
 use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::stats;
 use crate::postgres::storage::block::SegmentMetaEntry;
+use crate::postgres::storage::metadata::MetaPage;
+use crate::postgres::storage::tenants::tenant_key;
 use crate::{postgres::types::TantivyValueError, schema::SearchIndexSchema};
 
 struct PendingSegment {
@@ -96,17 +101,29 @@ pub struct CommittedSegment {
     pub max_doc: u32,
 }
 
+/// The most tenants of a partitioned index that a [`SerialIndexWriter`] writes segments for at
+/// once.  Together, their segments are held to the memory budget.
+const MAX_PENDING_TENANTS: usize = 16;
+
 /// Unlike Tantivy's IndexWriter, the SerialIndexWriter does not spin up any threads.
 /// Everything happens in the foreground, making it ideal for Postgres.
+///
+/// If the index has a `tenant_field`, every segment it writes holds the documents of a single
+/// tenant, and is tagged with that tenant in [`MetaPage::segment_tenants()`].
 pub struct SerialIndexWriter {
     // for logging purposes
     id: i32,
     indexrel: PgSearchRelation,
     ctid_field: Field,
+    tenant_field: Option<Field>,
+    /// The text fields that keep their tokens, the fields they're kept in, and their tokenizers
+    term_vectors: Vec<(Field, Field, TextAnalyzer)>,
     config: IndexWriterConfig,
     index: Index,
-    pending_segment: Option<PendingSegment>,
+    pending_segments: HashMap<Option<String>, PendingSegment>,
     new_metas: Vec<SegmentMeta>,
+    /// How many documents have been inserted, for the index's statistics
+    docs_written: u64,
     schema: SearchIndexSchema,
 }
 
@@ -142,15 +159,31 @@ impl SerialIndexWriter {
         let schema = index_relation.schema()?;
         setup_tokenizers(index_relation, &mut index)?;
         let ctid_field = schema.ctid_field();
+        let tenant_field = index_relation
+            .options()
+            .tenant_field_name()
+            .and_then(|field_name| schema.search_field(field_name))
+            .map(|search_field| search_field.field());
+        let term_vectors = schema
+            .fields()
+            .filter_map(|(field, _)| {
+                let term_vectors_field = schema.term_vectors_field(field)?;
+                let analyzer = index.tokenizer_for_field(field).ok()?;
+                Some((field, term_vectors_field, analyzer))
+            })
+            .collect();
 
         Ok(Self {
             id: worker_number,
             indexrel: Clone::clone(index_relation),
             ctid_field,
+            tenant_field,
+            term_vectors,
             config,
             index,
-            pending_segment: Default::default(),
+            pending_segments: Default::default(),
             new_metas: Default::default(),
+            docs_written: 0,
             schema,
         })
     }
@@ -159,38 +192,113 @@ impl SerialIndexWriter {
         &self.schema
     }
 
+    pub fn indexrel(&self) -> &PgSearchRelation {
+        &self.indexrel
+    }
+
     pub fn insert<OnFinalize: FnOnce()>(
+        &mut self,
+        document: TantivyDocument,
+        ctid: u64,
+        on_finalize: OnFinalize,
+    ) -> Result<Option<SegmentMeta>> {
+        self.insert_document(document, ctid, true, on_finalize)
+    }
+
+    /// Insert a batch of documents and their ctids, such as the rows of a `COPY` that have been
+    /// buffered by `aminsert`.  The memory budget is checked once, after the last of them, so a
+    /// segment can hold up to a batch's worth of documents more than the budget.
+    pub fn insert_batch(
+        &mut self,
+        documents: impl ExactSizeIterator<Item = (TantivyDocument, u64)>,
+    ) -> Result<Vec<SegmentMeta>> {
+        let last = documents.len().saturating_sub(1);
+        let mut segment_metas = Vec::new();
+        for (i, (document, ctid)) in documents.enumerate() {
+            segment_metas.extend(self.insert_document(document, ctid, i == last, || {})?);
+        }
+        Ok(segment_metas)
+    }
+
+    fn insert_document<OnFinalize: FnOnce()>(
         &mut self,
         mut document: TantivyDocument,
         ctid: u64,
+        check_memory: bool,
         on_finalize: OnFinalize,
     ) -> Result<Option<SegmentMeta>> {
+        for (field, term_vectors_field, analyzer) in &mut self.term_vectors {
+            // snippets highlight the values of an array joined into one string, so the tokens are
+            // those of that string
+            let values = document
+                .get_all(*field)
+                .filter_map(|value| value.as_str())
+                .collect::<Vec<_>>();
+            if !values.is_empty() {
+                let tokens = term_vectors::tokenize(analyzer, &values.join(" "));
+                document.add_field_value(
+                    *term_vectors_field,
+                    &OwnedValue::Bytes(term_vectors::encode(&tokens)),
+                );
+            }
+        }
         document.add_u64(self.ctid_field, ctid);
+        self.docs_written += 1;
 
-        if self.pending_segment.is_none() {
-            self.pending_segment = Some(self.new_segment()?);
-        }
+        let tenant = self
+            .tenant_field
+            .and_then(|field| document.get_first(field).and_then(tenant_key));
 
-        self.pending_segment
-            .as_mut()
-            .unwrap()
-            .add_document(document)?;
+        let mut evicted = None;
+        if !self.pending_segments.contains_key(&tenant) {
+            if self.pending_segments.len() >= MAX_PENDING_TENANTS {
+                // make room for this tenant by writing out the one that's taking up the most memory
+                let largest = self.largest_pending_tenant();
+                pgrx::debug1!(
+                    "writer {}: finalizing the segment of tenant {:?} to make room for tenant {:?}",
+                    self.id,
+                    largest,
+                    tenant
+                );
+                evicted = self.finalize_segment(largest, on_finalize)?;
+            }
+            let pending_segment = self.new_segment()?;
+            self.pending_segments
+                .insert(tenant.clone(), pending_segment);
+        }
 
-        let pending_segment = self.pending_segment.as_ref().unwrap();
-        let mem_usage = pending_segment.mem_usage();
+        let pending_segment = self.pending_segments.get_mut(&tenant).unwrap();
+        pending_segment.add_document(document)?;
         let max_doc = pending_segment.max_doc();
 
-        if mem_usage >= self.config.memory_budget.into() {
-            pgrx::debug1!(
-                "writer {}: finalizing segment {} with {} docs, mem_usage: {} (out of {}), has created {} segments so far",
-                self.id,
-                pending_segment.segment.id(),
-                max_doc,
-                mem_usage,
-                self.config.memory_budget.get(),
-                self.new_metas.len()
-            );
-            return self.finalize_segment(on_finalize);
+        if evicted.is_some() {
+            // a segment was already finalized, and `on_finalize` with it
+            return Ok(evicted);
+        }
+
+        // summing the memory of the pending segments walks the writers of every field, so a batch
+        // only does it once
+        if check_memory {
+            let mem_usage = self
+                .pending_segments
+                .values()
+                .map(|pending_segment| pending_segment.mem_usage())
+                .sum::<usize>();
+
+            if mem_usage >= self.config.memory_budget.into() {
+                let largest = self.largest_pending_tenant();
+                let pending_segment = &self.pending_segments[&largest];
+                pgrx::debug1!(
+                    "writer {}: finalizing segment {} with {} docs, mem_usage: {} (out of {}), has created {} segments so far",
+                    self.id,
+                    pending_segment.segment.id(),
+                    pending_segment.max_doc(),
+                    mem_usage,
+                    self.config.memory_budget.get(),
+                    self.new_metas.len()
+                );
+                return self.finalize_segment(largest, on_finalize);
+            }
         }
 
         if let Some(max_docs_per_segment) = self.config.max_docs_per_segment {
@@ -198,20 +306,33 @@ impl SerialIndexWriter {
                 pgrx::debug1!(
                     "writer {}: finalizing segment {} with {} docs, has created {} segments so far",
                     self.id,
-                    pending_segment.segment.id(),
+                    self.pending_segments[&tenant].segment.id(),
                     max_doc,
                     self.new_metas.len()
                 );
-                return self.finalize_segment(on_finalize);
+                return self.finalize_segment(tenant, on_finalize);
             }
         }
 
         Ok(None)
     }
 
-    pub fn commit(mut self) -> Result<Option<(SegmentMeta, PgSearchRelation)>> {
-        self.finalize_segment(|| {})
-            .map(|segment_meta| segment_meta.map(|segment_meta| (segment_meta, self.indexrel)))
+    pub fn commit(mut self) -> Result<Option<(Vec<SegmentMeta>, PgSearchRelation)>> {
+        let mut segment_metas = Vec::new();
+        let mut tenants = self.pending_segments.keys().cloned().collect::<Vec<_>>();
+        tenants.sort();
+        for tenant in tenants {
+            segment_metas.extend(self.finalize_segment(tenant, || {})?);
+        }
+        if self.docs_written > 0 {
+            stats::record_insert(
+                self.indexrel.oid(),
+                self.new_metas.len() as u64,
+                self.docs_written,
+            );
+        }
+
+        Ok((!segment_metas.is_empty()).then_some((segment_metas, self.indexrel)))
     }
 
     /// Intelligently create a new segment, backed by either a RamDirectory or a MVCCDirectory.
@@ -221,7 +342,21 @@ impl SerialIndexWriter {
     ///
     /// Otherwise, we create a MVCCDirectory-backed segment.
     fn new_segment(&mut self) -> Result<PendingSegment> {
-        PendingSegment::new(&self.index, self.config.memory_budget)
+        // the budget is shared by the tenants being written at once, so a statement that only
+        // writes one tenant's rows sizes its segment like an index without tenants does.  the sum
+        // of their usage is what's checked against the whole budget, in `insert_document()`
+        let pending_tenants = self.pending_segments.len() + 1;
+        let memory_budget = NonZeroUsize::new(self.config.memory_budget.get() / pending_tenants)
+            .unwrap_or(NonZeroUsize::MIN);
+        PendingSegment::new(&self.index, memory_budget)
+    }
+
+    fn largest_pending_tenant(&self) -> Option<String> {
+        self.pending_segments
+            .iter()
+            .max_by_key(|(_, pending_segment)| pending_segment.mem_usage())
+            .map(|(tenant, _)| tenant.clone())
+            .expect("there should be a pending segment")
     }
 
     /// Once the memory budget is reached, we "finalize" the segment:
@@ -235,20 +370,25 @@ impl SerialIndexWriter {
     /// then, only if a segment was created.
     fn finalize_segment<OnFinalize: FnOnce()>(
         &mut self,
+        tenant: Option<String>,
         on_finalize: OnFinalize,
     ) -> Result<Option<SegmentMeta>> {
         pgrx::debug1!("writer {}: finalizing segment", self.id);
-        let Some(pending_segment) = self.pending_segment.take() else {
+        let Some(pending_segment) = self.pending_segments.remove(&tenant) else {
             // no docs were ever added
             return Ok(None);
         };
 
         on_finalize();
         let finalized_segment = pending_segment.finalize()?;
-        Ok(Some(self.commit_segment(finalized_segment)?))
+        Ok(Some(self.commit_segment(finalized_segment, tenant)?))
     }
 
-    fn commit_segment(&mut self, finalized_segment: Segment) -> Result<SegmentMeta> {
+    fn commit_segment(
+        &mut self,
+        finalized_segment: Segment,
+        tenant: Option<String>,
+    ) -> Result<SegmentMeta> {
         pgrx::debug1!(
             "writer {}: committing segment {}",
             self.id,
@@ -258,6 +398,12 @@ impl SerialIndexWriter {
         let new_meta = finalized_segment.meta().clone();
         self.new_metas.push(new_meta.clone());
         self.save_metas(self.new_metas.clone(), previous_metas)?;
+
+        if let Some(tenant) = tenant {
+            if let Some(mut segment_tenants) = MetaPage::open(&self.indexrel).segment_tenants() {
+                unsafe { segment_tenants.tag(new_meta.id(), tenant) };
+            }
+        }
         Ok(new_meta)
     }
 
@@ -359,6 +505,20 @@ impl Mergeable for SearchIndexMerger {
                 .build(),
         )?;
         let new_segment = writer.merge_foreground(segment_ids, true)?;
+
+        // a merge of one tenant's segments still belongs to that tenant alone
+        if let Some(new_segment) = &new_segment {
+            if let Some(mut segment_tenants) =
+                MetaPage::open(self.directory.indexrel()).segment_tenants()
+            {
+                unsafe {
+                    if let Some(tenant) = segment_tenants.common_tenant(segment_ids) {
+                        segment_tenants.tag(new_segment.id(), tenant);
+                    }
+                }
+            }
+        }
+
         unsafe {
             // SAFETY:  The important thing here is that these segments are not used in any way
             // after their pins are dropped, and [`SearchIndexMerger`] ensures that
@@ -431,7 +591,14 @@ mod tests {
             }
         }
 
-        segment_ids.extend(writer.commit().unwrap().iter().map(|(meta, _)| meta.id()));
+        segment_ids.extend(
+            writer
+                .commit()
+                .unwrap()
+                .into_iter()
+                .flat_map(|(metas, _)| metas)
+                .map(|meta| meta.id()),
+        );
         segment_ids
     }
 
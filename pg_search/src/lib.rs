@@ -80,8 +80,13 @@ pub unsafe extern "C-unwind" fn _PG_init() {
     postgres::options::init();
     gucs::init();
 
+    if pg_sys::process_shared_preload_libraries_in_progress {
+        postgres::stats::init();
+    }
+
     #[cfg(not(feature = "pg17"))]
     postgres::fake_aminsertcleanup::register();
+    postgres::alter_index::register();
 
     #[allow(static_mut_refs)]
     #[allow(deprecated)]
@@ -0,0 +1,127 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Hooks `ALTER INDEX ... SET (...)` and `ALTER INDEX ... RESET (...)` so that fields added to a
+//! BM25 index's configuration are added to its stored schema, and a new `docstore_compression` or
+//! `docstore_compression_level` to its stored settings, without requiring a `REINDEX`.
+//!
+//! Options that don't change how documents are tokenized, like the merge policy, the memory
+//! budget of writes or the compression, are changed with a `SHARE UPDATE EXCLUSIVE` lock, so the
+//! index can still be searched and written to meanwhile.  Each applies to the segments written
+//! afterward.
+#![allow(static_mut_refs)]
+
+use crate::postgres::build::{is_bm25_index, sync_schema, sync_settings};
+use crate::postgres::rel::PgSearchRelation;
+use pgrx::{is_a, pg_guard, pg_sys, PgList};
+
+pub unsafe fn register() {
+    static mut PREV_PROCESS_UTILITY_HOOK: pg_sys::ProcessUtility_hook_type = None;
+
+    PREV_PROCESS_UTILITY_HOOK = pg_sys::ProcessUtility_hook;
+    pg_sys::ProcessUtility_hook = Some(process_utility_hook);
+
+    #[allow(clippy::too_many_arguments)]
+    #[rustfmt::skip]
+    #[pg_guard]
+    unsafe extern "C-unwind" fn process_utility_hook(
+        pstmt: *mut pg_sys::PlannedStmt,
+        query_string: *const ::core::ffi::c_char,
+        read_only_tree: bool,
+        context: pg_sys::ProcessUtilityContext::Type,
+        params: pg_sys::ParamListInfo,
+        query_env: *mut pg_sys::QueryEnvironment,
+        dest: *mut pg_sys::DestReceiver,
+        qc: *mut pg_sys::QueryCompletion,
+    ) {
+        // resolve the relation before the statement runs, as that's when it's still named as written
+        let relid = set_reloptions_target((*pstmt).utilityStmt);
+
+        if let Some(prev_hook) = PREV_PROCESS_UTILITY_HOOK {
+            prev_hook(pstmt, query_string, read_only_tree, context, params, query_env, dest, qc);
+        } else {
+            pg_sys::standard_ProcessUtility(pstmt, query_string, read_only_tree, context, params, query_env, dest, qc)
+        }
+
+        if let Some(relid) = relid {
+            sync_altered_index(relid);
+        }
+    }
+}
+
+/// If `stmt` is an `ALTER INDEX ... SET (...)` or `ALTER INDEX ... RESET (...)`, the relation it
+/// alters.
+unsafe fn set_reloptions_target(stmt: *mut pg_sys::Node) -> Option<pg_sys::Oid> {
+    if !is_a(stmt, pg_sys::NodeTag::T_AlterTableStmt) {
+        return None;
+    }
+    let stmt = stmt.cast::<pg_sys::AlterTableStmt>();
+    if (*stmt).objtype != pg_sys::ObjectType::OBJECT_INDEX {
+        return None;
+    }
+
+    let cmds = PgList::<pg_sys::AlterTableCmd>::from_pg((*stmt).cmds);
+    if !cmds.iter_ptr().any(|cmd| {
+        matches!(
+            (*cmd).subtype,
+            pg_sys::AlterTableType::AT_SetRelOptions | pg_sys::AlterTableType::AT_ResetRelOptions
+        )
+    }) {
+        return None;
+    }
+
+    let relid = pg_sys::RangeVarGetRelidExtended(
+        (*stmt).relation,
+        pg_sys::NoLock as _,
+        pg_sys::RVR_MISSING_OK as _,
+        None,
+        std::ptr::null_mut(),
+    );
+    (relid != pg_sys::InvalidOid).then_some(relid)
+}
+
+unsafe fn sync_altered_index(relid: pg_sys::Oid) {
+    // make the new options visible to the relcache
+    pg_sys::CommandCounterIncrement();
+
+    if pg_sys::get_rel_relkind(relid) as u8 != pg_sys::RELKIND_INDEX {
+        return;
+    }
+    let Some(indexrel) = PgSearchRelation::try_open(relid) else {
+        return;
+    };
+    if !is_bm25_index(&indexrel) {
+        return;
+    }
+
+    // changing the fields takes an AccessExclusiveLock, so nothing else is writing the schema
+    let added = sync_schema(&indexrel).unwrap_or_else(|e| panic!("{e}"));
+    if !added.is_empty() {
+        pgrx::notice!(
+            "added field(s) {} to index `{}`, existing rows will include them once updated or reindexed",
+            added.join(", "),
+            indexrel.name()
+        );
+    }
+
+    if sync_settings(&indexrel).unwrap_or_else(|e| panic!("{e}")) {
+        pgrx::notice!(
+            "segments written to index `{}` from now on will use the new `docstore_compression` and `docstore_compression_level`, existing segments keep theirs until they're merged",
+            indexrel.name()
+        );
+    }
+}
@@ -17,6 +17,10 @@
 
 use crate::api::FieldName;
 use crate::index::mvcc::MvccSatisfies;
+use crate::index::term_vectors::term_vectors_field_name;
+use crate::index::utils::{
+    load_index_schema, load_index_settings, replace_schema, replace_settings,
+};
 use crate::postgres::build_parallel::build_index;
 use crate::postgres::options::BM25IndexOptions;
 use crate::postgres::rel::PgSearchRelation;
@@ -26,10 +30,26 @@ use crate::schema::{SearchFieldConfig, SearchFieldType};
 use anyhow::Result;
 use pgrx::pg_sys::panic::ErrorReport;
 use pgrx::*;
-use tantivy::schema::Schema;
+use tantivy::schema::{BytesOptions, Schema};
 use tantivy::{Index, IndexSettings};
 use tokenizers::SearchTokenizer;
 
+/// The phases of a build reported by `pg_stat_progress_create_index`, after Postgres' own
+/// `initializing` phase.
+pub const PROGRESS_PHASE_INDEXING: i64 = 2;
+pub const PROGRESS_PHASE_FINALIZING: i64 = 3;
+
+#[pg_guard]
+pub extern "C-unwind" fn ambuildphasename(phasenum: i64) -> *mut std::ffi::c_char {
+    let name = match phasenum {
+        _ if phasenum == pg_sys::PROGRESS_CREATEIDX_SUBPHASE_INITIALIZE as i64 => c"initializing",
+        PROGRESS_PHASE_INDEXING => c"indexing table",
+        PROGRESS_PHASE_FINALIZING => c"finalizing segments",
+        _ => return std::ptr::null_mut(),
+    };
+    name.as_ptr().cast_mut()
+}
+
 #[pg_guard]
 pub extern "C-unwind" fn ambuild(
     heaprel: pg_sys::Relation,
@@ -123,6 +143,16 @@ unsafe fn validate_index_config(index_relation: &PgSearchRelation) {
             .set_hint("use `keyword` instead").report(PgLogLevel::WARNING);
     }
 
+    if let Some(tenant_field_name) = options.tenant_field_name() {
+        validate_tenant_field(&tenant_field_name, options);
+    }
+
+    match (options.ttl_field_name(), options.ttl()) {
+        (Some(ttl_field_name), Some(_)) => validate_ttl_field(&ttl_field_name, options),
+        (None, None) => {}
+        _ => panic!("`ttl_field` and `ttl` must be set together"),
+    }
+
     let options = index_relation.options();
     let text_configs = options.text_config();
     for (field_name, config) in text_configs.iter().flatten() {
@@ -138,6 +168,20 @@ unsafe fn validate_index_config(index_relation: &PgSearchRelation) {
         });
     }
 
+    let uuid_configs = options.uuid_config();
+    for (field_name, config) in uuid_configs.iter().flatten() {
+        validate_field_config(field_name, &key_field_name, config, options, |t| {
+            matches!(t, SearchFieldType::Uuid(_))
+        });
+    }
+
+    let bytes_configs = options.bytes_config();
+    for (field_name, config) in bytes_configs.iter().flatten() {
+        validate_field_config(field_name, &key_field_name, config, options, |t| {
+            matches!(t, SearchFieldType::Bytes(_))
+        });
+    }
+
     let numeric_configs = options.numeric_config();
     for (field_name, config) in numeric_configs.iter().flatten() {
         validate_field_config(field_name, &key_field_name, config, options, |t| {
@@ -146,6 +190,14 @@ unsafe fn validate_index_config(index_relation: &PgSearchRelation) {
                 SearchFieldType::I64(_) | SearchFieldType::U64(_) | SearchFieldType::F64(_)
             )
         });
+
+        let is_numeric = matches!(
+            options.get_field_type(field_name),
+            Some(SearchFieldType::F64(oid)) if oid == pg_sys::NUMERICOID
+        );
+        if config.numeric_scale().is_some() && !is_numeric {
+            panic!("`scale` can only be set for NUMERIC columns, but `{field_name}` is not one");
+        }
     }
 
     let boolean_configs = options.boolean_config();
@@ -177,6 +229,66 @@ unsafe fn validate_index_config(index_relation: &PgSearchRelation) {
     }
 }
 
+/// A tenant is named by the exact value of its field, so each document can only have the one, and
+/// searching for it mustn't match any other.
+fn validate_tenant_field(tenant_field_name: &FieldName, options: &BM25IndexOptions) {
+    let Some((pg_type, tantivy_type)) = options
+        .attributes()
+        .get(tenant_field_name)
+        .map(|attribute| (attribute.pg_type, attribute.tantivy_type))
+    else {
+        panic!("tenant_field '{tenant_field_name}' must be one of the indexed columns");
+    };
+
+    if unsafe { pg_sys::get_element_type(pg_type.value()) } != pg_sys::InvalidOid {
+        panic!("tenant_field '{tenant_field_name}' cannot be an array");
+    }
+
+    let is_exact = match tantivy_type {
+        SearchFieldType::I64(_) | SearchFieldType::U64(_) | SearchFieldType::Bool(_) => true,
+        SearchFieldType::Text(_) => matches!(
+            options
+                .field_config_or_default(tenant_field_name)
+                .tokenizer(),
+            Some(SearchTokenizer::Keyword)
+        ),
+        _ => false,
+    };
+    if !is_exact {
+        panic!(
+            "tenant_field '{tenant_field_name}' must be an integer or boolean column, or a text column that uses the `keyword` tokenizer"
+        );
+    }
+}
+
+/// Documents expire by a point in time, so the field has to hold one, and only the one.
+fn validate_ttl_field(ttl_field_name: &FieldName, options: &BM25IndexOptions) {
+    let Some((pg_type, tantivy_type)) = options
+        .attributes()
+        .get(ttl_field_name)
+        .map(|attribute| (attribute.pg_type, attribute.tantivy_type))
+    else {
+        panic!("ttl_field '{ttl_field_name}' must be one of the indexed columns");
+    };
+
+    let is_point_in_time = matches!(tantivy_type, SearchFieldType::Date(_))
+        && [
+            pg_sys::TIMESTAMPTZOID,
+            pg_sys::TIMESTAMPOID,
+            pg_sys::DATEOID,
+        ]
+        .contains(&pg_type.value());
+    if !is_point_in_time {
+        panic!("ttl_field '{ttl_field_name}' must be a timestamptz, timestamp, or date column");
+    }
+    if matches!(
+        options.field_config_or_default(ttl_field_name),
+        SearchFieldConfig::Date { indexed: false, .. }
+    ) {
+        panic!("ttl_field '{ttl_field_name}' must be indexed");
+    }
+}
+
 fn validate_field_config(
     field_name: &FieldName,
     key_field_name: &FieldName,
@@ -245,6 +357,78 @@ fn bm25_amhandler_oid() -> Option<pg_sys::Oid> {
 }
 
 fn create_index(index_relation: &PgSearchRelation) -> Result<()> {
+    let schema = build_schema(index_relation);
+    let directory = MvccSatisfies::Snapshot.directory(index_relation);
+    let settings = IndexSettings {
+        docstore_compression: index_relation.options().docstore_compression(),
+        docstore_compress_dedicated_thread: false,
+        ..IndexSettings::default()
+    };
+    let _ = Index::create(directory, schema, settings)?;
+    Ok(())
+}
+
+/// Add any fields that have been configured since the index was created to its stored schema.
+///
+/// An index's columns can't change without a `REINDEX`, but an aliased field (one with a `column`
+/// key) can be added to `text_fields` or `json_fields` with `ALTER INDEX ... SET`.  Rows that are
+/// inserted or updated afterward carry the new field, and existing rows gain it on `REINDEX`.
+///
+/// Returns the names of the fields that were added.
+pub unsafe fn sync_schema(index_relation: &PgSearchRelation) -> Result<Vec<String>> {
+    let Some(stored) = load_index_schema(index_relation)? else {
+        // nothing has been written yet, so the first commit will save the current schema
+        return Ok(vec![]);
+    };
+
+    validate_index_config(index_relation);
+
+    let configured = build_schema(index_relation);
+    let added = configured
+        .fields()
+        .filter(|(_, entry)| stored.get_field(entry.name()).is_err())
+        .map(|(_, entry)| entry.clone())
+        .collect::<Vec<_>>();
+    if added.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // existing fields keep both their configuration and their position
+    let mut builder = Schema::builder();
+    for (_, entry) in stored.fields() {
+        builder.add_field(entry.clone());
+    }
+    for entry in &added {
+        builder.add_field(entry.clone());
+    }
+    replace_schema(index_relation, &builder.build())?;
+
+    Ok(added
+        .into_iter()
+        .map(|entry| entry.name().to_string())
+        .collect())
+}
+
+/// Apply the index's `docstore_compression` to its stored settings, so that the segments written
+/// and merged from now on are compressed with it.  The segments it already has keep theirs.
+///
+/// Returns whether the settings changed.
+pub unsafe fn sync_settings(index_relation: &PgSearchRelation) -> Result<bool> {
+    let Some(mut settings) = load_index_settings(index_relation)? else {
+        // nothing has been written yet, so the first commit will save the current settings
+        return Ok(false);
+    };
+
+    let compression = index_relation.options().docstore_compression();
+    if settings.docstore_compression == compression {
+        return Ok(false);
+    }
+    settings.docstore_compression = compression;
+    replace_settings(index_relation, &settings)?;
+    Ok(true)
+}
+
+fn build_schema(index_relation: &PgSearchRelation) -> Schema {
     let options = index_relation.options();
     let mut builder = Schema::builder();
 
@@ -255,14 +439,24 @@ fn create_index(index_relation: &PgSearchRelation) -> Result<()> {
 
         match tantivy_type {
             SearchFieldType::Text(_) => builder.add_text_field(name.as_ref(), config.clone()),
+            SearchFieldType::Uuid(_) if matches!(config, SearchFieldConfig::Uuid { .. }) => {
+                builder.add_bytes_field(name.as_ref(), config.clone())
+            }
             SearchFieldType::Uuid(_) => builder.add_text_field(name.as_ref(), config.clone()),
             SearchFieldType::Inet(_) => builder.add_ip_addr_field(name.as_ref(), config.clone()),
             SearchFieldType::I64(_) => builder.add_i64_field(name.as_ref(), config.clone()),
             SearchFieldType::U64(_) => builder.add_u64_field(name.as_ref(), config.clone()),
+            // a NUMERIC column with a `scale` is stored as a fixed-point integer
+            SearchFieldType::F64(_) if config.numeric_scale().is_some() => {
+                builder.add_i64_field(name.as_ref(), config.clone())
+            }
             SearchFieldType::F64(_) => builder.add_f64_field(name.as_ref(), config.clone()),
             SearchFieldType::Bool(_) => builder.add_bool_field(name.as_ref(), config.clone()),
             SearchFieldType::Json(_) => builder.add_json_field(name.as_ref(), config.clone()),
             SearchFieldType::Range(_) => builder.add_json_field(name.as_ref(), config.clone()),
+            SearchFieldType::Bytes(_) | SearchFieldType::Vector(_) => {
+                builder.add_bytes_field(name.as_ref(), config.clone())
+            }
             SearchFieldType::Date(_) => builder.add_date_field(name.as_ref(), config.clone()),
         };
     }
@@ -281,12 +475,22 @@ fn create_index(index_relation: &PgSearchRelation) -> Result<()> {
         options.field_config_or_default(&FieldName::from("ctid")),
     );
 
-    let schema = builder.build();
-    let directory = MvccSatisfies::Snapshot.directory(index_relation);
-    let settings = IndexSettings {
-        docstore_compress_dedicated_thread: false,
-        ..IndexSettings::default()
-    };
-    let _ = Index::create(directory, schema, settings)?;
-    Ok(())
+    // and a hidden field for the tokens of each text field that keeps them
+    let text_configs = options
+        .attributes()
+        .iter()
+        .filter(|(_, attribute)| matches!(attribute.tantivy_type, SearchFieldType::Text(_)))
+        .map(|(name, _)| (name.clone(), options.field_config_or_default(name)))
+        .chain(options.aliased_text_configs())
+        .collect::<Vec<_>>();
+    for (name, config) in text_configs {
+        if config.term_vectors() {
+            builder.add_bytes_field(
+                &term_vectors_field_name(name.as_ref()),
+                BytesOptions::default().set_stored(),
+            );
+        }
+    }
+
+    builder.build()
 }
@@ -27,6 +27,7 @@ use crate::parallel_worker::{
     chunk_range, ParallelProcess, ParallelState, ParallelStateManager, ParallelStateType,
     ParallelWorker, WorkerStyle,
 };
+use crate::postgres::build::{PROGRESS_PHASE_FINALIZING, PROGRESS_PHASE_INDEXING};
 use crate::postgres::merge::garbage_collect_index;
 use crate::postgres::ps_status::{
     set_ps_display_remove_suffix, set_ps_display_suffix, COMMITTING, FINALIZING,
@@ -47,6 +48,9 @@ use std::ptr::{addr_of_mut, NonNull};
 use std::sync::OnceLock;
 use tantivy::{SegmentMeta, TantivyDocument};
 
+/// How many tuples each worker indexes between updates of the build's progress.
+const PROGRESS_INTERVAL: usize = 1000;
+
 /// General, immutable configuration used for the workers
 #[derive(Copy, Clone)]
 #[repr(C)]
@@ -69,6 +73,7 @@ struct WorkerCoordination {
     mutex: Spinlock,
     nstarted: usize,
     nlaunched: usize,
+    ntuples: usize,
 }
 
 impl ParallelStateType for WorkerCoordination {}
@@ -89,6 +94,12 @@ impl WorkerCoordination {
         let _lock = self.mutex.acquire();
         self.nlaunched
     }
+    /// Count `ntuples` more tuples as indexed, returning how many have been by all the workers.
+    fn add_ntuples(&mut self, ntuples: usize) -> usize {
+        let _lock = self.mutex.acquire();
+        self.ntuples += ntuples;
+        self.ntuples
+    }
 }
 
 /// The parallel process for setting up a parallel index build
@@ -255,6 +266,7 @@ impl<'a> BuildWorker<'a> {
                 worker_segment_target.max(1),
                 nlaunched,
                 worker_number,
+                self.coordination,
             )?;
 
             set_ps_display_suffix(INDEXING.as_ptr());
@@ -305,6 +317,11 @@ struct WorkerBuildState {
     unmerged_metas: Vec<SegmentMeta>,
 
     cnt: usize,
+
+    // shared with the other workers, for counting the tuples indexed so far, of which this
+    // worker has counted `nreported`
+    coordination: NonNull<WorkerCoordination>,
+    nreported: usize,
 }
 
 impl WorkerBuildState {
@@ -316,6 +333,7 @@ impl WorkerBuildState {
         worker_segment_target: usize,
         nlaunched: usize,
         worker_number: i32,
+        coordination: &mut WorkerCoordination,
     ) -> anyhow::Result<Self> {
         // if we're making more than one segment, do an early cutoff based on doc count in case
         // the memory budget is so high that all the docs fit into one segment
@@ -350,16 +368,42 @@ impl WorkerBuildState {
             nmerges: Default::default(),
             unmerged_metas: Default::default(),
             cnt: 0,
+            coordination: NonNull::from(coordination),
+            nreported: 0,
         })
     }
 
+    /// Count the tuples indexed since the last report, and if this is the leader, report how many
+    /// have been indexed by all of the workers to `pg_stat_progress_create_index`.
+    fn report_progress(&mut self) {
+        let ntuples = unsafe { self.coordination.as_mut() }.add_ntuples(self.cnt - self.nreported);
+        self.nreported = self.cnt;
+        if unsafe { !pg_sys::IsParallelWorker() } {
+            unsafe {
+                pg_sys::pgstat_progress_update_param(
+                    pg_sys::PROGRESS_CREATEIDX_TUPLES_DONE as _,
+                    ntuples as i64,
+                );
+            }
+        }
+    }
+
     fn commit(&mut self) -> anyhow::Result<()> {
         unsafe {
             set_ps_display_suffix(FINALIZING.as_ptr());
         }
+        self.report_progress();
+        if unsafe { !pg_sys::IsParallelWorker() } {
+            unsafe {
+                pg_sys::pgstat_progress_update_param(
+                    pg_sys::PROGRESS_CREATEIDX_SUBPHASE as _,
+                    PROGRESS_PHASE_FINALIZING,
+                );
+            }
+        }
         let writer = self.writer.take().expect("writer should be set");
-        if let Some((segment_meta, _)) = writer.commit()? {
-            self.unmerged_metas.push(segment_meta);
+        if let Some((segment_metas, _)) = writer.commit()? {
+            self.unmerged_metas.extend(segment_metas);
             self.try_merge(true)?;
         }
 
@@ -376,6 +420,12 @@ impl WorkerBuildState {
                 return Ok(());
             }
 
+            if self.indexrel.options().tenant_field_name().is_some() {
+                // the segments are of different tenants, which mustn't be merged together, so
+                // they're left for the merge policy to combine with others of the same tenant
+                return Ok(());
+            }
+
             let chunk_size = if !is_last_merge {
                 // calculate the chunk size for this merge iteration
                 //
@@ -502,6 +552,9 @@ unsafe extern "C-unwind" fn build_callback(
     build_state.per_row_context.reset();
 
     build_state.cnt += 1;
+    if build_state.cnt % PROGRESS_INTERVAL == 0 {
+        build_state.report_progress();
+    }
 
     if let Some(segment_meta) = segment_meta {
         build_state.unmerged_metas.push(segment_meta);
@@ -544,6 +597,22 @@ pub(super) fn build_index(
         }
     });
 
+    unsafe {
+        pg_sys::pgstat_progress_update_multi_param(
+            2,
+            [
+                pg_sys::PROGRESS_CREATEIDX_SUBPHASE as _,
+                pg_sys::PROGRESS_CREATEIDX_TUPLES_TOTAL as _,
+            ]
+            .as_ptr(),
+            [
+                PROGRESS_PHASE_INDEXING,
+                plan::estimate_heap_reltuples(&heaprel) as i64,
+            ]
+            .as_ptr(),
+        );
+    }
+
     let current_xid = unsafe { pg_sys::GetCurrentTransactionId() };
     let process = ParallelBuild::new(&heaprel, &indexrel, snapshot.0, concurrent, current_xid);
     let nworkers = plan::create_index_nworkers(&heaprel, &indexrel);
@@ -656,18 +725,23 @@ mod plan {
         // but doing either of these would prohibit the user from having direct control over the number of
         // workers used for a given CREATE INDEX/REINDEX statement.  Internal discussions led to that
         // being more important that us trying to be "smart"
-        let maintenance_workers = unsafe {
+        //
+        // As with Postgres' own index builds, the table's `parallel_workers` storage parameter takes
+        // precedence when it's set, and setting it to zero disables parallel builds altogether
+        let table_workers = unsafe {
             if !heaprel.rd_options.is_null() {
                 let options = heaprel.rd_options.cast::<pg_sys::StdRdOptions>();
-                if (*options).parallel_workers <= 0 {
-                    pg_sys::max_parallel_maintenance_workers as usize
-                } else {
-                    (*options).parallel_workers as usize
-                }
+                usize::try_from((*options).parallel_workers).ok()
             } else {
-                pg_sys::max_parallel_maintenance_workers as usize
+                None
             }
         };
+        if table_workers == Some(0) {
+            pgrx::debug1!("parallel index build disabled by the table's `parallel_workers`");
+            return 0;
+        }
+        let maintenance_workers =
+            table_workers.unwrap_or(unsafe { pg_sys::max_parallel_maintenance_workers as usize });
 
         // must also be less than max_parallel_workers and max_worker_processes
         let maintenance_workers = maintenance_workers
@@ -20,7 +20,7 @@ pub mod scan_state;
 
 use std::ffi::CStr;
 
-use crate::aggregate::execute_aggregate;
+use crate::aggregate::{execute_aggregate, execute_count};
 use crate::api::operator::anyelement_query_input_opoid;
 use crate::gucs;
 use crate::index::mvcc::MvccSatisfies;
@@ -42,7 +42,9 @@ use crate::postgres::customscan::explainer::Explainer;
 use crate::postgres::customscan::pdbscan::{
     extract_pathkey_styles_with_sortability_check, PathKeyInfo,
 };
-use crate::postgres::customscan::qual_inspect::{extract_quals, QualExtractState};
+use crate::postgres::customscan::qual_inspect::{
+    extract_quals, respects_security_barriers, QualExtractState,
+};
 use crate::postgres::customscan::{
     range_table, CreateUpperPathsHookArgs, CustomScan, ExecMethod, PlainExecCapable,
 };
@@ -149,8 +151,21 @@ impl CustomScan for AggregateScan {
                 &bm25_index,
                 false, // Base relation quals should not convert external to all
                 &mut QualExtractState::default(),
-            );
-            SearchQueryInput::from(&result?)
+            )?;
+
+            // and without evaluating a leaky filter ahead of a row-level security policy?
+            if !respects_security_barriers(
+                args.root,
+                heap_rti,
+                &restrict_info,
+                anyelement_query_input_opoid(),
+                ri_type,
+                &bm25_index,
+                false,
+            ) {
+                return None;
+            }
+            SearchQueryInput::from(&result)
         };
 
         // Check if any GROUP BY field is also being searched (conflicts with Tantivy aggregation)
@@ -278,6 +293,19 @@ impl CustomScan for AggregateScan {
             // TODO: Opening of the index could be deduped between custom scans: see
             // `PdbScanState::open_relations`.
             state.custom_state_mut().open_relations(lockmode);
+
+            if state.custom_state_mut().query.has_postgres_expressions() {
+                // the query has Postgres expressions, such as parameters or the settings that a
+                // row-level security policy compares against, which are solved when it's executed
+                //
+                // Our planstate's ExprContext isn't sufficiently configured for that, so just
+                // like `PdbScan`, we make a new one for our `runtime_context`
+                let planstate = state.planstate();
+                let stdecontext = (*planstate).ps_ExprContext;
+                pg_sys::ExecAssignExprContext(estate, planstate);
+                state.runtime_context = state.csstate.ss.ps.ps_ExprContext;
+                (*planstate).ps_ExprContext = stdecontext;
+            }
         }
     }
 
@@ -457,8 +485,7 @@ fn extract_grouping_columns(
 
                     // Check if this field exists in the index schema as a fast field
                     if let Some(search_field) = schema.search_field(field_name) {
-                        let is_fast = search_field.is_fast();
-                        if is_fast {
+                        if search_field.has_exact_fast_values() {
                             grouping_columns.push(GroupingColumn {
                                 field_name: field_name.to_string(),
                                 attno,
@@ -507,6 +534,10 @@ fn extract_and_validate_aggregates(
                     // Aggregate field is not a fast field
                     return None;
                 }
+                if search_field.numeric_scale().is_some() {
+                    // The fast field holds scaled integers, which Postgres can't aggregate as-is
+                    return None;
+                }
             } else {
                 // Aggregate field not found in schema
                 return None;
@@ -688,17 +719,49 @@ unsafe fn placeholder_procid() -> pg_sys::Oid {
         .expect("the `now()` function should exist")
 }
 
+/// The query to execute, with any Postgres expressions in it solved for this execution.
+fn solve_query(state: &mut CustomScanStateWrapper<AggregateScan>) -> SearchQueryInput {
+    let mut query = state.custom_state().query.clone();
+    if query.has_postgres_expressions() {
+        query.init_postgres_expressions(state.planstate());
+        query.solve_postgres_expressions(state.runtime_context);
+    }
+    query
+}
+
 fn execute(
-    state: &CustomScanStateWrapper<AggregateScan>,
+    state: &mut CustomScanStateWrapper<AggregateScan>,
 ) -> std::vec::IntoIter<GroupedAggregateRow> {
+    let query = solve_query(state);
+    if state.custom_state().is_count_only() {
+        // a plain `COUNT(*)` doesn't need tantivy's aggregation machinery at all
+        let count = execute_count(
+            state.custom_state().indexrel(),
+            query,
+            true, // solve_mvcc
+        )
+        .expect("failed to execute count");
+        let count = i64::try_from(count).expect("COUNT value was out of range");
+        return vec![GroupedAggregateRow {
+            group_keys: vec![],
+            aggregate_values: state
+                .custom_state()
+                .aggregate_types
+                .iter()
+                .map(|_| AggregateValue::Int(count))
+                .collect(),
+        }]
+        .into_iter();
+    }
+
     let result = execute_aggregate(
         state.custom_state().indexrel(),
-        state.custom_state().query.clone(),
+        query,
         state.custom_state().aggregates_to_json(),
         // TODO: Consider adding a GUC to control whether we solve MVCC.
         true,                                              // solve_mvcc
         gucs::adjust_work_mem().get().try_into().unwrap(), // memory_limit
-        65000,                                             // bucket_limit
+        bucket_limit(state.custom_state().grouping_columns.len()),
     )
     .expect("failed to execute aggregate");
 
@@ -708,6 +771,20 @@ fn execute(
         .into_iter()
 }
 
+/// The total number of buckets tantivy may create while grouping by `ngrouping_columns` columns.
+///
+/// Each `GROUP BY` column is a terms aggregation nested in the one before it, so every group of a
+/// level can have up to `paradedb.max_term_agg_buckets` groups of its own: grouping by `k`
+/// columns makes up to `n + n^2 + ... + n^k` buckets.
+fn bucket_limit(ngrouping_columns: usize) -> u32 {
+    let groups = gucs::max_term_agg_buckets();
+    let (_, total) = (0..ngrouping_columns.max(1)).fold((1u32, 0u32), |(level, total), _| {
+        let level = level.saturating_mul(groups);
+        (level, total.saturating_add(level))
+    });
+    total.max(65000)
+}
+
 impl ExecMethod for AggregateScan {
     fn exec_methods() -> *const pg_sys::CustomExecMethods {
         <AggregateScan as PlainExecCapable>::exec_methods()
@@ -749,8 +826,8 @@ fn extract_order_by_pathkeys(
             root,
             heap_rti,
             schema,
-            |search_field| search_field.is_fast(), // Use is_fast() for regular vars
-            |_search_field| false,                 // Don't accept lower functions in aggregatescan
+            |search_field| search_field.has_exact_fast_values(),
+            |_search_field| false, // Don't accept lower functions in aggregatescan
         )
     }
 }
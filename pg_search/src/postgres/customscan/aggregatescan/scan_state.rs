@@ -16,6 +16,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use crate::api::{OrderByFeature, OrderByInfo, SortDirection};
+use crate::gucs;
 use crate::postgres::customscan::aggregatescan::privdat::{
     AggregateResult, AggregateType, AggregateValue, GroupingColumn, TargetListEntry,
 };
@@ -25,7 +26,8 @@ use crate::postgres::PgSearchRelation;
 use crate::query::SearchQueryInput;
 use tantivy::schema::OwnedValue;
 
-use pgrx::pg_sys;
+use pgrx::pg_sys::panic::ErrorReport;
+use pgrx::{function_name, pg_sys, PgLogLevel, PgSqlErrorCode};
 use tinyvec::TinyVec;
 
 /// Source of aggregate result data - either from result map or bucket object
@@ -91,6 +93,16 @@ impl AggregateScanState {
             .expect("PdbScanState: indexrel should be initialized")
     }
 
+    /// Is this scan nothing more than one or more `COUNT(*)`s without a `GROUP BY`?
+    pub fn is_count_only(&self) -> bool {
+        self.grouping_columns.is_empty()
+            && !self.aggregate_types.is_empty()
+            && self
+                .aggregate_types
+                .iter()
+                .all(|agg| matches!(agg, AggregateType::Count))
+    }
+
     pub fn aggregates_to_json(&self) -> serde_json::Value {
         if self.grouping_columns.is_empty() {
             // No GROUP BY - simple aggregation
@@ -130,8 +142,10 @@ impl AggregateScanState {
                 serde_json::Value::String(group_col.field_name.clone()),
             );
             // if we remove this, we'd get the default size of 10, which means we receive 10 groups max from tantivy
-            // TODO: make configurable via issue ##2964
-            terms.insert("size".to_string(), serde_json::Value::Number(10000.into()));
+            terms.insert(
+                "size".to_string(),
+                serde_json::Value::Number(gucs::max_term_agg_buckets().into()),
+            );
 
             let mut terms_agg = serde_json::Map::new();
             terms_agg.insert("terms".to_string(), serde_json::Value::Object(terms));
@@ -283,9 +297,30 @@ impl AggregateScanState {
         rows: &mut Vec<GroupedAggregateRow>,
     ) {
         let bucket_name = format!("group_{depth}");
-        let buckets = json
-            .get(&bucket_name)
-            .and_then(|v| v.get("buckets"))
+        let terms = json.get(&bucket_name).expect("missing bucket results");
+
+        // tantivy quietly drops the groups that don't fit into the requested `size` and only
+        // tells us how many documents they held
+        let sum_other_doc_count = terms
+            .get("sum_other_doc_count")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        if sum_other_doc_count > 0 {
+            ErrorReport::new(
+                PgSqlErrorCode::ERRCODE_PROGRAM_LIMIT_EXCEEDED,
+                format!(
+                    "GROUP BY {} produced more than {} groups",
+                    self.grouping_columns[depth].field_name,
+                    gucs::max_term_agg_buckets()
+                ),
+                function_name!(),
+            )
+            .set_hint("increase `paradedb.max_term_agg_buckets` or disable `paradedb.enable_aggregate_custom_scan`")
+            .report(PgLogLevel::ERROR);
+        }
+
+        let buckets = terms
+            .get("buckets")
             .and_then(|v| v.as_array())
             .expect("missing bucket results");
 
@@ -17,6 +17,7 @@
 
 use crate::api::{Cardinality, FieldName, HashSet, OrderByFeature, OrderByInfo, SortDirection};
 use crate::index::fast_fields_helper::WhichFastField;
+use crate::postgres::customscan::pdbscan::projections::hybrid::{FastDistance, Fusion};
 use crate::postgres::customscan::CustomScan;
 use pgrx::{pg_sys, PgList};
 use serde::{Deserialize, Serialize};
@@ -90,11 +91,24 @@ pub enum ExecMethodType {
         heaprelid: pg_sys::Oid,
         limit: usize,
         orderby_info: Option<Vec<OrderByInfo>>,
+        /// Set if every column the scan returns is a fast field.
+        which_fast_fields: Option<HashSet<WhichFastField>>,
+        /// Set if the scan is one side of a join, whose `LIMIT` doesn't say how many of the
+        /// scan's rows it will need.  The scan then keeps returning rows in order for as long as
+        /// they're asked for, starting with enough for `limit` matches and then more.
+        joined: bool,
     },
     FastFieldMixed {
         which_fast_fields: HashSet<WhichFastField>,
         limit: Option<usize>,
     },
+    /// Ordered by `paradedb.hybrid()`: the best BM25 matches are fused with their distances, which
+    /// are read from the index if `distance` is set, or computed from their rows.
+    Hybrid {
+        limit: usize,
+        fusion: Fusion,
+        distance: Option<FastDistance>,
+    },
 }
 
 impl ExecMethodType {
@@ -235,11 +249,15 @@ impl<CS: CustomScan> CustomPathBuilder<CS> {
         self
     }
 
-    pub fn add_path_key(mut self, style: &OrderByStyle) -> Self {
+    pub fn add_path_key(self, style: &OrderByStyle) -> Self {
+        self.add_raw_path_key(style.pathkey())
+    }
+
+    pub fn add_raw_path_key(mut self, pathkey: *mut pg_sys::PathKey) -> Self {
         unsafe {
             let mut pklist =
                 PgList::<pg_sys::PathKey>::from_pg(self.custom_path_node.path.pathkeys);
-            pklist.push(style.pathkey());
+            pklist.push(pathkey);
 
             self.custom_path_node.path.pathkeys = pklist.into_pg();
         }
@@ -39,6 +39,10 @@ impl Explainer {
         unsafe { (*self.state.as_ptr()).analyze }
     }
 
+    pub fn is_timing(&self) -> bool {
+        unsafe { (*self.state.as_ptr()).timing }
+    }
+
     pub fn is_costs(&self) -> bool {
         unsafe { (*self.state.as_ptr()).costs }
     }
@@ -84,7 +88,6 @@ impl Explainer {
         }
     }
 
-    #[allow(dead_code)]
     pub fn add_float(&mut self, key: &str, value: f64, unit: Option<&str>, ndigits: i32) {
         unsafe {
             pg_sys::ExplainPropertyFloat(
@@ -97,6 +100,11 @@ impl Explainer {
         }
     }
 
+    /// Add `duration` in milliseconds, like Postgres' own timings.
+    pub fn add_duration(&mut self, key: &str, duration: Duration) {
+        self.add_float(key, duration.as_secs_f64() * 1000.0, Some("ms"), 3);
+    }
+
     pub fn add_bool(&mut self, key: &str, value: bool) {
         unsafe {
             pg_sys::ExplainPropertyBool(key.as_pg_cstr(), value, self.state.as_ptr());
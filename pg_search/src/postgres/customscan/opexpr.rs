@@ -83,6 +83,10 @@ pub unsafe fn initialize_equality_operator_lookup(
     if accepts == OperatorAccepts::All {
         // tantivy doesn't support range operators on bools, so we can only support the equality operator
         lookup.insert(operator_oid("=(bool,bool)"), "=");
+
+        // bytes are only ever matched exactly
+        lookup.insert(operator_oid("=(bytea,bytea)"), "=");
+        lookup.insert(operator_oid("<>(bytea,bytea)"), "<>");
     }
 
     let type_pairs = match accepts {
@@ -103,6 +107,17 @@ pub unsafe fn initialize_equality_operator_lookup(
     lookup
 }
 
+/// Postgres' range operators we can push down when the left side is an indexed range field.
+/// They're polymorphic, so this covers every range type we index.
+pub unsafe fn initialize_range_operator_lookup() -> HashMap<PostgresOperatorOid, TantivyOperator> {
+    let mut lookup = HashMap::default();
+    lookup.insert(operator_oid("&&(anyrange,anyrange)"), "&&");
+    lookup.insert(operator_oid("@>(anyrange,anyrange)"), "@>");
+    lookup.insert(operator_oid("@>(anyrange,anyelement)"), "@>");
+    lookup.insert(operator_oid("<@(anyrange,anyrange)"), "<@");
+    lookup
+}
+
 #[derive(Debug)]
 pub(crate) enum OpExpr {
     Array(*mut pg_sys::ScalarArrayOpExpr),
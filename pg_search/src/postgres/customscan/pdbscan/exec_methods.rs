@@ -16,6 +16,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 pub(crate) mod fast_fields;
+pub(crate) mod hybrid;
 pub(crate) mod normal;
 pub(crate) mod top_n;
 
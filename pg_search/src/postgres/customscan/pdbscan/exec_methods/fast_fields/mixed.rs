@@ -32,7 +32,8 @@ use crate::postgres::customscan::pdbscan::scan_state::PdbScanState;
 use crate::postgres::types_arrow::{arrow_array_to_datum, date_time_to_ts_nanos};
 
 use arrow_array::builder::{
-    BooleanBuilder, Float64Builder, Int64Builder, TimestampNanosecondBuilder, UInt64Builder,
+    BinaryBuilder, BooleanBuilder, Float64Builder, Int64Builder, TimestampNanosecondBuilder,
+    UInt64Builder,
 };
 use arrow_array::ArrayRef;
 use pgrx::itemptr::item_pointer_get_block_number;
@@ -213,6 +214,26 @@ impl MixedFastFieldExecState {
                                 .map(|maybe_ord| maybe_ord.unwrap_or(NULL_TERM_ORDINAL)),
                         ))
                     }
+                    FFType::Bytes(bytes_column) => {
+                        let mut term_ords = Vec::with_capacity(ids.len());
+                        term_ords.resize(ids.len(), None);
+                        bytes_column.ords().first_vals(&ids, &mut term_ords);
+                        let mut builder = BinaryBuilder::with_capacity(ids.len(), 0);
+                        let mut bytes = Vec::new();
+                        for maybe_ord in term_ords {
+                            match maybe_ord {
+                                Some(ord) => {
+                                    bytes.clear();
+                                    bytes_column
+                                        .ord_to_bytes(ord, &mut bytes)
+                                        .expect("bytes should be retrievable for term ord");
+                                    builder.append_value(&bytes);
+                                }
+                                None => builder.append_null(),
+                            }
+                        }
+                        Some(Arc::new(builder.finish()) as ArrayRef)
+                    }
                     FFType::Junk => None,
                     numeric_column => Some(fetch_ff_column!(numeric_column, ids,
                         I64  => identity => Int64Builder,
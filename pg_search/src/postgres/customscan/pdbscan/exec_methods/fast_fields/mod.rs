@@ -22,20 +22,25 @@ use std::sync::Arc;
 use crate::api::FieldName;
 use crate::api::HashSet;
 use crate::gucs;
-use crate::index::fast_fields_helper::{FFHelper, FastFieldType, WhichFastField};
+use crate::index::fast_fields_helper::{FFHelper, FFType, FastFieldType, WhichFastField};
+use crate::index::reader::index::SearchIndexScore;
 use crate::nodecast;
 use crate::postgres::customscan::builders::custom_state::CustomScanStateWrapper;
 use crate::postgres::customscan::explainer::Explainer;
 use crate::postgres::customscan::pdbscan::privdat::PrivateData;
 use crate::postgres::customscan::pdbscan::projections::score::uses_scores;
-use crate::postgres::customscan::pdbscan::{scan_state::PdbScanState, PdbScan};
+use crate::postgres::customscan::pdbscan::{
+    is_block_all_visible, scan_state::PdbScanState, PdbScan,
+};
 use crate::postgres::customscan::score_funcoid;
 use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::types::TantivyValue;
 use crate::postgres::var::{find_one_var, find_one_var_and_fieldname, VarContext};
 
 use arrow_array::builder::StringViewBuilder;
 use arrow_array::ArrayRef;
 use itertools::Itertools;
+use pgrx::itemptr::item_pointer_get_block_number;
 use pgrx::pg_sys::CustomScanState;
 use pgrx::{pg_sys, IntoDatum, PgList, PgOid, PgTupleDesc};
 use tantivy::columnar::StrColumn;
@@ -87,7 +92,7 @@ impl FastFieldExecState {
         }
     }
 
-    fn init(&mut self, state: &mut PdbScanState, cstate: *mut CustomScanState) {
+    pub(crate) fn init(&mut self, state: &mut PdbScanState, cstate: *mut CustomScanState) {
         unsafe {
             self.heaprel = Some(Clone::clone(state.heaprel()));
             self.tupdesc = Some(PgTupleDesc::from_pg_unchecked(
@@ -109,6 +114,85 @@ impl FastFieldExecState {
         self.did_query = false;
         self.blockvis = (pg_sys::InvalidBlockNumber, false);
     }
+
+    /// Render a single matching document into our virtual slot directly from its fast fields.
+    ///
+    /// This is only possible if the document's heap block is all-visible: otherwise we return
+    /// [`None`], and the caller must fetch the tuple from the heap to check its visibility.
+    ///
+    /// Unlike [`mixed::MixedFastFieldExecState`], which looks up string fast fields for a whole
+    /// batch at once, this looks up one row at a time, which is what a Top-N scan wants.
+    pub(crate) unsafe fn project(
+        &mut self,
+        scored: SearchIndexScore,
+        doc_address: DocAddress,
+    ) -> Option<*mut pg_sys::TupleTableSlot> {
+        let heaprel = self
+            .heaprel
+            .as_ref()
+            .expect("FastFieldExecState: heaprel should be initialized");
+        let slot = self.slot;
+        crate::postgres::utils::u64_to_item_pointer(scored.ctid, &mut (*slot).tts_tid);
+        (*slot).tts_tableOid = heaprel.oid();
+
+        let blockno = item_pointer_get_block_number(&(*slot).tts_tid);
+        if blockno != self.blockvis.0 {
+            self.blockvis = (
+                blockno,
+                is_block_all_visible(heaprel, &mut self.vmbuff, blockno),
+            );
+        }
+        if !self.blockvis.1 {
+            return None;
+        }
+
+        let natts = (*(*slot).tts_tupleDescriptor).natts as usize;
+        (*slot).tts_flags &= !pg_sys::TTS_FLAG_EMPTY as u16;
+        (*slot).tts_flags |= pg_sys::TTS_FLAG_SHOULDFREE as u16;
+        (*slot).tts_nvalid = natts as _;
+
+        let datums = std::slice::from_raw_parts_mut((*slot).tts_values, natts);
+        let isnull = std::slice::from_raw_parts_mut((*slot).tts_isnull, natts);
+        let tupdesc = self.tupdesc.as_ref().unwrap();
+        debug_assert!(natts == self.which_fast_fields.len());
+
+        for (i, (att, which_fast_field)) in tupdesc.iter().zip(&self.which_fast_fields).enumerate()
+        {
+            let datum = if matches!(
+                which_fast_field,
+                WhichFastField::Named(_, FastFieldType::String)
+            ) {
+                match self.ffhelper.column(doc_address.segment_ord, i) {
+                    FFType::Text(str_column) => str_column
+                        .term_ords(doc_address.doc_id)
+                        .next()
+                        .and_then(|ord| {
+                            let mut s = String::new();
+                            str_column
+                                .ord_to_str(ord, &mut s)
+                                .expect("string should be retrievable for term ord");
+                            TantivyValue(s.into())
+                                .try_into_datum(PgOid::from(att.atttypid))
+                                .expect("value should be convertible to Datum")
+                        }),
+                    _ => None,
+                }
+            } else {
+                non_string_ff_to_datum(
+                    (which_fast_field, i),
+                    att.atttypid,
+                    scored.bm25,
+                    doc_address,
+                    &mut self.ffhelper,
+                    slot,
+                )
+            };
+            datums[i] = datum.unwrap_or_else(pg_sys::Datum::null);
+            isnull[i] = datum.is_none();
+        }
+
+        Some(slot)
+    }
 }
 
 /// Extracts a non-String fast field value to a Datum.
@@ -216,7 +300,7 @@ fn collect_fast_field_try_for_attno(
                     .schema()
                     .expect("pullup_fast_fields: should have a schema");
                 if let Some(search_field) = schema.search_field(att.name()) {
-                    if search_field.is_fast() {
+                    if search_field.has_exact_fast_values() {
                         let ff_type = match att.type_oid().value() {
                             pg_sys::TEXTOID | pg_sys::VARCHAROID | pg_sys::UUIDOID => {
                                 FastFieldType::String
@@ -232,7 +316,9 @@ fn collect_fast_field_try_for_attno(
                             | pg_sys::TIMEOID
                             | pg_sys::TIMESTAMPOID
                             | pg_sys::TIMESTAMPTZOID
-                            | pg_sys::TIMETZOID => FastFieldType::Numeric,
+                            | pg_sys::TIMETZOID
+                            // bytea values are fetched one at a time, like the numeric types
+                            | pg_sys::BYTEAOID => FastFieldType::Numeric,
                             _ => {
                                 // This fast field type is supported for pushdown of queries, but not for
                                 // rendering via fast field execution.
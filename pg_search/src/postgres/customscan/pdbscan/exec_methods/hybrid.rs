@@ -0,0 +1,187 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::api::{OrderByFeature, OrderByInfo, SortDirection};
+use crate::gucs;
+use crate::index::fast_fields_helper::{FFHelper, FastFieldType, WhichFastField};
+use crate::index::reader::index::SearchIndexScore;
+use crate::postgres::customscan::pdbscan::exec_methods::{ExecMethod, ExecState};
+use crate::postgres::customscan::pdbscan::projections::hybrid::{
+    find_hybrid, FastDistance, Fusion,
+};
+use crate::postgres::customscan::pdbscan::scan_state::PdbScanState;
+use pgrx::{check_for_interrupts, pg_sys, FromDatum, PgList};
+use std::ptr::addr_of_mut;
+use tantivy::DocAddress;
+
+/// Returns the best BM25 matches in the order of their fused rankings with a distance, for a scan
+/// ordered by `paradedb.hybrid()`.
+///
+/// The distance of each candidate is computed from its row, which is fetched to do so, so the
+/// candidates that aren't visible are left out of both rankings.  When the distance can be read
+/// from a vector fast field instead, no rows are fetched until the results are returned, so every
+/// candidate, visible or not, is ranked.
+pub struct HybridScanExecState {
+    limit: usize,
+    fusion: Fusion,
+    fast_distance: Option<FastDistance>,
+
+    // set during init
+    slot: *mut pg_sys::TupleTableSlot,
+    distance: *mut pg_sys::ExprState,
+    econtext: *mut pg_sys::ExprContext,
+
+    results: std::vec::IntoIter<(f64, SearchIndexScore, DocAddress)>,
+    did_query: bool,
+}
+
+impl HybridScanExecState {
+    pub fn new(limit: usize, fusion: Fusion, fast_distance: Option<FastDistance>) -> Self {
+        Self {
+            limit,
+            fusion,
+            fast_distance,
+            slot: std::ptr::null_mut(),
+            distance: std::ptr::null_mut(),
+            econtext: std::ptr::null_mut(),
+            results: vec![].into_iter(),
+            did_query: false,
+        }
+    }
+
+    /// The candidate's distance, if its row is visible.
+    unsafe fn distance(&mut self, state: &mut PdbScanState, ctid: u64) -> Option<Option<f64>> {
+        let (slot, distance, econtext) = (self.slot, self.distance, self.econtext);
+        let result = state.visibility_checker().exec_if_visible(ctid, slot, |_| {
+            (*econtext).ecxt_scantuple = slot;
+            let mut isnull = false;
+            let datum = pg_sys::ExecEvalExpr(distance, econtext, &mut isnull);
+            f64::from_datum(datum, isnull)
+        });
+        pg_sys::MemoryContextReset((*econtext).ecxt_per_tuple_memory);
+        pg_sys::ExecClearTuple(slot);
+        result
+    }
+}
+
+impl ExecMethod for HybridScanExecState {
+    fn init(&mut self, state: &mut PdbScanState, cstate: *mut pg_sys::CustomScanState) {
+        if self.fast_distance.is_some() {
+            return self.reset(state);
+        }
+
+        unsafe {
+            let planstate = addr_of_mut!((*cstate).ss.ps);
+            let funcexpr = find_hybrid((*(*planstate).plan).targetlist.cast(), state.execution_rti)
+                .expect("the scan should return paradedb.hybrid()");
+            let args = PgList::<pg_sys::Expr>::from_pg((*funcexpr).args);
+
+            self.distance = pg_sys::ExecInitExpr(args.get_ptr(1).unwrap(), planstate);
+            self.econtext = (*planstate).ps_ExprContext;
+            self.slot = pg_sys::table_slot_create(state.heaprel().as_ptr(), std::ptr::null_mut());
+        }
+        self.reset(state)
+    }
+
+    fn query(&mut self, state: &mut PdbScanState) -> bool {
+        if self.did_query {
+            return false;
+        }
+        self.did_query = true;
+        state.query_count += 1;
+
+        let search_reader = state.search_reader.as_ref().unwrap();
+        let candidates = search_reader
+            .search_top_n_in_segments(
+                search_reader
+                    .segment_readers()
+                    .iter()
+                    .map(|segment| segment.segment_id()),
+                Some(&vec![OrderByInfo {
+                    feature: OrderByFeature::Score,
+                    direction: SortDirection::Desc,
+                }]),
+                gucs::hybrid_candidates().max(self.limit),
+                0,
+            )
+            .collect::<Vec<_>>();
+
+        let mut ranked = Vec::with_capacity(candidates.len());
+        match &self.fast_distance {
+            Some(fast_distance) => {
+                let ffhelper = FFHelper::with_fields(
+                    search_reader,
+                    &[WhichFastField::Named(
+                        fast_distance.field.to_string(),
+                        FastFieldType::Numeric,
+                    )],
+                );
+                for (scored, doc_address) in candidates {
+                    check_for_interrupts!();
+                    let distance = ffhelper
+                        .value(0, doc_address)
+                        .and_then(|value| fast_distance.distance(value.0));
+                    ranked.push((scored, doc_address, distance));
+                }
+            }
+            None => {
+                for (scored, doc_address) in candidates {
+                    check_for_interrupts!();
+                    if let Some(distance) = unsafe { self.distance(state, scored.ctid) } {
+                        ranked.push((scored, doc_address, distance));
+                    }
+                }
+            }
+        }
+
+        let fused = self.fusion.fuse(
+            &ranked
+                .iter()
+                .map(|(scored, _, distance)| (scored.bm25, *distance))
+                .collect::<Vec<_>>(),
+        );
+        let mut results = fused
+            .into_iter()
+            .zip(ranked)
+            .map(|(fused, (scored, doc_address, _))| (fused, scored, doc_address))
+            .collect::<Vec<_>>();
+        // ties keep their BM25 order
+        results.sort_by(|a, b| b.0.total_cmp(&a.0));
+        self.results = results.into_iter();
+
+        true
+    }
+
+    fn internal_next(&mut self, state: &mut PdbScanState) -> ExecState {
+        match self.results.next() {
+            None => ExecState::Eof,
+            Some((fused, scored, doc_address)) => {
+                state.hybrid_score = fused;
+                ExecState::RequiresVisibilityCheck {
+                    ctid: scored.ctid,
+                    score: scored.bm25,
+                    doc_address,
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self, _state: &mut PdbScanState) {
+        self.results = vec![].into_iter();
+        self.did_query = false;
+    }
+}
@@ -17,11 +17,17 @@
 
 use std::cell::RefCell;
 
-use crate::api::OrderByInfo;
+use crate::api::{OrderByFeature, OrderByInfo};
+use crate::gucs;
+use crate::index::fast_fields_helper::WhichFastField;
+use crate::index::reader::cache::{self, TopNCacheKey};
 use crate::index::reader::index::{SearchIndexReader, TopNSearchResults, MAX_TOPN_FEATURES};
+use crate::index::reader::parallel;
+use crate::postgres::customscan::pdbscan::exec_methods::fast_fields::FastFieldExecState;
 use crate::postgres::customscan::pdbscan::exec_methods::{ExecMethod, ExecState};
 use crate::postgres::customscan::pdbscan::parallel::checkout_segment;
 use crate::postgres::customscan::pdbscan::scan_state::PdbScanState;
+use crate::postgres::customscan::pdbscan::trace;
 use crate::postgres::ParallelScanState;
 use crate::query::SearchQueryInput;
 
@@ -31,12 +37,20 @@ use tantivy::index::SegmentId;
 // TODO:  should these be GUCs?  I think yes, probably
 const SUBSEQUENT_RETRY_SCALE_FACTOR: usize = 2;
 const MAX_CHUNK_SIZE: usize = 5000;
+// how many more of our rows than the LIMIT a join is first expected to need, as some of them
+// won't have a match on its other side
+const JOIN_OVERFETCH_FACTOR: usize = 4;
 
 pub struct TopNScanExecState {
     // required
     heaprelid: pg_sys::Oid,
     limit: usize,
     orderby_info: Option<Vec<OrderByInfo>>,
+    // if we're one side of a join, `limit` is only how many rows to start with
+    joined: bool,
+    // if every column we return is a fast field, rows on all-visible heap blocks are returned
+    // straight from the index
+    fast_fields: Option<FastFieldExecState>,
 
     // set during init
     search_query_input: Option<SearchQueryInput>,
@@ -59,6 +73,8 @@ impl TopNScanExecState {
         heaprelid: pg_sys::Oid,
         limit: usize,
         orderby_info: Option<Vec<OrderByInfo>>,
+        which_fast_fields: Option<Vec<WhichFastField>>,
+        joined: bool,
     ) -> Self {
         if matches!(&orderby_info, Some(orderby_info) if orderby_info.len() > MAX_TOPN_FEATURES) {
             panic!("Cannot sort by more than {MAX_TOPN_FEATURES} features.");
@@ -68,6 +84,8 @@ impl TopNScanExecState {
             heaprelid,
             limit,
             orderby_info,
+            joined,
+            fast_fields: which_fast_fields.map(FastFieldExecState::new),
             search_query_input: None,
             search_reader: None,
             search_results: TopNSearchResults::empty(),
@@ -134,9 +152,64 @@ impl TopNScanExecState {
             }
         }
     }
+
+    /// With `paradedb.enable_parallel_collection`, collect the top `limit` by score of a scan
+    /// that isn't parallel with parallel workers, if there are segments enough to share and any
+    /// workers could be launched.
+    fn collect_in_parallel(
+        &self,
+        state: &PdbScanState,
+        search_reader: &SearchIndexReader,
+        limit: usize,
+    ) -> Option<TopNSearchResults> {
+        let Some(
+            [OrderByInfo {
+                feature: OrderByFeature::Score,
+                direction,
+            }],
+        ) = self.orderby_info.as_deref()
+        else {
+            return None;
+        };
+        if !gucs::enable_parallel_collection()
+            || state.parallel_state.is_some()
+            || unsafe { pg_sys::IsInParallelMode() }
+            || search_reader.segment_readers().len() < 2
+            // the workers can't evaluate expressions of the leader's query
+            || state.search_query_input().depends_on_heap()
+        {
+            return None;
+        }
+
+        let results = parallel::search_top_n_by_score(
+            search_reader,
+            state.indexrelid,
+            state.search_query_input(),
+            *direction,
+            limit,
+            self.offset,
+        )
+        .expect("parallel collection should succeed");
+        if results.is_none() {
+            trace::trace(|| {
+                format!(
+                    "collecting the top {limit} of \"{}\" in this backend alone, because no parallel workers could be launched",
+                    state.indexrelname()
+                )
+            });
+        }
+        results
+    }
 }
 
 impl ExecMethod for TopNScanExecState {
+    fn init(&mut self, state: &mut PdbScanState, cstate: *mut pg_sys::CustomScanState) {
+        if let Some(fast_fields) = self.fast_fields.as_mut() {
+            fast_fields.init(state, cstate);
+        }
+        self.reset(state)
+    }
+
     ///
     /// Query more results.
     ///
@@ -148,7 +221,7 @@ impl ExecMethod for TopNScanExecState {
     fn query(&mut self, state: &mut PdbScanState) -> bool {
         self.did_query = true;
 
-        if self.found >= self.limit || self.exhausted {
+        if (self.found >= self.limit && !self.joined) || self.exhausted {
             return false;
         }
 
@@ -156,19 +229,52 @@ impl ExecMethod for TopNScanExecState {
         state.query_count += 1;
 
         // Calculate the limit for this query, and what the offset will be for the next query.
-        let local_limit = self.limit.max(self.chunk_size);
+        let limit = if self.joined {
+            self.limit * JOIN_OVERFETCH_FACTOR
+        } else {
+            self.limit
+        };
+        let local_limit = limit.max(self.chunk_size);
         let next_offset = self.offset + local_limit;
 
-        self.search_results = state
-            .search_reader
-            .as_ref()
-            .unwrap()
-            .search_top_n_in_segments(
-                self.segments_to_query(state.search_reader.as_ref().unwrap(), state.parallel_state),
-                self.orderby_info.as_ref(),
-                local_limit,
-                self.offset,
-            );
+        let search_reader = state.search_reader.as_ref().unwrap();
+        let cache_entries = gucs::search_cache_entries();
+        // a parallel scan's share of the segments depends on how fast each worker claims them
+        let cache_key = (cache_entries > 0 && state.parallel_state.is_none())
+            .then(|| {
+                TopNCacheKey::new(
+                    state.indexrelid,
+                    search_reader,
+                    state.search_query_input(),
+                    self.orderby_info.as_ref(),
+                    local_limit,
+                    self.offset,
+                )
+            })
+            .flatten();
+
+        self.search_results = match cache_key.as_ref().and_then(cache::get) {
+            Some(results) => {
+                state.cached_query_count += 1;
+                TopNSearchResults::new(results)
+            }
+            None => {
+                let search_results = self
+                    .collect_in_parallel(state, search_reader, local_limit)
+                    .unwrap_or_else(|| {
+                        search_reader.search_top_n_in_segments(
+                            self.segments_to_query(search_reader, state.parallel_state),
+                            self.orderby_info.as_ref(),
+                            local_limit,
+                            self.offset,
+                        )
+                    });
+                if let Some(cache_key) = cache_key {
+                    cache::insert(cache_key, search_results.remaining(), cache_entries);
+                }
+                search_results
+            }
+        };
 
         // Record the offset to start from for the next query.
         self.offset = next_offset;
@@ -195,12 +301,22 @@ impl ExecMethod for TopNScanExecState {
                         // we haven't even done a query yet, so this is our very first time in
                         return ExecState::Eof;
                     }
-                    None | Some(_) if self.found >= self.limit => {
+                    None | Some(_) if self.found >= self.limit && !self.joined => {
                         // we found all the matching rows
                         return ExecState::Eof;
                     }
                     Some((scored, doc_address)) => {
                         self.nresults += 1;
+                        if let Some(slot) = self
+                            .fast_fields
+                            .as_mut()
+                            .and_then(|fast_fields| fast_fields.project(scored, doc_address))
+                        {
+                            // the heap block is all-visible, so the row doesn't need to be
+                            // fetched to know that it's visible
+                            self.found += 1;
+                            return ExecState::Virtual { slot };
+                        }
                         return ExecState::RequiresVisibilityCheck {
                             ctid: scored.ctid,
                             score: scored.bm25,
@@ -250,6 +366,9 @@ impl ExecMethod for TopNScanExecState {
 
     fn reset(&mut self, state: &mut PdbScanState) {
         // Reset state
+        if let Some(fast_fields) = self.fast_fields.as_mut() {
+            fast_fields.reset(state);
+        }
         self.claimed_segments.take();
         self.did_query = false;
         self.exhausted = false;
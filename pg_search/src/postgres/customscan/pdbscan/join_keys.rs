@@ -0,0 +1,172 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Finding the join clauses whose keys a search can be restricted to.
+//!
+//! A search of one table that's joined to a search of another on `ours.field = theirs.key` can
+//! only return rows whose `field` is the `key` of one of the other search's matches, so it's
+//! restricted to those with a [`SearchQueryInput::JoinKeys`].  That's only possible when:
+//!
+//!   - all of the query's joins are inner joins, so that no row is returned without a match
+//!   - the other side is a table whose `WHERE` clause can be searched for in its BM25 index alone
+//!   - the other side's `key` is a fast field holding its exact values
+//!   - our `field` is indexed as an integer, or as text with the `keyword` tokenizer
+
+use crate::api::operator::anyelement_query_input_opoid;
+use crate::gucs;
+use crate::nodecast;
+use crate::postgres::customscan::builders::custom_path::RestrictInfoType;
+use crate::postgres::customscan::qual_inspect::{extract_quals, QualExtractState};
+use crate::postgres::rel_get_bm25_index;
+use crate::postgres::var::fieldname_from_var;
+use crate::query::SearchQueryInput;
+use crate::schema::{SearchField, SearchIndexSchema};
+use pgrx::{is_a, pg_sys, PgList};
+use tantivy::schema::FieldType;
+
+/// The [`SearchQueryInput::JoinKeys`] that the search of the relation `rti` can be restricted to,
+/// one for each other relation that it's joined to on a key that allows it.
+pub unsafe fn join_keys_queries(
+    root: *mut pg_sys::PlannerInfo,
+    rel: *mut pg_sys::RelOptInfo,
+    rti: pg_sys::Index,
+    schema: &SearchIndexSchema,
+) -> Vec<SearchQueryInput> {
+    let max_keys = gucs::max_join_keys();
+    // an outer, semi, or anti join can return rows that have no match on the other side
+    if max_keys == 0 || !(*rel).has_eclass_joins || !(*root).join_info_list.is_null() {
+        return vec![];
+    }
+
+    // a join on `ours.field = theirs.key` becomes an equivalence class with both as members
+    let mut queries = vec![];
+    for ec in PgList::<pg_sys::EquivalenceClass>::from_pg((*root).eq_classes).iter_ptr() {
+        if (*ec).ec_has_const || (*ec).ec_has_volatile {
+            continue;
+        }
+        let vars = PgList::<pg_sys::EquivalenceMember>::from_pg((*ec).ec_members)
+            .iter_ptr()
+            .filter(|em| !(**em).em_is_child)
+            .filter_map(|em| plain_var((*em).em_expr.cast()))
+            .filter(|var| (**var).varlevelsup == 0)
+            .collect::<Vec<_>>();
+        let Some(ours) = vars
+            .iter()
+            .find(|var| (***var).varno as pg_sys::Index == rti)
+        else {
+            continue;
+        };
+        queries.extend(
+            vars.iter()
+                .filter(|var| (***var).varno as pg_sys::Index != rti)
+                .filter_map(|theirs| join_keys_query(root, rti, *ours, *theirs, schema, max_keys)),
+        );
+    }
+    queries
+}
+
+unsafe fn join_keys_query(
+    root: *mut pg_sys::PlannerInfo,
+    rti: pg_sys::Index,
+    ours: *mut pg_sys::Var,
+    theirs: *mut pg_sys::Var,
+    schema: &SearchIndexSchema,
+    max_keys: usize,
+) -> Option<SearchQueryInput> {
+    let other_rti = (*theirs).varno as pg_sys::Index;
+    let rtable = (*(*root).parse).rtable;
+    let our_rte = pg_sys::rt_fetch(rti, rtable);
+    let other_rte = pg_sys::rt_fetch(other_rti, rtable);
+    if (*other_rte).rtekind != pg_sys::RTEKind::RTE_RELATION {
+        return None;
+    }
+    let field = fieldname_from_var((*our_rte).relid, ours, (*ours).varattno)?;
+    let key_field = fieldname_from_var((*other_rte).relid, theirs, (*theirs).varattno)?;
+
+    let (_, other_index) = rel_get_bm25_index((*other_rte).relid)?;
+    // a partial index doesn't have all of the rows that could match
+    if !other_index.rd_indpred.is_null() {
+        return None;
+    }
+    let other_schema = other_index.schema().ok()?;
+    let search_field = schema.search_field(&field)?;
+    let key_search_field = other_schema.search_field(&key_field)?;
+    if !is_joinable(&search_field, &key_search_field) {
+        return None;
+    }
+
+    // the other side's search, which must be entirely answerable by its index
+    let other_rel = *(*root).simple_rel_array.add(other_rti as usize);
+    if other_rel.is_null() || (*other_rel).baserestrictinfo.is_null() {
+        return None;
+    }
+    let mut state = QualExtractState::default();
+    let quals = extract_quals(
+        root,
+        other_rti,
+        (*other_rel).baserestrictinfo.cast(),
+        anyelement_query_input_opoid(),
+        RestrictInfoType::BaseRelation,
+        &other_index,
+        false,
+        &mut state,
+    )?;
+    if !state.uses_our_operator
+        || state.uses_heap_expr
+        || quals.contains_external_var()
+        || quals.contains_exprs()
+    {
+        return None;
+    }
+    let query = SearchQueryInput::from(&quals);
+    if query.depends_on_heap() {
+        return None;
+    }
+
+    Some(SearchQueryInput::JoinKeys {
+        field,
+        indexrelid: other_index.oid(),
+        key_field,
+        query: Box::new(query),
+        max_keys,
+    })
+}
+
+/// The [`pg_sys::Var`] that `node` is, looking through any binary-compatible cast.
+unsafe fn plain_var(mut node: *mut pg_sys::Node) -> Option<*mut pg_sys::Var> {
+    while let Some(relabel) = nodecast!(RelabelType, T_RelabelType, node) {
+        node = (*relabel).arg.cast();
+    }
+    is_a(node, pg_sys::NodeTag::T_Var).then(|| node.cast())
+}
+
+/// Can the values of `key_field`'s fast field be searched for as terms of `field`?
+fn is_joinable(field: &SearchField, key_field: &SearchField) -> bool {
+    if !field.field_entry().is_indexed() || !key_field.has_exact_fast_values() {
+        return false;
+    }
+    match (
+        field.field_entry().field_type(),
+        key_field.field_entry().field_type(),
+    ) {
+        (FieldType::Str(_), FieldType::Str(_)) => field.is_keyword(),
+        (FieldType::I64(_) | FieldType::U64(_), FieldType::I64(_) | FieldType::U64(_)) => {
+            field.numeric_scale().is_none()
+        }
+        _ => false,
+    }
+}
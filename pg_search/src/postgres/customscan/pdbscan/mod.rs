@@ -17,11 +17,13 @@
 
 #![allow(clippy::unnecessary_cast)] // helps with integer casting differences between postgres versions
 mod exec_methods;
+mod join_keys;
 pub mod parallel;
 mod privdat;
-mod projections;
+pub(crate) mod projections;
 mod scan_state;
 mod solve_expr;
+mod trace;
 
 use crate::api::operator::{anyelement_query_input_opoid, estimate_selectivity};
 use crate::api::{HashMap, HashSet, OrderByFeature, OrderByInfo};
@@ -43,16 +45,22 @@ use crate::postgres::customscan::pdbscan::exec_methods::{
 };
 use crate::postgres::customscan::pdbscan::parallel::{compute_nworkers, list_segment_ids};
 use crate::postgres::customscan::pdbscan::privdat::PrivateData;
+use crate::postgres::customscan::pdbscan::projections::highlight::Highlighter;
+use crate::postgres::customscan::pdbscan::projections::hybrid::{
+    find_hybrid, hybrid_funcoid, hybrid_pathkey,
+};
 use crate::postgres::customscan::pdbscan::projections::score::{is_score_func, uses_scores};
 use crate::postgres::customscan::pdbscan::projections::snippet::{
-    snippet_funcoid, snippet_positions_funcoid, uses_snippets, SnippetType,
+    highlights_funcoid, match_positions_funcoid, snippet_fragments_funcoid, snippet_funcoid,
+    snippet_positions_funcoid, uses_snippets, SnippetType,
 };
 use crate::postgres::customscan::pdbscan::projections::{
     inject_placeholders, maybe_needs_const_projections, pullout_funcexprs,
 };
 use crate::postgres::customscan::pdbscan::scan_state::PdbScanState;
 use crate::postgres::customscan::qual_inspect::{
-    extract_join_predicates, extract_quals, optimize_quals_with_heap_expr, Qual, QualExtractState,
+    extract_join_predicates, extract_quals, optimize_quals_with_heap_expr,
+    respects_security_barriers, Qual, QualExtractState,
 };
 use crate::postgres::customscan::score_funcoid;
 use crate::postgres::customscan::{
@@ -60,6 +68,7 @@ use crate::postgres::customscan::{
 };
 use crate::postgres::rel::PgSearchRelation;
 use crate::postgres::rel_get_bm25_index;
+use crate::postgres::stats::{self, ScanStats};
 use crate::postgres::var::find_var_relation;
 use crate::postgres::visibility_checker::VisibilityChecker;
 use crate::query::pdb_query::pdb;
@@ -68,10 +77,11 @@ use crate::schema::{SearchField, SearchIndexSchema};
 use crate::{nodecast, DEFAULT_STARTUP_COST, PARAMETERIZED_SELECTIVITY, UNKNOWN_SELECTIVITY};
 use crate::{FULL_RELATION_SELECTIVITY, UNASSIGNED_SELECTIVITY};
 use pgrx::pg_sys::CustomExecMethods;
-use pgrx::{direct_function_call, pg_sys, IntoDatum, PgList, PgMemoryContexts};
+use pgrx::{direct_function_call, pg_sys, IntoDatum, JsonB, PgList, PgMemoryContexts};
 use std::ffi::CStr;
 use std::ptr::addr_of_mut;
 use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
 use tantivy::snippet::SnippetGenerator;
 use tantivy::Index;
 
@@ -115,11 +125,29 @@ impl PdbScan {
                 }
             })
             .expect("should be able to open the search index reader");
+        let search_reader = match state.custom_state().search_timings.clone() {
+            Some(search_timings) => search_reader.with_timings(search_timings),
+            None => search_reader,
+        };
+        // a rescan searches with the same query, so it's only reported the first time
+        let is_first_search = state.custom_state().search_reader.is_none();
         state.custom_state_mut().search_reader = Some(search_reader);
 
         let csstate = addr_of_mut!(state.csstate);
         state.custom_state_mut().init_exec_method(csstate);
 
+        if is_first_search && unsafe { pg_sys::ParallelWorkerNumber == -1 } {
+            let custom_state = state.custom_state();
+            trace::trace(|| {
+                format!(
+                    "executing the search of \"{}\" with {}, for the tantivy query {:?}",
+                    custom_state.indexrelname(),
+                    custom_state.exec_method_name().split("::").last().unwrap(),
+                    custom_state.search_reader.as_ref().unwrap().query(),
+                )
+            });
+        }
+
         if state.custom_state().need_snippets() {
             let mut snippet_generators: HashMap<
                 SnippetType,
@@ -144,6 +172,7 @@ impl PdbScan {
                     None
                 };
 
+            let mut highlighters = HashMap::default();
             for (snippet_type, generator) in &mut snippet_generators {
                 // Use enhanced query if available, otherwise use base query
                 let query_to_use = enhanced_query_for_snippets
@@ -159,16 +188,36 @@ impl PdbScan {
 
                 // If SnippetType::Positions, set max_num_chars to u32::MAX because the entire doc must be considered
                 // This assumes text fields can be no more than u32::MAX bytes
+                // Several fragments are chosen from the highlights of the entire doc, too, except in
+                // a whole JSON column, where each string is a fragment of its own, and the entire
+                // doc is highlighted when it's shown whole
                 let max_num_chars = match snippet_type {
-                    SnippetType::Text(_, _, config) => config.max_num_chars,
-                    SnippetType::Positions(_, _) => u32::MAX as usize,
+                    SnippetType::Text(field, _, config)
+                    | SnippetType::Fragments(field, _, config)
+                        if config.number_of_fragments == 1
+                            || (config.number_of_fragments != 0
+                                && state.custom_state().is_whole_json_field(field)) =>
+                    {
+                        config.max_num_chars
+                    }
+                    _ => u32::MAX as usize,
                 };
                 new_generator.1.set_max_num_chars(max_num_chars);
 
+                if let Some(highlighter) = Highlighter::new(
+                    state.custom_state().search_reader.as_ref().unwrap(),
+                    snippet_type.field(),
+                    query_to_use,
+                    &new_generator.1,
+                ) {
+                    highlighters.insert(snippet_type.clone(), highlighter);
+                }
+
                 *generator = Some(new_generator);
             }
 
             state.custom_state_mut().snippet_generators = snippet_generators;
+            state.custom_state_mut().highlighters = highlighters;
         }
 
         unsafe {
@@ -263,6 +312,253 @@ impl PdbScan {
 
         quals.clone()
     }
+
+    /// Return the next tuple of the scan, or null once there are no more.
+    #[allow(clippy::blocks_in_conditions)]
+    fn exec_search(state: &mut CustomScanStateWrapper<Self>) -> *mut pg_sys::TupleTableSlot {
+        if state.custom_state().search_reader.is_none() {
+            Self::init_search_reader(state);
+        }
+
+        loop {
+            let exec_method = state.custom_state_mut().exec_method_mut();
+
+            // get the next matching document from our search results and look for it in the heap
+            match exec_method.next(state.custom_state_mut()) {
+                // reached the end of the SearchResults
+                ExecState::Eof => {
+                    return std::ptr::null_mut();
+                }
+
+                // SearchResults found a match
+                ExecState::RequiresVisibilityCheck {
+                    ctid,
+                    score,
+                    doc_address,
+                } => {
+                    unsafe {
+                        let heap_fetch_start = state
+                            .custom_state()
+                            .search_timings
+                            .is_some()
+                            .then(Instant::now);
+                        let visible = check_visibility(state, ctid, state.scanslot().cast());
+                        if let Some(heap_fetch_start) = heap_fetch_start {
+                            state.custom_state_mut().heap_fetch_time += heap_fetch_start.elapsed();
+                        }
+                        let slot = match visible {
+                            // the ctid is visible
+                            Some(slot) => {
+                                exec_method.increment_visible();
+                                state.custom_state_mut().heap_tuple_check_count += 1;
+                                slot
+                            }
+
+                            // the ctid is not visible
+                            None => {
+                                state.custom_state_mut().invisible_tuple_count += 1;
+                                continue;
+                            }
+                        };
+
+                        if !state.custom_state().need_scores()
+                            && !state.custom_state().need_snippets()
+                        {
+                            //
+                            // we don't need scores or snippets
+                            // do the projection and return
+                            //
+
+                            (*(*state.projection_info()).pi_exprContext).ecxt_scantuple = slot;
+                            return pg_sys::ExecProject(state.projection_info());
+                        } else {
+                            //
+                            // we do need scores or snippets
+                            //
+                            // replace their placeholder values and then rebuild the ProjectionInfo
+                            // and project it
+                            //
+
+                            let mut per_tuple_context = PgMemoryContexts::For(
+                                (*(*state.projection_info()).pi_exprContext).ecxt_per_tuple_memory,
+                            );
+                            per_tuple_context.reset();
+
+                            if state.custom_state().need_scores() {
+                                let const_score_node = state
+                                    .custom_state()
+                                    .const_score_node
+                                    .expect("const_score_node should be set");
+                                (*const_score_node).constvalue = score.into_datum().unwrap();
+                                (*const_score_node).constisnull = false;
+                            }
+
+                            if state.custom_state().need_hybrid {
+                                let const_hybrid_node = state
+                                    .custom_state()
+                                    .const_hybrid_node
+                                    .expect("const_hybrid_node should be set");
+                                (*const_hybrid_node).constvalue =
+                                    state.custom_state().hybrid_score.into_datum().unwrap();
+                                (*const_hybrid_node).constisnull = false;
+                            }
+
+                            if state.custom_state().need_snippets() {
+                                per_tuple_context.switch_to(|_| {
+                                    for (snippet_type, const_snippet_nodes) in
+                                        &state.custom_state().const_snippet_nodes
+                                    {
+                                        match snippet_type {
+                                            SnippetType::Text(_, _, config) => {
+                                                let snippet = state.custom_state().make_snippet(
+                                                    ctid,
+                                                    doc_address,
+                                                    snippet_type,
+                                                );
+
+                                                for const_ in const_snippet_nodes {
+                                                    match &snippet {
+                                                        Some(text) => {
+                                                            (**const_).constvalue =
+                                                                text.into_datum().unwrap();
+                                                            (**const_).constisnull = false;
+                                                        }
+                                                        None => {
+                                                            (**const_).constvalue =
+                                                                pg_sys::Datum::null();
+                                                            (**const_).constisnull = true;
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            SnippetType::Positions(..) => {
+                                                let positions =
+                                                    state.custom_state().get_snippet_positions(
+                                                        ctid,
+                                                        doc_address,
+                                                        snippet_type,
+                                                    );
+
+                                                for const_ in const_snippet_nodes {
+                                                    match &positions {
+                                                        Some(positions) => {
+                                                            (**const_).constvalue = positions
+                                                                .clone()
+                                                                .into_datum()
+                                                                .unwrap();
+                                                            (**const_).constisnull = false;
+                                                        }
+                                                        None => {
+                                                            (**const_).constvalue =
+                                                                pg_sys::Datum::null();
+                                                            (**const_).constisnull = true;
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            SnippetType::Fragments(..) => {
+                                                let fragments =
+                                                    state.custom_state().get_snippet_fragments(
+                                                        ctid,
+                                                        doc_address,
+                                                        snippet_type,
+                                                    );
+
+                                                for const_ in const_snippet_nodes {
+                                                    match &fragments {
+                                                        Some(fragments) => {
+                                                            (**const_).constvalue =
+                                                                JsonB(fragments.0.clone())
+                                                                    .into_datum()
+                                                                    .unwrap();
+                                                            (**const_).constisnull = false;
+                                                        }
+                                                        None => {
+                                                            (**const_).constvalue =
+                                                                pg_sys::Datum::null();
+                                                            (**const_).constisnull = true;
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            SnippetType::MatchPositions(..) => {
+                                                let matches =
+                                                    state.custom_state().get_match_positions(
+                                                        ctid,
+                                                        doc_address,
+                                                        snippet_type,
+                                                    );
+
+                                                for const_ in const_snippet_nodes {
+                                                    match &matches {
+                                                        Some(matches) => {
+                                                            (**const_).constvalue =
+                                                                JsonB(matches.0.clone())
+                                                                    .into_datum()
+                                                                    .unwrap();
+                                                            (**const_).constisnull = false;
+                                                        }
+                                                        None => {
+                                                            (**const_).constvalue =
+                                                                pg_sys::Datum::null();
+                                                            (**const_).constisnull = true;
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+
+                                    for (snippet_types, const_) in
+                                        &state.custom_state().const_highlights_nodes
+                                    {
+                                        match state.custom_state().make_highlights(
+                                            ctid,
+                                            doc_address,
+                                            snippet_types,
+                                        ) {
+                                            Some(highlights) => {
+                                                (**const_).constvalue =
+                                                    highlights.into_datum().unwrap();
+                                                (**const_).constisnull = false;
+                                            }
+                                            None => {
+                                                (**const_).constvalue = pg_sys::Datum::null();
+                                                (**const_).constisnull = true;
+                                            }
+                                        }
+                                    }
+                                });
+                            }
+
+                            // finally, do the projection
+                            return per_tuple_context.switch_to(|_| {
+                                let planstate = state.planstate();
+
+                                (*(*state.projection_info()).pi_exprContext).ecxt_scantuple = slot;
+                                let proj_info = pg_sys::ExecBuildProjectionInfo(
+                                    state
+                                        .custom_state()
+                                        .placeholder_targetlist
+                                        .expect("placeholder_targetlist must be set"),
+                                    (*planstate).ps_ExprContext,
+                                    (*planstate).ps_ResultTupleSlot,
+                                    planstate,
+                                    (*state.csstate.ss.ss_ScanTupleSlot).tts_tupleDescriptor,
+                                );
+                                pg_sys::ExecProject(proj_info)
+                            });
+                        }
+                    }
+                }
+
+                ExecState::Virtual { slot } => {
+                    state.custom_state_mut().virtual_tuple_count += 1;
+                    return slot;
+                }
+            }
+        }
+    }
 }
 
 impl customscan::ExecMethod for PdbScan {
@@ -352,6 +648,17 @@ impl CustomScan for PdbScan {
                 None
             };
 
+            // in a join, the LIMIT counts joined rows, which may need any number of ours, but if
+            // the join is ordered by our scores alone, we can still return our rows in that order
+            let join_limit = if limit.is_none()
+                && (*builder.args().root).limit_tuples > -1.0
+                && matches!(topn_pathkey_info.pathkeys(), Some(pathkeys) if pathkeys.iter().all(|pathkey| matches!(pathkey, OrderByStyle::Score(..))))
+            {
+                Some((*builder.args().root).limit_tuples)
+            } else {
+                None
+            };
+
             // quick look at the target list to see if we might need to do our const projections
             let target_list = (*(*builder.args().root).parse).targetList;
             let maybe_needs_const_projections = maybe_needs_const_projections(target_list.cast());
@@ -362,7 +669,11 @@ impl CustomScan for PdbScan {
             // Save the count of referenced columns for decision-making
             custom_private.set_referenced_columns_count(referenced_columns.len());
 
+            // a search ordered by `paradedb.hybrid()` can only rank so many of its best matches
+            let hybrid = limit.and_then(|_| hybrid_pathkey(root, rti, &schema));
+
             let is_maybe_topn = limit.is_some() && topn_pathkey_info.is_usable();
+            let is_maybe_joined_topn = join_limit.is_some() && topn_pathkey_info.is_usable();
 
             // When collecting which_fast_fields, analyze the entire set of referenced columns,
             // not just those in the target list. To avoid execution-time surprises, the "planned"
@@ -398,14 +709,57 @@ impl CustomScan for PdbScan {
                 // if we are not able to push down all of the quals, then do not propose the custom
                 // scan, as that would mean executing filtering against heap tuples (which amounts
                 // to a join, and would require more planning).
+                trace::trace(|| {
+                    let unsupported = trace::unsupported_clauses(root, rti, rel, &bm25_index);
+                    let reason = if unsupported.is_empty() {
+                        "none of its clauses use the @@@ operator".into()
+                    } else {
+                        format!(
+                            "these clauses can't be searched for in the index: {}",
+                            unsupported.join(", ")
+                        )
+                    };
+                    format!(
+                        "not searching \"{}\" with \"{}\": {reason}",
+                        table.name(),
+                        bm25_index.name()
+                    )
+                });
                 return None;
             };
 
+            // nor if that would evaluate a leaky filter ahead of a row-level security policy
+            if !respects_security_barriers(
+                root,
+                rti,
+                &restrict_info,
+                anyelement_query_input_opoid(),
+                ri_type,
+                &bm25_index,
+                matches!(ri_type, RestrictInfoType::Join),
+            ) {
+                trace::trace(|| {
+                    format!(
+                        "not searching \"{}\" with \"{}\": a leaky clause would be evaluated before a row-level security policy",
+                        table.name(),
+                        bm25_index.name()
+                    )
+                });
+                return None;
+            }
+
             // Check if this is a partial index and if the query is compatible with it
             if !bm25_index.rd_indpred.is_null() {
                 // This is a partial index - we need to check if the query can be satisfied by it
                 if !quals.is_query_compatible_with_partial_index() {
                     // The query cannot be satisfied by this partial index, fall back to heap scan
+                    trace::trace(|| {
+                        format!(
+                            "not searching \"{}\" with \"{}\": the query may match rows that the partial index doesn't have",
+                            table.name(),
+                            bm25_index.name()
+                        )
+                    });
                     return None;
                 }
             }
@@ -432,6 +786,49 @@ impl CustomScan for PdbScan {
                 estimate_selectivity(&bm25_index, query.clone()).unwrap_or(UNKNOWN_SELECTIVITY)
             };
 
+            // an inner join to another search on a key can only return our rows with one of the
+            // keys of its matches, so we needn't return any others
+            let join_keys = join_keys::join_keys_queries(root, rel, rti, &schema);
+            let query = if join_keys.is_empty() {
+                query
+            } else {
+                SearchQueryInput::Boolean {
+                    must: std::iter::once(query).chain(join_keys).collect(),
+                    should: vec![],
+                    must_not: vec![],
+                }
+            };
+
+            trace::trace(|| {
+                format!(
+                    "searching \"{}\" with \"{}\" for its {}: {}",
+                    table.name(),
+                    bm25_index.name(),
+                    match ri_type {
+                        // see `extract_all_possible_quals`
+                        RestrictInfoType::Join => "join clauses, to score every row",
+                        _ => "WHERE clauses",
+                    },
+                    serde_json::to_string(&query)
+                        .unwrap_or_else(|e| format!("<unserializable query: {e}>")),
+                )
+            });
+            let heap_exprs = quals.heap_exprs();
+            if !heap_exprs.is_empty() {
+                trace::trace(|| {
+                    format!(
+                        "checking these clauses against the rows of \"{}\", because \"{}\" can't: {}",
+                        table.name(),
+                        bm25_index.name(),
+                        heap_exprs
+                            .iter()
+                            .map(|node| trace::deparse(root, rti, *node))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                });
+            }
+
             // we must use this path if we need to do const projections for scores or snippets
             builder = builder.set_force_path(
                 maybe_needs_const_projections || is_maybe_topn || quals.contains_all(),
@@ -442,23 +839,28 @@ impl CustomScan for PdbScan {
             custom_private.set_range_table_index(rti);
             custom_private.set_query(query);
             custom_private.set_limit(limit);
+            custom_private.set_join_limit(join_limit);
             custom_private.set_segment_count(segment_count);
 
             // Determine whether we might be able to sort.
-            if is_maybe_topn && topn_pathkey_info.pathkeys().is_some() {
-                let pathkeys = topn_pathkey_info.pathkeys().unwrap();
-                // we can only (currently) do const projections if the first sort field is a score,
-                // because we currently discard all but the first sort field, and so will not
-                // produce a valid Score value. see TopNSearchResults.
-                let orderby_supported = !maybe_needs_const_projections
-                    || matches!(pathkeys.first(), Some(OrderByStyle::Score(..)));
-                if orderby_supported {
-                    custom_private.set_maybe_orderby_info(Some(pathkeys));
-                }
+            if (is_maybe_topn || is_maybe_joined_topn) && topn_pathkey_info.pathkeys().is_some() {
+                // when the first sort field isn't the score, the top n are scored after they're
+                // found, if scores are projected. see `SearchIndexReader::with_scores`.
+                custom_private.set_maybe_orderby_info(topn_pathkey_info.pathkeys());
             }
 
             // Choose the exec method type, and make claims about whether it is sorted.
-            let exec_method_type = choose_exec_method(&custom_private, &topn_pathkey_info);
+            let exec_method_type = match hybrid {
+                Some((pathkey, fusion, distance)) => {
+                    builder = builder.add_raw_path_key(pathkey);
+                    ExecMethodType::Hybrid {
+                        limit: custom_private.limit().expect("limit must be set"),
+                        fusion,
+                        distance,
+                    }
+                }
+                None => choose_exec_method(&custom_private, &topn_pathkey_info),
+            };
             custom_private.set_exec_method_type(exec_method_type);
             if custom_private.exec_method_type().is_sorted_topn() {
                 // TODO: Note that the ExecMethodType does not actually hold a pg_sys::PathKey,
@@ -559,10 +961,22 @@ impl CustomScan for PdbScan {
             let score_funcoid = score_funcoid();
             let snippet_funcoid = snippet_funcoid();
             let snippet_positions_funcoid = snippet_positions_funcoid();
+            let match_positions_funcoid = match_positions_funcoid();
+            let highlights_funcoid = highlights_funcoid();
+            let snippet_fragments_funcoid = snippet_fragments_funcoid();
+            let hybrid_funcoid = hybrid_funcoid();
             for te in processed_tlist.iter_ptr() {
                 let func_vars_at_level = pullout_funcexprs(
                     te.cast(),
-                    &[score_funcoid, snippet_funcoid, snippet_positions_funcoid],
+                    &[
+                        score_funcoid,
+                        hybrid_funcoid,
+                        snippet_funcoid,
+                        snippet_positions_funcoid,
+                        match_positions_funcoid,
+                        highlights_funcoid,
+                        snippet_fragments_funcoid,
+                    ],
                     rti,
                     builder.args().root,
                 );
@@ -658,16 +1072,39 @@ impl CustomScan for PdbScan {
             let score_funcoid = score_funcoid();
             let snippet_funcoid = snippet_funcoid();
             let snippet_positions_funcoid = snippet_positions_funcoid();
+            let match_positions_funcoid = match_positions_funcoid();
+            let highlights_funcoid = highlights_funcoid();
+            let snippet_fragments_funcoid = snippet_fragments_funcoid();
 
             builder.custom_state().score_funcoid = score_funcoid;
             builder.custom_state().snippet_funcoid = snippet_funcoid;
             builder.custom_state().snippet_positions_funcoid = snippet_positions_funcoid;
+            builder.custom_state().match_positions_funcoid = match_positions_funcoid;
+            builder.custom_state().highlights_funcoid = highlights_funcoid;
+            builder.custom_state().snippet_fragments_funcoid = snippet_fragments_funcoid;
             builder.custom_state().need_scores = uses_scores(
                 builder.target_list().as_ptr().cast(),
                 score_funcoid,
                 builder.custom_state().execution_rti,
             );
 
+            builder.custom_state().hybrid_funcoid = hybrid_funcoid();
+            builder.custom_state().need_hybrid = matches!(
+                builder.custom_state().exec_method_type,
+                ExecMethodType::Hybrid { .. }
+            );
+            if !builder.custom_state().need_hybrid
+                && find_hybrid(
+                    builder.target_list().as_ptr().cast(),
+                    builder.custom_state().execution_rti,
+                )
+                .is_some()
+            {
+                pgrx::error!(
+                    "paradedb.hybrid() can only be used to order a search of one table, like `ORDER BY paradedb.hybrid(...) DESC LIMIT n`"
+                );
+            }
+
             // Store join snippet predicates in the scan state
             builder.custom_state().join_predicates =
                 builder.custom_private().join_predicates().clone();
@@ -725,6 +1162,9 @@ impl CustomScan for PdbScan {
                 node,
                 snippet_funcoid,
                 snippet_positions_funcoid,
+                match_positions_funcoid,
+                highlights_funcoid,
+                snippet_fragments_funcoid,
             )
             .into_iter()
             .map(|field| (field, None))
@@ -807,6 +1247,26 @@ impl CustomScan for PdbScan {
             );
         }
 
+        if let ExecMethodType::Hybrid {
+            limit,
+            fusion,
+            distance,
+        } = &state.custom_state().exec_method_type
+        {
+            explainer.add_text("   Hybrid Fusion", fusion.to_string());
+            explainer.add_text(
+                "   Hybrid Distance",
+                distance
+                    .as_ref()
+                    .map_or_else(|| String::from("heap"), |distance| distance.to_string()),
+            );
+            explainer.add_unsigned_integer(
+                "   Hybrid Candidates",
+                gucs::hybrid_candidates().max(*limit) as u64,
+                None,
+            );
+        }
+
         if let Some(limit) = state.custom_state().limit() {
             explainer.add_unsigned_integer("   TopN Limit", limit as u64, None);
             if explainer.is_analyze() {
@@ -815,10 +1275,46 @@ impl CustomScan for PdbScan {
                     state.custom_state().query_count as u64,
                     None,
                 );
+                if gucs::search_cache_entries() > 0 {
+                    explainer.add_unsigned_integer(
+                        "   Cached Queries",
+                        state.custom_state().cached_query_count as u64,
+                        None,
+                    );
+                }
             }
         }
 
         explainer.add_query(state.custom_state().base_search_query_input());
+
+        // the search only ran if the query is being explained with `ANALYZE`
+        if let Some(search_reader) = state.custom_state().search_reader.as_ref() {
+            if explainer.is_verbose() {
+                explainer.add_text("Tantivy Query Tree", format!("{:?}", search_reader.query()));
+                if let Some(clause_matches) =
+                    clause_matches(search_reader, state.custom_state().search_query_input())
+                {
+                    explainer.add_text("   Clause Matches", clause_matches);
+                }
+            }
+            if let Some(timings) = search_reader.timings().filter(|_| explainer.is_timing()) {
+                explainer.add_unsigned_integer(
+                    "Segments Visited",
+                    timings.segments_visited() as u64,
+                    None,
+                );
+                explainer.add_duration("Weight Creation Time", timings.weight_time());
+                explainer.add_duration("Collection Time", timings.collect_time());
+                explainer.add_duration("Heap Fetch Time", state.custom_state().heap_fetch_time);
+            }
+            if explainer.is_analyze() {
+                explainer.add_unsigned_integer(
+                    "Peak Memory",
+                    search_reader.memory().peak().div_ceil(1024) as u64,
+                    Some("kB"),
+                );
+            }
+        }
     }
 
     fn begin_custom_scan(
@@ -839,6 +1335,14 @@ impl CustomScan for PdbScan {
                 return;
             }
 
+            // `EXPLAIN (ANALYZE)` shows where the search's time goes, unless it's told not to, and
+            // so does the log of slow searches
+            if (*estate).es_instrument & pg_sys::InstrumentOption::INSTRUMENT_TIMER as i32 != 0
+                || gucs::log_slow_searches().is_some()
+            {
+                state.custom_state_mut().search_timings = Some(Default::default());
+            }
+
             // setup the structures we need to do mvcc checking
             state.custom_state_mut().visibility_checker =
                 Some(VisibilityChecker::with_rel_and_snap(
@@ -884,165 +1388,31 @@ impl CustomScan for PdbScan {
     }
 
     fn rescan_custom_scan(state: &mut CustomScanStateWrapper<Self>) {
+        let mark = ScanStats::start();
         Self::init_search_reader(state);
         state.custom_state_mut().reset();
+        state.custom_state_mut().scan_stats.stop(mark);
     }
 
-    #[allow(clippy::blocks_in_conditions)]
     fn exec_custom_scan(state: &mut CustomScanStateWrapper<Self>) -> *mut pg_sys::TupleTableSlot {
-        if state.custom_state().search_reader.is_none() {
-            Self::init_search_reader(state);
-        }
-
-        loop {
-            let exec_method = state.custom_state_mut().exec_method_mut();
-
-            // get the next matching document from our search results and look for it in the heap
-            match exec_method.next(state.custom_state_mut()) {
-                // reached the end of the SearchResults
-                ExecState::Eof => {
-                    return std::ptr::null_mut();
-                }
-
-                // SearchResults found a match
-                ExecState::RequiresVisibilityCheck {
-                    ctid,
-                    score,
-                    doc_address,
-                } => {
-                    unsafe {
-                        let slot = match check_visibility(state, ctid, state.scanslot().cast()) {
-                            // the ctid is visible
-                            Some(slot) => {
-                                exec_method.increment_visible();
-                                state.custom_state_mut().heap_tuple_check_count += 1;
-                                slot
-                            }
-
-                            // the ctid is not visible
-                            None => {
-                                state.custom_state_mut().invisible_tuple_count += 1;
-                                continue;
-                            }
-                        };
-
-                        if !state.custom_state().need_scores()
-                            && !state.custom_state().need_snippets()
-                        {
-                            //
-                            // we don't need scores or snippets
-                            // do the projection and return
-                            //
-
-                            (*(*state.projection_info()).pi_exprContext).ecxt_scantuple = slot;
-                            return pg_sys::ExecProject(state.projection_info());
-                        } else {
-                            //
-                            // we do need scores or snippets
-                            //
-                            // replace their placeholder values and then rebuild the ProjectionInfo
-                            // and project it
-                            //
-
-                            let mut per_tuple_context = PgMemoryContexts::For(
-                                (*(*state.projection_info()).pi_exprContext).ecxt_per_tuple_memory,
-                            );
-                            per_tuple_context.reset();
-
-                            if state.custom_state().need_scores() {
-                                let const_score_node = state
-                                    .custom_state()
-                                    .const_score_node
-                                    .expect("const_score_node should be set");
-                                (*const_score_node).constvalue = score.into_datum().unwrap();
-                                (*const_score_node).constisnull = false;
-                            }
-
-                            if state.custom_state().need_snippets() {
-                                per_tuple_context.switch_to(|_| {
-                                    for (snippet_type, const_snippet_nodes) in
-                                        &state.custom_state().const_snippet_nodes
-                                    {
-                                        match snippet_type {
-                                            SnippetType::Text(_, _, config) => {
-                                                let snippet = state
-                                                    .custom_state()
-                                                    .make_snippet(ctid, snippet_type);
-
-                                                for const_ in const_snippet_nodes {
-                                                    match &snippet {
-                                                        Some(text) => {
-                                                            (**const_).constvalue =
-                                                                text.into_datum().unwrap();
-                                                            (**const_).constisnull = false;
-                                                        }
-                                                        None => {
-                                                            (**const_).constvalue =
-                                                                pg_sys::Datum::null();
-                                                            (**const_).constisnull = true;
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                            SnippetType::Positions(..) => {
-                                                let positions = state
-                                                    .custom_state()
-                                                    .get_snippet_positions(ctid, snippet_type);
-
-                                                for const_ in const_snippet_nodes {
-                                                    match &positions {
-                                                        Some(positions) => {
-                                                            (**const_).constvalue = positions
-                                                                .clone()
-                                                                .into_datum()
-                                                                .unwrap();
-                                                            (**const_).constisnull = false;
-                                                        }
-                                                        None => {
-                                                            (**const_).constvalue =
-                                                                pg_sys::Datum::null();
-                                                            (**const_).constisnull = true;
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                });
-                            }
-
-                            // finally, do the projection
-                            return per_tuple_context.switch_to(|_| {
-                                let planstate = state.planstate();
-
-                                (*(*state.projection_info()).pi_exprContext).ecxt_scantuple = slot;
-                                let proj_info = pg_sys::ExecBuildProjectionInfo(
-                                    state
-                                        .custom_state()
-                                        .placeholder_targetlist
-                                        .expect("placeholder_targetlist must be set"),
-                                    (*planstate).ps_ExprContext,
-                                    (*planstate).ps_ResultTupleSlot,
-                                    planstate,
-                                    (*state.csstate.ss.ss_ScanTupleSlot).tts_tupleDescriptor,
-                                );
-                                pg_sys::ExecProject(proj_info)
-                            });
-                        }
-                    }
-                }
-
-                ExecState::Virtual { slot } => {
-                    state.custom_state_mut().virtual_tuple_count += 1;
-                    return slot;
-                }
-            }
-        }
+        let mark = ScanStats::start();
+        let slot = Self::exec_search(state);
+        state.custom_state_mut().scan_stats.stop(mark);
+        slot
     }
 
     fn shutdown_custom_scan(state: &mut CustomScanStateWrapper<Self>) {}
 
     fn end_custom_scan(state: &mut CustomScanStateWrapper<Self>) {
+        // a scan that was only explained never searched
+        if state.custom_state().search_reader.is_some() {
+            let scan_stats = state.custom_state().final_scan_stats();
+            stats::record_scan(state.custom_state().indexrelid, &scan_stats);
+            if gucs::log_slow_searches().is_some_and(|threshold| scan_stats.elapsed >= threshold) {
+                log_slow_search(state.custom_state(), &scan_stats);
+            }
+        }
+
         // get some things dropped now
         drop(state.custom_state_mut().visibility_checker.take());
         drop(state.custom_state_mut().search_reader.take());
@@ -1080,6 +1450,8 @@ fn choose_exec_method(privdata: &PrivateData, topn_pathkey_info: &PathKeyInfo) -
                 heaprelid: privdata.heaprelid().expect("heaprelid must be set"),
                 limit,
                 orderby_info: Some(orderby_info.clone()),
+                which_fast_fields: top_n_fast_fields(privdata),
+                joined: false,
             };
         }
         if matches!(topn_pathkey_info, PathKeyInfo::None) {
@@ -1090,10 +1462,25 @@ fn choose_exec_method(privdata: &PrivateData, topn_pathkey_info: &PathKeyInfo) -
                 heaprelid: privdata.heaprelid().expect("heaprelid must be set"),
                 limit,
                 orderby_info: None,
+                which_fast_fields: top_n_fast_fields(privdata),
+                joined: false,
             };
         }
     }
 
+    // Or a TopN that returns rows in order until the join above it has enough.
+    if let (Some(limit), Some(orderby_info)) =
+        (privdata.join_limit(), privdata.maybe_orderby_info())
+    {
+        return ExecMethodType::TopN {
+            heaprelid: privdata.heaprelid().expect("heaprelid must be set"),
+            limit,
+            orderby_info: Some(orderby_info.clone()),
+            which_fast_fields: top_n_fast_fields(privdata),
+            joined: true,
+        };
+    }
+
     // Otherwise, see if we can use a fast fields method.
     if fast_fields::is_mixed_fast_field_capable(privdata) {
         return ExecMethodType::FastFieldMixed {
@@ -1106,6 +1493,17 @@ fn choose_exec_method(privdata: &PrivateData, topn_pathkey_info: &PathKeyInfo) -
     ExecMethodType::Normal
 }
 
+///
+/// If every column a TopN scan returns is a fast field, the fast fields to return them from.
+///
+/// TopN then returns the rows that are on all-visible heap blocks directly from the index, like
+/// [`MixedFastFieldExecState`] does, and only fetches the rest from the heap.
+///
+fn top_n_fast_fields(privdata: &PrivateData) -> Option<HashSet<WhichFastField>> {
+    fast_fields::is_mixed_fast_field_capable(privdata)
+        .then(|| privdata.planned_which_fast_fields().clone().unwrap())
+}
+
 ///
 /// Creates and assigns the execution method which was chosen at planning time.
 ///
@@ -1122,8 +1520,31 @@ fn assign_exec_method(builder: &mut CustomScanStateBuilder<PdbScan, PrivateData>
             heaprelid,
             limit,
             orderby_info,
+            which_fast_fields,
+            joined,
+        } => {
+            // if the fast fields can't be used after all, TopN still works by fetching every row
+            // from the heap
+            let which_fast_fields = which_fast_fields.and_then(|which_fast_fields| {
+                compute_exec_which_fast_fields(builder, which_fast_fields)
+            });
+            builder.custom_state().assign_exec_method(
+                exec_methods::top_n::TopNScanExecState::new(
+                    heaprelid,
+                    limit,
+                    orderby_info,
+                    which_fast_fields,
+                    joined,
+                ),
+                None,
+            )
+        }
+        ExecMethodType::Hybrid {
+            limit,
+            fusion,
+            distance,
         } => builder.custom_state().assign_exec_method(
-            exec_methods::top_n::TopNScanExecState::new(heaprelid, limit, orderby_info),
+            exec_methods::hybrid::HybridScanExecState::new(limit, fusion, distance),
             None,
         ),
         ExecMethodType::FastFieldMixed {
@@ -1188,6 +1609,9 @@ fn compute_exec_which_fast_fields(
         // TODO: In order to implement https://github.com/paradedb/paradedb/issues/2623, we will
         // need to differentiate these cases, so that we can always emit the sort order that we
         // claimed.
+        trace::trace(|| {
+            "not reading fast fields, because the scan returns none of them after all".into()
+        });
         return None;
     }
 
@@ -1202,6 +1626,11 @@ fn compute_exec_which_fast_fields(
              was missing {missing_fast_fields:?} from {planned_which_fast_fields:?} \
              Falling back to Normal execution.",
         );
+        trace::trace(|| {
+            format!(
+                "not reading fast fields, because {missing_fast_fields:?} weren't planned to be read"
+            )
+        });
         return None;
     }
 
@@ -1222,6 +1651,62 @@ fn check_visibility(
         .exec_if_visible(ctid, bslot.cast(), move |heaprel| bslot.cast())
 }
 
+/// Log a search that took longer than `paradedb.log_slow_searches_ms`, with where its time went.
+fn log_slow_search(state: &PdbScanState, scan_stats: &ScanStats) {
+    let as_millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    let query = serde_json::to_string(state.search_query_input())
+        .unwrap_or_else(|e| format!("<unserializable query: {e}>"));
+    let phases = match state
+        .search_reader
+        .as_ref()
+        .and_then(|search_reader| search_reader.timings())
+    {
+        Some(timings) => format!(
+            "segments visited: {}, weight creation: {:.3} ms, collection: {:.3} ms, heap fetches: {:.3} ms",
+            timings.segments_visited(),
+            as_millis(timings.weight_time()),
+            as_millis(timings.collect_time()),
+            as_millis(state.heap_fetch_time),
+        ),
+        None => "no phase timings".into(),
+    };
+    pgrx::log!(
+        "slow search of index \"{}\" on \"{}\": {:.3} ms, {} segments, {} documents scanned, {phases}, query: {query}",
+        state.indexrelname(),
+        state.heaprelname(),
+        as_millis(scan_stats.elapsed),
+        state.segment_count,
+        scan_stats.docs_scanned,
+    );
+}
+
+/// How many documents each clause of a boolean query matches on its own, like
+/// `must[0]: 120, should[0]: 8`, or [`None`] if the query isn't boolean.
+fn clause_matches(
+    search_reader: &SearchIndexReader,
+    search_query_input: &SearchQueryInput,
+) -> Option<String> {
+    match search_query_input {
+        SearchQueryInput::WithIndex { query, .. } => clause_matches(search_reader, query),
+        SearchQueryInput::Boolean {
+            must,
+            should,
+            must_not,
+        } => Some(
+            [("must", must), ("should", should), ("must_not", must_not)]
+                .into_iter()
+                .flat_map(|(occur, clauses)| {
+                    clauses.iter().enumerate().map(move |(index, clause)| {
+                        format!("{occur}[{index}]: {}", search_reader.count(clause.clone()))
+                    })
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        _ => None,
+    }
+}
+
 unsafe fn inject_score_and_snippet_placeholders(state: &mut CustomScanStateWrapper<PdbScan>) {
     if !state.custom_state().need_scores() && !state.custom_state().need_snippets() {
         // scores/snippets aren't necessary so we use whatever we originally setup as our ProjectionInfo
@@ -1233,19 +1718,31 @@ unsafe fn inject_score_and_snippet_placeholders(state: &mut CustomScanStateWrapp
     // forced projection we must do later.
     let planstate = state.planstate();
 
-    let (targetlist, const_score_node, const_snippet_nodes) = inject_placeholders(
+    let (
+        targetlist,
+        const_score_node,
+        const_hybrid_node,
+        const_snippet_nodes,
+        const_highlights_nodes,
+    ) = inject_placeholders(
         (*(*planstate).plan).targetlist,
         state.custom_state().planning_rti,
         state.custom_state().score_funcoid,
+        state.custom_state().hybrid_funcoid,
         state.custom_state().snippet_funcoid,
         state.custom_state().snippet_positions_funcoid,
+        state.custom_state().match_positions_funcoid,
+        state.custom_state().highlights_funcoid,
+        state.custom_state().snippet_fragments_funcoid,
         &state.custom_state().var_attname_lookup,
         &state.custom_state().snippet_generators,
     );
 
     state.custom_state_mut().placeholder_targetlist = Some(targetlist);
     state.custom_state_mut().const_score_node = Some(const_score_node);
+    state.custom_state_mut().const_hybrid_node = Some(const_hybrid_node);
     state.custom_state_mut().const_snippet_nodes = const_snippet_nodes;
+    state.custom_state_mut().const_highlights_nodes = const_highlights_nodes;
 }
 
 pub enum PathKeyInfo {
@@ -1560,6 +2057,9 @@ fn base_query_has_search_predicates(
         SearchQueryInput::DisjunctionMax { disjuncts, .. } => disjuncts
             .iter()
             .any(|q| base_query_has_search_predicates(q, current_index_oid)),
+        SearchQueryInput::Element { query, .. } => {
+            base_query_has_search_predicates(query, current_index_oid)
+        }
 
         // These are NOT search predicates (they're range/exists/other predicates)
         SearchQueryInput::FieldedQuery { query: pdb::Query::Range { .. }, .. }
@@ -1569,7 +2069,8 @@ fn base_query_has_search_predicates(
         | SearchQueryInput::FieldedQuery { query: pdb::Query::RangeWithin { .. }, .. }
         | SearchQueryInput::FieldedQuery { query: pdb::Query::Exists, .. }
         | SearchQueryInput::FieldedQuery { query: pdb::Query::FastFieldRangeWeight { .. }, .. }
-        | SearchQueryInput::MoreLikeThis { .. } => false,
+        | SearchQueryInput::MoreLikeThis { .. }
+        | SearchQueryInput::JoinKeys { .. } => false,
 
         // These are search predicates that use the @@@ operator
         SearchQueryInput::FieldedQuery { query: pdb::Query::ParseWithField { query_string, .. }, .. } => {
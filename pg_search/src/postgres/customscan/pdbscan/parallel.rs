@@ -122,6 +122,11 @@ pub fn compute_nworkers(
         nworkers = nworkers.min(nworkers_for_limited_segments);
     }
 
+    if matches!(exec_method, ExecMethodType::Hybrid { .. }) {
+        // all of the candidates are needed in one place to rank them
+        return 0;
+    }
+
     if contains_external_var {
         // Don't attempt to parallelize during a join.
         // TODO: Re-evaluate.
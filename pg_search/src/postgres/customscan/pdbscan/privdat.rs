@@ -32,6 +32,8 @@ pub struct PrivateData {
     range_table_index: Option<pg_sys::Index>,
     query: Option<SearchQueryInput>,
     limit: Option<usize>,
+    // The LIMIT of a join that this relation is one side of.
+    join_limit: Option<usize>,
     // ORDER-BY info that will be used iff the appropriate ExecMethodType is chosen.
     maybe_orderby_info: Option<Vec<OrderByInfo>>,
     #[serde(with = "var_attname_lookup_serializer")]
@@ -173,6 +175,10 @@ impl PrivateData {
         self.limit = limit.map(|l| l.round() as usize);
     }
 
+    pub fn set_join_limit(&mut self, limit: Option<Cardinality>) {
+        self.join_limit = limit.map(|l| l.round() as usize);
+    }
+
     pub fn set_maybe_orderby_info(&mut self, style: Option<&Vec<OrderByStyle>>) {
         self.maybe_orderby_info = Some(OrderByStyle::extract_orderby_info(style));
     }
@@ -241,6 +247,10 @@ impl PrivateData {
         self.limit
     }
 
+    pub fn join_limit(&self) -> Option<usize> {
+        self.join_limit
+    }
+
     pub fn maybe_orderby_info(&self) -> &Option<Vec<OrderByInfo>> {
         &self.maybe_orderby_info
     }
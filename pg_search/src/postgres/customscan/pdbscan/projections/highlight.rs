@@ -0,0 +1,424 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Highlighting that follows the phrases of the query.
+//!
+//! Tantivy's [`SnippetGenerator`] highlights every occurrence of every term of the query, so the
+//! terms of a phrase are highlighted wherever they appear, even where they don't make up the
+//! phrase.  A [`Highlighter`] finds where the phrases and proximity clauses of the query match the
+//! text, with their slop and distance, and only highlights their terms there.  Phrases are found
+//! in the query's own clauses and in its parsed query strings.
+
+use crate::api::{FieldName, HashSet};
+use crate::index::reader::index::SearchIndexReader;
+use crate::index::term_vectors::{tokenize, Token};
+use crate::query::pdb_query::pdb;
+use crate::query::proximity::{ProximityClause, ProximityDistance};
+use crate::query::SearchQueryInput;
+use std::ops::Range;
+use tantivy::query::{BooleanQuery, Occur, PhraseQuery, Query};
+use tantivy::schema::Field;
+use tantivy::snippet::SnippetGenerator;
+use tantivy::tokenizer::TextAnalyzer;
+
+/// A clause of the query whose terms only match near each other.
+enum Span {
+    /// `terms` in order, with at most `slop` other tokens between each of them.  The last term
+    /// is a prefix of the token it matches, if `prefix` is set.
+    Phrase {
+        terms: Vec<String>,
+        slop: u32,
+        prefix: bool,
+    },
+    /// One of `left` and one of `right`, within `distance` of each other.
+    Proximity {
+        left: Vec<String>,
+        distance: ProximityDistance,
+        right: Vec<String>,
+    },
+}
+
+impl Span {
+    fn terms(&self) -> Vec<&String> {
+        match self {
+            Span::Phrase { terms, .. } => terms.iter().collect(),
+            Span::Proximity { left, right, .. } => left.iter().chain(right).collect(),
+        }
+    }
+}
+
+pub struct Highlighter {
+    analyzer: TextAnalyzer,
+    /// The terms that are highlighted wherever they appear.
+    free_terms: HashSet<String>,
+    spans: Vec<Span>,
+}
+
+impl Highlighter {
+    /// A highlighter for the text `field`, if `query` has phrases or proximity clauses on it.
+    /// Otherwise, the `generator`'s own highlights are already those of the query.
+    pub fn new(
+        reader: &SearchIndexReader,
+        field: &FieldName,
+        query: &SearchQueryInput,
+        generator: &SnippetGenerator,
+    ) -> Option<Self> {
+        let search_field = reader.schema().search_field(field)?;
+        if !search_field.is_text() || field.path().is_some() {
+            return None;
+        }
+        let analyzer = reader
+            .searcher()
+            .index()
+            .tokenizer_for_field(search_field.field())
+            .ok()?;
+
+        let mut collector = SpanCollector {
+            reader,
+            field,
+            tantivy_field: search_field.field(),
+            analyzer,
+            spans: vec![],
+        };
+        collector.collect(query);
+        let SpanCollector {
+            analyzer, spans, ..
+        } = collector;
+        if spans.is_empty() {
+            return None;
+        }
+
+        // a term that's also searched for on its own is still highlighted everywhere
+        let mut standalone_terms = HashSet::default();
+        if let Some(segment_reader) = reader.searcher().segment_readers().first() {
+            reader.make_query(query.clone()).query_terms(
+                search_field.field(),
+                segment_reader,
+                &mut |term, need_positions| {
+                    if let (false, Some(text)) = (need_positions, term.value().as_str()) {
+                        standalone_terms.insert(text.to_string());
+                    }
+                },
+            );
+        }
+        let span_terms = spans.iter().flat_map(Span::terms).collect::<HashSet<_>>();
+        let free_terms = generator
+            .terms_text()
+            .keys()
+            .filter(|term| !span_terms.contains(term) || standalone_terms.contains(*term))
+            .cloned()
+            .collect();
+
+        Some(Self {
+            analyzer,
+            free_terms,
+            spans,
+        })
+    }
+
+    /// The byte offsets and the text of each token of `text` that the query matches, in order.
+    pub fn matches(&self, text: &str) -> Vec<(Range<usize>, String)> {
+        self.matching_tokens(tokenize(&mut self.analyzer.clone(), text))
+    }
+
+    /// Like [`Highlighter::matches`], for the already known `tokens` of a text.
+    pub fn matching_tokens(&self, tokens: Vec<Token>) -> Vec<(Range<usize>, String)> {
+        let mut matched = tokens
+            .iter()
+            .map(|token| self.free_terms.contains(&token.text))
+            .collect::<Vec<_>>();
+        for span in &self.spans {
+            match span {
+                Span::Phrase {
+                    terms,
+                    slop,
+                    prefix,
+                } => match_phrase(&tokens, terms, *slop, *prefix, &mut matched),
+                Span::Proximity {
+                    left,
+                    distance,
+                    right,
+                } => match_proximity(&tokens, left, *distance, right, &mut matched),
+            }
+        }
+
+        let mut matches = tokens
+            .into_iter()
+            .zip(matched)
+            .filter(|(_, matched)| *matched)
+            .map(|(token, _)| (token.offsets, token.text))
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|(offsets, _)| offsets.start);
+        matches
+    }
+
+    /// The byte ranges of `text` to highlight, in order and without overlaps.
+    pub fn highlight(&self, text: &str) -> Vec<Range<usize>> {
+        merge_ranges(self.matches(text).into_iter().map(|(range, _)| range))
+    }
+
+    /// Like [`Highlighter::highlight`], for the already known `tokens` of a text.
+    pub fn highlight_tokens(&self, tokens: Vec<Token>) -> Vec<Range<usize>> {
+        merge_ranges(
+            self.matching_tokens(tokens)
+                .into_iter()
+                .map(|(range, _)| range),
+        )
+    }
+}
+
+/// Ranges sorted by their start, with those that overlap merged.
+pub fn merge_ranges(ranges: impl IntoIterator<Item = Range<usize>>) -> Vec<Range<usize>> {
+    let mut merged: Vec<Range<usize>> = vec![];
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start < last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Collects the spans of a query that search one field.
+struct SpanCollector<'a> {
+    reader: &'a SearchIndexReader,
+    field: &'a FieldName,
+    tantivy_field: Field,
+    analyzer: TextAnalyzer,
+    spans: Vec<Span>,
+}
+
+impl SpanCollector<'_> {
+    /// Clauses that must not match can't be in the documents that are highlighted, and are left
+    /// out.
+    fn collect(&mut self, query: &SearchQueryInput) {
+        match query {
+            SearchQueryInput::Boolean { must, should, .. } => {
+                for query in must.iter().chain(should) {
+                    self.collect(query);
+                }
+            }
+            SearchQueryInput::DisjunctionMax { disjuncts, .. } => {
+                for query in disjuncts {
+                    self.collect(query);
+                }
+            }
+            SearchQueryInput::Boost { query, .. }
+            | SearchQueryInput::ConstScore { query, .. }
+            | SearchQueryInput::Element { query, .. }
+            | SearchQueryInput::WithIndex { query, .. }
+            | SearchQueryInput::HeapFilter {
+                indexed_query: query,
+                ..
+            } => self.collect(query),
+            SearchQueryInput::ScoreFilter {
+                query: Some(query), ..
+            } => self.collect(query),
+            // the phrases of a query string are only known once it's parsed
+            SearchQueryInput::Parse { .. } => {
+                let parsed = self.reader.make_query(query.clone());
+                self.collect_parsed(parsed.as_ref());
+            }
+            SearchQueryInput::FieldedQuery {
+                field,
+                query: fielded,
+            } if field == self.field => {
+                if matches!(fielded, pdb::Query::ParseWithField { .. }) {
+                    let parsed = self.reader.make_query(query.clone());
+                    self.collect_parsed(parsed.as_ref());
+                } else {
+                    self.collect_pdb(fielded);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_parsed(&mut self, query: &dyn Query) {
+        if let Some(boolean) = query.downcast_ref::<BooleanQuery>() {
+            for (occur, query) in boolean.clauses() {
+                if *occur != Occur::MustNot {
+                    self.collect_parsed(query.as_ref());
+                }
+            }
+        } else if let Some(phrase) = query.downcast_ref::<PhraseQuery>() {
+            if phrase.field() == self.tantivy_field {
+                self.spans.push(Span::Phrase {
+                    terms: phrase
+                        .phrase_terms()
+                        .iter()
+                        .filter_map(|term| term.value().as_str().map(String::from))
+                        .collect(),
+                    slop: phrase.slop(),
+                    prefix: false,
+                });
+            }
+        }
+    }
+
+    fn collect_pdb(&mut self, query: &pdb::Query) {
+        let span = match query {
+            pdb::Query::Boost { query, .. } => return self.collect_pdb(query),
+            pdb::Query::Phrase { phrases, slop } => Span::Phrase {
+                terms: phrases
+                    .iter()
+                    .flat_map(|phrase| self.tokenize(phrase))
+                    .collect(),
+                slop: slop.unwrap_or(0),
+                prefix: false,
+            },
+            pdb::Query::TokenizedPhrase { phrase, slop } => Span::Phrase {
+                terms: self.tokenize(phrase),
+                slop: slop.unwrap_or(0),
+                prefix: false,
+            },
+            pdb::Query::PhrasePrefix { phrases, .. } => Span::Phrase {
+                terms: phrases.clone(),
+                slop: 0,
+                prefix: true,
+            },
+            pdb::Query::Proximity {
+                left,
+                distance,
+                right,
+            } => {
+                let (Some(left), Some(right)) = (proximity_terms(left), proximity_terms(right))
+                else {
+                    return;
+                };
+                Span::Proximity {
+                    left,
+                    distance: *distance,
+                    right,
+                }
+            }
+            _ => return,
+        };
+
+        // a phrase of a single term is searched, and highlighted, like the term on its own
+        if matches!(&span, Span::Phrase { terms, prefix: false, .. } if terms.len() < 2) {
+            return;
+        }
+        self.spans.push(span);
+    }
+
+    fn tokenize(&mut self, text: &str) -> Vec<String> {
+        let mut stream = self.analyzer.token_stream(text);
+        let mut tokens = vec![];
+        while stream.advance() {
+            tokens.push(stream.token().text.clone());
+        }
+        tokens
+    }
+}
+
+/// The terms of a proximity clause, if it's made of terms alone.
+fn proximity_terms(clause: &ProximityClause) -> Option<Vec<String>> {
+    match clause {
+        ProximityClause::Term(term) => Some(vec![term.clone()]),
+        ProximityClause::Clauses(clauses) => {
+            let mut terms = vec![];
+            for clause in clauses {
+                terms.extend(proximity_terms(clause)?);
+            }
+            Some(terms)
+        }
+        _ => None,
+    }
+}
+
+fn match_phrase(tokens: &[Token], terms: &[String], slop: u32, prefix: bool, matched: &mut [bool]) {
+    let term_matches = |token: &Token, index: usize| -> bool {
+        if prefix && index == terms.len() - 1 {
+            token.text.starts_with(&terms[index])
+        } else {
+            token.text == terms[index]
+        }
+    };
+
+    // extend `path` to the next term of the phrase, trying each token that's close enough
+    fn extend(
+        tokens: &[Token],
+        nterms: usize,
+        slop: u32,
+        term_matches: &dyn Fn(&Token, usize) -> bool,
+        path: &mut Vec<usize>,
+    ) -> bool {
+        if path.len() == nterms {
+            return true;
+        }
+        let last = *path.last().unwrap();
+        for (next, token) in tokens.iter().enumerate().skip(last + 1) {
+            let gap = token.position.saturating_sub(tokens[last].position);
+            if gap == 0 {
+                continue;
+            }
+            if gap - 1 > slop as usize {
+                break;
+            }
+            if term_matches(token, path.len()) {
+                path.push(next);
+                if extend(tokens, nterms, slop, term_matches, path) {
+                    return true;
+                }
+                path.pop();
+            }
+        }
+        false
+    }
+
+    if terms.is_empty() {
+        return;
+    }
+    for (start, token) in tokens.iter().enumerate() {
+        if !term_matches(token, 0) {
+            continue;
+        }
+        let mut path = vec![start];
+        if extend(tokens, terms.len(), slop, &term_matches, &mut path) {
+            for index in path {
+                matched[index] = true;
+            }
+        }
+    }
+}
+
+fn match_proximity(
+    tokens: &[Token],
+    left: &[String],
+    distance: ProximityDistance,
+    right: &[String],
+    matched: &mut [bool],
+) {
+    let reach = distance.distance() as usize + 1;
+    for (l, left_token) in tokens.iter().enumerate() {
+        if !left.contains(&left_token.text) {
+            continue;
+        }
+        let from = tokens.partition_point(|token| token.position + reach < left_token.position);
+        for (r, right_token) in tokens.iter().enumerate().skip(from) {
+            if right_token.position > left_token.position + reach {
+                break;
+            }
+            let diff = distance.diff(left_token.position as u32, right_token.position as u32);
+            if r != l && right.contains(&right_token.text) && diff <= reach as u32 {
+                matched[l] = true;
+                matched[r] = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,358 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.hybrid()`, which ranks a search's matches by both their BM25 scores and a distance,
+//! like that of a pgvector embedding to the query's.
+//!
+//! A search ordered by it, like
+//! `ORDER BY paradedb.hybrid(id, embedding <=> '[1,2,3]', fusion => 'rrf') DESC LIMIT 10`, takes
+//! the best BM25 matches as candidates, computes each one's distance, and fuses the two rankings
+//! into one, all in the custom scan.
+//!
+//! When the distance is one of pgvector's operators between a `vector` column that the index
+//! includes and a constant, it's computed from the column's fast field instead of each
+//! candidate's row.
+
+use crate::api::FieldName;
+use crate::nodecast;
+use crate::postgres::var::fieldname_from_var;
+use crate::postgres::vector::{vector_from_bytes, vector_from_const, VectorMetric};
+use crate::schema::{SearchFieldType, SearchIndexSchema};
+use pgrx::pg_sys::expression_tree_walker;
+use pgrx::{
+    default, direct_function_call, pg_extern, pg_guard, pg_sys, AnyElement, FromDatum, IntoDatum,
+    PgList,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::ptr::addr_of_mut;
+use tantivy::schema::OwnedValue;
+
+/// The fused score of the row `key` and its `distance`, when a search is ordered by it.
+/// Otherwise it's NULL, like `paradedb.score()`.
+#[pg_extern(name = "hybrid", stable, parallel_safe, cost = 1)]
+fn hybrid_from_relation(
+    key: AnyElement,
+    distance: f64,
+    fusion: default!(String, "'rrf'"),
+    weight: default!(f64, "0.5"),
+    k: default!(i32, "60"),
+) -> Option<f64> {
+    None
+}
+
+pub fn hybrid_funcoid() -> pg_sys::Oid {
+    unsafe {
+        direct_function_call::<pg_sys::Oid>(
+            pg_sys::regprocedurein,
+            &[c"paradedb.hybrid(anyelement, float8, text, float8, int)".into_datum()],
+        )
+        .expect(
+            "the `paradedb.hybrid(anyelement, float8, text, float8, int)` function should exist",
+        )
+    }
+}
+
+/// How the BM25 ranking and the distance ranking of the candidates are fused.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Fusion {
+    /// Reciprocal rank fusion: the sum of `1 / (k + rank)` of both rankings.
+    Rrf { k: f64 },
+    /// The `weight` of the min-max normalized BM25 score, plus the rest of the normalized
+    /// closeness.
+    Weighted { weight: f64 },
+}
+
+impl Display for Fusion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fusion::Rrf { k } => write!(f, "rrf (k = {k})"),
+            Fusion::Weighted { weight } => write!(f, "weighted (weight = {weight})"),
+        }
+    }
+}
+
+impl Fusion {
+    /// The fused score of each candidate, given in order of BM25 score from the best, along with
+    /// its distance.  A candidate without a distance only has its BM25 part.
+    pub fn fuse(&self, candidates: &[(f32, Option<f64>)]) -> Vec<f64> {
+        match self {
+            Fusion::Rrf { k } => {
+                let mut closest = (0..candidates.len())
+                    .filter(|&i| candidates[i].1.is_some())
+                    .collect::<Vec<_>>();
+                closest.sort_by(|&a, &b| {
+                    candidates[a]
+                        .1
+                        .unwrap()
+                        .total_cmp(&candidates[b].1.unwrap())
+                });
+                let mut fused = (0..candidates.len())
+                    .map(|rank| 1.0 / (k + (rank + 1) as f64))
+                    .collect::<Vec<_>>();
+                for (rank, i) in closest.into_iter().enumerate() {
+                    fused[i] += 1.0 / (k + (rank + 1) as f64);
+                }
+                fused
+            }
+            Fusion::Weighted { weight } => {
+                let normalize = |values: &mut dyn Iterator<Item = f64>| {
+                    values.fold((f64::MAX, f64::MIN), |(min, max), value| {
+                        (min.min(value), max.max(value))
+                    })
+                };
+                let (min_score, max_score) =
+                    normalize(&mut candidates.iter().map(|(score, _)| *score as f64));
+                let (min_distance, max_distance) =
+                    normalize(&mut candidates.iter().filter_map(|(_, distance)| *distance));
+                let scale = |value: f64, min: f64, max: f64| {
+                    if max > min {
+                        (value - min) / (max - min)
+                    } else {
+                        1.0
+                    }
+                };
+
+                candidates
+                    .iter()
+                    .map(|(score, distance)| {
+                        weight * scale(*score as f64, min_score, max_score)
+                            + distance.map_or(0.0, |distance| {
+                                (1.0 - weight)
+                                    * (1.0 - scale(distance, min_distance, max_distance)).max(0.0)
+                            })
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A distance between a `vector` field of the index and a constant vector, which can be
+/// computed from the field's fast field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FastDistance {
+    pub field: FieldName,
+    pub metric: VectorMetric,
+    pub query: Vec<f32>,
+}
+
+impl Display for FastDistance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} (fast field)", self.field, self.metric)
+    }
+}
+
+impl FastDistance {
+    /// The distance of a document whose fast field `value` is given, if it has a vector.
+    pub fn distance(&self, value: OwnedValue) -> Option<f64> {
+        match value {
+            OwnedValue::Bytes(bytes) => Some(
+                self.metric
+                    .distance(&vector_from_bytes(&bytes), &self.query),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// If `distance` is a vector field of the relation `rti` compared to a `vector` constant with one
+/// of pgvector's distance operators, that distance.
+unsafe fn fast_distance(
+    root: *mut pg_sys::PlannerInfo,
+    rti: pg_sys::Index,
+    schema: &SearchIndexSchema,
+    distance: *mut pg_sys::Node,
+) -> Option<FastDistance> {
+    let opexpr = nodecast!(OpExpr, T_OpExpr, distance)?;
+    let metric = VectorMetric::from_operator((*opexpr).opno)?;
+    let args = PgList::<pg_sys::Node>::from_pg((*opexpr).args);
+    let (left, right) = (args.get_ptr(0)?, args.get_ptr(1)?);
+    // every one of the operators is commutative
+    let (var, query) = match (
+        nodecast!(Var, T_Var, left),
+        nodecast!(Const, T_Const, right),
+    ) {
+        (Some(var), Some(query)) => (var, query),
+        _ => (
+            nodecast!(Var, T_Var, right)?,
+            nodecast!(Const, T_Const, left)?,
+        ),
+    };
+    if (*var).varno as i32 != rti as i32 {
+        return None;
+    }
+
+    let relid = (*pg_sys::rt_fetch(rti, (*(*root).parse).rtable)).relid;
+    let field = fieldname_from_var(relid, var, (*var).varattno)?;
+    let search_field = schema.search_field(&field)?;
+    if !matches!(search_field.field_type(), SearchFieldType::Vector(_)) || !search_field.is_fast() {
+        return None;
+    }
+
+    Some(FastDistance {
+        field,
+        metric,
+        query: vector_from_const(query)?,
+    })
+}
+
+/// The first `paradedb.hybrid()` call in `node` for the relation `rti`.
+pub unsafe fn find_hybrid(
+    node: *mut pg_sys::Node,
+    rti: pg_sys::Index,
+) -> Option<*mut pg_sys::FuncExpr> {
+    #[pg_guard]
+    unsafe extern "C-unwind" fn walker(
+        node: *mut pg_sys::Node,
+        data: *mut core::ffi::c_void,
+    ) -> bool {
+        if node.is_null() {
+            return false;
+        }
+
+        let found = &mut *data.cast::<Data>();
+        if is_hybrid_func(node, found.funcoid, found.rti) {
+            found.found = nodecast!(FuncExpr, T_FuncExpr, node);
+            return true;
+        }
+
+        expression_tree_walker(node, Some(walker), data)
+    }
+
+    struct Data {
+        funcoid: pg_sys::Oid,
+        rti: pg_sys::Index,
+        found: Option<*mut pg_sys::FuncExpr>,
+    }
+
+    let mut data = Data {
+        funcoid: hybrid_funcoid(),
+        rti,
+        found: None,
+    };
+
+    walker(node, addr_of_mut!(data).cast());
+    data.found
+}
+
+unsafe fn is_hybrid_func(
+    node: *mut pg_sys::Node,
+    funcoid: pg_sys::Oid,
+    rti: pg_sys::Index,
+) -> bool {
+    if let Some(funcexpr) = nodecast!(FuncExpr, T_FuncExpr, node) {
+        if (*funcexpr).funcid == funcoid {
+            let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
+            if let Some(var) = nodecast!(Var, T_Var, args.get_ptr(0).unwrap()) {
+                return (*var).varno as i32 == rti as i32;
+            }
+        }
+    }
+
+    false
+}
+
+/// If the query is ordered by nothing but `paradedb.hybrid()` of the relation `rti`, from the best
+/// rows, that pathkey, how the call fuses the rankings, and how its distance can be computed from
+/// the index, if it can.
+pub unsafe fn hybrid_pathkey(
+    root: *mut pg_sys::PlannerInfo,
+    rti: pg_sys::Index,
+    schema: &SearchIndexSchema,
+) -> Option<(*mut pg_sys::PathKey, Fusion, Option<FastDistance>)> {
+    let pathkeys = PgList::<pg_sys::PathKey>::from_pg((*root).query_pathkeys);
+    if pathkeys.len() != 1 {
+        return None;
+    }
+    let pathkey = pathkeys.get_ptr(0)?;
+    if (*pathkey).pk_strategy as u32 != pg_sys::BTGreaterStrategyNumber {
+        return None;
+    }
+
+    let funcoid = hybrid_funcoid();
+    let members = PgList::<pg_sys::EquivalenceMember>::from_pg((*(*pathkey).pk_eclass).ec_members);
+    let funcexpr = members.iter_ptr().find_map(|member| {
+        is_hybrid_func((*member).em_expr.cast(), funcoid, rti)
+            .then(|| (*member).em_expr.cast::<pg_sys::FuncExpr>())
+    })?;
+
+    // the distance is computed from the candidate's row, so it can't refer to any other
+    let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
+    let distance = args.get_ptr(1)?;
+    if !pg_sys::bms_is_subset(
+        pg_sys::pull_varnos(root, distance),
+        pg_sys::bms_make_singleton(rti as _),
+    ) {
+        return None;
+    }
+
+    let option = |index: usize| nodecast!(Const, T_Const, args.get_ptr(index)?);
+    let (fusion, weight, k) = (option(2)?, option(3)?, option(4)?);
+    let fusion = String::from_datum((*fusion).constvalue, (*fusion).constisnull)
+        .unwrap_or_else(|| pgrx::error!("paradedb.hybrid: fusion must not be NULL"));
+    let fusion = match fusion.as_str() {
+        "rrf" => {
+            let k = i32::from_datum((*k).constvalue, (*k).constisnull).unwrap_or(60);
+            if k < 0 {
+                pgrx::error!("paradedb.hybrid: k must not be negative");
+            }
+            Fusion::Rrf { k: k as f64 }
+        }
+        "weighted" => {
+            let weight =
+                f64::from_datum((*weight).constvalue, (*weight).constisnull).unwrap_or(0.5);
+            if !(0.0..=1.0).contains(&weight) {
+                pgrx::error!("paradedb.hybrid: weight must be between 0 and 1");
+            }
+            Fusion::Weighted { weight }
+        }
+        other => pgrx::error!("paradedb.hybrid: fusion must be 'rrf' or 'weighted', not '{other}'"),
+    };
+
+    Some((pathkey, fusion, fast_distance(root, rti, schema, distance)))
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    use super::Fusion;
+    use pgrx::pg_test;
+
+    #[pg_test]
+    fn rrf_rewards_agreement() {
+        // the second best BM25 match is the closest, so it's the best of both
+        let fused = Fusion::Rrf { k: 60.0 }.fuse(&[
+            (4.0, Some(0.9)),
+            (3.0, Some(0.1)),
+            (2.0, Some(0.5)),
+            (1.0, None),
+        ]);
+        assert!(fused[1] > fused[0]);
+        assert!(fused[0] > fused[2]);
+        assert_eq!(fused[3], 1.0 / 64.0);
+    }
+
+    #[pg_test]
+    fn weighted_normalizes() {
+        let fused = Fusion::Weighted { weight: 0.5 }.fuse(&[(4.0, Some(1.0)), (2.0, Some(0.0))]);
+        assert_eq!(fused, vec![0.5, 0.5]);
+
+        let fused = Fusion::Weighted { weight: 1.0 }.fuse(&[(4.0, Some(1.0)), (2.0, Some(0.0))]);
+        assert_eq!(fused, vec![1.0, 0.0]);
+    }
+}
@@ -15,6 +15,8 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+pub mod highlight;
+pub mod hybrid;
 pub mod score;
 pub mod snippet;
 
@@ -23,11 +25,13 @@ use crate::api::FieldName;
 use crate::api::HashMap;
 use crate::api::Varno;
 use crate::nodecast;
+use crate::postgres::customscan::pdbscan::projections::hybrid::hybrid_funcoid;
 use crate::postgres::customscan::pdbscan::projections::snippet::{
-    snippet_funcoid, snippet_positions_funcoid, SnippetType,
+    highlights_funcoid, highlights_snippet_types, match_positions_funcoid, snippet_field,
+    snippet_fragments_funcoid, snippet_funcoid, snippet_positions_funcoid, SnippetType,
 };
 use crate::postgres::customscan::score_funcoid;
-use crate::postgres::var::{find_one_var, find_one_var_and_fieldname, find_vars, VarContext};
+use crate::postgres::var::{find_one_var_and_fieldname, find_vars, VarContext};
 use pgrx::pg_sys::expression_tree_walker;
 use pgrx::{pg_extern, pg_guard, pg_sys, Internal, PgList};
 use std::ptr::{addr_of_mut, NonNull};
@@ -53,9 +57,14 @@ pub unsafe fn placeholder_support(arg: Internal) -> ReturnedNodePointer {
             return ReturnedNodePointer(None);
         }
 
-        let mut vars = find_vars((*srs).fcall.cast());
-        assert!(vars.len() == 1, "function is improperly defined or called");
-        let var = vars.pop().unwrap();
+        // a function may read several columns, like `paradedb.highlights()`, but only of one
+        // relation
+        let vars = find_vars((*srs).fcall.cast());
+        assert!(
+            !vars.is_empty() && vars.iter().all(|var| (**var).varno == (*vars[0]).varno),
+            "function is improperly defined or called"
+        );
+        let var = vars[0];
 
         let phrels = pg_sys::bms_make_singleton((*var).varno as _);
         let phv = pg_sys::submodules::ffi::pg_guard_ffi_boundary(|| {
@@ -100,6 +109,10 @@ pub unsafe fn maybe_needs_const_projections(node: *mut pg_sys::Node) -> bool {
             if (*funcexpr).funcid == data.score_funcoid
                 || (*funcexpr).funcid == data.snipped_funcoid
                 || (*funcexpr).funcid == data.snippet_positions_funcoid
+                || (*funcexpr).funcid == data.match_positions_funcoid
+                || (*funcexpr).funcid == data.highlights_funcoid
+                || (*funcexpr).funcid == data.snippet_fragments_funcoid
+                || (*funcexpr).funcid == data.hybrid_funcoid
             {
                 return true;
             }
@@ -112,12 +125,20 @@ pub unsafe fn maybe_needs_const_projections(node: *mut pg_sys::Node) -> bool {
         score_funcoid: pg_sys::Oid,
         snipped_funcoid: pg_sys::Oid,
         snippet_positions_funcoid: pg_sys::Oid,
+        match_positions_funcoid: pg_sys::Oid,
+        highlights_funcoid: pg_sys::Oid,
+        snippet_fragments_funcoid: pg_sys::Oid,
+        hybrid_funcoid: pg_sys::Oid,
     }
 
     let mut data = Data {
         score_funcoid: score_funcoid(),
         snipped_funcoid: snippet_funcoid(),
         snippet_positions_funcoid: snippet_positions_funcoid(),
+        match_positions_funcoid: match_positions_funcoid(),
+        highlights_funcoid: highlights_funcoid(),
+        snippet_fragments_funcoid: snippet_fragments_funcoid(),
+        hybrid_funcoid: hybrid_funcoid(),
     };
 
     let data = addr_of_mut!(data).cast();
@@ -148,7 +169,16 @@ pub unsafe fn pullout_funcexprs(
             let data = &mut *data.cast::<Data>();
             if data.funcids.contains(&(*funcexpr).funcid) {
                 let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
-                for arg in args.iter_ptr() {
+                // the fields of `paradedb.highlights()` are the elements of an `ARRAY[]`
+                let args =
+                    args.iter_ptr()
+                        .flat_map(|arg| match nodecast!(ArrayExpr, T_ArrayExpr, arg) {
+                            Some(array) => PgList::<pg_sys::Node>::from_pg((*array).elements)
+                                .iter_ptr()
+                                .collect(),
+                            None => vec![arg],
+                        });
+                for arg in args {
                     if let Some((var, fieldname)) =
                         find_one_var_and_fieldname(VarContext::Planner(data.root), arg)
                     {
@@ -189,14 +219,20 @@ pub unsafe fn inject_placeholders(
     targetlist: *mut pg_sys::List,
     rti: pg_sys::Index,
     score_funcoid: pg_sys::Oid,
+    hybrid_funcoid: pg_sys::Oid,
     snippet_funcoid: pg_sys::Oid,
     snippet_positions_funcoid: pg_sys::Oid,
+    match_positions_funcoid: pg_sys::Oid,
+    highlights_funcoid: pg_sys::Oid,
+    snippet_fragments_funcoid: pg_sys::Oid,
     attname_lookup: &HashMap<(Varno, pg_sys::AttrNumber), FieldName>,
     snippet_generators: &HashMap<SnippetType, Option<(tantivy::schema::Field, SnippetGenerator)>>,
 ) -> (
     *mut pg_sys::List,
     *mut pg_sys::Const,
+    *mut pg_sys::Const,
     HashMap<SnippetType, Vec<*mut pg_sys::Const>>,
+    Vec<(Vec<SnippetType>, *mut pg_sys::Const)>,
 ) {
     #[pg_guard]
     unsafe extern "C-unwind" fn walker(
@@ -216,15 +252,20 @@ pub unsafe fn inject_placeholders(
                 return Some(data.const_score_node.cast());
             }
 
+            if (*funcexpr).funcid == data.hybrid_funcoid {
+                return Some(data.const_hybrid_node.cast());
+            }
+
             if (*funcexpr).funcid == data.snippet_funcoid
                 || (*funcexpr).funcid == data.snippet_positions_funcoid
+                || (*funcexpr).funcid == data.match_positions_funcoid
+                || (*funcexpr).funcid == data.snippet_fragments_funcoid
             {
-                let var = find_one_var(args.get_ptr(0)?)?;
-                let key = (data.rti as Varno, (*var).varattno);
-
-                if let Some(attname) = data.attname_lookup.get(&key) {
+                if let Some(attname) =
+                    snippet_field(args.get_ptr(0)?, data.rti, data.attname_lookup)
+                {
                     for snippet_type in data.snippet_generators.keys() {
-                        if snippet_type.field() == attname
+                        if snippet_type.field() == &attname
                             && snippet_type.funcoid() == (*funcexpr).funcid
                         {
                             let const_ = pg_sys::makeConst(
@@ -248,6 +289,26 @@ pub unsafe fn inject_placeholders(
                 }
             }
 
+            if (*funcexpr).funcid == data.highlights_funcoid {
+                let snippet_types = highlights_snippet_types(
+                    &args,
+                    data.rti,
+                    data.attname_lookup,
+                    data.highlights_funcoid,
+                )?;
+                let const_ = pg_sys::makeConst(
+                    pg_sys::JSONBOID,
+                    -1,
+                    pg_sys::Oid::INVALID,
+                    -1,
+                    pg_sys::Datum::null(),
+                    true,
+                    false,
+                );
+                data.const_highlights_nodes.push((snippet_types, const_));
+                return Some(const_.cast());
+            }
+
             None
         }
 
@@ -276,13 +337,20 @@ pub unsafe fn inject_placeholders(
         score_funcoid: pg_sys::Oid,
         const_score_node: *mut pg_sys::Const,
 
+        hybrid_funcoid: pg_sys::Oid,
+        const_hybrid_node: *mut pg_sys::Const,
+
         snippet_funcoid: pg_sys::Oid,
         snippet_positions_funcoid: pg_sys::Oid,
+        match_positions_funcoid: pg_sys::Oid,
+        highlights_funcoid: pg_sys::Oid,
+        snippet_fragments_funcoid: pg_sys::Oid,
         attname_lookup: &'a HashMap<(Varno, pg_sys::AttrNumber), FieldName>,
 
         snippet_generators:
             &'a HashMap<SnippetType, Option<(tantivy::schema::Field, SnippetGenerator)>>,
         const_snippet_nodes: HashMap<SnippetType, Vec<*mut pg_sys::Const>>,
+        const_highlights_nodes: Vec<(Vec<SnippetType>, *mut pg_sys::Const)>,
     }
 
     let mut data = Data {
@@ -299,16 +367,33 @@ pub unsafe fn inject_placeholders(
             true,
         ),
 
+        hybrid_funcoid,
+        const_hybrid_node: pg_sys::makeConst(
+            pg_sys::FLOAT8OID,
+            -1,
+            pg_sys::Oid::INVALID,
+            size_of::<f64>() as _,
+            pg_sys::Datum::null(),
+            true,
+            true,
+        ),
+
         snippet_funcoid,
         snippet_positions_funcoid,
+        match_positions_funcoid,
+        highlights_funcoid,
+        snippet_fragments_funcoid,
         attname_lookup,
         snippet_generators,
         const_snippet_nodes: Default::default(),
+        const_highlights_nodes: Default::default(),
     };
     let targetlist = walker(targetlist.cast(), addr_of_mut!(data).cast());
     (
         targetlist.cast(),
         data.const_score_node,
+        data.const_hybrid_node,
         data.const_snippet_nodes,
+        data.const_highlights_nodes,
     )
 }
@@ -16,32 +16,51 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use crate::api::FieldName;
-use crate::api::HashMap;
 use crate::api::Varno;
+use crate::api::{HashMap, HashSet};
 use crate::nodecast;
-use crate::postgres::var::find_one_var;
+use crate::postgres::customscan::pdbscan::projections::highlight::merge_ranges;
+use crate::postgres::var::{find_json_path_keys, find_one_var};
 use pgrx::pg_sys::expression_tree_walker;
 use pgrx::{
-    default, direct_function_call, extension_sql, pg_extern, pg_guard, pg_sys, AnyElement,
-    FromDatum, IntoDatum, PgList,
+    default, direct_function_call, extension_sql, pg_extern, pg_guard, pg_sys, AnyArray,
+    AnyElement, FromDatum, IntoDatum, JsonB, PgList,
 };
+use std::collections::BTreeMap;
+use std::ops::Range;
 use std::ptr::addr_of_mut;
+use tantivy::snippet::Snippet;
+use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
 
 const DEFAULT_SNIPPET_PREFIX: &str = "<b>";
 const DEFAULT_SNIPPET_POSTFIX: &str = "</b>";
 const DEFAULT_SNIPPET_MAX_NUM_CHARS: i32 = 150;
+const DEFAULT_SNIPPET_NUMBER_OF_FRAGMENTS: i32 = 1;
+const DEFAULT_SNIPPET_FRAGMENT_SEPARATOR: &str = " ... ";
+const DEFAULT_SNIPPET_FRAGMENTS_NUMBER_OF_FRAGMENTS: i32 = 3;
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
 pub struct SnippetConfig {
     pub start_tag: String,
     pub end_tag: String,
+    /// The most characters in each fragment
     pub max_num_chars: usize,
+    pub number_of_fragments: usize,
+    pub fragment_separator: String,
+    /// Escape the document's text for HTML, so the snippet can be embedded in a web page as-is
+    pub escape_html: bool,
+    /// Count fragment sizes in grapheme clusters, what a reader sees as one character, rather
+    /// than in Unicode code points
+    pub count_graphemes: bool,
 }
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
 pub enum SnippetType {
     Text(FieldName, pg_sys::Oid, SnippetConfig),
     Positions(FieldName, pg_sys::Oid),
+    MatchPositions(FieldName, pg_sys::Oid),
+    /// The best fragments of the field, each with its score
+    Fragments(FieldName, pg_sys::Oid, SnippetConfig),
 }
 
 impl SnippetType {
@@ -49,6 +68,8 @@ impl SnippetType {
         match self {
             SnippetType::Text(field, _, _) => field,
             SnippetType::Positions(field, _) => field,
+            SnippetType::MatchPositions(field, _) => field,
+            SnippetType::Fragments(field, _, _) => field,
         }
     }
 
@@ -56,6 +77,8 @@ impl SnippetType {
         match self {
             SnippetType::Text(_, funcoid, _) => *funcoid,
             SnippetType::Positions(_, funcoid) => *funcoid,
+            SnippetType::MatchPositions(_, funcoid) => *funcoid,
+            SnippetType::Fragments(_, funcoid, _) => *funcoid,
         }
     }
 
@@ -63,6 +86,8 @@ impl SnippetType {
         match self {
             SnippetType::Text(_, _, _) => pg_sys::TEXTOID,
             SnippetType::Positions(_, _) => pg_sys::INT4ARRAYOID,
+            SnippetType::MatchPositions(_, _) => pg_sys::JSONBOID,
+            SnippetType::Fragments(_, _, _) => pg_sys::JSONBOID,
         }
     }
 }
@@ -72,24 +97,74 @@ struct Context<'a> {
     attname_lookup: &'a HashMap<(Varno, pg_sys::AttrNumber), FieldName>,
     snippet_funcoid: pg_sys::Oid,
     snippet_positions_funcoid: pg_sys::Oid,
+    match_positions_funcoid: pg_sys::Oid,
+    highlights_funcoid: pg_sys::Oid,
+    snippet_fragments_funcoid: pg_sys::Oid,
     snippet_type: Vec<SnippetType>,
 }
 
 #[pg_extern(name = "snippet", stable, parallel_safe)]
+#[allow(clippy::too_many_arguments)]
 fn snippet_from_relation(
     field: AnyElement,
     start_tag: default!(String, "'<b>'"),
     end_tag: default!(String, "'</b>'"),
     max_num_chars: default!(i32, "150"),
+    fragment_size: default!(Option<i32>, "NULL"),
+    number_of_fragments: default!(i32, "1"),
+    fragment_separator: default!(String, "' ... '"),
+    escape_html: default!(bool, "true"),
+    count_graphemes: default!(bool, "false"),
 ) -> Option<String> {
     None
 }
 
+/// The snippets of several fields at once, as a JSON object of each field's name and its snippet,
+/// read from a single fetch of the row.  Fields without a match are left out.
+#[pg_extern(name = "highlights", stable, parallel_safe)]
+#[allow(clippy::too_many_arguments)]
+fn highlights_from_relation(
+    fields: AnyArray,
+    start_tag: default!(String, "'<b>'"),
+    end_tag: default!(String, "'</b>'"),
+    max_num_chars: default!(i32, "150"),
+    fragment_size: default!(Option<i32>, "NULL"),
+    number_of_fragments: default!(i32, "1"),
+    fragment_separator: default!(String, "' ... '"),
+    escape_html: default!(bool, "true"),
+    count_graphemes: default!(bool, "false"),
+) -> Option<JsonB> {
+    None
+}
+
 #[pg_extern(name = "snippet_positions", stable, parallel_safe)]
 fn snippet_positions_from_relation(field: AnyElement) -> Option<Vec<Vec<i32>>> {
     None
 }
 
+/// The byte offsets and the analyzed term of every match in `field`, as a JSON array of
+/// `{"byte_start": ..., "byte_end": ..., "term": ...}` objects, for applications to highlight the
+/// original document themselves.
+#[pg_extern(name = "match_positions", stable, parallel_safe)]
+fn match_positions_from_relation(field: AnyElement) -> Option<JsonB> {
+    None
+}
+
+/// The fragments of `field` that best match the query, best first, as a JSON array of
+/// `{"fragment": ..., "score": ...}` objects.
+#[pg_extern(name = "snippet_fragments", stable, parallel_safe)]
+fn snippet_fragments_from_relation(
+    field: AnyElement,
+    start_tag: default!(String, "'<b>'"),
+    end_tag: default!(String, "'</b>'"),
+    fragment_size: default!(i32, "150"),
+    number_of_fragments: default!(i32, "3"),
+    escape_html: default!(bool, "true"),
+    count_graphemes: default!(bool, "false"),
+) -> Option<JsonB> {
+    None
+}
+
 extension_sql!(
     r#"
 ALTER FUNCTION snippet SUPPORT placeholder_support;
@@ -106,13 +181,57 @@ ALTER FUNCTION snippet_positions SUPPORT placeholder_support;
     requires = [snippet_positions_from_relation, placeholder_support]
 );
 
+extension_sql!(
+    r#"
+ALTER FUNCTION highlights SUPPORT placeholder_support;
+"#,
+    name = "highlights_placeholder",
+    requires = [highlights_from_relation, placeholder_support]
+);
+
+extension_sql!(
+    r#"
+ALTER FUNCTION match_positions SUPPORT placeholder_support;
+"#,
+    name = "match_positions_placeholder",
+    requires = [match_positions_from_relation, placeholder_support]
+);
+
+extension_sql!(
+    r#"
+ALTER FUNCTION snippet_fragments SUPPORT placeholder_support;
+"#,
+    name = "snippet_fragments_placeholder",
+    requires = [snippet_fragments_from_relation, placeholder_support]
+);
+
 pub fn snippet_funcoid() -> pg_sys::Oid {
     unsafe {
         direct_function_call::<pg_sys::Oid>(
             pg_sys::regprocedurein,
-            &[c"paradedb.snippet(anyelement, text, text, int)".into_datum()],
+            &[
+                c"paradedb.snippet(anyelement, text, text, int, int, int, text, bool, bool)"
+                    .into_datum(),
+            ],
+        )
+        .expect(
+            "the `paradedb.snippet(anyelement, text, text, int, int, int, text, bool, bool) type should exist",
+        )
+    }
+}
+
+pub fn highlights_funcoid() -> pg_sys::Oid {
+    unsafe {
+        direct_function_call::<pg_sys::Oid>(
+            pg_sys::regprocedurein,
+            &[
+                c"paradedb.highlights(anyarray, text, text, int, int, int, text, bool, bool)"
+                    .into_datum(),
+            ],
+        )
+        .expect(
+            "the `paradedb.highlights(anyarray, text, text, int, int, int, text, bool, bool) type should exist",
         )
-        .expect("the `paradedb.snippet(anyelement, text, text, int) type should exist")
     }
 }
 
@@ -126,12 +245,41 @@ pub fn snippet_positions_funcoid() -> pg_sys::Oid {
     }
 }
 
+pub fn match_positions_funcoid() -> pg_sys::Oid {
+    unsafe {
+        direct_function_call::<pg_sys::Oid>(
+            pg_sys::regprocedurein,
+            &[c"paradedb.match_positions(anyelement)".into_datum()],
+        )
+        .expect("the `paradedb.match_positions(anyelement) type should exist")
+    }
+}
+
+pub fn snippet_fragments_funcoid() -> pg_sys::Oid {
+    unsafe {
+        direct_function_call::<pg_sys::Oid>(
+            pg_sys::regprocedurein,
+            &[
+                c"paradedb.snippet_fragments(anyelement, text, text, int, int, bool, bool)"
+                    .into_datum(),
+            ],
+        )
+        .expect(
+            "the `paradedb.snippet_fragments(anyelement, text, text, int, int, bool, bool) type should exist",
+        )
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub unsafe fn uses_snippets(
     planning_rti: pg_sys::Index,
     attname_lookup: &HashMap<(Varno, pg_sys::AttrNumber), FieldName>,
     node: *mut pg_sys::Node,
     snippet_funcoid: pg_sys::Oid,
     snippet_positions_funcoid: pg_sys::Oid,
+    match_positions_funcoid: pg_sys::Oid,
+    highlights_funcoid: pg_sys::Oid,
+    snippet_fragments_funcoid: pg_sys::Oid,
 ) -> Vec<SnippetType> {
     #[pg_guard]
     unsafe extern "C-unwind" fn walker(
@@ -162,6 +310,40 @@ pub unsafe fn uses_snippets(
                     panic!("`paradedb.snippet_positions()`'s arguments must be literals")
                 }
             }
+
+            if (*funcexpr).funcid == (*context).match_positions_funcoid {
+                let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
+                if let Some(snippet_type) = extract_match_positions(args, context) {
+                    (*context).snippet_type.push(snippet_type);
+                } else {
+                    panic!("`paradedb.match_positions()`'s arguments must be literals")
+                }
+            }
+
+            if (*funcexpr).funcid == (*context).snippet_fragments_funcoid {
+                let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
+                if let Some(snippet_type) = extract_snippet_fragments(args, context) {
+                    (*context).snippet_type.push(snippet_type);
+                } else {
+                    panic!("`paradedb.snippet_fragments()`'s arguments must be literals")
+                }
+            }
+
+            if (*funcexpr).funcid == (*context).highlights_funcoid {
+                let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
+                if let Some(snippet_types) = highlights_snippet_types(
+                    &args,
+                    (*context).planning_rti,
+                    (*context).attname_lookup,
+                    (*context).highlights_funcoid,
+                ) {
+                    (*context).snippet_type.extend(snippet_types);
+                } else {
+                    panic!(
+                        "`paradedb.highlights()`'s fields must be an ARRAY[] of columns, and its other arguments must be literals"
+                    )
+                }
+            }
         }
 
         expression_tree_walker(node, Some(walker), data)
@@ -172,6 +354,9 @@ pub unsafe fn uses_snippets(
         attname_lookup,
         snippet_funcoid,
         snippet_positions_funcoid,
+        match_positions_funcoid,
+        highlights_funcoid,
+        snippet_fragments_funcoid,
         snippet_type: vec![],
     };
 
@@ -179,45 +364,192 @@ pub unsafe fn uses_snippets(
     context.snippet_type
 }
 
+/// The field that `node`, the first argument of a snippet function, highlights: a column, or a path
+/// into a JSON column, like `metadata->>'color'` or `metadata #>> '{details,color}'`.
+///
+/// Returns `None` if it doesn't reference exactly one column of the relation at `planning_rti`.
+pub unsafe fn snippet_field(
+    node: *mut pg_sys::Node,
+    planning_rti: pg_sys::Index,
+    attname_lookup: &HashMap<(Varno, pg_sys::AttrNumber), FieldName>,
+) -> Option<FieldName> {
+    // the lookup names the column by the last path into it that the planner saw, so only its
+    // root is to be trusted
+    let var = find_one_var(node)?;
+    let column = attname_lookup
+        .get(&(planning_rti as _, (*var).varattno as _))?
+        .root();
+
+    match find_json_path_keys(node) {
+        Some(path) if !path.is_empty() => Some(format!("{column}.{}", path.join(".")).into()),
+        _ => Some(column.into()),
+    }
+}
+
 #[inline(always)]
 unsafe fn extract_snippet_text(
     args: PgList<pg_sys::Node>,
     context: *mut Context,
 ) -> Option<SnippetType> {
-    assert!(args.len() == 4);
-
-    let field_arg = find_one_var(args.get_ptr(0).unwrap());
-    let start_arg = nodecast!(Const, T_Const, args.get_ptr(1).unwrap());
-    let end_arg = nodecast!(Const, T_Const, args.get_ptr(2).unwrap());
-    let max_num_chars_arg = nodecast!(Const, T_Const, args.get_ptr(3).unwrap());
-
-    if let (Some(field_arg), Some(start_arg), Some(end_arg), Some(max_num_chars_arg)) =
-        (field_arg, start_arg, end_arg, max_num_chars_arg)
-    {
-        let attname = (*context)
-            .attname_lookup
-            .get(&((*context).planning_rti as _, (*field_arg).varattno as _))
-            .cloned()
-            .expect("Var attname should be in lookup");
-        let start_tag = String::from_datum((*start_arg).constvalue, (*start_arg).constisnull);
-        let end_tag = String::from_datum((*end_arg).constvalue, (*end_arg).constisnull);
-        let max_num_chars = i32::from_datum(
-            (*max_num_chars_arg).constvalue,
-            (*max_num_chars_arg).constisnull,
-        );
-
-        Some(SnippetType::Text(
-            attname,
-            (*context).snippet_funcoid,
-            SnippetConfig {
-                start_tag: start_tag.unwrap_or_else(|| DEFAULT_SNIPPET_PREFIX.to_string()),
-                end_tag: end_tag.unwrap_or_else(|| DEFAULT_SNIPPET_POSTFIX.to_string()),
-                max_num_chars: max_num_chars.unwrap_or(DEFAULT_SNIPPET_MAX_NUM_CHARS) as usize,
-            },
-        ))
-    } else {
-        None
+    assert!(args.len() == 9);
+
+    let attname = snippet_field(
+        args.get_ptr(0).unwrap(),
+        (*context).planning_rti,
+        (*context).attname_lookup,
+    )?;
+    let config = extract_snippet_config(&args)?;
+
+    Some(SnippetType::Text(
+        attname,
+        (*context).snippet_funcoid,
+        config,
+    ))
+}
+
+/// The snippet of each field of a `paradedb.highlights()` call, whose arguments are `args`.
+/// They're highlighted like `paradedb.snippet()`'s, but carry the Oid of `paradedb.highlights()`.
+///
+/// Returns `None` if the fields aren't an `ARRAY[]` of columns of the relation at `planning_rti`.
+pub unsafe fn highlights_snippet_types(
+    args: &PgList<pg_sys::Node>,
+    planning_rti: pg_sys::Index,
+    attname_lookup: &HashMap<(Varno, pg_sys::AttrNumber), FieldName>,
+    highlights_funcoid: pg_sys::Oid,
+) -> Option<Vec<SnippetType>> {
+    assert!(args.len() == 9);
+
+    let fields = nodecast!(ArrayExpr, T_ArrayExpr, args.get_ptr(0).unwrap())?;
+    let config = extract_snippet_config(args)?;
+
+    PgList::<pg_sys::Node>::from_pg((*fields).elements)
+        .iter_ptr()
+        .map(|element| {
+            let attname = snippet_field(element, planning_rti, attname_lookup)?;
+            Some(SnippetType::Text(
+                attname,
+                highlights_funcoid,
+                config.clone(),
+            ))
+        })
+        .collect()
+}
+
+/// The [`SnippetConfig`] of the arguments that `paradedb.snippet()` and `paradedb.highlights()`
+/// share, all after the first.  They must be literals.
+unsafe fn extract_snippet_config(args: &PgList<pg_sys::Node>) -> Option<SnippetConfig> {
+    let start_arg = nodecast!(Const, T_Const, args.get_ptr(1).unwrap())?;
+    let end_arg = nodecast!(Const, T_Const, args.get_ptr(2).unwrap())?;
+    let max_num_chars_arg = nodecast!(Const, T_Const, args.get_ptr(3).unwrap())?;
+    let fragment_size_arg = nodecast!(Const, T_Const, args.get_ptr(4).unwrap())?;
+    let number_of_fragments_arg = nodecast!(Const, T_Const, args.get_ptr(5).unwrap())?;
+    let fragment_separator_arg = nodecast!(Const, T_Const, args.get_ptr(6).unwrap())?;
+    let escape_html_arg = nodecast!(Const, T_Const, args.get_ptr(7).unwrap())?;
+    let count_graphemes_arg = nodecast!(Const, T_Const, args.get_ptr(8).unwrap())?;
+
+    let start_tag = String::from_datum((*start_arg).constvalue, (*start_arg).constisnull);
+    let end_tag = String::from_datum((*end_arg).constvalue, (*end_arg).constisnull);
+    let max_num_chars = i32::from_datum(
+        (*max_num_chars_arg).constvalue,
+        (*max_num_chars_arg).constisnull,
+    );
+    // `fragment_size` is the clearer name once there's more than one fragment, but the two
+    // mean the same
+    let fragment_size = i32::from_datum(
+        (*fragment_size_arg).constvalue,
+        (*fragment_size_arg).constisnull,
+    );
+    let number_of_fragments = i32::from_datum(
+        (*number_of_fragments_arg).constvalue,
+        (*number_of_fragments_arg).constisnull,
+    )
+    .unwrap_or(DEFAULT_SNIPPET_NUMBER_OF_FRAGMENTS);
+    if number_of_fragments < 0 {
+        panic!("`number_of_fragments` must not be negative");
     }
+    let fragment_separator = String::from_datum(
+        (*fragment_separator_arg).constvalue,
+        (*fragment_separator_arg).constisnull,
+    );
+    let escape_html = bool::from_datum(
+        (*escape_html_arg).constvalue,
+        (*escape_html_arg).constisnull,
+    );
+    let count_graphemes = bool::from_datum(
+        (*count_graphemes_arg).constvalue,
+        (*count_graphemes_arg).constisnull,
+    );
+
+    Some(SnippetConfig {
+        start_tag: start_tag.unwrap_or_else(|| DEFAULT_SNIPPET_PREFIX.to_string()),
+        end_tag: end_tag.unwrap_or_else(|| DEFAULT_SNIPPET_POSTFIX.to_string()),
+        max_num_chars: fragment_size
+            .or(max_num_chars)
+            .unwrap_or(DEFAULT_SNIPPET_MAX_NUM_CHARS) as usize,
+        number_of_fragments: number_of_fragments as usize,
+        fragment_separator: fragment_separator
+            .unwrap_or_else(|| DEFAULT_SNIPPET_FRAGMENT_SEPARATOR.to_string()),
+        escape_html: escape_html.unwrap_or(true),
+        count_graphemes: count_graphemes.unwrap_or(false),
+    })
+}
+
+#[inline(always)]
+unsafe fn extract_snippet_fragments(
+    args: PgList<pg_sys::Node>,
+    context: *mut Context,
+) -> Option<SnippetType> {
+    assert!(args.len() == 7);
+
+    let attname = snippet_field(
+        args.get_ptr(0).unwrap(),
+        (*context).planning_rti,
+        (*context).attname_lookup,
+    )?;
+
+    let start_arg = nodecast!(Const, T_Const, args.get_ptr(1).unwrap())?;
+    let end_arg = nodecast!(Const, T_Const, args.get_ptr(2).unwrap())?;
+    let fragment_size_arg = nodecast!(Const, T_Const, args.get_ptr(3).unwrap())?;
+    let number_of_fragments_arg = nodecast!(Const, T_Const, args.get_ptr(4).unwrap())?;
+    let escape_html_arg = nodecast!(Const, T_Const, args.get_ptr(5).unwrap())?;
+    let count_graphemes_arg = nodecast!(Const, T_Const, args.get_ptr(6).unwrap())?;
+
+    let start_tag = String::from_datum((*start_arg).constvalue, (*start_arg).constisnull);
+    let end_tag = String::from_datum((*end_arg).constvalue, (*end_arg).constisnull);
+    let fragment_size = i32::from_datum(
+        (*fragment_size_arg).constvalue,
+        (*fragment_size_arg).constisnull,
+    );
+    let number_of_fragments = i32::from_datum(
+        (*number_of_fragments_arg).constvalue,
+        (*number_of_fragments_arg).constisnull,
+    )
+    .unwrap_or(DEFAULT_SNIPPET_FRAGMENTS_NUMBER_OF_FRAGMENTS);
+    if number_of_fragments < 0 {
+        panic!("`number_of_fragments` must not be negative");
+    }
+    let escape_html = bool::from_datum(
+        (*escape_html_arg).constvalue,
+        (*escape_html_arg).constisnull,
+    );
+    let count_graphemes = bool::from_datum(
+        (*count_graphemes_arg).constvalue,
+        (*count_graphemes_arg).constisnull,
+    );
+
+    Some(SnippetType::Fragments(
+        attname,
+        (*context).snippet_fragments_funcoid,
+        SnippetConfig {
+            start_tag: start_tag.unwrap_or_else(|| DEFAULT_SNIPPET_PREFIX.to_string()),
+            end_tag: end_tag.unwrap_or_else(|| DEFAULT_SNIPPET_POSTFIX.to_string()),
+            max_num_chars: fragment_size.unwrap_or(DEFAULT_SNIPPET_MAX_NUM_CHARS) as usize,
+            number_of_fragments: number_of_fragments as usize,
+            fragment_separator: DEFAULT_SNIPPET_FRAGMENT_SEPARATOR.to_string(),
+            escape_html: escape_html.unwrap_or(true),
+            count_graphemes: count_graphemes.unwrap_or(false),
+        },
+    ))
 }
 
 #[inline(always)]
@@ -227,20 +559,330 @@ unsafe fn extract_snippet_positions(
 ) -> Option<SnippetType> {
     assert!(args.len() == 1);
 
-    let field_arg = find_one_var(args.get_ptr(0).unwrap());
+    let attname = snippet_field(
+        args.get_ptr(0).unwrap(),
+        (*context).planning_rti,
+        (*context).attname_lookup,
+    )?;
+
+    Some(SnippetType::Positions(
+        attname,
+        (*context).snippet_positions_funcoid,
+    ))
+}
+
+#[inline(always)]
+unsafe fn extract_match_positions(
+    args: PgList<pg_sys::Node>,
+    context: *mut Context,
+) -> Option<SnippetType> {
+    assert!(args.len() == 1);
+
+    let attname = snippet_field(
+        args.get_ptr(0).unwrap(),
+        (*context).planning_rti,
+        (*context).attname_lookup,
+    )?;
+
+    Some(SnippetType::MatchPositions(
+        attname,
+        (*context).match_positions_funcoid,
+    ))
+}
+
+/// Render the `matches` of `text` in the fragments that `config` asks for, best first, joined by
+/// its separator.  A `number_of_fragments` of 0 shows the whole text, as one fragment.
+pub fn render_fragments(
+    text: &str,
+    matches: &[(Range<usize>, String)],
+    weights: &BTreeMap<String, f32>,
+    config: &SnippetConfig,
+) -> String {
+    render_scored_fragments(text, matches, weights, config)
+        .into_iter()
+        .map(|(fragment, _)| fragment)
+        .collect::<Vec<_>>()
+        .join(&config.fragment_separator)
+}
+
+/// Like [`render_fragments`], but each fragment is returned on its own, with its
+/// [score](fragment_score).
+pub fn render_scored_fragments(
+    text: &str,
+    matches: &[(Range<usize>, String)],
+    weights: &BTreeMap<String, f32>,
+    config: &SnippetConfig,
+) -> Vec<(String, f32)> {
+    // a highlight never splits a grapheme cluster, even if the tokenizer's offsets do
+    let matches = matches
+        .iter()
+        .map(|(range, term)| (grapheme_range(text, range.clone()), term.clone()))
+        .collect::<Vec<_>>();
+    let fragments = match config.number_of_fragments {
+        _ if matches.is_empty() => vec![],
+        0 => vec![(
+            0..text.len(),
+            fragment_score(matches.iter().map(|(_, term)| term.as_str()), weights),
+        )],
+        _ => select_fragments(text, &matches, weights, config),
+    };
+
+    let highlighted = merge_ranges(matches.iter().map(|(range, _)| range.clone()));
+    fragments
+        .into_iter()
+        .map(|(fragment, score)| {
+            let highlighted = highlighted
+                .iter()
+                .filter(|range| fragment.contains(&range.start))
+                .map(|range| range.start - fragment.start..range.end - fragment.start)
+                .collect::<Vec<_>>();
+            (
+                render_highlighted(&text[fragment], &highlighted, config),
+                score,
+            )
+        })
+        .filter(|(html, _)| !html.trim().is_empty())
+        .collect()
+}
+
+/// Choose up to the config's `number_of_fragments` non-overlapping ranges of `text`, each of at
+/// most `max_num_chars` characters, that score the best for the `matches` they hold.  They're
+/// returned best first, with their scores, and those that tie in the order they appear in `text`.
+pub fn select_fragments(
+    text: &str,
+    matches: &[(Range<usize>, String)],
+    weights: &BTreeMap<String, f32>,
+    config: &SnippetConfig,
+) -> Vec<(Range<usize>, f32)> {
+    // neighbouring matches that fit in one fragment are shown together
+    let mut candidates: Vec<(Vec<&str>, Range<usize>)> = vec![];
+    for (range, term) in matches {
+        match candidates.last_mut() {
+            Some((terms, candidate))
+                if text_length(&text[candidate.start..range.end], config.count_graphemes)
+                    <= config.max_num_chars =>
+            {
+                terms.push(term.as_str());
+                candidate.end = candidate.end.max(range.end);
+            }
+            _ => candidates.push((vec![term.as_str()], range.clone())),
+        }
+    }
+
+    let mut candidates = candidates
+        .into_iter()
+        .map(|(terms, range)| (fragment_score(terms, weights), range))
+        .collect::<Vec<_>>();
+    candidates.sort_by(|(a_score, a), (b_score, b)| {
+        b_score.total_cmp(a_score).then(a.start.cmp(&b.start))
+    });
+    candidates.truncate(config.number_of_fragments);
+    candidates.sort_by_key(|(_, range)| range.start);
+
+    // each fragment may only widen into the text between its neighbours
+    let mut fragments: Vec<(Range<usize>, f32)> = vec![];
+    for (index, (score, range)) in candidates.iter().enumerate() {
+        let lower = fragments.last().map_or(0, |(previous, _)| previous.end);
+        let upper = candidates
+            .get(index + 1)
+            .map_or(text.len(), |(_, next)| next.start);
+        fragments.push((widen(text, range.clone(), config, lower..upper), *score));
+    }
+    fragments.sort_by(|(a, a_score), (b, b_score)| {
+        b_score.total_cmp(a_score).then(a.start.cmp(&b.start))
+    });
+    fragments
+}
 
-    if let Some(field_arg) = field_arg {
-        let attname = (*context)
-            .attname_lookup
-            .get(&((*context).planning_rti as _, (*field_arg).varattno as _))
-            .cloned()
-            .expect("Var attname should be in lookup");
+/// The score of a fragment that matches `terms`.  Each distinct term counts its weight in the
+/// query, and each further match of a term already counted adds half of its weight, so that a
+/// fragment with more of the query's terms beats one that repeats a single term, and of two
+/// fragments with the same terms the denser wins.
+pub fn fragment_score<'a>(
+    terms: impl IntoIterator<Item = &'a str>,
+    weights: &BTreeMap<String, f32>,
+) -> f32 {
+    let mut counted = HashSet::default();
+    terms
+        .into_iter()
+        .map(|term| {
+            let weight = weights.get(term).copied().unwrap_or(1.0);
+            if counted.insert(term) {
+                weight
+            } else {
+                weight / 2.0
+            }
+        })
+        .sum()
+}
+
+/// Widen `range` with the text on either side of it, evenly, up to `max_num_chars` characters
+/// and without leaving `bounds`.  Words cut in two at either end are left out, and so are
+/// grapheme clusters.
+fn widen(
+    text: &str,
+    range: Range<usize>,
+    config: &SnippetConfig,
+    bounds: Range<usize>,
+) -> Range<usize> {
+    let count_graphemes = config.count_graphemes;
+    let spare = config
+        .max_num_chars
+        .saturating_sub(text_length(&text[range.clone()], count_graphemes));
+    let before = text_length(&text[bounds.start..range.start], count_graphemes);
+    let after = text_length(&text[range.end..bounds.end], count_graphemes);
+    let right = (spare - (spare / 2).min(before)).min(after);
+    let left = (spare - right).min(before);
+
+    let mut start = text_offsets(&text[bounds.start..range.start], count_graphemes)
+        .rev()
+        .take(left)
+        .last()
+        .map_or(range.start, |offset| bounds.start + offset);
+    let mut end = text_offsets(&text[range.end..bounds.end], count_graphemes)
+        .nth(right)
+        .map_or(bounds.end, |offset| range.end + offset);
+
+    let starts_mid_word = text[..start]
+        .chars()
+        .next_back()
+        .is_some_and(|c| !c.is_whitespace());
+    if starts_mid_word {
+        start = text[start..range.start]
+            .find(char::is_whitespace)
+            .map_or(range.start, |offset| start + offset);
+    }
+    let ends_mid_word = text[end..]
+        .chars()
+        .next()
+        .is_some_and(|c| !c.is_whitespace());
+    if ends_mid_word {
+        end = text[range.end..end]
+            .rfind(char::is_whitespace)
+            .map_or(range.end, |offset| range.end + offset);
+    }
 
-        Some(SnippetType::Positions(
-            attname,
-            (*context).snippet_positions_funcoid,
-        ))
+    ceil_grapheme(text, start).min(range.start)..floor_grapheme(text, end).max(range.end)
+}
+
+/// The length of `text`, in grapheme clusters if `count_graphemes` is set and in characters
+/// otherwise.
+fn text_length(text: &str, count_graphemes: bool) -> usize {
+    if count_graphemes {
+        text.graphemes(true).count()
+    } else {
+        text.chars().count()
+    }
+}
+
+/// The byte offsets at which each grapheme cluster of `text` starts if `count_graphemes` is set,
+/// and each character otherwise.
+fn text_offsets(
+    text: &str,
+    count_graphemes: bool,
+) -> Box<dyn DoubleEndedIterator<Item = usize> + '_> {
+    if count_graphemes {
+        Box::new(text.grapheme_indices(true).map(|(offset, _)| offset))
     } else {
-        None
+        Box::new(text.char_indices().map(|(offset, _)| offset))
+    }
+}
+
+/// The nearest grapheme cluster boundary of `text` at or before `offset`.
+fn floor_grapheme(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let mut cursor = GraphemeCursor::new(offset, text.len(), true);
+    match cursor.is_boundary(text, 0) {
+        Ok(true) => offset,
+        _ => cursor.prev_boundary(text, 0).ok().flatten().unwrap_or(0),
+    }
+}
+
+/// The nearest grapheme cluster boundary of `text` at or after `offset`.
+fn ceil_grapheme(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset += 1;
+    }
+    let mut cursor = GraphemeCursor::new(offset, text.len(), true);
+    match cursor.is_boundary(text, 0) {
+        Ok(true) => offset,
+        _ => cursor
+            .next_boundary(text, 0)
+            .ok()
+            .flatten()
+            .unwrap_or(text.len()),
+    }
+}
+
+/// `range` of `text`, widened to whole grapheme clusters.
+fn grapheme_range(text: &str, range: Range<usize>) -> Range<usize> {
+    floor_grapheme(text, range.start)..ceil_grapheme(text, range.end)
+}
+
+/// Render `snippet`, made from `text`, with its highlights between the config's tags.  The
+/// fragment and its highlights are widened to whole grapheme clusters of `text`, so that neither
+/// ends inside an emoji sequence or splits a letter from its accents.  The tags are always
+/// written as they are, and only the document's text is escaped.
+pub fn render_snippet(text: &str, snippet: &Snippet, config: &SnippetConfig) -> String {
+    let fragment = snippet.fragment();
+    let Some(offset) = text.find(fragment) else {
+        return render_highlighted(fragment, snippet.highlighted(), config);
+    };
+
+    let range = grapheme_range(text, offset..offset + fragment.len());
+    let highlighted = merge_ranges(
+        snippet
+            .highlighted()
+            .iter()
+            .map(|h| grapheme_range(text, offset + h.start..offset + h.end)),
+    )
+    .into_iter()
+    .map(|h| h.start - range.start..h.end - range.start)
+    .collect::<Vec<_>>();
+    render_highlighted(&text[range], &highlighted, config)
+}
+
+/// Render `fragment` with the `highlighted` byte ranges of it between the config's tags.
+pub fn render_highlighted(
+    fragment: &str,
+    highlighted: &[Range<usize>],
+    config: &SnippetConfig,
+) -> String {
+    let escape = |text: &str, rendered: &mut String| {
+        push_escaped(text, config.escape_html, rendered);
+    };
+
+    let mut rendered = String::with_capacity(fragment.len());
+    let mut from = 0;
+    for range in highlighted {
+        escape(&fragment[from..range.start], &mut rendered);
+        rendered.push_str(&config.start_tag);
+        escape(&fragment[range.clone()], &mut rendered);
+        rendered.push_str(&config.end_tag);
+        from = range.end;
+    }
+    escape(&fragment[from..], &mut rendered);
+    rendered
+}
+
+/// Append `text` to `rendered`, escaped for HTML if `escape_html` is set.
+pub fn push_escaped(text: &str, escape_html: bool, rendered: &mut String) {
+    if !escape_html {
+        rendered.push_str(text);
+        return;
+    }
+    for c in text.chars() {
+        match c {
+            '&' => rendered.push_str("&amp;"),
+            '<' => rendered.push_str("&lt;"),
+            '>' => rendered.push_str("&gt;"),
+            '"' => rendered.push_str("&quot;"),
+            '\'' => rendered.push_str("&#x27;"),
+            c => rendered.push(c),
+        }
     }
 }
@@ -17,21 +17,32 @@
 
 use crate::api::{FieldName, HashMap, OrderByInfo, Varno};
 use crate::index::reader::index::SearchIndexReader;
+use crate::index::reader::timings::SearchTimings;
+use crate::index::term_vectors::{self, Token};
 use crate::postgres::customscan::builders::custom_path::ExecMethodType;
 use crate::postgres::customscan::pdbscan::exec_methods::ExecMethod;
-use crate::postgres::customscan::pdbscan::projections::snippet::SnippetType;
+use crate::postgres::customscan::pdbscan::projections::highlight::{merge_ranges, Highlighter};
+use crate::postgres::customscan::pdbscan::projections::snippet::{
+    fragment_score, push_escaped, render_fragments, render_scored_fragments, render_snippet,
+    SnippetConfig, SnippetType,
+};
 use crate::postgres::customscan::qual_inspect::Qual;
 use crate::postgres::customscan::CustomScanState;
 use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::stats::ScanStats;
 use crate::postgres::utils::u64_to_item_pointer;
 use crate::postgres::visibility_checker::VisibilityChecker;
 use crate::postgres::ParallelScanState;
 use crate::query::SearchQueryInput;
 use pgrx::heap_tuple::PgHeapTuple;
-use pgrx::{pg_sys, PgTupleDesc};
+use pgrx::{pg_sys, JsonB, PgTupleDesc};
 use std::cell::UnsafeCell;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+use tantivy::schema::Value;
 use tantivy::snippet::SnippetGenerator;
-use tantivy::SegmentReader;
+use tantivy::{DocAddress, SegmentReader};
 
 #[derive(Default)]
 pub struct PdbScanState {
@@ -52,9 +63,15 @@ pub struct PdbScanState {
     pub targetlist_len: usize,
 
     pub query_count: usize,
+    pub cached_query_count: usize,
     pub heap_tuple_check_count: usize,
     pub virtual_tuple_count: usize,
     pub invisible_tuple_count: usize,
+    /// Where the search's time goes, if it's being explained with `ANALYZE` and `TIMING`
+    pub search_timings: Option<Arc<SearchTimings>>,
+    pub heap_fetch_time: Duration,
+    /// What the scan has done across all of its rescans, for `paradedb.index_stats`
+    pub scan_stats: ScanStats,
 
     pub heaprelid: pg_sys::Oid,
     pub heaprel: Option<PgSearchRelation>,
@@ -70,13 +87,25 @@ pub struct PdbScanState {
     pub const_score_node: Option<*mut pg_sys::Const>,
     pub score_funcoid: pg_sys::Oid,
 
+    /// Set if the scan is ordered by `paradedb.hybrid()`, whose fused score of the current row
+    /// is set by its exec method
+    pub need_hybrid: bool,
+    pub const_hybrid_node: Option<*mut pg_sys::Const>,
+    pub hybrid_funcoid: pg_sys::Oid,
+    pub hybrid_score: f64,
+
     pub const_snippet_nodes: HashMap<SnippetType, Vec<*mut pg_sys::Const>>,
+    pub const_highlights_nodes: Vec<(Vec<SnippetType>, *mut pg_sys::Const)>,
 
     pub snippet_funcoid: pg_sys::Oid,
     pub snippet_positions_funcoid: pg_sys::Oid,
+    pub match_positions_funcoid: pg_sys::Oid,
+    pub highlights_funcoid: pg_sys::Oid,
 
     pub snippet_generators:
         HashMap<SnippetType, Option<(tantivy::schema::Field, SnippetGenerator)>>,
+    /// Highlighters for the snippets whose field the query searches for phrases
+    pub highlighters: HashMap<SnippetType, Highlighter>,
 
     pub var_attname_lookup: HashMap<(Varno, pg_sys::AttrNumber), FieldName>,
     pub placeholder_targetlist: Option<*mut pg_sys::List>,
@@ -202,6 +231,7 @@ impl PdbScanState {
     #[inline(always)]
     pub fn need_scores(&self) -> bool {
         self.need_scores
+            || self.need_hybrid
             || self.base_search_query_input.need_scores()
             || self
                 .quals
@@ -250,15 +280,92 @@ impl PdbScanState {
         self.visibility_checker.as_mut().unwrap()
     }
 
-    pub fn make_snippet(&self, ctid: u64, snippet_type: &SnippetType) -> Option<String> {
-        let text = unsafe { self.doc_from_heap(ctid, snippet_type.field())? };
-        let (field, generator) = self.snippet_generators.get(snippet_type)?.as_ref()?;
-        let mut snippet = generator.snippet(&text);
-        if let SnippetType::Text(_, _, config) = snippet_type {
-            snippet.set_snippet_prefix_postfix(&config.start_tag, &config.end_tag);
+    /// Whether `field` is a whole JSON column, whose strings are each highlighted on their own.
+    pub fn is_whole_json_field(&self, field: &FieldName) -> bool {
+        field.path().is_none()
+            && self
+                .search_reader
+                .as_ref()
+                .and_then(|reader| reader.schema().search_field(field.root()))
+                .is_some_and(|search_field| search_field.is_json())
+    }
+
+    pub fn make_snippet(
+        &self,
+        ctid: u64,
+        doc_address: DocAddress,
+        snippet_type: &SnippetType,
+    ) -> Option<String> {
+        unsafe {
+            let mut htup = self.heap_tuple_data(ctid)?;
+            self.snippet_from_tuple(&mut htup, doc_address, snippet_type)
+        }
+    }
+
+    /// The snippet of each of `snippet_types`, named by its field, all read from one fetch of
+    /// the tuple at `ctid`.
+    pub fn make_highlights(
+        &self,
+        ctid: u64,
+        doc_address: DocAddress,
+        snippet_types: &[SnippetType],
+    ) -> Option<JsonB> {
+        let mut htup = unsafe { self.heap_tuple_data(ctid)? };
+        let highlights = snippet_types
+            .iter()
+            .filter_map(|snippet_type| {
+                let snippet =
+                    unsafe { self.snippet_from_tuple(&mut htup, doc_address, snippet_type)? };
+                Some((snippet_type.field().to_string(), snippet.into()))
+            })
+            .collect::<serde_json::Map<_, _>>();
+
+        (!highlights.is_empty()).then(|| JsonB(serde_json::Value::Object(highlights)))
+    }
+
+    unsafe fn snippet_from_tuple(
+        &self,
+        htup: &mut pg_sys::HeapTupleData,
+        doc_address: DocAddress,
+        snippet_type: &SnippetType,
+    ) -> Option<String> {
+        if let SnippetType::Text(field, _, config) = snippet_type {
+            if self.is_whole_json_field(field) {
+                let json = self.json_from_tuple(htup, field)?;
+                let fragments = self.json_fragments(&json, snippet_type, config)?;
+                return (!fragments.is_empty()).then(|| {
+                    fragments
+                        .into_iter()
+                        .map(|(fragment, _)| fragment)
+                        .collect::<Vec<_>>()
+                        .join(&config.fragment_separator)
+                });
+            }
         }
 
-        let html = snippet.to_html();
+        let text = self.doc_from_tuple(htup, snippet_type.field())?;
+        let (_, generator) = self.snippet_generators.get(snippet_type)?.as_ref()?;
+        let matches = match snippet_type {
+            // several fragments are chosen from every match in the text, and so are those sized
+            // in grapheme clusters, which tantivy doesn't count
+            SnippetType::Text(_, _, config)
+                if config.number_of_fragments != 1 || config.count_graphemes =>
+            {
+                self.all_matches(doc_address, snippet_type, &text)
+            }
+            _ => self.matched_tokens(doc_address, snippet_type, &text),
+        };
+        if let (Some(matches), SnippetType::Text(_, _, config)) = (matches, snippet_type) {
+            let html = render_fragments(&text, &matches, generator.terms_text(), config);
+            return (!html.trim().is_empty()).then_some(html);
+        }
+
+        let snippet = generator.snippet(&text);
+        let html = match snippet_type {
+            SnippetType::Text(_, _, config) => render_snippet(&text, &snippet, config),
+            _ => snippet.to_html(),
+        };
+
         if html.trim().is_empty() {
             None
         } else {
@@ -266,12 +373,137 @@ impl PdbScanState {
         }
     }
 
+    /// The best fragments of the field, best first, as a JSON array of each fragment and its
+    /// score.
+    pub fn get_snippet_fragments(
+        &self,
+        ctid: u64,
+        doc_address: DocAddress,
+        snippet_type: &SnippetType,
+    ) -> Option<JsonB> {
+        let SnippetType::Fragments(field, _, config) = snippet_type else {
+            return None;
+        };
+        let mut htup = unsafe { self.heap_tuple_data(ctid)? };
+        let fragments = if self.is_whole_json_field(field) {
+            let json = unsafe { self.json_from_tuple(&mut htup, field)? };
+            self.json_fragments(&json, snippet_type, config)?
+        } else {
+            let text = unsafe { self.doc_from_tuple(&mut htup, field)? };
+            let (_, generator) = self.snippet_generators.get(snippet_type)?.as_ref()?;
+            let matches = self.all_matches(doc_address, snippet_type, &text)?;
+            render_scored_fragments(&text, &matches, generator.terms_text(), config)
+        };
+
+        let fragments = fragments
+            .into_iter()
+            .map(|(fragment, score)| serde_json::json!({"fragment": fragment, "score": score}))
+            .collect::<Vec<_>>();
+        (!fragments.is_empty()).then(|| JsonB(serde_json::Value::Array(fragments)))
+    }
+
+    /// Each string in a JSON document is a fragment of its own, labelled with its path.  The ones
+    /// that score the best are chosen, best first, like the fragments of a text field.
+    fn json_fragments(
+        &self,
+        json: &serde_json::Value,
+        snippet_type: &SnippetType,
+        config: &SnippetConfig,
+    ) -> Option<Vec<(String, f32)>> {
+        fn json_strings<'a>(
+            value: &'a serde_json::Value,
+            path: &mut Vec<&'a str>,
+            strings: &mut Vec<(String, &'a str)>,
+        ) {
+            match value {
+                serde_json::Value::String(string) => strings.push((path.join("."), string)),
+                serde_json::Value::Array(elements) => {
+                    for element in elements {
+                        json_strings(element, path, strings);
+                    }
+                }
+                serde_json::Value::Object(object) => {
+                    for (key, value) in object {
+                        path.push(key);
+                        json_strings(value, path, strings);
+                        path.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let (field, generator) = self.snippet_generators.get(snippet_type)?.as_ref()?;
+        let mut analyzer = self
+            .search_reader
+            .as_ref()?
+            .searcher()
+            .index()
+            .tokenizer_for_field(*field)
+            .ok()?;
+
+        let mut strings = vec![];
+        json_strings(json, &mut vec![], &mut strings);
+
+        let mut fragments = strings
+            .into_iter()
+            .filter_map(|(path, string)| {
+                let matches =
+                    generator_matches(term_vectors::tokenize(&mut analyzer, string), generator);
+                if matches.is_empty() {
+                    return None;
+                }
+                let score = fragment_score(
+                    matches.iter().map(|(_, term)| term.as_str()),
+                    generator.terms_text(),
+                );
+
+                let mut fragment = String::new();
+                push_escaped(&path, config.escape_html, &mut fragment);
+                fragment.push_str(": ");
+                if config.number_of_fragments == 0 || config.count_graphemes {
+                    // each string gives one fragment at most
+                    let config = SnippetConfig {
+                        number_of_fragments: config.number_of_fragments.min(1),
+                        ..config.clone()
+                    };
+                    fragment.push_str(&render_fragments(
+                        string,
+                        &matches,
+                        generator.terms_text(),
+                        &config,
+                    ));
+                } else {
+                    fragment.push_str(&render_snippet(string, &generator.snippet(string), config));
+                }
+                Some((fragment, score))
+            })
+            .collect::<Vec<_>>();
+        // the sort is stable, so strings that tie are in the order they appear
+        fragments.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        if config.number_of_fragments > 0 {
+            fragments.truncate(config.number_of_fragments);
+        }
+        Some(fragments)
+    }
+
     pub fn get_snippet_positions(
         &self,
         ctid: u64,
+        doc_address: DocAddress,
         snippet_type: &SnippetType,
     ) -> Option<Vec<Vec<i32>>> {
         let text = unsafe { self.doc_from_heap(ctid, snippet_type.field())? };
+        if let Some(matches) = self.matched_tokens(doc_address, snippet_type, &text) {
+            let highlighted = merge_ranges(matches.into_iter().map(|(offsets, _)| offsets));
+            return (!highlighted.is_empty()).then(|| {
+                highlighted
+                    .iter()
+                    .map(|span| vec![span.start as i32, span.end as i32])
+                    .collect()
+            });
+        }
+
         let (field, generator) = self.snippet_generators.get(snippet_type)?.as_ref()?;
         let snippet = generator.snippet(&text);
         let highlighted = snippet.highlighted();
@@ -288,6 +520,105 @@ impl PdbScanState {
         }
     }
 
+    /// Every token of the field's text that the query matched, with its byte offsets, read from
+    /// the field's stored tokens or found by running the text back through its tokenizer.
+    pub fn get_match_positions(
+        &self,
+        ctid: u64,
+        doc_address: DocAddress,
+        snippet_type: &SnippetType,
+    ) -> Option<JsonB> {
+        let text = unsafe { self.doc_from_heap(ctid, snippet_type.field())? };
+        let matches = self
+            .all_matches(doc_address, snippet_type, &text)?
+            .into_iter()
+            .map(|(offsets, term)| {
+                serde_json::json!({
+                    "byte_start": offsets.start,
+                    "byte_end": offsets.end,
+                    "term": term,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if matches.is_empty() {
+            None
+        } else {
+            Some(JsonB(serde_json::Value::Array(matches)))
+        }
+    }
+
+    /// Like [`PdbScanState::matched_tokens`], but the text is run back through the field's
+    /// tokenizer when its matches can't be found otherwise.
+    fn all_matches(
+        &self,
+        doc_address: DocAddress,
+        snippet_type: &SnippetType,
+        text: &str,
+    ) -> Option<Vec<(Range<usize>, String)>> {
+        if let Some(matches) = self.matched_tokens(doc_address, snippet_type, text) {
+            return Some(matches);
+        }
+
+        let (field, generator) = self.snippet_generators.get(snippet_type)?.as_ref()?;
+        let mut analyzer = self
+            .search_reader
+            .as_ref()?
+            .searcher()
+            .index()
+            .tokenizer_for_field(*field)
+            .ok()?;
+        Some(generator_matches(
+            term_vectors::tokenize(&mut analyzer, text),
+            generator,
+        ))
+    }
+
+    /// The byte offsets and the text of each token of `text` that the query matched, if they
+    /// can be found without the snippet generator: by the snippet's [`Highlighter`], from the
+    /// field's stored tokens, or both.
+    fn matched_tokens(
+        &self,
+        doc_address: DocAddress,
+        snippet_type: &SnippetType,
+        text: &str,
+    ) -> Option<Vec<(Range<usize>, String)>> {
+        let tokens = self.stored_tokens(doc_address, snippet_type.field(), text);
+        match (self.highlighters.get(snippet_type), tokens) {
+            (Some(highlighter), Some(tokens)) => Some(highlighter.matching_tokens(tokens)),
+            (Some(highlighter), None) => Some(highlighter.matches(text)),
+            (None, Some(tokens)) => {
+                let (_, generator) = self.snippet_generators.get(snippet_type)?.as_ref()?;
+                Some(generator_matches(tokens, generator))
+            }
+            (None, None) => None,
+        }
+    }
+
+    /// The tokens of the text `field` of the document at `doc_address`, if the field is
+    /// configured to keep them, and they were made from `text`.
+    fn stored_tokens(
+        &self,
+        doc_address: DocAddress,
+        field: &FieldName,
+        text: &str,
+    ) -> Option<Vec<Token>> {
+        if field.path().is_some() {
+            return None;
+        }
+        let reader = self.search_reader.as_ref()?;
+        let search_field = reader.schema().search_field(field)?;
+        let term_vectors_field = reader.schema().term_vectors_field(search_field.field())?;
+        let document = reader.get_doc(doc_address).ok()?;
+        let tokens = term_vectors::decode(document.get_first(term_vectors_field)?.as_bytes()?)?;
+
+        // the tokens are only of use if their offsets are those of `text`
+        tokens
+            .iter()
+            .all(|token| text.get(token.offsets.clone()).is_some())
+            .then_some(tokens)
+    }
+
     pub fn limit(&self) -> Option<usize> {
         match &self.exec_method_type {
             ExecMethodType::TopN { limit, .. } => Some(*limit),
@@ -312,17 +643,36 @@ impl PdbScanState {
                 }
             }
         }
+        self.scan_stats.docs_scanned += self.docs_scanned();
         self.query_count = 0;
+        self.cached_query_count = 0;
         self.heap_tuple_check_count = 0;
         self.virtual_tuple_count = 0;
         self.invisible_tuple_count = 0;
+        if let Some(search_timings) = &self.search_timings {
+            search_timings.reset();
+        }
+        self.heap_fetch_time = Duration::ZERO;
         self.exec_method_mut().reset(self);
     }
 
+    /// The documents the search has returned since the last rescan, visible or not.
+    fn docs_scanned(&self) -> u64 {
+        (self.heap_tuple_check_count + self.virtual_tuple_count + self.invisible_tuple_count) as u64
+    }
+
+    /// The scan's statistics, once it has finished.
+    pub fn final_scan_stats(&self) -> ScanStats {
+        let mut scan_stats = self.scan_stats;
+        scan_stats.docs_scanned += self.docs_scanned();
+        scan_stats
+    }
+
     /// Given a ctid and field name, get the corresponding value from the heap
     ///
     /// This function supports text, text[], and json/jsonb fields
-    unsafe fn doc_from_heap(&self, ctid: u64, field: &FieldName) -> Option<String> {
+    /// Fetch the tuple at `ctid`, if it's visible to the active snapshot.
+    unsafe fn heap_tuple_data(&self, ctid: u64) -> Option<pg_sys::HeapTupleData> {
         let heaprel = self.heaprel();
         let mut ipd = pg_sys::ItemPointerData::default();
         u64_to_item_pointer(ctid, &mut ipd);
@@ -359,9 +709,50 @@ impl PdbScanState {
         }
 
         pg_sys::ReleaseBuffer(buffer);
+        Some(htup)
+    }
 
+    /// The whole of the JSON column `field` of `htup`.
+    unsafe fn json_from_tuple(
+        &self,
+        htup: &mut pg_sys::HeapTupleData,
+        field: &FieldName,
+    ) -> Option<serde_json::Value> {
+        let tuple_desc = PgTupleDesc::from_pg_unchecked(self.heaprel().rd_att);
+        let heap_tuple = PgHeapTuple::from_heap_tuple(tuple_desc, &mut *htup);
+        let root = field.root();
+        let (_, attribute) = heap_tuple.get_attribute_by_name(&root)?;
+
+        match attribute.type_oid().value() {
+            pg_sys::JSONOID => heap_tuple
+                .get_by_name::<pgrx::datum::Json>(&root)
+                .unwrap_or_else(|_| {
+                    panic!("json_from_tuple: should be able to read json field {root}")
+                })
+                .map(|json| json.0),
+            pg_sys::JSONBOID => heap_tuple
+                .get_by_name::<pgrx::datum::JsonB>(&root)
+                .unwrap_or_else(|_| {
+                    panic!("json_from_tuple: should be able to read jsonb field {root}")
+                })
+                .map(|json| json.0),
+            _ => None,
+        }
+    }
+
+    unsafe fn doc_from_heap(&self, ctid: u64, field: &FieldName) -> Option<String> {
+        let mut htup = self.heap_tuple_data(ctid)?;
+        self.doc_from_tuple(&mut htup, field)
+    }
+
+    unsafe fn doc_from_tuple(
+        &self,
+        htup: &mut pg_sys::HeapTupleData,
+        field: &FieldName,
+    ) -> Option<String> {
+        let heaprel = self.heaprel();
         let tuple_desc = PgTupleDesc::from_pg_unchecked(heaprel.rd_att);
-        let heap_tuple = PgHeapTuple::from_heap_tuple(tuple_desc.clone(), &mut htup);
+        let heap_tuple = PgHeapTuple::from_heap_tuple(tuple_desc.clone(), &mut *htup);
         let (index, attribute) = heap_tuple.get_attribute_by_name(&field.root()).unwrap();
 
         if pg_sys::type_is_array(attribute.type_oid().value()) {
@@ -369,7 +760,7 @@ impl PdbScanState {
             // to emulate Tantivy's default behavior for highlighting text arrays
             Some(
                 pgrx::htup::heap_getattr::<Vec<Option<String>>, _>(
-                    &pgrx::pgbox::PgBox::from_pg(&mut htup),
+                    &pgrx::pgbox::PgBox::from_pg(&mut *htup),
                     index,
                     &tuple_desc,
                 )
@@ -430,3 +821,16 @@ impl PdbScanState {
         }
     }
 }
+
+/// The byte offsets and the text of each of `tokens` that `generator` highlights.
+fn generator_matches(
+    tokens: Vec<Token>,
+    generator: &SnippetGenerator,
+) -> Vec<(Range<usize>, String)> {
+    let terms = generator.terms_text();
+    tokens
+        .into_iter()
+        .filter(|token| terms.contains_key(&token.text))
+        .map(|token| (token.offsets, token.text))
+        .collect()
+}
@@ -0,0 +1,86 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `paradedb.trace`, which reports the decisions made while a search is planned and executed.
+//!
+//! `EXPLAIN` shows the plan that was chosen, but not why a predicate wasn't pushed down, or that
+//! a scan fell back to a slower execution method once it started.  With `SET paradedb.trace = on`
+//! each of those decisions is sent to the client as a NOTICE.
+
+use crate::api::operator::anyelement_query_input_opoid;
+use crate::gucs;
+use crate::postgres::customscan::builders::custom_path::RestrictInfoType;
+use crate::postgres::customscan::qual_inspect::{extract_quals, QualExtractState};
+use crate::postgres::rel::PgSearchRelation;
+use pgrx::{pg_sys, PgList};
+use std::ffi::CStr;
+
+/// Send the `message` to the client if `paradedb.trace` is on.  It's only formatted if it is.
+pub fn trace(message: impl FnOnce() -> String) {
+    if gucs::trace() {
+        pgrx::notice!("paradedb.trace: {}", message());
+    }
+}
+
+/// The `WHERE` clauses of the relation `rti` that can't be searched for in `indexrel` on their
+/// own, as SQL.
+pub unsafe fn unsupported_clauses(
+    root: *mut pg_sys::PlannerInfo,
+    rti: pg_sys::Index,
+    rel: *mut pg_sys::RelOptInfo,
+    indexrel: &PgSearchRelation,
+) -> Vec<String> {
+    PgList::<pg_sys::RestrictInfo>::from_pg((*rel).baserestrictinfo)
+        .iter_ptr()
+        .filter(|ri| {
+            extract_quals(
+                root,
+                rti,
+                ri.cast(),
+                anyelement_query_input_opoid(),
+                RestrictInfoType::BaseRelation,
+                indexrel,
+                false,
+                &mut QualExtractState::default(),
+            )
+            .is_none()
+        })
+        .map(|ri| deparse(root, rti, (*ri).clause.cast()))
+        .collect()
+}
+
+/// `node`, an expression of the relation `rti`, as SQL.
+pub unsafe fn deparse(
+    root: *mut pg_sys::PlannerInfo,
+    rti: pg_sys::Index,
+    node: *mut pg_sys::Node,
+) -> String {
+    // the expression of a join clause can't be deparsed with the context of one relation
+    let varnos = pg_sys::pull_varnos(root, node);
+    if !pg_sys::bms_is_subset(varnos, pg_sys::bms_make_singleton(rti as _)) {
+        return "<a join clause>".into();
+    }
+    let relid = (*pg_sys::rt_fetch(rti, (*(*root).parse).rtable)).relid;
+
+    // the context has just the one relation, so its vars are renumbered to be the first
+    let node = pg_sys::copyObjectImpl(node.cast()).cast::<pg_sys::Node>();
+    pg_sys::ChangeVarNodes(node, rti as _, 1, 0);
+    let context = pg_sys::deparse_context_for(pg_sys::get_rel_name(relid), relid);
+    CStr::from_ptr(pg_sys::deparse_expression(node, context, false, false))
+        .to_string_lossy()
+        .into_owned()
+}
@@ -19,14 +19,14 @@ use crate::api::operator::searchqueryinput_typoid;
 use crate::api::{fieldname_typoid, FieldName, HashMap};
 use crate::nodecast;
 use crate::postgres::customscan::opexpr::{
-    initialize_equality_operator_lookup, OpExpr, OperatorAccepts, PostgresOperatorOid,
-    TantivyOperator, TantivyOperatorExt,
+    initialize_equality_operator_lookup, initialize_range_operator_lookup, OpExpr, OperatorAccepts,
+    PostgresOperatorOid, TantivyOperator, TantivyOperatorExt,
 };
 use crate::postgres::customscan::qual_inspect::Qual;
 use crate::postgres::rel::PgSearchRelation;
 use crate::postgres::var::{find_one_var_and_fieldname, VarContext};
 use crate::schema::SearchField;
-use pgrx::{direct_function_call, pg_guard, pg_sys, IntoDatum, PgList};
+use pgrx::{direct_function_call, pg_guard, pg_sys, FromDatum, IntoDatum, PgList};
 use std::sync::OnceLock;
 
 #[derive(Debug, Clone)]
@@ -85,7 +85,14 @@ impl PushdownField {
 
 macro_rules! pushdown {
     ($attname:expr, $opexpr:expr, $operator:expr, $rhs:ident) => {{
-        let funcexpr = make_opexpr($attname, $opexpr, $operator, $rhs);
+        let funcid = match $opexpr {
+            OpExpr::Array(_) => terms_with_operator_procid(),
+            OpExpr::Single(_) => term_with_operator_procid(),
+        };
+        pushdown!($attname, $opexpr, $operator, $rhs, funcid)
+    }};
+    ($attname:expr, $opexpr:expr, $operator:expr, $rhs:ident, $funcid:expr) => {{
+        let funcexpr = make_opexpr($attname, $opexpr, $operator, $rhs, $funcid);
 
         if !is_complex(funcexpr.cast()) {
             Qual::PushdownExpr { funcexpr }
@@ -144,13 +151,61 @@ pub unsafe fn try_pushdown_inner(
                 Some(Qual::ExternalVar)
             }
         },
-        None => {
-            // TODO:  support other types of OpExprs
-            None
-        }
+        None => try_pushdown_range(rti, opexpr, &pushdown, rhs),
+    }
+}
+
+/// Push down one of Postgres' range operators (`&&`, `@>`, `<@`) whose left side is an indexed
+/// range field, so that availability-window style filters are evaluated by the index.
+unsafe fn try_pushdown_range(
+    rti: pg_sys::Index,
+    opexpr: OpExpr,
+    pushdown: &PushdownField,
+    rhs: *mut pg_sys::Node,
+) -> Option<Qual> {
+    static RANGE_OPERATOR_LOOKUP: OnceLock<HashMap<PostgresOperatorOid, TantivyOperator>> =
+        OnceLock::new();
+
+    // `range @> ANY(...)` and friends aren't supported
+    if !matches!(opexpr, OpExpr::Single(_)) || !pushdown.search_field().is_range() {
+        return None;
+    }
+
+    let pgsearch_operator = RANGE_OPERATOR_LOOKUP
+        .get_or_init(|| unsafe { initialize_range_operator_lookup() })
+        .get(&opexpr.opno())?;
+
+    if pushdown.varno() == rti {
+        Some(pushdown!(
+            &pushdown.attname(),
+            opexpr,
+            pgsearch_operator,
+            rhs,
+            range_term_with_operator_procid()
+        ))
+    } else {
+        Some(Qual::ExternalVar)
     }
 }
 
+/// The field that `node` compares, if it's a comparison that was pushed down to the index.
+pub unsafe fn pushed_down_field(node: *mut pg_sys::Node) -> Option<FieldName> {
+    let funcexpr = nodecast!(FuncExpr, T_FuncExpr, node)?;
+    if ![
+        term_with_operator_procid(),
+        terms_with_operator_procid(),
+        range_term_with_operator_procid(),
+    ]
+    .contains(&(*funcexpr).funcid)
+    {
+        return None;
+    }
+
+    let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
+    let field = nodecast!(Const, T_Const, args.get_ptr(0)?)?;
+    FieldName::from_datum((*field).constvalue, (*field).constisnull)
+}
+
 unsafe fn term_with_operator_procid() -> pg_sys::Oid {
     direct_function_call::<pg_sys::Oid>(
             pg_sys::regprocedurein,
@@ -169,19 +224,26 @@ unsafe fn terms_with_operator_procid() -> pg_sys::Oid {
             .expect("the `paradedb.terms_with_operator(paradedb.fieldname, text, anyelement, bool)` function should exist")
 }
 
+unsafe fn range_term_with_operator_procid() -> pg_sys::Oid {
+    direct_function_call::<pg_sys::Oid>(
+            pg_sys::regprocedurein,
+            // NB:  the SQL signature here needs to match our Rust implementation
+            &[c"paradedb.range_term_with_operator(paradedb.fieldname, text, anyelement)".into_datum()],
+        )
+            .expect("the `paradedb.range_term_with_operator(paradedb.fieldname, text, anyelement)` function should exist")
+}
+
 unsafe fn make_opexpr(
     field: &FieldName,
     orig_opexor: OpExpr,
     operator: &str,
     value: *mut pg_sys::Node,
+    funcid: pg_sys::Oid,
 ) -> *mut pg_sys::FuncExpr {
     let paradedb_funcexpr: *mut pg_sys::FuncExpr =
         pg_sys::palloc0(size_of::<pg_sys::FuncExpr>()).cast();
     (*paradedb_funcexpr).xpr.type_ = pg_sys::NodeTag::T_FuncExpr;
-    (*paradedb_funcexpr).funcid = match orig_opexor {
-        OpExpr::Array(_) => terms_with_operator_procid(),
-        OpExpr::Single(_) => term_with_operator_procid(),
-    };
+    (*paradedb_funcexpr).funcid = funcid;
     (*paradedb_funcexpr).funcresulttype = searchqueryinput_typoid();
     (*paradedb_funcexpr).funcretset = false;
     (*paradedb_funcexpr).funcvariadic = false;
@@ -226,6 +288,12 @@ unsafe fn make_opexpr(
     paradedb_funcexpr
 }
 
+/// Whether `root` has to be evaluated when the plan is executed, rather than when it's planned.
+///
+/// This includes stable functions, such as `current_setting()` and `now()`, whose value can
+/// change between executions of a cached plan.  Row-level security policies often compare
+/// against them, and a policy that's been evaluated at planning time could show one session's
+/// rows to another.
 pub unsafe fn is_complex(root: *mut pg_sys::Node) -> bool {
     #[pg_guard]
     unsafe extern "C-unwind" fn walker(
@@ -234,7 +302,7 @@ pub unsafe fn is_complex(root: *mut pg_sys::Node) -> bool {
     ) -> bool {
         nodecast!(Var, T_Var, node).is_some()
             || nodecast!(Param, T_Param, node).is_some()
-            || pg_sys::contain_volatile_functions(node)
+            || pg_sys::contain_mutable_functions(node)
             || pg_sys::expression_tree_walker(node, Some(walker), std::ptr::null_mut())
     }
 
@@ -19,7 +19,9 @@ use crate::gucs;
 use crate::nodecast;
 use crate::postgres::customscan::builders::custom_path::RestrictInfoType;
 use crate::postgres::customscan::opexpr::OpExpr;
-use crate::postgres::customscan::pushdown::{is_complex, try_pushdown_inner, PushdownField};
+use crate::postgres::customscan::pushdown::{
+    is_complex, pushed_down_field, try_pushdown_inner, PushdownField,
+};
 use crate::postgres::customscan::{operator_oid, score_funcoid};
 use crate::postgres::rel::PgSearchRelation;
 use crate::postgres::var::{find_one_var_and_fieldname, VarContext};
@@ -207,6 +209,18 @@ impl Qual {
         }
     }
 
+    /// The expressions that are evaluated against heap tuples rather than searched for.
+    pub fn heap_exprs(&self) -> Vec<*mut pg_sys::Node> {
+        match self {
+            Qual::HeapExpr { expr_node, .. } => vec![*expr_node],
+            Qual::And(quals) | Qual::Or(quals) => {
+                quals.iter().flat_map(|q| q.heap_exprs()).collect()
+            }
+            Qual::Not(qual) => qual.heap_exprs(),
+            _ => vec![],
+        }
+    }
+
     pub fn collect_exprs<'a>(&'a mut self, exprs: &mut Vec<&'a mut Qual>) {
         match self {
             Qual::Expr { .. } => exprs.push(self),
@@ -422,51 +436,163 @@ impl From<&Qual> for SearchQueryInput {
                     },
                 }
             }
-            Qual::Not(qual) => {
-                // Special handling for boolean fields to correctly handle NULL values
-                match qual.as_ref() {
-                    // If we're negating a PushdownVarEqTrue, we should use PushdownVarEqFalse directly
-                    // rather than using must_not, to avoid including NULLs
-                    // This follows SQL standard where NOT (field = TRUE) is equivalent to (field = FALSE)
-                    // and does NOT include NULL values
-                    Qual::PushdownVarEqTrue { field } => Self::from(&Qual::PushdownVarEqFalse {
-                        field: field.clone(),
-                    }),
-                    // Similarly, if we're negating a PushdownVarEqFalse, use PushdownVarEqTrue
-                    // This follows SQL standard where NOT (field = FALSE) is equivalent to (field = TRUE)
-                    // and does NOT include NULL values
-                    Qual::PushdownVarEqFalse { field } => Self::from(&Qual::PushdownVarEqTrue {
-                        field: field.clone(),
-                    }),
-
-                    // If the Qual represents a placeholder to another Var elsewhere in the plan,
-                    // that means it's a JOIN of some kind and what we actually need to return, in its place,
-                    // is "all" rather than "NOT all"
-                    Qual::ExternalVar => SearchQueryInput::All,
-
-                    // If the Qual represents a placeholder to another Expr elsewhere in the plan,
-                    // that means it's a JOIN of some kind and what we actually need to return, in its place,
-                    // is "all" rather than "NOT all"
-                    Qual::ExternalExpr => SearchQueryInput::All,
-
-                    // For other types of negation, use the standard Boolean query with must_not
-                    // Note that when negating an IS operator (e.g., IS NOT TRUE), PostgreSQL handles
-                    // NULL values differently than when negating equality operators
-                    _ => {
-                        let must_not = vec![SearchQueryInput::from(qual.as_ref())];
-
-                        SearchQueryInput::Boolean {
-                            must: vec![SearchQueryInput::All],
-                            should: Default::default(),
-                            must_not,
-                        }
-                    }
-                }
-            }
+            Qual::Not(qual) => falsified(qual),
         }
     }
 }
 
+/// The search for the rows that `qual` is false for, which are the rows that `NOT qual` matches.
+///
+/// In SQL, `NOT` of NULL is NULL, so a row that `qual` is NULL for, like one whose boolean field is
+/// NULL, matches neither `qual` nor `NOT qual`.  Excluding the rows that `qual` matches from all
+/// rows would include it, so quals that can be NULL are falsified one at a time instead.
+fn falsified(qual: &Qual) -> SearchQueryInput {
+    match qual {
+        // NOT (field = TRUE) is equivalent to (field = FALSE), and doesn't include NULL values
+        Qual::PushdownVarEqTrue { field } => SearchQueryInput::from(&Qual::PushdownVarEqFalse {
+            field: field.clone(),
+        }),
+        // and NOT (field = FALSE) is equivalent to (field = TRUE)
+        Qual::PushdownVarEqFalse { field } => SearchQueryInput::from(&Qual::PushdownVarEqTrue {
+            field: field.clone(),
+        }),
+
+        // If the Qual represents a placeholder to another Var or Expr elsewhere in the plan,
+        // that means it's a JOIN of some kind and what we actually need to return, in its place,
+        // is "all" rather than "NOT all"
+        Qual::ExternalVar | Qual::ExternalExpr => SearchQueryInput::All,
+
+        Qual::Not(qual) => SearchQueryInput::from(qual.as_ref()),
+
+        // a conjunction is false when any of its quals is, and a disjunction when all of them are.
+        // Score filters only narrow the matches of the search they're part of, so they can't be
+        // falsified on their own
+        Qual::And(quals) if !qual.contains_score_exprs() => SearchQueryInput::Boolean {
+            must: Default::default(),
+            should: quals.iter().map(falsified).collect(),
+            must_not: Default::default(),
+        },
+        Qual::Or(quals) if !qual.contains_score_exprs() => SearchQueryInput::Boolean {
+            must: quals.iter().map(falsified).collect(),
+            should: Default::default(),
+            must_not: Default::default(),
+        },
+
+        // an expression that's NULL for a row isn't false for it either
+        Qual::HeapExpr {
+            expr_node,
+            expr_desc,
+            ..
+        } => SearchQueryInput::HeapFilter {
+            indexed_query: Box::new(SearchQueryInput::All),
+            field_filters: vec![unsafe {
+                HeapFieldFilter::new(is_false(*expr_node), format!("({expr_desc}) IS FALSE"))
+            }],
+        },
+
+        // a comparison is NULL for the rows whose field is NULL, so it's false only for the rows
+        // that have a value it doesn't match
+        Qual::PushdownExpr { funcexpr } => falsified_comparison(qual, (*funcexpr).cast()),
+        Qual::Expr { node, .. } => falsified_comparison(qual, *node),
+
+        // everything else, like a search or an IS test, is never NULL, so it's false for the rows
+        // it doesn't match
+        _ => SearchQueryInput::Boolean {
+            must: vec![SearchQueryInput::All],
+            should: Default::default(),
+            must_not: vec![SearchQueryInput::from(qual)],
+        },
+    }
+}
+
+/// The search for the rows that `qual`, whose expression is `node`, is false for.  `node` is
+/// either a comparison that was pushed down to the index, or a search.
+fn falsified_comparison(qual: &Qual, node: *mut pg_sys::Node) -> SearchQueryInput {
+    let exists = match unsafe { pushed_down_field(node) } {
+        Some(field) => SearchQueryInput::FieldedQuery {
+            field,
+            query: pdb::Query::Exists,
+        },
+        None => SearchQueryInput::All,
+    };
+    SearchQueryInput::Boolean {
+        must: vec![exists],
+        should: Default::default(),
+        must_not: vec![SearchQueryInput::from(qual)],
+    }
+}
+
+/// `expr IS FALSE`, which is false rather than NULL when `expr` is NULL.
+unsafe fn is_false(expr: *mut pg_sys::Node) -> *mut pg_sys::Node {
+    let booltest =
+        pg_sys::palloc0(std::mem::size_of::<pg_sys::BooleanTest>()).cast::<pg_sys::BooleanTest>();
+    (*booltest).xpr.type_ = pg_sys::NodeTag::T_BooleanTest;
+    (*booltest).arg = expr.cast();
+    (*booltest).booltesttype = pg_sys::BoolTestType::IS_FALSE;
+    (*booltest).location = -1;
+    booltest.cast()
+}
+
+/// Whether a scan that evaluates all of `restrict_info` at once still applies the restrictions
+/// of row-level security policies and security barrier views before any of the query's own
+/// restrictions that could leak the rows they hide.
+///
+/// Postgres gives those restrictions a lower `security_level` than the query's, and only lets a
+/// restriction at a higher level be evaluated first if it's leakproof.  The index answers the
+/// restrictions it can without calling any SQL functions on the rows, so those are always safe.
+/// But the ones that it can't are filtered against the heap, in no particular order, so a policy
+/// that needs the heap can't share a scan with a leaky heap filter from a higher level.
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn respects_security_barriers(
+    root: *mut pg_sys::PlannerInfo,
+    rti: pg_sys::Index,
+    restrict_info: &PgList<pg_sys::RestrictInfo>,
+    pdbopoid: pg_sys::Oid,
+    ri_type: RestrictInfoType,
+    indexrel: &PgSearchRelation,
+    convert_external_to_special_qual: bool,
+) -> bool {
+    let Some(min_security_level) = restrict_info
+        .iter_ptr()
+        .map(|ri| (*ri).security_level)
+        .min()
+    else {
+        return true;
+    };
+
+    let needs_heap = |ri: *mut pg_sys::RestrictInfo| {
+        extract_quals(
+            root,
+            rti,
+            ri.cast(),
+            pdbopoid,
+            ri_type,
+            indexrel,
+            convert_external_to_special_qual,
+            &mut QualExtractState::default(),
+        )
+        .is_none_or(|qual| qual.contains_heap_expr())
+    };
+
+    let leaky_heap_filter = restrict_info
+        .iter_ptr()
+        .any(|ri| (*ri).security_level > min_security_level && !(*ri).leakproof && needs_heap(ri));
+    if !leaky_heap_filter {
+        return true;
+    }
+
+    // a leaky filter is only safe if every restriction it could be evaluated ahead of is answered
+    // by the index
+    let max_security_level = restrict_info
+        .iter_ptr()
+        .map(|ri| (*ri).security_level)
+        .max()
+        .unwrap_or(min_security_level);
+    !restrict_info
+        .iter_ptr()
+        .any(|ri| (*ri).security_level < max_security_level && needs_heap(ri))
+}
+
 #[derive(Default)]
 pub struct QualExtractState {
     pub uses_tantivy_to_query: bool,
@@ -581,7 +707,7 @@ pub unsafe fn extract_quals(
                 }
 
                 if let Some(search_field) = schema.search_field(field.attname()) {
-                    if search_field.is_fast() {
+                    if search_field.is_fast() || search_field.field_entry().is_indexed() {
                         // This is an indexed boolean field, create proper pushdown qual
                         // T_Var alone represents "field = true"
                         state.uses_tantivy_to_query = true;
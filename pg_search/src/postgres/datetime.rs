@@ -17,9 +17,24 @@
 
 use chrono::{DateTime, NaiveDate};
 use pgrx::datum::datetime_support::DateTimeConversionError;
+use pgrx::datum::TimestampWithTimeZone;
+use pgrx::pg_sys;
 
 pub static MICROSECONDS_IN_SECOND: u32 = 1_000_000;
 
+/// Postgres timestamps count microseconds from 2000-01-01 rather than from the unix epoch
+pub const POSTGRES_EPOCH_UNIX_MICROS: i64 = 946_684_800_000_000;
+
+/// Convert the milliseconds-since-unix-epoch keys that tantivy's date aggregations return into a
+/// Postgres `timestamptz`
+pub fn unix_millis_to_timestamptz(millis: f64) -> Option<TimestampWithTimeZone> {
+    let micros = (millis * 1000.0) as i64;
+    TimestampWithTimeZone::try_from(
+        micros.checked_sub(POSTGRES_EPOCH_UNIX_MICROS)? as pg_sys::TimestampTz
+    )
+    .ok()
+}
+
 pub fn datetime_components_to_tantivy_date(
     ymd: Option<(i32, u8, u8)>,
     hms_micro: (u8, u8, u8, u32),
@@ -17,6 +17,7 @@
 
 use pgrx::{pg_sys::ItemPointerData, *};
 
+use crate::index::expiry::Expiry;
 use crate::index::fast_fields_helper::FFType;
 use crate::index::mvcc::MvccSatisfies;
 use crate::index::reader::index::SearchIndexReader;
@@ -48,9 +49,34 @@ pub unsafe extern "C-unwind" fn ambulkdelete(
         callback(&mut ctid, callback_state)
     };
 
+    bulk_delete(&index_relation, callback, || Ok(()))
+        .expect("ambulkdelete: should be able to delete documents");
+
+    if stats.is_null() {
+        stats = unsafe {
+            PgBox::from_pg(
+                pg_sys::palloc0(std::mem::size_of::<pg_sys::IndexBulkDeleteResult>()).cast(),
+            )
+        };
+        stats.pages_deleted = 0;
+    }
+
+    stats.into_pg()
+}
+
+/// Delete every document whose ctid `callback` returns true for, returning whether any were.
+///
+/// `before_delete` is called once the segments to delete from have been chosen and protected from
+/// concurrent merges, but before anything is deleted.  Segments it writes are left alone, and if it
+/// fails, nothing is deleted.
+pub unsafe fn bulk_delete(
+    index_relation: &PgSearchRelation,
+    callback: impl Fn(u64) -> bool,
+    before_delete: impl FnOnce() -> Result<()>,
+) -> Result<bool> {
     // first, we need an exclusive lock on the CLEANUP_LOCK.  Once we get it, we know that there
     // are no concurrent merges happening
-    let mut metadata = MetaPage::open(&index_relation);
+    let mut metadata = MetaPage::open(index_relation);
     let cleanup_lock = metadata.cleanup_lock_exclusive();
 
     // take the MergeLock
@@ -67,7 +93,7 @@ pub unsafe extern "C-unwind" fn ambulkdelete(
     );
     drop(cleanup_lock);
 
-    let reader = SearchIndexReader::empty(&index_relation, MvccSatisfies::Vacuum)
+    let reader = SearchIndexReader::empty(index_relation, MvccSatisfies::Vacuum)
         .expect("ambulkdelete: should be able to open a SearchIndexReader");
     let writer_segment_ids = reader.segment_ids();
 
@@ -79,6 +105,8 @@ pub unsafe extern "C-unwind" fn ambulkdelete(
     // It's important to drop the merge lock after the `vacuum_sentinel` is pinned
     drop(merge_lock);
 
+    before_delete()?;
+
     let mut did_delete = false;
     for segment_reader in reader.segment_readers() {
         let segment_id = segment_reader.segment_id();
@@ -90,7 +118,7 @@ pub unsafe extern "C-unwind" fn ambulkdelete(
             // need to concern ourselves with the ones the writer is aware of
             continue;
         }
-        let mut deleter = SegmentDeleter::open(&index_relation, segment_id)
+        let mut deleter = SegmentDeleter::open(index_relation, segment_id)
             .expect("ambulkdelete: should be able to open a SegmentDeleter");
         let ctid_ff = FFType::new_ctid(segment_reader.fast_fields());
         let mut needs_commit = false;
@@ -101,6 +129,12 @@ pub unsafe extern "C-unwind" fn ambulkdelete(
                 pg_sys::vacuum_delay_point();
             }
 
+            // `validate_index()` for `CREATE INDEX CONCURRENTLY` uses the callback to learn which
+            // ctids are already indexed, so it mustn't see the ones we've already deleted
+            if segment_reader.is_deleted(doc_id) {
+                continue;
+            }
+
             let ctid = ctid_ff.as_u64(doc_id).expect("ctid should be present");
             if callback(ctid) {
                 did_delete = true;
@@ -119,15 +153,6 @@ pub unsafe extern "C-unwind" fn ambulkdelete(
     // will get in the way of our CLEANUP_LOCK barrier below
     drop(reader);
 
-    if stats.is_null() {
-        stats = unsafe {
-            PgBox::from_pg(
-                pg_sys::palloc0(std::mem::size_of::<pg_sys::IndexBulkDeleteResult>()).cast(),
-            )
-        };
-        stats.pages_deleted = 0;
-    }
-
     // As soon as ambulkdelete returns, Postgres will update the visibility map
     // This can cause concurrent scans that have just read ctids, which are dead but
     // are about to be marked visible, to return wrong results. To guard against this,
@@ -142,7 +167,30 @@ pub unsafe extern "C-unwind" fn ambulkdelete(
 
     // we're done, no need to hold onto the sentinel any longer
     drop(vacuum_sentinel);
-    stats.into_pg()
+    Ok(did_delete)
+}
+
+/// Delete the documents that have outlived the index's `ttl`, returning whether any were.
+///
+/// Expired documents are already hidden from searches, but they aren't dead to Postgres, so no
+/// `ambulkdelete()` will ever ask for them to be deleted.
+pub unsafe fn delete_expired(index_relation: &PgSearchRelation) -> Result<bool> {
+    let schema = index_relation.schema()?;
+    let Some(expiry) = Expiry::for_index(index_relation, &schema) else {
+        return Ok(false);
+    };
+
+    // the reader pins the CLEANUP_LOCK, which `bulk_delete()` has to wait out, so it can't outlive
+    // this block
+    let expired = {
+        let reader = SearchIndexReader::empty(index_relation, MvccSatisfies::Vacuum)?;
+        expiry.expired_ctids(reader.searcher())?
+    };
+    if expired.is_empty() {
+        return Ok(false);
+    }
+
+    bulk_delete(index_relation, |ctid| expired.contains(&ctid), || Ok(()))
 }
 
 struct SegmentDeleter {
@@ -130,8 +130,8 @@ pub unsafe fn register() {
         let entry = EXECUTOR_RUN_STACK
             .pop()
             .expect("should have an ExecutorRuntimeState entry")?;
-        for (_, insert_state) in entry.active {
-            paradedb_aminsertcleanup(insert_state.writer);
+        for (_, mut insert_state) in entry.active {
+            paradedb_aminsertcleanup(&mut insert_state);
         }
         None
     }
@@ -0,0 +1,625 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! `tantivy_fdw`, a foreign data wrapper that makes an index directory written by tantivy outside
+//! of Postgres queryable as a foreign table.
+//!
+//! The columns of a foreign table are the stored fields of the index with the same names, plus a
+//! `query` column and a `score` column.  A `query = '<tantivy query>'` condition is searched for
+//! by the index rather than checked against the rows, and the `LIMIT` of a query that reads only
+//! the foreign table is the number of top-scoring documents the index collects.
+
+use crate::api::{ensure_role, AsCStr};
+use crate::postgres::types::TantivyValue;
+use anyhow::{anyhow, bail, Result};
+use pgrx::pg_sys::panic::ErrorReport;
+use pgrx::pg_sys::AsPgCStr;
+use pgrx::*;
+use serde::{Deserialize, Serialize};
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use tantivy::collector::TopDocs;
+use tantivy::directory::error::OpenDirectoryError;
+use tantivy::query::{AllQuery, BooleanQuery, Query, QueryParser};
+use tantivy::schema::{FieldType, OwnedValue, Schema};
+use tantivy::{
+    DocAddress, Index, IndexReader, ReloadPolicy, Score, Searcher, TantivyDocument, TantivyError,
+};
+
+const PATH_OPTION: &str = "path";
+const QUERY_COLUMN_OPTION: &str = "query_column";
+const SCORE_COLUMN_OPTION: &str = "score_column";
+
+#[pg_extern(sql = "
+CREATE FUNCTION tantivy_fdw_handler() RETURNS fdw_handler STRICT LANGUAGE c AS 'MODULE_PATHNAME', '@FUNCTION_NAME@';
+")]
+fn tantivy_fdw_handler(_fcinfo: pg_sys::FunctionCallInfo) -> PgBox<pg_sys::FdwRoutine> {
+    let mut routine =
+        unsafe { PgBox::<pg_sys::FdwRoutine>::alloc_node(pg_sys::NodeTag::T_FdwRoutine) };
+
+    routine.GetForeignRelSize = Some(get_foreign_rel_size);
+    routine.GetForeignPaths = Some(get_foreign_paths);
+    routine.GetForeignPlan = Some(get_foreign_plan);
+    routine.ExplainForeignScan = Some(explain_foreign_scan);
+    routine.BeginForeignScan = Some(begin_foreign_scan);
+    routine.IterateForeignScan = Some(iterate_foreign_scan);
+    routine.ReScanForeignScan = Some(rescan_foreign_scan);
+    routine.EndForeignScan = Some(end_foreign_scan);
+
+    routine.into_pg_boxed()
+}
+
+/// Checks the options of the wrapper's servers, user mappings and foreign tables, of which only
+/// the tables have any.
+///
+/// Like `file_fdw`'s `filename`, a table's `path` lets whoever can read the table read files on
+/// the database server, so only roles that could read them anyway may set it.
+#[pg_extern]
+fn tantivy_fdw_validator(options: Vec<String>, catalog: pg_sys::Oid) -> Result<()> {
+    let options = options
+        .iter()
+        .map(|option| {
+            option
+                .split_once('=')
+                .ok_or_else(|| anyhow!("`{option}` is not an option"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if catalog == pg_sys::Oid::from(pg_sys::ForeignTableRelationId) {
+        if options.iter().any(|(name, _)| *name == PATH_OPTION) {
+            ensure_role("pg_read_server_files")?;
+        }
+        TableOptions::new(options.into_iter())?;
+    } else if let Some((name, _)) = options.first() {
+        bail!("`{name}` is not an option of `tantivy_fdw` here, only of its foreign tables");
+    }
+    Ok(())
+}
+
+extension_sql!(
+    r#"
+CREATE FOREIGN DATA WRAPPER tantivy_fdw HANDLER tantivy_fdw_handler VALIDATOR tantivy_fdw_validator;
+"#,
+    name = "tantivy_fdw",
+    requires = [tantivy_fdw_handler, tantivy_fdw_validator]
+);
+
+/// The options of a foreign table.
+struct TableOptions {
+    path: String,
+    query_column: String,
+    score_column: String,
+}
+
+impl TableOptions {
+    fn new<'a>(options: impl Iterator<Item = (&'a str, &'a str)>) -> Result<Self> {
+        let mut path = None;
+        let mut query_column = "query".to_string();
+        let mut score_column = "score".to_string();
+        for (name, value) in options {
+            match name {
+                PATH_OPTION => path = Some(value.to_string()),
+                QUERY_COLUMN_OPTION => query_column = value.to_string(),
+                SCORE_COLUMN_OPTION => score_column = value.to_string(),
+                other => bail!("`{other}` is not an option of a `tantivy_fdw` foreign table"),
+            }
+        }
+        let path = path.ok_or_else(|| anyhow!("the `{PATH_OPTION}` option is required"))?;
+        if !Path::new(&path).is_absolute() {
+            bail!("`{path}` is not an absolute path");
+        }
+        Ok(Self {
+            path,
+            query_column,
+            score_column,
+        })
+    }
+
+    unsafe fn of_table(relid: pg_sys::Oid) -> Self {
+        let table = pg_sys::GetForeignTable(relid);
+        let options = PgList::<pg_sys::DefElem>::from_pg((*table).options)
+            .iter_ptr()
+            .map(|def| {
+                (
+                    CStr::from_ptr((*def).defname)
+                        .to_string_lossy()
+                        .into_owned(),
+                    CStr::from_ptr(pg_sys::defGetString(def))
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            })
+            .collect::<Vec<_>>();
+        Self::new(
+            options
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        )
+        .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    fn open_index(&self) -> Index {
+        Index::open_in_dir(&self.path).unwrap_or_else(|err| {
+            let code = match &err {
+                TantivyError::OpenDirectoryError(
+                    OpenDirectoryError::DoesNotExist(_) | OpenDirectoryError::NotADirectory(_),
+                ) => PgSqlErrorCode::ERRCODE_UNDEFINED_FILE,
+                _ => PgSqlErrorCode::ERRCODE_FDW_ERROR,
+            };
+            ErrorReport::new(
+                code,
+                format!("could not open the tantivy index at `{}`", self.path),
+                function_name!(),
+            )
+            .set_detail(err.to_string())
+            .report(PgLogLevel::ERROR);
+            unreachable!("an ERROR should not return")
+        })
+    }
+}
+
+/// What the planner decided the index will do for a scan.
+#[derive(Default, Serialize, Deserialize)]
+struct ScanPrivate {
+    /// The query strings that every document must match.
+    queries: Vec<String>,
+    /// How many of the top-scoring documents to return, if not all of them.
+    limit: Option<usize>,
+}
+
+impl From<*mut pg_sys::List> for ScanPrivate {
+    fn from(list: *mut pg_sys::List) -> Self {
+        unsafe {
+            let list = PgList::<pg_sys::Node>::from_pg(list);
+            let content = list
+                .get_ptr(0)
+                .and_then(|node| node.as_c_str())
+                .expect("foreign scan should have its private data")
+                .to_str()
+                .expect("string node should be valid utf8");
+            serde_json::from_str(content).unwrap()
+        }
+    }
+}
+
+impl From<ScanPrivate> for *mut pg_sys::List {
+    fn from(value: ScanPrivate) -> Self {
+        let content = serde_json::to_string(&value).unwrap();
+        unsafe {
+            let mut list = PgList::new();
+            list.push(pg_sys::makeString(content.as_pg_cstr()).cast::<pg_sys::Node>());
+            list.into_pg()
+        }
+    }
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn get_foreign_rel_size(
+    root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+    foreigntableid: pg_sys::Oid,
+) {
+    let index = TableOptions::of_table(foreigntableid).open_index();
+    let num_docs = index
+        .load_metas()
+        .map(|metas| {
+            metas
+                .segments
+                .iter()
+                .map(|segment| segment.num_docs())
+                .sum::<u32>()
+        })
+        .unwrap_or_default();
+    let selectivity = pg_sys::clauselist_selectivity(
+        root,
+        (*baserel).baserestrictinfo,
+        0,
+        pg_sys::JoinType::JOIN_INNER,
+        std::ptr::null_mut(),
+    );
+    (*baserel).rows = (num_docs as f64 * selectivity).max(1.0);
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn get_foreign_paths(
+    root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+    _foreigntableid: pg_sys::Oid,
+) {
+    // opening the index's files is the same for every scan, and then each document costs about
+    // as much to fetch as a row would
+    let startup_cost = 10.0;
+    let total_cost = startup_cost + (*baserel).rows;
+
+    #[cfg(not(feature = "pg17"))]
+    let path = pg_sys::create_foreignscan_path(
+        root,
+        baserel,
+        std::ptr::null_mut(),
+        (*baserel).rows,
+        startup_cost,
+        total_cost,
+        std::ptr::null_mut(),
+        std::ptr::null_mut(),
+        std::ptr::null_mut(),
+        std::ptr::null_mut(),
+    );
+    #[cfg(feature = "pg17")]
+    let path = pg_sys::create_foreignscan_path(
+        root,
+        baserel,
+        std::ptr::null_mut(),
+        (*baserel).rows,
+        startup_cost,
+        total_cost,
+        std::ptr::null_mut(),
+        std::ptr::null_mut(),
+        std::ptr::null_mut(),
+        std::ptr::null_mut(),
+        std::ptr::null_mut(),
+    );
+    pg_sys::add_path(baserel, path.cast());
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn get_foreign_plan(
+    root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+    foreigntableid: pg_sys::Oid,
+    _best_path: *mut pg_sys::ForeignPath,
+    tlist: *mut pg_sys::List,
+    scan_clauses: *mut pg_sys::List,
+    outer_plan: *mut pg_sys::Plan,
+) -> *mut pg_sys::ForeignScan {
+    let options = TableOptions::of_table(foreigntableid);
+    let query_column = CString::new(options.query_column).unwrap();
+    let query_attno = pg_sys::get_attnum(foreigntableid, query_column.as_ptr());
+
+    let mut private = ScanPrivate::default();
+    let mut local_clauses = PgList::<pg_sys::Node>::new();
+    for restrictinfo in PgList::<pg_sys::RestrictInfo>::from_pg(scan_clauses).iter_ptr() {
+        if (*restrictinfo).pseudoconstant {
+            continue;
+        }
+        let clause = (*restrictinfo).clause.cast::<pg_sys::Node>();
+        match pushdown_query(clause, (*baserel).relid, query_attno) {
+            Some(query) => private.queries.push(query),
+            None => local_clauses.push(clause),
+        }
+    }
+
+    // the index returns its top documents, which are the first rows of the query if nothing else
+    // filters, joins, groups or sorts them
+    #[cfg(any(feature = "pg14", feature = "pg15"))]
+    let baserels = (*root).all_baserels;
+    #[cfg(any(feature = "pg16", feature = "pg17"))]
+    let baserels = (*root).all_query_rels;
+    if (*root).limit_tuples > -1.0
+        && local_clauses.is_empty()
+        && (*(*root).parse).sortClause.is_null()
+        && pg_sys::bms_equal((*baserel).relids, baserels)
+    {
+        private.limit = Some((*root).limit_tuples as usize);
+    }
+
+    pg_sys::make_foreignscan(
+        tlist,
+        local_clauses.into_pg(),
+        (*baserel).relid,
+        std::ptr::null_mut(),
+        private.into(),
+        std::ptr::null_mut(),
+        std::ptr::null_mut(),
+        outer_plan,
+    )
+}
+
+/// The query string of a `<query column> = '<query>'` condition.
+unsafe fn pushdown_query(
+    clause: *mut pg_sys::Node,
+    varno: pg_sys::Index,
+    query_attno: pg_sys::AttrNumber,
+) -> Option<String> {
+    let opexpr = nodecast!(OpExpr, T_OpExpr, clause)?;
+    if (*opexpr).opno != pg_sys::Oid::from(pg_sys::TextEqualOperator) {
+        return None;
+    }
+    let args = PgList::<pg_sys::Node>::from_pg((*opexpr).args);
+    let (lhs, rhs) = (args.get_ptr(0)?, args.get_ptr(1)?);
+    let (var, const_) = match (nodecast!(Var, T_Var, lhs), nodecast!(Const, T_Const, rhs)) {
+        (Some(var), Some(const_)) => (var, const_),
+        _ => (nodecast!(Var, T_Var, rhs)?, nodecast!(Const, T_Const, lhs)?),
+    };
+    if (*var).varno as pg_sys::Index != varno || (*var).varattno != query_attno {
+        return None;
+    }
+    String::from_datum((*const_).constvalue, (*const_).constisnull)
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn explain_foreign_scan(
+    node: *mut pg_sys::ForeignScanState,
+    es: *mut pg_sys::ExplainState,
+) {
+    let plan = (*node).ss.ps.plan.cast::<pg_sys::ForeignScan>();
+    let private = ScanPrivate::from((*plan).fdw_private);
+    let options = TableOptions::of_table((*(*node).ss.ss_currentRelation).rd_id);
+
+    pg_sys::ExplainPropertyText(c"Index Path".as_ptr(), options.path.as_pg_cstr(), es);
+    if !private.queries.is_empty() {
+        pg_sys::ExplainPropertyText(
+            c"Tantivy Query".as_ptr(),
+            private.queries.join(" AND ").as_pg_cstr(),
+            es,
+        );
+    }
+    if let Some(limit) = private.limit {
+        pg_sys::ExplainPropertyInteger(c"Limit".as_ptr(), std::ptr::null(), limit as i64, es);
+    }
+}
+
+/// Where the values of an attribute of the foreign table come from.
+enum ColumnSource {
+    Dropped,
+    Query(pg_sys::Oid),
+    Score(pg_sys::Oid),
+    Field { name: String, typid: pg_sys::Oid },
+}
+
+struct ScanState {
+    searcher: Searcher,
+    schema: Schema,
+    query: Box<dyn Query>,
+    query_string: Option<String>,
+    limit: Option<usize>,
+    columns: Vec<ColumnSource>,
+    results: Option<std::vec::IntoIter<(Score, DocAddress)>>,
+}
+
+impl ScanState {
+    fn search(&self) -> Vec<(Score, DocAddress)> {
+        let limit = self
+            .limit
+            .unwrap_or(self.searcher.num_docs() as usize)
+            .max(1);
+        self.searcher
+            .search(&self.query, &TopDocs::with_limit(limit))
+            .unwrap_or_else(|err| panic!("could not search the tantivy index: {err}"))
+    }
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn begin_foreign_scan(node: *mut pg_sys::ForeignScanState, eflags: i32) {
+    if eflags & pg_sys::EXEC_FLAG_EXPLAIN_ONLY as i32 != 0 {
+        return;
+    }
+
+    let plan = (*node).ss.ps.plan.cast::<pg_sys::ForeignScan>();
+    let private = ScanPrivate::from((*plan).fdw_private);
+    let relation = (*node).ss.ss_currentRelation;
+    let options = TableOptions::of_table((*relation).rd_id);
+    let index = options.open_index();
+    let schema = index.schema();
+
+    // the query strings are parsed the way that tantivy's own query parser does, searching every
+    // indexed text and JSON field for terms without a field
+    let default_fields = schema
+        .fields()
+        .filter(|(_, entry)| {
+            entry.is_indexed()
+                && matches!(
+                    entry.field_type(),
+                    FieldType::Str(_) | FieldType::JsonObject(_)
+                )
+        })
+        .map(|(field, _)| field)
+        .collect::<Vec<_>>();
+    let parser = QueryParser::for_index(&index, default_fields);
+    let mut queries = private
+        .queries
+        .iter()
+        .map(|query| {
+            parser
+                .parse_query(query)
+                .unwrap_or_else(|err| panic!("could not parse `{query}`: {err}"))
+        })
+        .collect::<Vec<_>>();
+    let query: Box<dyn Query> = match queries.len() {
+        0 => Box::new(AllQuery),
+        1 => queries.pop().unwrap(),
+        _ => Box::new(BooleanQuery::intersection(queries)),
+    };
+
+    let tupdesc = PgTupleDesc::from_pg_unchecked((*relation).rd_att);
+    let columns = tupdesc
+        .iter()
+        .map(|att| {
+            let name = att.name();
+            let typid = att.type_oid().value();
+            if att.is_dropped() {
+                ColumnSource::Dropped
+            } else if name == options.query_column {
+                ColumnSource::Query(typid)
+            } else if name == options.score_column {
+                ColumnSource::Score(typid)
+            } else {
+                ColumnSource::Field {
+                    name: name.to_string(),
+                    typid,
+                }
+            }
+        })
+        .collect();
+
+    let reader: IndexReader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()
+        .unwrap_or_else(|err| {
+            panic!(
+                "could not read the tantivy index at `{}`: {err}",
+                options.path
+            )
+        });
+    let state = ScanState {
+        searcher: reader.searcher(),
+        schema,
+        query,
+        query_string: (!private.queries.is_empty()).then(|| private.queries.join(" AND ")),
+        limit: private.limit,
+        columns,
+        results: None,
+    };
+    (*node).fdw_state = PgMemoryContexts::CurrentMemoryContext
+        .leak_and_drop_on_delete(state)
+        .cast();
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn iterate_foreign_scan(
+    node: *mut pg_sys::ForeignScanState,
+) -> *mut pg_sys::TupleTableSlot {
+    let state = &mut *(*node).fdw_state.cast::<ScanState>();
+    let slot = (*node).ss.ss_ScanTupleSlot;
+    pg_sys::ExecClearTuple(slot);
+
+    if state.results.is_none() {
+        state.results = Some(state.search().into_iter());
+    }
+    let Some((score, doc_address)) = state.results.as_mut().unwrap().next() else {
+        return slot;
+    };
+    let document = state
+        .searcher
+        .doc::<TantivyDocument>(doc_address)
+        .unwrap_or_else(|err| panic!("could not read a document of the tantivy index: {err}"))
+        .to_named_doc(&state.schema);
+
+    let natts = (*(*slot).tts_tupleDescriptor).natts as usize;
+    let values = std::slice::from_raw_parts_mut((*slot).tts_values, natts);
+    let isnull = std::slice::from_raw_parts_mut((*slot).tts_isnull, natts);
+    for (i, column) in state.columns.iter().enumerate() {
+        let datum = match column {
+            ColumnSource::Dropped => None,
+            ColumnSource::Query(typid) => state
+                .query_string
+                .as_ref()
+                .and_then(|query| value_to_datum(&OwnedValue::Str(query.clone()), *typid)),
+            ColumnSource::Score(typid) => value_to_datum(&OwnedValue::F64(score as f64), *typid),
+            ColumnSource::Field { name, typid } => document
+                .0
+                .get(name)
+                .and_then(|values| values_to_datum(values, *typid)),
+        };
+        values[i] = datum.unwrap_or(pg_sys::Datum::from(0));
+        isnull[i] = datum.is_none();
+    }
+    pg_sys::ExecStoreVirtualTuple(slot);
+    slot
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn rescan_foreign_scan(node: *mut pg_sys::ForeignScanState) {
+    let state = &mut *(*node).fdw_state.cast::<ScanState>();
+    state.results = None;
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn end_foreign_scan(_node: *mut pg_sys::ForeignScanState) {
+    // the state is dropped along with the executor's memory context
+}
+
+/// The values of a field of a document as a datum of the column type `typid`.  An array column
+/// holds all of them, and any other column the first.
+unsafe fn values_to_datum(values: &[OwnedValue], typid: pg_sys::Oid) -> Option<pg_sys::Datum> {
+    if values.is_empty() {
+        return None;
+    }
+    if typid == pg_sys::JSONOID || typid == pg_sys::JSONBOID {
+        let json = match values {
+            [value] => serde_json::to_string(value),
+            values => serde_json::to_string(values),
+        }
+        .expect("a tantivy value should serialize to JSON");
+        return Some(input_datum(&json, typid));
+    }
+
+    let elemtype = pg_sys::get_element_type(typid);
+    if elemtype == pg_sys::InvalidOid {
+        return value_to_datum(&values[0], typid);
+    }
+    let mut elems = values
+        .iter()
+        .filter_map(|value| value_to_datum(value, elemtype))
+        .collect::<Vec<_>>();
+    let mut typlen = 0;
+    let mut typbyval = false;
+    let mut typalign = 0;
+    pg_sys::get_typlenbyvalalign(elemtype, &mut typlen, &mut typbyval, &mut typalign);
+    let array = pg_sys::construct_array(
+        elems.as_mut_ptr(),
+        elems.len() as _,
+        elemtype,
+        typlen as _,
+        typbyval,
+        typalign,
+    );
+    Some(pg_sys::Datum::from(array))
+}
+
+/// A value as a datum of type `typid`, converted the way the index's own values are or parsed
+/// from its text if it's of another type than the column's.
+unsafe fn value_to_datum(value: &OwnedValue, typid: pg_sys::Oid) -> Option<pg_sys::Datum> {
+    if let Ok(datum) = TantivyValue(value.clone()).try_into_datum(PgOid::from(typid)) {
+        return datum;
+    }
+    let text = match value {
+        OwnedValue::Null => return None,
+        OwnedValue::Str(string) => string.clone(),
+        OwnedValue::PreTokStr(pretokenized) => pretokenized.text.clone(),
+        OwnedValue::U64(u64) => u64.to_string(),
+        OwnedValue::I64(i64) => i64.to_string(),
+        OwnedValue::F64(f64) => f64.to_string(),
+        OwnedValue::Bool(bool) => bool.to_string(),
+        OwnedValue::Date(datetime) => datetime.into_primitive().to_string(),
+        OwnedValue::Facet(facet) => facet.to_path_string(),
+        OwnedValue::Bytes(bytes) => format!(
+            "\\x{}",
+            bytes
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+        ),
+        OwnedValue::IpAddr(addr) => match addr.to_ipv4_mapped() {
+            Some(ipv4) => ipv4.to_string(),
+            None => addr.to_string(),
+        },
+        OwnedValue::Array(_) | OwnedValue::Object(_) => {
+            serde_json::to_string(value).expect("a tantivy value should serialize to JSON")
+        }
+    };
+    Some(input_datum(&text, typid))
+}
+
+/// The datum of type `typid` whose text is `text`.
+unsafe fn input_datum(text: &str, typid: pg_sys::Oid) -> pg_sys::Datum {
+    let mut input = pg_sys::InvalidOid;
+    let mut ioparam = pg_sys::InvalidOid;
+    pg_sys::getTypeInputInfo(typid, &mut input, &mut ioparam);
+    let text = CString::new(text).expect("a tantivy value should not contain a NUL byte");
+    pg_sys::OidInputFunctionCall(input, text.as_ptr() as *mut _, ioparam, -1)
+}
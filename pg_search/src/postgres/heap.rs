@@ -0,0 +1,114 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Reads a single column of heap tuples, identified by ctid.
+
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::utils;
+use anyhow::{anyhow, Result};
+use pgrx::pg_sys;
+
+/// Reads one column of the heap tuples identified by ctid, skipping tuples that aren't visible
+/// to the snapshot.
+pub struct HeapColumnReader {
+    scan: *mut pg_sys::IndexFetchTableData,
+    slot: *mut pg_sys::TupleTableSlot,
+    snapshot: pg_sys::Snapshot,
+    attno: i32,
+    typoid: pg_sys::Oid,
+    tid: pg_sys::ItemPointerData,
+}
+
+impl HeapColumnReader {
+    pub fn new(
+        heaprel: &PgSearchRelation,
+        column: &str,
+        snapshot: pg_sys::Snapshot,
+    ) -> Result<Self> {
+        let tupdesc = heaprel.tuple_desc();
+        let (attno, attribute) = tupdesc
+            .iter()
+            .enumerate()
+            .find(|(_, attribute)| !attribute.is_dropped() && attribute.name() == column)
+            .ok_or_else(|| anyhow!("column `{column}` does not exist"))?;
+        let typoid = attribute.type_oid().value();
+
+        unsafe {
+            Ok(Self {
+                scan: pg_sys::table_index_fetch_begin(heaprel.as_ptr()),
+                slot: pg_sys::MakeSingleTupleTableSlot(
+                    (*heaprel.as_ptr()).rd_att,
+                    &pg_sys::TTSOpsBufferHeapTuple,
+                ),
+                snapshot,
+                attno: attno as i32 + 1,
+                typoid,
+                tid: pg_sys::ItemPointerData::default(),
+            })
+        }
+    }
+
+    /// The type of the column.
+    pub fn typoid(&self) -> pg_sys::Oid {
+        self.typoid
+    }
+
+    /// Convert the column's value in the tuple identified by `ctid` with `convert`, which is
+    /// given the datum and whether it's null.  Returns [`None`] if the tuple isn't visible.
+    pub fn read<T>(
+        &mut self,
+        ctid: u64,
+        convert: impl FnOnce(pg_sys::Datum, bool) -> Option<T>,
+    ) -> Option<T> {
+        unsafe {
+            utils::u64_to_item_pointer(ctid, &mut self.tid);
+            let mut call_again = false;
+            let mut all_dead = false;
+            pg_sys::ExecClearTuple(self.slot);
+            if !pg_sys::table_index_fetch_tuple(
+                self.scan,
+                &mut self.tid,
+                self.snapshot,
+                self.slot,
+                &mut call_again,
+                &mut all_dead,
+            ) {
+                return None;
+            }
+
+            pg_sys::slot_getsomeattrs_int(self.slot, self.attno);
+            let index = (self.attno - 1) as usize;
+            convert(
+                *(*self.slot).tts_values.add(index),
+                *(*self.slot).tts_isnull.add(index),
+            )
+        }
+    }
+}
+
+impl Drop for HeapColumnReader {
+    fn drop(&mut self) {
+        unsafe {
+            if !pg_sys::IsTransactionState() {
+                // we are not in a transaction, so we can't do things like release buffers and close relations
+                return;
+            }
+            pg_sys::ExecDropSingleTupleTableSlot(self.slot);
+            pg_sys::table_index_fetch_end(self.scan);
+        }
+    }
+}
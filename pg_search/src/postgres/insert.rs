@@ -15,7 +15,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use crate::api::FieldName;
+use crate::api::{FieldName, HashMap};
 use crate::gucs;
 use crate::index::mvcc::MvccSatisfies;
 use crate::index::writer::index::{IndexWriterConfig, SerialIndexWriter};
@@ -24,33 +24,52 @@ use crate::postgres::rel::PgSearchRelation;
 use crate::postgres::utils::{item_pointer_to_u64, row_to_search_document};
 use crate::schema::{CategorizedFieldData, SearchField};
 use pgrx::{pg_guard, pg_sys, PgMemoryContexts};
-use std::panic::{catch_unwind, resume_unwind};
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::Entry;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
 use tantivy::TantivyDocument;
 
+thread_local! {
+    /// Writers kept open between the statements of a transaction by `paradedb.bulk_load_mode`,
+    /// which are committed along with the transaction.  [`None`] until the first is opened.
+    static BULK_LOAD_WRITERS: RefCell<Option<HashMap<pg_sys::Oid, SerialIndexWriter>>> =
+        const { RefCell::new(None) };
+
+    /// Set while `paradedb.bulk_index()` loads a file, whose inserts are written like those of
+    /// `paradedb.bulk_load_mode`.
+    static BULK_INDEXING: Cell<bool> = const { Cell::new(false) };
+}
+
 pub struct InsertState {
-    #[allow(dead_code)] // field is used by pg<16 for the fakeaminsertcleanup stuff
     pub indexrelid: pg_sys::Oid,
+    /// [`None`] in `paradedb.bulk_load_mode`, where the transaction's writer is written to instead
     pub writer: Option<SerialIndexWriter>,
     categorized_fields: Vec<(SearchField, CategorizedFieldData)>,
     key_field_name: FieldName,
     per_row_context: PgMemoryContexts,
+    /// The documents of the statement's rows that haven't been given to the writer yet, up to
+    /// `paradedb.insert_batch_size` of them
+    batch: Vec<(TantivyDocument, u64)>,
+    batch_size: usize,
 }
 
 impl InsertState {
     unsafe fn new(indexrel: &PgSearchRelation) -> anyhow::Result<Self> {
-        let config = IndexWriterConfig {
-            memory_budget: gucs::adjust_work_mem(),
-            max_docs_per_segment: None,
-        };
-        let writer = SerialIndexWriter::with_mvcc(
-            indexrel,
-            MvccSatisfies::Mergeable,
-            config,
-            Default::default(),
-        )?;
-        let schema = writer.schema();
-        let categorized_fields = schema.categorized_fields().clone();
-        let key_field_name = schema.key_field_name();
+        let (writer, categorized_fields, key_field_name) =
+            if gucs::bulk_load_mode() || BULK_INDEXING.get() {
+                let (categorized_fields, key_field_name) =
+                    with_bulk_load_writer(indexrel.oid(), |writer| {
+                        let schema = writer.schema();
+                        (schema.categorized_fields().clone(), schema.key_field_name())
+                    })?;
+                (None, categorized_fields, key_field_name)
+            } else {
+                let writer = open_writer(indexrel)?;
+                let schema = writer.schema();
+                let categorized_fields = schema.categorized_fields().clone();
+                let key_field_name = schema.key_field_name();
+                (Some(writer), categorized_fields, key_field_name)
+            };
 
         let per_row_context = pg_sys::AllocSetContextCreateExtended(
             PgMemoryContexts::CurrentMemoryContext.value(),
@@ -62,12 +81,63 @@ impl InsertState {
 
         Ok(Self {
             indexrelid: indexrel.oid(),
-            writer: Some(writer),
+            writer,
             categorized_fields,
             key_field_name,
             per_row_context: PgMemoryContexts::For(per_row_context),
+            batch: Vec::new(),
+            batch_size: gucs::insert_batch_size(),
         })
     }
+
+    /// Give the batched documents to the writer, or to the transaction's bulk load writer.
+    unsafe fn flush_batch(&mut self) {
+        if self.batch.is_empty() {
+            return;
+        }
+        self.per_row_context.switch_to(|cxt| {
+            let documents = self.batch.drain(..);
+            let insert = |writer: &mut SerialIndexWriter| {
+                writer
+                    .insert_batch(documents)
+                    .expect("insertion into index should succeed");
+            };
+            match self.writer.as_mut() {
+                Some(writer) => insert(writer),
+                None => with_bulk_load_writer(self.indexrelid, insert)
+                    .expect("should be able to open the bulk load writer"),
+            }
+            cxt.reset();
+        });
+    }
+}
+
+fn open_writer(indexrel: &PgSearchRelation) -> anyhow::Result<SerialIndexWriter> {
+    // a bulk index is written in segments as large as those of a `CREATE INDEX`
+    let memory_budget = if BULK_INDEXING.get() {
+        gucs::adjust_maintenance_work_mem(1)
+    } else {
+        gucs::adjust_insert_work_mem(indexrel.options().insert_work_mem())
+    };
+    let config = IndexWriterConfig {
+        memory_budget,
+        max_docs_per_segment: None,
+    };
+    SerialIndexWriter::with_mvcc(
+        indexrel,
+        MvccSatisfies::Mergeable,
+        config,
+        Default::default(),
+    )
+}
+
+/// Open `oid` with a reference owned by the transaction rather than the current statement.
+unsafe fn open_for_transaction(oid: pg_sys::Oid) -> PgSearchRelation {
+    let owner = pg_sys::CurrentResourceOwner;
+    pg_sys::CurrentResourceOwner = pg_sys::TopTransactionResourceOwner;
+    let indexrel = PgSearchRelation::open(oid);
+    pg_sys::CurrentResourceOwner = owner;
+    indexrel
 }
 
 #[cfg(not(feature = "pg17"))]
@@ -138,11 +208,9 @@ pub unsafe extern "C-unwind" fn aminsert(
                 .expect("index_info argument must not be null"),
         );
 
-        state.per_row_context.switch_to(|cxt| {
+        let search_document = state.per_row_context.switch_to(|cxt| {
             let categorized_fields = &state.categorized_fields;
             let key_field_name = &state.key_field_name;
-            let writer = state.writer.as_mut().expect("writer should not be null");
-
             let mut search_document = TantivyDocument::new();
 
             row_to_search_document(
@@ -153,13 +221,20 @@ pub unsafe extern "C-unwind" fn aminsert(
                 &mut search_document,
             )
             .unwrap_or_else(|err| panic!("{err}"));
-            writer
-                .insert(search_document, item_pointer_to_u64(*ctid), || {})
-                .expect("insertion into index should succeed");
 
             cxt.reset();
-            true
-        })
+            search_document
+        });
+
+        // the rows of a `COPY` or a multi-row `INSERT` are written to the segment a batch at a
+        // time, and whatever is left of the last batch at the end of the statement
+        state
+            .batch
+            .push((search_document, item_pointer_to_u64(*ctid)));
+        if state.batch.len() >= state.batch_size {
+            state.flush_batch();
+        }
+        true
     });
 
     match result {
@@ -179,39 +254,88 @@ pub unsafe extern "C-unwind" fn aminsertcleanup(
         return;
     }
 
-    paradedb_aminsertcleanup(state.as_mut().and_then(|state| state.writer.take()));
+    paradedb_aminsertcleanup(&mut *state);
 }
 
-pub fn paradedb_aminsertcleanup(mut writer: Option<SerialIndexWriter>) {
-    if let Some(writer) = writer.take() {
-        if let Some((_, indexrel)) = writer
-            .commit()
-            .expect("must be able to commit inserts in paradedb_aminsertcleanup")
-        {
-            /*
-             * Recompute VACUUM XID boundaries.
-             *
-             * We don't actually care about the oldest non-removable XID.  Computing
-             * the oldest such XID has a useful side-effect that we rely on: it
-             * forcibly updates the XID horizon state for this backend.  This step is
-             * essential; GlobalVisCheckRemovableFullXid() will not reliably recognize
-             * that it is now safe to recycle newly deleted pages without this step.
-             */
-            unsafe {
-                let heaprel = indexrel
-                    .heap_relation()
-                    .expect("index should belong to a heap relation");
-                pg_sys::GetOldestNonRemovableTransactionId(heaprel.as_ptr());
-            }
+pub unsafe fn paradedb_aminsertcleanup(state: &mut InsertState) {
+    state.flush_batch();
+    if let Some(writer) = state.writer.take() {
+        commit_writer(writer);
+    }
+}
 
-            unsafe {
-                do_merge(
-                    &indexrel,
-                    MergeStyle::Insert,
-                    Some(pg_sys::GetCurrentTransactionId()),
-                )
-                .expect("should be able to merge");
+/// Call `f` with the rows it inserts written to this transaction's bulk load writers, which are
+/// opened with `maintenance_work_mem` if they aren't open already.
+pub fn with_bulk_indexing<R>(f: impl FnOnce() -> R) -> R {
+    BULK_INDEXING.set(true);
+    let result = catch_unwind(AssertUnwindSafe(f));
+    BULK_INDEXING.set(false);
+    result.unwrap_or_else(|e| resume_unwind(e))
+}
+
+/// Call `f` with this transaction's bulk load writer for `indexrelid`, opening it if this is the
+/// first statement to write to the index.  The writer is kept open across statements, and even
+/// when one of them fails, so that the documents it has buffered are committed along with the
+/// transaction.
+unsafe fn with_bulk_load_writer<R>(
+    indexrelid: pg_sys::Oid,
+    f: impl FnOnce(&mut SerialIndexWriter) -> R,
+) -> anyhow::Result<R> {
+    BULK_LOAD_WRITERS.with(|writers| {
+        let mut writers = writers.borrow_mut();
+        let writers = writers.get_or_insert_with(|| {
+            pgrx::register_xact_callback(pgrx::PgXactCallbackEvent::PreCommit, || {
+                let writers = BULK_LOAD_WRITERS.with(|writers| writers.take());
+                for (_, writer) in writers.into_iter().flatten() {
+                    commit_writer(writer);
+                }
+            });
+            pgrx::register_xact_callback(pgrx::PgXactCallbackEvent::Abort, || {
+                BULK_LOAD_WRITERS.with(|writers| writers.take());
+            });
+            Default::default()
+        });
+        let writer = match writers.entry(indexrelid) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                // the writer outlives this statement, so it needs a reference to the relation
+                // that does too
+                let indexrel = open_for_transaction(indexrelid);
+                entry.insert(open_writer(&indexrel)?)
             }
+        };
+        Ok(f(writer))
+    })
+}
+
+fn commit_writer(writer: SerialIndexWriter) {
+    if let Some((_, indexrel)) = writer
+        .commit()
+        .expect("must be able to commit inserts in paradedb_aminsertcleanup")
+    {
+        /*
+         * Recompute VACUUM XID boundaries.
+         *
+         * We don't actually care about the oldest non-removable XID.  Computing
+         * the oldest such XID has a useful side-effect that we rely on: it
+         * forcibly updates the XID horizon state for this backend.  This step is
+         * essential; GlobalVisCheckRemovableFullXid() will not reliably recognize
+         * that it is now safe to recycle newly deleted pages without this step.
+         */
+        unsafe {
+            let heaprel = indexrel
+                .heap_relation()
+                .expect("index should belong to a heap relation");
+            pg_sys::GetOldestNonRemovableTransactionId(heaprel.as_ptr());
+        }
+
+        unsafe {
+            do_merge(
+                &indexrel,
+                MergeStyle::Insert,
+                Some(pg_sys::GetCurrentTransactionId()),
+            )
+            .expect("should be able to merge");
         }
     }
 }
@@ -15,14 +15,17 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use crate::index::merge_policy::LayeredMergePolicy;
+use crate::gucs;
+use crate::index::merge_policy::{force_merge_candidates, LayeredMergePolicy};
 use crate::index::mvcc::MvccSatisfies;
 use crate::index::writer::index::{Mergeable, SearchIndexMerger};
 use crate::postgres::ps_status::{set_ps_display_suffix, MERGING};
+use crate::postgres::stats;
 use crate::postgres::storage::block::SegmentMetaEntry;
 use crate::postgres::storage::buffer::{Buffer, BufferManager};
 use crate::postgres::storage::merge::MergeLock;
 use crate::postgres::storage::metadata::MetaPage;
+use crate::postgres::storage::throttle::WriteThrottle;
 use crate::postgres::storage::LinkedBytesList;
 use crate::postgres::PgSearchRelation;
 
@@ -30,7 +33,9 @@ use pgrx::bgworkers::*;
 use pgrx::{check_for_interrupts, pg_sys};
 use pgrx::{pg_guard, FromDatum, IntoDatum};
 use std::ffi::CStr;
-use tantivy::index::SegmentMeta;
+use std::time::{Duration, Instant};
+use tantivy::index::{SegmentId, SegmentMeta};
+use tantivy::indexer::MergeCandidate;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -111,6 +116,8 @@ impl FromDatum for BackgroundMergeArgs {
 struct IndexLayerSizes {
     foreground_layer_sizes: Vec<u64>,
     background_layer_sizes: Vec<u64>,
+    min_merge_count: usize,
+    max_merge_count: Option<usize>,
 }
 
 impl From<&PgSearchRelation> for IndexLayerSizes {
@@ -155,6 +162,8 @@ impl From<&PgSearchRelation> for IndexLayerSizes {
         Self {
             foreground_layer_sizes,
             background_layer_sizes,
+            min_merge_count: index_options.min_merge_count(),
+            max_merge_count: index_options.max_merge_count(),
         }
     }
 }
@@ -166,6 +175,11 @@ impl IndexLayerSizes {
     fn background(&self) -> Vec<u64> {
         self.background_layer_sizes.clone()
     }
+
+    fn merge_policy(&self, layer_sizes: Vec<u64>) -> LayeredMergePolicy {
+        LayeredMergePolicy::new(layer_sizes)
+            .with_merge_counts(self.min_merge_count, self.max_merge_count)
+    }
 }
 
 /// Kick off a merge of the index, if needed.
@@ -183,11 +197,15 @@ pub unsafe fn do_merge(
     let background_layers = layer_sizes.background();
 
     let metadata = MetaPage::open(index);
+
+    // free the files left behind by writers that crashed or aborted before committing them
+    metadata.write_journal().recover();
+
     let cleanup_lock = metadata.cleanup_lock_shared();
     let merge_lock = metadata.acquire_merge_lock();
 
     let needs_background_merge = !background_layers.is_empty() && {
-        let mut background_merge_policy = LayeredMergePolicy::new(background_layers.clone());
+        let mut background_merge_policy = layer_sizes.merge_policy(background_layers.clone());
         background_merge_policy.set_mergeable_segment_entries(&metadata, &merge_lock, &merger);
         let merge_candidates = background_merge_policy.simulate();
         !merge_candidates.is_empty()
@@ -195,7 +213,7 @@ pub unsafe fn do_merge(
 
     // first merge down the foreground layers
     if !foreground_layers.is_empty() && style == MergeStyle::Insert {
-        let foreground_merge_policy = LayeredMergePolicy::new(foreground_layers);
+        let foreground_merge_policy = layer_sizes.merge_policy(foreground_layers);
         unsafe {
             merge_index(
                 index,
@@ -279,6 +297,16 @@ extern "C-unwind" fn background_merge(arg: pg_sys::Datum) {
             return;
         }
         let index = index.unwrap();
+
+        if !unsafe { try_claim_merger_slot() } {
+            pgrx::debug1!(
+                "{}: `paradedb.max_background_mergers` are already running, deferring merge",
+                BackgroundWorker::get_name()
+            );
+            return;
+        }
+        WriteThrottle::enable(gucs::background_merge_write_limit());
+
         let metadata = MetaPage::open(&index);
         let layer_sizes = IndexLayerSizes::from(&index);
 
@@ -289,7 +317,7 @@ extern "C-unwind" fn background_merge(arg: pg_sys::Datum) {
             );
 
             let foreground_layers = layer_sizes.foreground();
-            let merge_policy = LayeredMergePolicy::new(foreground_layers);
+            let merge_policy = layer_sizes.merge_policy(foreground_layers);
             let cleanup_lock = metadata.cleanup_lock_shared();
             let merge_lock = unsafe { metadata.acquire_merge_lock() };
             unsafe {
@@ -310,7 +338,7 @@ extern "C-unwind" fn background_merge(arg: pg_sys::Datum) {
         );
 
         let background_layers = layer_sizes.background();
-        let merge_policy = LayeredMergePolicy::new(background_layers);
+        let merge_policy = layer_sizes.merge_policy(background_layers);
         let cleanup_lock = metadata.cleanup_lock_shared();
         let merge_lock = unsafe { metadata.acquire_merge_lock() };
         unsafe {
@@ -323,9 +351,56 @@ extern "C-unwind" fn background_merge(arg: pg_sys::Datum) {
                 current_xid,
             )
         };
+
+        // segments that merging left alone may still be mostly deleted documents
+        if args.merge_style() == MergeStyle::Vacuum {
+            let options = index.options();
+            if let Some(min_deleted_fraction) = options.vacuum_deleted_fraction() {
+                let nrewritten = unsafe {
+                    reclaim_deleted_docs(
+                        &index,
+                        min_deleted_fraction,
+                        options.vacuum_max_segments(),
+                        current_xid,
+                    )
+                };
+                pgrx::debug1!(
+                    "{}: rewrote {nrewritten} segments to reclaim deleted documents",
+                    BackgroundWorker::get_name()
+                );
+            }
+        }
     });
 }
 
+/// Advisory locks are keyed by a database oid, so an invalid one can't collide with user locks.
+const MERGER_SLOT_LOCK_DATABASE: u32 = 0;
+const MERGER_SLOT_LOCK_KEY: u32 = u32::from_be_bytes(*b"pdbm");
+/// `USER_LOCKMETHOD` from `lock.h`, which holds advisory locks.
+const USER_LOCKMETHOD: u8 = 2;
+
+/// Claim one of the `paradedb.max_background_mergers` slots for the rest of this transaction,
+/// returning `false` if they're all taken.
+unsafe fn try_claim_merger_slot() -> bool {
+    let max_mergers = gucs::max_background_mergers();
+    if max_mergers == 0 {
+        return true;
+    }
+
+    (0..max_mergers).any(|slot| {
+        let locktag = pg_sys::LOCKTAG {
+            locktag_field1: MERGER_SLOT_LOCK_DATABASE,
+            locktag_field2: MERGER_SLOT_LOCK_KEY,
+            locktag_field3: slot as u32,
+            locktag_field4: 0,
+            locktag_type: pg_sys::LockTagType::LOCKTAG_ADVISORY as u8,
+            locktag_lockmethodid: USER_LOCKMETHOD,
+        };
+        pg_sys::LockAcquire(&locktag, pg_sys::ExclusiveLock as _, false, true)
+            != pg_sys::LockAcquireResult::LOCKACQUIRE_NOT_AVAIL
+    })
+}
+
 #[inline]
 unsafe fn merge_index(
     indexrel: &PgSearchRelation,
@@ -348,9 +423,41 @@ unsafe fn merge_index(
     // the segments it decides can be merged into one or more candidates
     merge_policy.set_mergeable_segment_entries(&metadata, &merge_lock, &merger);
     let merge_candidates = merge_policy.simulate();
+    let mergeable_segments = merge_policy
+        .mergeable_segments()
+        .cloned()
+        .collect::<Vec<_>>();
+    run_merges(
+        indexrel,
+        &metadata,
+        merger,
+        merge_lock,
+        &mergeable_segments,
+        merge_candidates,
+        gc_after_merge,
+        current_xid,
+    );
+    drop(cleanup_lock);
+}
+
+/// Merge each candidate's segments together, given the [`MergeLock`] under which they were chosen.
+///
+/// `mergeable_segments` are all the segments the candidates were chosen from.  They're recorded
+/// in the merge list so that concurrent merges leave them alone.
+#[allow(clippy::too_many_arguments)]
+unsafe fn run_merges(
+    indexrel: &PgSearchRelation,
+    metadata: &MetaPage,
+    merger: SearchIndexMerger,
+    merge_lock: MergeLock,
+    mergeable_segments: &[SegmentId],
+    merge_candidates: Vec<MergeCandidate>,
+    gc_after_merge: bool,
+    current_xid: pg_sys::TransactionId,
+) {
     // before we start merging, tell the merger to release pins on the segments it won't be merging
     let mut merger = merger
-        .adjust_pins(merge_policy.mergeable_segments())
+        .adjust_pins(mergeable_segments.iter())
         .expect("should be able to adjust merger pins");
 
     let mut need_gc = !gc_after_merge;
@@ -360,7 +467,7 @@ unsafe fn merge_index(
         // could be merged
         let merge_entry = merge_lock
             .merge_list()
-            .add_segment_ids(merge_policy.mergeable_segments())
+            .add_segment_ids(mergeable_segments)
             .expect("should be able to write current merge segment_id list");
         drop(merge_lock);
 
@@ -375,8 +482,10 @@ unsafe fn merge_index(
             pgrx::debug1!("merging candidate with {} segments", candidate.0.len());
 
             merge_result = merger.merge_segments(&candidate.0);
-            if merge_result.is_err() {
-                break;
+            match &merge_result {
+                Err(_) => break,
+                Ok(Some(_)) => stats::record_merge(indexrel.oid(), candidate.0.len() as u64),
+                Ok(None) => {}
             }
             if gc_after_merge {
                 garbage_collect_index(indexrel, current_xid);
@@ -410,7 +519,300 @@ unsafe fn merge_index(
     } else {
         drop(merge_lock);
     }
+}
+
+/// Merge the index down to `segment_count` segments, or as close to it as possible, returning how
+/// many segments were created and how many were merged into them.
+///
+/// If `min_deleted_fraction` is given, only segments with more than that fraction of deleted
+/// documents are merged, and a segment may be merged on its own to drop its deleted documents.
+pub unsafe fn force_merge(
+    indexrel: &PgSearchRelation,
+    segment_count: usize,
+    min_deleted_fraction: Option<f64>,
+) -> (usize, usize) {
+    let current_xid = pg_sys::GetCurrentTransactionId();
+    let metadata = MetaPage::open(indexrel);
+    let cleanup_lock = metadata.cleanup_lock_shared();
+    let merge_lock = metadata.acquire_merge_lock();
+    let merger = SearchIndexMerger::open(MvccSatisfies::Mergeable.directory(indexrel))
+        .expect("should be able to open merger");
+
+    // segments that are being vacuumed or merged by someone else are off limits
+    let mut busy_segments = metadata.vacuum_list().read_list();
+    busy_segments.extend(merge_lock.merge_list().list_segment_ids());
+
+    let all_entries = merger.all_entries();
+    let eligible = all_entries
+        .values()
+        .filter(|entry| !busy_segments.contains(&entry.segment_id))
+        .filter(|entry| {
+            min_deleted_fraction
+                .is_none_or(|min_deleted_fraction| entry.deleted_fraction() > min_deleted_fraction)
+        })
+        .map(|entry| (entry.segment_id, entry.byte_size()))
+        .collect::<Vec<_>>();
+    let merge_candidates = force_merge_candidates(
+        eligible.clone(),
+        all_entries.len() - eligible.len(),
+        segment_count,
+        min_deleted_fraction.is_some(),
+    );
+
+    let new_segments = merge_candidates.len();
+    let merged_segments = merge_candidates
+        .iter()
+        .map(|candidate| candidate.0.len())
+        .sum();
+    let mergeable_segments = merge_candidates
+        .iter()
+        .flat_map(|candidate| candidate.0.iter().cloned())
+        .collect::<Vec<_>>();
+    run_merges(
+        indexrel,
+        &metadata,
+        merger,
+        merge_lock,
+        &mergeable_segments,
+        merge_candidates,
+        false,
+        current_xid,
+    );
     drop(cleanup_lock);
+
+    (new_segments, merged_segments)
+}
+
+/// Rewrite each segment with more than `min_deleted_fraction` of its documents deleted on its own,
+/// the most deleted first and at most `max_segments` of them, returning how many were rewritten.
+///
+/// This is how `VACUUM` reclaims the space that deleted documents take up in segments that are
+/// too big to be merged with others.
+pub unsafe fn reclaim_deleted_docs(
+    indexrel: &PgSearchRelation,
+    min_deleted_fraction: f64,
+    max_segments: Option<usize>,
+    current_xid: pg_sys::TransactionId,
+) -> usize {
+    let metadata = MetaPage::open(indexrel);
+    let cleanup_lock = metadata.cleanup_lock_shared();
+    let merge_lock = metadata.acquire_merge_lock();
+    let merger = SearchIndexMerger::open(MvccSatisfies::Mergeable.directory(indexrel))
+        .expect("should be able to open merger");
+
+    // segments that are being vacuumed or merged by someone else are off limits
+    let mut busy_segments = metadata.vacuum_list().read_list();
+    busy_segments.extend(merge_lock.merge_list().list_segment_ids());
+
+    let mut eligible = merger
+        .all_entries()
+        .values()
+        .filter(|entry| !busy_segments.contains(&entry.segment_id))
+        .filter(|entry| entry.deleted_fraction() > min_deleted_fraction)
+        .map(|entry| (entry.segment_id, entry.deleted_fraction()))
+        .collect::<Vec<_>>();
+    eligible.sort_by(|(a_id, a_fraction), (b_id, b_fraction)| {
+        b_fraction.total_cmp(a_fraction).then(a_id.cmp(b_id))
+    });
+    if let Some(max_segments) = max_segments {
+        eligible.truncate(max_segments);
+    }
+
+    let mergeable_segments = eligible
+        .into_iter()
+        .map(|(segment_id, _)| segment_id)
+        .collect::<Vec<_>>();
+    let merge_candidates = mergeable_segments
+        .iter()
+        .map(|segment_id| MergeCandidate(vec![*segment_id]))
+        .collect::<Vec<_>>();
+    run_merges(
+        indexrel,
+        &metadata,
+        merger,
+        merge_lock,
+        &mergeable_segments,
+        merge_candidates,
+        true,
+        current_xid,
+    );
+    drop(cleanup_lock);
+
+    mergeable_segments.len()
+}
+
+/// How much work a call of [`maintain`] may do: it starts no merge after its `deadline`, and
+/// rewrites no more than `remaining_bytes` of segments.
+pub struct MaintenanceBudget {
+    deadline: Instant,
+    remaining_bytes: Option<u64>,
+}
+
+impl MaintenanceBudget {
+    pub fn new(duration: Duration, max_bytes: Option<u64>) -> Self {
+        Self {
+            deadline: Instant::now() + duration,
+            remaining_bytes: max_bytes,
+        }
+    }
+
+    fn exhausted(&self) -> bool {
+        Instant::now() >= self.deadline || self.remaining_bytes == Some(0)
+    }
+
+    fn allows(&self, nbytes: u64) -> bool {
+        self.remaining_bytes
+            .is_none_or(|remaining_bytes| nbytes <= remaining_bytes)
+    }
+
+    fn spend(&mut self, nbytes: u64) {
+        if let Some(remaining_bytes) = self.remaining_bytes.as_mut() {
+            *remaining_bytes = remaining_bytes.saturating_sub(nbytes);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MaintenanceResult {
+    /// How many segments were merged into others.
+    pub merged_segments: usize,
+    /// How many segments were rewritten on their own to drop their deleted documents.
+    pub rewritten_segments: usize,
+    /// The size of all the segments that were merged or rewritten.
+    pub bytes_rewritten: u64,
+    /// Whether the index was left with nothing more to merge or rewrite.
+    pub finished: bool,
+}
+
+/// Do the merging that `VACUUM` and the background mergers would, one merge at a time and in the
+/// foreground, until there's none left to do or the `budget` runs out, and then rewrite the
+/// segments with more than `vacuum_deleted_percent` of their documents deleted, the most deleted
+/// first.
+///
+/// Each merge is chosen afresh from the index as it is, so a call that runs out of budget leaves
+/// the index no worse off, and the next call picks up where it stopped.
+pub unsafe fn maintain(
+    indexrel: &PgSearchRelation,
+    budget: &mut MaintenanceBudget,
+    current_xid: pg_sys::TransactionId,
+) -> MaintenanceResult {
+    let metadata = MetaPage::open(indexrel);
+
+    // free the files left behind by writers that crashed or aborted before committing them, and
+    // the segments that no transaction can see anymore
+    metadata.write_journal().recover();
+    garbage_collect_index(indexrel, current_xid);
+
+    let layer_sizes = IndexLayerSizes::from(indexrel);
+    let mut layers = layer_sizes.foreground();
+    layers.extend(layer_sizes.background());
+    let mut result = MaintenanceResult::default();
+
+    let mut finished = loop {
+        if budget.exhausted() {
+            break false;
+        }
+        let cleanup_lock = metadata.cleanup_lock_shared();
+        let merge_lock = metadata.acquire_merge_lock();
+        let merger = SearchIndexMerger::open(MvccSatisfies::Mergeable.directory(indexrel))
+            .expect("should be able to open merger");
+        let mut merge_policy = layer_sizes.merge_policy(layers.clone());
+        merge_policy.set_mergeable_segment_entries(&metadata, &merge_lock, &merger);
+        let merge_candidates = merge_policy.simulate();
+        if merge_candidates.is_empty() {
+            break true;
+        }
+
+        let all_entries = merger.all_entries();
+        let Some((candidate, nbytes)) = merge_candidates
+            .into_iter()
+            .map(|candidate| {
+                let nbytes = candidate
+                    .0
+                    .iter()
+                    .filter_map(|segment_id| all_entries.get(segment_id))
+                    .map(|entry| entry.byte_size())
+                    .sum::<u64>();
+                (candidate, nbytes)
+            })
+            .find(|(_, nbytes)| budget.allows(*nbytes))
+        else {
+            break false;
+        };
+        budget.spend(nbytes);
+        result.merged_segments += candidate.0.len();
+        result.bytes_rewritten += nbytes;
+
+        let mergeable_segments = candidate.0.clone();
+        run_merges(
+            indexrel,
+            &metadata,
+            merger,
+            merge_lock,
+            &mergeable_segments,
+            vec![candidate],
+            false,
+            current_xid,
+        );
+        drop(cleanup_lock);
+    };
+
+    if let Some(min_deleted_fraction) = indexrel.options().vacuum_deleted_fraction() {
+        loop {
+            if budget.exhausted() {
+                finished = false;
+                break;
+            }
+            let cleanup_lock = metadata.cleanup_lock_shared();
+            let merge_lock = metadata.acquire_merge_lock();
+            let merger = SearchIndexMerger::open(MvccSatisfies::Mergeable.directory(indexrel))
+                .expect("should be able to open merger");
+
+            // segments that are being vacuumed or merged by someone else are off limits
+            let mut busy_segments = metadata.vacuum_list().read_list();
+            busy_segments.extend(merge_lock.merge_list().list_segment_ids());
+
+            let eligible = merger
+                .all_entries()
+                .into_values()
+                .filter(|entry| !busy_segments.contains(&entry.segment_id))
+                .filter(|entry| entry.deleted_fraction() > min_deleted_fraction)
+                .collect::<Vec<_>>();
+            if eligible.is_empty() {
+                break;
+            }
+            let Some(entry) = eligible
+                .into_iter()
+                .filter(|entry| budget.allows(entry.byte_size()))
+                .max_by(|a, b| {
+                    a.deleted_fraction()
+                        .total_cmp(&b.deleted_fraction())
+                        .then(b.segment_id.cmp(&a.segment_id))
+                })
+            else {
+                finished = false;
+                break;
+            };
+            budget.spend(entry.byte_size());
+            result.rewritten_segments += 1;
+            result.bytes_rewritten += entry.byte_size();
+
+            run_merges(
+                indexrel,
+                &metadata,
+                merger,
+                merge_lock,
+                &[entry.segment_id],
+                vec![MergeCandidate(vec![entry.segment_id])],
+                true,
+                current_xid,
+            );
+            drop(cleanup_lock);
+        }
+    }
+
+    result.finished = finished;
+    result
 }
 
 ///
@@ -432,13 +834,17 @@ pub unsafe fn garbage_collect_index(
     // SEGMENT_METAS must be updated atomically so that a consistent list is visible for consumers:
     // SEGMENT_METAS_GARBAGE need not be because it is only ever consumed on the physical
     // replication primary.
-    let mut segment_metas_linked_list = MetaPage::open(indexrel).segment_metas();
+    let metadata = MetaPage::open(indexrel);
+    let mut segment_metas_linked_list = metadata.segment_metas();
     let mut segment_metas = segment_metas_linked_list.atomically();
     let entries = segment_metas.garbage_collect();
 
     // Replication is not enabled: immediately free the entries. It doesn't matter when we
     // commit the segment metas list in this case.
     segment_metas.commit();
+    if let Some(mut segment_tenants) = metadata.segment_tenants() {
+        segment_tenants.forget(&entries.iter().map(|entry| entry.segment_id).collect());
+    }
     free_entries(indexrel, entries, current_xid);
 }
 
@@ -482,6 +888,7 @@ mod tests {
         Default,
         Foreground(String),
         Background(String),
+        MergeCounts(i32, i32),
     }
 
     impl LayerSizes {
@@ -490,6 +897,9 @@ mod tests {
                 LayerSizes::Default => "".to_string(),
                 LayerSizes::Foreground(sizes) => format!(", layer_sizes = '{sizes}'"),
                 LayerSizes::Background(sizes) => format!(", background_layer_sizes = '{sizes}'"),
+                LayerSizes::MergeCounts(min, max) => {
+                    format!(", min_merge_count = {min}, max_merge_count = {max}")
+                }
             }
         }
     }
@@ -564,4 +974,21 @@ mod tests {
         let layer_sizes = index.options().background_layer_sizes();
         assert_eq!(layer_sizes, DEFAULT_BACKGROUND_LAYER_SIZES.to_vec());
     }
+
+    #[pg_test]
+    fn test_configured_merge_counts() {
+        let index_oid = create_index_with_layer_sizes(LayerSizes::MergeCounts(4, 3));
+        let index = PgSearchRelation::open(index_oid);
+        assert_eq!(index.options().min_merge_count(), 4);
+        // a maximum below the minimum is raised to it
+        assert_eq!(index.options().max_merge_count(), Some(4));
+    }
+
+    #[pg_test]
+    fn test_default_merge_counts() {
+        let index_oid = create_index_with_layer_sizes(LayerSizes::Default);
+        let index = PgSearchRelation::open(index_oid);
+        assert_eq!(index.options().min_merge_count(), 2);
+        assert_eq!(index.options().max_merge_count(), None);
+    }
 }
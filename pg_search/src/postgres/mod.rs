@@ -16,7 +16,6 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use crate::api::HashMap;
-use crate::postgres::build::is_bm25_index;
 use crate::postgres::spinlock::Spinlock;
 use crate::query::SearchQueryInput;
 use pgrx::*;
@@ -27,9 +26,10 @@ use tantivy::SegmentReader;
 
 mod build;
 mod cost;
-mod delete;
+pub mod delete;
+mod fdw;
 pub mod insert;
-mod merge;
+pub mod merge;
 pub mod options;
 mod ps_status;
 mod range;
@@ -37,20 +37,24 @@ mod scan;
 mod vacuum;
 mod validate;
 
+pub mod alter_index;
 mod build_parallel;
 pub mod customscan;
 pub mod datetime;
 #[cfg(not(feature = "pg17"))]
 pub mod fake_aminsertcleanup;
+pub mod heap;
 pub mod index;
 mod parallel;
 pub mod rel;
 pub mod spinlock;
+pub mod stats;
 pub mod storage;
 pub mod types;
 pub mod types_arrow;
 pub mod utils;
 pub mod var;
+pub mod vector;
 pub mod visibility_checker;
 
 #[repr(u16)] // b/c that's what [`pg_sys::StrategyNumber`] is
@@ -93,6 +97,7 @@ fn bm25_handler(_fcinfo: pg_sys::FunctionCallInfo) -> PgBox<pg_sys::IndexAmRouti
     amroutine.amvalidate = Some(validate::amvalidate);
     amroutine.ambuild = Some(build::ambuild);
     amroutine.ambuildempty = Some(build::ambuildempty);
+    amroutine.ambuildphasename = Some(build::ambuildphasename);
     amroutine.aminsert = Some(insert::aminsert);
     #[cfg(feature = "pg17")]
     {
@@ -125,10 +130,10 @@ pub fn rel_get_bm25_index(
         return None;
     }
 
+    // an index that's still being built by `CREATE INDEX CONCURRENTLY`, or whose build failed,
+    // isn't valid and can't be searched
     let rel = PgSearchRelation::with_lock(relid, pg_sys::AccessShareLock as _);
-    rel.indices(pg_sys::AccessShareLock as _)
-        .find(is_bm25_index)
-        .map(|index| (rel, index))
+    utils::locate_bm25_index_from_heaprel(&rel).map(|index| (rel, index))
 }
 
 // 16 bytes for segment id + 4 bytes for u32 num_deleted_docs
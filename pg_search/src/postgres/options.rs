@@ -19,16 +19,18 @@ use crate::api::FieldName;
 use crate::api::HashMap;
 use crate::postgres::utils::{extract_field_attributes, ExtractedFieldAttribute};
 use crate::schema::IndexRecordOption;
-use crate::schema::{SearchFieldConfig, SearchFieldType};
+use crate::schema::{DatePrecision, SearchFieldConfig, SearchFieldType};
 use std::cell::{Ref, RefCell};
 
 use anyhow::Result;
 use memoffset::*;
+use pgrx::datum::Interval;
 use pgrx::pg_sys::AsPgCStr;
 use pgrx::*;
 use serde_json::Map;
 use std::ffi::CStr;
 use std::rc::Rc;
+use tantivy::store::{Compressor, ZstdCompressor};
 use tokenizers::manager::SearchTokenizerFilters;
 use tokenizers::{SearchNormalizer, SearchTokenizer};
 /* ADDING OPTIONS
@@ -82,6 +84,24 @@ extern "C-unwind" fn validate_inet_fields(value: *const std::os::raw::c_char) {
     deserialize_config_fields(json_str, &SearchFieldConfig::inet_from_json);
 }
 
+#[pg_guard]
+extern "C-unwind" fn validate_uuid_fields(value: *const std::os::raw::c_char) {
+    let json_str = cstr_to_rust_str(value);
+    if json_str.is_empty() {
+        return;
+    }
+    deserialize_config_fields(json_str, &SearchFieldConfig::uuid_from_json);
+}
+
+#[pg_guard]
+extern "C-unwind" fn validate_bytes_fields(value: *const std::os::raw::c_char) {
+    let json_str = cstr_to_rust_str(value);
+    if json_str.is_empty() {
+        return;
+    }
+    deserialize_config_fields(json_str, &SearchFieldConfig::bytes_from_json);
+}
+
 #[pg_guard]
 extern "C-unwind" fn validate_numeric_fields(value: *const std::os::raw::c_char) {
     let json_str = cstr_to_rust_str(value);
@@ -144,6 +164,67 @@ extern "C-unwind" fn validate_key_field(value: *const std::os::raw::c_char) {
     cstr_to_rust_str(value);
 }
 
+#[pg_guard]
+extern "C-unwind" fn validate_ttl(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        return;
+    }
+    let cstr = unsafe { CStr::from_ptr(value) };
+    if cstr.is_empty() {
+        return;
+    }
+    parse_ttl(cstr);
+}
+
+/// Parse a `ttl` with Postgres' own `interval` input function, so it accepts whatever an
+/// `interval` literal does.
+pub(crate) fn parse_ttl(ttl: &CStr) -> Interval {
+    let interval = unsafe {
+        direct_function_call::<Interval>(
+            pg_sys::interval_in,
+            &[
+                ttl.into_datum(),
+                pg_sys::InvalidOid.into_datum(),
+                (-1i32).into_datum(),
+            ],
+        )
+        .expect("`interval_in()` should not return NULL")
+    };
+    if interval.months() < 0 || interval.days() < 0 || interval.micros() < 0 {
+        panic!("`ttl` must not be negative");
+    }
+    interval
+}
+
+#[pg_guard]
+extern "C-unwind" fn validate_docstore_compression(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        return;
+    }
+    let cstr = unsafe { CStr::from_ptr(value) };
+    parse_docstore_compression(
+        cstr.to_str()
+            .expect("`docstore_compression` must be valid UTF-8"),
+        0,
+    );
+}
+
+/// The compressor named by `docstore_compression`, where an empty name is the default, at the
+/// `docstore_compression_level` `level`, where 0 is the compressor's default level.
+fn parse_docstore_compression(name: &str, level: i32) -> Compressor {
+    match name.trim().to_lowercase().as_str() {
+        "zstd" => Compressor::Zstd(ZstdCompressor {
+            compression_level: (level != 0).then_some(level),
+        }),
+        "" | "lz4" | "none" if level != 0 => {
+            panic!("`docstore_compression_level` can only be set when `docstore_compression` is `zstd`")
+        }
+        "" | "lz4" => Compressor::Lz4,
+        "none" => Compressor::None,
+        other => panic!("`docstore_compression` must be `lz4`, `zstd` or `none`, not `{other}`"),
+    }
+}
+
 #[pg_guard]
 extern "C-unwind" fn validate_layer_sizes(value: *const std::os::raw::c_char) {
     if value.is_null() {
@@ -183,7 +264,7 @@ fn cstr_to_rust_str(value: *const std::os::raw::c_char) -> String {
         .to_string()
 }
 
-const NUM_REL_OPTS: usize = 11;
+const NUM_REL_OPTS: usize = 24;
 #[pg_guard]
 pub unsafe extern "C-unwind" fn amoptions(
     reloptions: pg_sys::Datum,
@@ -245,8 +326,79 @@ pub unsafe extern "C-unwind" fn amoptions(
             opttype: pg_sys::relopt_type::RELOPT_TYPE_STRING,
             offset: offset_of!(BM25IndexOptionsData, background_layer_sizes_offset) as i32,
         },
+        pg_sys::relopt_parse_elt {
+            optname: "min_merge_count".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_INT,
+            offset: offset_of!(BM25IndexOptionsData, min_merge_count) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "max_merge_count".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_INT,
+            offset: offset_of!(BM25IndexOptionsData, max_merge_count) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "uuid_fields".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_STRING,
+            offset: offset_of!(BM25IndexOptionsData, uuid_fields_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "bytes_fields".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_STRING,
+            offset: offset_of!(BM25IndexOptionsData, bytes_fields_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "vacuum_deleted_percent".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_REAL,
+            offset: offset_of!(BM25IndexOptionsData, vacuum_deleted_percent) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "vacuum_max_segments".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_INT,
+            offset: offset_of!(BM25IndexOptionsData, vacuum_max_segments) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "tenant_field".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_STRING,
+            offset: offset_of!(BM25IndexOptionsData, tenant_field_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "ttl_field".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_STRING,
+            offset: offset_of!(BM25IndexOptionsData, ttl_field_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "ttl".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_STRING,
+            offset: offset_of!(BM25IndexOptionsData, ttl_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "insert_work_mem".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_INT,
+            offset: offset_of!(BM25IndexOptionsData, insert_work_mem) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "docstore_compression".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_STRING,
+            offset: offset_of!(BM25IndexOptionsData, docstore_compression_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "docstore_compression_level".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_INT,
+            offset: offset_of!(BM25IndexOptionsData, docstore_compression_level) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "standby_searches".as_pg_cstr(),
+            opttype: pg_sys::relopt_type::RELOPT_TYPE_BOOL,
+            offset: offset_of!(BM25IndexOptionsData, standby_searches) as i32,
+        },
     ];
-    build_relopts(reloptions, validate, options)
+    let rdopts = build_relopts(reloptions, validate, options);
+    if validate && !rdopts.is_null() {
+        // the compression and its level are each valid on their own, so check they suit each other
+        // now rather than when the next segment is written
+        (*rdopts.cast::<BM25IndexOptionsData>()).docstore_compression();
+    }
+    rdopts
 }
 
 unsafe fn build_relopts(
@@ -276,6 +428,8 @@ struct LazyInfo {
     json: Rc<RefCell<Option<HashMap<FieldName, SearchFieldConfig>>>>,
     range: Rc<RefCell<Option<HashMap<FieldName, SearchFieldConfig>>>>,
     inet: Rc<RefCell<Option<HashMap<FieldName, SearchFieldConfig>>>>,
+    uuid: Rc<RefCell<Option<HashMap<FieldName, SearchFieldConfig>>>>,
+    bytes: Rc<RefCell<Option<HashMap<FieldName, SearchFieldConfig>>>>,
 
     attributes: Rc<RefCell<HashMap<FieldName, ExtractedFieldAttribute>>>,
 }
@@ -317,6 +471,61 @@ impl BM25IndexOptions {
             })
     }
 
+    /// The fewest segments that are merged together at once.
+    pub fn min_merge_count(&self) -> usize {
+        self.options_data().min_merge_count as usize
+    }
+
+    /// The most segments that are merged together at once, or [`None`] if there's no limit.
+    pub fn max_merge_count(&self) -> Option<usize> {
+        match self.options_data().max_merge_count {
+            0 => None,
+            // a limit below the minimum would prevent all merging
+            max => Some((max as usize).max(self.min_merge_count())),
+        }
+    }
+
+    /// The fraction of a segment's documents that must be deleted for `VACUUM` to rewrite it, or
+    /// [`None`] if segments are left to regular merging.
+    pub fn vacuum_deleted_fraction(&self) -> Option<f64> {
+        match self.options_data().vacuum_deleted_percent {
+            percent if percent > 0.0 => Some(percent / 100.0),
+            _ => None,
+        }
+    }
+
+    /// The most segments a single `VACUUM` rewrites, or [`None`] if there's no limit.
+    pub fn vacuum_max_segments(&self) -> Option<usize> {
+        match self.options_data().vacuum_max_segments {
+            0 => None,
+            max => Some(max as usize),
+        }
+    }
+
+    /// The memory budget, in bytes, of the statements writing to the index, or [`None`] if it
+    /// isn't set.
+    pub fn insert_work_mem(&self) -> Option<usize> {
+        match self.options_data().insert_work_mem {
+            0 => None,
+            kb => Some(kb as usize * 1024),
+        }
+    }
+
+    /// How the documents of the segments written from now on are compressed.
+    pub fn docstore_compression(&self) -> Compressor {
+        self.options_data().docstore_compression()
+    }
+
+    /// Can the index be searched on hot standbys?
+    ///
+    /// The index is WAL-logged with generic records, whose replay doesn't resolve recovery
+    /// conflicts, so a standby's searches are only as safe as the `hot_standby_feedback` the
+    /// primary has received: a search that starts before its feedback is sent, or while the
+    /// standby is disconnected without a replication slot, can read blocks the primary reused.
+    pub fn standby_searches(&self) -> bool {
+        self.options_data().standby_searches
+    }
+
     pub fn key_field_name(&self) -> FieldName {
         self.options_data()
             .key_field_name()
@@ -328,6 +537,21 @@ impl BM25IndexOptions {
             .expect(Self::MISSING_KEY_FIELD_CONFIG)
     }
 
+    /// The field whose value partitions the index's segments by tenant, if any.
+    pub fn tenant_field_name(&self) -> Option<FieldName> {
+        self.options_data().tenant_field_name()
+    }
+
+    /// The datetime field whose documents expire once they're older than [`Self::ttl()`], if any.
+    pub fn ttl_field_name(&self) -> Option<FieldName> {
+        self.options_data().ttl_field_name()
+    }
+
+    /// How long documents live for, as a Postgres `interval` string like `'30 days'`.
+    pub fn ttl(&self) -> Option<String> {
+        self.options_data().ttl()
+    }
+
     /// Returns either the config explicitly set in the CREATE INDEX WITH options,
     /// falling back to the default config for the field type.
     pub fn field_config_or_default(&self, field_name: &FieldName) -> SearchFieldConfig {
@@ -393,6 +617,20 @@ impl BM25IndexOptions {
         self.lazy.inet.borrow()
     }
 
+    pub fn uuid_config(&self) -> Ref<Option<HashMap<FieldName, SearchFieldConfig>>> {
+        if self.lazy.uuid.borrow().is_none() {
+            *self.lazy.uuid.borrow_mut() = Some(self.options_data().uuid_configs());
+        }
+        self.lazy.uuid.borrow()
+    }
+
+    pub fn bytes_config(&self) -> Ref<Option<HashMap<FieldName, SearchFieldConfig>>> {
+        if self.lazy.bytes.borrow().is_none() {
+            *self.lazy.bytes.borrow_mut() = Some(self.options_data().bytes_configs());
+        }
+        self.lazy.bytes.borrow()
+    }
+
     /// Returns the config only if it is explicitly set in the CREATE INDEX WITH options
     fn field_config(&self, field_name: &FieldName) -> Option<SearchFieldConfig> {
         let data = self.options_data();
@@ -400,6 +638,8 @@ impl BM25IndexOptions {
             return Some(SearchFieldConfig::Numeric {
                 indexed: true,
                 fast: true,
+                stored: false,
+                scale: None,
             });
         }
 
@@ -454,6 +694,20 @@ impl BM25IndexOptions {
                     .get(field_name)
                     .cloned()
             })
+            .or_else(|| {
+                self.uuid_config()
+                    .as_ref()
+                    .unwrap()
+                    .get(field_name)
+                    .cloned()
+            })
+            .or_else(|| {
+                self.bytes_config()
+                    .as_ref()
+                    .unwrap()
+                    .get(field_name)
+                    .cloned()
+            })
     }
 
     /// Returns a `Vec` of aliased text field names and their configs.
@@ -576,6 +830,19 @@ struct BM25IndexOptionsData {
     inet_fields_offset: i32,
     target_segment_count: i32,
     background_layer_sizes_offset: i32,
+    min_merge_count: i32,
+    max_merge_count: i32,
+    uuid_fields_offset: i32,
+    bytes_fields_offset: i32,
+    vacuum_max_segments: i32,
+    tenant_field_offset: i32,
+    ttl_field_offset: i32,
+    ttl_offset: i32,
+    insert_work_mem: i32,
+    docstore_compression_offset: i32,
+    docstore_compression_level: i32,
+    standby_searches: bool,
+    vacuum_deleted_percent: f64,
 }
 
 impl BM25IndexOptionsData {
@@ -615,6 +882,37 @@ impl BM25IndexOptionsData {
         Some(key_field_name.into())
     }
 
+    pub fn tenant_field_name(&self) -> Option<FieldName> {
+        let tenant_field_name = self.get_str(self.tenant_field_offset, "".to_string());
+        if tenant_field_name.is_empty() {
+            return None;
+        }
+        Some(tenant_field_name.into())
+    }
+
+    pub fn ttl_field_name(&self) -> Option<FieldName> {
+        let ttl_field_name = self.get_str(self.ttl_field_offset, "".to_string());
+        if ttl_field_name.is_empty() {
+            return None;
+        }
+        Some(ttl_field_name.into())
+    }
+
+    pub fn ttl(&self) -> Option<String> {
+        let ttl = self.get_str(self.ttl_offset, "".to_string());
+        if ttl.trim().is_empty() {
+            return None;
+        }
+        Some(ttl)
+    }
+
+    pub fn docstore_compression(&self) -> Compressor {
+        parse_docstore_compression(
+            &self.get_str(self.docstore_compression_offset, "".to_string()),
+            self.docstore_compression_level,
+        )
+    }
+
     pub fn text_configs(&self) -> HashMap<FieldName, SearchFieldConfig> {
         self.deserialize_configs(self.text_fields_offset, &SearchFieldConfig::text_from_json)
     }
@@ -623,6 +921,17 @@ impl BM25IndexOptionsData {
         self.deserialize_configs(self.inet_fields_offset, &SearchFieldConfig::inet_from_json)
     }
 
+    fn uuid_configs(&self) -> HashMap<FieldName, SearchFieldConfig> {
+        self.deserialize_configs(self.uuid_fields_offset, &SearchFieldConfig::uuid_from_json)
+    }
+
+    fn bytes_configs(&self) -> HashMap<FieldName, SearchFieldConfig> {
+        self.deserialize_configs(
+            self.bytes_fields_offset,
+            &SearchFieldConfig::bytes_from_json,
+        )
+    }
+
     pub fn numeric_configs(&self) -> HashMap<FieldName, SearchFieldConfig> {
         self.deserialize_configs(
             self.numeric_fields_offset,
@@ -687,6 +996,11 @@ impl BM25IndexOptionsData {
     }
 }
 
+/// The lock that `ALTER INDEX ... SET` takes to change an option that doesn't affect how documents
+/// are tokenized, which lets the index be searched and written to while it's changed.  The new
+/// value applies to the segments written and merged afterward.
+const ONLINE_LOCKMODE: pg_sys::LOCKMODE = pg_sys::ShareUpdateExclusiveLock as pg_sys::LOCKMODE;
+
 // it adds the tokenizer option to the list of relation options so we can parse it in amoptions
 pub unsafe fn init() {
     // adding our own relopt type because zombodb does, but one of the built-in Postgres ones might be more appropriate
@@ -739,6 +1053,30 @@ pub unsafe fn init() {
         Some(validate_datetime_fields),
         pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE,
     );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_PDB,
+        "inet_fields".as_pg_cstr(),
+        "JSON string specifying how inet fields should be indexed".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_inet_fields),
+        pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE,
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_PDB,
+        "uuid_fields".as_pg_cstr(),
+        "JSON string specifying which UUID fields should be indexed as binary".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_uuid_fields),
+        pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE,
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_PDB,
+        "bytes_fields".as_pg_cstr(),
+        "JSON string specifying how bytea fields should be indexed".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_bytes_fields),
+        pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE,
+    );
     pg_sys::add_string_reloption(
         RELOPT_KIND_PDB,
         "key_field".as_pg_cstr(),
@@ -747,13 +1085,37 @@ pub unsafe fn init() {
         Some(validate_key_field),
         pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE,
     );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_PDB,
+        "tenant_field".as_pg_cstr(),
+        "Column name as a string whose value partitions segments by tenant".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_key_field),
+        pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE,
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_PDB,
+        "ttl_field".as_pg_cstr(),
+        "Column name as a string of the timestamp that documents expire by".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_key_field),
+        pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE,
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_PDB,
+        "ttl".as_pg_cstr(),
+        "How long documents live for after their ttl_field, as an interval".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_ttl),
+        pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE,
+    );
     pg_sys::add_string_reloption(
         RELOPT_KIND_PDB,
         "layer_sizes".as_pg_cstr(),
         "The sizes of each layer to merge in the foreground".as_pg_cstr(),
         std::ptr::null(),
         Some(validate_layer_sizes),
-        pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE,
+        ONLINE_LOCKMODE,
     );
     pg_sys::add_int_reloption(
         RELOPT_KIND_PDB,
@@ -762,7 +1124,7 @@ pub unsafe fn init() {
         0,
         0,
         i32::MAX,
-        pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE,
+        ONLINE_LOCKMODE,
     );
     pg_sys::add_string_reloption(
         RELOPT_KIND_PDB,
@@ -770,7 +1132,79 @@ pub unsafe fn init() {
         "The sizes of each layer to merge in the background".as_pg_cstr(),
         std::ptr::null(),
         Some(validate_layer_sizes),
-        pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE,
+        ONLINE_LOCKMODE,
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_PDB,
+        "min_merge_count".as_pg_cstr(),
+        "The fewest segments in a layer that are merged together".as_pg_cstr(),
+        2,
+        2,
+        i32::MAX,
+        ONLINE_LOCKMODE,
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_PDB,
+        "max_merge_count".as_pg_cstr(),
+        "The most segments that are merged together at once, or 0 for no limit".as_pg_cstr(),
+        0,
+        0,
+        i32::MAX,
+        ONLINE_LOCKMODE,
+    );
+    pg_sys::add_real_reloption(
+        RELOPT_KIND_PDB,
+        "vacuum_deleted_percent".as_pg_cstr(),
+        "The percentage of a segment's documents that must be deleted for VACUUM to rewrite it, or 0 to leave it to merging".as_pg_cstr(),
+        0.0,
+        0.0,
+        100.0,
+        ONLINE_LOCKMODE,
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_PDB,
+        "vacuum_max_segments".as_pg_cstr(),
+        "The most segments that a VACUUM rewrites to reclaim deleted documents, or 0 for no limit"
+            .as_pg_cstr(),
+        0,
+        0,
+        i32::MAX,
+        ONLINE_LOCKMODE,
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_PDB,
+        "insert_work_mem".as_pg_cstr(),
+        "The memory, in kilobytes, each statement writing to the index may buffer documents in, or 0 for paradedb.insert_work_mem".as_pg_cstr(),
+        0,
+        0,
+        i32::MAX,
+        ONLINE_LOCKMODE,
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_PDB,
+        "docstore_compression".as_pg_cstr(),
+        "How the stored documents of new segments are compressed, `lz4`, `zstd` or `none`"
+            .as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_docstore_compression),
+        ONLINE_LOCKMODE,
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_PDB,
+        "docstore_compression_level".as_pg_cstr(),
+        "The zstd level the stored documents of new segments are compressed at, or 0 for zstd's default".as_pg_cstr(),
+        0,
+        0,
+        22,
+        ONLINE_LOCKMODE,
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_PDB,
+        "standby_searches".as_pg_cstr(),
+        "Whether the index can be searched on hot standbys, at the cost of reusing freed blocks later"
+            .as_pg_cstr(),
+        false,
+        ONLINE_LOCKMODE,
     );
 }
 
@@ -809,11 +1243,14 @@ fn key_field_config(field_type: SearchFieldType) -> SearchFieldConfig {
             SearchFieldConfig::Numeric {
                 indexed: true,
                 fast: true,
+                stored: false,
+                scale: None,
             }
         }
         SearchFieldType::Text(_) | SearchFieldType::Uuid(_) => SearchFieldConfig::Text {
             indexed: true,
             fast: true,
+            stored: false,
             fieldnorms: false,
 
             // NB:  This should use the `SearchTokenizer::Keyword` tokenizer but for historical
@@ -824,14 +1261,17 @@ fn key_field_config(field_type: SearchFieldType) -> SearchFieldConfig {
             record: IndexRecordOption::Basic,
             normalizer: SearchNormalizer::Raw,
             column: None,
+            term_vectors: false,
         },
         SearchFieldType::Inet(_) => SearchFieldConfig::Inet {
             indexed: true,
             fast: true,
+            stored: false,
         },
         SearchFieldType::Json(_) => SearchFieldConfig::Json {
             indexed: true,
             fast: true,
+            stored: false,
             fieldnorms: false,
             expand_dots: false,
             #[allow(deprecated)]
@@ -839,15 +1279,26 @@ fn key_field_config(field_type: SearchFieldType) -> SearchFieldConfig {
             record: IndexRecordOption::Basic,
             normalizer: SearchNormalizer::Raw,
             column: None,
+            include_paths: vec![],
+            exclude_paths: vec![],
         },
         SearchFieldType::Range(_) => SearchFieldConfig::Range { fast: true },
+        SearchFieldType::Bytes(_) => SearchFieldConfig::Bytes {
+            indexed: true,
+            fast: true,
+            stored: false,
+        },
         SearchFieldType::Bool(_) => SearchFieldConfig::Boolean {
             indexed: true,
             fast: true,
+            stored: false,
         },
+        SearchFieldType::Vector(_) => panic!("a vector column cannot be the key_field"),
         SearchFieldType::Date(_) => SearchFieldConfig::Date {
             indexed: true,
             fast: true,
+            stored: false,
+            precision: DatePrecision::default(),
         },
     }
 }
@@ -68,6 +68,7 @@ pub extern "C-unwind" fn amrescan(
                     query_string,
                     lenient: None,
                     conjunction_mode: None,
+                    syntax: None,
                 }
             },
             ScanStrategy::SearchQueryInput => unsafe {
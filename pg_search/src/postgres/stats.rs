@@ -0,0 +1,414 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-index search statistics, kept in shared memory so that every backend adds to the same
+//! counts, for the `paradedb.index_stats` view.
+//!
+//! Each search that the custom scan runs is recorded against its index when the scan ends: how
+//! long it took, how many documents it returned, and how many blocks it found in, or had to read
+//! into, the buffer cache.  Every backend reads an index's blocks through the same shared buffers,
+//! so the blocks of the index itself are also counted on their own, to show how much of it those
+//! buffers hold onto.  Latencies are kept in a histogram with four buckets for every doubling,
+//! so that percentiles can be estimated to within about 20 percent without keeping every sample.
+//!
+//! Writes are recorded too:  how many documents were indexed and in how many segments, and how
+//! many merges combined how many segments.  So are lookups of the per-connection caches of search
+//! results, aggregates, and translated queries, so that their hit rates can be monitored.
+//!
+//! The shared memory can only be allocated when `pg_search` is in `shared_preload_libraries`.
+//! Otherwise nothing is recorded and the view is empty.
+
+use crate::gucs;
+use pgrx::lwlock::PgLwLock;
+use pgrx::shmem::PGRXSharedMemory;
+use pgrx::{pg_shmem_init, pg_sys};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// The most indexes whose statistics are kept.  Searches of any others aren't recorded until the
+/// statistics are reset.
+pub const MAX_TRACKED_INDEXES: usize = 512;
+
+/// Four buckets for each doubling of a latency in microseconds, which covers up to 2^36 µs, or
+/// about 19 hours, before everything lands in the last bucket.
+const BUCKETS_PER_DOUBLING: f64 = 4.0;
+const LATENCY_BUCKETS: usize = 145;
+
+static INDEX_STATS: PgLwLock<IndexStatsTable> = unsafe { PgLwLock::new(c"pg_search_index_stats") };
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// How many blocks of BM25 indexes this backend has found in, and had to read into, the buffer
+/// cache, for [`ScanStats`] to count the ones each scan read.
+static INDEX_BLOCKS_HIT: AtomicU64 = AtomicU64::new(0);
+static INDEX_BLOCKS_READ: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Copy, Clone)]
+pub struct IndexStats {
+    pub database_oid: pg_sys::Oid,
+    pub index_oid: pg_sys::Oid,
+    pub queries: u64,
+    pub total_time: Duration,
+    pub docs_scanned: u64,
+    pub blocks_hit: u64,
+    pub blocks_read: u64,
+    pub index_blocks_hit: u64,
+    pub index_blocks_read: u64,
+    pub docs_indexed: u64,
+    pub segments_written: u64,
+    pub merges: u64,
+    pub segments_merged: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    latencies: [u64; LATENCY_BUCKETS],
+}
+
+impl IndexStats {
+    fn new(database_oid: pg_sys::Oid, index_oid: pg_sys::Oid) -> Self {
+        Self {
+            database_oid,
+            index_oid,
+            queries: 0,
+            total_time: Duration::ZERO,
+            docs_scanned: 0,
+            blocks_hit: 0,
+            blocks_read: 0,
+            index_blocks_hit: 0,
+            index_blocks_read: 0,
+            docs_indexed: 0,
+            segments_written: 0,
+            merges: 0,
+            segments_merged: 0,
+            cache_hits: 0,
+            cache_misses: 0,
+            latencies: [0; LATENCY_BUCKETS],
+        }
+    }
+
+    pub fn mean_time(&self) -> Option<Duration> {
+        (self.queries > 0).then(|| self.total_time.div_f64(self.queries as f64))
+    }
+
+    /// The latency that `percentile` of the searches took no longer than, rounded up to the
+    /// bucket it falls in.
+    pub fn percentile_time(&self, percentile: f64) -> Option<Duration> {
+        if self.queries == 0 {
+            return None;
+        }
+        let rank = (self.queries as f64 * percentile).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        let bucket = self
+            .latencies
+            .iter()
+            .position(|count| {
+                seen += count;
+                seen >= rank
+            })
+            .unwrap_or(LATENCY_BUCKETS - 1);
+        Some(bucket_upper_bound(bucket))
+    }
+
+    /// How many searches took no longer than each of `bounds`, which must be in ascending order,
+    /// counting a search by the upper bound of the bucket its latency falls in.
+    pub fn cumulative_latencies(&self, bounds: &[Duration]) -> Vec<u64> {
+        let mut counts = vec![0; bounds.len()];
+        for (bucket, count) in self.latencies.iter().enumerate() {
+            let upper_bound = bucket_upper_bound(bucket);
+            if let Some(first) = bounds.iter().position(|bound| upper_bound <= *bound) {
+                for cumulative in &mut counts[first..] {
+                    *cumulative += count;
+                }
+            }
+        }
+        counts
+    }
+
+    pub fn hit_ratio(&self) -> Option<f64> {
+        ratio(self.blocks_hit, self.blocks_read)
+    }
+
+    pub fn index_hit_ratio(&self) -> Option<f64> {
+        ratio(self.index_blocks_hit, self.index_blocks_read)
+    }
+
+    fn record(&mut self, scan: &ScanStats, count_query: bool) {
+        if count_query {
+            self.queries += 1;
+            self.total_time += scan.elapsed;
+            self.latencies[latency_bucket(scan.elapsed)] += 1;
+        }
+        self.docs_scanned += scan.docs_scanned;
+        self.blocks_hit += scan.blocks_hit;
+        self.blocks_read += scan.blocks_read;
+        self.index_blocks_hit += scan.index_blocks_hit;
+        self.index_blocks_read += scan.index_blocks_read;
+    }
+}
+
+fn ratio(hit: u64, read: u64) -> Option<f64> {
+    match hit + read {
+        0 => None,
+        total => Some(hit as f64 / total as f64),
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct IndexStatsTable {
+    len: usize,
+    entries: [IndexStats; MAX_TRACKED_INDEXES],
+}
+
+impl Default for IndexStatsTable {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            entries: [IndexStats::new(pg_sys::InvalidOid, pg_sys::InvalidOid); MAX_TRACKED_INDEXES],
+        }
+    }
+}
+
+unsafe impl PGRXSharedMemory for IndexStatsTable {}
+
+/// Allocate the shared memory for the statistics.  Must be called from `_PG_init()` while the
+/// shared preload libraries are being loaded.
+pub fn init() {
+    pg_shmem_init!(INDEX_STATS);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// What one scan of an index has done so far.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ScanStats {
+    pub elapsed: Duration,
+    pub docs_scanned: u64,
+    pub blocks_hit: u64,
+    pub blocks_read: u64,
+    pub index_blocks_hit: u64,
+    pub index_blocks_read: u64,
+}
+
+/// When a stretch of a scan's work started, for [`ScanStats::stop`] to add it up.
+pub struct ScanMark {
+    started: Instant,
+    blocks_hit: i64,
+    blocks_read: i64,
+    index_blocks_hit: u64,
+    index_blocks_read: u64,
+}
+
+impl ScanStats {
+    pub fn start() -> ScanMark {
+        let (blocks_hit, blocks_read) = shared_blocks();
+        ScanMark {
+            started: Instant::now(),
+            blocks_hit,
+            blocks_read,
+            index_blocks_hit: INDEX_BLOCKS_HIT.load(Ordering::Relaxed),
+            index_blocks_read: INDEX_BLOCKS_READ.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn stop(&mut self, mark: ScanMark) {
+        let (blocks_hit, blocks_read) = shared_blocks();
+        self.elapsed += mark.started.elapsed();
+        self.blocks_hit += (blocks_hit - mark.blocks_hit).max(0) as u64;
+        self.blocks_read += (blocks_read - mark.blocks_read).max(0) as u64;
+        self.index_blocks_hit += INDEX_BLOCKS_HIT.load(Ordering::Relaxed) - mark.index_blocks_hit;
+        self.index_blocks_read +=
+            INDEX_BLOCKS_READ.load(Ordering::Relaxed) - mark.index_blocks_read;
+    }
+}
+
+/// Count whether `read_buffer`, which reads one block of a BM25 index, found it in the buffer
+/// cache or had to read it in.
+#[inline]
+pub fn count_index_block<T>(read_buffer: impl FnOnce() -> T) -> T {
+    let (blocks_hit, blocks_read) = shared_blocks();
+    let buffer = read_buffer();
+    let (hit_after, read_after) = shared_blocks();
+    INDEX_BLOCKS_HIT.fetch_add((hit_after - blocks_hit).max(0) as u64, Ordering::Relaxed);
+    INDEX_BLOCKS_READ.fetch_add((read_after - blocks_read).max(0) as u64, Ordering::Relaxed);
+    buffer
+}
+
+fn shared_blocks() -> (i64, i64) {
+    unsafe {
+        let usage = &*std::ptr::addr_of!(pg_sys::pgBufferUsage);
+        (usage.shared_blks_hit, usage.shared_blks_read)
+    }
+}
+
+/// Add a scan to the statistics of `index_oid`.  A parallel scan is only counted as a query, and
+/// timed, by its leader, but the documents and blocks of every worker are added up.
+pub fn record_scan(index_oid: pg_sys::Oid, scan: &ScanStats) {
+    let count_query = unsafe { pg_sys::ParallelWorkerNumber == -1 };
+    record(index_oid, |stats| stats.record(scan, count_query));
+}
+
+/// Add the `segments` that a writer wrote, with `docs` documents in all, to the statistics of
+/// `index_oid`.
+pub fn record_insert(index_oid: pg_sys::Oid, segments: u64, docs: u64) {
+    record(index_oid, |stats| {
+        stats.segments_written += segments;
+        stats.docs_indexed += docs;
+    });
+}
+
+/// Add a merge of `segments` segments into one to the statistics of `index_oid`.
+pub fn record_merge(index_oid: pg_sys::Oid, segments: u64) {
+    record(index_oid, |stats| {
+        stats.merges += 1;
+        stats.segments_merged += segments;
+    });
+}
+
+/// Add a lookup of one of the per-connection caches of `index_oid`'s searches.
+pub fn record_cache_lookup(index_oid: pg_sys::Oid, hit: bool) {
+    record(index_oid, |stats| {
+        if hit {
+            stats.cache_hits += 1;
+        } else {
+            stats.cache_misses += 1;
+        }
+    });
+}
+
+fn record(index_oid: pg_sys::Oid, update: impl FnOnce(&mut IndexStats)) {
+    if !ENABLED.load(Ordering::Relaxed) || !gucs::track_index_stats() {
+        return;
+    }
+    let database_oid = unsafe { pg_sys::MyDatabaseId };
+
+    let mut table = INDEX_STATS.exclusive();
+    let len = table.len;
+    if let Some(stats) = table.entries[..len]
+        .iter_mut()
+        .find(|stats| stats.database_oid == database_oid && stats.index_oid == index_oid)
+    {
+        update(stats);
+    } else if len < MAX_TRACKED_INDEXES {
+        let mut stats = IndexStats::new(database_oid, index_oid);
+        update(&mut stats);
+        table.entries[len] = stats;
+        table.len += 1;
+    }
+}
+
+/// The statistics of every index in the current database that has been searched since they were
+/// last reset.
+pub fn database_stats() -> Vec<IndexStats> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return vec![];
+    }
+    let database_oid = unsafe { pg_sys::MyDatabaseId };
+    let table = INDEX_STATS.share();
+    table.entries[..table.len]
+        .iter()
+        .filter(|stats| stats.database_oid == database_oid)
+        .copied()
+        .collect()
+}
+
+/// Forget the statistics of `index_oid` in the current database, or of every index in every
+/// database if it's [`None`].
+pub fn reset(index_oid: Option<pg_sys::Oid>) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let database_oid = unsafe { pg_sys::MyDatabaseId };
+    let mut table = INDEX_STATS.exclusive();
+    match index_oid {
+        None => table.len = 0,
+        Some(index_oid) => {
+            let len = table.len;
+            if let Some(position) = table.entries[..len].iter().position(|stats| {
+                stats.database_oid == database_oid && stats.index_oid == index_oid
+            }) {
+                table.entries.swap(position, len - 1);
+                table.len -= 1;
+            }
+        }
+    }
+}
+
+fn latency_bucket(elapsed: Duration) -> usize {
+    match elapsed.as_micros() {
+        0 => 0,
+        micros => ((micros as f64).log2() * BUCKETS_PER_DOUBLING) as usize + 1,
+    }
+    .min(LATENCY_BUCKETS - 1)
+}
+
+fn bucket_upper_bound(bucket: usize) -> Duration {
+    Duration::from_secs_f64(2f64.powf(bucket as f64 / BUCKETS_PER_DOUBLING) / 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_latency_bucket_bounds() {
+        for micros in [1, 2, 3, 100, 1_500, 1_000_000] {
+            let elapsed = Duration::from_micros(micros);
+            let bucket = latency_bucket(elapsed);
+            assert!(bucket_upper_bound(bucket) >= elapsed, "{micros}");
+            assert!(bucket_upper_bound(bucket - 1) <= elapsed, "{micros}");
+        }
+        assert_eq!(latency_bucket(Duration::ZERO), 0);
+        assert_eq!(latency_bucket(Duration::MAX), LATENCY_BUCKETS - 1);
+    }
+
+    #[rstest]
+    fn test_percentile_time() {
+        let mut stats = IndexStats::new(pg_sys::InvalidOid, pg_sys::InvalidOid);
+        assert_eq!(stats.percentile_time(0.95), None);
+        for millis in 1..=100 {
+            stats.record(
+                &ScanStats {
+                    elapsed: Duration::from_millis(millis),
+                    ..Default::default()
+                },
+                true,
+            );
+        }
+        let p95 = stats.percentile_time(0.95).unwrap();
+        assert!(p95 >= Duration::from_millis(95), "{p95:?}");
+        assert!(p95 < Duration::from_millis(120), "{p95:?}");
+        assert_eq!(stats.mean_time(), Some(Duration::from_micros(50_500)));
+    }
+
+    #[rstest]
+    fn test_cumulative_latencies() {
+        let mut stats = IndexStats::new(pg_sys::InvalidOid, pg_sys::InvalidOid);
+        for millis in [1, 10, 100, 1000] {
+            stats.record(
+                &ScanStats {
+                    elapsed: Duration::from_millis(millis),
+                    ..Default::default()
+                },
+                true,
+            );
+        }
+        let bounds = [
+            Duration::from_millis(5),
+            Duration::from_millis(500),
+            Duration::MAX,
+        ];
+        assert_eq!(stats.cumulative_latencies(&bounds), vec![1, 3, 4]);
+    }
+}
@@ -145,16 +145,29 @@ pub struct DeleteEntry {
     pub num_deleted_docs: u32,
 }
 
+/// 2025-01-01T00:00:00Z, before which no [`SegmentMetaEntry::created_at`] was recorded.
+const CREATED_AT_RECORDED_SINCE: u32 = 1_735_689_600;
+
+/// The current time, as a [`SegmentMetaEntry::created_at`].
+pub fn segment_created_at_now() -> u32 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs().min(u32::MAX as u64) as u32)
+        .unwrap_or(0)
+}
+
 /// Metadata for tracking alive segments
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SegmentMetaEntry {
     pub segment_id: SegmentId,
     pub max_doc: u32,
 
-    /// this is the unused space that was once where we stored the `xmin` transaction id that created this entry
-    #[doc(hidden)]
-    #[serde(alias = "xmin")]
-    pub _unused: pg_sys::TransactionId,
+    /// When the segment was created, in seconds since the unix epoch, or zero if unknown.
+    ///
+    /// This space once held the `xmin` transaction id that created this entry, which older
+    /// segments may still carry -- see [`SegmentMetaEntry::created_at`].
+    #[serde(alias = "xmin", alias = "_unused")]
+    pub created_at: u32,
 
     /// If set to [`pg_sys::FrozenTransactionId`] then this entry has been deleted via a Tantivy merge
     /// and a) is no longer visible to any transaction and b) is subject to being garbage collected
@@ -175,7 +188,7 @@ impl Default for SegmentMetaEntry {
         Self {
             segment_id: SegmentId::generate_random(),
             max_doc: Default::default(),
-            _unused: pg_sys::InvalidTransactionId,
+            created_at: 0,
             xmax: pg_sys::InvalidTransactionId,
             postings: None,
             positions: None,
@@ -208,6 +221,14 @@ impl SegmentMetaEntry {
         0
     }
 
+    /// When the segment was created, in seconds since the unix epoch.
+    ///
+    /// Segments written before creation times were recorded may hold an old transaction id
+    /// instead, which is recognized as predating the feature and reported as unknown.
+    pub fn created_at(&self) -> Option<u32> {
+        (self.created_at >= CREATED_AT_RECORDED_SINCE).then_some(self.created_at)
+    }
+
     pub fn num_docs(&self) -> usize {
         self.max_doc as usize - self.num_deleted_docs()
     }
@@ -218,6 +239,14 @@ impl SegmentMetaEntry {
             .unwrap_or(0)
     }
 
+    /// The fraction of this segment's documents that are deleted.
+    pub fn deleted_fraction(&self) -> f64 {
+        match self.max_doc {
+            0 => 0.0,
+            max_doc => self.num_deleted_docs() as f64 / max_doc as f64,
+        }
+    }
+
     pub fn file_entry(&self, path: &Path) -> Option<FileEntry> {
         for (file_path, (file_entry, _)) in self.get_component_paths().zip(self.file_entries()) {
             if path == file_path {
@@ -1,4 +1,5 @@
 use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::stats;
 use crate::postgres::storage::block::{BM25PageSpecialData, PgItem};
 use crate::postgres::storage::fsm::FreeSpaceManager;
 use crate::postgres::storage::metadata::MetaPage;
@@ -194,26 +195,83 @@ impl Buffer {
     }
 }
 
+/// The generic WAL record of the changes made to a [`BufferMut`]'s page, so that they're replayed
+/// by crash recovery and on physical replicas.
+///
+/// Changes are made to a copy of the page, which [`pg_sys::GenericXLogFinish`] diffs against the
+/// buffer to build the record, before copying it into the buffer and marking the buffer dirty.
+#[derive(Debug)]
+struct PageWal {
+    state: *mut pg_sys::GenericXLogState,
+    page: pg_sys::Page,
+}
+
 #[derive(Debug)]
 pub struct BufferMut {
     dirty: bool,
+    rel: pg_sys::Relation,
+    wal: Option<PageWal>,
     inner: Buffer,
 }
 
+// the relation and WAL state are only ever touched by the backend that locked the buffer
+unsafe impl Send for BufferMut {}
+unsafe impl Sync for BufferMut {}
+
 impl Drop for BufferMut {
     fn drop(&mut self) {
         unsafe {
-            if crate::postgres::utils::IsTransactionState() && self.dirty {
-                pg_sys::MarkBufferDirty(self.inner.pg_buffer);
+            if !crate::postgres::utils::IsTransactionState() {
+                // the WAL state's memory goes away with the aborted transaction's
+                return;
+            }
+
+            match self.wal.take() {
+                Some(wal) if self.dirty => {
+                    pg_sys::GenericXLogFinish(wal.state);
+                }
+                Some(wal) => pg_sys::GenericXLogAbort(wal.state),
+                None if self.dirty => pg_sys::MarkBufferDirty(self.inner.pg_buffer),
+                None => {}
             }
         }
     }
 }
 
 impl BufferMut {
+    fn new(rel: pg_sys::Relation, inner: Buffer) -> Self {
+        Self {
+            dirty: false,
+            rel,
+            wal: None,
+            inner,
+        }
+    }
+
+    /// The page that changes are made to, which is copied into the buffer, and WAL-logged, when
+    /// this [`BufferMut`] is dropped.
+    fn wal_page(&mut self, flags: u32) -> pg_sys::Page {
+        let rel = self.rel;
+        let pg_buffer = self.inner.pg_buffer;
+        self.wal
+            .get_or_insert_with(|| unsafe {
+                // the state outlives whatever memory context this buffer happened to be locked in
+                let state = pgrx::PgMemoryContexts::TopTransactionContext
+                    .switch_to(|_| pg_sys::GenericXLogStart(rel));
+                let page = pg_sys::GenericXLogRegisterBuffer(state, pg_buffer, flags as _);
+                PageWal { state, page }
+            })
+            .page
+    }
+
     pub fn init_page(&mut self) -> PageMut {
         let page_size = self.page_size();
-        let page = self.page_mut();
+        // a new page's previous contents don't matter, so it's logged in full rather than diffed
+        let pg_page = self.wal_page(pg_sys::GENERIC_XLOG_FULL_IMAGE);
+        let page = PageMut {
+            buffer: self,
+            pg_page,
+        };
         page.buffer.dirty = true;
         unsafe {
             pg_sys::PageInit(page.pg_page, page_size, size_of::<BM25PageSpecialData>());
@@ -227,16 +285,18 @@ impl BufferMut {
 
     #[allow(dead_code)]
     pub fn page(&self) -> Page {
-        unsafe {
-            Page {
-                pg_page: pg_sys::BufferGetPage(self.inner.pg_buffer),
-                _buffer: &self.inner,
-            }
+        let pg_page = match &self.wal {
+            Some(wal) => wal.page,
+            None => unsafe { pg_sys::BufferGetPage(self.inner.pg_buffer) },
+        };
+        Page {
+            pg_page,
+            _buffer: &self.inner,
         }
     }
 
     pub fn page_mut(&mut self) -> PageMut {
-        let pg_page = unsafe { pg_sys::BufferGetPage(self.inner.pg_buffer) };
+        let pg_page = self.wal_page(0);
         PageMut {
             buffer: self,
             pg_page,
@@ -589,10 +649,7 @@ impl BufferManager {
             .unwrap_or_else(|| self.rbufacc.new_buffer());
 
         block_tracker::track!(Write, pg_buffer);
-        BufferMut {
-            dirty: false,
-            inner: Buffer { pg_buffer },
-        }
+        BufferMut::new(self.rbufacc.rel().as_ptr(), Buffer { pg_buffer })
     }
 
     /// Like [`new_buffer`], but returns an iterator of buffers instead.
@@ -610,10 +667,7 @@ impl BufferManager {
         let mut fsm_blocknos = self.fsm().drain(self, npages).map(move |blockno| {
             let pg_buffer = buffer_access.get_buffer(blockno, Some(pg_sys::BUFFER_LOCK_EXCLUSIVE));
             block_tracker::track!(Write, pg_buffer);
-            BufferMut {
-                dirty: false,
-                inner: Buffer { pg_buffer },
-            }
+            BufferMut::new(buffer_access.rel().as_ptr(), Buffer { pg_buffer })
         });
 
         let bman = self.clone();
@@ -633,13 +687,11 @@ impl BufferManager {
             if new_buffers.is_none() {
                 // the fsm didn't give us all the buffers we asked for, so we need to get the rest
                 // by extending the relation with brand new buffers
+                let rel = bman.buffer_access().rel().as_ptr();
                 new_buffers = Some(bman.buffer_access().new_buffers(remaining_from_fsm).map(
                     move |pg_buffer| {
                         block_tracker::track!(Write, pg_buffer);
-                        BufferMut {
-                            dirty: false,
-                            inner: Buffer { pg_buffer },
-                        }
+                        BufferMut::new(rel, Buffer { pg_buffer })
                     },
                 ));
             }
@@ -652,13 +704,16 @@ impl BufferManager {
 
     pub fn pinned_buffer(&self, blockno: pg_sys::BlockNumber) -> PinnedBuffer {
         block_tracker::track!(Pinned, pg_buffer);
-        PinnedBuffer::new(self.rbufacc.get_buffer(blockno, None))
+        PinnedBuffer::new(stats::count_index_block(|| {
+            self.rbufacc.get_buffer(blockno, None)
+        }))
     }
 
     pub fn get_buffer(&self, blockno: pg_sys::BlockNumber) -> Buffer {
-        let pg_buffer = self
-            .rbufacc
-            .get_buffer(blockno, Some(pg_sys::BUFFER_LOCK_SHARE));
+        let pg_buffer = stats::count_index_block(|| {
+            self.rbufacc
+                .get_buffer(blockno, Some(pg_sys::BUFFER_LOCK_SHARE))
+        });
 
         block_tracker::track!(Read, pg_buffer);
         Buffer::new(pg_buffer)
@@ -678,13 +733,13 @@ impl BufferManager {
 
     pub fn get_buffer_mut(&mut self, blockno: pg_sys::BlockNumber) -> BufferMut {
         block_tracker::track!(Write, pg_buffer);
-        BufferMut {
-            dirty: false,
-            inner: Buffer::new(
+        BufferMut::new(
+            self.rbufacc.rel().as_ptr(),
+            Buffer::new(
                 self.rbufacc
                     .get_buffer(blockno, Some(pg_sys::BUFFER_LOCK_EXCLUSIVE)),
             ),
-        }
+        )
     }
 
     ///
@@ -706,10 +761,10 @@ impl BufferManager {
             let pg_buffer = self.rbufacc.get_buffer(blockno, None);
             if pg_sys::ConditionalLockBuffer(pg_buffer) {
                 block_tracker::track!(Conditional, pg_buffer);
-                Some(BufferMut {
-                    dirty: false,
-                    inner: Buffer::new(pg_buffer),
-                })
+                Some(BufferMut::new(
+                    self.rbufacc.rel().as_ptr(),
+                    Buffer::new(pg_buffer),
+                ))
             } else {
                 pg_sys::ReleaseBuffer(pg_buffer);
                 None
@@ -722,10 +777,7 @@ impl BufferManager {
             let pg_buffer = self.rbufacc.get_buffer(blockno, None);
             block_tracker::track!(Cleanup, pg_buffer);
             pg_sys::LockBufferForCleanup(pg_buffer);
-            BufferMut {
-                dirty: false,
-                inner: Buffer::new(pg_buffer),
-            }
+            BufferMut::new(self.rbufacc.rel().as_ptr(), Buffer::new(pg_buffer))
         }
     }
 
@@ -737,10 +789,10 @@ impl BufferManager {
             let pg_buffer = self.rbufacc.get_buffer(blockno, None);
             if pg_sys::ConditionalLockBufferForCleanup(pg_buffer) {
                 block_tracker::track!(ConditionalCleanup, pg_buffer);
-                Some(BufferMut {
-                    dirty: false,
-                    inner: Buffer::new(pg_buffer),
-                })
+                Some(BufferMut::new(
+                    self.rbufacc.rel().as_ptr(),
+                    Buffer::new(pg_buffer),
+                ))
             } else {
                 pg_sys::ReleaseBuffer(pg_buffer);
                 None
@@ -759,10 +811,7 @@ pub fn init_new_buffer(rel: &PgSearchRelation) -> BufferMut {
     let rbacc = RelationBufferAccess::open(rel);
     let pg_buffer = rbacc.new_buffer();
 
-    let mut buffer = BufferMut {
-        dirty: false,
-        inner: Buffer { pg_buffer },
-    };
+    let mut buffer = BufferMut::new(rel.as_ptr(), Buffer { pg_buffer });
     let mut page = buffer.init_page();
     let special = page.special_mut::<BM25PageSpecialData>();
     special.next_blockno = pg_sys::InvalidBlockNumber;
@@ -267,13 +267,32 @@ impl FreeSpaceManager {
 /// The "visibility horizon" is the oldest transaction id, across the Postgres cluster, that can see
 /// blocks in the FSM.
 ///
-/// We use the current transaction id.
+/// By default, we use the current transaction id: blocks are only added to the FSM once no local
+/// backend has them pinned.  Queries on a hot standby don't hold pins on the primary though, so
+/// for an index with `standby_searches` on, a block is only recyclable once the transaction that
+/// freed it precedes the oldest transaction that any snapshot, including those that standbys
+/// report with `hot_standby_feedback`, might still consider running.  That also means any
+/// long-running transaction on the primary holds off reusing the index's freed blocks, which is
+/// why it's opt-in.
 ///
 /// When being drained, the FSM compares each block's stored xid with this value, ensuring the stored
 /// value precedes or equals this one, before it is considered recyclable.
 #[inline(always)]
-fn visibility_horizon() -> pg_sys::TransactionId {
-    unsafe { pg_sys::GetCurrentTransactionId() }
+fn visibility_horizon(indexrel: &PgSearchRelation) -> pg_sys::TransactionId {
+    unsafe {
+        if !indexrel.options().standby_searches() {
+            return pg_sys::GetCurrentTransactionId();
+        }
+
+        // what `TransactionIdRetreat()` does, to make the oldest non-removable xid exclusive
+        let mut horizon = pg_sys::GetOldestNonRemovableTransactionId(std::ptr::null_mut());
+        loop {
+            horizon = pg_sys::TransactionId::from_inner(horizon.into_inner().wrapping_sub(1));
+            if horizon >= pg_sys::FirstNormalTransactionId {
+                return horizon;
+            }
+        }
+    }
 }
 
 /// Draining iterator over FSM entries. As entries are yielded, they are
@@ -291,7 +310,7 @@ struct FSMDrainIter {
 impl FSMDrainIter {
     #[inline]
     fn new(fsm: &FreeSpaceManager, bman: &BufferManager) -> Self {
-        let xid_horizon = visibility_horizon();
+        let xid_horizon = visibility_horizon(bman.buffer_access().rel());
         Self {
             bman: bman.clone(),
             current_blockno: fsm.start_blockno,
@@ -0,0 +1,155 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! The write journal, which keeps track of the segment files that are being written but that no
+//! [`SegmentMetaEntry`] refers to yet.
+//!
+//! A file is recorded in the journal as soon as it's opened for writing, and forgotten just
+//! before the segment it belongs to is saved to the segment metas list.  So any entry that's
+//! still in the journal once the transaction that wrote it has ended, whether it aborted, its
+//! backend crashed, or Tantivy abandoned the file, is an orphan that nothing else will ever free.
+//! [`WriteJournal::recover()`] returns those files' blocks to the FSM.
+//!
+//! Forgetting a file before it's referenced means that a crash in between leaks its blocks rather
+//! than letting them be freed twice.
+//!
+//! [`SegmentMetaEntry`]: crate::postgres::storage::block::SegmentMetaEntry
+
+use crate::api::HashSet;
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::storage::block::{MVCCEntry, PgItem};
+use crate::postgres::storage::buffer::BufferManager;
+use crate::postgres::storage::linked_items::RetainItem;
+use crate::postgres::storage::{LinkedBytesList, LinkedItemList};
+use pgrx::{pg_sys, StringInfo};
+use serde::{Deserialize, Serialize};
+use std::slice::from_raw_parts;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub pid: i32,
+
+    /// The top-level transaction id that's writing the file
+    pub xid: pg_sys::TransactionId,
+
+    /// The header block of the file's [`LinkedBytesList`]
+    pub header_blockno: pg_sys::BlockNumber,
+}
+
+impl From<PgItem> for JournalEntry {
+    fn from(value: PgItem) -> Self {
+        let PgItem(item, size) = value;
+        let (decoded, _) = bincode::serde::decode_from_slice(
+            unsafe { from_raw_parts(item as *const u8, size) },
+            bincode::config::legacy(),
+        )
+        .expect("expected to deserialize valid JournalEntry");
+        decoded
+    }
+}
+
+impl From<JournalEntry> for PgItem {
+    fn from(value: JournalEntry) -> Self {
+        let mut buf = StringInfo::new();
+        let len = bincode::serde::encode_into_std_write(value, &mut buf, bincode::config::legacy())
+            .expect("expected to serialize valid JournalEntry");
+        PgItem(buf.into_char_ptr() as pg_sys::Item, len as pg_sys::Size)
+    }
+}
+
+impl MVCCEntry for JournalEntry {
+    fn pintest_blockno(&self) -> pg_sys::BlockNumber {
+        pg_sys::InvalidBlockNumber
+    }
+
+    unsafe fn visible(&self) -> bool {
+        true
+    }
+
+    unsafe fn recyclable(&self, _: &mut BufferManager) -> bool {
+        unsafe {
+            self.xid != pg_sys::InvalidTransactionId && !pg_sys::TransactionIdIsInProgress(self.xid)
+        }
+    }
+
+    unsafe fn mergeable(&self) -> bool {
+        unimplemented!("`MVCCEntry::mergeable()` is not supported for `JournalEntry")
+    }
+}
+
+pub struct WriteJournal {
+    entries: LinkedItemList<JournalEntry>,
+    bman: BufferManager,
+}
+
+impl WriteJournal {
+    pub fn open(indexrel: &PgSearchRelation, header_blockno: pg_sys::BlockNumber) -> Self {
+        Self {
+            entries: LinkedItemList::<JournalEntry>::open(indexrel, header_blockno),
+            bman: BufferManager::new(indexrel),
+        }
+    }
+
+    /// Record that this transaction is writing the file whose [`LinkedBytesList`] starts at
+    /// `header_blockno`.
+    pub unsafe fn record(&mut self, header_blockno: pg_sys::BlockNumber) {
+        // the top-level transaction, because a writer can outlive the subtransaction that opened it
+        let entry = JournalEntry {
+            pid: pg_sys::MyProcPid,
+            xid: pg_sys::GetTopTransactionId(),
+            header_blockno,
+        };
+        self.entries.add_items(&[entry], None);
+    }
+
+    /// Forget the files starting at `header_blocknos`, which are about to be referenced by the
+    /// segment metas list.
+    pub unsafe fn forget(&mut self, header_blocknos: &HashSet<pg_sys::BlockNumber>) {
+        if header_blocknos.is_empty() {
+            return;
+        }
+
+        self.entries.retain(|_, entry| {
+            if header_blocknos.contains(&entry.header_blockno) {
+                RetainItem::Remove(entry)
+            } else {
+                RetainItem::Retain
+            }
+        });
+    }
+
+    /// Return the blocks of every file whose writer is gone without having forgotten it to the
+    /// FSM, returning how many files there were.
+    pub unsafe fn recover(&mut self) -> usize {
+        // each entry is only ever removed by one backend, so its blocks are only freed once
+        let orphans = self.entries.garbage_collect();
+        if orphans.is_empty() {
+            return 0;
+        }
+
+        let indexrel = self.bman.buffer_access().rel().clone();
+        let blocks = orphans
+            .iter()
+            .flat_map(|entry| {
+                LinkedBytesList::open(&indexrel, entry.header_blockno).linked_blocks()
+            })
+            .collect::<Vec<_>>();
+        self.bman.fsm().extend(&mut self.bman, blocks.into_iter());
+
+        orphans.len()
+    }
+}
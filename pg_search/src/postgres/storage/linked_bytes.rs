@@ -19,6 +19,7 @@ use super::block::{bm25_max_free_space, BM25PageSpecialData, LinkedList, LinkedL
 use crate::postgres::rel::PgSearchRelation;
 use crate::postgres::storage::blocklist;
 use crate::postgres::storage::buffer::{init_new_buffer, BufferManager, PageHeaderMethods};
+use crate::postgres::storage::throttle::throttle;
 use anyhow::Result;
 use pgrx::{check_for_interrupts, pg_sys};
 use std::cmp::min;
@@ -76,6 +77,8 @@ pub struct LinkedBytesListWriter {
 
 impl LinkedBytesListWriter {
     pub unsafe fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        throttle(bytes.len());
+
         let mut data_cursor = Cursor::new(bytes);
         let mut bytes_written = 0;
 
@@ -312,6 +315,22 @@ impl LinkedBytesList {
         bytes
     }
 
+    /// Read every block holding this list's bytes into the buffer cache, returning how many
+    /// blocks were read.
+    pub unsafe fn prewarm(&self) -> usize {
+        let (mut blockno, mut buffer) = self.get_start_blockno();
+        let mut nblocks = 0;
+
+        while blockno != pg_sys::InvalidBlockNumber {
+            check_for_interrupts!();
+            buffer = self.bman.get_buffer_exchange(blockno, buffer);
+            blockno = buffer.page().special::<BM25PageSpecialData>().next_blockno;
+            nblocks += 1;
+        }
+
+        nblocks
+    }
+
     /// Returns a lazily-evaluated iterator of all the [`pg_sys::BlockNumber`]s used by this [`LinkedBytesList`].
     ///
     /// There's no locking per-se that happens while the returned Iterator emits block numbers.  It's
@@ -353,6 +372,32 @@ impl LinkedBytesList {
             }))
     }
 
+    /// Like [`freeable_blocks()`], but found by following the list's chain of blocks rather than
+    /// its [`BlockList`], which a list that was never finalized, such as one abandoned by a crashed
+    /// backend part-way through being written, doesn't have.
+    ///
+    /// The same caveats apply: no other backend may be using any of these blocks.
+    pub unsafe fn linked_blocks(&self) -> Vec<pg_sys::BlockNumber> {
+        let metadata = self.get_linked_list_data();
+        let mut blocks = vec![self.header_blockno];
+
+        let mut blockno = metadata.start_blockno;
+        while blockno != pg_sys::InvalidBlockNumber {
+            check_for_interrupts!();
+            blocks.push(blockno);
+            blockno = self.bman.get_buffer(blockno).page().next_blockno();
+        }
+
+        // a finalized list also has the blocks of its BlockList
+        let mut blockno = metadata.blocklist_start;
+        while blockno != pg_sys::InvalidBlockNumber {
+            blocks.push(blockno);
+            blockno = self.bman.get_buffer(blockno).page().next_blockno();
+        }
+
+        blocks
+    }
+
     /// Return all the allocated blocks used by this [`LinkedBytesList`] back to the
     /// Free Space Map behind this index.
     pub unsafe fn return_to_fsm(self) {
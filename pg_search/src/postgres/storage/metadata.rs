@@ -21,10 +21,23 @@ use crate::postgres::storage::buffer::{
     init_new_buffer, Buffer, BufferManager, BufferMut, PinnedBuffer,
 };
 use crate::postgres::storage::fsm::FreeSpaceManager;
+use crate::postgres::storage::journal::{JournalEntry, WriteJournal};
 use crate::postgres::storage::merge::{MergeLock, VacuumList, VacuumSentinel};
+use crate::postgres::storage::tenants::{SegmentTenantEntry, SegmentTenants};
 use crate::postgres::storage::{LinkedBytesList, LinkedItemList};
+use anyhow::Result;
 use pgrx::pg_sys;
 
+/// The version of the on-disk format of the [`MetaPage`] and the [`SegmentMetaEntry`]s it points
+/// to, which [`MetaPage::upgrade()`] migrates older indexes up to.
+///
+/// - `0`: indexes created before the format was versioned.  Their metapage may rely on the legacy
+///   hardcoded block numbers, and their segments' `created_at` may hold the `xmin` of the
+///   transaction that wrote them.
+/// - `1`: every block number is recorded on the metapage, and `created_at` is either a creation
+///   time or zero.
+pub const INDEX_FORMAT_VERSION: u32 = 1;
+
 /// The metadata stored on the [`Metadata`] page
 #[derive(Debug, Copy, Clone)]
 #[repr(C, packed)]
@@ -59,6 +72,17 @@ pub struct MetaPageData {
 
     /// The block where our FSM starts
     fsm: pg_sys::BlockNumber,
+
+    /// The [`INDEX_FORMAT_VERSION`] the index is written in, which is zero for indexes created
+    /// before it was recorded
+    format_version: u32,
+
+    /// The header block for the [`LinkedItemList<JournalEntry>`] of segment files being written
+    write_journal: pg_sys::BlockNumber,
+
+    /// The header block for the [`LinkedItemList<SegmentTenantEntry>`] of the segments that belong to a
+    /// single tenant
+    segment_tenants: pg_sys::BlockNumber,
 }
 
 /// Provides read access to the metadata page
@@ -93,6 +117,10 @@ impl MetaPage {
             metadata.settings_start = LinkedBytesList::create_without_fsm(indexrel);
             metadata.segment_metas_start =
                 LinkedItemList::<SegmentMetaEntry>::create_without_fsm(indexrel);
+            metadata.format_version = INDEX_FORMAT_VERSION;
+            metadata.write_journal = LinkedItemList::<JournalEntry>::create_without_fsm(indexrel);
+            metadata.segment_tenants =
+                LinkedItemList::<SegmentTenantEntry>::create_without_fsm(indexrel);
         }
     }
 
@@ -102,6 +130,14 @@ impl MetaPage {
         let page = buffer.page();
         let metadata = page.contents::<MetaPageData>();
 
+        let format_version = metadata.format_version;
+        if format_version > INDEX_FORMAT_VERSION {
+            pgrx::error!(
+                "index `{}` is in on-disk format version {format_version}, but this version of pg_search only reads up to version {INDEX_FORMAT_VERSION}",
+                indexrel.name()
+            );
+        }
+
         // Skip create_index_list because it doesn't need to be initialized yet
         //
         // also skip:
@@ -115,13 +151,17 @@ impl MetaPage {
         let may_need_init = !block_number_is_valid(metadata.active_vacuum_list)
             || !block_number_is_valid(metadata.ambulkdelete_sentinel)
             || !block_number_is_valid(metadata.merge_lock)
-            || !block_number_is_valid(metadata.fsm);
+            || !block_number_is_valid(metadata.fsm)
+            || !block_number_is_valid(metadata.write_journal)
+            || !block_number_is_valid(metadata.segment_tenants);
 
         drop(buffer);
 
         // If any of the fields are not initialized, we need to initialize them
         // We swap our share lock for an exclusive lock
-        if may_need_init {
+        //
+        // A hot standby can't, but only writers, which it doesn't have, need these fields
+        if may_need_init && unsafe { !pg_sys::RecoveryInProgress() } {
             let mut buffer = bman.get_buffer_mut(METAPAGE);
             let mut page = buffer.page_mut();
             let metadata = page.contents_mut::<MetaPageData>();
@@ -142,6 +182,16 @@ impl MetaPage {
                 if !block_number_is_valid(metadata.fsm) {
                     metadata.fsm = FreeSpaceManager::create(indexrel);
                 }
+
+                if !block_number_is_valid(metadata.write_journal) {
+                    metadata.write_journal =
+                        LinkedItemList::<JournalEntry>::create_without_fsm(indexrel);
+                }
+
+                if !block_number_is_valid(metadata.segment_tenants) {
+                    metadata.segment_tenants =
+                        LinkedItemList::<SegmentTenantEntry>::create_without_fsm(indexrel);
+                }
             }
 
             Self {
@@ -181,6 +231,77 @@ impl MetaPage {
         assert!(block_number_is_valid(self.data.fsm));
         self.data.fsm
     }
+
+    pub fn write_journal(&self) -> WriteJournal {
+        assert!(block_number_is_valid(self.data.write_journal));
+        WriteJournal::open(self.bman.buffer_access().rel(), self.data.write_journal)
+    }
+
+    /// The tenants of the index's segments, or [`None`] on a hot standby that hasn't yet replayed
+    /// the list's creation
+    pub fn segment_tenants(&self) -> Option<SegmentTenants> {
+        block_number_is_valid(self.data.segment_tenants).then(|| {
+            SegmentTenants::open(self.bman.buffer_access().rel(), self.data.segment_tenants)
+        })
+    }
+
+    pub fn format_version(&self) -> u32 {
+        self.data.format_version
+    }
+
+    /// Migrate an index written in an older on-disk format to [`INDEX_FORMAT_VERSION`] in place,
+    /// returning the version it was in before.
+    ///
+    /// The caller must hold a lock that keeps out concurrent writers.
+    pub unsafe fn upgrade(&mut self) -> Result<u32> {
+        let from_version = self.data.format_version;
+        if from_version >= INDEX_FORMAT_VERSION {
+            return Ok(from_version);
+        }
+
+        // version 0 segments may carry an `xmin` where their creation time belongs
+        let mut segment_metas = self.segment_metas();
+        let stale = segment_metas
+            .list()
+            .into_iter()
+            .filter(|entry| entry.created_at != 0 && entry.created_at().is_none())
+            .collect::<Vec<_>>();
+        if !stale.is_empty() {
+            let mut segment_metas = segment_metas.atomically();
+            for entry in stale {
+                segment_metas.remove_item(|candidate| candidate == &entry)?;
+                segment_metas.add_items(
+                    &[SegmentMetaEntry {
+                        created_at: 0,
+                        ..entry
+                    }],
+                    None,
+                );
+            }
+            segment_metas.commit();
+        }
+
+        // and record the blocks that they still find at their legacy hardcoded locations
+        let mut buffer = self.bman.get_buffer_mut(METAPAGE);
+        let mut page = buffer.page_mut();
+        let metadata = page.contents_mut::<MetaPageData>();
+        if metadata.cleanup_lock == 0 {
+            metadata.cleanup_lock = Self::LEGACY_CLEANUP_LOCK;
+        }
+        if metadata.schema_start == 0 {
+            metadata.schema_start = Self::LEGACY_SCHEMA_START;
+        }
+        if metadata.settings_start == 0 {
+            metadata.settings_start = Self::LEGACY_SETTINGS_START;
+        }
+        if metadata.segment_metas_start == 0 {
+            metadata.segment_metas_start = Self::LEGACY_SEGMENT_METAS_START;
+        }
+        metadata.format_version = INDEX_FORMAT_VERSION;
+        self.data = *metadata;
+
+        Ok(from_version)
+    }
 }
 
 // legacy hardcoded page support for various index objects
@@ -235,6 +356,18 @@ impl MetaPage {
         LinkedBytesList::open(self.bman.buffer_access().rel(), blockno)
     }
 
+    /// Point the metapage at a new schema list.
+    ///
+    /// The previous list is left as-is, rather than returned to the FSM, because concurrent
+    /// backends may have already read the old `schema_start` and still be reading from it.
+    pub fn replace_schema_bytes(&mut self, schema: &LinkedBytesList) {
+        let mut buffer = self.bman.get_buffer_mut(METAPAGE);
+        let mut page = buffer.page_mut();
+        let metadata = page.contents_mut::<MetaPageData>();
+        metadata.schema_start = schema.header_blockno;
+        self.data.schema_start = schema.header_blockno;
+    }
+
     pub fn settings_bytes(&self) -> LinkedBytesList {
         let blockno = if self.data.settings_start == 0 {
             Self::LEGACY_SETTINGS_START
@@ -244,6 +377,16 @@ impl MetaPage {
         LinkedBytesList::open(self.bman.buffer_access().rel(), blockno)
     }
 
+    /// Point the metapage at a new settings list, leaving the previous one as-is for the same
+    /// reason as [`Self::replace_schema_bytes`].
+    pub fn replace_settings_bytes(&mut self, settings: &LinkedBytesList) {
+        let mut buffer = self.bman.get_buffer_mut(METAPAGE);
+        let mut page = buffer.page_mut();
+        let metadata = page.contents_mut::<MetaPageData>();
+        metadata.settings_start = settings.header_blockno;
+        self.data.settings_start = settings.header_blockno;
+    }
+
     pub fn segment_metas(&self) -> LinkedItemList<SegmentMetaEntry> {
         let blockno = if self.data.segment_metas_start == 0 {
             Self::LEGACY_SEGMENT_METAS_START
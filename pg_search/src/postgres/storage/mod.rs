@@ -94,10 +94,13 @@ pub mod block;
 mod blocklist;
 pub mod buffer;
 pub mod fsm;
+pub mod journal;
 pub mod linked_bytes;
 pub mod linked_items;
 pub mod merge;
 pub mod metadata;
+pub mod tenants;
+pub mod throttle;
 pub mod utils;
 
 pub use self::linked_bytes::{LinkedBytesList, LinkedBytesListWriter};
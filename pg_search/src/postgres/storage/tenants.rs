@@ -0,0 +1,154 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! The segment tenants list, which records the one tenant that every document in a segment
+//! belongs to, for indexes that are partitioned by a `tenant_field`.
+//!
+//! A segment that isn't in the list may hold documents of any tenant, and is searched by every
+//! query.  A segment that is can be skipped by a query that's restricted to another tenant without
+//! being opened at all.
+
+use crate::api::{HashMap, HashSet};
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::storage::block::{MVCCEntry, PgItem};
+use crate::postgres::storage::buffer::BufferManager;
+use crate::postgres::storage::linked_items::RetainItem;
+use crate::postgres::storage::LinkedItemList;
+use pgrx::{pg_sys, StringInfo};
+use serde::{Deserialize, Serialize};
+use std::slice::from_raw_parts;
+use tantivy::index::SegmentId;
+use tantivy::schema::Value;
+
+/// The tenant that a value of the `tenant_field` names.
+///
+/// Documents and queries spell the same tenant with different types, say an `i32` column that's
+/// searched with an `i64`, so numbers are named by their decimal digits.  Values that can't name a
+/// tenant, like floats, don't partition the index.
+pub fn tenant_key<'a>(value: impl Value<'a>) -> Option<String> {
+    if let Some(value) = value.as_str() {
+        Some(value.to_string())
+    } else if let Some(value) = value.as_i64() {
+        Some(value.to_string())
+    } else if let Some(value) = value.as_u64() {
+        Some(value.to_string())
+    } else {
+        value.as_bool().map(|value| value.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SegmentTenantEntry {
+    pub segment_id: SegmentId,
+    pub tenant: String,
+}
+
+impl From<PgItem> for SegmentTenantEntry {
+    fn from(value: PgItem) -> Self {
+        let PgItem(item, size) = value;
+        let (decoded, _) = bincode::serde::decode_from_slice(
+            unsafe { from_raw_parts(item as *const u8, size) },
+            bincode::config::legacy(),
+        )
+        .expect("expected to deserialize valid SegmentTenantEntry");
+        decoded
+    }
+}
+
+impl From<SegmentTenantEntry> for PgItem {
+    fn from(value: SegmentTenantEntry) -> Self {
+        let mut buf = StringInfo::new();
+        let len = bincode::serde::encode_into_std_write(value, &mut buf, bincode::config::legacy())
+            .expect("expected to serialize valid SegmentTenantEntry");
+        PgItem(buf.into_char_ptr() as pg_sys::Item, len as pg_sys::Size)
+    }
+}
+
+impl MVCCEntry for SegmentTenantEntry {
+    fn pintest_blockno(&self) -> pg_sys::BlockNumber {
+        pg_sys::InvalidBlockNumber
+    }
+
+    unsafe fn visible(&self) -> bool {
+        true
+    }
+
+    // entries are only removed along with their segments, by `SegmentTenants::forget()`
+    unsafe fn recyclable(&self, _: &mut BufferManager) -> bool {
+        false
+    }
+
+    unsafe fn mergeable(&self) -> bool {
+        unimplemented!("`MVCCEntry::mergeable()` is not supported for `SegmentTenantEntry")
+    }
+}
+
+pub struct SegmentTenants {
+    entries: LinkedItemList<SegmentTenantEntry>,
+}
+
+impl SegmentTenants {
+    pub fn open(indexrel: &PgSearchRelation, header_blockno: pg_sys::BlockNumber) -> Self {
+        Self {
+            entries: LinkedItemList::<SegmentTenantEntry>::open(indexrel, header_blockno),
+        }
+    }
+
+    /// Record that every document in `segment_id` belongs to `tenant`.
+    pub unsafe fn tag(&mut self, segment_id: SegmentId, tenant: String) {
+        self.entries
+            .add_items(&[SegmentTenantEntry { segment_id, tenant }], None);
+    }
+
+    /// The tenant of each segment that has one.
+    pub unsafe fn list(&self) -> HashMap<SegmentId, String> {
+        self.entries
+            .list()
+            .into_iter()
+            .map(|entry| (entry.segment_id, entry.tenant))
+            .collect()
+    }
+
+    /// The tenant that all of `segment_ids` belong to, if they do belong to the same one.
+    pub unsafe fn common_tenant(&self, segment_ids: &[SegmentId]) -> Option<String> {
+        let tenants = self.list();
+        let mut common = None;
+        for segment_id in segment_ids {
+            let tenant = tenants.get(segment_id)?;
+            if common.is_some_and(|common| common != tenant) {
+                return None;
+            }
+            common = Some(tenant);
+        }
+        common.cloned()
+    }
+
+    /// Forget the tenants of `segment_ids`, which have been garbage collected.
+    pub unsafe fn forget(&mut self, segment_ids: &HashSet<SegmentId>) {
+        if segment_ids.is_empty() {
+            return;
+        }
+
+        self.entries.retain(|_, entry| {
+            if segment_ids.contains(&entry.segment_id) {
+                RetainItem::Remove(entry)
+            } else {
+                RetainItem::Retain
+            }
+        });
+    }
+}
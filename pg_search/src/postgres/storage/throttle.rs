@@ -0,0 +1,100 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Limits the rate at which a backend writes index blocks.
+//!
+//! Background merges can rewrite gigabytes of segment data, and left unchecked they compete with
+//! foreground queries for disk bandwidth.  A process opts into throttling with [`WriteThrottle::enable`],
+//! after which [`throttle`] sleeps whenever it's written more than its budget allows.
+
+use pgrx::{check_for_interrupts, pg_sys};
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+/// The longest we sleep at once, so that interrupts are still noticed promptly.
+const MAX_SLEEP: Duration = Duration::from_millis(100);
+
+thread_local! {
+    static THROTTLE: RefCell<Option<WriteThrottle>> = const { RefCell::new(None) };
+}
+
+pub struct WriteThrottle {
+    bytes_per_second: u64,
+    started: Instant,
+    written: u64,
+}
+
+impl WriteThrottle {
+    /// Limit this process's writes to `bytes_per_second`.  Zero means unlimited.
+    pub fn enable(bytes_per_second: u64) {
+        let throttle = (bytes_per_second > 0).then(|| WriteThrottle {
+            bytes_per_second,
+            started: Instant::now(),
+            written: 0,
+        });
+        THROTTLE.with(|cell| *cell.borrow_mut() = throttle);
+    }
+
+    /// How long to wait before `nbytes` more can be written.
+    fn consume(&mut self, nbytes: usize) -> Duration {
+        self.written += nbytes as u64;
+        let allowed = Duration::from_secs_f64(self.written as f64 / self.bytes_per_second as f64);
+        allowed.saturating_sub(self.started.elapsed())
+    }
+}
+
+/// Account for `nbytes` about to be written, sleeping first if this process is over its budget.
+pub fn throttle(nbytes: usize) {
+    let mut wait = THROTTLE.with(|cell| {
+        cell.borrow_mut()
+            .as_mut()
+            .map(|throttle| throttle.consume(nbytes))
+            .unwrap_or_default()
+    });
+
+    while !wait.is_zero() {
+        check_for_interrupts!();
+        let sleep = wait.min(MAX_SLEEP);
+        unsafe {
+            pg_sys::pg_usleep(sleep.as_micros() as _);
+        }
+        wait -= sleep;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_consume() {
+        let mut throttle = WriteThrottle {
+            bytes_per_second: 1000,
+            started: Instant::now(),
+            written: 0,
+        };
+
+        // half a second's worth of writing, all at once, should have to wait about that long
+        let wait = throttle.consume(500);
+        assert!(wait > Duration::from_millis(400) && wait <= Duration::from_millis(500));
+
+        // and a process that's been writing slowly doesn't wait at all
+        throttle.started -= Duration::from_secs(10);
+        assert_eq!(throttle.consume(500), Duration::ZERO);
+    }
+}
@@ -17,6 +17,8 @@
 
 use crate::postgres::datetime::{datetime_components_to_tantivy_date, MICROSECONDS_IN_SECOND};
 use crate::postgres::range::RangeToTantivyValue;
+use crate::postgres::vector::{is_vector_type, vector_from_datum, vector_to_bytes};
+use crate::schema::json_paths::JsonPathFilter;
 use crate::schema::{AnyEnum, SearchField};
 use ordered_float::OrderedFloat;
 use pgrx::datum::datetime_support::DateTimeConversionError;
@@ -95,6 +97,7 @@ impl TantivyValue {
                     }
                     PgBuiltInOids::UUIDOID => pgrx::datum::Uuid::try_from(self)?.into_datum(),
                     PgBuiltInOids::INETOID => pgrx::datum::Inet::try_from(self)?.into_datum(),
+                    PgBuiltInOids::BYTEAOID => Vec::<u8>::try_from(self)?.into_datum(),
                     _ => return Err(TantivyValueError::UnsupportedOid(oid.value())),
                 };
                 Ok(datum)
@@ -152,10 +155,73 @@ impl TantivyValue {
         }
     }
 
+    /// Converts a NUMERIC datum, or each element of a NUMERIC array datum, to a fixed-point
+    /// integer with `scale` decimal digits.
+    ///
+    /// The conversion goes through the numeric's text form so no precision is lost to a float.
+    pub unsafe fn try_from_numeric_datum_scaled(
+        datum: Datum,
+        is_array: bool,
+        scale: u8,
+    ) -> Result<Vec<Self>, TantivyValueError> {
+        let numerics = if is_array {
+            pgrx::Array::<pgrx::AnyNumeric>::from_datum(datum, false)
+                .ok_or(TantivyValueError::DatumDeref)?
+                .iter()
+                .flatten()
+                .collect()
+        } else {
+            vec![pgrx::AnyNumeric::from_datum(datum, false).ok_or(TantivyValueError::DatumDeref)?]
+        };
+
+        numerics
+            .into_iter()
+            .map(|numeric| {
+                let text = numeric.to_string();
+                scale_decimal(&text, scale)
+                    .map(|n| TantivyValue(OwnedValue::I64(n)))
+                    .ok_or(TantivyValueError::NumericScaleOverflow(text, scale))
+            })
+            .collect()
+    }
+
+    /// Converts a UUID datum, or each element of a UUID array datum, to its 16 raw bytes.
+    pub unsafe fn try_from_uuid_datum_binary(
+        datum: Datum,
+        is_array: bool,
+    ) -> Result<Vec<Self>, TantivyValueError> {
+        let uuids = if is_array {
+            pgrx::Array::<pgrx::Uuid>::from_datum(datum, false)
+                .ok_or(TantivyValueError::DatumDeref)?
+                .iter()
+                .flatten()
+                .collect()
+        } else {
+            vec![pgrx::Uuid::from_datum(datum, false).ok_or(TantivyValueError::DatumDeref)?]
+        };
+
+        Ok(uuids
+            .into_iter()
+            .map(|uuid| TantivyValue(OwnedValue::Bytes(uuid.as_bytes().to_vec())))
+            .collect())
+    }
+
     pub unsafe fn try_from_datum_array(
         datum: Datum,
         oid: PgOid,
     ) -> Result<Vec<Self>, TantivyValueError> {
+        Ok(Self::try_from_datum_array_elements(datum, oid)?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    /// Like [`TantivyValue::try_from_datum_array`], but with a [`None`] for each null element, so
+    /// that every value keeps its position in the array.
+    pub unsafe fn try_from_datum_array_elements(
+        datum: Datum,
+        oid: PgOid,
+    ) -> Result<Vec<Option<Self>>, TantivyValueError> {
         match &oid {
             PgOid::BuiltIn(builtin) => match builtin {
                 PgBuiltInOids::BOOLOID
@@ -174,13 +240,17 @@ impl TantivyValue {
                 | PgBuiltInOids::TIMEOID
                 | PgBuiltInOids::TIMETZOID
                 | PgBuiltInOids::UUIDOID
-                | PgBuiltInOids::INETOID => {
+                | PgBuiltInOids::INETOID
+                | PgBuiltInOids::BYTEAOID => {
                     let array: pgrx::Array<Datum> = pgrx::Array::from_datum(datum, false)
                         .ok_or(TantivyValueError::DatumDeref)?;
                     array
                         .iter()
-                        .flatten()
-                        .map(|element_datum| Self::try_from_datum(element_datum, oid))
+                        .map(|element_datum| {
+                            element_datum
+                                .map(|element_datum| Self::try_from_datum(element_datum, oid))
+                                .transpose()
+                        })
                         .collect()
                 }
                 _ => Err(TantivyValueError::UnsupportedArrayOid(oid.value())),
@@ -189,10 +259,16 @@ impl TantivyValue {
         }
     }
 
+    /// Convert a JSON datum, keeping only the keys that `filter` allows, if given.
     pub unsafe fn try_from_datum_json(
         datum: Datum,
         oid: PgOid,
+        filter: Option<&JsonPathFilter>,
     ) -> Result<Vec<Self>, TantivyValueError> {
+        let filtered = |value: Value| match filter {
+            Some(filter) => filter.apply(value),
+            None => Some(value),
+        };
         match &oid {
             PgOid::BuiltIn(builtin) => match builtin {
                 // Tantivy has a limitation that prevents JSON top-level arrays from being
@@ -201,12 +277,16 @@ impl TantivyValue {
                 PgBuiltInOids::JSONBOID => {
                     let pgrx_value = pgrx::JsonB::from_datum(datum, false)
                         .ok_or(TantivyValueError::DatumDeref)?;
-                    Ok(Self::json_value_to_tantivy_value(pgrx_value.0))
+                    Ok(filtered(pgrx_value.0)
+                        .map(Self::json_value_to_tantivy_value)
+                        .unwrap_or_default())
                 }
                 PgBuiltInOids::JSONOID => {
                     let pgrx_value = pgrx::Json::from_datum(datum, false)
                         .ok_or(TantivyValueError::DatumDeref)?;
-                    Ok(Self::json_value_to_tantivy_value(pgrx_value.0))
+                    Ok(filtered(pgrx_value.0)
+                        .map(Self::json_value_to_tantivy_value)
+                        .unwrap_or_default())
                 }
                 _ => Err(TantivyValueError::UnsupportedJsonOid(oid.value())),
             },
@@ -273,6 +353,9 @@ impl TantivyValue {
                     pgrx::datum::Inet::from_datum(datum, false)
                         .ok_or(TantivyValueError::DatumDeref)?,
                 ),
+                PgBuiltInOids::BYTEAOID => TantivyValue::try_from(
+                    Vec::<u8>::from_datum(datum, false).ok_or(TantivyValueError::DatumDeref)?,
+                ),
                 PgBuiltInOids::INT4RANGEOID => TantivyValue::from_range(
                     pgrx::datum::Range::<i32>::from_datum(datum, false)
                         .ok_or(TantivyValueError::DatumDeref)?,
@@ -316,6 +399,10 @@ impl TantivyValue {
                             .ok_or(TantivyValueError::DatumDeref)?,
                     );
                     TantivyValue::try_from(ordinal)
+                } else if is_vector_type(*custom) {
+                    Ok(TantivyValue(OwnedValue::Bytes(vector_to_bytes(
+                        &vector_from_datum(datum),
+                    ))))
                 } else {
                     Err(TantivyValueError::UnsupportedOid(oid.value()))
                 }
@@ -709,6 +796,39 @@ impl TryFrom<TantivyValue> for pgrx::AnyNumeric {
     }
 }
 
+/// Converts a decimal number's text form to an integer scaled by `10^scale`, rounding half away
+/// from zero.  Returns [`None`] for `NaN`, infinities, and values that don't fit in an `i64`.
+pub fn scale_decimal(text: &str, scale: u8) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole.bytes().all(|b| b.is_ascii_digit())
+        || !fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let mut scaled: i128 = 0;
+    for digit in whole.bytes().chain(
+        fraction
+            .bytes()
+            .chain(std::iter::repeat(b'0'))
+            .take(scale as usize),
+    ) {
+        scaled = scaled
+            .checked_mul(10)?
+            .checked_add((digit - b'0') as i128)?;
+    }
+    if fraction.as_bytes().get(scale as usize) >= Some(&b'5') {
+        scaled += 1;
+    }
+
+    i64::try_from(if negative { -scaled } else { scaled }).ok()
+}
+
 impl TryFrom<bool> for TantivyValue {
     type Error = TantivyValueError;
 
@@ -1079,6 +1199,9 @@ pub enum TantivyValueError {
     #[error(transparent)]
     ParseFloatError(#[from] ParseFloatError),
 
+    #[error("numeric value {0} cannot be stored with a scale of {1}")]
+    NumericScaleOverflow(String, u8),
+
     #[error("Cannot convert oid of InvalidOid to TantivyValue")]
     InvalidOid,
 
@@ -64,6 +64,13 @@ pub fn arrow_array_to_datum(
                 _ => return Err(format!("Unsupported OID for Utf8 Arrow type: {oid:?}")),
             }
         }
+        DataType::Binary => {
+            let arr = array.as_binary::<i32>();
+            match &oid {
+                PgOid::BuiltIn(PgBuiltInOids::BYTEAOID) => arr.value(index).into_datum(),
+                _ => return Err(format!("Unsupported OID for Binary Arrow type: {oid:?}")),
+            }
+        }
         DataType::UInt64 => {
             let arr = array.as_primitive::<arrow_array::types::UInt64Type>();
             let val = arr.value(index);
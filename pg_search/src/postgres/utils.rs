@@ -15,6 +15,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use crate::api::alias::aliased_index;
 use crate::api::{FieldName, HashMap};
 use crate::index::writer::index::IndexError;
 use crate::postgres::build::is_bm25_index;
@@ -94,14 +95,29 @@ pub fn locate_bm25_index(heaprelid: pg_sys::Oid) -> Option<PgSearchRelation> {
 
 /// Finds and returns the `USING bm25` index on the specified relation with the
 /// highest OID, or [`None`] if there aren't any.
+///
+/// If there's more than one, as while an index is rebuilt concurrently, one that an index
+/// alias points at is preferred.
 pub fn locate_bm25_index_from_heaprel(heaprel: &PgSearchRelation) -> Option<PgSearchRelation> {
     unsafe {
         let indices = heaprel.indices(pg_sys::AccessShareLock as _);
 
         // Find all bm25 indexes and keep the one with highest OID
-        indices
+        let mut bm25_indices = indices
             .into_iter()
             .filter(|index| pg_sys::get_index_isvalid(index.oid()) && is_bm25_index(index))
+            .collect::<Vec<_>>();
+        if bm25_indices.len() > 1 {
+            let oids = bm25_indices
+                .iter()
+                .map(|index| index.oid())
+                .collect::<Vec<_>>();
+            if let Some(aliased) = aliased_index(&oids) {
+                bm25_indices.retain(|index| index.oid() == aliased);
+            }
+        }
+        bm25_indices
+            .into_iter()
             .max_by_key(|index| index.oid().to_u32())
     }
 }
@@ -205,6 +221,7 @@ pub unsafe fn row_to_search_document(
             base_oid,
             is_array,
             is_json,
+            json_path_filter,
         },
     ) in categorized_fields
     {
@@ -219,12 +236,22 @@ pub unsafe fn row_to_search_document(
             continue;
         }
 
-        if *is_array {
+        if let Some(scale) = search_field.numeric_scale() {
+            for value in TantivyValue::try_from_numeric_datum_scaled(datum, *is_array, scale)? {
+                document.add_field_value(search_field.field(), &OwnedValue::from(value));
+            }
+        } else if search_field.is_binary_uuid() {
+            for value in TantivyValue::try_from_uuid_datum_binary(datum, *is_array)? {
+                document.add_field_value(search_field.field(), &OwnedValue::from(value));
+            }
+        } else if *is_array {
             for value in TantivyValue::try_from_datum_array(datum, *base_oid)? {
                 document.add_field_value(search_field.field(), &OwnedValue::from(value));
             }
         } else if *is_json {
-            for value in TantivyValue::try_from_datum_json(datum, *base_oid)? {
+            for value in
+                TantivyValue::try_from_datum_json(datum, *base_oid, json_path_filter.as_ref())?
+            {
                 document.add_field_value(search_field.field(), &OwnedValue::from(value));
             }
         } else {
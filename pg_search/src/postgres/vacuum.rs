@@ -17,6 +17,7 @@
 
 use pgrx::*;
 
+use crate::postgres::delete::delete_expired;
 use crate::postgres::merge::{do_merge, MergeStyle};
 use crate::postgres::rel::PgSearchRelation;
 
@@ -26,6 +27,9 @@ pub unsafe extern "C-unwind" fn amvacuumcleanup(
     stats: *mut pg_sys::IndexBulkDeleteResult,
 ) -> *mut pg_sys::IndexBulkDeleteResult {
     let index = PgSearchRelation::open((*(*info).index).rd_id);
+    if !(*info).analyze_only {
+        delete_expired(&index).expect("should be able to delete expired documents");
+    }
     do_merge(&index, MergeStyle::Vacuum, None).expect("should be able to merge");
     stats
 }
@@ -206,14 +206,9 @@ pub unsafe fn find_one_var_and_fieldname(
     node: *mut pg_sys::Node,
 ) -> Option<(*mut pg_sys::Var, FieldName)> {
     if is_a(node, T_OpExpr) {
-        let opexpr = node.cast::<OpExpr>();
-        static JSON_OPERATOR_LOOKUP: OnceLock<HashSet<pg_sys::Oid>> = OnceLock::new();
-        if JSON_OPERATOR_LOOKUP
-            .get_or_init(|| initialize_json_operator_lookup())
-            .contains(&(*opexpr).opno)
-        {
+        if is_json_path_expr(node) {
             let var = find_one_var(node)?;
-            let path = find_json_path(&context, node);
+            let path = find_json_path(Some(&context), node);
             return Some((var, path.join(".").into()));
         }
         None
@@ -234,15 +229,34 @@ pub unsafe fn find_one_var_and_fieldname(
     }
 }
 
+/// Given a [`pg_sys::Node`], attempt to find the keys of the path into its JSON column that it
+/// references, like `["a", "b"]` for `metadata->'a'->>'b'`.
+///
+/// Returns `None` if the node isn't a JSON path expression.
+pub unsafe fn find_json_path_keys(node: *mut pg_sys::Node) -> Option<Vec<String>> {
+    is_json_path_expr(node).then(|| find_json_path(None, node))
+}
+
+unsafe fn is_json_path_expr(node: *mut pg_sys::Node) -> bool {
+    static JSON_OPERATOR_LOOKUP: OnceLock<HashSet<pg_sys::Oid>> = OnceLock::new();
+    is_a(node, T_OpExpr)
+        && JSON_OPERATOR_LOOKUP
+            .get_or_init(|| initialize_json_operator_lookup())
+            .contains(&(*node.cast::<OpExpr>()).opno)
+}
+
 /// Given a [`pg_sys::Node`] and a [`pg_sys::PlannerInfo`], attempt to find the JSON path that the
-/// node references.
+/// node references.  Without a `context`, the path leaves out the name of the JSON column.
 ///
 /// It is the caller's responsibility to ensure that the node is a JSON path expression.
 #[inline(always)]
-unsafe fn find_json_path(context: &VarContext, node: *mut pg_sys::Node) -> Vec<String> {
+unsafe fn find_json_path(context: Option<&VarContext>, node: *mut pg_sys::Node) -> Vec<String> {
     let mut path = Vec::new();
 
     if is_a(node, T_Var) {
+        let Some(context) = context else {
+            return path;
+        };
         let node = node as *mut Var;
         let (heaprelid, varattno) = context.var_relation(node);
         let field_name = fieldname_from_var(heaprelid, node, varattno)
@@ -0,0 +1,184 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! pgvector's `vector` type, whose values are indexed as fast fields of their dimensions'
+//! little-endian `f32`s so that they can be read back without fetching their rows.
+
+use pgrx::pg_sys;
+use serde::{Deserialize, Serialize};
+use std::ffi::CStr;
+use std::fmt::{Display, Formatter};
+
+/// Is `oid` pgvector's `vector` type?
+///
+/// pgvector can be installed into any schema, so the type is recognized by its name and its
+/// variable length rather than by where it lives.
+pub fn is_vector_type(oid: pg_sys::Oid) -> bool {
+    unsafe {
+        let entry = pg_sys::SearchSysCache1(pg_sys::SysCacheIdentifier::TYPEOID as _, oid.into());
+        if entry.is_null() {
+            return false;
+        }
+
+        let mut is_null = false;
+        let typname = pg_sys::SysCacheGetAttr(
+            pg_sys::SysCacheIdentifier::TYPEOID as _,
+            entry,
+            pg_sys::Anum_pg_type_typname as _,
+            &mut is_null,
+        );
+        let is_vector = !is_null
+            && CStr::from_ptr(typname.cast_mut_ptr::<std::os::raw::c_char>()) == c"vector"
+            && pg_sys::get_typlen(oid) == -1;
+        pg_sys::ReleaseSysCache(entry);
+        is_vector
+    }
+}
+
+/// The dimensions of a `vector` datum.
+///
+/// A `vector` is a varlena of an `int16` dimension count, an unused `int16`, and then that many
+/// `float4`s.
+pub unsafe fn vector_from_datum(datum: pg_sys::Datum) -> Vec<f32> {
+    let varlena = pg_sys::pg_detoast_datum(datum.cast_mut_ptr());
+    let header = varlena.cast::<u8>().add(pg_sys::VARHDRSZ);
+    let dim = header.cast::<i16>().read_unaligned() as usize;
+    let values = header.add(2 * size_of::<i16>()).cast::<f32>();
+    (0..dim).map(|i| values.add(i).read_unaligned()).collect()
+}
+
+/// The dimensions of a `vector` constant, if it isn't NULL.
+pub unsafe fn vector_from_const(node: *mut pg_sys::Const) -> Option<Vec<f32>> {
+    if (*node).constisnull || !is_vector_type((*node).consttype) {
+        return None;
+    }
+    Some(vector_from_datum((*node).constvalue))
+}
+
+/// How a vector's dimensions are stored in its fast field.
+pub fn vector_to_bytes(vector: &[f32]) -> Vec<u8> {
+    vector
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+pub fn vector_from_bytes(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(size_of::<f32>())
+        .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect()
+}
+
+/// One of pgvector's distance operators on two `vector`s, computed the way pgvector does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VectorMetric {
+    /// `<->`
+    L2,
+    /// `<#>`, which is negated so that it sorts the closest vectors first, like the others.
+    NegativeInnerProduct,
+    /// `<=>`
+    Cosine,
+    /// `<+>`
+    L1,
+}
+
+impl Display for VectorMetric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.opname())
+    }
+}
+
+impl VectorMetric {
+    /// The metric of the operator `opno`, if it's one of pgvector's on two `vector`s.
+    pub unsafe fn from_operator(opno: pg_sys::Oid) -> Option<Self> {
+        let (mut left, mut right) = (pg_sys::InvalidOid, pg_sys::InvalidOid);
+        pg_sys::op_input_types(opno, &mut left, &mut right);
+        if !is_vector_type(left) || !is_vector_type(right) {
+            return None;
+        }
+
+        let opname = pg_sys::get_opname(opno);
+        if opname.is_null() {
+            return None;
+        }
+        match CStr::from_ptr(opname).to_str().ok()? {
+            "<->" => Some(VectorMetric::L2),
+            "<#>" => Some(VectorMetric::NegativeInnerProduct),
+            "<=>" => Some(VectorMetric::Cosine),
+            "<+>" => Some(VectorMetric::L1),
+            _ => None,
+        }
+    }
+
+    pub fn opname(&self) -> &'static str {
+        match self {
+            VectorMetric::L2 => "<->",
+            VectorMetric::NegativeInnerProduct => "<#>",
+            VectorMetric::Cosine => "<=>",
+            VectorMetric::L1 => "<+>",
+        }
+    }
+
+    /// The distance between `a` and `b`, which must have as many dimensions as each other.
+    pub fn distance(&self, a: &[f32], b: &[f32]) -> f64 {
+        if a.len() != b.len() {
+            pgrx::error!("different vector dimensions {} and {}", a.len(), b.len());
+        }
+
+        let pairs = a.iter().zip(b);
+        match self {
+            VectorMetric::L2 => (pairs.map(|(a, b)| (a - b) * (a - b)).sum::<f32>() as f64).sqrt(),
+            VectorMetric::NegativeInnerProduct => -(pairs.map(|(a, b)| a * b).sum::<f32>() as f64),
+            VectorMetric::Cosine => {
+                let (dot, norm_a, norm_b) = pairs.fold((0.0f32, 0.0f32, 0.0f32), |acc, (a, b)| {
+                    (acc.0 + a * b, acc.1 + a * a, acc.2 + b * b)
+                });
+                // like pgvector, a zero vector is NaN away from anything
+                let similarity = dot as f64 / ((norm_a as f64) * (norm_b as f64)).sqrt();
+                if similarity.is_nan() {
+                    return f64::NAN;
+                }
+                1.0 - similarity.clamp(-1.0, 1.0)
+            }
+            VectorMetric::L1 => pairs.map(|(a, b)| (a - b).abs()).sum::<f32>() as f64,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    use super::{vector_from_bytes, vector_to_bytes, VectorMetric};
+    use pgrx::pg_test;
+
+    #[pg_test]
+    fn vector_bytes_round_trip() {
+        let vector = vec![1.0, -0.5, 3.25];
+        assert_eq!(vector_from_bytes(&vector_to_bytes(&vector)), vector);
+    }
+
+    #[pg_test]
+    fn vector_distances() {
+        let (a, b) = ([1.0, 0.0], [0.0, 1.0]);
+        assert_eq!(VectorMetric::L2.distance(&a, &b), 2.0f64.sqrt());
+        assert_eq!(VectorMetric::NegativeInnerProduct.distance(&a, &b), 0.0);
+        assert_eq!(VectorMetric::Cosine.distance(&a, &b), 1.0);
+        assert_eq!(VectorMetric::L1.distance(&a, &b), 2.0);
+        assert_eq!(VectorMetric::Cosine.distance(&a, &a), 0.0);
+    }
+}
@@ -0,0 +1,336 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Element-level matching over array fields.
+//!
+//! The index flattens an array into one bag of values, so a row with `tags` of
+//! `{'red shirt', 'large hat'}` matches both `red` and `hat`, even though no single element
+//! contains both.  An element query instead requires that one element, on its own, matches the
+//! whole query.
+//!
+//! Which values belong to the same element is only known in the heap, so the arrays of the rows
+//! that match the query as a whole are read from the heap, and their elements are indexed into a
+//! throwaway in-memory index, one document per element, so that the query can be run against
+//! each element separately.
+
+use crate::api::{FieldName, HashSet};
+use crate::index::fast_fields_helper::FFType;
+use crate::index::mvcc::MvccSatisfies;
+use crate::index::reader::index::SearchIndexReader;
+use crate::index::search::setup_tokenizers;
+use crate::postgres::heap::HeapColumnReader;
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::types::TantivyValue;
+use crate::query::SearchQueryInput;
+use anyhow::{anyhow, Result};
+use pgrx::{check_for_interrupts, pg_extern, pg_sys, AnyArray, PgOid, PgRelation};
+use tantivy::collector::DocSetCollector;
+use tantivy::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use tantivy::schema::{Field, OwnedValue, Schema, FAST};
+use tantivy::{
+    DocId, DocSet, Index, IndexWriter, Score, SegmentReader, TantivyDocument, TantivyError,
+    TERMINATED,
+};
+
+/// The field of the in-memory index identifying which element each document is.
+const ELEMENT_FIELD: &str = "_pg_search_element";
+
+/// The smallest memory budget tantivy accepts for an index writer.
+const WRITER_MEMORY_BUDGET: usize = 15_000_000;
+
+/// An in-memory index holding each element of some arrays as its own document.
+pub struct ElementIndex {
+    index: Index,
+    /// The row and 1-based position of each element, in the order they were added
+    elements: Vec<(usize, u32)>,
+}
+
+impl ElementIndex {
+    /// Index the elements of each of `rows`, as values of `field`.  The in-memory index has all
+    /// the fields and tokenizers of `indexrel`, so that queries built for it work unchanged.
+    pub fn new(
+        indexrel: &PgSearchRelation,
+        field: Field,
+        rows: impl IntoIterator<Item = Vec<Option<TantivyValue>>>,
+    ) -> Result<Self> {
+        let schema = indexrel.schema()?;
+        let mut builder = Schema::builder();
+        for (_, field_entry) in schema.tantivy_schema().fields() {
+            builder.add_field(field_entry.clone());
+        }
+        // added last, so that every other field keeps its id
+        let element_field = builder.add_u64_field(ELEMENT_FIELD, FAST);
+
+        let mut index = Index::create_in_ram(builder.build());
+        setup_tokenizers(indexrel, &mut index)?;
+        let mut writer: IndexWriter = index.writer_with_num_threads(1, WRITER_MEMORY_BUDGET)?;
+
+        let mut elements = Vec::new();
+        for (row, values) in rows.into_iter().enumerate() {
+            for (position, value) in values.into_iter().enumerate() {
+                let Some(value) = value else {
+                    continue;
+                };
+                let mut document = TantivyDocument::new();
+                document.add_field_value(field, &OwnedValue::from(value));
+                document.add_u64(element_field, elements.len() as u64);
+                writer.add_document(document)?;
+                elements.push((row, position as u32 + 1));
+            }
+        }
+        writer.commit()?;
+
+        Ok(Self { index, elements })
+    }
+
+    /// The row and 1-based position of every element that matches `query`, in order.
+    pub fn matches(&self, query: &dyn Query) -> Result<Vec<(usize, u32)>> {
+        let searcher = self.index.reader()?.searcher();
+        let mut matches = Vec::new();
+        for address in searcher.search(query, &DocSetCollector)? {
+            let element = searcher
+                .segment_reader(address.segment_ord)
+                .fast_fields()
+                .u64(ELEMENT_FIELD)?
+                .first(address.doc_id)
+                .expect("every element should be numbered");
+            matches.push(self.elements[element as usize]);
+        }
+        matches.sort_unstable();
+        Ok(matches)
+    }
+}
+
+/// The 1-based positions of the elements of `value` that, on their own, match `query`, where
+/// `value` is an array indexed as `field` of `index`.
+#[pg_extern(stable, parallel_safe)]
+fn matching_elements(
+    index: PgRelation,
+    field: FieldName,
+    value: AnyArray,
+    query: SearchQueryInput,
+) -> Result<Vec<i32>> {
+    let indexrel = PgSearchRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _);
+    let schema = indexrel.schema()?;
+    let search_field = schema
+        .search_field(&field)
+        .ok_or_else(|| anyhow!("`{field}` is not a field of index `{}`", indexrel.name()))?;
+
+    let element_oid = PgOid::from_untagged(unsafe { pg_sys::get_element_type(value.oid()) });
+    let elements =
+        unsafe { TantivyValue::try_from_datum_array_elements(value.datum(), element_oid)? };
+
+    let reader = SearchIndexReader::empty(&indexrel, MvccSatisfies::Snapshot)?;
+    let query = reader.make_query(query);
+    Ok(
+        ElementIndex::new(&indexrel, search_field.field(), [elements])?
+            .matches(query.as_ref())?
+            .into_iter()
+            .map(|(_, position)| position as i32)
+            .collect(),
+    )
+}
+
+/// Matches the rows where a single element of an array field matches `query`.
+#[derive(Debug)]
+pub struct ElementQuery {
+    /// Matches at least every row that `query` matches an element of
+    candidates: Box<dyn Query>,
+    query: Box<dyn Query>,
+    field: Field,
+    column: String,
+    element_oid: PgOid,
+    index_oid: pg_sys::Oid,
+    heap_oid: pg_sys::Oid,
+}
+
+// SAFETY:  we don't execute within threads, despite Tantivy expecting that to be the case
+unsafe impl Send for ElementQuery {}
+unsafe impl Sync for ElementQuery {}
+
+impl ElementQuery {
+    pub fn new(
+        candidates: Box<dyn Query>,
+        query: Box<dyn Query>,
+        field: Field,
+        column: String,
+        element_oid: PgOid,
+        index_oid: pg_sys::Oid,
+        heap_oid: pg_sys::Oid,
+    ) -> Self {
+        Self {
+            candidates,
+            query,
+            field,
+            column,
+            element_oid,
+            index_oid,
+            heap_oid,
+        }
+    }
+}
+
+impl tantivy::query::QueryClone for ElementQuery {
+    fn box_clone(&self) -> Box<dyn Query> {
+        Box::new(Self {
+            candidates: self.candidates.box_clone(),
+            query: self.query.box_clone(),
+            field: self.field,
+            column: self.column.clone(),
+            element_oid: self.element_oid,
+            index_oid: self.index_oid,
+            heap_oid: self.heap_oid,
+        })
+    }
+}
+
+impl Query for ElementQuery {
+    fn weight(&self, enable_scoring: EnableScoring) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(ElementWeight {
+            candidates: self.candidates.weight(enable_scoring)?,
+            query: self.query.box_clone(),
+            field: self.field,
+            column: self.column.clone(),
+            element_oid: self.element_oid,
+            index_oid: self.index_oid,
+            heap_oid: self.heap_oid,
+        }))
+    }
+}
+
+struct ElementWeight {
+    candidates: Box<dyn Weight>,
+    query: Box<dyn Query>,
+    field: Field,
+    column: String,
+    element_oid: PgOid,
+    index_oid: pg_sys::Oid,
+    heap_oid: pg_sys::Oid,
+}
+
+// SAFETY:  we don't execute within threads, despite Tantivy expecting that to be the case
+unsafe impl Send for ElementWeight {}
+unsafe impl Sync for ElementWeight {}
+
+impl ElementWeight {
+    /// Read the array of each of `docs` from the heap.  An invisible row or a null array has no
+    /// elements.
+    fn read_arrays(
+        &self,
+        reader: &SegmentReader,
+        docs: &[(DocId, Score)],
+    ) -> Result<Vec<Vec<Option<TantivyValue>>>> {
+        let heaprel = PgSearchRelation::open(self.heap_oid);
+        let mut heap = HeapColumnReader::new(&heaprel, &self.column, unsafe {
+            pg_sys::GetActiveSnapshot()
+        })?;
+        let ctid_ff = FFType::new_ctid(reader.fast_fields());
+
+        let mut arrays = Vec::with_capacity(docs.len());
+        for (doc, _) in docs {
+            check_for_interrupts!();
+            let ctid = ctid_ff.as_u64(*doc).expect("ctid should be present");
+            let elements = heap
+                .read(ctid, |datum, is_null| unsafe {
+                    (!is_null).then(|| {
+                        TantivyValue::try_from_datum_array_elements(datum, self.element_oid)
+                    })
+                })
+                .transpose()?
+                .unwrap_or_default();
+            arrays.push(elements);
+        }
+        Ok(arrays)
+    }
+}
+
+impl Weight for ElementWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let mut candidates = self.candidates.scorer(reader, boost)?;
+        let mut docs = Vec::new();
+        let mut doc = candidates.doc();
+        while doc != TERMINATED {
+            if reader
+                .alive_bitset()
+                .is_none_or(|alive_bitset| alive_bitset.is_alive(doc))
+            {
+                docs.push((doc, candidates.score()));
+            }
+            doc = candidates.advance();
+        }
+
+        if !docs.is_empty() {
+            let matched = self
+                .read_arrays(reader, &docs)
+                .and_then(|arrays| {
+                    let indexrel = PgSearchRelation::open(self.index_oid);
+                    ElementIndex::new(&indexrel, self.field, arrays)?.matches(self.query.as_ref())
+                })
+                .map_err(|e| TantivyError::InternalError(e.to_string()))?
+                .into_iter()
+                .map(|(row, _)| row)
+                .collect::<HashSet<_>>();
+            docs = docs
+                .into_iter()
+                .enumerate()
+                .filter(|(row, _)| matched.contains(row))
+                .map(|(_, doc)| doc)
+                .collect();
+        }
+
+        Ok(Box::new(ElementScorer { docs, cursor: 0 }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new("ElementQuery", scorer.score()))
+    }
+}
+
+/// The rows that matched, with the scores they had for the candidate query.
+struct ElementScorer {
+    docs: Vec<(DocId, Score)>,
+    cursor: usize,
+}
+
+impl Scorer for ElementScorer {
+    fn score(&mut self) -> Score {
+        self.docs[self.cursor].1
+    }
+}
+
+impl DocSet for ElementScorer {
+    fn advance(&mut self) -> DocId {
+        self.cursor += 1;
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.docs
+            .get(self.cursor)
+            .map_or(TERMINATED, |(doc, _)| *doc)
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.docs.len() as u32
+    }
+}
@@ -46,6 +46,14 @@ impl<'a> Iterator for SearchQueryInputIter<'a> {
                     self.stack.push(query);
                     continue;
                 }
+                SearchQueryInput::Element { query, .. } => {
+                    self.stack.push(query);
+                    continue;
+                }
+                SearchQueryInput::HeapFilter { indexed_query, .. } => {
+                    self.stack.push(indexed_query);
+                    continue;
+                }
 
                 _ => {}
             }
@@ -0,0 +1,90 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Restricting a search to the rows that can join with another index's matches.
+//!
+//! When a search of one table is joined to a search of another on a key, like
+//! `orders.customer_id = customers.id`, every match of the first whose key isn't the key of a
+//! match of the second is fetched from the heap only for the join to throw it away.  If the
+//! second search's keys are a fast field, they can be read straight out of its index, and the
+//! first search restricted to the rows with one of those keys, as a set of terms.
+//!
+//! The keys are read from the index's matches before they're checked against the heap, so they
+//! may include those of some dead rows.  That only lets a few more rows through to the join, which
+//! checks its condition anyway.
+
+use crate::api::{FieldName, HashSet};
+use crate::index::fast_fields_helper::FFType;
+use crate::index::mvcc::MvccSatisfies;
+use crate::index::reader::index::SearchIndexReader;
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::types::TantivyValue;
+use crate::query::SearchQueryInput;
+use anyhow::Result;
+use pgrx::{check_for_interrupts, pg_sys};
+use tantivy::schema::OwnedValue;
+use tantivy::SegmentOrdinal;
+
+/// The distinct values of `key_field` of the documents in the index `indexrelid` that match
+/// `query`, or [`None`] if there are more than `max_keys` of them.
+pub fn join_key_values(
+    indexrelid: pg_sys::Oid,
+    key_field: &FieldName,
+    query: SearchQueryInput,
+    max_keys: usize,
+) -> Result<Option<Vec<OwnedValue>>> {
+    let indexrel = PgSearchRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _);
+    let reader = SearchIndexReader::open(&indexrel, query, false, MvccSatisfies::Snapshot)?;
+
+    let mut keys = HashSet::default();
+    let mut column: Option<(SegmentOrdinal, FFType)> = None;
+    for (_, doc_address) in reader.search() {
+        check_for_interrupts!();
+
+        if column.as_ref().map(|(segment_ord, _)| *segment_ord) != Some(doc_address.segment_ord) {
+            let segment_reader = reader.searcher().segment_reader(doc_address.segment_ord);
+            column = Some((
+                doc_address.segment_ord,
+                FFType::new(segment_reader.fast_fields(), &key_field.root()),
+            ));
+        }
+
+        // a NULL key never joins
+        let value = match &column.as_ref().unwrap().1 {
+            FFType::Text(ff) => {
+                let Some(ord) = ff.term_ords(doc_address.doc_id).next() else {
+                    continue;
+                };
+                let mut text = String::new();
+                ff.ord_to_str(ord, &mut text)?;
+                OwnedValue::Str(text)
+            }
+            ff => match ff.value(doc_address.doc_id) {
+                TantivyValue(OwnedValue::Null) => continue,
+                TantivyValue(value) => value,
+            },
+        };
+        keys.insert(TantivyValue(value));
+        if keys.len() > max_keys {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(
+        keys.into_iter().map(|TantivyValue(value)| value).collect(),
+    ))
+}
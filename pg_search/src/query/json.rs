@@ -0,0 +1,415 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Parses the JSON form of a [`SearchQueryInput`], as it's written in a `jsonb` query.
+//!
+//! The compound queries, `boolean`, `boost`, `const_score` and `disjunction_max`, are walked here
+//! rather than by serde, so that any query of the tree can carry a `boost` or a `const_score` of
+//! its own, like `{"term": {"field": "description", "value": "shoes", "boost": 2.0}}`, and so that
+//! an error names the path of the query it was found in, like `boolean.must[1].match.value`.
+
+use crate::query::pdb_query::pdb;
+use crate::query::SearchQueryInput;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fmt::{Display, Formatter};
+
+/// An error in a JSON query, and the path of the JSON value it was found in.
+#[derive(Debug, PartialEq)]
+pub struct JsonQueryError {
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for JsonQueryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, r#"at "{}": {}"#, self.path, self.message)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BooleanNode {
+    #[serde(default)]
+    must: Vec<Value>,
+    #[serde(default)]
+    should: Vec<Value>,
+    #[serde(default)]
+    must_not: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BoostNode {
+    query: Value,
+    factor: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConstScoreNode {
+    query: Value,
+    score: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DisjunctionMaxNode {
+    disjuncts: Vec<Value>,
+    tie_breaker: Option<f32>,
+}
+
+pub fn parse(value: Value) -> Result<SearchQueryInput, JsonQueryError> {
+    parse_query(value, &Path::root())
+}
+
+fn parse_query(value: Value, path: &Path) -> Result<SearchQueryInput, JsonQueryError> {
+    let Value::Object(object) = value else {
+        // a unit query, like `"all"`
+        return deserialize(value, path);
+    };
+    if object.len() != 1 {
+        let keys = object
+            .keys()
+            .map(|key| format!("`{key}`"))
+            .collect::<Vec<_>>();
+        return Err(path.error(format!(
+            "a query must be an object with a single key that names its type, like {{\"term\": {{...}}}}, but it has {}",
+            if keys.is_empty() {
+                "no keys".to_string()
+            } else {
+                format!("the keys {}", keys.join(", "))
+            }
+        )));
+    }
+
+    let (kind, mut body) = object.into_iter().next().unwrap();
+    let path_to_body = path.key(&kind);
+
+    // `boost` is a field of the fielded `boost` query, rather than a boost of the query
+    let boost = match body.as_object_mut() {
+        Some(body) if kind != "boost" => take_score(body, "boost", &path_to_body)?,
+        _ => None,
+    };
+    let const_score = match body.as_object_mut() {
+        Some(body) => take_score(body, "const_score", &path_to_body)?,
+        None => None,
+    };
+    if boost.is_some() && const_score.is_some() {
+        return Err(path_to_body.error(
+            "`boost` and `const_score` can't both be set on a query, as a constant score isn't boosted",
+        ));
+    }
+
+    let query = match kind.as_str() {
+        "boolean" => {
+            let node: BooleanNode = deserialize(body, &path_to_body)?;
+            let clauses = |occur: &str, queries: Vec<Value>| {
+                queries
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, query)| parse_query(query, &path_to_body.key(occur).index(i)))
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            SearchQueryInput::Boolean {
+                must: clauses("must", node.must)?,
+                should: clauses("should", node.should)?,
+                must_not: clauses("must_not", node.must_not)?,
+            }
+        }
+        "boost" if !is_fielded(&body) => {
+            let node: BoostNode = deserialize(body, &path_to_body)?;
+            SearchQueryInput::Boost {
+                query: Box::new(parse_query(node.query, &path_to_body.key("query"))?),
+                factor: node.factor,
+            }
+        }
+        "const_score" if !is_fielded(&body) => {
+            let node: ConstScoreNode = deserialize(body, &path_to_body)?;
+            SearchQueryInput::ConstScore {
+                query: Box::new(parse_query(node.query, &path_to_body.key("query"))?),
+                score: node.score,
+            }
+        }
+        "disjunction_max" => {
+            let node: DisjunctionMaxNode = deserialize(body, &path_to_body)?;
+            SearchQueryInput::DisjunctionMax {
+                disjuncts: node
+                    .disjuncts
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, query)| parse_query(query, &path_to_body.key("disjuncts").index(i)))
+                    .collect::<Result<_, _>>()?,
+                tie_breaker: node.tie_breaker,
+            }
+        }
+        _ => parse_leaf(kind, body, path)?,
+    };
+
+    Ok(match (boost, const_score) {
+        (Some(factor), _) => SearchQueryInput::Boost {
+            query: Box::new(query),
+            factor,
+        },
+        (_, Some(score)) => SearchQueryInput::ConstScore {
+            query: Box::new(query),
+            score,
+        },
+        (None, None) => query,
+    })
+}
+
+/// A query that isn't compound, which serde deserializes.
+fn parse_leaf(kind: String, body: Value, path: &Path) -> Result<SearchQueryInput, JsonQueryError> {
+    let fielded = is_fielded(&body);
+    let mut object = Map::new();
+    object.insert(kind, body);
+    let value = Value::Object(object);
+
+    match deserialize(value.clone(), path) {
+        Ok(query) => Ok(query),
+        // the error of a fielded query is the one of its `pdb::Query`, as the untagged variant
+        // it's deserialized through only tells that it didn't match
+        Err(err) if fielded && err.message.contains("untagged enum SearchQueryInput") => {
+            let Value::Object(mut object) = value else {
+                unreachable!("a leaf query should be an object")
+            };
+            let (kind, body) = object.iter_mut().next().unwrap();
+            let field = body.as_object_mut().unwrap().remove("field").unwrap();
+            let path_to_body = path.key(kind);
+            if !field.is_string() {
+                return Err(path_to_body
+                    .key("field")
+                    .error(format!("expected the name of a field, got {field}")));
+            }
+
+            Err(deserialize::<pdb::Query>(Value::Object(object), path)
+                .err()
+                .unwrap_or(err))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn is_fielded(body: &Value) -> bool {
+    body.as_object()
+        .is_some_and(|body| body.contains_key("field"))
+}
+
+/// Removes the score named `key` from the `body` of a query, which has to be a finite number, and
+/// not a negative one for a `boost`.
+fn take_score(
+    body: &mut Map<String, Value>,
+    key: &str,
+    path: &Path,
+) -> Result<Option<f32>, JsonQueryError> {
+    let Some(value) = body.remove(key) else {
+        return Ok(None);
+    };
+    match value.as_f64() {
+        Some(score) if score.is_finite() && (key != "boost" || score >= 0.0) => {
+            Ok(Some(score as f32))
+        }
+        _ if key == "boost" => Err(path
+            .key(key)
+            .error(format!("expected a non-negative number, got {value}"))),
+        _ => Err(path
+            .key(key)
+            .error(format!("expected a number, got {value}"))),
+    }
+}
+
+fn deserialize<T: DeserializeOwned>(value: Value, path: &Path) -> Result<T, JsonQueryError> {
+    serde_path_to_error::deserialize(value).map_err(|err| JsonQueryError {
+        path: path.join(&err.path().to_string()),
+        message: err.inner().to_string(),
+    })
+}
+
+/// The path of a JSON value, written the way `serde_path_to_error` writes one.
+pub struct Path(String);
+
+impl Display for Path {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0.as_str() {
+            "" => f.write_str("."),
+            path => f.write_str(path),
+        }
+    }
+}
+
+impl Path {
+    pub fn root() -> Self {
+        Path(String::new())
+    }
+
+    pub fn key(&self, key: &str) -> Self {
+        Path(self.join(key))
+    }
+
+    pub fn index(&self, index: usize) -> Self {
+        Path(format!("{}[{index}]", self.0))
+    }
+
+    fn join(&self, rest: &str) -> String {
+        match (self.0.as_str(), rest) {
+            ("", rest) => rest.to_string(),
+            (path, ".") => path.to_string(),
+            (path, rest) if rest.starts_with('[') => format!("{path}{rest}"),
+            (path, rest) => format!("{path}.{rest}"),
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> JsonQueryError {
+        JsonQueryError {
+            path: self.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    fn error(value: Value) -> String {
+        parse(value).unwrap_err().to_string()
+    }
+
+    #[rstest]
+    fn test_nested_boosts() {
+        let query = parse(json!({
+            "boolean": {
+                "must": [
+                    {"term": {"field": "category", "value": "footwear", "const_score": 1.0}},
+                    {
+                        "boolean": {
+                            "should": [
+                                {"match": {"field": "description", "value": "shoes", "boost": 2.0}},
+                                {"all": null}
+                            ],
+                            "boost": 0.5
+                        }
+                    }
+                ],
+                "must_not": [{"term": {"field": "description", "value": "socks"}}]
+            }
+        }))
+        .unwrap();
+
+        let term = |value: &str| parse(json!({"term": {"field": "category", "value": value}}));
+        let SearchQueryInput::Boolean {
+            must,
+            should,
+            must_not,
+        } = query
+        else {
+            panic!("expected a boolean query, got {query:?}");
+        };
+        assert!(should.is_empty());
+        assert_eq!(must_not.len(), 1);
+        assert_eq!(
+            must[0],
+            SearchQueryInput::ConstScore {
+                query: Box::new(term("footwear").unwrap()),
+                score: 1.0,
+            }
+        );
+        let SearchQueryInput::Boost { query, factor } = &must[1] else {
+            panic!("expected a boosted query, got {:?}", must[1]);
+        };
+        assert_eq!(*factor, 0.5);
+        let SearchQueryInput::Boolean { should, .. } = query.as_ref() else {
+            panic!("expected a boolean query, got {query:?}");
+        };
+        assert!(matches!(
+            &should[0],
+            SearchQueryInput::Boost { factor, .. } if *factor == 2.0
+        ));
+        assert_eq!(should[1], SearchQueryInput::All);
+    }
+
+    #[rstest]
+    fn test_compound_queries() {
+        let query = json!({
+            "disjunction_max": {
+                "disjuncts": [
+                    {"const_score": {"query": {"all": null}, "score": 3.0}},
+                    {"boost": {"query": {"empty": null}, "factor": 1.5}}
+                ],
+                "tie_breaker": 0.5
+            }
+        });
+        assert_eq!(
+            parse(query.clone()).unwrap(),
+            serde_json::from_value::<SearchQueryInput>(query).unwrap()
+        );
+
+        // the fielded `boost` query has a `boost` of its own
+        let query = json!({
+            "boost": {"field": "description", "query": {"term": {"value": "shoes"}}, "boost": 2.0}
+        });
+        assert_eq!(
+            parse(query.clone()).unwrap(),
+            serde_json::from_value::<SearchQueryInput>(query).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case::unknown_occur(
+        json!({"boolean": {"must": [], "musnt": []}}),
+        r#"at "boolean.musnt": unknown field `musnt`, expected one of `must`, `should`, `must_not`"#
+    )]
+    #[case::not_a_list(
+        json!({"boolean": {"should": {"all": null}}}),
+        r#"at "boolean.should": invalid type: map, expected a sequence"#
+    )]
+    #[case::two_types(
+        json!({"boolean": {"must": [{"all": null, "boost": 2.0}]}}),
+        r#"at "boolean.must[0]": a query must be an object with a single key that names its type, like {"term": {...}}, but it has the keys `all`, `boost`"#
+    )]
+    #[case::negative_boost(
+        json!({"boolean": {"should": [{"all": null}, {"term": {"field": "description", "value": "shoes", "boost": -1}}]}}),
+        r#"at "boolean.should[1].term.boost": expected a non-negative number, got -1"#
+    )]
+    #[case::invalid_const_score(
+        json!({"boolean": {"must": [{"boolean": {"should": [], "const_score": "high"}}]}}),
+        r#"at "boolean.must[0].boolean.const_score": expected a number, got "high""#
+    )]
+    #[case::boost_and_const_score(
+        json!({"match": {"field": "description", "value": "shoes", "boost": 2.0, "const_score": 1.0}}),
+        r#"at "match": `boost` and `const_score` can't both be set on a query, as a constant score isn't boosted"#
+    )]
+    #[case::fielded_query(
+        json!({"boolean": {"must": [{"match": {"field": "description", "valu": "shoes"}}]}}),
+        r#"at "boolean.must[0].match": missing field `value`"#
+    )]
+    #[case::unknown_type(
+        json!({"const_score": {"query": {"terms": {"field": "description", "value": "shoes"}}, "score": 1.0}}),
+        r#"at "const_score.query": unknown variant `terms`, expected one of"#
+    )]
+    fn test_errors(#[case] query: Value, #[case] expected: &str) {
+        let error = error(query);
+        assert!(
+            error.starts_with(expected),
+            "expected an error starting with {expected:?}, got {error:?}"
+        );
+    }
+}
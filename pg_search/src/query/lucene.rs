@@ -0,0 +1,649 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Lucene's classic query syntax, which `paradedb.parse(..., syntax => 'lucene')` accepts so that
+//! queries saved for Lucene or Solr can be run as they are.
+//!
+//! A query string is parsed into a [`SearchQueryInput`] whose clauses are combined the way
+//! Lucene's `QueryParser` combines them.  Its terms, phrases, and ranges are then searched for by
+//! the index's own query parser, so that they're tokenized and typed like the fields they're
+//! searched in.
+
+use crate::api::FieldName;
+use crate::query::pdb_query::pdb;
+use crate::query::{QueryError, SearchQueryInput};
+use std::fmt::Display;
+use tantivy::query_grammar::Occur;
+
+/// How many edits away a fuzzy term is when it doesn't say, as in Lucene.
+const DEFAULT_FUZZY_DISTANCE: u8 = 2;
+
+/// Parse the Lucene `query_string`.
+///
+/// The fuzzy, wildcard, and regex terms that don't name their field are searched for in each of
+/// `text_fields`, and the other terms that don't in the index's default fields.  With
+/// `conjunction_mode`, the clauses without an operator are required, like Lucene's `AND` default
+/// operator.
+pub fn parse(
+    query_string: &str,
+    text_fields: &[FieldName],
+    lenient: Option<bool>,
+    conjunction_mode: bool,
+) -> Result<SearchQueryInput, QueryError> {
+    let mut parser = Parser {
+        query_string,
+        chars: query_string.chars().collect(),
+        pos: 0,
+        text_fields,
+        lenient,
+        conjunction_mode,
+    };
+    let query = parser.query(None)?;
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected `)`"));
+    }
+    Ok(query)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Conjunction {
+    None,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Modifier {
+    None,
+    Required,
+    Prohibited,
+}
+
+struct Parser<'a> {
+    query_string: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+    text_fields: &'a [FieldName],
+    lenient: Option<bool>,
+    conjunction_mode: bool,
+}
+
+impl Parser<'_> {
+    /// The clauses up to the end of the query string or of the group they're in, searched for in
+    /// `field` unless they name their own.
+    fn query(&mut self, field: Option<&FieldName>) -> Result<SearchQueryInput, QueryError> {
+        let mut clauses = vec![];
+        loop {
+            self.skip_whitespace();
+            if matches!(self.peek(), None | Some(')')) {
+                break;
+            }
+
+            let conjunction = self.conjunction();
+            if conjunction != Conjunction::None {
+                if clauses.is_empty() {
+                    return Err(self.error("expected a clause before the operator"));
+                }
+                self.skip_whitespace();
+                if matches!(self.peek(), None | Some(')')) {
+                    return Err(self.error("expected a clause after the operator"));
+                }
+            }
+            let modifier = self.modifier();
+            let query = self.clause(field)?;
+            self.add_clause(&mut clauses, conjunction, modifier, query);
+        }
+
+        match clauses.len() {
+            0 => Ok(SearchQueryInput::Empty),
+            1 if clauses[0].0 != Occur::MustNot => Ok(clauses.pop().unwrap().1),
+            _ => {
+                let (mut must, mut should, mut must_not) = (vec![], vec![], vec![]);
+                for (occur, query) in clauses {
+                    match occur {
+                        Occur::Must => must.push(query),
+                        Occur::Should => should.push(query),
+                        Occur::MustNot => must_not.push(query),
+                    }
+                }
+                Ok(SearchQueryInput::Boolean {
+                    must,
+                    should,
+                    must_not,
+                })
+            }
+        }
+    }
+
+    /// Add a clause the way Lucene does: `AND` makes the clause before it required too, and `OR`
+    /// makes it optional when clauses are otherwise required.  Prohibited clauses stay so.
+    fn add_clause(
+        &self,
+        clauses: &mut Vec<(Occur, SearchQueryInput)>,
+        conjunction: Conjunction,
+        modifier: Modifier,
+        query: SearchQueryInput,
+    ) {
+        if let Some((occur, _)) = clauses.last_mut() {
+            if *occur != Occur::MustNot {
+                match conjunction {
+                    Conjunction::And => *occur = Occur::Must,
+                    Conjunction::Or if self.conjunction_mode => *occur = Occur::Should,
+                    _ => {}
+                }
+            }
+        }
+
+        let occur = match (modifier, conjunction) {
+            (Modifier::Prohibited, _) => Occur::MustNot,
+            (Modifier::Required, _) | (Modifier::None, Conjunction::And) => Occur::Must,
+            (Modifier::None, Conjunction::Or) => Occur::Should,
+            (Modifier::None, Conjunction::None) if self.conjunction_mode => Occur::Must,
+            (Modifier::None, Conjunction::None) => Occur::Should,
+        };
+        clauses.push((occur, query));
+    }
+
+    fn conjunction(&mut self) -> Conjunction {
+        if self.keyword("AND") || self.symbol("&&") {
+            Conjunction::And
+        } else if self.keyword("OR") || self.symbol("||") {
+            Conjunction::Or
+        } else {
+            Conjunction::None
+        }
+    }
+
+    fn modifier(&mut self) -> Modifier {
+        let modifier = if self.symbol("+") {
+            Modifier::Required
+        } else if self.symbol("-") || self.symbol("!") || self.keyword("NOT") {
+            Modifier::Prohibited
+        } else {
+            return Modifier::None;
+        };
+        self.skip_whitespace();
+        modifier
+    }
+
+    /// A clause, which is a term, phrase, range, regex, or group, prefixed by the field it's
+    /// searched in and followed by its boost.
+    fn clause(&mut self, field: Option<&FieldName>) -> Result<SearchQueryInput, QueryError> {
+        if self.keyword("*:*") {
+            return self.boosted(SearchQueryInput::All);
+        }
+
+        let start = self.pos;
+        let named = self.term_chars();
+        let field = if !named.is_empty() && self.symbol(":") {
+            Some(FieldName::from(
+                named.into_iter().map(|(c, _)| c).collect::<String>(),
+            ))
+        } else {
+            self.pos = start;
+            field.cloned()
+        };
+        let field = field.as_ref();
+
+        let query = match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let query = self.query(field)?;
+                if !self.symbol(")") {
+                    return Err(self.error("expected `)` to close the group"));
+                }
+                query
+            }
+            Some('"') => {
+                let phrase = quote(&self.quoted('"')?);
+                if self.symbol("~") {
+                    let slop = self
+                        .number()
+                        .and_then(|slop| slop.parse::<u32>().ok())
+                        .ok_or_else(|| self.error("expected the slop of the phrase"))?;
+                    self.text(field, format!("{phrase}~{slop}"))
+                } else {
+                    self.text(field, phrase)
+                }
+            }
+            Some('[' | '{') => self.range(field)?,
+            Some('/') => {
+                let pattern = self.quoted('/')?;
+                self.each_text_field(field, |field| SearchQueryInput::FieldedQuery {
+                    field: field.clone(),
+                    query: pdb::Query::Regex {
+                        pattern: pattern.clone(),
+                    },
+                })
+            }
+            Some(_) => self.term(field)?,
+            None => return Err(self.error("expected a term")),
+        };
+        self.boosted(query)
+    }
+
+    /// A term, which is fuzzy if it's followed by `~`, and a wildcard if it has an unescaped `*`
+    /// or `?`.
+    fn term(&mut self, field: Option<&FieldName>) -> Result<SearchQueryInput, QueryError> {
+        let term = self.term_chars();
+        if term.is_empty() {
+            let unexpected = self.peek().unwrap_or_default();
+            return Err(self.error(format!("unexpected `{unexpected}`")));
+        }
+        let text = term.iter().map(|(c, _)| c).collect::<String>();
+
+        if self.symbol("~") {
+            let distance = match self.number() {
+                None => DEFAULT_FUZZY_DISTANCE,
+                Some(distance) => match distance.parse::<u8>() {
+                    Ok(distance @ 0..=2) => distance,
+                    _ => return Err(self.error("the distance of a fuzzy term must be 0, 1, or 2")),
+                },
+            };
+            return Ok(
+                self.each_text_field(field, |field| SearchQueryInput::FieldedQuery {
+                    field: field.clone(),
+                    query: pdb::Query::Match {
+                        value: text.clone(),
+                        tokenizer: None,
+                        distance: Some(distance),
+                        transposition_cost_one: Some(true),
+                        prefix: Some(false),
+                        conjunction_mode: None,
+                        stopwords: None,
+                    },
+                }),
+            );
+        }
+
+        if !term
+            .iter()
+            .any(|(c, escaped)| !escaped && matches!(c, '*' | '?'))
+        {
+            return Ok(self.text(field, quote(&text)));
+        }
+        if let (Some(field), [('*', false)]) = (field, term.as_slice()) {
+            return Ok(SearchQueryInput::FieldedQuery {
+                field: field.clone(),
+                query: pdb::Query::Exists,
+            });
+        }
+
+        let pattern = term
+            .iter()
+            .map(|(c, escaped)| match (c, escaped) {
+                ('*', false) => ".*".into(),
+                ('?', false) => ".".into(),
+                (c, _) => regex::escape(&c.to_string()),
+            })
+            .collect::<String>();
+        Ok(
+            self.each_text_field(field, |field| SearchQueryInput::FieldedQuery {
+                field: field.clone(),
+                query: pdb::Query::Regex {
+                    pattern: pattern.clone(),
+                },
+            }),
+        )
+    }
+
+    /// A range, whose bounds are inclusive in `[]` and exclusive in `{}`, and unbounded if `*`.
+    fn range(&mut self, field: Option<&FieldName>) -> Result<SearchQueryInput, QueryError> {
+        let open = self.next().unwrap();
+        self.skip_whitespace();
+        let lower = self.bound()?;
+        self.skip_whitespace();
+        if !self.keyword("TO") {
+            return Err(self.error("expected `TO` in the range"));
+        }
+        self.skip_whitespace();
+        let upper = self.bound()?;
+        self.skip_whitespace();
+        let close = match self.next() {
+            Some(close @ (']' | '}')) => close,
+            _ => return Err(self.error("expected `]` or `}` to close the range")),
+        };
+        Ok(self.text(field, format!("{open}{lower} TO {upper}{close}")))
+    }
+
+    fn bound(&mut self) -> Result<String, QueryError> {
+        if self.peek() == Some('"') {
+            return Ok(quote(&self.quoted('"')?));
+        }
+
+        let bound = self.take_while(|c| !c.is_whitespace() && !matches!(c, ']' | '}'));
+        match bound.as_str() {
+            "" => Err(self.error("expected a bound of the range")),
+            "*" => Ok(bound),
+            _ => Ok(quote(&bound)),
+        }
+    }
+
+    fn boosted(&mut self, query: SearchQueryInput) -> Result<SearchQueryInput, QueryError> {
+        if !self.symbol("^") {
+            return Ok(query);
+        }
+        let factor = self
+            .number()
+            .and_then(|factor| factor.parse::<f32>().ok())
+            .ok_or_else(|| self.error("expected a boost after `^`"))?;
+        Ok(SearchQueryInput::Boost {
+            query: Box::new(query),
+            factor,
+        })
+    }
+
+    /// The terms, phrases, and ranges are parsed by the index's query parser, in the `field` or
+    /// in its default fields.
+    fn text(&self, field: Option<&FieldName>, query_string: String) -> SearchQueryInput {
+        match field {
+            Some(field) => SearchQueryInput::FieldedQuery {
+                field: field.clone(),
+                query: pdb::Query::ParseWithField {
+                    query_string,
+                    lenient: self.lenient,
+                    conjunction_mode: None,
+                    fuzzy_data: None,
+                },
+            },
+            None => SearchQueryInput::Parse {
+                query_string,
+                lenient: self.lenient,
+                conjunction_mode: None,
+                syntax: None,
+            },
+        }
+    }
+
+    /// The query of the `field`, or either of the queries of the text fields.
+    fn each_text_field(
+        &self,
+        field: Option<&FieldName>,
+        query: impl Fn(&FieldName) -> SearchQueryInput,
+    ) -> SearchQueryInput {
+        match field {
+            Some(field) => query(field),
+            None => SearchQueryInput::Boolean {
+                must: vec![],
+                should: self.text_fields.iter().map(query).collect(),
+                must_not: vec![],
+            },
+        }
+    }
+
+    /// The characters of a term, with whether each was escaped by a `\`.
+    fn term_chars(&mut self) -> Vec<(char, bool)> {
+        let mut chars = vec![];
+        while let Some(c) = self.peek() {
+            match c {
+                '\\' => match self.chars.get(self.pos + 1) {
+                    Some(&escaped) => {
+                        chars.push((escaped, true));
+                        self.pos += 2;
+                        continue;
+                    }
+                    None => break,
+                },
+                // like Lucene, `+` and `-` are only operators at the start of a term
+                '+' | '-' if !chars.is_empty() => {}
+                c if c.is_whitespace() || r#"+-!():^[]"{}~/"#.contains(c) => break,
+                _ => {}
+            }
+            chars.push((c, false));
+            self.pos += 1;
+        }
+        chars
+    }
+
+    /// The text between a pair of `delimiter`s, which can be escaped inside with a `\`.
+    fn quoted(&mut self, delimiter: char) -> Result<String, QueryError> {
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                None => return Err(self.error(format!("expected a closing `{delimiter}`"))),
+                Some(c) if c == delimiter => return Ok(text),
+                Some('\\') if self.peek() == Some(delimiter) => {
+                    text.push(self.next().unwrap());
+                }
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Option<String> {
+        let number = self.take_while(|c| c.is_ascii_digit() || c == '.');
+        (!number.is_empty()).then_some(number)
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&predicate) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Consume the `keyword` if it's a word of its own.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let end = self.pos + keyword.chars().count();
+        let is_keyword = self.lookahead(keyword)
+            && self
+                .chars
+                .get(end)
+                .is_none_or(|c| c.is_whitespace() || matches!(c, '(' | ')'));
+        if is_keyword {
+            self.pos = end;
+        }
+        is_keyword
+    }
+
+    fn symbol(&mut self, symbol: &str) -> bool {
+        let is_symbol = self.lookahead(symbol);
+        if is_symbol {
+            self.pos += symbol.chars().count();
+        }
+        is_symbol
+    }
+
+    fn lookahead(&self, text: &str) -> bool {
+        text.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += c.is_some() as usize;
+        c
+    }
+
+    fn error(&self, message: impl Display) -> QueryError {
+        QueryError::LuceneParseError(
+            format!("{message} at character {}", self.pos + 1),
+            self.query_string.to_string(),
+        )
+    }
+}
+
+/// A phrase of the index's query parser.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn parse(query_string: &str) -> SearchQueryInput {
+        super::parse(query_string, &["title".into(), "body".into()], None, false).unwrap()
+    }
+
+    fn fielded(field: &str, query_string: &str) -> SearchQueryInput {
+        SearchQueryInput::FieldedQuery {
+            field: field.into(),
+            query: pdb::Query::ParseWithField {
+                query_string: query_string.into(),
+                lenient: None,
+                conjunction_mode: None,
+                fuzzy_data: None,
+            },
+        }
+    }
+
+    fn unfielded(query_string: &str) -> SearchQueryInput {
+        SearchQueryInput::Parse {
+            query_string: query_string.into(),
+            lenient: None,
+            conjunction_mode: None,
+            syntax: None,
+        }
+    }
+
+    fn regex(field: &str, pattern: &str) -> SearchQueryInput {
+        SearchQueryInput::FieldedQuery {
+            field: field.into(),
+            query: pdb::Query::Regex {
+                pattern: pattern.into(),
+            },
+        }
+    }
+
+    #[rstest]
+    fn test_operators() {
+        assert_eq!(
+            parse("+apple -title:pie crumble"),
+            SearchQueryInput::Boolean {
+                must: vec![unfielded(r#""apple""#)],
+                should: vec![unfielded(r#""crumble""#)],
+                must_not: vec![fielded("title", r#""pie""#)],
+            }
+        );
+        // like Lucene, `a AND b OR c` requires `a` and `b`
+        assert_eq!(
+            parse("a AND b OR c NOT d"),
+            SearchQueryInput::Boolean {
+                must: vec![unfielded(r#""a""#), unfielded(r#""b""#)],
+                should: vec![unfielded(r#""c""#)],
+                must_not: vec![unfielded(r#""d""#)],
+            }
+        );
+        assert_eq!(
+            super::parse("a b || c", &[], None, true).unwrap(),
+            SearchQueryInput::Boolean {
+                must: vec![unfielded(r#""a""#)],
+                should: vec![unfielded(r#""b""#), unfielded(r#""c""#)],
+                must_not: vec![],
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_fields_and_groups() {
+        assert_eq!(
+            parse("title:(red OR wi-fi)^2"),
+            SearchQueryInput::Boost {
+                query: Box::new(SearchQueryInput::Boolean {
+                    must: vec![],
+                    should: vec![fielded("title", r#""red""#), fielded("title", r#""wi-fi""#)],
+                    must_not: vec![],
+                }),
+                factor: 2.0,
+            }
+        );
+        assert_eq!(parse("*:*"), SearchQueryInput::All);
+        assert_eq!(
+            parse("body:*"),
+            SearchQueryInput::FieldedQuery {
+                field: "body".into(),
+                query: pdb::Query::Exists,
+            }
+        );
+        assert_eq!(parse(r"title:a\:b"), fielded("title", r#""a:b""#));
+    }
+
+    #[rstest]
+    fn test_phrases_and_ranges() {
+        assert_eq!(
+            parse(r#"body:"running shoes"~3"#),
+            fielded("body", r#""running shoes"~3"#)
+        );
+        assert_eq!(parse("rating:[2 TO *}"), fielded("rating", r#"["2" TO *}"#));
+        assert_eq!(
+            parse(r#"created_at:{"2023-05-01T00:00:00Z" TO 2023-06-01T00:00:00Z]"#),
+            fielded(
+                "created_at",
+                r#"{"2023-05-01T00:00:00Z" TO "2023-06-01T00:00:00Z"]"#
+            )
+        );
+    }
+
+    #[rstest]
+    fn test_fuzzy_and_wildcards() {
+        let fuzzy = |field: &str, distance| SearchQueryInput::FieldedQuery {
+            field: field.into(),
+            query: pdb::Query::Match {
+                value: "shoo".into(),
+                tokenizer: None,
+                distance: Some(distance),
+                transposition_cost_one: Some(true),
+                prefix: Some(false),
+                conjunction_mode: None,
+                stopwords: None,
+            },
+        };
+        assert_eq!(parse("body:shoo~1"), fuzzy("body", 1));
+        assert_eq!(
+            parse("shoo~"),
+            SearchQueryInput::Boolean {
+                must: vec![],
+                should: vec![fuzzy("title", 2), fuzzy("body", 2)],
+                must_not: vec![],
+            }
+        );
+        assert_eq!(parse(r"title:te?t*\*"), regex("title", r"te.t.*\*"));
+        assert_eq!(parse("body:/sho[eo]s?/"), regex("body", "sho[eo]s?"));
+    }
+
+    #[rstest]
+    #[case("(a b", "expected `)` to close the group")]
+    #[case("a b)", "unexpected `)`")]
+    #[case("a AND", "expected a clause after the operator")]
+    #[case("OR a", "expected a clause before the operator")]
+    #[case("a~3", "the distance of a fuzzy term must be 0, 1, or 2")]
+    #[case("rating:[1 2]", "expected `TO` in the range")]
+    #[case(r#"body:"open"#, "expected a closing `\"`")]
+    #[case("a^", "expected a boost after `^`")]
+    fn test_errors(#[case] query_string: &str, #[case] message: &str) {
+        match super::parse(query_string, &[], None, false) {
+            Err(QueryError::LuceneParseError(err, _)) => assert!(
+                err.starts_with(message),
+                "`{query_string}` failed with `{err}`"
+            ),
+            other => panic!("`{query_string}` should not parse: {other:?}"),
+        }
+    }
+}
@@ -15,30 +15,40 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+pub mod element;
 pub mod heap_field_filter;
 pub mod iter_mut;
+mod join_keys;
+pub mod json;
+pub mod lucene;
 mod more_like_this;
 pub mod pdb_query;
 pub(crate) mod proximity;
 mod range;
 mod score;
+pub mod tsquery;
+mod verified_phrase;
 
 use heap_field_filter::HeapFieldFilter;
 
 use crate::api::operator::searchqueryinput_typoid;
 use crate::api::FieldName;
 use crate::api::HashMap;
+use crate::postgres::storage::tenants::tenant_key;
+use crate::postgres::types::scale_decimal;
 use crate::postgres::utils::convert_pg_date_string;
 use crate::query::more_like_this::MoreLikeThisQuery;
 use crate::query::pdb_query::pdb;
 use crate::query::score::ScoreFilter;
-use crate::schema::SearchIndexSchema;
-use anyhow::Result;
+use crate::schema::{SearchField, SearchFieldType, SearchIndexSchema};
+use anyhow::{anyhow, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
 use core::panic;
 use pgrx::{pg_sys, IntoDatum, PgBuiltInOids, PgOid, PostgresType};
 use serde::de::{MapAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::{Debug, Formatter};
+use std::net::IpAddr;
 use std::ops::Bound;
 use tantivy::query::{
     AllQuery, BooleanQuery, BoostQuery, ConstScoreQuery, DisjunctionMaxQuery, EmptyQuery,
@@ -47,7 +57,7 @@ use tantivy::query::{
 use tantivy::DateTime;
 use tantivy::{
     query_grammar::Occur,
-    schema::{Field, FieldType, OwnedValue, DATE_TIME_PRECISION_INDEXED},
+    schema::{Field, FieldType, IntoIpv6Addr, OwnedValue, DATE_TIME_PRECISION_INDEXED},
     Searcher, Term,
 };
 use thiserror::Error;
@@ -87,6 +97,11 @@ pub enum SearchQueryInput {
         disjuncts: Vec<SearchQueryInput>,
         tie_breaker: Option<f32>,
     },
+    /// Matches the rows where a single element of the array `field` matches `query`
+    Element {
+        field: FieldName,
+        query: Box<SearchQueryInput>,
+    },
     Empty,
     MoreLikeThis {
         min_doc_frequency: Option<u64>,
@@ -104,6 +119,9 @@ pub enum SearchQueryInput {
         query_string: String,
         lenient: Option<bool>,
         conjunction_mode: Option<bool>,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        syntax: Option<QuerySyntax>,
     },
 
     TermSet {
@@ -121,6 +139,15 @@ pub enum SearchQueryInput {
         indexed_query: Box<SearchQueryInput>,
         field_filters: Vec<HeapFieldFilter>,
     },
+    /// Matches the rows whose `field` is one of the values of `key_field` of the rows in the index
+    /// `indexrelid` that match `query`, or every row if there are more than `max_keys` of them
+    JoinKeys {
+        field: FieldName,
+        indexrelid: pg_sys::Oid,
+        key_field: FieldName,
+        query: Box<SearchQueryInput>,
+        max_keys: usize,
+    },
 
     #[serde(serialize_with = "serialize_fielded_query")]
     #[serde(deserialize_with = "deserialize_fielded_query")]
@@ -131,6 +158,16 @@ pub enum SearchQueryInput {
     },
 }
 
+/// The syntax of the query string of a [`SearchQueryInput::Parse`].
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QuerySyntax {
+    /// The syntax of the index's own query parser
+    Tantivy,
+    /// Lucene's classic query syntax, which is parsed by [`lucene::parse`]
+    Lucene,
+}
+
 fn serialize_fielded_query<S>(
     field: &FieldName,
     query: &pdb::Query,
@@ -185,15 +222,22 @@ where
                 ));
             };
 
-            if let Some(field_entry) = value.as_object_mut().unwrap().remove_entry("field") {
+            let not_a_query = || {
+                <A::Error as serde::de::Error>::custom(
+                    "this does not appear to be a `pdb::Query` instance",
+                )
+            };
+            let object = value.as_object_mut().ok_or_else(not_a_query)?;
+            if let Some(field_entry) = object.remove_entry("field") {
                 // pull the field out of the object that also contains the FieldedQueryInput
                 let field = field_entry.1;
-                let field = serde_json::from_value::<FieldName>(field).unwrap();
+                let field = serde_json::from_value::<FieldName>(field)
+                    .map_err(<A::Error as serde::de::Error>::custom)?;
 
-                if value.as_object_mut().unwrap().is_empty() {
+                if object.is_empty() {
                     let field_query_input =
                         serde_json::from_value::<pdb::Query>(serde_json::Value::String(key))
-                            .unwrap();
+                            .map_err(<A::Error as serde::de::Error>::custom)?;
                     Ok((field, field_query_input))
                 } else {
                     let mut reconstructed = serde_json::Map::new();
@@ -202,13 +246,11 @@ where
                     let field_query_input = serde_json::from_value::<pdb::Query>(
                         serde_json::Value::Object(reconstructed),
                     )
-                    .unwrap();
+                    .map_err(<A::Error as serde::de::Error>::custom)?;
                     Ok((field, field_query_input))
                 }
             } else {
-                Err(<A::Error as serde::de::Error>::custom(
-                    "this does not appear to be a `pdb::Query` instance",
-                ))
+                Err(not_a_query())
             }
         }
     }
@@ -242,6 +284,7 @@ impl SearchQueryInput {
                 disjuncts.iter().any(Self::need_scores)
             }
             SearchQueryInput::WithIndex { query, .. } => Self::need_scores(query),
+            SearchQueryInput::Element { query, .. } => Self::need_scores(query),
             SearchQueryInput::HeapFilter { indexed_query, .. } => Self::need_scores(indexed_query),
             SearchQueryInput::MoreLikeThis { .. } => true,
             SearchQueryInput::ScoreFilter { .. } => true,
@@ -249,6 +292,119 @@ impl SearchQueryInput {
         }
     }
 
+    /// Does any part of this query exclude the documents matching a subquery?
+    fn has_negation(&self) -> bool {
+        match self {
+            SearchQueryInput::Boolean {
+                must,
+                should,
+                must_not,
+            } => !must_not.is_empty() || must.iter().chain(should.iter()).any(Self::has_negation),
+            SearchQueryInput::Boost { query, .. }
+            | SearchQueryInput::ConstScore { query, .. }
+            | SearchQueryInput::WithIndex { query, .. }
+            | SearchQueryInput::Element { query, .. } => query.has_negation(),
+            SearchQueryInput::ScoreFilter { query, .. } => {
+                query.as_ref().is_some_and(|query| query.has_negation())
+            }
+            SearchQueryInput::DisjunctionMax { disjuncts, .. } => {
+                disjuncts.iter().any(Self::has_negation)
+            }
+            SearchQueryInput::HeapFilter { indexed_query, .. } => indexed_query.has_negation(),
+            _ => false,
+        }
+    }
+
+    /// Does any part of this query evaluate an expression against the heap, rather than only
+    /// searching the index?
+    pub fn depends_on_heap(&self) -> bool {
+        match self {
+            SearchQueryInput::Boolean {
+                must,
+                should,
+                must_not,
+            } => must
+                .iter()
+                .chain(should.iter())
+                .chain(must_not.iter())
+                .any(Self::depends_on_heap),
+            SearchQueryInput::Boost { query, .. }
+            | SearchQueryInput::ConstScore { query, .. }
+            | SearchQueryInput::WithIndex { query, .. }
+            | SearchQueryInput::Element { query, .. } => query.depends_on_heap(),
+            SearchQueryInput::ScoreFilter { query, .. } => {
+                query.as_ref().is_some_and(|query| query.depends_on_heap())
+            }
+            SearchQueryInput::DisjunctionMax { disjuncts, .. } => {
+                disjuncts.iter().any(Self::depends_on_heap)
+            }
+            SearchQueryInput::HeapFilter { .. } | SearchQueryInput::PostgresExpression { .. } => {
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Does any part of this query search another index, so that what it matches depends on more
+    /// than this index's segments?
+    pub fn searches_other_index(&self) -> bool {
+        match self {
+            SearchQueryInput::Boolean {
+                must,
+                should,
+                must_not,
+            } => must
+                .iter()
+                .chain(should.iter())
+                .chain(must_not.iter())
+                .any(Self::searches_other_index),
+            SearchQueryInput::Boost { query, .. }
+            | SearchQueryInput::ConstScore { query, .. }
+            | SearchQueryInput::WithIndex { query, .. }
+            | SearchQueryInput::Element { query, .. } => query.searches_other_index(),
+            SearchQueryInput::ScoreFilter { query, .. } => query
+                .as_ref()
+                .is_some_and(|query| query.searches_other_index()),
+            SearchQueryInput::DisjunctionMax { disjuncts, .. } => {
+                disjuncts.iter().any(Self::searches_other_index)
+            }
+            SearchQueryInput::HeapFilter { indexed_query, .. } => {
+                indexed_query.searches_other_index()
+            }
+            SearchQueryInput::JoinKeys { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// The tenants whose documents are the only ones this query can match, if it's restricted to
+    /// some by a term on `tenant_field` that every match must have.
+    pub fn tenants(&self, tenant_field: &FieldName) -> Option<crate::api::HashSet<String>> {
+        match self {
+            SearchQueryInput::Boolean { must, .. } => {
+                must.iter().find_map(|query| query.tenants(tenant_field))
+            }
+            SearchQueryInput::Boost { query, .. }
+            | SearchQueryInput::ConstScore { query, .. }
+            | SearchQueryInput::WithIndex { query, .. } => query.tenants(tenant_field),
+            SearchQueryInput::ScoreFilter {
+                query: Some(query), ..
+            } => query.tenants(tenant_field),
+            SearchQueryInput::HeapFilter { indexed_query, .. } => {
+                indexed_query.tenants(tenant_field)
+            }
+            SearchQueryInput::FieldedQuery { field, query } if field == tenant_field => match query
+            {
+                pdb::Query::Term {
+                    value,
+                    is_datetime: false,
+                } => tenant_key(value).map(|tenant| [tenant].into_iter().collect()),
+                pdb::Query::TermSet { terms } => terms.iter().map(tenant_key).collect(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     pub fn index_oid(&self) -> Option<pg_sys::Oid> {
         match self {
             SearchQueryInput::WithIndex { oid, .. } => Some(*oid),
@@ -284,7 +440,12 @@ impl SearchQueryInput {
             SearchQueryInput::HeapFilter { indexed_query, .. } => {
                 indexed_query.extract_field_names(field_names);
             }
-            SearchQueryInput::FieldedQuery { field, .. } => {
+            SearchQueryInput::Element { field, query } => {
+                field_names.insert(field.root());
+                query.extract_field_names(field_names);
+            }
+            SearchQueryInput::FieldedQuery { field, .. }
+            | SearchQueryInput::JoinKeys { field, .. } => {
                 field_names.insert(field.root());
             }
             // For other query types, we can't easily extract field names
@@ -418,6 +579,83 @@ fn check_range_bounds(
     Ok((lower_bound, upper_bound))
 }
 
+/// Converts a query value into the form a field's indexed values take when that differs from
+/// how the value arrives:  a NUMERIC field stored with a fixed-point scale holds scaled integers,
+/// a binary UUID field holds each UUID's raw bytes, and a bytea field's values come back from the
+/// query's serialized form as base64 text.  Values for any other field are returned unchanged.
+fn coerce_to_indexed_value(search_field: &SearchField, value: OwnedValue) -> Result<OwnedValue> {
+    if matches!(search_field.field_type(), SearchFieldType::Bytes(_)) {
+        return match value {
+            OwnedValue::Str(text) => Ok(OwnedValue::Bytes(decode_bytes(&text)?)),
+            value => Ok(value),
+        };
+    }
+
+    if search_field.is_binary_uuid() {
+        return match value {
+            OwnedValue::Str(text) => Ok(OwnedValue::Bytes(
+                uuid::Uuid::parse_str(&text)
+                    .map_err(|_| anyhow!("`{text}` is not a valid UUID"))?
+                    .as_bytes()
+                    .to_vec(),
+            )),
+            value => Ok(value),
+        };
+    }
+
+    let Some(scale) = search_field.numeric_scale() else {
+        return Ok(value);
+    };
+
+    let text = match &value {
+        OwnedValue::U64(n) => n.to_string(),
+        OwnedValue::I64(n) => n.to_string(),
+        OwnedValue::F64(n) => n.to_string(),
+        OwnedValue::Str(text) => text.clone(),
+        _ => return Ok(value),
+    };
+    scale_decimal(&text, scale)
+        .map(OwnedValue::I64)
+        .ok_or_else(|| {
+            anyhow!("`{text}` cannot be compared to a numeric field with a scale of {scale}")
+        })
+}
+
+/// Decodes a bytea query value, given either in Postgres' hex format (`\xdeadbeef`) or as
+/// base64, which is how a bytes value is serialized.
+fn decode_bytes(text: &str) -> Result<Vec<u8>> {
+    if let Some(hex) = text.strip_prefix("\\x") {
+        if hex.len() % 2 != 0 {
+            return Err(anyhow!("`{text}` is not valid hex-encoded bytea"));
+        }
+        return hex
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| {
+                std::str::from_utf8(pair)
+                    .ok()
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| anyhow!("`{text}` is not valid hex-encoded bytea"))
+            })
+            .collect();
+    }
+
+    BASE64_STANDARD
+        .decode(text)
+        .map_err(|_| anyhow!("`{text}` is not valid base64-encoded bytea"))
+}
+
+fn coerce_bound_to_indexed_value(
+    search_field: &SearchField,
+    bound: Bound<OwnedValue>,
+) -> Result<Bound<OwnedValue>> {
+    Ok(match bound {
+        Bound::Included(value) => Bound::Included(coerce_to_indexed_value(search_field, value)?),
+        Bound::Excluded(value) => Bound::Excluded(coerce_to_indexed_value(search_field, value)?),
+        Bound::Unbounded => Bound::Unbounded,
+    })
+}
+
 fn coerce_bound_to_field_type(
     bound: Bound<OwnedValue>,
     field_type: &FieldType,
@@ -530,6 +768,49 @@ impl SearchQueryInput {
                     Ok(Box::new(DisjunctionMaxQuery::new(disjuncts)))
                 }
             }
+            SearchQueryInput::Element { field, query } => {
+                let search_field = schema
+                    .search_field(&field)
+                    .ok_or(QueryError::NonIndexedField(field.clone()))?;
+                let element_oid = schema
+                    .categorized_fields()
+                    .iter()
+                    .find(|(categorized, data)| {
+                        categorized.field() == search_field.field() && data.is_array
+                    })
+                    .map(|(_, data)| data.base_oid)
+                    .ok_or_else(|| {
+                        anyhow!("element queries require an array field, but `{field}` is not")
+                    })?;
+                let column = search_field
+                    .field_config()
+                    .alias()
+                    .map(String::from)
+                    .unwrap_or_else(|| field.root());
+
+                // a row can only have an element matching the query if the row as a whole matches
+                // it, unless part of the query is negated
+                let candidates = if query.has_negation() {
+                    SearchQueryInput::All
+                } else {
+                    (*query).clone()
+                };
+                Ok(Box::new(element::ElementQuery::new(
+                    candidates.into_tantivy_query(
+                        schema,
+                        parser,
+                        searcher,
+                        index_oid,
+                        relation_oid,
+                    )?,
+                    query.into_tantivy_query(schema, parser, searcher, index_oid, relation_oid)?,
+                    search_field.field(),
+                    column,
+                    element_oid,
+                    index_oid,
+                    relation_oid.expect("relation_oid is required for element queries"),
+                )))
+            }
             SearchQueryInput::Empty => Ok(Box::new(EmptyQuery)),
             SearchQueryInput::MoreLikeThis {
                 min_doc_frequency,
@@ -605,6 +886,36 @@ impl SearchQueryInput {
                 query_string,
                 lenient,
                 conjunction_mode,
+                syntax: Some(QuerySyntax::Lucene),
+            } => {
+                let text_fields = schema
+                    .fields()
+                    .filter(|(_, entry)| {
+                        schema
+                            .search_field(entry.name())
+                            .is_some_and(|field| field.is_text())
+                    })
+                    .map(|(_, entry)| FieldName::from(entry.name()))
+                    .collect::<Vec<_>>();
+                lucene::parse(
+                    &query_string,
+                    &text_fields,
+                    lenient,
+                    conjunction_mode.unwrap_or(false),
+                )?
+                .into_tantivy_query(
+                    schema,
+                    parser,
+                    searcher,
+                    index_oid,
+                    relation_oid,
+                )
+            }
+            SearchQueryInput::Parse {
+                query_string,
+                lenient,
+                conjunction_mode,
+                ..
             } => {
                 let mut parser = parser();
                 if let Some(true) = conjunction_mode {
@@ -636,6 +947,7 @@ impl SearchQueryInput {
                         .ok_or(QueryError::NonIndexedField(field.clone()))?;
                     let field_type = search_field.field_entry().field_type();
                     let is_datetime = search_field.is_datetime() || is_datetime;
+                    let value = coerce_to_indexed_value(&search_field, value)?;
                     terms.push(value_to_term(
                         search_field.field(),
                         &value,
@@ -670,6 +982,41 @@ impl SearchQueryInput {
                     relation_oid.expect("relation_oid is required for HeapFilter queries"),
                 )))
             }
+            SearchQueryInput::JoinKeys {
+                field,
+                indexrelid,
+                key_field,
+                query,
+                max_keys,
+            } => {
+                let search_field = schema
+                    .search_field(field.root())
+                    .ok_or(QueryError::NonIndexedField(field.clone()))?;
+                let Some(values) =
+                    join_keys::join_key_values(indexrelid, &key_field, *query, max_keys)?
+                else {
+                    // too many keys to be worth searching for, and every row is a superset of
+                    // the rows that can join
+                    return Ok(Box::new(ConstScoreQuery::new(Box::new(AllQuery), 0.0)));
+                };
+
+                let field_type = search_field.field_entry().field_type();
+                let mut terms = vec![];
+                for value in values {
+                    let value = coerce_to_indexed_value(&search_field, value)?;
+                    terms.push(value_to_term(
+                        search_field.field(),
+                        &value,
+                        field_type,
+                        None,
+                        search_field.is_datetime(),
+                    )?);
+                }
+                Ok(Box::new(ConstScoreQuery::new(
+                    Box::new(TermSetQuery::new(terms)),
+                    0.0,
+                )))
+            }
             SearchQueryInput::PostgresExpression { .. } => {
                 panic!("postgres expressions have not been solved")
             }
@@ -761,6 +1108,15 @@ pub fn value_to_term(
         }
     }
 
+    if let (FieldType::IpAddr(_), OwnedValue::Str(text)) = (field_type, value) {
+        // IP addresses are serialized as strings, so they come back as text once the query
+        // has made a roundtrip through its datum representation
+        let addr = text
+            .parse::<IpAddr>()
+            .map_err(|_| QueryError::FieldTypeMismatch)?;
+        return Ok(Term::from_field_ip_addr(field, addr.into_ipv6_addr()));
+    }
+
     Ok(match value {
         OwnedValue::Str(text) => Term::from_field_text(field, text),
         OwnedValue::PreTokStr(_) => panic!("pre-tokenized text cannot be converted to term"),
@@ -816,6 +1172,14 @@ pub enum QueryError {
     InvalidTokenizer,
     #[error("field '{0}' is not part of the pg_search index")]
     NonIndexedField(FieldName),
+    #[error(
+        r#"field '{0}' does not record positions, which phrase queries need. Set its "record" to "position" to search it with phrases"#
+    )]
+    NoPositions(FieldName),
+    #[error(
+        r#"field '{0}' does not record positions, and paradedb.phrase_fallback can only check for phrases in text fields that are stored. Set its "record" to "position" or its "stored" to true to search it with phrases"#
+    )]
+    NotStoredForPhrase(FieldName),
     #[error("wrong type given for field")]
     FieldTypeMismatch,
     #[error("could not build regex with pattern '{1}': {0}")]
@@ -825,6 +1189,10 @@ pub enum QueryError {
            make sure to use column:term pairs, and to capitalize AND/OR."#
     )]
     ParseError(#[source] tantivy::query::QueryParserError, String),
+    #[error("could not parse Lucene query '{1}': {0}")]
+    LuceneParseError(String, String),
+    #[error("could not translate tsquery '{1}': {0}")]
+    TsQueryError(String, String),
     #[error("{0}")]
     TantivyError(#[source] tantivy::TantivyError),
     #[error("{0}")]
@@ -161,7 +161,11 @@ impl MoreLikeThisQueryBuilder {
                         doc_fields.push((search_field.field(), values));
                     } else if categorized.is_json {
                         let values = unsafe {
-                            TantivyValue::try_from_datum_json(datum, categorized.base_oid)
+                            TantivyValue::try_from_datum_json(
+                                datum,
+                                categorized.base_oid,
+                                categorized.json_path_filter.as_ref(),
+                            )
                                 .expect("more_like_this: should be able to convert json to tantivy value")
                                 .into_iter()
                                 .map(|v| v.into())
@@ -16,14 +16,17 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use crate::api::FieldName;
+use crate::gucs;
 use crate::query::pdb_query::pdb::FuzzyData;
 use crate::query::proximity::query::ProximityQuery;
 use crate::query::proximity::{ProximityClause, ProximityDistance};
 use crate::query::range::{Comparison, RangeField};
+use crate::query::verified_phrase::VerifiedPhraseQuery;
 use crate::query::{
-    check_range_bounds, coerce_bound_to_field_type, value_to_term, QueryError, SearchQueryInput,
+    check_range_bounds, coerce_bound_to_field_type, coerce_bound_to_indexed_value,
+    coerce_to_indexed_value, value_to_term, QueryError, SearchQueryInput,
 };
-use crate::schema::{IndexRecordOption, SearchIndexSchema};
+use crate::schema::{IndexRecordOption, SearchField, SearchFieldConfig, SearchIndexSchema};
 use pgrx::{pg_extern, pg_schema, InOutFuncs, StringInfo};
 use serde_json::Value;
 use std::collections::Bound;
@@ -33,7 +36,7 @@ use tantivy::query::{
     PhrasePrefixQuery, PhraseQuery, Query as TantivyQuery, Query, QueryParser, RangeQuery,
     RegexPhraseQuery, RegexQuery, TermQuery, TermSetQuery,
 };
-use tantivy::schema::OwnedValue;
+use tantivy::schema::{FieldType, OwnedValue};
 use tantivy::{Score, Searcher, Term};
 use tokenizers::SearchTokenizer;
 
@@ -161,6 +164,9 @@ pub mod pdb {
             transposition_cost_one: Option<bool>,
             prefix: Option<bool>,
             conjunction_mode: Option<bool>,
+            /// Whether the stopwords of the field's tokenizer are removed from `value`.
+            #[serde(skip_serializing_if = "Option::is_none")]
+            stopwords: Option<bool>,
         },
         ParseWithField {
             query_string: String,
@@ -380,6 +386,7 @@ impl pdb::Query {
                 transposition_cost_one,
                 prefix,
                 conjunction_mode,
+                stopwords,
             } => match_query(
                 &field,
                 schema,
@@ -390,6 +397,7 @@ impl pdb::Query {
                 transposition_cost_one,
                 prefix,
                 conjunction_mode,
+                stopwords,
             )?,
             pdb::Query::ParseWithField {
                 query_string,
@@ -420,7 +428,7 @@ impl pdb::Query {
                 right,
             } => proximity(&field, schema, left, distance, right)?,
             pdb::Query::TokenizedPhrase { phrase, slop } => {
-                tokenized_phrase(&field, schema, searcher, &phrase, slop)
+                tokenized_phrase(&field, schema, searcher, &phrase, slop)?
             }
             pdb::Query::Range {
                 lower_bound,
@@ -511,9 +519,10 @@ fn proximity(
     let search_field = schema
         .search_field(field.root())
         .ok_or(QueryError::NonIndexedField(field.clone()))?;
-    if !search_field.is_tokenized_with_freqs_and_positions() {
+    if !search_field.is_text() || search_field.uses_raw_tokenizer() {
         return Err(QueryError::InvalidTokenizer.into());
     }
+    ensure_positions(field, &search_field)?;
 
     let prox = ProximityQuery::new(search_field.field(), left, distance, right);
     Ok(Box::new(prox))
@@ -534,6 +543,7 @@ fn term_set(
     let terms = terms
         .into_iter()
         .map(|term| {
+            let term = coerce_to_indexed_value(&search_field, term)?;
             value_to_term(
                 tantivy_field,
                 &term,
@@ -558,6 +568,7 @@ fn term(
         .ok_or(QueryError::NonIndexedField(field.clone()))?;
     let field_type = search_field.field_entry().field_type();
     let is_datetime = search_field.is_datetime() || is_datetime;
+    let value = &coerce_to_indexed_value(&search_field, value.clone())?;
     let term = value_to_term(
         search_field.field(),
         value,
@@ -566,6 +577,14 @@ fn term(
         is_datetime,
     )?;
 
+    // a field that's only fast, like a boolean with `"indexed": false`, has no postings to look
+    // the term up in, but its fast field has the same values
+    if !search_field.field_entry().is_indexed() && search_field.is_numeric_fast() {
+        return Ok(Box::new(FastFieldRangeQuery::new(
+            Bound::Included(term.clone()),
+            Bound::Included(term),
+        )));
+    }
     Ok(Box::new(TermQuery::new(term, record_option.into())))
 }
 
@@ -579,6 +598,9 @@ fn regex_phrase(
     let search_field = schema
         .search_field(field.root())
         .ok_or(QueryError::NonIndexedField(field.clone()))?;
+    if regexes.len() > 1 {
+        ensure_positions(field, &search_field)?;
+    }
     let mut query = RegexPhraseQuery::new(search_field.field(), regexes);
 
     if let Some(slop) = slop {
@@ -1171,6 +1193,8 @@ fn range(
     let typeoid = search_field.field_type().typeoid();
     let is_datetime = search_field.is_datetime() || is_datetime;
 
+    let lower_bound = coerce_bound_to_indexed_value(&search_field, lower_bound)?;
+    let upper_bound = coerce_bound_to_indexed_value(&search_field, upper_bound)?;
     let lower_bound = coerce_bound_to_field_type(lower_bound, field_type);
     let upper_bound = coerce_bound_to_field_type(upper_bound, field_type);
     let (lower_bound, upper_bound) = check_range_bounds(typeoid, lower_bound, upper_bound)?;
@@ -1220,11 +1244,11 @@ fn tokenized_phrase(
     searcher: &Searcher,
     phrase: &str,
     slop: Option<u32>,
-) -> Box<dyn TantivyQuery> {
-    let tantivy_field = schema
+) -> anyhow::Result<Box<dyn TantivyQuery>> {
+    let search_field = schema
         .search_field(field)
-        .unwrap_or_else(|| core::panic!("Field `{field}` not found in tantivy schema"))
-        .field();
+        .unwrap_or_else(|| core::panic!("Field `{field}` not found in tantivy schema"));
+    let tantivy_field = search_field.field();
     let mut tokenizer = searcher
         .index()
         .tokenizer_for_field(tantivy_field)
@@ -1236,14 +1260,16 @@ fn tokenized_phrase(
         tokens.push(Term::from_field_text(tantivy_field, &token.text));
     }
     if tokens.is_empty() {
-        Box::new(EmptyQuery)
+        Ok(Box::new(EmptyQuery))
     } else if tokens.len() == 1 {
         let query = TermQuery::new(tokens.remove(0), IndexRecordOption::WithFreqs.into());
-        Box::new(query)
+        Ok(Box::new(query))
+    } else if !search_field.has_positions() {
+        phrase_without_positions(field, &search_field, searcher, tokens, slop)
     } else {
         let mut query = PhraseQuery::new(tokens);
         query.set_slop(slop.unwrap_or(0));
-        Box::new(query)
+        Ok(Box::new(query))
     }
 }
 
@@ -1256,6 +1282,9 @@ fn phrase_prefix(
     let search_field = schema
         .search_field(field.root())
         .ok_or(QueryError::NonIndexedField(field.clone()))?;
+    if phrases.len() > 1 {
+        ensure_positions(field, &search_field)?;
+    }
     let field_type = search_field.field_entry().field_type();
     let terms = phrases.clone().into_iter().map(|phrase| {
         value_to_term(
@@ -1320,6 +1349,9 @@ fn phrase(
         pgrx::warning!("Phrase query with multiple tokens per phrase may not be correctly interpreted. Consider using a different tokenizer or switch to parse/match");
     }
 
+    if terms.len() > 1 && !search_field.has_positions() {
+        return phrase_without_positions(field, &search_field, searcher, terms, slop);
+    }
     let mut query = PhraseQuery::new(terms);
     if let Some(slop) = slop {
         query.set_slop(slop)
@@ -1327,6 +1359,45 @@ fn phrase(
     Ok(Box::new(query))
 }
 
+/// A phrase of more than one term can only search a field that records the positions of its
+/// tokens.  Without them, tantivy would only fail once the query's scorer is built.
+fn ensure_positions(field: &FieldName, search_field: &SearchField) -> Result<(), QueryError> {
+    if search_field.has_positions() {
+        Ok(())
+    } else {
+        Err(QueryError::NoPositions(field.clone()))
+    }
+}
+
+/// A phrase of more than one term of a field that doesn't record positions, which is an error
+/// unless `paradedb.phrase_fallback` is on.  Then the documents with all of the terms are checked
+/// for the phrase in the field's stored values, so the field has to be a stored text field.
+fn phrase_without_positions(
+    field: &FieldName,
+    search_field: &SearchField,
+    searcher: &Searcher,
+    terms: Vec<Term>,
+    slop: Option<u32>,
+) -> anyhow::Result<Box<dyn TantivyQuery>> {
+    if !gucs::phrase_fallback() {
+        return Err(QueryError::NoPositions(field.clone()).into());
+    }
+    let is_stored = matches!(
+        search_field.field_config(),
+        SearchFieldConfig::Text { stored: true, .. }
+    );
+    if !search_field.is_text() || !is_stored {
+        return Err(QueryError::NotStoredForPhrase(field.clone()).into());
+    }
+    let analyzer = searcher.index().tokenizer_for_field(search_field.field())?;
+    Ok(Box::new(VerifiedPhraseQuery::new(
+        search_field.field(),
+        terms,
+        slop.unwrap_or(0),
+        analyzer,
+    )))
+}
+
 fn parse<QueryParserCtor: Fn() -> QueryParser>(
     field: &FieldName,
     parser: &QueryParserCtor,
@@ -1336,6 +1407,15 @@ fn parse<QueryParserCtor: Fn() -> QueryParser>(
     conjunction_mode: Option<bool>,
     fuzzy_data: Option<FuzzyData>,
 ) -> anyhow::Result<Box<dyn TantivyQuery>> {
+    // a boolean field's query is a boolean, which Postgres spells more ways than tantivy does
+    if let Some(search_field) = schema.search_field(field) {
+        if matches!(search_field.field_entry().field_type(), FieldType::Bool(_)) {
+            if let Some(value) = parse_bool(&query_string) {
+                return term(field.clone(), schema, &OwnedValue::Bool(value), false);
+            }
+        }
+    }
+
     let mut parser = parser();
     let query_string = format!("{field}:({query_string})");
     if let Some(true) = conjunction_mode {
@@ -1367,6 +1447,15 @@ fn parse<QueryParserCtor: Fn() -> QueryParser>(
     })
 }
 
+/// `value` as a boolean in any of the spellings of Postgres' `boolean` type, like `t` or `yes`.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "t" | "true" | "y" | "yes" | "on" | "1" => Some(true),
+        "f" | "false" | "n" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn match_query(
     field: &FieldName,
@@ -1378,6 +1467,7 @@ fn match_query(
     transposition_cost_one: Option<bool>,
     prefix: Option<bool>,
     conjunction_mode: Option<bool>,
+    stopwords: Option<bool>,
 ) -> anyhow::Result<Box<dyn TantivyQuery>> {
     let distance = distance.unwrap_or(0);
     let transposition_cost_one = transposition_cost_one.unwrap_or(true);
@@ -1388,10 +1478,22 @@ fn match_query(
         .search_field(field.root())
         .ok_or(QueryError::NonIndexedField(field.clone()))?;
     let field_type = search_field.field_entry().field_type();
+    let tokenizer = match tokenizer {
+        Some(tokenizer) => Some(
+            SearchTokenizer::from_json_value(&tokenizer)
+                .map_err(|_| QueryError::InvalidTokenizer)?,
+        ),
+        // `value` keeps its stopwords by being analyzed with the field's tokenizer, minus its stopwords
+        None if stopwords == Some(false) => search_field.field_config().tokenizer().cloned(),
+        None => None,
+    };
     let mut analyzer = match tokenizer {
         Some(tokenizer) => {
-            let tokenizer = SearchTokenizer::from_json_value(&tokenizer)
-                .map_err(|_| QueryError::InvalidTokenizer)?;
+            let tokenizer = if stopwords == Some(false) {
+                tokenizer.without_stopwords()
+            } else {
+                tokenizer
+            };
             tokenizer
                 .to_tantivy_tokenizer()
                 .ok_or(QueryError::InvalidTokenizer)?
@@ -0,0 +1,704 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Postgres' `tsquery`s, which `@@@` and `paradedb.tsquery()` search a BM25 index with, so that
+//! the queries of an application that uses a `tsvector` column and a GIN index can be moved to the
+//! BM25 index without being rewritten.
+//!
+//! A `tsquery` is read from its text representation, whose lexemes have already been normalized by
+//! its text search configuration.  They're searched for as terms, so the fields they're searched
+//! in should be tokenized and stemmed the same way.
+
+use crate::api::FieldName;
+use crate::postgres::rel::PgSearchRelation;
+use crate::postgres::utils::locate_bm25_index_from_heaprel;
+use crate::query::pdb_query::pdb;
+use crate::query::{QueryError, SearchQueryInput};
+use pgrx::callconv::{Arg, ArgAbi};
+use pgrx::pgrx_sql_entity_graph::metadata::{
+    ArgumentError, Returns, ReturnsError, SqlMapping, SqlTranslatable,
+};
+use pgrx::{direct_function_call, pg_sys, FromDatum, PgList};
+use std::ffi::CStr;
+use std::fmt::Display;
+use tantivy::schema::OwnedValue;
+
+/// A `tsquery` argument, as its text representation.
+pub struct TsQuery(pub String);
+
+impl FromDatum for TsQuery {
+    unsafe fn from_polymorphic_datum(
+        datum: pg_sys::Datum,
+        is_null: bool,
+        _typoid: pg_sys::Oid,
+    ) -> Option<Self> {
+        if is_null {
+            return None;
+        }
+        let text = direct_function_call::<&CStr>(pg_sys::tsqueryout, &[Some(datum)])?;
+        Some(TsQuery(text.to_string_lossy().into_owned()))
+    }
+}
+
+unsafe impl SqlTranslatable for TsQuery {
+    fn argument_sql() -> Result<SqlMapping, ArgumentError> {
+        Ok(SqlMapping::As("tsquery".into()))
+    }
+
+    fn return_sql() -> Result<Returns, ReturnsError> {
+        Ok(Returns::One(SqlMapping::As("tsquery".into())))
+    }
+}
+
+unsafe impl<'fcx> ArgAbi<'fcx> for TsQuery
+where
+    Self: 'fcx,
+{
+    unsafe fn unbox_arg_unchecked(arg: Arg<'_, 'fcx>) -> Self {
+        let index = arg.index();
+        unsafe {
+            arg.unbox_arg_using_from_datum()
+                .unwrap_or_else(|| panic!("argument {index} must not be null"))
+        }
+    }
+}
+
+/// A field that a `tsquery` is searched in, and the weight its lexemes have in the `tsvector` it
+/// stands in for, if it was given one with `setweight()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TsVectorField {
+    pub field: FieldName,
+    pub weight: Option<char>,
+}
+
+impl TsVectorField {
+    /// Whether a lexeme restricted to `weights` can be in this field.  A field without a weight
+    /// can have lexemes of any.
+    fn has_weight(&self, weights: &[char]) -> bool {
+        match self.weight {
+            Some(weight) if !weights.is_empty() => weights.contains(&weight),
+            _ => true,
+        }
+    }
+}
+
+/// Translate the text representation of a `tsquery` to a query of the `fields`, which match the
+/// rows whose `tsvector` it would match.
+///
+/// A lexeme matches a row with the term in any of the fields of its weights, and the lexemes of a
+/// phrase have to be in the same field.  A phrase whose lexemes are further apart than `<->` is
+/// searched for with some slop, so it also matches them when they're closer.
+pub fn to_search_query_input(
+    tsquery: &str,
+    fields: &[TsVectorField],
+) -> Result<SearchQueryInput, QueryError> {
+    let mut parser = Parser {
+        tsquery,
+        chars: tsquery.chars().collect(),
+        pos: 0,
+    };
+    parser.skip_whitespace();
+    if parser.peek().is_none() {
+        // an empty `tsquery`, which is what the stopwords of a query are normalized to
+        return Ok(SearchQueryInput::Empty);
+    }
+    let node = parser.or()?;
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected `)`"));
+    }
+    node.to_query(fields)
+        .map_err(|message| QueryError::TsQueryError(message, tsquery.to_string()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Lexeme {
+        text: String,
+        prefix: bool,
+        weights: Vec<char>,
+    },
+    Not(Box<Node>),
+    And(Vec<Node>),
+    Or(Vec<Node>),
+    /// The right side is `distance` positions after the left
+    Phrase(Box<Node>, u32, Box<Node>),
+}
+
+impl Node {
+    fn to_query(&self, fields: &[TsVectorField]) -> Result<SearchQueryInput, String> {
+        Ok(match self {
+            Node::Lexeme {
+                text,
+                prefix,
+                weights,
+            } => any_field(fields, &[weights.as_slice()], |field| {
+                to_fielded(
+                    field,
+                    if *prefix {
+                        pdb::Query::FuzzyTerm {
+                            value: text.clone(),
+                            distance: Some(0),
+                            transposition_cost_one: Some(true),
+                            prefix: Some(true),
+                        }
+                    } else {
+                        pdb::Query::Term {
+                            value: OwnedValue::Str(text.clone()),
+                            is_datetime: false,
+                        }
+                    },
+                )
+            }),
+            Node::Not(node) => SearchQueryInput::Boolean {
+                must: vec![SearchQueryInput::All],
+                should: vec![],
+                must_not: vec![node.to_query(fields)?],
+            },
+            Node::And(nodes) => {
+                let (mut must, mut must_not) = (vec![], vec![]);
+                for node in nodes {
+                    match node {
+                        Node::Not(node) => must_not.push(node.to_query(fields)?),
+                        node => must.push(node.to_query(fields)?),
+                    }
+                }
+                if must.is_empty() {
+                    must.push(SearchQueryInput::All);
+                }
+                SearchQueryInput::Boolean {
+                    must,
+                    should: vec![],
+                    must_not,
+                }
+            }
+            Node::Or(nodes) => SearchQueryInput::Boolean {
+                must: vec![],
+                should: nodes
+                    .iter()
+                    .map(|node| node.to_query(fields))
+                    .collect::<Result<_, _>>()?,
+                must_not: vec![],
+            },
+            Node::Phrase(..) => {
+                let mut lexemes = vec![];
+                let mut slop = 0;
+                self.phrase_lexemes(&mut lexemes, &mut slop)?;
+                let (last, rest) = lexemes.split_last().unwrap();
+                if rest.iter().any(|(_, prefix, _)| *prefix) {
+                    return Err("only the last lexeme of a phrase can be a prefix".into());
+                }
+                let prefix = last.1;
+                if prefix && slop > 0 {
+                    return Err("a phrase that ends with a prefix must use `<->`".into());
+                }
+                let weights = lexemes
+                    .iter()
+                    .map(|(_, _, weights)| weights.as_slice())
+                    .collect::<Vec<_>>();
+                let phrases = lexemes
+                    .iter()
+                    .map(|(text, _, _)| text.clone())
+                    .collect::<Vec<_>>();
+                any_field(fields, &weights, |field| {
+                    to_fielded(
+                        field,
+                        if prefix {
+                            pdb::Query::PhrasePrefix {
+                                phrases: phrases.clone(),
+                                max_expansions: None,
+                            }
+                        } else {
+                            pdb::Query::Phrase {
+                                phrases: phrases.clone(),
+                                slop: (slop > 0).then_some(slop),
+                            }
+                        },
+                    )
+                })
+            }
+        })
+    }
+
+    /// The lexemes of a phrase in order, and how many more positions apart than adjacent they are.
+    fn phrase_lexemes<'a>(
+        &'a self,
+        lexemes: &mut Vec<(String, bool, &'a Vec<char>)>,
+        slop: &mut u32,
+    ) -> Result<(), String> {
+        match self {
+            Node::Lexeme {
+                text,
+                prefix,
+                weights,
+            } => lexemes.push((text.clone(), *prefix, weights)),
+            Node::Phrase(left, distance, right) => {
+                left.phrase_lexemes(lexemes, slop)?;
+                *slop += distance.saturating_sub(1);
+                right.phrase_lexemes(lexemes, slop)?;
+            }
+            _ => return Err("the operands of a phrase must be lexemes".into()),
+        }
+        Ok(())
+    }
+}
+
+/// `query` of each of the `fields` that can have lexemes of each of the `weights`.
+fn any_field(
+    fields: &[TsVectorField],
+    weights: &[&[char]],
+    query: impl Fn(&FieldName) -> SearchQueryInput,
+) -> SearchQueryInput {
+    let mut queries = fields
+        .iter()
+        .filter(|field| weights.iter().all(|weights| field.has_weight(weights)))
+        .map(|field| query(&field.field))
+        .collect::<Vec<_>>();
+    match queries.len() {
+        0 => SearchQueryInput::Empty,
+        1 => queries.pop().unwrap(),
+        _ => SearchQueryInput::Boolean {
+            must: vec![],
+            should: queries,
+            must_not: vec![],
+        },
+    }
+}
+
+fn to_fielded(field: &FieldName, query: pdb::Query) -> SearchQueryInput {
+    SearchQueryInput::FieldedQuery {
+        field: field.clone(),
+        query,
+    }
+}
+
+/// A parser of the text representation of a `tsquery`, whose operators bind from `!`, the
+/// tightest, to `<->`, `&`, and `|`, the loosest.
+struct Parser<'a> {
+    tsquery: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn or(&mut self) -> Result<Node, QueryError> {
+        let mut nodes = vec![self.and()?];
+        while self.symbol("|") {
+            nodes.push(self.and()?);
+        }
+        Ok(match nodes.len() {
+            1 => nodes.pop().unwrap(),
+            _ => Node::Or(nodes),
+        })
+    }
+
+    fn and(&mut self) -> Result<Node, QueryError> {
+        let mut nodes = vec![self.phrase()?];
+        while self.symbol("&") {
+            nodes.push(self.phrase()?);
+        }
+        Ok(match nodes.len() {
+            1 => nodes.pop().unwrap(),
+            _ => Node::And(nodes),
+        })
+    }
+
+    fn phrase(&mut self) -> Result<Node, QueryError> {
+        let mut node = self.unary()?;
+        loop {
+            let distance = if self.symbol("<->") {
+                1
+            } else if self.symbol("<") {
+                let distance = self.take_while(|c| c.is_ascii_digit());
+                if !self.symbol(">") {
+                    return Err(self.error("expected `>` after the distance"));
+                }
+                distance
+                    .parse()
+                    .map_err(|_| self.error("expected a distance"))?
+            } else {
+                return Ok(node);
+            };
+            node = Node::Phrase(Box::new(node), distance, Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, QueryError> {
+        if self.symbol("!") {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        if self.symbol("(") {
+            let node = self.or()?;
+            if !self.symbol(")") {
+                return Err(self.error("expected `)` to close the group"));
+            }
+            return Ok(node);
+        }
+        self.skip_whitespace();
+        if self.peek() != Some('\'') {
+            return Err(self.error("expected a lexeme"));
+        }
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                None => return Err(self.error("expected a closing `'`")),
+                Some('\'') if self.peek() == Some('\'') => text.push(self.next().unwrap()),
+                Some('\'') => break,
+                Some('\\') => match self.next() {
+                    Some(c) => text.push(c),
+                    None => return Err(self.error("expected a closing `'`")),
+                },
+                Some(c) => text.push(c),
+            }
+        }
+
+        let (mut prefix, mut weights) = (false, vec![]);
+        if self.peek() == Some(':') {
+            self.pos += 1;
+            for c in self.take_while(|c| matches!(c.to_ascii_uppercase(), '*' | 'A'..='D')) {
+                match c {
+                    '*' => prefix = true,
+                    weight => weights.push(weight.to_ascii_uppercase()),
+                }
+            }
+        }
+        Ok(Node::Lexeme {
+            text,
+            prefix,
+            weights,
+        })
+    }
+
+    /// Consume the `symbol` if it's next, after any whitespace.
+    fn symbol(&mut self, symbol: &str) -> bool {
+        self.skip_whitespace();
+        let is_symbol = symbol
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c));
+        if is_symbol {
+            self.pos += symbol.chars().count();
+        }
+        is_symbol
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&predicate) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += c.is_some() as usize;
+        c
+    }
+
+    fn error(&self, message: impl Display) -> QueryError {
+        QueryError::TsQueryError(
+            format!("{message} at character {}", self.pos + 1),
+            self.tsquery.to_string(),
+        )
+    }
+}
+
+/// The fields of the BM25 index on `heaprelid` that the generated `tsvector` column `attno` is
+/// made from, such as `title` and `body` of
+///
+/// ```sql
+/// setweight(to_tsvector('english', title), 'A') || setweight(to_tsvector('english', body), 'B')
+/// ```
+pub unsafe fn tsvector_fields(
+    heaprelid: pg_sys::Oid,
+    attno: pg_sys::AttrNumber,
+) -> Result<Vec<TsVectorField>, String> {
+    let heaprel = PgSearchRelation::open(heaprelid);
+    let tupdesc = heaprel.tuple_desc();
+    let attribute = tupdesc
+        .get(attno as usize - 1)
+        .ok_or_else(|| format!("attribute {attno} of `{}` does not exist", heaprel.name()))?;
+    let column = attribute.name().to_string();
+
+    let constr = (*(*heaprel.as_ptr()).rd_att).constr;
+    let expression = (attribute.attgenerated != 0 && !constr.is_null())
+        .then(|| {
+            (0..(*constr).num_defval as usize)
+                .map(|i| &*(*constr).defval.add(i))
+                .find(|default| default.adnum == attno)
+        })
+        .flatten()
+        .map(|default| pg_sys::stringToNode(default.adbin).cast::<pg_sys::Node>())
+        .ok_or_else(|| {
+            format!("`{column}` is not a generated column, so search the columns its `tsvector` is made from instead")
+        })?;
+
+    // like those of `to_tsvector()`, the lexemes that aren't given a weight have the weight `D`
+    let mut columns = vec![];
+    collect_columns(expression, 'D', &mut columns)
+        .map_err(|message| format!("the expression of `{column}` {message}"))?;
+
+    let indexrel = locate_bm25_index_from_heaprel(&heaprel)
+        .ok_or_else(|| format!("`{}` does not have a BM25 index", heaprel.name()))?;
+    let schema = indexrel.schema().map_err(|err| err.to_string())?;
+    columns
+        .into_iter()
+        .map(|(attno, weight)| {
+            let name = tupdesc
+                .get(attno as usize - 1)
+                .map(|attribute| attribute.name().to_string())
+                .unwrap_or_default();
+            if schema.search_field(&name).is_none() {
+                return Err(format!(
+                    "`{column}` is made from `{name}`, which is not a field of `{}`",
+                    indexrel.name()
+                ));
+            }
+            Ok(TsVectorField {
+                field: name.into(),
+                weight: Some(weight),
+            })
+        })
+        .collect()
+}
+
+/// The columns of a generated `tsvector`'s expression, and the weights `setweight()` gives them.
+unsafe fn collect_columns(
+    node: *mut pg_sys::Node,
+    weight: char,
+    columns: &mut Vec<(pg_sys::AttrNumber, char)>,
+) -> Result<(), String> {
+    let args = match (*node).type_ {
+        pg_sys::NodeTag::T_Const => return Ok(()),
+        pg_sys::NodeTag::T_Var => {
+            let var = node.cast::<pg_sys::Var>();
+            // the text search configuration can be a column too
+            if (*var).vartype != pg_sys::REGCONFIGOID {
+                columns.push(((*var).varattno, weight));
+            }
+            return Ok(());
+        }
+        pg_sys::NodeTag::T_RelabelType => {
+            return collect_columns(
+                (*node.cast::<pg_sys::RelabelType>()).arg.cast(),
+                weight,
+                columns,
+            )
+        }
+        pg_sys::NodeTag::T_CoerceViaIO => {
+            return collect_columns(
+                (*node.cast::<pg_sys::CoerceViaIO>()).arg.cast(),
+                weight,
+                columns,
+            )
+        }
+        pg_sys::NodeTag::T_FuncExpr => {
+            let funcexpr = node.cast::<pg_sys::FuncExpr>();
+            let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
+            let name = CStr::from_ptr(pg_sys::get_func_name((*funcexpr).funcid));
+            if name == c"setweight" && args.len() == 2 {
+                let weight = args
+                    .get_ptr(1)
+                    .and_then(|arg| {
+                        let arg = arg.cast::<pg_sys::Const>();
+                        ((*arg).xpr.type_ == pg_sys::NodeTag::T_Const
+                            && (*arg).consttype == pg_sys::CHAROID
+                            && !(*arg).constisnull)
+                            .then(|| ((*arg).constvalue.value() as u8 as char).to_ascii_uppercase())
+                    })
+                    .ok_or_else(|| "gives a weight that isn't a constant".to_string())?;
+                return collect_columns(args.get_ptr(0).unwrap(), weight, columns);
+            }
+            args
+        }
+        pg_sys::NodeTag::T_OpExpr => PgList::from_pg((*node.cast::<pg_sys::OpExpr>()).args),
+        pg_sys::NodeTag::T_CoalesceExpr => {
+            PgList::from_pg((*node.cast::<pg_sys::CoalesceExpr>()).args)
+        }
+        _ => return Err("is not made of function calls and operators".into()),
+    };
+    for arg in args.iter_ptr() {
+        collect_columns(arg, weight, columns)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn body() -> Vec<TsVectorField> {
+        vec![TsVectorField {
+            field: "body".into(),
+            weight: None,
+        }]
+    }
+
+    fn term(field: &str, text: &str) -> SearchQueryInput {
+        to_fielded(
+            &field.into(),
+            pdb::Query::Term {
+                value: OwnedValue::Str(text.into()),
+                is_datetime: false,
+            },
+        )
+    }
+
+    #[rstest]
+    fn test_operators() {
+        assert_eq!(
+            to_search_query_input("'cat' & !'dog' | 'fish'", &body()).unwrap(),
+            SearchQueryInput::Boolean {
+                must: vec![],
+                should: vec![
+                    SearchQueryInput::Boolean {
+                        must: vec![term("body", "cat")],
+                        should: vec![],
+                        must_not: vec![term("body", "dog")],
+                    },
+                    term("body", "fish"),
+                ],
+                must_not: vec![],
+            }
+        );
+        assert_eq!(
+            to_search_query_input("!( 'cat' | 'dog' )", &body()).unwrap(),
+            SearchQueryInput::Boolean {
+                must: vec![SearchQueryInput::All],
+                should: vec![],
+                must_not: vec![SearchQueryInput::Boolean {
+                    must: vec![],
+                    should: vec![term("body", "cat"), term("body", "dog")],
+                    must_not: vec![],
+                }],
+            }
+        );
+        assert_eq!(
+            to_search_query_input("'it''s' & 'a\\\\b'", &body()).unwrap(),
+            SearchQueryInput::Boolean {
+                must: vec![term("body", "it's"), term("body", "a\\b")],
+                should: vec![],
+                must_not: vec![],
+            }
+        );
+        assert_eq!(
+            to_search_query_input("", &body()).unwrap(),
+            SearchQueryInput::Empty
+        );
+    }
+
+    #[rstest]
+    fn test_phrases_and_prefixes() {
+        let phrase = |slop| {
+            to_fielded(
+                &"body".into(),
+                pdb::Query::Phrase {
+                    phrases: vec!["fat".into(), "rat".into(), "run".into()],
+                    slop,
+                },
+            )
+        };
+        assert_eq!(
+            to_search_query_input("'fat' <-> 'rat' <-> 'run'", &body()).unwrap(),
+            phrase(None)
+        );
+        assert_eq!(
+            to_search_query_input("'fat' <3> 'rat' <-> 'run'", &body()).unwrap(),
+            phrase(Some(2))
+        );
+        assert_eq!(
+            to_search_query_input("'fat' <-> 'ra':*", &body()).unwrap(),
+            to_fielded(
+                &"body".into(),
+                pdb::Query::PhrasePrefix {
+                    phrases: vec!["fat".into(), "ra".into()],
+                    max_expansions: None,
+                },
+            )
+        );
+        assert_eq!(
+            to_search_query_input("'supern':*", &body()).unwrap(),
+            to_fielded(
+                &"body".into(),
+                pdb::Query::FuzzyTerm {
+                    value: "supern".into(),
+                    distance: Some(0),
+                    transposition_cost_one: Some(true),
+                    prefix: Some(true),
+                },
+            )
+        );
+    }
+
+    #[rstest]
+    fn test_weights() {
+        let fields = vec![
+            TsVectorField {
+                field: "title".into(),
+                weight: Some('A'),
+            },
+            TsVectorField {
+                field: "body".into(),
+                weight: Some('B'),
+            },
+        ];
+        assert_eq!(
+            to_search_query_input("'cat'", &fields).unwrap(),
+            SearchQueryInput::Boolean {
+                must: vec![],
+                should: vec![term("title", "cat"), term("body", "cat")],
+                must_not: vec![],
+            }
+        );
+        assert_eq!(
+            to_search_query_input("'cat':A", &fields).unwrap(),
+            term("title", "cat")
+        );
+        assert_eq!(
+            to_search_query_input("'cat':C", &fields).unwrap(),
+            SearchQueryInput::Empty
+        );
+    }
+
+    #[rstest]
+    #[case("'cat' &", "expected a lexeme")]
+    #[case("( 'cat'", "expected `)` to close the group")]
+    #[case("'cat' )", "unexpected `)`")]
+    #[case("'cat", "expected a closing `'`")]
+    #[case("'cat' <2 'dog'", "expected `>` after the distance")]
+    #[case("( 'a' | 'b' ) <-> 'c'", "the operands of a phrase must be lexemes")]
+    #[case("'a':* <-> 'b'", "only the last lexeme of a phrase can be a prefix")]
+    fn test_errors(#[case] tsquery: &str, #[case] message: &str) {
+        match to_search_query_input(tsquery, &body()) {
+            Err(QueryError::TsQueryError(err, _)) => {
+                assert!(err.starts_with(message), "`{tsquery}` failed with `{err}`")
+            }
+            other => panic!("`{tsquery}` should not translate: {other:?}"),
+        }
+    }
+}
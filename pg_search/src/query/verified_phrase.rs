@@ -0,0 +1,262 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! A phrase query for a text field that doesn't record the positions of its tokens.
+//!
+//! The documents that have all of the phrase's terms are found from the field's postings, and
+//! each of them is then checked for the phrase by tokenizing the field's stored values again, which
+//! is as slow as it sounds: it's meant to keep phrase queries working across indexes whose fields
+//! don't all record positions, not to replace recording them.
+
+use std::fmt::{Debug, Formatter};
+use tantivy::query::{
+    BooleanQuery, EmptyScorer, EnableScoring, Explanation, Occur, Query, QueryClone, Scorer,
+    TermQuery, Weight,
+};
+use tantivy::schema::{Field, IndexRecordOption, Value};
+use tantivy::store::StoreReader;
+use tantivy::tokenizer::TextAnalyzer;
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyDocument, Term, TERMINATED};
+
+pub struct VerifiedPhraseQuery {
+    field: Field,
+    words: Vec<String>,
+    slop: u32,
+    analyzer: TextAnalyzer,
+    query: Box<dyn Query>,
+}
+
+impl Debug for VerifiedPhraseQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VerifiedPhraseQuery")
+            .field("field", &self.field)
+            .field("words", &self.words)
+            .field("slop", &self.slop)
+            .finish()
+    }
+}
+
+impl QueryClone for VerifiedPhraseQuery {
+    fn box_clone(&self) -> Box<dyn Query> {
+        Box::new(Self {
+            field: self.field,
+            words: self.words.clone(),
+            slop: self.slop,
+            analyzer: self.analyzer.clone(),
+            query: self.query.box_clone(),
+        })
+    }
+}
+
+impl VerifiedPhraseQuery {
+    /// Match the documents whose stored values of `field` have `terms` in order, with at most
+    /// `slop` other tokens between them, as tokenized by `analyzer`.  The terms must be text.
+    pub fn new(field: Field, terms: Vec<Term>, slop: u32, analyzer: TextAnalyzer) -> Self {
+        let words = terms
+            .iter()
+            .map(|term| {
+                term.value()
+                    .as_str()
+                    .expect("phrase terms should be text")
+                    .to_string()
+            })
+            .collect();
+        let query = BooleanQuery::new(
+            terms
+                .into_iter()
+                .map(|term| {
+                    let query: Box<dyn Query> =
+                        Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs));
+                    (Occur::Must, query)
+                })
+                .collect(),
+        );
+        Self {
+            field,
+            words,
+            slop,
+            analyzer,
+            query: Box::new(query),
+        }
+    }
+}
+
+struct VerifiedPhraseWeight {
+    field: Field,
+    words: Vec<String>,
+    slop: u32,
+    analyzer: TextAnalyzer,
+    weight: Box<dyn Weight>,
+}
+
+impl Weight for VerifiedPhraseWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let scorer = self.weight.scorer(reader, boost)?;
+        let mut scorer = VerifiedPhraseScorer {
+            field: self.field,
+            words: self.words.clone(),
+            slop: self.slop,
+            analyzer: self.analyzer.clone(),
+            // candidates are visited in order, so only the block being read is worth keeping
+            store: reader.get_store_reader(1)?,
+            scorer,
+        };
+
+        let doc = scorer.scorer.doc();
+        if doc != TERMINATED && !scorer.has_phrase(doc) && scorer.advance() == TERMINATED {
+            return Ok(Box::new(EmptyScorer));
+        }
+        Ok(Box::new(scorer))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        self.weight.explain(reader, doc)
+    }
+}
+
+struct VerifiedPhraseScorer {
+    field: Field,
+    words: Vec<String>,
+    slop: u32,
+    analyzer: TextAnalyzer,
+    store: StoreReader,
+    scorer: Box<dyn Scorer>,
+}
+
+impl VerifiedPhraseScorer {
+    /// Does one of `doc`'s stored values of the field have the phrase?
+    fn has_phrase(&mut self, doc: DocId) -> bool {
+        let Ok(document) = self.store.get::<TantivyDocument>(doc) else {
+            return false;
+        };
+        document
+            .get_all(self.field)
+            .filter_map(|value| value.as_str())
+            .any(|text| contains_phrase(&mut self.analyzer, text, &self.words, self.slop))
+    }
+}
+
+impl Scorer for VerifiedPhraseScorer {
+    fn score(&mut self) -> Score {
+        self.scorer.score()
+    }
+}
+
+impl DocSet for VerifiedPhraseScorer {
+    fn advance(&mut self) -> DocId {
+        loop {
+            let doc = self.scorer.advance();
+            if doc == TERMINATED || self.has_phrase(doc) {
+                return doc;
+            }
+        }
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+}
+
+impl Query for VerifiedPhraseQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(VerifiedPhraseWeight {
+            field: self.field,
+            words: self.words.clone(),
+            slop: self.slop,
+            analyzer: self.analyzer.clone(),
+            weight: self.query.weight(enable_scoring)?,
+        }))
+    }
+
+    fn query_terms(
+        &self,
+        field: Field,
+        segment_reader: &SegmentReader,
+        visitor: &mut dyn FnMut(&Term, bool),
+    ) {
+        self.query.query_terms(field, segment_reader, visitor)
+    }
+}
+
+/// Does `text` have `words` in order, with at most `slop` other tokens between them all together,
+/// as a [`tantivy::query::PhraseQuery`] would match them?
+fn contains_phrase(analyzer: &mut TextAnalyzer, text: &str, words: &[String], slop: u32) -> bool {
+    let mut positions = vec![Vec::new(); words.len()];
+    let mut stream = analyzer.token_stream(text);
+    while let Some(token) = stream.next() {
+        for (word, positions) in words.iter().zip(positions.iter_mut()) {
+            if token.text == *word {
+                positions.push(token.position);
+            }
+        }
+    }
+
+    // the positions each word can end the phrase so far at, with the fewest tokens skipped to
+    let mut reachable: Vec<(usize, u32)> = positions[0].iter().map(|&p| (p, 0)).collect();
+    for positions in &positions[1..] {
+        reachable = positions
+            .iter()
+            .filter_map(|&position| {
+                reachable
+                    .iter()
+                    .filter(|&&(previous, _)| previous < position)
+                    .map(|&(previous, skipped)| skipped + (position - previous - 1) as u32)
+                    .filter(|&skipped| skipped <= slop)
+                    .min()
+                    .map(|skipped| (position, skipped))
+            })
+            .collect();
+        if reachable.is_empty() {
+            return false;
+        }
+    }
+    !reachable.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tantivy::tokenizer::SimpleTokenizer;
+
+    #[rstest]
+    #[case("running shoes", &["running", "shoes"], 0, true)]
+    #[case("shoes for running", &["running", "shoes"], 0, false)]
+    #[case("running in shoes", &["running", "shoes"], 0, false)]
+    #[case("running in shoes", &["running", "shoes"], 1, true)]
+    #[case("red running in blue shoes", &["red", "running", "shoes"], 1, false)]
+    #[case("red running in blue shoes", &["red", "running", "shoes"], 2, true)]
+    #[case("running shoes running", &["shoes", "running"], 0, true)]
+    #[case("", &["running", "shoes"], 5, false)]
+    fn test_contains_phrase(
+        #[case] text: &str,
+        #[case] words: &[&str],
+        #[case] slop: u32,
+        #[case] expected: bool,
+    ) {
+        let mut analyzer = TextAnalyzer::from(SimpleTokenizer::default());
+        let words = words
+            .iter()
+            .map(|word| word.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(contains_phrase(&mut analyzer, text, &words, slop), expected);
+    }
+}
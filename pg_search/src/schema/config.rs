@@ -15,16 +15,21 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use crate::schema::json_paths::JsonPathFilter;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::fmt::{Display, Formatter};
 use tantivy::schema::{
-    DateOptions, DateTimePrecision, IpAddrOptions, JsonObjectOptions, NumericOptions,
+    BytesOptions, DateOptions, DateTimePrecision, IpAddrOptions, JsonObjectOptions, NumericOptions,
     TextFieldIndexing, TextOptions,
 };
 use tokenizers::{SearchNormalizer, SearchTokenizer};
 
+/// The largest `scale` a numeric field can use.  A scaled value must fit in an `i64`, so this
+/// still leaves room for values up to about 9.2.
+pub const MAX_NUMERIC_SCALE: u8 = 18;
+
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 // TODO: re-enable this once we are okay with a breaking change
 // #[serde(deny_unknown_fields)]
@@ -34,6 +39,8 @@ pub enum SearchFieldConfig {
         indexed: bool,
         #[serde(default)]
         fast: bool,
+        #[serde(default)]
+        stored: bool,
         #[serde(default = "default_as_true")]
         fieldnorms: bool,
         #[serde(default)]
@@ -44,18 +51,43 @@ pub enum SearchFieldConfig {
         normalizer: SearchNormalizer,
         #[serde(default)]
         column: Option<String>,
+        /// Keep each document's tokens, with their positions and offsets, so that snippets are
+        /// highlighted without tokenizing the text again.
+        #[serde(default)]
+        term_vectors: bool,
     },
     Inet {
         #[serde(default = "default_as_true")]
         indexed: bool,
         #[serde(default = "default_as_true")]
         fast: bool,
+        #[serde(default)]
+        stored: bool,
+    },
+    /// A UUID column stored as its 16 raw bytes rather than as text.
+    Uuid {
+        #[serde(default = "default_as_true")]
+        indexed: bool,
+        #[serde(default = "default_as_true")]
+        fast: bool,
+        #[serde(default)]
+        stored: bool,
+    },
+    Bytes {
+        #[serde(default = "default_as_true")]
+        indexed: bool,
+        #[serde(default = "default_as_true")]
+        fast: bool,
+        #[serde(default)]
+        stored: bool,
     },
     Json {
         #[serde(default = "default_as_true")]
         indexed: bool,
         #[serde(default)]
         fast: bool,
+        #[serde(default)]
+        stored: bool,
         #[serde(default = "default_as_true")]
         fieldnorms: bool,
         #[serde(default = "default_as_true")]
@@ -68,6 +100,10 @@ pub enum SearchFieldConfig {
         normalizer: SearchNormalizer,
         #[serde(default)]
         column: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        include_paths: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        exclude_paths: Vec<String>,
     },
     Range {
         #[serde(default = "default_as_true")]
@@ -78,22 +114,71 @@ pub enum SearchFieldConfig {
         indexed: bool,
         #[serde(default = "default_as_true")]
         fast: bool,
+        #[serde(default)]
+        stored: bool,
+        /// For `NUMERIC` columns, the number of decimal digits to keep.  When set, values are
+        /// indexed as fixed-point integers scaled by `10^scale` instead of as floats.
+        #[serde(default)]
+        scale: Option<u8>,
     },
     Boolean {
         #[serde(default = "default_as_true")]
         indexed: bool,
         #[serde(default = "default_as_true")]
         fast: bool,
+        #[serde(default)]
+        stored: bool,
     },
     Date {
         #[serde(default = "default_as_true")]
         indexed: bool,
         #[serde(default = "default_as_true")]
         fast: bool,
+        #[serde(default)]
+        stored: bool,
+        /// How finely the field's fast values are kept.  Values are truncated to it before
+        /// they're encoded, so a coarser precision compresses better but can't tell apart the
+        /// values within one of its units.
+        #[serde(default)]
+        precision: DatePrecision,
     },
 }
 
+/// The unit a date field's fast values are truncated to.
+#[derive(Default, Copy, Clone, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub enum DatePrecision {
+    #[serde(rename = "seconds", alias = "secs")]
+    Seconds,
+    #[serde(rename = "milliseconds", alias = "millis")]
+    Milliseconds,
+    // Match Postgres' maximum allowed precision of microseconds
+    #[serde(rename = "microseconds", alias = "micros")]
+    #[default]
+    Microseconds,
+    #[serde(rename = "nanoseconds", alias = "nanos")]
+    Nanoseconds,
+}
+
+impl DatePrecision {
+    /// Does this precision keep every value of a Postgres date or time exactly as it is?
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Self::Microseconds | Self::Nanoseconds)
+    }
+}
+
+impl From<DatePrecision> for DateTimePrecision {
+    fn from(value: DatePrecision) -> Self {
+        match value {
+            DatePrecision::Seconds => DateTimePrecision::Seconds,
+            DatePrecision::Milliseconds => DateTimePrecision::Milliseconds,
+            DatePrecision::Microseconds => DateTimePrecision::Microseconds,
+            DatePrecision::Nanoseconds => DateTimePrecision::Nanoseconds,
+        }
+    }
+}
+
 impl SearchFieldConfig {
+    #[allow(deprecated)]
     pub fn text_from_json(value: serde_json::Value) -> Result<Self> {
         let mut config: Self = serde_json::from_value(json!({
             "Text": value
@@ -105,7 +190,12 @@ impl SearchFieldConfig {
                 ref mut fast,
                 ..
             } => {
-                if matches!(tokenizer, SearchTokenizer::Keyword) {
+                // keyword fields are for filtering, sorting, and grouping by whole values, all of
+                // which the columnar fast field serves best
+                if matches!(
+                    tokenizer,
+                    SearchTokenizer::Keyword | SearchTokenizer::Raw(_)
+                ) {
                     *fast = true;
                 }
                 Ok(config)
@@ -131,7 +221,10 @@ impl SearchFieldConfig {
         }))?;
 
         match config {
-            SearchFieldConfig::Json { .. } => Ok(config),
+            SearchFieldConfig::Json { .. } => {
+                config.json_path_filter()?;
+                Ok(config)
+            }
             _ => Err(anyhow::anyhow!("Expected Json configuration")),
         }
     }
@@ -147,12 +240,39 @@ impl SearchFieldConfig {
         }
     }
 
+    pub fn uuid_from_json(value: serde_json::Value) -> Result<Self> {
+        let config: Self = serde_json::from_value(json!({
+            "Uuid": value
+        }))?;
+
+        match config {
+            SearchFieldConfig::Uuid { .. } => Ok(config),
+            _ => Err(anyhow::anyhow!("Expected Uuid configuration")),
+        }
+    }
+
+    pub fn bytes_from_json(value: serde_json::Value) -> Result<Self> {
+        let config: Self = serde_json::from_value(json!({
+            "Bytes": value
+        }))?;
+
+        match config {
+            SearchFieldConfig::Bytes { .. } => Ok(config),
+            _ => Err(anyhow::anyhow!("Expected Bytes configuration")),
+        }
+    }
+
     pub fn numeric_from_json(value: serde_json::Value) -> Result<Self> {
         let config: Self = serde_json::from_value(json!({
             "Numeric": value
         }))?;
 
         match config {
+            SearchFieldConfig::Numeric {
+                scale: Some(scale), ..
+            } if scale > MAX_NUMERIC_SCALE => Err(anyhow::anyhow!(
+                "numeric scale must be between 0 and {MAX_NUMERIC_SCALE}, got {scale}"
+            )),
             SearchFieldConfig::Numeric { .. } => Ok(config),
             _ => Err(anyhow::anyhow!("Expected Numeric configuration")),
         }
@@ -187,6 +307,48 @@ impl SearchFieldConfig {
         }
     }
 
+    /// Whether the tokens of a text field are stored for highlighting.
+    pub fn term_vectors(&self) -> bool {
+        matches!(
+            self,
+            Self::Text {
+                term_vectors: true,
+                ..
+            }
+        )
+    }
+
+    /// The fixed-point scale of a numeric field, or [`None`] if it's indexed as a float.
+    pub fn numeric_scale(&self) -> Option<u8> {
+        match self {
+            Self::Numeric { scale, .. } => *scale,
+            _ => None,
+        }
+    }
+
+    /// The precision of a date field's fast values, or [`None`] if it's not a date field.
+    pub fn date_precision(&self) -> Option<DatePrecision> {
+        match self {
+            Self::Date { precision, .. } => Some(*precision),
+            _ => None,
+        }
+    }
+
+    /// The keys of a JSON field's documents that are indexed, or [`None`] if they all are.
+    pub fn json_path_filter(&self) -> Result<Option<JsonPathFilter>> {
+        match self {
+            Self::Json {
+                include_paths,
+                exclude_paths,
+                ..
+            } => {
+                let filter = JsonPathFilter::new(include_paths, exclude_paths)?;
+                Ok((!filter.is_empty()).then_some(filter))
+            }
+            _ => Ok(None),
+        }
+    }
+
     pub fn tokenizer(&self) -> Option<&SearchTokenizer> {
         match self {
             Self::Text { tokenizer, .. } | Self::Json { tokenizer, .. } => Some(tokenizer),
@@ -223,6 +385,15 @@ impl SearchFieldConfig {
         Self::from_json(json!({"Inet": {}}))
     }
 
+    pub fn default_bytes() -> Self {
+        Self::from_json(json!({"Bytes": {}}))
+    }
+
+    /// A vector is only ever read back, to compute distances, so it isn't indexed
+    pub fn default_vector() -> Self {
+        Self::from_json(json!({"Bytes": {"indexed": false}}))
+    }
+
     pub fn default_numeric() -> Self {
         Self::from_json(json!({"Numeric": {}}))
     }
@@ -251,6 +422,7 @@ impl From<SearchFieldConfig> for TextOptions {
             SearchFieldConfig::Text {
                 indexed,
                 fast,
+                stored,
                 fieldnorms,
                 tokenizer,
                 record,
@@ -260,6 +432,9 @@ impl From<SearchFieldConfig> for TextOptions {
                 if fast {
                     text_options = text_options.set_fast(Some(normalizer.name()));
                 }
+                if stored {
+                    text_options = text_options.set_stored();
+                }
                 if indexed {
                     let text_field_indexing = TextFieldIndexing::default()
                         .set_index_option(record.into())
@@ -279,10 +454,17 @@ impl From<SearchFieldConfig> for IpAddrOptions {
     fn from(config: SearchFieldConfig) -> Self {
         let mut inet_options = IpAddrOptions::default();
         match config {
-            SearchFieldConfig::Inet { indexed, fast, .. } => {
+            SearchFieldConfig::Inet {
+                indexed,
+                fast,
+                stored,
+            } => {
                 if fast {
                     inet_options = inet_options.set_fast();
                 }
+                if stored {
+                    inet_options = inet_options.set_stored();
+                }
                 if indexed {
                     inet_options = inet_options.set_indexed();
                 }
@@ -297,6 +479,38 @@ impl From<SearchFieldConfig> for IpAddrOptions {
     }
 }
 
+impl From<SearchFieldConfig> for BytesOptions {
+    fn from(config: SearchFieldConfig) -> Self {
+        let mut bytes_options = BytesOptions::default();
+        match config {
+            SearchFieldConfig::Uuid {
+                indexed,
+                fast,
+                stored,
+            }
+            | SearchFieldConfig::Bytes {
+                indexed,
+                fast,
+                stored,
+            } => {
+                if fast {
+                    bytes_options = bytes_options.set_fast();
+                }
+                if stored {
+                    bytes_options = bytes_options.set_stored();
+                }
+                if indexed {
+                    bytes_options = bytes_options.set_indexed();
+                }
+            }
+            _ => {
+                panic!("attempted to convert non-bytes search field config to tantivy bytes config")
+            }
+        }
+        bytes_options
+    }
+}
+
 impl From<SearchFieldConfig> for NumericOptions {
     fn from(config: SearchFieldConfig) -> Self {
         let mut numeric_options = NumericOptions::default();
@@ -304,13 +518,17 @@ impl From<SearchFieldConfig> for NumericOptions {
             SearchFieldConfig::Numeric {
                 indexed,
                 fast,
+                stored,
                 ..
             }
             // Following the example of Quickwit, which uses NumericOptions for boolean options.
-            | SearchFieldConfig::Boolean { indexed, fast, .. } => {
+            | SearchFieldConfig::Boolean { indexed, fast, stored } => {
                 if fast {
                     numeric_options = numeric_options.set_fast();
                 }
+                if stored {
+                    numeric_options = numeric_options.set_stored();
+                }
                 if indexed {
                     numeric_options = numeric_options.set_indexed();
                 }
@@ -332,6 +550,7 @@ impl From<SearchFieldConfig> for JsonObjectOptions {
             SearchFieldConfig::Json {
                 indexed,
                 fast,
+                stored,
                 fieldnorms,
                 expand_dots,
                 tokenizer,
@@ -342,6 +561,9 @@ impl From<SearchFieldConfig> for JsonObjectOptions {
                 if fast {
                     json_options = json_options.set_fast(Some(normalizer.name()));
                 }
+                if stored {
+                    json_options = json_options.set_stored();
+                }
                 if expand_dots {
                     json_options = json_options.set_expand_dots_enabled();
                 }
@@ -373,12 +595,17 @@ impl From<SearchFieldConfig> for DateOptions {
     fn from(config: SearchFieldConfig) -> Self {
         let mut date_options = DateOptions::default();
         match config {
-            SearchFieldConfig::Date { indexed, fast, .. } => {
+            SearchFieldConfig::Date {
+                indexed,
+                fast,
+                stored,
+                precision,
+            } => {
                 if fast {
-                    date_options = date_options
-                        .set_fast()
-                        // Match Postgres' maximum allowed precision of microseconds
-                        .set_precision(DateTimePrecision::Microseconds);
+                    date_options = date_options.set_fast().set_precision(precision.into());
+                }
+                if stored {
+                    date_options = date_options.set_stored();
                 }
                 if indexed {
                     date_options = date_options.set_indexed();
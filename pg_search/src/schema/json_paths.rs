@@ -0,0 +1,262 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! The `include_paths` and `exclude_paths` options of a JSON field, which pick out the keys of
+//! each document that are indexed.
+//!
+//! A pattern is a dot-separated path of keys, where `*` matches any part of a single key and `**`
+//! matches any number of keys.  For example `user.*_id` matches `user.account_id`, and
+//! `payload.**.secret` matches `secret` anywhere under `payload`.
+
+use anyhow::{bail, Result};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// A single key, where each `*` matches any run of characters
+    Key(String),
+    /// `**`, any number of keys
+    AnyKeys,
+}
+
+impl Segment {
+    fn matches(&self, key: &str) -> bool {
+        match self {
+            Segment::Key(pattern) => wildcard_match(pattern, key),
+            Segment::AnyKeys => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PathPattern(Vec<Segment>);
+
+impl PathPattern {
+    fn parse(pattern: &str) -> Result<Self> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            bail!("a JSON path pattern must not be empty");
+        }
+        pattern
+            .split('.')
+            .map(|segment| match segment {
+                "" => bail!("JSON path pattern `{pattern}` has an empty key"),
+                "**" => Ok(Segment::AnyKeys),
+                key => Ok(Segment::Key(key.to_string())),
+            })
+            .collect::<Result<_>>()
+            .map(PathPattern)
+    }
+
+    /// Does this pattern match exactly `path`?
+    fn matches(&self, path: &[&str]) -> bool {
+        fn matches(pattern: &[Segment], path: &[&str]) -> bool {
+            match pattern.split_first() {
+                None => path.is_empty(),
+                Some((Segment::AnyKeys, rest)) => {
+                    (0..=path.len()).any(|i| matches(rest, &path[i..]))
+                }
+                Some((segment, rest)) => path
+                    .split_first()
+                    .is_some_and(|(key, path)| segment.matches(key) && matches(rest, path)),
+            }
+        }
+        matches(&self.0, path)
+    }
+
+    /// Could this pattern match a key nested somewhere under `path`?
+    fn matches_below(&self, path: &[&str]) -> bool {
+        fn matches_below(pattern: &[Segment], path: &[&str]) -> bool {
+            match (pattern.split_first(), path.split_first()) {
+                (Some(_), None) => true,
+                (None, _) => false,
+                (Some((Segment::AnyKeys, _)), Some(_)) => true,
+                (Some((segment, rest)), Some((key, path))) => {
+                    segment.matches(key) && matches_below(rest, path)
+                }
+            }
+        }
+        matches_below(&self.0, path)
+    }
+}
+
+/// Which keys of a JSON field's documents are indexed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonPathFilter {
+    include: Vec<PathPattern>,
+    exclude: Vec<PathPattern>,
+}
+
+impl JsonPathFilter {
+    /// Keep only the keys matching an `include` pattern, or all keys if there are none, except for
+    /// those matching an `exclude` pattern.  A matched key keeps everything nested under it.
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            include: include
+                .iter()
+                .map(|p| PathPattern::parse(p))
+                .collect::<Result<_>>()?,
+            exclude: exclude
+                .iter()
+                .map(|p| PathPattern::parse(p))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Remove the keys of `value` that aren't to be indexed, returning [`None`] if none are left.
+    pub fn apply(&self, value: Value) -> Option<Value> {
+        self.filter(value, &mut Vec::new(), self.include.is_empty())
+    }
+
+    fn filter(&self, value: Value, path: &mut Vec<String>, included: bool) -> Option<Value> {
+        match value {
+            Value::Object(object) => {
+                let mut filtered = Map::new();
+                for (key, value) in object {
+                    path.push(key);
+                    let keys = path.iter().map(String::as_str).collect::<Vec<_>>();
+                    let kept = if self.exclude.iter().any(|p| p.matches(&keys)) {
+                        None
+                    } else if included || self.include.iter().any(|p| p.matches(&keys)) {
+                        if self.exclude.is_empty() {
+                            Some(value)
+                        } else {
+                            // something further down might still be excluded
+                            self.filter(value, path, true)
+                        }
+                    } else if self.include.iter().any(|p| p.matches_below(&keys)) {
+                        self.filter(value, path, false)
+                    } else {
+                        None
+                    };
+                    let key = path.pop().unwrap();
+                    if let Some(value) = kept {
+                        filtered.insert(key, value);
+                    }
+                }
+                (!filtered.is_empty()).then_some(Value::Object(filtered))
+            }
+            // an array's elements are indexed under the array's own path
+            Value::Array(values) => {
+                let filtered = values
+                    .into_iter()
+                    .filter_map(|value| self.filter(value, path, included))
+                    .collect::<Vec<_>>();
+                (!filtered.is_empty()).then_some(Value::Array(filtered))
+            }
+            scalar => included.then_some(scalar),
+        }
+    }
+}
+
+fn wildcard_match(pattern: &str, key: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = key.strip_prefix(first) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // no wildcard at all
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> JsonPathFilter {
+        let strings =
+            |patterns: &[&str]| patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        JsonPathFilter::new(&strings(include), &strings(exclude)).unwrap()
+    }
+
+    #[rstest]
+    #[case("user_*", "user_id", true)]
+    #[case("user_*", "user_", true)]
+    #[case("user_*", "account_id", false)]
+    #[case("*_id", "user_id", true)]
+    #[case("a*b*c", "aXbYc", true)]
+    #[case("a*b*c", "aXcYb", false)]
+    #[case("name", "name", true)]
+    #[case("name", "names", false)]
+    fn test_wildcard_match(#[case] pattern: &str, #[case] key: &str, #[case] expected: bool) {
+        assert_eq!(wildcard_match(pattern, key), expected);
+    }
+
+    #[rstest]
+    fn test_include() {
+        let document = json!({
+            "user": {"id": 1, "name": "alice", "address": {"city": "Paris"}},
+            "event": "login",
+            "payload": {"raw": "..."}
+        });
+        assert_eq!(
+            filter(&["user.name", "event"], &[]).apply(document.clone()),
+            Some(json!({"user": {"name": "alice"}, "event": "login"}))
+        );
+        // a matched key keeps everything under it
+        assert_eq!(
+            filter(&["user"], &[]).apply(document.clone()),
+            Some(json!({"user": {"id": 1, "name": "alice", "address": {"city": "Paris"}}}))
+        );
+        assert_eq!(
+            filter(&["**.city"], &[]).apply(document.clone()),
+            Some(json!({"user": {"address": {"city": "Paris"}}}))
+        );
+        assert_eq!(filter(&["missing"], &[]).apply(document), None);
+    }
+
+    #[rstest]
+    fn test_exclude() {
+        let document = json!({
+            "user": {"id": 1, "password": "hunter2", "tokens": [{"secret": "x", "kind": "api"}]},
+            "event": "login"
+        });
+        assert_eq!(
+            filter(&[], &["**.password", "user.tokens.secret"]).apply(document.clone()),
+            Some(json!({"user": {"id": 1, "tokens": [{"kind": "api"}]}, "event": "login"}))
+        );
+        // exclusions win over inclusions
+        assert_eq!(
+            filter(&["user"], &["user.password", "user.tokens"]).apply(document),
+            Some(json!({"user": {"id": 1}}))
+        );
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("user..name")]
+    #[case(".user")]
+    fn test_invalid_patterns(#[case] pattern: &str) {
+        assert!(JsonPathFilter::new(&[pattern.to_string()], &[]).is_err());
+    }
+}
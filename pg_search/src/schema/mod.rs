@@ -17,12 +17,14 @@
 
 mod anyenum;
 mod config;
+pub mod json_paths;
 pub mod range;
 
 use crate::api::FieldName;
 use crate::api::HashMap;
 use crate::postgres::options::BM25IndexOptions;
 use crate::postgres::utils::{resolve_base_type, ExtractedFieldAttribute};
+use crate::postgres::vector::is_vector_type;
 pub use anyenum::AnyEnum;
 use anyhow::bail;
 pub use config::*;
@@ -30,8 +32,10 @@ use std::cell::{Ref, RefCell};
 use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
+use crate::index::term_vectors::{is_term_vectors_field, term_vectors_field_name};
 use crate::index::utils::load_index_schema;
 use crate::postgres::rel::PgSearchRelation;
+use crate::schema::json_paths::JsonPathFilter;
 use anyhow::Result;
 use derive_more::Into;
 use pgrx::{pg_sys, PgBuiltInOids, PgOid};
@@ -56,6 +60,9 @@ pub enum SearchFieldType {
     Json(pg_sys::Oid),
     Date(pg_sys::Oid),
     Range(pg_sys::Oid),
+    Bytes(pg_sys::Oid),
+    /// pgvector's `vector`, kept as a fast field of its dimensions
+    Vector(pg_sys::Oid),
 }
 
 impl SearchFieldType {
@@ -71,6 +78,8 @@ impl SearchFieldType {
             SearchFieldType::Json(_) => SearchFieldConfig::default_json(),
             SearchFieldType::Date(_) => SearchFieldConfig::default_date(),
             SearchFieldType::Range(_) => SearchFieldConfig::default_range(),
+            SearchFieldType::Bytes(_) => SearchFieldConfig::default_bytes(),
+            SearchFieldType::Vector(_) => SearchFieldConfig::default_vector(),
         }
     }
 
@@ -86,6 +95,8 @@ impl SearchFieldType {
             SearchFieldType::Json(oid) => *oid,
             SearchFieldType::Date(oid) => *oid,
             SearchFieldType::Range(oid) => *oid,
+            SearchFieldType::Bytes(oid) => *oid,
+            SearchFieldType::Vector(oid) => *oid,
         }
         .into()
     }
@@ -110,6 +121,7 @@ impl TryFrom<PgOid> for SearchFieldType {
                 }
                 PgBuiltInOids::UUIDOID => Ok(SearchFieldType::Uuid((*builtin).into())),
                 PgBuiltInOids::INETOID => Ok(SearchFieldType::Inet((*builtin).into())),
+                PgBuiltInOids::BYTEAOID => Ok(SearchFieldType::Bytes((*builtin).into())),
                 PgBuiltInOids::INT2OID | PgBuiltInOids::INT4OID | PgBuiltInOids::INT8OID => {
                     Ok(SearchFieldType::I64((*builtin).into()))
                 }
@@ -139,6 +151,8 @@ impl TryFrom<PgOid> for SearchFieldType {
             PgOid::Custom(custom) => {
                 if unsafe { pgrx::pg_sys::type_is_enum(*custom) } {
                     Ok(SearchFieldType::F64(*custom))
+                } else if is_vector_type(*custom) {
+                    Ok(SearchFieldType::Vector(*custom))
                 } else {
                     Err(SearchIndexSchemaError::InvalidPgOid(pg_oid))
                 }
@@ -154,6 +168,8 @@ pub struct CategorizedFieldData {
     pub base_oid: PgOid,
     pub is_array: bool,
     pub is_json: bool,
+    /// The keys of a JSON field that are indexed, if not all of them
+    pub json_path_filter: Option<JsonPathFilter>,
 }
 
 #[derive(Clone, Into)]
@@ -210,8 +226,18 @@ impl SearchIndexSchema {
         }
     }
 
+    /// The fields of the index, without those that hold the tokens of text fields configured
+    /// with `term_vectors`.
     pub fn fields(&self) -> impl Iterator<Item = (Field, &FieldEntry)> {
-        self.schema.fields()
+        self.schema
+            .fields()
+            .filter(|(_, entry)| !is_term_vectors_field(entry.name()))
+    }
+
+    /// The hidden field that holds the tokens of the text field `field`, if it keeps them.
+    pub fn term_vectors_field(&self, field: Field) -> Option<Field> {
+        let name = self.schema.get_field_name(field);
+        self.schema.get_field(&term_vectors_field_name(name)).ok()
     }
 
     /// A lookup from a Postgres column name to search fields that have
@@ -284,6 +310,14 @@ impl SearchIndexSchema {
                         base_oid,
                         PgOid::BuiltIn(pg_sys::BuiltinOid::JSONBOID | pg_sys::BuiltinOid::JSONOID)
                     );
+                    let json_path_filter = if is_json {
+                        search_field
+                            .field_config()
+                            .json_path_filter()
+                            .unwrap_or_else(|e| panic!("{e}"))
+                    } else {
+                        None
+                    };
                     categorized.push((
                         search_field,
                         CategorizedFieldData {
@@ -291,6 +325,7 @@ impl SearchIndexSchema {
                             base_oid,
                             is_array,
                             is_json,
+                            json_path_filter,
                         },
                     ));
                 }
@@ -374,6 +409,52 @@ impl SearchField {
         self.field_entry.is_fast()
     }
 
+    /// Is this a fast field holding each value exactly as it is in the heap, so that the values
+    /// can be returned, grouped, and ordered by without visiting the heap?  A text fast field
+    /// only holds its values as they came out of its normalizer, so it has to use `raw`.
+    pub fn has_exact_fast_values(&self) -> bool {
+        match self.field_entry.field_type() {
+            FieldType::Str(options) => {
+                options.is_fast()
+                    && options.get_fast_field_tokenizer_name() == Some(SearchNormalizer::Raw.name())
+            }
+            // a scaled NUMERIC field's fast values are integers, not the numerics themselves
+            FieldType::I64(_) if self.numeric_scale().is_some() => false,
+            // nor are a binary UUID field's fast values the UUIDs' text
+            FieldType::Bytes(_) if self.is_binary_uuid() => false,
+            // and a date field's fast values are truncated to its precision
+            FieldType::Date(_) if !self.has_exact_date_precision() => false,
+            _ => self.is_fast(),
+        }
+    }
+
+    /// The fixed-point scale of a NUMERIC field whose values are stored as scaled integers, or
+    /// [`None`] if the field isn't one.
+    pub fn numeric_scale(&self) -> Option<u8> {
+        match self.field_entry.field_type() {
+            FieldType::I64(_) if matches!(self.field_type, SearchFieldType::F64(_)) => {
+                self.field_config.numeric_scale()
+            }
+            _ => None,
+        }
+    }
+
+    /// Do this date field's fast values keep its values exactly?  A `DATE` column has no time of
+    /// day to lose, so any precision keeps its values.
+    fn has_exact_date_precision(&self) -> bool {
+        matches!(self.field_type, SearchFieldType::Date(oid) if oid == pg_sys::DATEOID)
+            || self
+                .field_config
+                .date_precision()
+                .is_none_or(|precision| precision.is_exact())
+    }
+
+    /// Is this a UUID field whose values are stored as their 16 raw bytes instead of as text?
+    pub fn is_binary_uuid(&self) -> bool {
+        matches!(self.field_type, SearchFieldType::Uuid(_))
+            && matches!(self.field_entry.field_type(), FieldType::Bytes(_))
+    }
+
     pub fn is_numeric_fast(&self) -> bool {
         match self.field_entry.field_type() {
             FieldType::I64(options) => options.is_fast(),
@@ -397,7 +478,8 @@ impl SearchField {
             FieldType::U64(options) => options.is_fast(),
             FieldType::F64(options) => options.is_fast(),
             FieldType::Bool(options) => options.is_fast(),
-            FieldType::Date(options) => options.is_fast(),
+            // values truncated to the same unit would be returned in arbitrary order
+            FieldType::Date(options) => options.is_fast() && self.has_exact_date_precision(),
             // TODO: Neither JSON nor range fields are not yet sortable by us
             FieldType::JsonObject(_) => false,
             _ => false,
@@ -416,17 +498,23 @@ impl SearchField {
         self.field_entry.field_type().is_str()
     }
 
-    pub fn is_tokenized_with_freqs_and_positions(&self) -> bool {
-        // NB:  'uses_raw_tokenizer()' might not be enough to ensure the field is tokenized
-        self.is_text()
-            && !self.uses_raw_tokenizer()
-            && matches!(&self.field_config, SearchFieldConfig::Text { record, .. } if *record == IndexRecordOption::WithFreqsAndPositions)
+    /// Does the field record the positions of its tokens, which phrase queries need?
+    pub fn has_positions(&self) -> bool {
+        matches!(
+            &self.field_config,
+            SearchFieldConfig::Text { record, .. } | SearchFieldConfig::Json { record, .. }
+                if *record == IndexRecordOption::WithFreqsAndPositions
+        )
     }
 
     pub fn is_json(&self) -> bool {
         matches!(self.field_type, SearchFieldType::Json(_))
     }
 
+    pub fn is_range(&self) -> bool {
+        matches!(self.field_type, SearchFieldType::Range(_))
+    }
+
     #[allow(deprecated)]
     pub fn is_keyword(&self) -> bool {
         self.field_config
@@ -128,3 +128,686 @@ fn test_aggregate_without_mvcc(mut conn: PgConnection) {
         .unwrap();
     assert_eq!(buckets.len(), 2); // Should have 2 categories
 }
+
+#[rstest]
+fn test_aggregate_pipelines(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE sales (id SERIAL PRIMARY KEY, description TEXT, day INTEGER, amount FLOAT8);
+    INSERT INTO sales (description, day, amount) VALUES
+        ('widget', 0, 10.0),
+        ('widget', 1, 20.0),
+        ('widget', 1, 10.0),
+        ('widget', 2, 5.0);
+    CREATE INDEX idxsales ON sales
+    USING bm25 (id, description, day, amount)
+    WITH (
+        key_field='id',
+        numeric_fields='{"day": {"fast": true}, "amount": {"fast": true}}'
+    );
+    "#
+    .execute(&mut conn);
+
+    let (result,) = r#"
+    SELECT paradedb.aggregate(
+        'idxsales',
+        paradedb.all(),
+        '{
+            "by_day": {
+                "histogram": {"field": "day", "interval": 1},
+                "aggs": {
+                    "total": {"sum": {"field": "amount"}},
+                    "total_change": {"derivative": {"buckets_path": "total"}},
+                    "running_total": {"cumulative_sum": {"buckets_path": "total"}},
+                    "average_sale": {
+                        "bucket_script": {
+                            "buckets_path": {"total": "total", "count": "_count"},
+                            "script": "params.total / params.count"
+                        }
+                    }
+                }
+            }
+        }'::json
+    )
+    "#
+    .fetch_one::<(serde_json::Value,)>(&mut conn);
+
+    let buckets = result
+        .pointer("/by_day/buckets")
+        .unwrap()
+        .as_array()
+        .unwrap();
+    let values = |name: &str| {
+        buckets
+            .iter()
+            .map(|bucket| bucket[name]["value"].as_f64())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(values("total_change"), vec![None, Some(20.0), Some(-25.0)]);
+    assert_eq!(
+        values("running_total"),
+        vec![Some(10.0), Some(40.0), Some(45.0)]
+    );
+    assert_eq!(
+        values("average_sale"),
+        vec![Some(10.0), Some(15.0), Some(5.0)]
+    );
+
+    // pipelines must be declared within a compatible bucket aggregation
+    let error = r#"
+    SELECT paradedb.aggregate(
+        'idxsales',
+        paradedb.all(),
+        '{"total_change": {"derivative": {"buckets_path": "total"}}}'::json
+    )
+    "#
+    .fetch_result::<(serde_json::Value,)>(&mut conn)
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("must be declared within a bucket aggregation"));
+}
+
+#[rstest]
+fn test_aggregate_multi_terms(mut conn: PgConnection) {
+    r#"
+    CALL paradedb.create_bm25_test_table(table_name => 'bm25_search', schema_name => 'paradedb');
+    CREATE INDEX idxbm25_search ON paradedb.bm25_search
+    USING bm25 (id, description, category, rating)
+    WITH (
+        key_field='id',
+        text_fields='{
+            "description": {},
+            "category": {"fast": true, "normalizer": "raw"}
+        }',
+        numeric_fields='{"rating": {"fast": true}}'
+    );
+    "#
+    .execute(&mut conn);
+
+    let (result,) = r#"
+    SELECT paradedb.aggregate(
+        'paradedb.idxbm25_search',
+        paradedb.all(),
+        '{
+            "combos": {
+                "multi_terms": {
+                    "terms": [{"field": "category"}, {"field": "rating"}],
+                    "size": 1000
+                },
+                "aggs": {"ratings": {"value_count": {"field": "rating"}}}
+            }
+        }'::json
+    )
+    "#
+    .fetch_one::<(serde_json::Value,)>(&mut conn);
+
+    let expected = r#"
+    SELECT category, rating, COUNT(*) FROM paradedb.bm25_search
+    GROUP BY category, rating
+    ORDER BY COUNT(*) DESC, category, rating
+    "#
+    .fetch::<(String, i32, i64)>(&mut conn);
+
+    let buckets = result
+        .pointer("/combos/buckets")
+        .unwrap()
+        .as_array()
+        .unwrap();
+    assert_eq!(buckets.len(), expected.len());
+    for (bucket, (category, rating, count)) in buckets.iter().zip(expected) {
+        assert_eq!(bucket["key"], serde_json::json!([category, rating as f64]));
+        assert_eq!(bucket["key_as_string"], format!("{category}|{rating}"));
+        assert_eq!(bucket["doc_count"], count);
+        assert_eq!(
+            bucket.pointer("/ratings/value"),
+            Some(&(count as f64).into())
+        );
+    }
+
+    let error = r#"
+    SELECT paradedb.aggregate(
+        'paradedb.idxbm25_search',
+        paradedb.all(),
+        '{"combos": {"multi_terms": {"terms": [{"field": "category"}]}}}'::json
+    )
+    "#
+    .fetch_result::<(serde_json::Value,)>(&mut conn)
+    .unwrap_err();
+    assert!(error.to_string().contains("requires at least two `terms`"));
+}
+
+#[rstest]
+fn test_aggregate_geo_grid(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE places (id SERIAL PRIMARY KEY, name TEXT, lat FLOAT8, lon FLOAT8, visitors INT);
+    INSERT INTO places (name, lat, lon, visitors) VALUES
+        ('eiffel tower', 48.8584, 2.2945, 10),
+        ('louvre', 48.8606, 2.3376, 20),
+        ('sydney opera house', -33.8568, 151.2153, 30),
+        ('nowhere', NULL, NULL, 40);
+    CREATE INDEX places_idx ON places
+    USING bm25 (id, name, lat, lon, visitors)
+    WITH (
+        key_field='id',
+        numeric_fields='{"lat": {"fast": true}, "lon": {"fast": true}, "visitors": {"fast": true}}'
+    );
+    "#
+    .execute(&mut conn);
+
+    let (result,) = r#"
+    SELECT paradedb.aggregate(
+        'places_idx',
+        paradedb.all(),
+        '{
+            "cities": {
+                "geohash_grid": {"lat_field": "lat", "lon_field": "lon", "precision": 3},
+                "aggs": {"visitors": {"sum": {"field": "visitors"}}}
+            },
+            "tiles": {
+                "geotile_grid": {"lat_field": "lat", "lon_field": "lon", "precision": 1}
+            }
+        }'::json
+    )
+    "#
+    .fetch_one::<(serde_json::Value,)>(&mut conn);
+
+    assert_eq!(
+        result,
+        serde_json::json!({
+            "cities": {"buckets": [
+                {"key": "u09", "doc_count": 2, "visitors": {"value": 30.0}},
+                {"key": "r3g", "doc_count": 1, "visitors": {"value": 30.0}}
+            ]},
+            "tiles": {"buckets": [
+                {"key": "1/1/0", "doc_count": 2},
+                {"key": "1/1/1", "doc_count": 1}
+            ]}
+        })
+    );
+
+    // deleted rows aren't counted
+    "DELETE FROM places WHERE name = 'louvre'".execute(&mut conn);
+    let (result,) = r#"
+    SELECT paradedb.aggregate(
+        'places_idx',
+        paradedb.all(),
+        '{"cities": {"geohash_grid": {"lat_field": "lat", "lon_field": "lon", "precision": 3}}}'::json
+    )
+    "#
+    .fetch_one::<(serde_json::Value,)>(&mut conn);
+    assert_eq!(
+        result.pointer("/cities/buckets/0"),
+        Some(&serde_json::json!({"key": "r3g", "doc_count": 1}))
+    );
+}
+
+#[rstest]
+fn test_aggregate_cache(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE cached (id SERIAL PRIMARY KEY, rating INT);
+    INSERT INTO cached (rating) SELECT x % 5 FROM generate_series(1, 100) x;
+    CREATE INDEX cached_idx ON cached
+    USING bm25 (id, rating)
+    WITH (key_field='id', numeric_fields='{"rating": {"fast": true}}');
+    SET paradedb.aggregate_cache_entries = 4;
+    "#
+    .execute(&mut conn);
+
+    let count = |conn: &mut PgConnection, solve_mvcc: bool| {
+        let (result,) = format!(
+            r#"
+            SELECT paradedb.aggregate('cached_idx', paradedb.all(), '{{"c": {{"value_count": {{"field": "rating"}}}}}}'::json, solve_mvcc => {solve_mvcc})
+            "#
+        )
+        .fetch_one::<(serde_json::Value,)>(conn);
+        result.pointer("/c/value").and_then(|v| v.as_f64()).unwrap()
+    };
+
+    assert_eq!(count(&mut conn, false), 100.0);
+    assert_eq!(count(&mut conn, false), 100.0);
+
+    // new rows create a new segment, so the cached result isn't reused
+    "INSERT INTO cached (rating) VALUES (1), (2)".execute(&mut conn);
+    assert_eq!(count(&mut conn, false), 102.0);
+
+    // deleted rows stay in the index until a vacuum, but aren't visible to aggregations that
+    // solve MVCC, which aren't cached
+    assert_eq!(count(&mut conn, true), 102.0);
+    "DELETE FROM cached WHERE id <= 10".execute(&mut conn);
+    assert_eq!(count(&mut conn, true), 92.0);
+    assert_eq!(count(&mut conn, false), 102.0);
+
+    // vacuuming deletes documents from the index, which also invalidates the cache
+    "VACUUM cached".execute(&mut conn);
+    assert_eq!(count(&mut conn, false), 92.0);
+}
+
+#[rstest]
+fn test_aggregate_elasticsearch_format(mut conn: PgConnection) {
+    r#"
+    CALL paradedb.create_bm25_test_table(table_name => 'bm25_search', schema_name => 'paradedb');
+    CREATE INDEX idxbm25_search ON paradedb.bm25_search
+    USING bm25 (id, description, category, rating, created_at)
+    WITH (
+        key_field='id',
+        text_fields='{
+            "description": {},
+            "category": {"fast": true, "normalizer": "raw"}
+        }',
+        numeric_fields='{"rating": {"fast": true}}',
+        datetime_fields='{"created_at": {"fast": true}}'
+    );
+    "#
+    .execute(&mut conn);
+
+    let (result,) = r#"
+    SELECT paradedb.aggregate(
+        'paradedb.idxbm25_search',
+        paradedb.parse('description:keyboard'),
+        '{
+            "size": 0,
+            "aggs": {
+                "categories": {
+                    "terms": {"field": "category", "order": {"_term": "asc"}},
+                    "meta": {"panel": 1}
+                },
+                "per_day": {
+                    "date_histogram": {"field": "created_at", "calendar_interval": "day"}
+                }
+            }
+        }'::json,
+        format => 'elasticsearch'
+    )
+    "#
+    .fetch_one::<(serde_json::Value,)>(&mut conn);
+
+    let (matches,) = "SELECT COUNT(*) FROM paradedb.bm25_search WHERE description @@@ 'keyboard'"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(result.pointer("/hits/total/value"), Some(&matches.into()));
+    assert_eq!(
+        result.pointer("/aggregations/categories/meta"),
+        Some(&serde_json::json!({"panel": 1}))
+    );
+    assert!(result
+        .pointer("/aggregations/categories/buckets")
+        .unwrap()
+        .is_array());
+    assert!(result
+        .pointer("/aggregations/per_day/buckets/0/key_as_string")
+        .unwrap()
+        .is_string());
+
+    let error = r#"
+    SELECT paradedb.aggregate(
+        'paradedb.idxbm25_search',
+        paradedb.all(),
+        '{"size": 0, "aggs": {"m": {"date_histogram": {"field": "created_at", "calendar_interval": "month"}}}}'::json,
+        format => 'elasticsearch'
+    )
+    "#
+    .fetch_result::<(serde_json::Value,)>(&mut conn)
+    .unwrap_err();
+    assert!(error.to_string().contains("is not supported"));
+}
+
+#[rstest]
+fn test_timeseries(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE logs (id SERIAL PRIMARY KEY, message TEXT, latency INT, ts TIMESTAMPTZ);
+    INSERT INTO logs (message, latency, ts) VALUES
+        ('request ok', 10, '2025-01-01 00:10:00+00'),
+        ('request ok', 20, '2025-01-01 00:50:00+00'),
+        ('request failed', 300, '2025-01-01 01:30:00+00'),
+        ('request ok', 30, '2025-01-01 03:00:00+00');
+    CREATE INDEX logs_idx ON logs
+    USING bm25 (id, message, latency, ts)
+    WITH (
+        key_field='id',
+        numeric_fields='{"latency": {"fast": true}}',
+        datetime_fields='{"ts": {"fast": true}}'
+    );
+    SET TIME ZONE 'UTC';
+    "#
+    .execute(&mut conn);
+
+    let rows = r#"
+    SELECT bucket::text, doc_count, value
+    FROM paradedb.timeseries('logs_idx', paradedb.all(), 'ts', '1h', 'avg', 'latency')
+    "#
+    .fetch::<(String, i64, Option<f64>)>(&mut conn);
+    assert_eq!(
+        rows,
+        vec![
+            ("2025-01-01 00:00:00+00".into(), 2, Some(15.0)),
+            ("2025-01-01 01:00:00+00".into(), 1, Some(300.0)),
+            ("2025-01-01 02:00:00+00".into(), 0, None),
+            ("2025-01-01 03:00:00+00".into(), 1, Some(30.0)),
+        ]
+    );
+
+    let rows = r#"
+    SELECT doc_count, value
+    FROM paradedb.timeseries('logs_idx', paradedb.parse('message:ok'), 'ts', '1d')
+    "#
+    .fetch::<(i64, Option<f64>)>(&mut conn);
+    assert_eq!(rows, vec![(3, Some(3.0))]);
+
+    let error = "SELECT * FROM paradedb.timeseries('logs_idx', paradedb.all(), 'ts', '1h', 'avg')"
+        .fetch_result::<(String, i64, Option<f64>)>(&mut conn)
+        .unwrap_err();
+    assert!(error.to_string().contains("requires a `metric_field`"));
+}
+
+#[rstest]
+fn test_timeseries_time_zone(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE logs (id SERIAL PRIMARY KEY, message TEXT, ts TIMESTAMPTZ);
+    INSERT INTO logs (message, ts) VALUES
+        ('request ok', '2024-03-09 23:30:00-05'),
+        ('request ok', '2024-03-10 00:30:00-05'),
+        ('request ok', '2024-03-10 12:00:00-04'),
+        ('request ok', '2024-03-11 00:30:00-04');
+    CREATE INDEX logs_idx ON logs
+    USING bm25 (id, message, ts)
+    WITH (key_field='id', datetime_fields='{"ts": {"fast": true}}');
+    SET TIME ZONE 'UTC';
+    "#
+    .execute(&mut conn);
+
+    let rows = r#"
+    SELECT bucket::text, doc_count
+    FROM paradedb.timeseries('logs_idx', paradedb.all(), 'ts', '1d')
+    "#
+    .fetch::<(String, i64)>(&mut conn);
+    assert_eq!(
+        rows,
+        vec![
+            ("2024-03-10 00:00:00+00".into(), 3),
+            ("2024-03-11 00:00:00+00".into(), 1),
+        ]
+    );
+
+    let rows = r#"
+    SELECT bucket::text, doc_count
+    FROM paradedb.timeseries('logs_idx', paradedb.all(), 'ts', '1d', time_zone => '+09:00')
+    "#
+    .fetch::<(String, i64)>(&mut conn);
+    assert_eq!(
+        rows,
+        vec![
+            ("2024-03-09 15:00:00+00".into(), 2),
+            ("2024-03-10 15:00:00+00".into(), 2),
+        ]
+    );
+
+    // the clocks of New York spring forward on 2024-03-10, so its days start at different offsets
+    "SET TIME ZONE 'America/New_York'".execute(&mut conn);
+    let rows = r#"
+    SELECT bucket::text, doc_count
+    FROM paradedb.timeseries('logs_idx', paradedb.all(), 'ts', '1d', time_zone => 'America/New_York')
+    "#
+    .fetch::<(String, i64)>(&mut conn);
+    assert_eq!(
+        rows,
+        vec![
+            ("2024-03-09 00:00:00-05".into(), 1),
+            ("2024-03-10 00:00:00-05".into(), 2),
+            ("2024-03-11 00:00:00-04".into(), 1),
+        ]
+    );
+
+    let (result,) = r#"
+    SELECT paradedb.aggregate(
+        'logs_idx',
+        paradedb.all(),
+        '{"per_day": {"date_histogram": {"field": "ts", "fixed_interval": "1d", "time_zone": "+09:00"}}}'::json
+    )
+    "#
+    .fetch_one::<(serde_json::Value,)>(&mut conn);
+    let keys = result
+        .pointer("/per_day/buckets")
+        .and_then(serde_json::Value::as_array)
+        .unwrap()
+        .iter()
+        .map(|bucket| bucket["key"].as_f64().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec![1_709_996_400_000.0, 1_710_082_800_000.0]);
+
+    let error = r#"
+    SELECT paradedb.aggregate(
+        'logs_idx',
+        paradedb.all(),
+        '{"per_day": {"date_histogram": {"field": "ts", "fixed_interval": "1d", "time_zone": "Europe/Paris"}}}'::json
+    )
+    "#
+    .fetch_result::<(serde_json::Value,)>(&mut conn)
+    .unwrap_err();
+    assert!(error.to_string().contains("must be a UTC offset"));
+}
+
+#[rstest]
+fn test_timeseries_half_hour_transition(mut conn: PgConnection) {
+    // Lord Howe Island turns its clocks back from +11 to +10:30 at 02:00 on 2024-04-07, so the
+    // half hour from 01:30 happens twice
+    r#"
+    CREATE TABLE island_logs (id SERIAL PRIMARY KEY, message TEXT, ts TIMESTAMPTZ);
+    INSERT INTO island_logs (message, ts) VALUES
+        ('request ok', '2024-04-06 12:00:00+11'),
+        ('request ok', '2024-04-07 01:15:00+11'),
+        ('request ok', '2024-04-07 01:45:00+10:30'),
+        ('request ok', '2024-04-07 02:15:00+10:30'),
+        ('request ok', '2024-04-07 23:45:00+10:30'),
+        ('request ok', '2024-04-08 00:15:00+10:30');
+    CREATE INDEX island_logs_idx ON island_logs
+    USING bm25 (id, message, ts)
+    WITH (key_field='id', datetime_fields='{"ts": {"fast": true}}');
+    SET TIME ZONE 'Australia/Lord_Howe';
+    "#
+    .execute(&mut conn);
+
+    // the day of the transition is 24 and a half hours long
+    let rows = r#"
+    SELECT bucket::text, doc_count
+    FROM paradedb.timeseries('island_logs_idx', paradedb.all(), 'ts', '1d', time_zone => 'Australia/Lord_Howe')
+    "#
+    .fetch::<(String, i64)>(&mut conn);
+    assert_eq!(
+        rows,
+        vec![
+            ("2024-04-06 00:00:00+11".into(), 1),
+            ("2024-04-07 00:00:00+11".into(), 4),
+            ("2024-04-08 00:00:00+10:30".into(), 1),
+        ]
+    );
+
+    // and the hour from 01:00 is an hour and a half long, including both of its 01:30s
+    let rows = r#"
+    SELECT bucket::text, doc_count
+    FROM paradedb.timeseries('island_logs_idx', paradedb.term('message', 'ok'), 'ts', '1h', time_zone => 'Australia/Lord_Howe')
+    WHERE bucket >= '2024-04-07 01:00:00+11' AND bucket < '2024-04-07 03:00:00+10:30'
+    "#
+    .fetch::<(String, i64)>(&mut conn);
+    assert_eq!(
+        rows,
+        vec![
+            ("2024-04-07 01:00:00+11".into(), 2),
+            ("2024-04-07 02:00:00+10:30".into(), 1),
+        ]
+    );
+}
+
+#[rstest]
+fn test_aggregate_nested(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE orders (id SERIAL PRIMARY KEY, description TEXT, details JSONB);
+    INSERT INTO orders (description, details) VALUES
+        ('first order', '{"items": [{"category": "shoes", "price": 100}, {"category": "hats", "price": 5}]}'),
+        ('second order', '{"items": [{"category": "hats", "price": 15}]}'),
+        ('third order', '{"items": {"category": "shoes", "price": 50}}'),
+        ('empty order', '{}');
+    CREATE INDEX orders_idx ON orders
+    USING bm25 (id, description, details)
+    WITH (key_field='id', json_fields='{"details": {"fast": true}}');
+    "#
+    .execute(&mut conn);
+
+    let (result,) = r#"
+    SELECT paradedb.aggregate(
+        'orders_idx',
+        paradedb.all(),
+        '{
+            "items": {
+                "nested": {"path": "details.items"},
+                "aggs": {
+                    "categories": {
+                        "terms": {"field": "details.items.category", "order": {"_key": "asc"}},
+                        "aggs": {"max_price": {"max": {"field": "details.items.price"}}}
+                    }
+                }
+            }
+        }'::json
+    )
+    "#
+    .fetch_one::<(serde_json::Value,)>(&mut conn);
+
+    assert_eq!(result.pointer("/items/doc_count"), Some(&4.into()));
+    let buckets = result
+        .pointer("/items/categories/buckets")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|bucket| {
+            (
+                bucket["key"].as_str().unwrap().to_string(),
+                bucket["doc_count"].as_i64().unwrap(),
+                bucket
+                    .pointer("/max_price/value")
+                    .unwrap()
+                    .as_f64()
+                    .unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    // without scoping, the hats bucket would include the first order's shoe price of 100
+    assert_eq!(
+        buckets,
+        vec![("hats".into(), 2, 15.0), ("shoes".into(), 2, 100.0)]
+    );
+
+    // only elements of matching, visible rows are aggregated
+    "DELETE FROM orders WHERE description = 'second order'".execute(&mut conn);
+    let (result,) = r#"
+    SELECT paradedb.aggregate(
+        'orders_idx',
+        paradedb.parse('description:order'),
+        '{"items": {"nested": {"path": "details.items"}, "aggs": {"total": {"sum": {"field": "details.items.price"}}}}}'::json
+    )
+    "#
+    .fetch_one::<(serde_json::Value,)>(&mut conn);
+    assert_eq!(result.pointer("/items/doc_count"), Some(&3.into()));
+    assert_eq!(result.pointer("/items/total/value"), Some(&155.0.into()));
+
+    let error = r#"
+    SELECT paradedb.aggregate(
+        'orders_idx',
+        paradedb.all(),
+        '{"items": {"nested": {"path": "description.items"}}}'::json
+    )
+    "#
+    .fetch_result::<(serde_json::Value,)>(&mut conn)
+    .unwrap_err();
+    assert!(error.to_string().contains("require a json or jsonb column"));
+}
+
+#[rstest]
+fn test_aggregate_sampler(mut conn: PgConnection) {
+    r#"
+    CALL paradedb.create_bm25_test_table(table_name => 'bm25_search', schema_name => 'paradedb');
+    CREATE INDEX idxbm25_search ON paradedb.bm25_search
+    USING bm25 (id, description, category, rating)
+    WITH (
+        key_field='id',
+        text_fields='{
+            "description": {},
+            "category": {"fast": true, "normalizer": "raw"}
+        }',
+        numeric_fields='{"rating": {"fast": true}}'
+    );
+    "#
+    .execute(&mut conn);
+
+    let (result,) = r#"
+    SELECT paradedb.aggregate(
+        'paradedb.idxbm25_search',
+        paradedb.all(),
+        '{
+            "top": {
+                "sampler": {"shard_size": 5},
+                "aggs": {"avg_rating": {"avg": {"field": "rating"}}}
+            },
+            "everything": {
+                "random_sampler": {"probability": 1.0}
+            },
+            "total": {"value_count": {"field": "rating"}}
+        }'::json
+    )
+    "#
+    .fetch_one::<(serde_json::Value,)>(&mut conn);
+
+    let (total,) = "SELECT COUNT(*) FROM paradedb.bm25_search".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(result.pointer("/top/doc_count"), Some(&5.into()));
+    assert!(result.pointer("/top/avg_rating/value").unwrap().is_number());
+    assert_eq!(result.pointer("/everything/doc_count"), Some(&total.into()));
+    assert_eq!(
+        result.pointer("/total/value").and_then(|v| v.as_f64()),
+        Some(total as f64)
+    );
+
+    // a sampler only sees the documents that matched the query
+    let (result,) = r#"
+    SELECT paradedb.aggregate(
+        'paradedb.idxbm25_search',
+        paradedb.parse('description:keyboard'),
+        '{"top": {"sampler": {"shard_size": 100}}}'::json
+    )
+    "#
+    .fetch_one::<(serde_json::Value,)>(&mut conn);
+    let (matches,) = "SELECT COUNT(*) FROM paradedb.bm25_search WHERE description @@@ 'keyboard'"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(result.pointer("/top/doc_count"), Some(&matches.into()));
+}
+
+#[rstest]
+fn test_aggregate_honors_statement_timeout(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE timeout_docs (id SERIAL PRIMARY KEY, body TEXT, rating INT);
+    INSERT INTO timeout_docs (body, rating)
+        SELECT 'document ' || x, x % 1000 FROM generate_series(1, 200000) x;
+    CREATE INDEX timeout_docs_idx ON timeout_docs
+    USING bm25 (id, body, rating) WITH (key_field = 'id', numeric_fields = '{"rating": {"fast": true}}');
+    "#
+    .execute(&mut conn);
+
+    let aggregate = r#"
+    SELECT paradedb.aggregate(
+        'timeout_docs_idx',
+        paradedb.regex('body', 'doc.*'),
+        '{"ratings": {"terms": {"field": "rating", "size": 1000}}}'::json
+    )
+    "#;
+
+    "SET statement_timeout = '1ms'".execute(&mut conn);
+    let error = aggregate.execute_result(&mut conn).unwrap_err();
+    assert!(error.to_string().contains("statement timeout"), "{error}");
+
+    // the cancelled search mustn't leave anything behind that breaks the next one
+    "RESET statement_timeout".execute(&mut conn);
+    let (result,) = aggregate.fetch_one::<(serde_json::Value,)>(&mut conn);
+    let buckets = result
+        .pointer("/ratings/buckets")
+        .unwrap()
+        .as_array()
+        .unwrap();
+    assert_eq!(buckets.len(), 1000);
+}
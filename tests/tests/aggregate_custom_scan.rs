@@ -166,3 +166,133 @@ fn test_other_aggregates(mut conn: PgConnection) {
         );
     }
 }
+
+#[rstest]
+fn test_group_by_exceeds_max_term_agg_buckets(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    "SET paradedb.enable_aggregate_custom_scan TO on;".execute(&mut conn);
+
+    let query = r#"
+        SELECT rating, COUNT(*)
+        FROM paradedb.bm25_search WHERE
+        id @@@ paradedb.all()
+        GROUP BY rating
+        ORDER BY rating
+    "#;
+    assert_uses_custom_scan(&mut conn, true, query);
+
+    let rows = query.fetch::<(i32, i64)>(&mut conn);
+    assert!(rows.len() > 2);
+
+    // rather than returning a truncated set of groups, we should error
+    "SET paradedb.max_term_agg_buckets TO 2;".execute(&mut conn);
+    let error = query
+        .fetch_result::<(i32, i64)>(&mut conn)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("produced more than 2 groups"),
+        "unexpected error: {error}"
+    );
+
+    "RESET paradedb.max_term_agg_buckets;".execute(&mut conn);
+    assert_eq!(query.fetch::<(i32, i64)>(&mut conn), rows);
+}
+
+#[rstest]
+fn test_count_respects_visibility(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let query = "SELECT COUNT(*) FROM paradedb.bm25_search WHERE description @@@ 'keyboard'";
+
+    // dead and newly-inserted tuples on pages that aren't all-visible must be checked against the heap
+    r#"
+    DELETE FROM paradedb.bm25_search WHERE id = 1;
+    INSERT INTO paradedb.bm25_search (description, category, rating, in_stock)
+        VALUES ('Another keyboard', 'Electronics', 3, true);
+    "#
+    .execute(&mut conn);
+
+    "SET paradedb.enable_aggregate_custom_scan TO off;".execute(&mut conn);
+    let (expected,) = query.fetch_one::<(i64,)>(&mut conn);
+
+    "SET paradedb.enable_aggregate_custom_scan TO on;".execute(&mut conn);
+    assert_uses_custom_scan(&mut conn, true, query);
+    let (count,) = query.fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, expected);
+
+    // and once the pages are all-visible, the visibility map alone should give the same answer
+    "VACUUM paradedb.bm25_search;".execute(&mut conn);
+    let (count,) = query.fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, expected);
+
+    // multiple COUNT(*)s in the target list are answered by the same count
+    let (a, b) =
+        "SELECT COUNT(*), COUNT(*) FROM paradedb.bm25_search WHERE description @@@ 'keyboard'"
+            .fetch_one::<(i64, i64)>(&mut conn);
+    assert_eq!((a, b), (expected, expected));
+}
+
+#[rstest]
+fn test_group_by_many_columns_within_max_term_agg_buckets(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE grid (id SERIAL PRIMARY KEY, x INT, y INT);
+    INSERT INTO grid (x, y) SELECT i % 300, i / 300 FROM generate_series(0, 89999) i;
+    CREATE INDEX grid_idx ON grid USING bm25 (id, x, y) WITH (key_field = 'id');
+    SET paradedb.enable_aggregate_custom_scan TO on;
+    SET paradedb.max_term_agg_buckets TO 300;
+    "#
+    .execute(&mut conn);
+
+    // every one of the 300 groups of `x` has 300 groups of `y`, which is more buckets than there
+    // would be room for if the limit only grew with the number of columns
+    let query = "SELECT x, y, COUNT(*) FROM grid WHERE id @@@ paradedb.all() GROUP BY x, y";
+    assert_uses_custom_scan(&mut conn, true, query);
+    let rows = query.fetch::<(i32, i32, i64)>(&mut conn);
+    assert_eq!(rows.len(), 90000);
+    assert!(rows.iter().all(|(_, _, count)| *count == 1));
+}
+
+#[rstest]
+fn test_count_sees_deletes_before_vacuum(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+    r#"
+    SET paradedb.enable_aggregate_custom_scan TO on;
+    VACUUM paradedb.bm25_search;
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT COUNT(*) FROM paradedb.bm25_search WHERE id @@@ paradedb.all()";
+    assert_uses_custom_scan(&mut conn, true, query);
+    let (before,) = query.fetch_one::<(i64,)>(&mut conn);
+
+    // the deleted rows stay in the index until a VACUUM, on pages that are no longer all-visible
+    "DELETE FROM paradedb.bm25_search WHERE id <= 5".execute(&mut conn);
+    let (after,) = query.fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(after, before - 5);
+}
+
+#[rstest]
+fn test_count_with_scattered_deletes(mut conn: PgConnection) {
+    // one row per page, so that deleting every other row leaves more runs of pages that aren't
+    // all-visible than are searched for one at a time
+    r#"
+    CREATE TABLE scattered (id SERIAL PRIMARY KEY, body TEXT) WITH (fillfactor = 10);
+    INSERT INTO scattered (body) SELECT repeat('x', 500) FROM generate_series(1, 3000);
+    CREATE INDEX scattered_idx ON scattered USING bm25 (id, body) WITH (key_field = 'id');
+    VACUUM scattered;
+    DELETE FROM scattered WHERE id % 2 = 0;
+    SET paradedb.enable_aggregate_custom_scan TO on;
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT COUNT(*) FROM scattered WHERE id @@@ paradedb.all()";
+    assert_uses_custom_scan(&mut conn, true, query);
+    let (count,) = query.fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 1500);
+
+    let (count,) = "SELECT COUNT(*) FROM scattered WHERE id @@@ paradedb.term('body', 'none')"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 0);
+}
@@ -0,0 +1,144 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod fixtures;
+
+use fixtures::*;
+use pretty_assertions::assert_eq;
+use rstest::*;
+use serde_json::Value;
+use sqlx::PgConnection;
+
+fn index_used(conn: &mut PgConnection) -> String {
+    let (plan,) = r#"
+    EXPLAIN (FORMAT JSON) SELECT id FROM products WHERE description @@@ 'shoes'
+    "#
+    .fetch_one::<(Value,)>(conn);
+    let plan = plan.to_string();
+    ["products_idx_v1", "products_idx_v2"]
+        .into_iter()
+        .find(|name| plan.contains(&format!("\"Index\":\"{name}\"")))
+        .unwrap_or_else(|| panic!("expected a custom scan in {plan}"))
+        .to_string()
+}
+
+#[rstest]
+fn swap_alias_between_rebuilds(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, description TEXT);
+    INSERT INTO products (description) VALUES ('red shoes'), ('blue hat');
+    CREATE INDEX products_idx_v1 ON products USING bm25 (id, description) WITH (key_field = 'id');
+    SELECT paradedb.create_alias('products_search', 'products_idx_v1');
+    "#
+    .execute(&mut conn);
+
+    "CREATE INDEX CONCURRENTLY products_idx_v2 ON products USING bm25 (id, description) WITH (key_field = 'id')"
+        .execute(&mut conn);
+
+    // the newer index is built, but the alias keeps queries on the old one
+    assert_eq!(index_used(&mut conn), "products_idx_v1");
+
+    let (previous,) = "SELECT paradedb.swap_alias('products_search', 'products_idx_v2')"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(previous, "products_idx_v1");
+    assert_eq!(index_used(&mut conn), "products_idx_v2");
+
+    let (resolved,) = "SELECT paradedb.resolve_alias('products_search')::regclass::text"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(resolved, "products_idx_v2");
+
+    "DROP INDEX products_idx_v1".execute(&mut conn);
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM products WHERE description @@@ 'shoes'".fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+
+    "SELECT paradedb.drop_alias('products_search')".execute(&mut conn);
+    assert!("SELECT paradedb.resolve_alias('products_search')"
+        .execute_result(&mut conn)
+        .is_err());
+}
+
+#[rstest]
+fn alias_errors(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, description TEXT);
+    CREATE INDEX products_idx_v1 ON products USING bm25 (id, description) WITH (key_field = 'id');
+    CREATE INDEX products_btree ON products (description);
+    SELECT paradedb.create_alias('products_search', 'products_idx_v1');
+    "#
+    .execute(&mut conn);
+
+    let err = "SELECT paradedb.create_alias('products_search', 'products_idx_v1')"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("already exists"), "{err}");
+
+    let err = "SELECT paradedb.swap_alias('products_search', 'products_btree')"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("is not a BM25 index"), "{err}");
+
+    let err = "SELECT paradedb.swap_alias('missing', 'products_idx_v1')"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"), "{err}");
+}
+
+#[rstest]
+fn rollover_to_next_generation(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE logs (id SERIAL, message TEXT);
+    CREATE TABLE logs_000001 () INHERITS (logs);
+    CREATE INDEX logs_000001_idx ON logs_000001 USING bm25 (id, message) WITH (key_field = 'id');
+    SELECT paradedb.create_alias('logs_write', 'logs_000001_idx');
+    INSERT INTO logs_000001 (message) VALUES ('disk full');
+    "#
+    .execute(&mut conn);
+
+    // neither threshold has been reached
+    let (rolled,) = "SELECT paradedb.rollover('logs_write', max_age => '1 day')"
+        .fetch_one::<(Option<String>,)>(&mut conn);
+    assert_eq!(rolled, None);
+
+    let (rolled,) = "SELECT paradedb.rollover('logs_write', max_size => 0)"
+        .fetch_one::<(Option<String>,)>(&mut conn);
+    let next_index = rolled.expect("should have rolled over");
+
+    let (table,) = r#"
+    SELECT indrelid::regclass::text FROM pg_index
+    WHERE indexrelid = paradedb.resolve_alias('logs_write')
+    "#
+    .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(table, "logs_000002");
+    let (resolved,) = "SELECT paradedb.resolve_alias('logs_write')::regclass::text"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(resolved, next_index);
+
+    "INSERT INTO logs_000002 (message) VALUES ('disk full again')".execute(&mut conn);
+    let rows: Vec<(String,)> =
+        "SELECT message FROM logs_000002 WHERE message @@@ 'disk'".fetch(&mut conn);
+    assert_eq!(rows, vec![("disk full again".to_string(),)]);
+
+    // the parent still sees every generation
+    let (count,) = "SELECT count(*) FROM logs".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 2);
+
+    let err = "SELECT paradedb.rollover('logs_write')"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("must be given"), "{err}");
+}
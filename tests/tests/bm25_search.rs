@@ -397,6 +397,314 @@ fn snippet(mut conn: PgConnection) {
     assert_relative_eq!(row.2, 2.877_26, epsilon = 1e-6);
 }
 
+#[rstest]
+fn snippet_fragments(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE fables (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO fables (body) VALUES (
+        'The quick brown fox jumps over the lazy dog. Nothing happens for a long while. '
+        'Then another fox appears by the river. A third fox sleeps.'
+    );
+    CREATE INDEX fables_idx ON fables USING bm25 (id, body) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    // every fragment has a single fox, so the earliest ones are chosen
+    let (snippet,) = "
+        SELECT paradedb.snippet(body, fragment_size => 20, number_of_fragments => 2, fragment_separator => ' | ')
+        FROM fables WHERE body @@@ 'fox'"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(
+        snippet,
+        "brown <b>fox</b> jumps | another <b>fox</b> appears"
+    );
+
+    // a single fragment is the best excerpt, as before
+    let (snippet,) = "
+        SELECT paradedb.snippet(body, max_num_chars => 20) FROM fables WHERE body @@@ 'fox'"
+        .fetch_one::<(String,)>(&mut conn);
+    assert!(!snippet.contains(" ... "), "{snippet}");
+}
+
+#[rstest]
+fn snippet_fragment_scores(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE fables (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO fables (body) VALUES (
+        'The fox ran off. Then a long quiet stretch of nothing at all. A dog chased a fox.'
+    );
+    CREATE INDEX fables_idx ON fables USING bm25 (id, body) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    // the fragment with both terms beats the earlier one with only one of them, and comes first
+    let (snippet,) = "
+        SELECT paradedb.snippet(body, fragment_size => 20, number_of_fragments => 2, fragment_separator => ' | ')
+        FROM fables WHERE body @@@ 'fox OR dog'"
+        .fetch_one::<(String,)>(&mut conn);
+    let (best, next) = snippet
+        .split_once(" | ")
+        .expect("should have two fragments");
+    assert!(best.contains("<b>dog</b> chased a <b>fox</b>"), "{snippet}");
+    assert!(next.contains("The <b>fox</b>"), "{snippet}");
+
+    let (fragments,) = "
+        SELECT paradedb.snippet_fragments(body, fragment_size => 20)
+        FROM fables WHERE body @@@ 'fox OR dog'"
+        .fetch_one::<(serde_json::Value,)>(&mut conn);
+    let fragments = fragments.as_array().expect("should be an array");
+    assert_eq!(fragments.len(), 2);
+    assert_eq!(fragments[0]["fragment"], best);
+    assert_eq!(fragments[1]["fragment"], next);
+    assert!(fragments[0]["score"].as_f64() > fragments[1]["score"].as_f64());
+}
+
+#[rstest]
+fn snippet_grapheme_boundaries(mut conn: PgConnection) {
+    let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+    let accented = "cafe\u{301}";
+    format!(
+        r#"
+        CREATE TABLE notes (id SERIAL PRIMARY KEY, body TEXT, accented TEXT);
+        INSERT INTO notes (body, accented) VALUES ('{family} fox {family}', '{accented}');
+        CREATE INDEX notes_idx ON notes USING bm25 (id, body, accented)
+        WITH (
+            key_field = 'id',
+            text_fields = '{{"accented": {{"tokenizer": {{"type": "ngram", "min_gram": 1, "max_gram": 1, "prefix_only": false}}}}}}'
+        );
+        "#
+    )
+    .execute(&mut conn);
+
+    // the whole text is seven grapheme clusters, though many more code points
+    let (snippet,) = "
+        SELECT paradedb.snippet(body, fragment_size => 7, count_graphemes => true)
+        FROM notes WHERE body @@@ 'fox'"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(snippet, format!("{family} <b>fox</b> {family}"));
+
+    // counted in code points, the emoji don't fit and are left out whole
+    let (snippet,) = "
+        SELECT paradedb.snippet(body, fragment_size => 7)
+        FROM notes WHERE body @@@ 'fox'"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(snippet.trim(), "<b>fox</b>");
+
+    // the ngram tokenizer splits the accent from its letter, but the highlight keeps them together
+    let (snippet,) = "
+        SELECT paradedb.snippet(accented)
+        FROM notes WHERE accented @@@ 'e'"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(snippet, "caf<b>e\u{301}</b>");
+}
+
+#[rstest]
+fn snippet_escape_html(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE pages (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO pages (body) VALUES ('Cats & dogs say <hello> to the fox');
+    CREATE INDEX pages_idx ON pages USING bm25 (id, body) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let (snippet,) = "
+        SELECT paradedb.snippet(body, '<mark class=\"hit\">', '</mark>')
+        FROM pages WHERE body @@@ 'fox OR hello'"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(
+        snippet,
+        "Cats &amp; dogs say &lt;<mark class=\"hit\">hello</mark>&gt; to the <mark class=\"hit\">fox</mark>"
+    );
+
+    let (snippet,) = "
+        SELECT paradedb.snippet(body, '[', ']', escape_html => false)
+        FROM pages WHERE body @@@ 'fox OR hello'"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(snippet, "Cats & dogs say <[hello]> to the [fox]");
+}
+
+#[rstest]
+fn snippet_whole_field(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE fables (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO fables (body) VALUES ('A fox, a hound, and another fox.');
+    CREATE INDEX fables_idx ON fables USING bm25 (id, body) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let (snippet,) = "
+        SELECT paradedb.snippet(body, max_num_chars => 10, number_of_fragments => 0)
+        FROM fables WHERE body @@@ 'fox'"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(snippet, "A <b>fox</b>, a hound, and another <b>fox</b>.");
+
+    let result =
+        "SELECT paradedb.snippet(body, number_of_fragments => -1) FROM fables WHERE body @@@ 'fox'"
+            .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn snippet_json(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE shoes (id SERIAL PRIMARY KEY, metadata JSONB);
+    INSERT INTO shoes (metadata) VALUES (
+        '{"color": "red", "details": {"note": "a red shoe", "size": 9}, "tags": ["blue", "red sky"]}'
+    );
+    CREATE INDEX shoes_idx ON shoes USING bm25 (id, metadata) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    // a path into the column is highlighted like a text column
+    let (color, note) = "
+        SELECT paradedb.snippet(metadata->>'color'), paradedb.snippet(metadata #>> '{details,note}')
+        FROM shoes WHERE id @@@ paradedb.parse('metadata.color:red')"
+        .fetch_one::<(String, String)>(&mut conn);
+    assert_eq!(color, "<b>red</b>");
+    assert_eq!(note, "a <b>red</b> shoe");
+
+    // the whole column is highlighted string by string, each labelled with its path
+    let (snippet,) = "
+        SELECT paradedb.snippet(metadata, number_of_fragments => 3, fragment_separator => ' | ')
+        FROM shoes WHERE id @@@ paradedb.parse('metadata.color:red')"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(
+        snippet,
+        "tags: <b>red</b> sky | color: <b>red</b> | details.note: a <b>red</b> shoe"
+    );
+}
+
+#[rstest]
+fn highlights(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE books (id SERIAL PRIMARY KEY, title TEXT, body TEXT, notes VARCHAR);
+    INSERT INTO books (title, body, notes)
+    VALUES ('The fox', 'A quick brown fox & a dog', 'nothing to see');
+    CREATE INDEX books_idx ON books USING bm25 (id, title, body, notes) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let (highlights,) = "
+        SELECT paradedb.highlights(ARRAY[title, body, notes], '<em>', '</em>')::text
+        FROM books WHERE title @@@ 'fox' OR body @@@ 'fox' OR notes @@@ 'fox'"
+        .fetch_one::<(String,)>(&mut conn);
+    let highlights: serde_json::Value = serde_json::from_str(&highlights).unwrap();
+    assert_eq!(
+        highlights,
+        serde_json::json!({
+            "title": "The <em>fox</em>",
+            "body": "A quick brown <em>fox</em> &amp; a dog",
+        })
+    );
+
+    let result =
+        "SELECT paradedb.highlights(ARRAY[title, 'body']) FROM books WHERE title @@@ 'fox'"
+            .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn snippet_phrase(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE notes (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO notes (body) VALUES ('shoes for running and running shoes');
+    CREATE INDEX notes_idx ON notes USING bm25 (id, body) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    // only the occurrence of the phrase is highlighted, not each of its terms
+    let (snippet,) = r#"SELECT paradedb.snippet(body) FROM notes WHERE body @@@ '"running shoes"'"#
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(snippet, "shoes for running and <b>running</b> <b>shoes</b>");
+
+    let (snippet,) = "
+        SELECT paradedb.snippet(body) FROM notes
+        WHERE id @@@ paradedb.phrase('body', ARRAY['shoes', 'running'], slop => 1)"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(snippet, "<b>shoes</b> for <b>running</b> and running shoes");
+
+    // a term that's also searched for on its own is still highlighted everywhere
+    let (snippet,) = r#"
+        SELECT paradedb.snippet(body) FROM notes
+        WHERE body @@@ '"running shoes"' OR body @@@ 'for'"#
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(
+        snippet,
+        "shoes <b>for</b> running and <b>running</b> <b>shoes</b>"
+    );
+
+    let (positions,) = r#"
+        SELECT paradedb.snippet_positions(body)::text FROM notes WHERE body @@@ '"running shoes"'"#
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(positions, "{{22,29},{30,35}}");
+}
+
+#[rstest]
+fn snippet_term_vectors(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE notes (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO notes (body) VALUES ('shoes for running and Running Shoes');
+    CREATE INDEX notes_idx ON notes USING bm25 (id, body)
+    WITH (key_field = 'id', text_fields = '{"body": {"term_vectors": true}}');
+    "#
+    .execute(&mut conn);
+
+    // the stored tokens highlight the same text that tokenizing it again would
+    let (snippet,) = "SELECT paradedb.snippet(body) FROM notes WHERE body @@@ 'running'"
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(snippet, "shoes for <b>running</b> and <b>Running</b> Shoes");
+
+    let (snippet,) = r#"SELECT paradedb.snippet(body) FROM notes WHERE body @@@ '"running shoes"'"#
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(snippet, "shoes for running and <b>Running</b> <b>Shoes</b>");
+
+    let (matches,) = "
+        SELECT paradedb.match_positions(body) FROM notes WHERE body @@@ 'shoes'"
+        .fetch_one::<(serde_json::Value,)>(&mut conn);
+    assert_eq!(
+        matches,
+        serde_json::json!([
+            {"byte_start": 0, "byte_end": 5, "term": "shoes"},
+            {"byte_start": 30, "byte_end": 35, "term": "shoes"}
+        ])
+    );
+
+    // the field that keeps the tokens isn't one of the index's own
+    let (count,) =
+        "SELECT count(*) FROM paradedb.schema('notes_idx') WHERE name LIKE '_pg_search_%'"
+            .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 0);
+}
+
+#[rstest]
+fn match_positions(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE notes (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO notes (body) VALUES ('Running shoes and more Shoes'), ('no match here');
+    CREATE INDEX notes_idx ON notes USING bm25 (id, body) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let (positions,) =
+        "SELECT paradedb.match_positions(body)::text FROM notes WHERE body @@@ 'shoes'"
+            .fetch_one::<(String,)>(&mut conn);
+    let positions: serde_json::Value = serde_json::from_str(&positions).unwrap();
+    assert_eq!(
+        positions,
+        serde_json::json!([
+            {"byte_start": 8, "byte_end": 13, "term": "shoes"},
+            {"byte_start": 23, "byte_end": 28, "term": "shoes"},
+        ])
+    );
+
+    // a document with no matches in the field has no positions
+    let (positions,) = "
+        SELECT paradedb.match_positions(body)::text FROM notes
+        WHERE id @@@ paradedb.term('id', 2)"
+        .fetch_one::<(Option<String>,)>(&mut conn);
+    assert_eq!(positions, None);
+}
+
 #[rstest]
 fn snippet_text_array(mut conn: PgConnection) {
     r#"
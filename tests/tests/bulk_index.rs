@@ -0,0 +1,104 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod fixtures;
+
+use fixtures::*;
+use pretty_assertions::assert_eq;
+use rstest::*;
+use sqlx::PgConnection;
+
+/// Have the server write the `lines` to a new file, and return its path.
+fn ndjson_file(conn: &mut PgConnection, lines: &[&str]) -> String {
+    let (data_directory,) = "SHOW data_directory".fetch_one::<(String,)>(conn);
+    let path = format!(
+        "{data_directory}/bulk_index_{}.ndjson",
+        uuid::Uuid::new_v4().simple()
+    );
+    let values = lines
+        .iter()
+        .map(|line| format!("($${line}$$)"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("COPY (VALUES {values}) TO '{path}'").execute(conn);
+    path
+}
+
+#[rstest]
+fn bulk_index_ndjson(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, description TEXT, rating INT, tags TEXT[]);
+    CREATE INDEX products_idx ON products USING bm25 (id, description, rating, tags)
+    WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let path = ndjson_file(
+        &mut conn,
+        &[
+            r#"{"description": "red shoes", "rating": 4}"#,
+            "",
+            r#"{"_index": "products", "_id": "10", "_source": {"description": "blue shoes", "rating": 5, "color": "blue"}}"#,
+            r#"{"description": "green hat", "tags": ["wool"]}"#,
+        ],
+    );
+    let (inserted,) =
+        format!("SELECT paradedb.bulk_index('products_idx', '{path}', batch_size => 2)")
+            .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(inserted, 3);
+
+    // the key of a hit is its `_id`, and the columns a document doesn't have get their defaults
+    let rows: Vec<(i32, Option<i32>)> = "SELECT id, rating FROM products WHERE id @@@ paradedb.term('description', 'shoes') ORDER BY id"
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(1, Some(4)), (10, Some(5))]);
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM products WHERE id @@@ paradedb.term('tags', 'wool')".fetch(&mut conn);
+    assert_eq!(rows, vec![(2,)]);
+
+    // every batch is written by the one writer, whose segment is committed with the transaction
+    let (nsegments,) =
+        "SELECT COUNT(*) FROM paradedb.index_info('products_idx')".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(nsegments, 1);
+}
+
+#[rstest]
+fn bulk_index_invalid_line(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, description TEXT);
+    CREATE INDEX products_idx ON products USING bm25 (id, description) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let path = ndjson_file(
+        &mut conn,
+        &[r#"{"description": "red shoes"}"#, r#"["blue shoes"]"#],
+    );
+    let err = format!("SELECT paradedb.bulk_index('products_idx', '{path}')")
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("line 2"), "{err}");
+
+    // nothing is inserted by a load that fails
+    let (count,) = "SELECT COUNT(*) FROM products".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 0);
+
+    let path = ndjson_file(&mut conn, &[r#"{"color": "red"}"#]);
+    let err = format!("SELECT paradedb.bulk_index('products_idx', '{path}')")
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("has none of the columns"), "{err}");
+}
@@ -24,3 +24,49 @@ async fn test_copy_to_table(mut conn: PgConnection) {
         .fetch_one::<(i64,)>(&mut conn);
     assert_eq!(count, 1);
 }
+
+#[rstest]
+async fn test_copy_in_batches(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE test_copy_batches (id SERIAL PRIMARY KEY, name TEXT);
+        CREATE INDEX idx_test_copy_batches ON test_copy_batches USING bm25(id, name)
+        WITH (key_field = 'id', layer_sizes = '0', background_layer_sizes = '0');
+        SET paradedb.insert_batch_size = 2;
+    "#
+    .execute(&mut conn);
+
+    // five rows are written as two full batches and the rest at the end of the statement
+    let mut copyin = conn
+        .copy_in_raw("COPY test_copy_batches(name) FROM STDIN")
+        .await
+        .unwrap();
+    copyin
+        .send("one\ntwo\nthree\nfour\nfive".as_bytes())
+        .await
+        .unwrap();
+    copyin.finish().await.unwrap();
+    "INSERT INTO test_copy_batches(name) VALUES ('six'), ('seven'), ('eight')".execute(&mut conn);
+
+    let (count,) = "SELECT COUNT(*) FROM test_copy_batches WHERE id @@@ paradedb.all()"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 8);
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM test_copy_batches WHERE name @@@ 'five OR six' ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(5,), (6,)]);
+
+    // the batches of each statement are written to the one segment of its writer
+    let (nsegments,) = "SELECT COUNT(*) FROM paradedb.index_info('idx_test_copy_batches')"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(nsegments, 2);
+
+    // and in bulk load mode, to the transaction's writer
+    "BEGIN".execute(&mut conn);
+    "SET LOCAL paradedb.bulk_load_mode = true".execute(&mut conn);
+    "INSERT INTO test_copy_batches(name) VALUES ('nine'), ('ten'), ('eleven')".execute(&mut conn);
+    "INSERT INTO test_copy_batches(name) VALUES ('twelve')".execute(&mut conn);
+    "COMMIT".execute(&mut conn);
+    let (count,) = "SELECT COUNT(*) FROM test_copy_batches WHERE name @@@ 'eleven OR twelve'"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 2);
+}
@@ -94,6 +94,157 @@ fn includes_segment_count(mut conn: PgConnection) {
     assert!(plan.get("Segment Count").is_some());
 }
 
+#[rstest]
+fn explain_analyze_shows_search_phases(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let query = "SELECT * FROM paradedb.bm25_search WHERE description @@@ 'keyboard' AND category @@@ 'electronics'";
+    let (plan,) =
+        format!("EXPLAIN (ANALYZE, VERBOSE, FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    let plan = plan.pointer("/0/Plan").unwrap();
+
+    assert!(plan
+        .get("Tantivy Query Tree")
+        .and_then(Value::as_str)
+        .is_some_and(|tree| tree.contains("BooleanQuery")));
+    assert_eq!(
+        plan.get("   Clause Matches"),
+        Some(&Value::String("must[0]: 2, must[1]: 5".into()))
+    );
+    assert!(plan
+        .get("Segments Visited")
+        .and_then(Value::as_u64)
+        .is_some_and(|segments| segments > 0));
+    for key in ["Weight Creation Time", "Collection Time", "Heap Fetch Time"] {
+        assert!(plan.get(key).and_then(Value::as_f64).is_some(), "{key}");
+    }
+
+    // the timings are left out with the rest of Postgres' own
+    let (plan,) = format!("EXPLAIN (ANALYZE, TIMING OFF, FORMAT JSON) {query}")
+        .fetch_one::<(Value,)>(&mut conn);
+    let plan = plan.pointer("/0/Plan").unwrap();
+    assert_eq!(plan.get("Collection Time"), None);
+    assert_eq!(plan.get("Tantivy Query Tree"), None);
+}
+
+#[rstest]
+fn log_slow_searches(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let query = "SELECT id FROM paradedb.bm25_search WHERE description @@@ 'keyboard' ORDER BY id";
+    let expected = query.fetch::<(i32,)>(&mut conn);
+
+    // every search is slow enough to be logged, which mustn't change its results
+    "SET paradedb.log_slow_searches_ms = 0".execute(&mut conn);
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), expected);
+
+    // nor what EXPLAIN shows when it's asked not to time anything
+    let (plan,) = format!("EXPLAIN (ANALYZE, TIMING OFF, FORMAT JSON) {query}")
+        .fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(find_key(&plan, "Collection Time"), None);
+}
+
+#[rstest]
+fn trace_searches(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let queries = [
+        "SELECT id FROM paradedb.bm25_search WHERE description @@@ 'keyboard' ORDER BY id",
+        "SELECT id FROM paradedb.bm25_search WHERE description @@@ 'keyboard' AND rating > 2 ORDER BY id",
+        "SELECT id FROM paradedb.bm25_search WHERE description @@@ 'keyboard' OR lower(category) = 'footwear' ORDER BY id",
+        "SELECT id FROM paradedb.bm25_search WHERE rating > 2 ORDER BY id",
+        "SELECT a.id FROM paradedb.bm25_search a JOIN paradedb.bm25_search b ON a.id = b.id WHERE a.description @@@ 'keyboard' OR b.category @@@ 'electronics' ORDER BY a.id",
+    ];
+    let expected = queries
+        .iter()
+        .map(|query| query.fetch::<(i32,)>(&mut conn))
+        .collect::<Vec<_>>();
+
+    // reporting how each search is planned and executed mustn't change its results
+    "SET paradedb.trace = on".execute(&mut conn);
+    for (query, expected) in queries.iter().zip(expected) {
+        assert_eq!(query.fetch::<(i32,)>(&mut conn), expected, "{query}");
+    }
+}
+
+fn find_key<'a>(node: &'a Value, key: &str) -> Option<&'a Value> {
+    node.get(key)
+        .or_else(|| find_key(node.get(0)?.get("Plan")?, key))
+        .or_else(|| {
+            node.get("Plans")?
+                .as_array()?
+                .iter()
+                .find_map(|plan| find_key(plan, key))
+        })
+}
+
+#[rstest]
+fn work_mem_limits_searches(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE work_mem_docs (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO work_mem_docs (body) SELECT 'document ' || x FROM generate_series(1, 100000) x;
+    CREATE INDEX work_mem_docs_idx ON work_mem_docs USING bm25 (id, body) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT id FROM work_mem_docs WHERE body @@@ 'document'";
+    let (plan,) =
+        format!("EXPLAIN (ANALYZE, FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    let peak = plan
+        .pointer("/0/Plan/Peak Memory")
+        .and_then(Value::as_u64)
+        .unwrap();
+    assert!(peak > 64, "{peak}");
+
+    "SET paradedb.work_mem = '64kB'".execute(&mut conn);
+    let error = query.execute_result(&mut conn).unwrap_err();
+    assert!(error.to_string().contains("paradedb.work_mem"), "{error}");
+
+    // without the limit, the same search succeeds
+    "RESET paradedb.work_mem".execute(&mut conn);
+    assert_eq!(query.fetch::<(i32,)>(&mut conn).len(), 100000);
+}
+
+#[rstest]
+fn search_cache_reuses_top_n_results(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+    "SET paradedb.search_cache_entries = 10".execute(&mut conn);
+
+    let query = "SELECT id FROM paradedb.bm25_search WHERE description @@@ 'keyboard' ORDER BY paradedb.score(id) DESC, id LIMIT 5";
+    let cached_queries = |conn: &mut PgConnection| {
+        let (plan,) = format!("EXPLAIN (ANALYZE, FORMAT JSON) {query}").fetch_one::<(Value,)>(conn);
+        find_key(&plan, "   Cached Queries").and_then(Value::as_u64)
+    };
+
+    let expected = query.fetch::<(i32,)>(&mut conn);
+    assert_eq!(cached_queries(&mut conn), Some(1));
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), expected);
+
+    // a new segment makes the cached results unreachable
+    "INSERT INTO paradedb.bm25_search (description, rating, category) VALUES ('keyboard keyboard keyboard', 5, 'Electronics')"
+        .execute(&mut conn);
+    let results = query.fetch::<(i32,)>(&mut conn);
+    assert_ne!(results, expected);
+    assert_eq!(results.len(), expected.len() + 1);
+    assert_eq!(cached_queries(&mut conn), Some(1));
+}
+
+#[rstest]
+fn parallel_collection_matches_serial(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+    // each insert is a segment of its own
+    for i in 0..4 {
+        format!("INSERT INTO paradedb.bm25_search (description, rating, category) VALUES ('{} keyboard', {}, 'Electronics')", "wireless ".repeat(i), i + 1)
+            .execute(&mut conn);
+    }
+    "SET max_parallel_workers_per_gather = 2".execute(&mut conn);
+
+    let query = "SELECT id, paradedb.score(id) FROM paradedb.bm25_search WHERE description @@@ 'keyboard OR wireless' ORDER BY paradedb.score(id) DESC LIMIT 4 OFFSET 1";
+    let serial = query.fetch::<(i32, f32)>(&mut conn);
+    "SET paradedb.enable_parallel_collection = true".execute(&mut conn);
+    assert_eq!(query.fetch::<(i32, f32)>(&mut conn), serial);
+}
+
 #[rstest]
 fn field_on_left(mut conn: PgConnection) {
     SimpleProductsTable::setup().execute(&mut conn);
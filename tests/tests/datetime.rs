@@ -20,6 +20,7 @@ mod fixtures;
 use fixtures::*;
 use pretty_assertions::assert_eq;
 use rstest::*;
+use serde_json::Value;
 use sqlx::PgConnection;
 
 #[rstest]
@@ -141,3 +142,53 @@ fn datetime_term_second(mut conn: PgConnection) {
     let rows: Vec<(i32,)> = "SELECT id FROM ts WHERE id @@@ paradedb.range('t', tsrange('2025-01-28T18:19:14.001Z'::timestamp, NULL, '(]')) ORDER BY id".fetch(&mut conn);
     assert_eq!(rows, expected);
 }
+
+#[rstest]
+fn datetime_precision(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE events (id SERIAL, t TIMESTAMPTZ);
+    INSERT INTO events (t) values ('2025-01-28T18:19:14.000003Z');
+    INSERT INTO events (t) values ('2025-01-28T18:19:14.000002Z');
+    INSERT INTO events (t) values ('2025-01-28T18:19:14.000001Z');
+    INSERT INTO events (t) values ('2025-01-28T18:19:13.9Z');
+    "#
+    .execute(&mut conn);
+
+    let expected: Vec<(i32, String)> =
+        "SELECT id, t::text FROM events ORDER BY t LIMIT 3".fetch(&mut conn);
+    assert_eq!(
+        expected.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        [4, 3, 2]
+    );
+
+    for precision in ["micros", "nanoseconds", "seconds"] {
+        format!(
+            r#"
+            DROP INDEX IF EXISTS events_idx;
+            CREATE INDEX events_idx ON events USING bm25 (id, t)
+            WITH (key_field = 'id', datetime_fields = '{{"t": {{"precision": "{precision}"}}}}');
+            "#
+        )
+        .execute(&mut conn);
+
+        let query = "SELECT id, t::text FROM events WHERE id @@@ paradedb.all() ORDER BY t LIMIT 3";
+        let rows: Vec<(i32, String)> = query.fetch(&mut conn);
+        assert_eq!(rows, expected, "precision {precision}");
+
+        // events within the same second can't be ordered by their truncated fast values
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+        assert_eq!(
+            format!("{plan:?}").contains("TopN Order By"),
+            precision != "seconds",
+            "precision {precision}"
+        );
+    }
+
+    let result = r#"
+    DROP INDEX events_idx;
+    CREATE INDEX events_idx ON events USING bm25 (id, t)
+    WITH (key_field = 'id', datetime_fields = '{"t": {"precision": "minutes"}}');
+    "#
+    .execute_result(&mut conn);
+    assert!(result.is_err());
+}
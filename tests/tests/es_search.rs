@@ -0,0 +1,148 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod fixtures;
+
+use fixtures::*;
+use pretty_assertions::assert_eq;
+use rstest::*;
+use serde_json::{json, Value};
+use sqlx::PgConnection;
+
+fn es_search(conn: &mut PgConnection, request: Value) -> Value {
+    format!("SELECT paradedb.es_search('paradedb.bm25_search_bm25_index', '{request}'::jsonb)")
+        .fetch_one::<(Value,)>(conn)
+        .0
+}
+
+#[rstest]
+fn es_search_hits(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let response = es_search(
+        &mut conn,
+        json!({
+            "query": {"bool": {
+                "must": [{"match": {"description": "shoes"}}],
+                "filter": [{"range": {"rating": {"gte": 4}}}]
+            }},
+            "size": 2,
+            "_source": ["description", "rating"],
+            "highlight": {"fields": {"description": {}}}
+        }),
+    );
+
+    let expected: Vec<(i32, String, i32)> = r#"
+    SELECT id, description, rating FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes' AND rating >= 4
+    ORDER BY paradedb.score(id) DESC LIMIT 2
+    "#
+    .fetch(&mut conn);
+    let (total,) =
+        "SELECT count(*) FROM paradedb.bm25_search WHERE description @@@ 'shoes' AND rating >= 4"
+            .fetch_one::<(i64,)>(&mut conn);
+
+    assert_eq!(response["timed_out"], json!(false));
+    assert_eq!(response["hits"]["total"]["value"], json!(total));
+    let hits = response["hits"]["hits"].as_array().unwrap();
+    assert_eq!(hits.len(), expected.len());
+    for (hit, (id, description, rating)) in hits.iter().zip(&expected) {
+        assert_eq!(hit["_index"], json!("bm25_search_bm25_index"));
+        assert_eq!(hit["_id"], json!(id.to_string()));
+        assert_eq!(
+            hit["_source"],
+            json!({"description": description, "rating": rating})
+        );
+        assert!(hit["highlight"]["description"][0]
+            .as_str()
+            .unwrap()
+            .contains("<em>shoes</em>"));
+    }
+    assert_eq!(response["hits"]["max_score"], hits[0]["_score"]);
+}
+
+#[rstest]
+fn es_search_sort_and_page(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let response = es_search(
+        &mut conn,
+        json!({
+            "query": {"match_all": {}},
+            "sort": [{"rating": "desc"}, {"id": {"order": "asc"}}],
+            "from": 2,
+            "size": 3,
+            "_source": false
+        }),
+    );
+    let expected: Vec<(i32, i32)> =
+        "SELECT id, rating FROM paradedb.bm25_search ORDER BY rating DESC, id ASC LIMIT 3 OFFSET 2"
+            .fetch(&mut conn);
+
+    let hits = response["hits"]["hits"].as_array().unwrap();
+    assert_eq!(
+        hits.iter()
+            .map(|hit| hit["sort"].clone())
+            .collect::<Vec<_>>(),
+        expected
+            .iter()
+            .map(|(id, rating)| json!([rating, id]))
+            .collect::<Vec<_>>()
+    );
+    assert!(hits.iter().all(|hit| hit.get("_source").is_none()));
+}
+
+#[rstest]
+fn es_search_aggregations(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let response = es_search(
+        &mut conn,
+        json!({
+            "query": {"term": {"category": "electronics"}},
+            "size": 0,
+            "aggs": {"ratings": {"terms": {"field": "rating", "order": {"_term": "asc"}}}}
+        }),
+    );
+    let (total,) = "SELECT count(*) FROM paradedb.bm25_search WHERE category @@@ 'electronics'"
+        .fetch_one::<(i64,)>(&mut conn);
+
+    assert_eq!(response["hits"]["total"]["value"], json!(total));
+    assert_eq!(response["hits"]["hits"], json!([]));
+    let buckets = response["aggregations"]["ratings"]["buckets"]
+        .as_array()
+        .unwrap();
+    assert_eq!(
+        buckets
+            .iter()
+            .map(|bucket| bucket["doc_count"].as_i64().unwrap())
+            .sum::<i64>(),
+        total
+    );
+}
+
+#[rstest]
+fn es_search_unsupported(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let error = "SELECT paradedb.es_search('paradedb.bm25_search_bm25_index', '{\"query\": {\"geo_shape\": {}}}'::jsonb)"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("the `geo_shape` query is not supported"));
+}
@@ -0,0 +1,177 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod fixtures;
+
+use fixtures::*;
+use pretty_assertions::assert_eq;
+use rstest::*;
+use sqlx::PgConnection;
+
+/// The path of a file on the server that doesn't exist yet.
+fn export_path(conn: &mut PgConnection) -> String {
+    let (data_directory,) = "SHOW data_directory".fetch_one::<(String,)>(conn);
+    format!(
+        "{data_directory}/export_{}.parquet",
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+#[rstest]
+fn export_parquet_matching_documents(mut conn: PgConnection) {
+    r#"
+    CALL paradedb.create_bm25_test_table(schema_name => 'public', table_name => 'mock_items');
+    CREATE INDEX search_idx ON mock_items USING bm25 (id, description, rating, created_at, in_stock)
+    WITH (key_field = 'id', text_fields = '{"description": {"fast": true}}');
+    DELETE FROM mock_items WHERE id = 3;
+    "#
+    .execute(&mut conn);
+
+    let path = export_path(&mut conn);
+    let (exported,) = format!(
+        "SELECT paradedb.export_parquet('search_idx', paradedb.term('description', 'shoes'), '{path}')"
+    )
+    .fetch_one::<(i64,)>(&mut conn);
+
+    // the deleted row isn't exported, even though its document is still in the index
+    let (expected,) = "SELECT COUNT(*) FROM mock_items WHERE description @@@ 'shoes'"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(exported, expected);
+
+    let (size,) = format!("SELECT size FROM pg_stat_file('{path}')").fetch_one::<(i64,)>(&mut conn);
+    assert!(size > 0);
+
+    // the file can't be overwritten
+    let err = format!(
+        "SELECT paradedb.export_parquet('search_idx', paradedb.all(), '{path}', fields => ARRAY['id', 'rating'])"
+    )
+    .execute_result(&mut conn)
+    .unwrap_err();
+    assert!(err.to_string().contains("could not create"), "{err}");
+}
+
+#[rstest]
+fn export_parquet_invalid_fields(mut conn: PgConnection) {
+    r#"
+    CALL paradedb.create_bm25_test_table(schema_name => 'public', table_name => 'mock_items');
+    CREATE INDEX search_idx ON mock_items USING bm25 (id, description, rating)
+    WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let path = export_path(&mut conn);
+    let err = format!(
+        "SELECT paradedb.export_parquet('search_idx', paradedb.all(), '{path}', fields => ARRAY['description'])"
+    )
+    .execute_result(&mut conn)
+    .unwrap_err();
+    assert!(err.to_string().contains("is not a fast field"), "{err}");
+
+    let err = format!(
+        "SELECT paradedb.export_parquet('search_idx', paradedb.all(), '{path}', fields => ARRAY['color'])"
+    )
+    .execute_result(&mut conn)
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("is not a field of the index"),
+        "{err}"
+    );
+
+    // nothing is written by an export that fails
+    let exists = format!("SELECT size FROM pg_stat_file('{path}', missing_ok => true)")
+        .fetch_one::<(Option<i64>,)>(&mut conn)
+        .0;
+    assert_eq!(exists, None);
+}
+
+#[rstest]
+fn export_parquet_requires_select(mut conn: PgConnection) {
+    r#"
+    CALL paradedb.create_bm25_test_table(schema_name => 'public', table_name => 'mock_items');
+    CREATE INDEX search_idx ON mock_items USING bm25 (id, description, rating)
+    WITH (key_field = 'id');
+    DO $$
+    BEGIN
+        IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'export_user') THEN
+            CREATE ROLE export_user;
+        END IF;
+    END
+    $$;
+    GRANT pg_write_server_files TO export_user;
+    SET ROLE export_user;
+    "#
+    .execute(&mut conn);
+
+    let path = export_path(&mut conn);
+    let err = format!(
+        "SELECT paradedb.export_parquet('search_idx', paradedb.all(), '{path}', fields => ARRAY['id', 'rating'])"
+    )
+    .execute_result(&mut conn)
+    .unwrap_err();
+    assert!(err.to_string().contains("permission denied"), "{err}");
+
+    // SELECT on the exported columns is enough, but not on only some of them
+    "RESET ROLE; GRANT SELECT (id) ON mock_items TO export_user; SET ROLE export_user"
+        .execute(&mut conn);
+    let err = format!(
+        "SELECT paradedb.export_parquet('search_idx', paradedb.all(), '{path}', fields => ARRAY['id', 'rating'])"
+    )
+    .execute_result(&mut conn)
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("permission denied for column `rating`"),
+        "{err}"
+    );
+    let (exported,) = format!(
+        "SELECT paradedb.export_parquet('search_idx', paradedb.all(), '{path}', fields => ARRAY['id'])"
+    )
+    .fetch_one::<(i64,)>(&mut conn);
+    assert!(exported > 0);
+    "RESET ROLE".execute(&mut conn);
+}
+
+#[rstest]
+fn export_parquet_refuses_row_level_security(mut conn: PgConnection) {
+    r#"
+    CALL paradedb.create_bm25_test_table(schema_name => 'public', table_name => 'mock_items');
+    CREATE INDEX search_idx ON mock_items USING bm25 (id, description, rating)
+    WITH (key_field = 'id');
+    DO $$
+    BEGIN
+        IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'export_user') THEN
+            CREATE ROLE export_user;
+        END IF;
+    END
+    $$;
+    GRANT pg_write_server_files TO export_user;
+    GRANT SELECT ON mock_items TO export_user;
+    ALTER TABLE mock_items ENABLE ROW LEVEL SECURITY;
+    CREATE POLICY high_ratings ON mock_items USING (rating > 4);
+    SET ROLE export_user;
+    "#
+    .execute(&mut conn);
+
+    let path = export_path(&mut conn);
+    let err = format!(
+        "SELECT paradedb.export_parquet('search_idx', paradedb.all(), '{path}', fields => ARRAY['id'])"
+    )
+    .execute_result(&mut conn)
+    .unwrap_err();
+    assert!(err.to_string().contains("row-level security"), "{err}");
+    "RESET ROLE".execute(&mut conn);
+}
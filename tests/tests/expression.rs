@@ -93,3 +93,55 @@ fn expression_conflicting_query_string(mut conn: PgConnection) {
             .fetch_one::<(i64,)>(&mut conn);
     assert_eq!(count, 1);
 }
+
+#[rstest]
+fn expression_computed_fields(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE books (id SERIAL PRIMARY KEY, title TEXT, subtitle TEXT, metadata JSONB);
+    INSERT INTO books (title, subtitle, metadata) VALUES
+        ('The Hobbit', 'There and Back Again', '{"genre": "Fantasy", "attrs": {"pages": 310}}'),
+        ('Dune', NULL, '{"genre": "Science Fiction", "attrs": {"pages": 412}}');
+
+    CREATE INDEX books_idx ON books USING bm25 (
+        id,
+        (lower(title) || ' ' || coalesce(subtitle, '')),
+        (metadata->>'genre')
+    ) WITH (
+        key_field = 'id',
+        text_fields = '{
+            "full_title": {"column": "_pg_search_1"},
+            "genre": {"column": "_pg_search_2", "tokenizer": {"type": "keyword"}, "fast": true}
+        }'
+    );
+    "#
+    .execute(&mut conn);
+
+    // the expressions themselves can be searched...
+    let ids: Vec<(i32,)> = "SELECT id FROM books WHERE (lower(title) || ' ' || coalesce(subtitle, '')) @@@ 'back' ORDER BY id"
+        .fetch(&mut conn);
+    assert_eq!(ids, vec![(1,)]);
+    let ids: Vec<(i32,)> =
+        "SELECT id FROM books WHERE (metadata->>'genre') @@@ 'Fiction' ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(ids, vec![(2,)]);
+
+    // ...as can the names given to them with `column`
+    let ids: Vec<(i32,)> =
+        "SELECT id FROM books WHERE id @@@ 'full_title:dune' ORDER BY id".fetch(&mut conn);
+    assert_eq!(ids, vec![(2,)]);
+    let ids: Vec<(i32,)> =
+        "SELECT id FROM books WHERE id @@@ paradedb.term('genre', 'Science Fiction') ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(ids, vec![(2,)]);
+
+    // and they follow changes to the columns they're computed from
+    "UPDATE books SET subtitle = 'Desert Planet', metadata = '{\"genre\": \"Classic\"}' WHERE id = 2"
+        .execute(&mut conn);
+    let ids: Vec<(i32,)> =
+        "SELECT id FROM books WHERE id @@@ 'full_title:desert' ORDER BY id".fetch(&mut conn);
+    assert_eq!(ids, vec![(2,)]);
+    let ids: Vec<(i32,)> =
+        "SELECT id FROM books WHERE id @@@ paradedb.term('genre', 'Science Fiction') ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(ids, vec![]);
+}
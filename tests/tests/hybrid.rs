@@ -154,3 +154,140 @@ fn reciprocal_rank_fusion(mut conn: PgConnection) {
         )
     );
 }
+
+#[rstest]
+fn hybrid_fusion_in_one_scan(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    SimpleProductsTable::setup().execute(&mut conn);
+    r#"
+    CREATE EXTENSION vector;
+    ALTER TABLE paradedb.bm25_search ADD COLUMN embedding vector(3);
+
+    UPDATE paradedb.bm25_search m
+    SET embedding = ('[' ||
+    ((m.id + 1) % 10 + 1)::integer || ',' ||
+    ((m.id + 2) % 10 + 1)::integer || ',' ||
+    ((m.id + 3) % 10 + 1)::integer || ']')::vector;
+    "#
+    .execute(&mut conn);
+
+    // the scan returns the rows in the order of their fused scores, without a sort above it
+    let (plan,) = r#"
+    EXPLAIN (FORMAT JSON) SELECT id FROM paradedb.bm25_search
+    WHERE description @@@ 'keyboard OR shoes'
+    ORDER BY paradedb.hybrid(id, embedding <=> '[1,2,3]', fusion => 'rrf') DESC
+    LIMIT 3
+    "#
+    .fetch_one::<(Value,)>(&mut conn);
+    let scan = plan.pointer("/0/Plan/Plans/0").unwrap();
+    assert_eq!(
+        scan.get("Exec Method"),
+        Some(&Value::String(String::from("HybridScanExecState")))
+    );
+    assert_eq!(
+        scan.get("Hybrid Fusion"),
+        Some(&Value::String(String::from("rrf (k = 60)")))
+    );
+
+    let rows: Vec<(i32, f64)> = r#"
+    SELECT id, paradedb.hybrid(id, embedding <=> '[1,2,3]', fusion => 'rrf') FROM paradedb.bm25_search
+    WHERE description @@@ 'keyboard OR shoes'
+    ORDER BY paradedb.hybrid(id, embedding <=> '[1,2,3]', fusion => 'rrf') DESC
+    LIMIT 3
+    "#
+    .fetch(&mut conn);
+    assert_eq!(rows.len(), 3);
+    assert!(rows.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    // ranked first by both, a row's score can be no more than 2 / (60 + 1)
+    assert!(rows[0].1 <= 2.0 / 61.0);
+
+    // with all of the weight on BM25, the rows are in the order of their BM25 scores
+    let rows: Vec<(f32, f64)> = r#"
+    SELECT paradedb.score(id), paradedb.hybrid(id, embedding <=> '[1,2,3]', fusion => 'weighted', weight => 1.0)
+    FROM paradedb.bm25_search
+    WHERE description @@@ 'keyboard OR shoes'
+    ORDER BY paradedb.hybrid(id, embedding <=> '[1,2,3]', fusion => 'weighted', weight => 1.0) DESC
+    LIMIT 5
+    "#
+    .fetch(&mut conn);
+    assert_eq!(rows.len(), 5);
+    assert!(rows.windows(2).all(|pair| pair[0].0 >= pair[1].0));
+    assert_eq!(rows[0].1, 1.0);
+
+    let error = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE description @@@ 'keyboard'
+    ORDER BY paradedb.hybrid(id, embedding <=> '[1,2,3]', fusion => 'borda') DESC
+    LIMIT 3
+    "#
+    .execute_result(&mut conn)
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("fusion must be 'rrf' or 'weighted'"));
+}
+
+#[rstest]
+fn hybrid_distance_from_fast_field(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    SimpleProductsTable::setup().execute(&mut conn);
+    r#"
+    CREATE EXTENSION vector;
+    ALTER TABLE paradedb.bm25_search ADD COLUMN embedding vector(3);
+
+    UPDATE paradedb.bm25_search m
+    SET embedding = ('[' ||
+    ((m.id + 1) % 10 + 1)::integer || ',' ||
+    ((m.id + 2) % 10 + 1)::integer || ',' ||
+    ((m.id + 3) % 10 + 1)::integer || ']')::vector;
+    "#
+    .execute(&mut conn);
+
+    let query = r#"
+    SELECT id, paradedb.hybrid(id, embedding <=> '[1,2,3]') FROM paradedb.bm25_search
+    WHERE description @@@ 'keyboard OR shoes'
+    ORDER BY paradedb.hybrid(id, embedding <=> '[1,2,3]') DESC
+    LIMIT 5
+    "#;
+    let explain = format!("EXPLAIN (FORMAT JSON) {query}");
+
+    let (plan,) = explain.fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(
+        plan.pointer("/0/Plan/Plans/0/Hybrid Distance"),
+        Some(&Value::String(String::from("heap")))
+    );
+    let from_heap: Vec<(i32, f64)> = query.fetch(&mut conn);
+
+    // once the index includes the embeddings, the distances are read from it instead
+    r#"
+    DROP INDEX paradedb.bm25_search_bm25_index;
+    CREATE INDEX bm25_search_bm25_index
+    ON paradedb.bm25_search
+    USING bm25 (id, description, embedding)
+    WITH (key_field='id');
+    "#
+    .execute(&mut conn);
+
+    let (plan,) = explain.fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(
+        plan.pointer("/0/Plan/Plans/0/Hybrid Distance"),
+        Some(&Value::String(String::from("embedding <=> (fast field)")))
+    );
+    let from_index: Vec<(i32, f64)> = query.fetch(&mut conn);
+    assert_eq!(from_index, from_heap);
+
+    // a distance to something other than a constant is still computed from the rows
+    let (plan,) = r#"
+    EXPLAIN (FORMAT JSON) SELECT id FROM paradedb.bm25_search
+    WHERE description @@@ 'keyboard OR shoes'
+    ORDER BY paradedb.hybrid(id, embedding <=> embedding) DESC
+    LIMIT 5
+    "#
+    .fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(
+        plan.pointer("/0/Plan/Plans/0/Hybrid Distance"),
+        Some(&Value::String(String::from("heap")))
+    );
+}
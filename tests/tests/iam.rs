@@ -139,3 +139,30 @@ fn explain_row_estimate(mut conn: PgConnection) {
             || plan_rows == Some(&Value::Number(Number::from(3)))
     );
 }
+
+#[rstest]
+fn explain_row_estimate_of_conjunction(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE estimates (id serial8, body text);
+    -- "alpha" is in 60% of the rows and "beta" in 50%, but both are in only 10%
+    INSERT INTO estimates (body)
+    SELECT CASE WHEN x % 10 = 0 THEN 'alpha beta' WHEN x % 10 < 6 THEN 'alpha' ELSE 'beta' END
+    FROM generate_series(1, 200000) x;
+    CREATE INDEX idx_estimates ON estimates USING bm25 (id, body) WITH (key_field = 'id');
+    ANALYZE estimates;
+    "#
+    .execute(&mut conn);
+
+    let (plan,) = "EXPLAIN (FORMAT JSON) SELECT * FROM estimates WHERE body @@@ 'alpha AND beta'"
+        .fetch_one::<(Value,)>(&mut conn);
+    let plan_rows = plan
+        .pointer("/0/Plan/Plan Rows")
+        .and_then(Value::as_f64)
+        .unwrap();
+    assert!(
+        (10000.0..40000.0).contains(&plan_rows),
+        "estimated {plan_rows} rows"
+    );
+}
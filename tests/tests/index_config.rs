@@ -595,6 +595,46 @@ fn partitioned_query(mut conn: PgConnection) {
     }
 }
 
+#[rstest]
+fn partitioned_scores_are_comparable(mut conn: PgConnection) {
+    PartitionedTable::setup().execute(&mut conn);
+
+    // "keyboard" is common in the first partition, but rare in the second
+    r#"
+        INSERT INTO sales (sale_date, amount, description) VALUES
+        ('2023-01-10', 150.00, 'Ergonomic metal keyboard'),
+        ('2023-01-15', 200.00, 'Plastic keyboard'),
+        ('2023-02-05', 300.00, 'Wireless keyboard'),
+        ('2023-04-01', 250.00, 'Keyboard stand'),
+        ('2023-04-18', 180.00, 'Designer wall paintings'),
+        ('2023-05-09', 320.00, 'Handcrafted wooden frame');
+
+        CREATE TABLE unpartitioned_sales AS SELECT * FROM sales;
+        CREATE INDEX unpartitioned_sales_index ON unpartitioned_sales
+        USING bm25 (id, description, sale_date, amount)
+        WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let scores = |conn: &mut PgConnection, table: &str| -> Vec<(String, f32)> {
+        format!(
+            "SELECT description, paradedb.score(id) FROM {table}
+             WHERE description @@@ 'keyboard'
+             ORDER BY paradedb.score(id) DESC, description LIMIT 10"
+        )
+        .fetch(conn)
+    };
+
+    // every row is scored as if the table weren't partitioned
+    let expected = scores(&mut conn, "unpartitioned_sales");
+    assert_eq!(scores(&mut conn, "sales"), expected);
+    assert_eq!(expected[0].0, "Keyboard stand");
+
+    // otherwise each partition only scores with its own statistics
+    "SET paradedb.enable_partition_wide_scoring = false".execute(&mut conn);
+    assert_ne!(scores(&mut conn, "sales"), expected);
+}
+
 #[rstest]
 fn partitioned_uses_custom_scan(mut conn: PgConnection) {
     PartitionedTable::setup().execute(&mut conn);
@@ -1157,3 +1197,423 @@ fn expression_with_options(mut conn: PgConnection) {
     assert_eq!(rows[1], ("ctid".into(), "U64".into()));
     assert_eq!(rows[2], ("id".into(), "I64".into()));
 }
+
+#[rstest]
+fn alter_index_add_aliased_field(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, name TEXT);
+    INSERT INTO products (name) VALUES ('running shoes'), ('hiking boots');
+    CREATE INDEX products_index ON products
+    USING bm25 (id, name)
+    WITH (key_field='id', text_fields='{"name": {}}');
+    "#
+    .execute(&mut conn);
+
+    r#"
+    ALTER INDEX products_index SET (text_fields='{
+        "name": {},
+        "name_ngram": {"tokenizer": {"type": "ngram", "min_gram": 3, "max_gram": 3, "prefix_only": false}, "column": "name"}
+    }')"#
+        .execute(&mut conn);
+
+    let rows: Vec<(String, String)> =
+        "SELECT name, field_type FROM paradedb.schema('products_index') ORDER BY name"
+            .fetch(&mut conn);
+    assert!(rows.contains(&("name_ngram".into(), "Str".into())));
+
+    // existing rows only gain the new field once they're rewritten
+    "INSERT INTO products (name) VALUES ('trail runners')".execute(&mut conn);
+    let ids: Vec<(i32,)> =
+        "SELECT id FROM products WHERE id @@@ paradedb.term('name_ngram', 'rai') ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(ids, vec![(3,)]);
+
+    "UPDATE products SET name = 'trail shoes' WHERE id = 1".execute(&mut conn);
+    let ids: Vec<(i32,)> =
+        "SELECT id FROM products WHERE id @@@ paradedb.term('name_ngram', 'rai') ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(ids, vec![(1,), (3,)]);
+
+    "REINDEX INDEX products_index".execute(&mut conn);
+    let ids: Vec<(i32,)> =
+        "SELECT id FROM products WHERE id @@@ paradedb.term('name_ngram', 'boo') ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(ids, vec![(2,)]);
+}
+
+#[rstest]
+fn index_field_sizes(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE field_sizes (id serial8, description text, rating int);
+        INSERT INTO field_sizes (description, rating)
+            SELECT repeat('lorem ipsum dolor sit amet ' || x, 20), x % 5 FROM generate_series(1, 1000) x;
+        CREATE INDEX idxfield_sizes ON field_sizes USING bm25 (id, description, rating) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let rows: Vec<(Option<String>, i64, i64, i64)> = r#"
+        SELECT field_name, total_bytes, postings_bytes, fast_fields_bytes
+        FROM paradedb.index_field_sizes('idxfield_sizes')
+    "#
+    .fetch(&mut conn);
+
+    // the long text field is the biggest one, and rows are sorted largest first
+    let (_, total, postings, _) = rows
+        .iter()
+        .find(|(name, ..)| name.as_deref() == Some("description"))
+        .unwrap();
+    assert!(*total > 0 && *postings > 0);
+    let (_, rating_total, _, rating_fast) = rows
+        .iter()
+        .find(|(name, ..)| name.as_deref() == Some("rating"))
+        .unwrap();
+    assert!(*rating_fast > 0);
+    assert!(total > rating_total);
+    assert!(rows.windows(2).all(|w| w[0].1 >= w[1].1));
+
+    // and the document store gets a row of its own
+    assert!(rows.iter().any(|(name, ..)| name.is_none()));
+}
+
+#[rstest]
+fn per_field_storage_toggles(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE storage_toggles (id serial8, description text, sku text, rating int, created_at timestamp);
+        INSERT INTO storage_toggles (description, sku, rating, created_at)
+            VALUES ('red running shoes', 'SKU-1', 4, now()), ('blue boots', 'SKU-2', 2, now());
+        CREATE INDEX storage_toggles_idx ON storage_toggles
+        USING bm25 (id, description, sku, rating, created_at)
+        WITH (
+            key_field = 'id',
+            text_fields = '{
+                "description": {"stored": true},
+                "sku": {"tokenizer": {"type": "keyword"}, "fieldnorms": false, "record": "basic"}
+            }',
+            numeric_fields = '{"rating": {"indexed": false, "stored": true}}',
+            datetime_fields = '{"created_at": {"fast": false}}'
+        );
+    "#
+    .execute(&mut conn);
+
+    let rows: Vec<(String, bool, bool, bool, bool, Option<String>)> = r#"
+        SELECT name, stored, indexed, fast, fieldnorms, record
+        FROM paradedb.schema('storage_toggles_idx')
+        WHERE name IN ('description', 'sku', 'rating', 'created_at')
+    "#
+    .fetch(&mut conn);
+
+    assert_eq!(
+        rows,
+        vec![
+            ("created_at".into(), false, true, false, false, None),
+            (
+                "description".into(),
+                true,
+                true,
+                false,
+                true,
+                Some("position".into())
+            ),
+            ("rating".into(), true, false, true, false, None),
+            ("sku".into(), false, true, true, false, Some("basic".into())),
+        ]
+    );
+
+    let rows: Vec<(i64,)> =
+        "SELECT id FROM storage_toggles WHERE description @@@ 'shoes' AND sku @@@ 'SKU-1'"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+}
+
+#[rstest]
+fn upgrade_index_in_current_format(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE upgrade_me (id serial8, description text);
+        INSERT INTO upgrade_me (description) VALUES ('red shoes'), ('blue socks');
+        CREATE INDEX upgrade_me_idx ON upgrade_me USING bm25 (id, description) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    // a newly created index is already in the current format, so there's nothing to migrate
+    let rows: Vec<(String, i32, i32)> =
+        "SELECT * FROM paradedb.upgrade_index('upgrade_me_idx')".fetch(&mut conn);
+    assert_eq!(rows, vec![("upgrade_me_idx".into(), 1, 1)]);
+
+    let rows: Vec<(i64,)> =
+        "SELECT id FROM upgrade_me WHERE description @@@ 'shoes'".fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+}
+
+#[rstest]
+fn alter_index_set_online_options(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE online_options (id serial8, description text);
+        INSERT INTO online_options (description) VALUES ('red shoes'), ('blue socks');
+        CREATE INDEX online_options_idx ON online_options USING bm25 (id, description) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    // options that don't change how documents are tokenized don't block searches or writes
+    "BEGIN".execute(&mut conn);
+    "ALTER INDEX online_options_idx SET (min_merge_count = 4, insert_work_mem = 65536, docstore_compression = 'none')"
+        .execute(&mut conn);
+    let modes: Vec<(String,)> =
+        "SELECT mode FROM pg_locks WHERE relation = 'online_options_idx'::regclass AND pid = pg_backend_pid()"
+            .fetch(&mut conn);
+    assert_eq!(modes, vec![("ShareUpdateExclusiveLock".into(),)]);
+    "COMMIT".execute(&mut conn);
+
+    // while the fields still take an exclusive lock
+    "BEGIN".execute(&mut conn);
+    r#"ALTER INDEX online_options_idx SET (text_fields = '{"description": {}}')"#
+        .execute(&mut conn);
+    let modes: Vec<(String,)> =
+        "SELECT mode FROM pg_locks WHERE relation = 'online_options_idx'::regclass AND pid = pg_backend_pid()"
+            .fetch(&mut conn);
+    assert_eq!(modes, vec![("AccessExclusiveLock".into(),)]);
+    "COMMIT".execute(&mut conn);
+
+    // the new segments are uncompressed, and old and new are searched alike
+    "INSERT INTO online_options (description) VALUES ('green shoes')".execute(&mut conn);
+    let rows: Vec<(i64,)> =
+        "SELECT id FROM online_options WHERE description @@@ 'shoes' ORDER BY id".fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (3,)]);
+
+    let result = "ALTER INDEX online_options_idx SET (docstore_compression = 'brotli')"
+        .execute_result(&mut conn);
+    assert!(result.is_err());
+
+    // as does switching to zstd at a level of its own
+    "BEGIN".execute(&mut conn);
+    "ALTER INDEX online_options_idx SET (docstore_compression = 'zstd', docstore_compression_level = 9)"
+        .execute(&mut conn);
+    let modes: Vec<(String,)> =
+        "SELECT mode FROM pg_locks WHERE relation = 'online_options_idx'::regclass AND pid = pg_backend_pid()"
+            .fetch(&mut conn);
+    assert_eq!(modes, vec![("ShareUpdateExclusiveLock".into(),)]);
+    "COMMIT".execute(&mut conn);
+
+    "INSERT INTO online_options (description) VALUES ('yellow shoes')".execute(&mut conn);
+    let rows: Vec<(i64,)> =
+        "SELECT id FROM online_options WHERE description @@@ 'shoes' ORDER BY id".fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (3,), (4,)]);
+}
+
+#[rstest]
+fn docstore_compression_zstd(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE zstd_docs (id serial8, description text);
+        INSERT INTO zstd_docs (description) VALUES ('red shoes'), ('blue socks');
+        CREATE INDEX zstd_docs_idx ON zstd_docs USING bm25 (id, description)
+        WITH (
+            key_field = 'id',
+            text_fields = '{"description": {"stored": true}}',
+            docstore_compression = 'zstd',
+            docstore_compression_level = 9
+        );
+    "#
+    .execute(&mut conn);
+
+    let rows: Vec<(i64,)> =
+        "SELECT id FROM zstd_docs WHERE description @@@ 'shoes'".fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+
+    // a level is only meaningful for zstd
+    let result =
+        "ALTER INDEX zstd_docs_idx SET (docstore_compression = 'lz4')".execute_result(&mut conn);
+    assert!(result.is_err());
+    let result =
+        "ALTER INDEX zstd_docs_idx SET (docstore_compression_level = 23)".execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn alter_index_reset_docstore_compression(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE reset_docs (id serial8, description text);
+        CREATE INDEX reset_docs_idx ON reset_docs USING bm25 (id, description)
+        WITH (
+            key_field = 'id',
+            text_fields = '{"description": {"stored": true}}',
+            docstore_compression = 'none'
+        );
+        INSERT INTO reset_docs (description) VALUES (repeat('red shoes ', 10000));
+        ALTER INDEX reset_docs_idx RESET (docstore_compression);
+        INSERT INTO reset_docs (description) VALUES (repeat('red shoes ', 10000));
+    "#
+    .execute(&mut conn);
+
+    // the segment written after the RESET is compressed with the default lz4 again
+    let sizes: Vec<(i64,)> =
+        "SELECT store_bytes::int8 FROM paradedb.index_info('reset_docs_idx') ORDER BY store_bytes"
+            .fetch(&mut conn);
+    assert_eq!(sizes.len(), 2);
+    assert!(sizes[0].0 * 10 < sizes[1].0, "{sizes:?}");
+
+    // a level can't be left behind without zstd, neither by SET nor by RESET
+    "ALTER INDEX reset_docs_idx SET (docstore_compression = 'zstd', docstore_compression_level = 9)"
+        .execute(&mut conn);
+    let result =
+        "ALTER INDEX reset_docs_idx RESET (docstore_compression)".execute_result(&mut conn);
+    assert!(result.is_err());
+    "ALTER INDEX reset_docs_idx RESET (docstore_compression, docstore_compression_level)"
+        .execute(&mut conn);
+    let result =
+        "ALTER INDEX reset_docs_idx SET (docstore_compression_level = 3)".execute_result(&mut conn);
+    assert!(result.is_err());
+
+    let rows: Vec<(i64,)> =
+        "SELECT id FROM reset_docs WHERE description @@@ 'shoes' ORDER BY id".fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (2,)]);
+}
+
+#[rstest]
+fn dump_and_create_index_from_config(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE configured (id serial8, description text, rating int);
+        INSERT INTO configured (description, rating) VALUES ('running shoes', 5), ('running socks', 1), ('hiking boots', 4);
+        CREATE INDEX configured_idx ON configured USING bm25 (id, description, (description || ' for sale'), rating)
+        WITH (
+            key_field = 'id',
+            text_fields = '{"description": {"tokenizer": {"type": "default", "stemmer": "English"}}}',
+            target_segment_count = 2
+        )
+        WHERE rating > 2;
+    "#
+    .execute(&mut conn);
+
+    let (config,) =
+        "SELECT paradedb.dump_index_config('configured_idx')".fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(config["index_name"], "configured_idx");
+    assert_eq!(config["table_schema"], "public");
+    assert_eq!(config["table_name"], "configured");
+    assert_eq!(config["columns"].as_array().unwrap().len(), 4);
+    assert_eq!(config["columns"][0], "id");
+    assert_eq!(config["where"], "rating > 2");
+    // options that are JSON are dumped as JSON
+    assert_eq!(
+        config["options"],
+        serde_json::json!({
+            "key_field": "id",
+            "text_fields": {"description": {"tokenizer": {"type": "default", "stemmer": "English"}}},
+            "target_segment_count": 2
+        })
+    );
+
+    // an index created from the config is the same index; the config is dollar-quoted b/c the
+    // expression's string has quotes of its own
+    "DROP INDEX configured_idx".execute(&mut conn);
+    let (name,) = format!("SELECT paradedb.create_index_from_config($config${config}$config$)")
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(name, "configured_idx");
+    let (recreated,) =
+        "SELECT paradedb.dump_index_config('configured_idx')".fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(recreated, config);
+
+    let rows: Vec<(i64,)> =
+        "SELECT id FROM configured WHERE description @@@ 'run' AND rating > 2 ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+}
+
+#[rstest]
+fn create_index_from_invalid_config(mut conn: PgConnection) {
+    "CREATE TABLE configured (id serial8, description text)".execute(&mut conn);
+
+    let err = r#"SELECT paradedb.create_index_from_config('{"index_name": "configured_idx", "table_schema": "public", "table_name": "configured", "columns": ["id"], "options": {}}')"#
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("must include a `key_field`"),
+        "{err}"
+    );
+
+    let err = r#"SELECT paradedb.create_index_from_config('{"index_name": "configured_idx", "table_name": "configured", "columns": ["id"]}')"#
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid index config"), "{err}");
+
+    "CREATE INDEX configured_btree ON configured (id)".execute(&mut conn);
+    let err = "SELECT paradedb.dump_index_config('configured_btree')"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("is not a BM25 index"), "{err}");
+}
+
+#[rstest]
+fn create_index_from_quickwit_config(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE app_logs (
+            id bigserial PRIMARY KEY,
+            timestamp timestamptz,
+            severity_text text,
+            body text,
+            attributes jsonb
+        );
+        INSERT INTO app_logs (timestamp, severity_text, body, attributes) VALUES
+            (now(), 'ERROR', 'connection refused by upstream', '{"service": "checkout"}'),
+            (now(), 'INFO', 'connecting to upstream', '{"service": "cart"}');
+    "#
+    .execute(&mut conn);
+
+    let (name,) = r#"SELECT paradedb.create_index_from_config($yaml$
+version: 0.8
+index_id: app-logs
+doc_mapping:
+  field_mappings:
+    - name: timestamp
+      type: datetime
+      fast: true
+    - name: severity_text
+      type: text
+      tokenizer: raw
+      fast:
+        normalizer: lowercase
+    - name: body
+      type: text
+      tokenizer: en_stem
+      record: position
+    - name: attributes
+      type: json
+      tokenizer: raw
+  timestamp_field: timestamp
+search_settings:
+  default_search_fields: [body]
+$yaml$, 'app_logs')"#
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(name, "app_logs_idx");
+
+    // the fields are mapped as Quickwit mapped them
+    let rows: Vec<(i64,)> =
+        "SELECT id FROM app_logs WHERE body @@@ 'connect' ORDER BY id".fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (2,)]);
+    let rows: Vec<(i64,)> =
+        "SELECT id FROM app_logs WHERE severity_text @@@ 'ERROR' ORDER BY id".fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+    let rows: Vec<(i64,)> =
+        "SELECT id FROM app_logs WHERE id @@@ paradedb.term('attributes.service', 'cart') ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(2,)]);
+
+    let (config,) =
+        "SELECT paradedb.dump_index_config('app_logs_idx')".fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(config["options"]["key_field"], "id");
+    assert_eq!(
+        config["options"]["text_fields"]["body"]["tokenizer"],
+        serde_json::json!({"type": "default", "stemmer": "English"})
+    );
+
+    // a JSON config names its table by its `index_id`
+    "DROP INDEX app_logs_idx".execute(&mut conn);
+    let err = r#"SELECT paradedb.create_index_from_config('{"index_id": "app-logs", "doc_mapping": {"field_mappings": []}}')"#
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("there is no table named `app-logs`"),
+        "{err}"
+    );
+    let (name,) = r#"SELECT paradedb.create_index_from_config('{"index_id": "app_logs", "doc_mapping": {"field_mappings": [{"name": "body", "type": "text"}]}}')"#
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(name, "app_logs_idx");
+}
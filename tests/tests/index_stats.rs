@@ -0,0 +1,115 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod fixtures;
+
+use fixtures::db::Query;
+use fixtures::*;
+use rstest::*;
+use sqlx::PgConnection;
+
+#[rstest]
+fn index_stats_count_searches(mut conn: PgConnection) {
+    // the statistics live in shared memory, which needs pg_search to be preloaded
+    let (preloaded,) = "SELECT current_setting('shared_preload_libraries') LIKE '%pg_search%'"
+        .fetch_one::<(bool,)>(&mut conn);
+    if !preloaded {
+        return;
+    }
+
+    r#"
+    CREATE TABLE stats_docs (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO stats_docs (body) SELECT 'document ' || x FROM generate_series(1, 10) x;
+    INSERT INTO stats_docs (body) VALUES ('a rare word');
+    CREATE INDEX stats_docs_idx ON stats_docs USING bm25 (id, body) WITH (key_field = 'id');
+    SELECT paradedb.index_stats_reset('stats_docs_idx');
+    "#
+    .execute(&mut conn);
+
+    for _ in 0..3 {
+        "SELECT id FROM stats_docs WHERE body @@@ 'document'".fetch::<(i32,)>(&mut conn);
+    }
+    "SELECT id FROM stats_docs WHERE body @@@ 'rare'".fetch::<(i32,)>(&mut conn);
+
+    let (queries, docs_scanned, has_mean, has_p95) = "
+        SELECT queries, docs_scanned, mean_time_ms IS NOT NULL, p95_time_ms >= mean_time_ms
+        FROM paradedb.index_stats WHERE index = 'stats_docs_idx'::regclass"
+        .fetch_one::<(i64, i64, bool, bool)>(&mut conn);
+    assert_eq!(queries, 4);
+    assert_eq!(docs_scanned, 31);
+    assert!(has_mean);
+    assert!(has_p95);
+
+    let (index_blocks, index_hit_ratio) = "
+        SELECT index_blocks_hit + index_blocks_read, index_hit_ratio
+        FROM paradedb.index_stats WHERE index = 'stats_docs_idx'::regclass"
+        .fetch_one::<(i64, f64)>(&mut conn);
+    assert!(index_blocks > 0);
+    assert!((0.0..=1.0).contains(&index_hit_ratio));
+
+    "SELECT paradedb.index_stats_reset('stats_docs_idx')".execute(&mut conn);
+    let rows = "SELECT queries FROM paradedb.index_stats WHERE index = 'stats_docs_idx'::regclass"
+        .fetch::<(i64,)>(&mut conn);
+    assert!(rows.is_empty());
+}
+
+#[rstest]
+fn metrics_export_index_stats(mut conn: PgConnection) {
+    let (preloaded,) = "SELECT current_setting('shared_preload_libraries') LIKE '%pg_search%'"
+        .fetch_one::<(bool,)>(&mut conn);
+    if !preloaded {
+        return;
+    }
+
+    r#"
+    CREATE TABLE metrics_docs (id SERIAL PRIMARY KEY, body TEXT);
+    CREATE INDEX metrics_docs_idx ON metrics_docs USING bm25 (id, body) WITH (key_field = 'id');
+    SELECT paradedb.index_stats_reset('metrics_docs_idx');
+    INSERT INTO metrics_docs (body) SELECT 'document ' || x FROM generate_series(1, 10) x;
+    "#
+    .execute(&mut conn);
+    for _ in 0..2 {
+        "SELECT id FROM metrics_docs WHERE body @@@ 'document'".fetch::<(i32,)>(&mut conn);
+    }
+
+    let metric = |name: &str, conn: &mut PgConnection| {
+        format!("SELECT value FROM paradedb.metrics() WHERE index = 'metrics_docs_idx' AND metric = '{name}' AND le IS NULL")
+            .fetch_one::<(f64,)>(conn)
+            .0
+    };
+    assert_eq!(metric("paradedb_queries_total", &mut conn), 2.0);
+    assert_eq!(
+        metric("paradedb_query_duration_seconds_count", &mut conn),
+        2.0
+    );
+    assert_eq!(metric("paradedb_docs_indexed_total", &mut conn), 10.0);
+    assert!(metric("paradedb_segments_written_total", &mut conn) >= 1.0);
+
+    let (infinite_bucket,) = "
+        SELECT value FROM paradedb.metrics()
+        WHERE index = 'metrics_docs_idx' AND metric = 'paradedb_query_duration_seconds_bucket'
+        AND le = 'Infinity'"
+        .fetch_one::<(f64,)>(&mut conn);
+    assert_eq!(infinite_bucket, 2.0);
+
+    let (text,) = "SELECT paradedb.metrics_text()".fetch_one::<(String,)>(&mut conn);
+    assert!(text.contains("# TYPE paradedb_query_duration_seconds histogram\n"));
+    assert!(text.contains(
+        "paradedb_query_duration_seconds_bucket{index=\"metrics_docs_idx\",le=\"+Inf\"} 2\n"
+    ));
+    assert!(text.contains("paradedb_queries_total{index=\"metrics_docs_idx\"} 2\n"));
+}
@@ -122,3 +122,59 @@ fn snippet_from_join(mut conn: PgConnection) -> Result<(), sqlx::Error> {
 
     Ok(())
 }
+
+#[rstest]
+fn top_n_by_score_through_join(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE docs (id bigint, body text);
+    CREATE TABLE tags (doc_id bigint, tag text);
+    INSERT INTO docs SELECT x, repeat('beer ', x % 5 + 1) || 'wine' FROM generate_series(1, 100) x;
+    -- only every third document has a tag, so the join needs more than LIMIT of the top docs
+    INSERT INTO tags SELECT x, 'tag' || x FROM generate_series(3, 100, 3) x;
+    CREATE INDEX idx_docs ON docs USING bm25 (id, body) WITH (key_field=id);
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT docs.id, tags.tag, paradedb.score(docs.id) FROM docs JOIN tags ON tags.doc_id = docs.id WHERE docs.body @@@ 'beer' ORDER BY paradedb.score(docs.id) DESC";
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query} LIMIT 5").fetch_one::<(Value,)>(&mut conn);
+    assert!(format!("{plan:?}").contains("TopNScanExecState"));
+
+    let expected = query.fetch::<(i64, String, f32)>(&mut conn);
+    let top = format!("{query} LIMIT 5").fetch::<(i64, String, f32)>(&mut conn);
+    assert_eq!(top.len(), 5);
+    assert_eq!(
+        top.iter().map(|(_, _, score)| *score).collect::<Vec<_>>(),
+        expected[..5]
+            .iter()
+            .map(|(_, _, score)| *score)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[rstest]
+fn search_restricted_to_join_keys(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE customers (id bigint, name text);
+    CREATE TABLE orders (id bigint, customer_id bigint, description text);
+    INSERT INTO customers SELECT x, CASE WHEN x % 10 = 0 THEN 'alice' ELSE 'bob' END FROM generate_series(1, 1000) x;
+    INSERT INTO orders SELECT x, x % 1000 + 1, 'shipped parcel' FROM generate_series(1, 10000) x;
+    CREATE INDEX idx_customers ON customers USING bm25 (id, name) WITH (key_field=id);
+    CREATE INDEX idx_orders ON orders USING bm25 (id, customer_id, description) WITH (key_field=id);
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT orders.id, customers.id FROM orders JOIN customers ON orders.customer_id = customers.id WHERE orders.description @@@ 'shipped' AND customers.name @@@ 'alice' ORDER BY orders.id";
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    assert!(format!("{plan:?}").contains("join_keys"));
+    let restricted = query.fetch::<(i64, i64)>(&mut conn);
+    assert_eq!(restricted.len(), 1000);
+
+    "SET paradedb.max_join_keys = 0".execute(&mut conn);
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    assert!(!format!("{plan:?}").contains("join_keys"));
+    assert_eq!(query.fetch::<(i64, i64)>(&mut conn), restricted);
+
+    // with more keys than allowed, every order is searched for
+    "SET paradedb.max_join_keys = 10".execute(&mut conn);
+    assert_eq!(query.fetch::<(i64, i64)>(&mut conn), restricted);
+}
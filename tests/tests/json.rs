@@ -65,3 +65,55 @@ fn simple_jsonb_string_array_crash(mut conn: PgConnection) {
     "#
     .execute(&mut conn);
 }
+
+#[rstest]
+fn json_include_exclude_paths(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE events (
+        id serial8,
+        metadata jsonb
+    );
+
+    INSERT INTO events (metadata) VALUES
+        ('{"user": {"name": "alice", "password": "hunter"}, "event": "login", "raw": "alice"}'),
+        ('{"user": {"name": "bob", "tokens": [{"password": "alice"}]}, "event": "logout"}');
+
+    CREATE INDEX events_idx ON events
+    USING bm25 (id, metadata)
+    WITH (
+        key_field = 'id',
+        json_fields = '{"metadata": {"include_paths": ["user.*", "event"], "exclude_paths": ["**.password"]}}'
+    );
+    "#
+    .execute(&mut conn);
+
+    let count = |field: &str, value: &str, conn: &mut PgConnection| {
+        format!("SELECT COUNT(*) FROM events WHERE id @@@ paradedb.term('{field}', '{value}')")
+            .fetch_one::<(i64,)>(conn)
+            .0
+    };
+
+    assert_eq!(count("metadata.user.name", "alice", &mut conn), 1);
+    assert_eq!(count("metadata.event", "logout", &mut conn), 1);
+    assert_eq!(count("metadata.raw", "alice", &mut conn), 0);
+    assert_eq!(count("metadata.user.password", "hunter", &mut conn), 0);
+    assert_eq!(
+        count("metadata.user.tokens.password", "alice", &mut conn),
+        0
+    );
+
+    // rows inserted after the index is built are filtered the same way
+    r#"INSERT INTO events (metadata) VALUES ('{"user": {"password": "hunter"}, "raw": "carol"}')"#
+        .execute(&mut conn);
+    assert_eq!(count("metadata.user.password", "hunter", &mut conn), 0);
+    assert_eq!(count("metadata.raw", "carol", &mut conn), 0);
+
+    let error = r#"
+    CREATE INDEX events_bad_idx ON events
+    USING bm25 (id, metadata)
+    WITH (key_field = 'id', json_fields = '{"metadata": {"include_paths": ["user..name"]}}');
+    "#
+    .execute_result(&mut conn)
+    .unwrap_err();
+    assert!(error.to_string().contains("empty key"), "{error}");
+}
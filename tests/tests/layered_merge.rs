@@ -66,3 +66,135 @@ fn merge_with_no_positions(mut conn: PgConnection) {
         "select count(*) from paradedb.index_info('idxtest')".fetch_one::<(i64,)>(&mut conn);
     assert_eq!(count, 2);
 }
+
+#[rstest]
+fn force_merge_to_segment_count(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE force_merge (id bigint);
+        CREATE INDEX idxforce_merge ON force_merge USING bm25(id) WITH (key_field='id', layer_sizes = '0', background_layer_sizes = '0');
+    "#
+    .execute(&mut conn);
+
+    for _ in 0..8 {
+        "insert into force_merge select x from generate_series(1, 100) x;".execute(&mut conn);
+    }
+    let (nsegments,) = "select count(*) from paradedb.index_info('idxforce_merge');"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(nsegments, 8);
+
+    let (new_segments, merged_segments) =
+        "select * from paradedb.force_merge('idxforce_merge', 2);"
+            .fetch_one::<(i64, i64)>(&mut conn);
+    assert_eq!((new_segments, merged_segments), (2, 8));
+
+    let (nsegments, ndocs) =
+        "select count(*), sum(num_docs)::bigint from paradedb.index_info('idxforce_merge');"
+            .fetch_one::<(i64, i64)>(&mut conn);
+    assert_eq!((nsegments, ndocs), (2, 800));
+
+    // already at the target, so there's nothing to merge
+    let (new_segments, merged_segments) =
+        "select * from paradedb.force_merge('idxforce_merge', 2);"
+            .fetch_one::<(i64, i64)>(&mut conn);
+    assert_eq!((new_segments, merged_segments), (0, 0));
+}
+
+#[rstest]
+fn force_merge_deleted_segments(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE force_merge (id bigint);
+        CREATE INDEX idxforce_merge ON force_merge USING bm25(id) WITH (key_field='id', layer_sizes = '0', background_layer_sizes = '0');
+        INSERT INTO force_merge SELECT x FROM generate_series(1, 100) x;
+        INSERT INTO force_merge SELECT x FROM generate_series(101, 200) x;
+        DELETE FROM force_merge WHERE id > 150;
+        VACUUM force_merge;
+    "#
+    .execute(&mut conn);
+
+    // only the second segment has more than 10% of its documents deleted, and it's rewritten on its own
+    let (new_segments, merged_segments) =
+        "select * from paradedb.force_merge('idxforce_merge', 1, 10);"
+            .fetch_one::<(i64, i64)>(&mut conn);
+    assert_eq!((new_segments, merged_segments), (1, 1));
+
+    let (nsegments, ndeleted) =
+        "select count(*), sum(num_deleted)::bigint from paradedb.index_info('idxforce_merge');"
+            .fetch_one::<(i64, i64)>(&mut conn);
+    assert_eq!((nsegments, ndeleted), (2, 0));
+
+    let result =
+        "select * from paradedb.force_merge('idxforce_merge', 0);".execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn vacuum_reclaims_deleted_docs(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE vacuum_reclaim (id bigint);
+        CREATE INDEX idxvacuum_reclaim ON vacuum_reclaim USING bm25(id)
+        WITH (key_field='id', layer_sizes = '0', background_layer_sizes = '0', vacuum_deleted_percent = 50);
+        INSERT INTO vacuum_reclaim SELECT x FROM generate_series(1, 100) x;
+        INSERT INTO vacuum_reclaim SELECT x FROM generate_series(101, 200) x;
+        DELETE FROM vacuum_reclaim WHERE id <= 30 OR id > 120;
+    "#
+    .execute(&mut conn);
+
+    // deletes aren't counted until VACUUM finds them
+    let rows: Vec<(String, i64, i64, f64, i64)> =
+        "SELECT * FROM paradedb.index_dead_docs('idxvacuum_reclaim')".fetch(&mut conn);
+    assert_eq!(rows, vec![("idxvacuum_reclaim".into(), 200, 0, 0.0, 0)]);
+
+    // then only the second segment, with 80% of its documents deleted, is rewritten
+    "VACUUM vacuum_reclaim".execute(&mut conn);
+    let rows: Vec<(String, i64, i64, f64, i64)> =
+        "SELECT * FROM paradedb.index_dead_docs('idxvacuum_reclaim')".fetch_retry(
+            &mut conn,
+            30,
+            100,
+            |rows| rows.first().is_some_and(|row| row.2 == 30),
+        );
+    assert_eq!(rows, vec![("idxvacuum_reclaim".into(), 90, 30, 25.0, 0)]);
+
+    let (count,) = "SELECT count(*) FROM vacuum_reclaim WHERE id @@@ paradedb.all()"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 90);
+}
+
+#[rstest]
+fn bulk_load_mode_creates_one_segment(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE bulk_load (id bigint, message text);
+        CREATE INDEX idxbulk_load ON bulk_load USING bm25 (id, message) WITH (key_field = 'id', layer_sizes = '0', background_layer_sizes = '0');
+    "#
+    .execute(&mut conn);
+
+    "BEGIN".execute(&mut conn);
+    "SET LOCAL paradedb.bulk_load_mode = true".execute(&mut conn);
+    for i in 0..10 {
+        format!(
+            "INSERT INTO bulk_load SELECT x, 'message ' || x FROM generate_series({}, {}) x",
+            i * 10,
+            i * 10 + 9
+        )
+        .execute(&mut conn);
+    }
+    "COMMIT".execute(&mut conn);
+
+    let (nsegments,) =
+        "SELECT count(*) FROM paradedb.index_info('idxbulk_load')".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(nsegments, 1);
+    let (count,) =
+        "SELECT count(*) FROM bulk_load WHERE message @@@ 'message'".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 100);
+
+    // an aborted transaction's buffered documents are discarded along with it
+    "BEGIN".execute(&mut conn);
+    "SET LOCAL paradedb.bulk_load_mode = true".execute(&mut conn);
+    "INSERT INTO bulk_load SELECT x, 'message ' || x FROM generate_series(100, 199) x"
+        .execute(&mut conn);
+    "ROLLBACK".execute(&mut conn);
+
+    let (nsegments,) =
+        "SELECT count(*) FROM paradedb.index_info('idxbulk_load')".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(nsegments, 1);
+}
@@ -0,0 +1,96 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod fixtures;
+
+use fixtures::*;
+use pretty_assertions::assert_eq;
+use rstest::*;
+use sqlx::PgConnection;
+
+#[rstest]
+fn maintain_merges_within_budget(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE maintained (id bigint);
+        CREATE INDEX idxmaintained ON maintained USING bm25(id) WITH (key_field='id', layer_sizes = '0', background_layer_sizes = '0');
+    "#
+    .execute(&mut conn);
+    for _ in 0..8 {
+        "INSERT INTO maintained SELECT x FROM generate_series(1, 100) x".execute(&mut conn);
+    }
+    // nothing has merged the segments, which `maintain()` can now merge
+    "ALTER INDEX idxmaintained SET (layer_sizes = '1mb')".execute(&mut conn);
+
+    // no merge fits in an I/O budget of one byte
+    let (merged_segments, finished) =
+        "SELECT merged_segments, finished FROM paradedb.maintain('idxmaintained', io_budget => 1)"
+            .fetch_one::<(i64, bool)>(&mut conn);
+    assert_eq!((merged_segments, finished), (0, false));
+    let (nsegments,) =
+        "SELECT count(*) FROM paradedb.index_info('idxmaintained')".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(nsegments, 8);
+
+    let (merged_segments, live_docs, finished) = "SELECT merged_segments, live_docs, finished FROM paradedb.maintain('idxmaintained', '10 seconds')"
+        .fetch_one::<(i64, i64, bool)>(&mut conn);
+    assert!(merged_segments > 0);
+    assert_eq!((live_docs, finished), (800, true));
+
+    let (nsegments, ndocs) =
+        "SELECT count(*), sum(num_docs)::bigint FROM paradedb.index_info('idxmaintained')"
+            .fetch_one::<(i64, i64)>(&mut conn);
+    assert!(nsegments < 8);
+    assert_eq!(ndocs, 800);
+
+    // the planner's estimate of the index's size is refreshed
+    let (reltuples,) = "SELECT reltuples::bigint FROM pg_class WHERE relname = 'idxmaintained'"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(reltuples, 800);
+
+    // and there's nothing left to do
+    let (merged_segments, rewritten_segments, finished) =
+        "SELECT merged_segments, rewritten_segments, finished FROM paradedb.maintain('idxmaintained')"
+            .fetch_one::<(i64, i64, bool)>(&mut conn);
+    assert_eq!(
+        (merged_segments, rewritten_segments, finished),
+        (0, 0, true)
+    );
+}
+
+#[rstest]
+fn maintain_rewrites_deleted_segments(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE maintained (id bigint);
+        CREATE INDEX idxmaintained ON maintained USING bm25(id) WITH (key_field='id', layer_sizes = '0', background_layer_sizes = '0');
+        INSERT INTO maintained SELECT x FROM generate_series(1, 100) x;
+        INSERT INTO maintained SELECT x FROM generate_series(101, 200) x;
+        DELETE FROM maintained WHERE id > 150;
+        VACUUM maintained;
+        ALTER INDEX idxmaintained SET (vacuum_deleted_percent = 10);
+    "#
+    .execute(&mut conn);
+
+    // only the second segment has more than 10% of its documents deleted
+    let (rewritten_segments, finished) =
+        "SELECT rewritten_segments, finished FROM paradedb.maintain('idxmaintained')"
+            .fetch_one::<(i64, bool)>(&mut conn);
+    assert_eq!((rewritten_segments, finished), (1, true));
+
+    let (nsegments, ndeleted) =
+        "SELECT count(*), sum(num_deleted)::bigint FROM paradedb.index_info('idxmaintained')"
+            .fetch_one::<(i64, i64)>(&mut conn);
+    assert_eq!((nsegments, ndeleted), (2, 0));
+}
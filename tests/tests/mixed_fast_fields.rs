@@ -256,3 +256,67 @@ fn test_complex_aggregation_with_mixed_fast_fields(mut conn: PgConnection) {
     // Reset setting
     "SET enable_indexscan = on;".execute(&mut conn);
 }
+
+// Find the first Custom Scan in the plan
+fn find_custom_scan(node: &Value) -> Option<&Value> {
+    if node.get("Custom Plan Provider").is_some() {
+        return Some(node);
+    }
+    if let Some(root) = node.get(0).and_then(|root| root.get("Plan")) {
+        return find_custom_scan(root);
+    }
+    node.get("Plans")?
+        .as_array()?
+        .iter()
+        .find_map(find_custom_scan)
+}
+
+#[rstest]
+fn test_top_n_returns_fast_fields_without_heap_fetches(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE covering (id SERIAL PRIMARY KEY, name TEXT, rating INT, body TEXT);
+        INSERT INTO covering (name, rating, body)
+        SELECT 'name ' || i, i % 10, 'body ' || i FROM generate_series(1, 100) i;
+        CREATE INDEX covering_idx ON covering USING bm25 (id, name, rating, body)
+        WITH (key_field = 'id', text_fields = '{"name": {"tokenizer": {"type": "raw"}}}');
+        VACUUM covering;
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT name, rating FROM covering WHERE body @@@ 'body' ORDER BY rating DESC, name LIMIT 5";
+    let (plan,) =
+        format!("EXPLAIN (ANALYZE, VERBOSE, FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    let scan = find_custom_scan(&plan).expect("plan should have a Custom Scan");
+    assert_eq!(
+        scan.get("Exec Method"),
+        Some(&Value::String("TopNScanExecState".into()))
+    );
+    // a row is either returned from the index, or fetched from the heap if its block isn't
+    // all-visible
+    let count = |key: &str| scan.get(key).and_then(Value::as_u64).unwrap();
+    assert_eq!(count("Virtual Tuples") + count("Heap Fetches"), 5);
+
+    let expected = vec![
+        ("name 19".to_string(), 9),
+        ("name 29".to_string(), 9),
+        ("name 39".to_string(), 9),
+        ("name 49".to_string(), 9),
+        ("name 59".to_string(), 9),
+    ];
+    assert_eq!(query.fetch::<(String, i32)>(&mut conn), expected);
+
+    // rows on blocks that are no longer all-visible are checked against the heap
+    "UPDATE covering SET rating = 0 WHERE name = 'name 19'".execute(&mut conn);
+    let rows = query.fetch::<(String, i32)>(&mut conn);
+    assert_eq!(rows.len(), 5);
+    assert!(rows
+        .iter()
+        .all(|(name, rating)| name != "name 19" && *rating == 9));
+
+    // columns that aren't fast fields still come from the heap
+    let query = "SELECT body FROM covering WHERE body @@@ 'body' ORDER BY rating DESC LIMIT 5";
+    let (plan,) =
+        format!("EXPLAIN (ANALYZE, VERBOSE, FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    let scan = find_custom_scan(&plan).expect("plan should have a Custom Scan");
+    assert_eq!(scan.get("Virtual Tuples").and_then(Value::as_u64), Some(0));
+}
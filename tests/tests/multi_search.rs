@@ -0,0 +1,99 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod fixtures;
+
+use fixtures::*;
+use pretty_assertions::assert_eq;
+use rstest::*;
+use sqlx::PgConnection;
+
+fn setup_tenants(conn: &mut PgConnection) {
+    r#"
+    CREATE TABLE tenant_a (id SERIAL PRIMARY KEY, description TEXT);
+    CREATE TABLE tenant_b (id SERIAL PRIMARY KEY, description TEXT);
+
+    INSERT INTO tenant_a (description) VALUES ('red shoes'), ('blue hat'), ('green hat'), ('black hat');
+    INSERT INTO tenant_b (description) VALUES ('red shoes'), ('white shoes'), ('running shoes'), ('blue socks');
+
+    CREATE INDEX tenant_a_idx ON tenant_a USING bm25 (id, description) WITH (key_field = 'id');
+    CREATE INDEX tenant_b_idx ON tenant_b USING bm25 (id, description) WITH (key_field = 'id');
+    "#
+    .execute(conn);
+}
+
+#[rstest]
+fn multi_search_global_top_k(mut conn: PgConnection) {
+    setup_tenants(&mut conn);
+
+    let rows: Vec<(String, String)> = r#"
+    SELECT hits.tableoid::regclass::text, coalesce(a.description, b.description)
+    FROM paradedb.multi_search(
+        ARRAY['tenant_a_idx', 'tenant_b_idx']::regclass[],
+        paradedb.match('description', 'shoes'),
+        "limit" => 3
+    ) hits
+    LEFT JOIN tenant_a a ON a.tableoid = hits.tableoid AND a.ctid = hits.ctid
+    LEFT JOIN tenant_b b ON b.tableoid = hits.tableoid AND b.ctid = hits.ctid
+    ORDER BY hits.score DESC, 1, 2
+    "#
+    .fetch(&mut conn);
+
+    // four rows match, but only the top three across both tables come back
+    assert_eq!(rows.len(), 3);
+    assert!(rows
+        .iter()
+        .all(|(_, description)| description.ends_with("shoes")));
+}
+
+#[rstest]
+fn multi_search_unified_scoring(mut conn: PgConnection) {
+    setup_tenants(&mut conn);
+
+    // "shoes" is rare in tenant_a and common in tenant_b, but the same document scores the same
+    // in both because the statistics of both indexes are combined
+    let scores: Vec<(f32,)> = r#"
+    SELECT score FROM paradedb.multi_search(
+        ARRAY['tenant_a_idx', 'tenant_b_idx']::regclass[],
+        paradedb.term('description', 'red')
+    )
+    "#
+    .fetch(&mut conn);
+    assert_eq!(scores.len(), 2);
+    assert_eq!(scores[0], scores[1]);
+
+    // deleted rows aren't returned
+    "DELETE FROM tenant_b WHERE description = 'red shoes'".execute(&mut conn);
+    let rows: Vec<(String,)> = r#"
+    SELECT tableoid::regclass::text FROM paradedb.multi_search(
+        ARRAY['tenant_a_idx', 'tenant_b_idx']::regclass[],
+        paradedb.term('description', 'red')
+    )
+    "#
+    .fetch(&mut conn);
+    assert_eq!(rows, vec![("tenant_a".into(),)]);
+
+    let result = r#"
+    SELECT * FROM paradedb.multi_search(
+        ARRAY['tenant_a_idx']::regclass[],
+        paradedb.all(),
+        "limit" => 0
+    )
+    "#
+    .execute_result(&mut conn);
+    assert!(result.is_err());
+}
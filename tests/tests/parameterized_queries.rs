@@ -161,3 +161,29 @@ fn test_issue2061(mut conn: PgConnection) {
         ]
     )
 }
+
+#[rstest]
+fn prepared_statement_with_query_cache(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE cached (id bigint NOT NULL PRIMARY KEY, value text);
+    INSERT INTO cached (id, value) SELECT x, 'value ' || x FROM generate_series(1, 100) x;
+    CREATE INDEX idxcached ON cached USING bm25 (id, value) WITH (key_field='id');
+    SET paradedb.query_cache_entries = 8;
+    PREPARE find AS SELECT id FROM cached WHERE value @@@ paradedb.regex('value', $1) ORDER BY id;
+    "#
+    .execute(&mut conn);
+
+    // executing with the same and with different parameters finds what the query asks for each
+    // time, whether or not its translation was cached
+    for _ in 0..6 {
+        let results = "EXECUTE find('4[0-2]')".fetch::<(i64,)>(&mut conn);
+        assert_eq!(results, vec![(40,), (41,), (42,)]);
+        let results = "EXECUTE find('9.')".fetch::<(i64,)>(&mut conn);
+        assert_eq!(results.len(), 10);
+    }
+
+    // and a new segment is searched with a new translation
+    "INSERT INTO cached (id, value) VALUES (101, 'value 42')".execute(&mut conn);
+    let results = "EXECUTE find('4[0-2]')".fetch::<(i64,)>(&mut conn);
+    assert_eq!(results, vec![(40,), (41,), (42,), (101,)]);
+}
@@ -1025,6 +1025,33 @@ mod pushdown_is_bool_operator {
             "NOT (field = FALSE) should only return TRUE rows, not NULL rows",
         );
 
+        // NOT (NULL AND TRUE) is NULL, so the NULL row doesn't match
+        test_boolean_condition(
+            &mut conn,
+            "NOT (bool_field = TRUE AND message @@@ 'beer')",
+            1,
+            &[Some(false)],
+            "NOT (field = TRUE AND ...) should only return FALSE rows, not NULL rows",
+        );
+
+        // nor does it match NOT (NULL OR FALSE)
+        test_boolean_condition(
+            &mut conn,
+            "NOT (bool_field = TRUE OR message @@@ 'wine')",
+            1,
+            &[Some(false)],
+            "NOT (field = TRUE OR ...) should only return FALSE rows, not NULL rows",
+        );
+
+        // but NOT (NULL AND FALSE) is TRUE
+        test_boolean_condition(
+            &mut conn,
+            "NOT (bool_field = TRUE AND message @@@ 'wine')",
+            3,
+            &[Some(true), Some(false), None],
+            "NOT (field = TRUE AND <false>) should return every row",
+        );
+
         // Test for whether comparison with NULL returns expected results
         // (These provide the reference behavior for the IS operators)
         {
@@ -1040,4 +1067,88 @@ mod pushdown_is_bool_operator {
             assert_eq!(None, results[0].1, "The row should have bool_field = NULL");
         }
     }
+
+    #[rstest]
+    fn test_boolean_search(mut conn: PgConnection) {
+        r#"
+        CREATE TABLE bool_search_test (
+            id serial8 not null primary key,
+            bool_field boolean,
+            fast_bool_field boolean
+        );
+        CREATE INDEX idx_bool_search_test ON bool_search_test
+        USING bm25 (id, bool_field, fast_bool_field)
+        WITH (key_field = 'id', boolean_fields = '{"fast_bool_field": {"indexed": false}}');
+        INSERT INTO bool_search_test (bool_field, fast_bool_field) VALUES
+            (true, true), (false, false), (NULL, NULL);
+        "#
+        .execute(&mut conn);
+
+        for (query, expected) in [
+            ("bool_field @@@ 'true'", vec![(1,)]),
+            ("bool_field @@@ 't'", vec![(1,)]),
+            ("bool_field @@@ 'no'", vec![(2,)]),
+            ("fast_bool_field @@@ 'true'", vec![(1,)]),
+            ("fast_bool_field @@@ 'off'", vec![(2,)]),
+            ("id @@@ paradedb.term('fast_bool_field', false)", vec![(2,)]),
+            (
+                "fast_bool_field IS NULL AND id @@@ paradedb.all()",
+                vec![(3,)],
+            ),
+            (
+                "NOT (fast_bool_field = TRUE) AND id @@@ paradedb.all()",
+                vec![(2,)],
+            ),
+        ] {
+            let rows: Vec<(i64,)> =
+                format!("SELECT id FROM bool_search_test WHERE {query} ORDER BY id")
+                    .fetch(&mut conn);
+            assert_eq!(rows, expected, "{query}");
+        }
+    }
+
+    /// `NOT` of a comparison with a NULL value is NULL, so negated comparisons shouldn't match the
+    /// rows whose field is NULL, whatever its type
+    #[rstest]
+    fn test_negated_comparisons_with_null_values(mut conn: PgConnection) {
+        r#"
+        CREATE TABLE null_comparison_test (
+            id serial8 not null primary key,
+            price float8,
+            category text,
+            released date,
+            message text
+        );
+        CREATE INDEX idx_null_comparison_test ON null_comparison_test
+        USING bm25 (id, price, category, released, message)
+        WITH (key_field = 'id', text_fields = '{"category": {"tokenizer": {"type": "keyword"}}}');
+        INSERT INTO null_comparison_test (price, category, released, message) VALUES
+            (5, 'books', '2024-01-01', 'beer'),
+            (20, 'games', '2025-01-01', 'beer'),
+            (NULL, NULL, NULL, 'beer');
+        "#
+        .execute(&mut conn);
+
+        for (condition, expected) in [
+            ("NOT (price > 10)", vec![(1,)]),
+            ("NOT (price = 5)", vec![(2,)]),
+            ("NOT (price > 10 OR price = 5)", vec![]),
+            ("NOT (price > 10 AND message @@@ 'beer')", vec![(1,)]),
+            ("NOT (category = 'games')", vec![(1,)]),
+            ("NOT (category IN ('books', 'toys'))", vec![(2,)]),
+            ("NOT (released >= '2025-01-01')", vec![(1,)]),
+            ("NOT (released < '2025-01-01' AND price < 10)", vec![(2,)]),
+            // but a search is never NULL, so its negation matches every row it doesn't
+            ("NOT (category @@@ 'games')", vec![(1,), (3,)]),
+        ] {
+            let sql = format!(
+                "SELECT id FROM null_comparison_test WHERE {condition} AND message @@@ 'beer' ORDER BY id"
+            );
+            let (plan,) = format!("EXPLAIN (FORMAT JSON) {sql}").fetch_one::<(Value,)>(&mut conn);
+            verify_custom_scan(&plan, condition);
+
+            let rows: Vec<(i64,)> = sql.fetch(&mut conn);
+            assert_eq!(rows, expected, "{condition}");
+        }
+    }
 }
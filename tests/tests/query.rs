@@ -999,6 +999,138 @@ fn match_query(mut conn: PgConnection) {
     assert_eq!(columns.id.len(), 0);
 }
 
+#[rstest]
+fn match_query_stopwords(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE quotes (id SERIAL PRIMARY KEY, quote TEXT);
+    INSERT INTO quotes (quote) VALUES ('to be or not to be'), ('today is not the day'), ('tomorrow');
+    CREATE INDEX quotes_idx ON quotes USING bm25 (id, quote) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    // every token of "to be" is an English stopword, so the tokenizer leaves nothing to match
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM quotes
+    WHERE id @@@ paradedb.match(
+        'quote',
+        'to be',
+        tokenizer => '{"type": "default", "stopwords_language": "English"}',
+        conjunction_mode => true
+    )
+    ORDER BY id
+    "#
+    .fetch(&mut conn);
+    assert_eq!(rows, vec![]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM quotes
+    WHERE id @@@ paradedb.match(
+        'quote',
+        'to be',
+        tokenizer => '{"type": "default", "stopwords_language": "English"}',
+        conjunction_mode => true,
+        stopwords => false
+    )
+    ORDER BY id
+    "#
+    .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+}
+
+#[rstest]
+fn phrase_without_positions(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE notes (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO notes (body) VALUES ('running shoes'), ('shoes for running');
+    CREATE INDEX notes_idx ON notes USING bm25 (id, body) WITH (
+        key_field = 'id',
+        text_fields = '{"body": {"record": "freq", "fieldnorms": false}}'
+    );
+    "#
+    .execute(&mut conn);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM notes WHERE id @@@ paradedb.match('body', 'running shoes', conjunction_mode => true)
+    ORDER BY id
+    "#
+    .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (2,)]);
+
+    for query in [
+        "paradedb.phrase('body', ARRAY['running', 'shoes'])",
+        "paradedb.phrase('body', 'running shoes')",
+        "paradedb.phrase_prefix('body', ARRAY['running', 'sh'])",
+    ] {
+        let err = format!("SELECT id FROM notes WHERE id @@@ {query}")
+            .fetch_result::<(i32,)>(&mut conn)
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("field 'body' does not record positions, which phrase queries need"),
+            "unexpected error for {query}: {err}"
+        );
+    }
+
+    // a phrase of one token is a term
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM notes WHERE id @@@ paradedb.phrase('body', 'running') ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (2,)]);
+}
+
+#[rstest]
+fn phrase_fallback(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE notes (id SERIAL PRIMARY KEY, body TEXT, title TEXT);
+    INSERT INTO notes (body, title) VALUES
+        ('running shoes', 'running shoes'),
+        ('shoes for running', 'shoes for running'),
+        ('running in red shoes', 'running in red shoes');
+    CREATE INDEX notes_idx ON notes USING bm25 (id, body, title) WITH (
+        key_field = 'id',
+        text_fields = '{
+            "body": {"record": "freq", "stored": true},
+            "title": {"record": "freq"}
+        }'
+    );
+    SET paradedb.phrase_fallback = true;
+    "#
+    .execute(&mut conn);
+
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM notes WHERE id @@@ paradedb.phrase('body', 'running shoes') ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM notes WHERE id @@@ paradedb.phrase('body', ARRAY['running', 'shoes'], slop => 2)
+    ORDER BY id
+    "#
+    .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (3,)]);
+
+    // without stored values, there's nothing to check the phrase against
+    let err = "SELECT id FROM notes WHERE id @@@ paradedb.phrase('title', 'running shoes')"
+        .fetch_result::<(i32,)>(&mut conn)
+        .unwrap_err();
+    assert!(
+        err.to_string().contains(
+            "paradedb.phrase_fallback can only check for phrases in text fields that are stored"
+        ),
+        "unexpected error: {err}"
+    );
+
+    "SET paradedb.phrase_fallback = false".execute(&mut conn);
+    let err = "SELECT id FROM notes WHERE id @@@ paradedb.phrase('body', 'running shoes')"
+        .fetch_result::<(i32,)>(&mut conn)
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("field 'body' does not record positions, which phrase queries need"),
+        "unexpected error: {err}"
+    );
+}
+
 #[rstest]
 fn parse_lenient(mut conn: PgConnection) {
     SimpleProductsTable::setup().execute(&mut conn);
@@ -1159,3 +1291,140 @@ async fn direct_prepared_statement_replanning_custom_scan(mut conn: PgConnection
         assert_eq!((score, id), (3.2668595, 2))
     }
 }
+
+#[rstest]
+fn element_query(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id serial PRIMARY KEY, tags text[], sizes int[]);
+    INSERT INTO products (tags, sizes) VALUES
+        (ARRAY['red shirt', 'large hat'], ARRAY[1, 2]),
+        (ARRAY['red hat', 'blue shirt'], ARRAY[3]),
+        (ARRAY[NULL, 'green hat', 'red hat'], NULL);
+
+    CREATE INDEX products_idx ON products
+    USING bm25 (id, tags, sizes)
+    WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let red_hat = "paradedb.boolean(must => ARRAY[paradedb.term('tags', 'red'), paradedb.term('tags', 'hat')])";
+
+    // the row as a whole matches all three
+    let rows: Vec<(i32,)> =
+        format!("SELECT id FROM products WHERE id @@@ {red_hat} ORDER BY id").fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (2,), (3,)]);
+
+    // but only these have a single element matching both terms
+    let rows: Vec<(i32,)> = format!(
+        "SELECT id FROM products WHERE id @@@ paradedb.element('tags', {red_hat}) ORDER BY id"
+    )
+    .fetch(&mut conn);
+    assert_eq!(rows, vec![(2,), (3,)]);
+
+    // negations are checked per element too
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM products
+    WHERE id @@@ paradedb.element('tags', paradedb.boolean(
+        must => ARRAY[paradedb.term('tags', 'hat')],
+        must_not => ARRAY[paradedb.term('tags', 'red')]
+    ))
+    ORDER BY id
+    "#
+    .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (3,)]);
+
+    // positions count from 1 and account for null elements
+    let rows: Vec<(i32, Vec<i32>)> = format!(
+        "SELECT id, paradedb.matching_elements('products_idx', 'tags', tags, {red_hat}) FROM products ORDER BY id"
+    )
+    .fetch(&mut conn);
+    assert_eq!(rows, vec![(1, vec![]), (2, vec![1]), (3, vec![3])]);
+
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM products WHERE id @@@ paradedb.element('sizes', paradedb.term('sizes', 3))"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(2,)]);
+
+    // new rows are matched the same way
+    "INSERT INTO products (tags) VALUES (ARRAY['hat', 'red'])".execute(&mut conn);
+    let (count,) =
+        format!("SELECT COUNT(*) FROM products WHERE id @@@ paradedb.element('tags', {red_hat})")
+            .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 2);
+
+    let error = "SELECT id FROM products WHERE id @@@ paradedb.element('id', paradedb.all())"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(error.to_string().contains("array field"), "{error}");
+}
+
+#[rstest]
+fn parse_lucene_syntax(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let mut ids = |query: &str| -> Vec<(i32,)> {
+        format!("SELECT id FROM paradedb.bm25_search WHERE id @@@ {query} ORDER BY id")
+            .fetch(&mut conn)
+    };
+    let lucene =
+        |query_string: &str| format!("paradedb.parse($${query_string}$$, syntax => 'lucene')");
+
+    assert_eq!(
+        ids(&lucene("+description:shoes -category:footwear keyboard")),
+        ids("paradedb.boolean(
+                must => ARRAY[paradedb.term('description', 'shoes')],
+                must_not => ARRAY[paradedb.term('category', 'footwear')]
+            )"),
+    );
+    assert_eq!(
+        ids(&lucene(
+            "description:keyboard OR description:shoes AND rating:[4 TO *]"
+        )),
+        ids("paradedb.boolean(must => ARRAY[
+                paradedb.term('description', 'shoes'),
+                paradedb.range('rating', int4range(4, NULL, '[)'))
+            ])"),
+    );
+    assert_eq!(
+        ids(&lucene("description:(running shoes)^2 NOT category:footwear")),
+        ids(
+            "paradedb.boolean(
+                should => ARRAY[paradedb.term('description', 'running'), paradedb.term('description', 'shoes')],
+                must_not => ARRAY[paradedb.term('category', 'footwear')]
+            )"
+        ),
+    );
+    assert_eq!(
+        ids(&lucene("description:shoez~1")),
+        ids("paradedb.fuzzy_term('description', 'shoez', distance => 1)"),
+    );
+    assert_eq!(
+        ids(&lucene(r#"description:"running shoes"~1"#)),
+        ids("paradedb.phrase('description', ARRAY['running', 'shoes'], slop => 1)"),
+    );
+    assert_eq!(
+        ids(&lucene("description:sho*")),
+        ids("paradedb.regex('description', 'sho.*')"),
+    );
+    // unfielded fuzzy terms are searched for in every text field
+    assert_eq!(
+        ids(&lucene("electornics~")),
+        ids("paradedb.boolean(should => ARRAY[
+                paradedb.fuzzy_term('description', 'electornics'),
+                paradedb.fuzzy_term('category', 'electornics')
+            ])"),
+    );
+
+    let error = format!(
+        "SELECT id FROM paradedb.bm25_search WHERE id @@@ {}",
+        lucene("(shoes")
+    )
+    .execute_result(&mut conn)
+    .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("expected `)` to close the group"),
+        "{error}"
+    );
+}
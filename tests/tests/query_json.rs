@@ -1242,3 +1242,108 @@ fn parse_error(mut conn: PgConnection) {
         }
     }
 }
+
+#[rstest]
+fn nested_boolean_scores(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let rows: Vec<(i32, f32)> = r#"
+    SELECT id, paradedb.score(id) FROM paradedb.bm25_search WHERE bm25_search @@@
+    '{
+        "boolean": {
+            "should": [
+                {"term": {"field": "description", "value": "shoes", "const_score": 1.0}},
+                {
+                    "boolean": {
+                        "must": [{"term": {"field": "description", "value": "keyboard", "const_score": 1.0}}],
+                        "must_not": [{"term": {"field": "description", "value": "shoes"}}],
+                        "boost": 2.0
+                    }
+                }
+            ]
+        }
+    }'::jsonb ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(1, 2.0), (2, 2.0), (3, 1.0), (4, 1.0), (5, 1.0)]);
+}
+
+#[rstest]
+fn nested_boolean_error(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+    let result = r#"
+    SELECT id FROM paradedb.bm25_search WHERE bm25_search @@@
+    '{
+        "boolean": {
+            "must": [
+                {"all": null},
+                {"boolean": {"should": [{"term": {"field": "description", "value": "shoes", "boost": "high"}}]}}
+            ]
+        }
+    }'::jsonb ORDER BY id"#
+        .fetch_result::<(i32,)>(&mut conn);
+
+    match result {
+        Err(err) => assert_eq!(
+            err.to_string(),
+            r#"error returned from database: error parsing search query input json at "boolean.must[1].boolean.should[0].term.boost": expected a non-negative number, got "high""#
+        ),
+        _ => panic!("a boost that isn't a number should be an error"),
+    }
+}
+
+#[rstest]
+fn validate_query(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let rows: Vec<(String, Option<String>, String)> = r#"
+    SELECT path, field, error FROM paradedb.validate_query('paradedb.bm25_search_bm25_index',
+    '{
+        "boolean": {
+            "must": [
+                {"term": {"field": "description", "value": "shoes"}},
+                {"term": {"field": "colour", "value": "red"}}
+            ],
+            "should": [
+                {"range": {"field": "rating", "lower_bound": {"included": 5}, "upper_bound": {"excluded": 1}}}
+            ]
+        }
+    }'::jsonb)"#
+        .fetch(&mut conn);
+    assert_eq!(
+        rows,
+        vec![
+            (
+                "boolean.must[1]".into(),
+                Some("colour".into()),
+                "field 'colour' is not part of the pg_search index".into()
+            ),
+            (
+                "boolean.should[0]".into(),
+                Some("rating".into()),
+                "the range is empty, as its lower bound isn't below its upper bound".into()
+            ),
+        ]
+    );
+
+    let rows: Vec<(String, Option<String>, String)> = r#"
+    SELECT path, field, error FROM paradedb.validate_query(
+        'paradedb.bm25_search_bm25_index',
+        paradedb.boolean(must => ARRAY[paradedb.term('description', 'shoes'), paradedb.all()])
+    )"#
+    .fetch(&mut conn);
+    assert_eq!(rows, vec![]);
+
+    // a query whose JSON is invalid is a row too
+    let rows: Vec<(String, Option<String>, String)> = r#"
+    SELECT path, field, error FROM paradedb.validate_query('paradedb.bm25_search_bm25_index',
+    '{"boolean": {"must": [{"term": {"field": "description", "value": "shoes", "boost": -1}}]}}'::jsonb)"#
+        .fetch(&mut conn);
+    assert_eq!(
+        rows,
+        vec![(
+            "boolean.must[0].term.boost".into(),
+            None,
+            "expected a non-negative number, got -1".into()
+        )]
+    );
+}
@@ -289,3 +289,128 @@ fn integer_bounds_coercion(mut conn: PgConnection) {
     assert_eq!(rows[0].1, 2222.2222);
     assert_eq!(rows[1].1, 3333.3333);
 }
+
+#[rstest]
+fn ip_range(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE access_logs (
+        id SERIAL PRIMARY KEY,
+        client_ip INET
+    );
+
+    INSERT INTO access_logs (client_ip) VALUES
+        ('10.0.0.1'),
+        ('10.255.255.255'),
+        ('11.0.0.1'),
+        ('192.168.1.10'),
+        ('192.168.2.10'),
+        ('2001:db8::1'),
+        ('2001:db9::1');
+
+    CREATE INDEX access_logs_idx ON access_logs
+    USING bm25 (id, client_ip)
+    WITH (key_field = 'id', inet_fields = '{"client_ip": {"fast": true}}');
+    "#
+    .execute(&mut conn);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM access_logs
+    WHERE id @@@ paradedb.ip_range('client_ip', '10.0.0.0/8')
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (2,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM access_logs
+    WHERE client_ip @@@ pdb.ip_range('192.168.1.0/24')
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(4,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM access_logs
+    WHERE id @@@ paradedb.ip_range('client_ip', '2001:db8::/32')
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(6,)]);
+
+    // an address without a prefix length matches only itself
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM access_logs
+    WHERE id @@@ paradedb.ip_range('client_ip', '11.0.0.1')
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(3,)]);
+
+    // term queries on inet fields survive the query's text roundtrip
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM access_logs
+    WHERE id @@@ paradedb.term('client_ip', '192.168.2.10'::inet)
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(5,)]);
+}
+
+#[rstest]
+fn numeric_scale_range(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (
+        id SERIAL PRIMARY KEY,
+        price NUMERIC
+    );
+
+    INSERT INTO products (price) VALUES
+        (0.1),
+        (0.2),
+        (0.3),
+        (19.99),
+        (20.00),
+        (49.995);
+
+    CREATE INDEX products_idx ON products
+    USING bm25 (id, price)
+    WITH (key_field = 'id', numeric_fields = '{"price": {"fast": true, "scale": 2}}');
+    "#
+    .execute(&mut conn);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM products
+    WHERE products @@@ paradedb.range(field => 'price', range => '[19.99,20.00]'::numrange)
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(4,), (5,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM products
+    WHERE products @@@ paradedb.term('price', 0.3::numeric)
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(3,)]);
+
+    // 49.995 is rounded to 50.00 when it's indexed
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM products
+    WHERE products @@@ paradedb.term('price', 50::numeric)
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(6,)]);
+
+    // aggregates over a scaled field are computed by Postgres from the heap values
+    let (sum,) = r#"
+    SELECT SUM(price)::text FROM products
+    WHERE products @@@ paradedb.range(field => 'price', range => '[0.1,0.3]'::numrange)"#
+        .fetch_one::<(String,)>(&mut conn);
+    assert_eq!(sum, "0.6");
+
+    let result = r#"
+    CREATE TABLE not_numeric (id SERIAL PRIMARY KEY, rating INTEGER);
+    CREATE INDEX not_numeric_idx ON not_numeric
+    USING bm25 (id, rating)
+    WITH (key_field = 'id', numeric_fields = '{"rating": {"scale": 2}}');
+    "#
+    .execute_result(&mut conn);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("`scale` can only be set for NUMERIC columns"));
+}
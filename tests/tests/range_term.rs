@@ -410,6 +410,63 @@ async fn range_term_intersects_tstzrange(mut conn: PgConnection) {
 }
 
 #[allow(clippy::too_many_arguments)]
+#[rstest]
+fn range_operator_pushdown(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE rentals (
+        id SERIAL PRIMARY KEY,
+        description TEXT,
+        available TSTZRANGE
+    );
+
+    INSERT INTO rentals (description, available) VALUES
+        ('beach house', '[2025-06-01 00:00:00+00, 2025-06-15 00:00:00+00)'),
+        ('beach cabin', '[2025-07-01 00:00:00+00, 2025-07-31 00:00:00+00)'),
+        ('mountain cabin', '[2025-06-10 00:00:00+00, 2025-06-20 00:00:00+00)'),
+        ('beach condo', NULL);
+
+    CREATE INDEX rentals_idx ON rentals
+    USING bm25 (id, description, available)
+    WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM rentals
+    WHERE description @@@ 'beach' AND available @> '2025-06-12 00:00:00+00'::timestamptz
+    ORDER BY id
+    "#
+    .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM rentals
+    WHERE description @@@ 'cabin' AND available && '[2025-06-14 00:00:00+00, 2025-07-02 00:00:00+00)'::tstzrange
+    ORDER BY id
+    "#
+    .fetch(&mut conn);
+    assert_eq!(rows, vec![(2,), (3,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM rentals
+    WHERE description @@@ 'beach' AND available <@ '[2025-05-01 00:00:00+00, 2025-07-01 00:00:00+00)'::tstzrange
+    ORDER BY id
+    "#
+    .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+
+    let (plan,) = r#"
+    EXPLAIN (FORMAT JSON)
+    SELECT id FROM rentals
+    WHERE description @@@ 'beach' AND available @> '2025-06-12 00:00:00+00'::timestamptz
+    "#
+    .fetch_one::<(serde_json::Value,)>(&mut conn);
+    assert!(
+        plan.to_string().contains("range_term"),
+        "expected the range filter to be pushed down: {plan:#?}"
+    );
+}
+
 fn execute_range_test<T>(
     conn: &mut PgConnection,
     relation: RangeRelation,
@@ -497,6 +554,10 @@ fn execute_range_test<T>(
                         }
                     };
 
+                    let result_pushdown: Vec<(i32,)> =
+                        pg_search_pushdown_query(&relation, &range, table, field, range_type)
+                            .fetch(conn);
+
                     println!(
                         "expected: {expected:?}, {result:?} {} {}",
                         postgres_contains_query(&range, table, field, range_type),
@@ -509,6 +570,11 @@ fn execute_range_test<T>(
                         "json query failed for range: {:?}",
                         range
                     );
+                    assert_eq!(
+                        expected, result_pushdown,
+                        "pushdown query failed for range: {:?}",
+                        range
+                    );
                 }
             }
         }
@@ -808,3 +874,27 @@ where
         ORDER BY delivery_id"
     )
 }
+
+fn pg_search_pushdown_query<T>(
+    relation: &RangeRelation,
+    range: &PgRange<T>,
+    table: &str,
+    field: &str,
+    range_type: &str,
+) -> String
+where
+    T: Debug + Display + Clone + PartialEq,
+{
+    let operator = match relation {
+        RangeRelation::Contains => "<@",
+        RangeRelation::Within => "@>",
+        RangeRelation::Intersects => "&&",
+    };
+
+    format!(
+        "
+        SELECT delivery_id FROM {table}
+        WHERE delivery_id @@@ paradedb.all() AND {field} {operator} '{range}'::{range_type}
+        ORDER BY delivery_id"
+    )
+}
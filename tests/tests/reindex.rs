@@ -269,3 +269,143 @@ async fn concurrent_index_creation(mut conn: PgConnection) -> Result<()> {
 
     Ok(())
 }
+
+#[rstest]
+async fn invalid_index_is_not_searched(mut conn: PgConnection) -> Result<()> {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    r#"CREATE INDEX CONCURRENTLY bm25_search_bm25_index_2 ON paradedb.bm25_search
+    USING bm25 (id, description)
+    WITH (
+        key_field='id',
+        text_fields='{"description": {"tokenizer": {"type": "en_stem"}}}'
+    )"#
+    .execute(&mut conn);
+
+    // the newest valid index is used, and it stems
+    let columns: SimpleProductsTableVec =
+        "SELECT * FROM paradedb.bm25_search WHERE id @@@ 'description:keyboards' ORDER BY id"
+            .fetch_collect(&mut conn);
+    assert_eq!(columns.id, vec![1, 2]);
+
+    // which is what a `CREATE INDEX CONCURRENTLY` that's still running, or that failed, looks like
+    "UPDATE pg_index SET indisvalid = false WHERE indexrelid = 'paradedb.bm25_search_bm25_index_2'::regclass"
+        .execute(&mut conn);
+
+    let columns: SimpleProductsTableVec =
+        "SELECT * FROM paradedb.bm25_search WHERE id @@@ 'description:keyboards' ORDER BY id"
+            .fetch_collect(&mut conn);
+    assert!(columns.id.is_empty());
+
+    Ok(())
+}
+
+#[rstest]
+fn validate_index_reports_problems(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE validate (id serial8, description text);
+        INSERT INTO validate (description) SELECT 'row ' || x FROM generate_series(1, 100) x;
+        CREATE INDEX idxvalidate ON validate USING bm25 (id, description) WITH (key_field = 'id');
+        UPDATE validate SET description = 'updated' WHERE id <= 10;
+        DELETE FROM validate WHERE id > 90;
+    "#
+    .execute(&mut conn);
+
+    // a healthy index, including HOT and non-HOT updates and deletes, has nothing to report
+    let problems: Vec<(String, String, Option<String>, String)> =
+        "SELECT * FROM paradedb.validate_index('idxvalidate')".fetch(&mut conn);
+    assert_eq!(problems, vec![]);
+
+    // rows inserted while the index is disabled aren't indexed, and are reported as such
+    r#"
+        UPDATE pg_index SET indisready = false WHERE indexrelid = 'idxvalidate'::regclass;
+        INSERT INTO validate (description) SELECT 'unindexed ' || x FROM generate_series(1, 5) x;
+        UPDATE pg_index SET indisready = true WHERE indexrelid = 'idxvalidate'::regclass;
+    "#
+    .execute(&mut conn);
+    let problems: Vec<(String, String, Option<String>, String)> =
+        "SELECT * FROM paradedb.validate_index('idxvalidate')".fetch(&mut conn);
+    assert_eq!(problems.len(), 5);
+    assert!(problems
+        .iter()
+        .all(|(index_name, check, _, _)| index_name == "idxvalidate" && check == "heap"));
+}
+
+#[rstest]
+fn index_in_tablespace(mut conn: PgConnection) {
+    "SET allow_in_place_tablespaces = true".execute(&mut conn);
+    "CREATE TABLESPACE bm25_tablespace LOCATION ''".execute(&mut conn);
+
+    r#"
+        CREATE TABLE tablespaced (id serial8, description text);
+        INSERT INTO tablespaced (description) SELECT 'row ' || x FROM generate_series(1, 100) x;
+        CREATE INDEX idxtablespaced ON tablespaced USING bm25 (id, description) WITH (key_field = 'id') TABLESPACE bm25_tablespace;
+    "#
+    .execute(&mut conn);
+
+    let tablespace = |conn: &mut PgConnection| {
+        "SELECT COALESCE(t.spcname, 'pg_default')::text FROM pg_class c LEFT JOIN pg_tablespace t ON t.oid = c.reltablespace WHERE c.relname = 'idxtablespaced'"
+            .fetch_one::<(String,)>(conn)
+            .0
+    };
+    let count = |conn: &mut PgConnection| {
+        "SELECT count(*) FROM tablespaced WHERE description @@@ 'row'"
+            .fetch_one::<(i64,)>(conn)
+            .0
+    };
+    assert_eq!(tablespace(&mut conn), "bm25_tablespace");
+    assert_eq!(count(&mut conn), 100);
+
+    // moving the index copies its segments along with it
+    "ALTER INDEX idxtablespaced SET TABLESPACE pg_default".execute(&mut conn);
+    "INSERT INTO tablespaced (description) VALUES ('row 101')".execute(&mut conn);
+    assert_eq!(tablespace(&mut conn), "pg_default");
+    assert_eq!(count(&mut conn), 101);
+
+    // as does rebuilding it concurrently
+    "REINDEX (TABLESPACE bm25_tablespace) INDEX CONCURRENTLY idxtablespaced".execute(&mut conn);
+    assert_eq!(tablespace(&mut conn), "bm25_tablespace");
+    assert_eq!(count(&mut conn), 101);
+
+    "DROP TABLE tablespaced".execute(&mut conn);
+    "DROP TABLESPACE bm25_tablespace".execute(&mut conn);
+}
+
+#[rstest]
+fn reindex_where_replaces_matching_rows(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE reindex_where (id serial8, category text, description text);
+        INSERT INTO reindex_where (category, description) SELECT CASE WHEN x % 2 = 0 THEN 'even' ELSE 'odd' END, 'row ' || x FROM generate_series(1, 100) x;
+        CREATE INDEX idxreindex_where ON reindex_where USING bm25 (id, category, description) WITH (key_field = 'id');
+        UPDATE reindex_where SET description = 'updated row' WHERE id <= 10;
+    "#
+    .execute(&mut conn);
+
+    // a field added to the index isn't populated for existing rows
+    r#"
+        ALTER INDEX idxreindex_where SET (text_fields = '{
+            "category": {}, "description": {},
+            "description_ngram": {"tokenizer": {"type": "ngram", "min_gram": 3, "max_gram": 3, "prefix_only": false}, "column": "description"}
+        }');
+    "#
+    .execute(&mut conn);
+    let count = |conn: &mut PgConnection| {
+        "SELECT count(*) FROM reindex_where WHERE id @@@ paradedb.term('description_ngram', 'row')"
+            .fetch_one::<(i64,)>(conn)
+            .0
+    };
+    assert_eq!(count(&mut conn), 0);
+
+    let (nreindexed,) = "SELECT paradedb.reindex_where('idxreindex_where', $$category = 'even'$$)"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(nreindexed, 50);
+    assert_eq!(count(&mut conn), 50);
+
+    // every row is still indexed exactly once, including those that were HOT-updated
+    let problems: Vec<(String, String, Option<String>, String)> =
+        "SELECT * FROM paradedb.validate_index('idxreindex_where')".fetch(&mut conn);
+    assert_eq!(problems, vec![]);
+    let (ncategory,) = "SELECT count(*) FROM reindex_where WHERE category @@@ 'even'"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(ncategory, 50);
+}
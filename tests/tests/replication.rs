@@ -413,3 +413,355 @@ async fn test_physical_streaming_replication() -> Result<()> {
 
     Ok(())
 }
+
+#[rstest]
+async fn test_bm25_index_after_failover() -> Result<()> {
+    let primary_config = "
+        listen_addresses = 'localhost'
+        wal_level = replica
+        max_wal_senders = 3
+        wal_keep_size = '160MB'
+        shared_preload_libraries = 'pg_search'
+    ";
+    let primary_pg_hba = "
+        host replication replicator 127.0.0.1/32 md5
+        host replication replicator ::1/128 md5
+    ";
+    let primary_postgres = EphemeralPostgres::new(Some(primary_config), Some(primary_pg_hba));
+    let mut primary_conn = primary_postgres.connection().await?;
+
+    "CREATE USER replicator WITH REPLICATION ENCRYPTED PASSWORD 'replicator_pass';"
+        .execute(&mut primary_conn);
+    "CREATE EXTENSION pg_search;".execute(&mut primary_conn);
+    "CREATE TABLE test_data (id SERIAL PRIMARY KEY, info TEXT);".execute(&mut primary_conn);
+    "INSERT INTO test_data (info) VALUES ('before the backup');".execute(&mut primary_conn);
+    "CREATE INDEX test_data_idx ON test_data USING bm25 (id, info) WITH (key_field = 'id');"
+        .execute(&mut primary_conn);
+
+    let standby_tempdir = TempDir::new().expect("Failed to create temp dir for standby");
+    std::fs::set_permissions(
+        standby_tempdir.path(),
+        std::fs::Permissions::from_mode(0o700),
+    )?;
+    let pg_basebackup = EphemeralPostgres::pg_basebackup_path();
+    let standby_tempdir = standby_tempdir.path();
+    let primary_port = primary_postgres.port;
+    run_cmd!(
+        $pg_basebackup
+        -D $standby_tempdir
+        -Fp -Xs -P -R
+        -h localhost
+        -U replicator
+        --port $primary_port
+        &> /dev/null
+    )
+    .expect("Failed to run pg_basebackup for standby setup");
+
+    let standby_postgres = EphemeralPostgres::new_from_initialized(
+        standby_tempdir,
+        Some("shared_preload_libraries = 'pg_search'"),
+        None,
+    );
+
+    // these segments only reach the standby through streamed WAL
+    for i in 0..5 {
+        format!("INSERT INTO test_data (info) VALUES ('after the backup {i}');")
+            .execute(&mut primary_conn);
+    }
+    "DELETE FROM test_data WHERE info = 'after the backup 0';".execute(&mut primary_conn);
+    "VACUUM test_data;".execute(&mut primary_conn);
+    "SELECT pg_switch_wal();".execute(&mut primary_conn);
+
+    // wait for the standby to replay everything the primary wrote
+    let (primary_lsn,) =
+        "SELECT pg_current_wal_lsn()::text".fetch_one::<(String,)>(&mut primary_conn);
+    let mut standby_conn = standby_postgres.connection().await?;
+    format!("SELECT pg_last_wal_replay_lsn() >= '{primary_lsn}'::pg_lsn").fetch_retry::<(bool,)>(
+        &mut standby_conn,
+        60,
+        1000,
+        |result| result.first().is_some_and(|(caught_up,)| *caught_up),
+    );
+
+    let pg_ctl_path = primary_postgres.pg_ctl_path.clone();
+    let tempdir_path = primary_postgres.tempdir_path.clone();
+    run_cmd!($pg_ctl_path -D $tempdir_path stop &> /dev/null).unwrap();
+
+    let tempdir_path = standby_postgres.tempdir_path.clone();
+    let pg_ctl_path = standby_postgres.pg_ctl_path.clone();
+    run_cmd!($pg_ctl_path -D $tempdir_path promote -w &> /dev/null)
+        .expect("Failed to promote standby");
+    let mut standby_conn = standby_postgres.connection().await?;
+
+    let rows: Vec<(String,)> =
+        "SELECT info FROM test_data WHERE info @@@ 'backup' ORDER BY id".fetch(&mut standby_conn);
+    assert_eq!(
+        rows.into_iter().map(|(info,)| info).collect::<Vec<_>>(),
+        vec![
+            "before the backup",
+            "after the backup 1",
+            "after the backup 2",
+            "after the backup 3",
+            "after the backup 4",
+        ]
+    );
+
+    // the promoted standby's index can be written to, and every segment file it needs is there
+    "INSERT INTO test_data (info) VALUES ('after the failover backup');".execute(&mut standby_conn);
+    let (count,) = "SELECT count(*) FROM test_data WHERE info @@@ 'backup'"
+        .fetch_one::<(i64,)>(&mut standby_conn);
+    assert_eq!(count, 6);
+    let problems: Vec<(String, String, Option<String>, String)> =
+        "SELECT * FROM paradedb.validate_index('test_data_idx')".fetch(&mut standby_conn);
+    assert_eq!(problems, vec![]);
+
+    Ok(())
+}
+
+#[rstest]
+async fn test_bm25_index_after_crash() -> Result<()> {
+    let postgres = EphemeralPostgres::new(Some("shared_preload_libraries = 'pg_search'"), None);
+    let mut conn = postgres.connection().await?;
+
+    "CREATE EXTENSION pg_search;".execute(&mut conn);
+    "CREATE TABLE test_data (id SERIAL PRIMARY KEY, info TEXT);".execute(&mut conn);
+    "CREATE INDEX test_data_idx ON test_data USING bm25 (id, info) WITH (key_field = 'id');"
+        .execute(&mut conn);
+    // from here on, the index's changes are only on disk in the WAL
+    "CHECKPOINT;".execute(&mut conn);
+    for i in 0..5 {
+        format!("INSERT INTO test_data (info) VALUES ('committed {i}');").execute(&mut conn);
+    }
+    drop(conn);
+
+    // an immediate shutdown doesn't flush shared buffers, so the index has to be recovered from WAL
+    let pg_ctl_path = postgres.pg_ctl_path.clone();
+    let tempdir_path = postgres.tempdir_path.clone();
+    run_cmd!($pg_ctl_path -D $tempdir_path stop -m immediate &> /dev/null).unwrap();
+    run_cmd!($pg_ctl_path -D $tempdir_path start -w &> /dev/null).unwrap();
+
+    let mut conn = postgres.connection().await?;
+    let (count,) =
+        "SELECT count(*) FROM test_data WHERE info @@@ 'committed'".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 5);
+    let problems: Vec<(String, String, Option<String>, String)> =
+        "SELECT * FROM paradedb.validate_index('test_data_idx')".fetch(&mut conn);
+    assert_eq!(problems, vec![]);
+
+    Ok(())
+}
+
+/// How many bytes the test index grows by while rows are repeatedly written, deleted and vacuumed.
+fn index_growth(conn: &mut PgConnection) -> i64 {
+    let (before,) = "SELECT pg_relation_size('test_data_idx')".fetch_one::<(i64,)>(conn);
+    for _ in 0..5 {
+        "INSERT INTO test_data (info) SELECT 'churn ' || n FROM generate_series(1, 100) n;"
+            .execute(conn);
+        "DELETE FROM test_data WHERE info LIKE 'churn %';".execute(conn);
+        "VACUUM test_data;".execute(conn);
+    }
+    let (after,) = "SELECT pg_relation_size('test_data_idx')".fetch_one::<(i64,)>(conn);
+    after - before
+}
+
+#[rstest]
+async fn test_bm25_search_on_hot_standby() -> Result<()> {
+    let primary_config = "
+        listen_addresses = 'localhost'
+        wal_level = replica
+        max_wal_senders = 3
+        wal_keep_size = '160MB'
+        shared_preload_libraries = 'pg_search'
+    ";
+    let primary_pg_hba = "
+        host replication replicator 127.0.0.1/32 md5
+        host replication replicator ::1/128 md5
+    ";
+    let primary_postgres = EphemeralPostgres::new(Some(primary_config), Some(primary_pg_hba));
+    let mut primary_conn = primary_postgres.connection().await?;
+
+    "CREATE USER replicator WITH REPLICATION ENCRYPTED PASSWORD 'replicator_pass';"
+        .execute(&mut primary_conn);
+    "CREATE EXTENSION pg_search;".execute(&mut primary_conn);
+    "CREATE TABLE test_data (id SERIAL PRIMARY KEY, info TEXT);".execute(&mut primary_conn);
+    "INSERT INTO test_data (info) VALUES ('first');".execute(&mut primary_conn);
+    "CREATE INDEX test_data_idx ON test_data USING bm25 (id, info) WITH (key_field = 'id', standby_searches = true);"
+        .execute(&mut primary_conn);
+    "CREATE TABLE other_data (id SERIAL PRIMARY KEY, info TEXT);".execute(&mut primary_conn);
+    "INSERT INTO other_data (info) VALUES ('first');".execute(&mut primary_conn);
+    "CREATE INDEX other_data_idx ON other_data USING bm25 (id, info) WITH (key_field = 'id');"
+        .execute(&mut primary_conn);
+
+    let standby_tempdir = TempDir::new().expect("Failed to create temp dir for standby");
+    std::fs::set_permissions(
+        standby_tempdir.path(),
+        std::fs::Permissions::from_mode(0o700),
+    )?;
+    let pg_basebackup = EphemeralPostgres::pg_basebackup_path();
+    let standby_tempdir = standby_tempdir.path();
+    let primary_port = primary_postgres.port;
+    run_cmd!(
+        $pg_basebackup
+        -D $standby_tempdir
+        -Fp -Xs -P -R
+        -h localhost
+        -U replicator
+        --port $primary_port
+        &> /dev/null
+    )
+    .expect("Failed to run pg_basebackup for standby setup");
+
+    let standby_config = "
+        shared_preload_libraries = 'pg_search'
+        hot_standby = on
+        hot_standby_feedback = on
+    ";
+    let standby_postgres =
+        EphemeralPostgres::new_from_initialized(standby_tempdir, Some(standby_config), None);
+    let mut standby_conn = standby_postgres.connection().await?;
+
+    let rows: Vec<(String,)> = "SELECT info FROM test_data WHERE info @@@ 'first'".fetch_retry(
+        &mut standby_conn,
+        60,
+        1000,
+        |result| !result.is_empty(),
+    );
+    assert_eq!(rows, vec![("first".to_string(),)]);
+
+    // a search that's open while the primary merges away the segments it's reading keeps
+    // reading them, because the primary doesn't reuse their blocks
+    "BEGIN ISOLATION LEVEL REPEATABLE READ;".execute(&mut standby_conn);
+    let (count,) = "SELECT count(*) FROM test_data WHERE info @@@ 'first'"
+        .fetch_one::<(i64,)>(&mut standby_conn);
+    assert_eq!(count, 1);
+
+    // the primary only holds on to the blocks once the standby's feedback has reached it
+    let held: Vec<(String,)> =
+        "SELECT backend_xmin::text FROM pg_stat_replication WHERE backend_xmin IS NOT NULL"
+            .fetch_retry(&mut primary_conn, 60, 1000, |result| !result.is_empty());
+    let (held,) = held[0].clone();
+
+    for i in 0..10 {
+        format!("INSERT INTO test_data (info) VALUES ('second {i}');").execute(&mut primary_conn);
+    }
+    "DELETE FROM test_data WHERE info = 'first';".execute(&mut primary_conn);
+    // vacuum merges the segments, and garbage collects the ones that were merged
+    "VACUUM test_data;".execute(&mut primary_conn);
+    "VACUUM test_data;".execute(&mut primary_conn);
+    for i in 0..10 {
+        format!("INSERT INTO test_data (info) VALUES ('third {i}');").execute(&mut primary_conn);
+    }
+
+    let (count,) = "SELECT count(*) FROM test_data WHERE info @@@ 'first'"
+        .fetch_one::<(i64,)>(&mut standby_conn);
+    assert_eq!(count, 1);
+
+    // so the blocks freed meanwhile aren't recycled, and the index grows instead
+    let growth_while_held = index_growth(&mut primary_conn);
+    "COMMIT;".execute(&mut standby_conn);
+
+    // until the standby's snapshot is gone, after which they're reused
+    format!(
+        "SELECT backend_xmin::text FROM pg_stat_replication WHERE backend_xmin IS DISTINCT FROM '{held}'::xid"
+    )
+    .fetch_retry::<(String,)>(&mut primary_conn, 60, 1000, |result| !result.is_empty());
+    let growth_after = index_growth(&mut primary_conn);
+    assert!(
+        growth_after < growth_while_held,
+        "{growth_after} should be less than {growth_while_held}"
+    );
+
+    // and new searches see everything the primary has written since
+    let rows: Vec<(i64,)> = "SELECT count(*) FROM test_data WHERE info @@@ 'second OR third'"
+        .fetch_retry(&mut standby_conn, 60, 1000, |result| result == [(20,)]);
+    assert_eq!(rows, vec![(20,)]);
+    let (count,) = "SELECT count(*) FROM test_data WHERE info @@@ 'first'"
+        .fetch_one::<(i64,)>(&mut standby_conn);
+    assert_eq!(count, 0);
+
+    // an index without `standby_searches` may have its freed blocks reused under the standby
+    let err = "SELECT count(*) FROM other_data WHERE info @@@ 'first'"
+        .execute_result(&mut standby_conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("standby_searches"), "{err}");
+
+    // without feedback, searches are refused rather than risk reading reused blocks
+    "ALTER SYSTEM SET hot_standby_feedback = off;".execute(&mut standby_conn);
+    "SELECT pg_reload_conf();".execute(&mut standby_conn);
+    let mut standby_conn = standby_postgres.connection().await?;
+    let err = "SELECT count(*) FROM test_data WHERE info @@@ 'second'"
+        .execute_result(&mut standby_conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("hot_standby_feedback"), "{err}");
+
+    Ok(())
+}
+
+#[rstest]
+async fn test_bm25_index_definitions_logically_replicated() -> Result<()> {
+    let config = "
+        wal_level = logical
+        max_replication_slots = 4
+        max_wal_senders = 4
+        shared_preload_libraries = 'pg_search'
+    ";
+
+    let publisher = EphemeralPostgres::new(Some(config), None);
+    let mut publisher_conn = publisher.connection().await?;
+    let publisher_port = publisher.port;
+    let username = "SELECT CURRENT_USER"
+        .fetch_one::<(String,)>(&mut publisher_conn)
+        .0;
+
+    "CREATE EXTENSION pg_search;".execute(&mut publisher_conn);
+    "CREATE TABLE test_data (id SERIAL PRIMARY KEY, info TEXT);".execute(&mut publisher_conn);
+    "INSERT INTO test_data (info) VALUES ('first');".execute(&mut publisher_conn);
+    r#"CREATE INDEX test_data_idx ON test_data USING bm25 (id, info)
+    WITH (
+        key_field = 'id',
+        text_fields = '{"info": {"tokenizer": {"type": "default", "stemmer": "English"}}}'
+    );"#
+    .execute(&mut publisher_conn);
+    "CREATE PUBLICATION test_pub FOR TABLE test_data;".execute(&mut publisher_conn);
+    "SELECT paradedb.publish_index_definitions('test_pub');".execute(&mut publisher_conn);
+
+    let subscriber = EphemeralPostgres::new(Some(config), None);
+    let mut subscriber_conn = subscriber.connection().await?;
+    "CREATE EXTENSION pg_search;".execute(&mut subscriber_conn);
+    "CREATE TABLE test_data (id SERIAL PRIMARY KEY, info TEXT);".execute(&mut subscriber_conn);
+    format!(
+        "CREATE SUBSCRIPTION test_sub
+         CONNECTION 'host=localhost port={publisher_port} dbname=postgres user={username}'
+         PUBLICATION test_pub;"
+    )
+    .execute(&mut subscriber_conn);
+
+    // the index already existed when the table was published, and has the same configuration
+    let rows: Vec<(String,)> =
+        "SELECT pg_get_indexdef('test_data_idx'::regclass) FROM pg_class WHERE relname = 'test_data_idx'"
+            .fetch_retry(&mut subscriber_conn, 60, 1000, |result| !result.is_empty());
+    let (publisher_def,) = "SELECT pg_get_indexdef('test_data_idx'::regclass)"
+        .fetch_one::<(String,)>(&mut publisher_conn);
+    assert_eq!(rows, vec![(publisher_def,)]);
+
+    let rows: Vec<(i32,)> = "SELECT id FROM test_data WHERE info @@@ 'firsts'".fetch_retry(
+        &mut subscriber_conn,
+        60,
+        1000,
+        |result| !result.is_empty(),
+    );
+    assert_eq!(rows, vec![(1,)]);
+
+    // indexes created and dropped later follow
+    "DROP INDEX test_data_idx;".execute(&mut publisher_conn);
+    "CREATE INDEX test_data_new_idx ON test_data USING bm25 (id, info) WITH (key_field = 'id');"
+        .execute(&mut publisher_conn);
+    let rows: Vec<(String,)> =
+        "SELECT relname::text FROM pg_class WHERE relname LIKE 'test_data%idx' ORDER BY relname"
+            .fetch_retry(&mut subscriber_conn, 60, 1000, |result| {
+                result == [("test_data_new_idx".to_string(),)]
+            });
+    assert_eq!(rows, vec![("test_data_new_idx".to_string(),)]);
+
+    Ok(())
+}
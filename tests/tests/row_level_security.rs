@@ -0,0 +1,165 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+// Tests for row-level security policies on tables searched by our custom scans
+mod fixtures;
+
+use fixtures::*;
+use pretty_assertions::assert_eq;
+use rstest::*;
+use serde_json::Value;
+use sqlx::PgConnection;
+
+fn setup_tenant_documents(conn: &mut PgConnection) {
+    r#"
+    CREATE TABLE tenant_documents (
+        id SERIAL PRIMARY KEY,
+        tenant TEXT NOT NULL,
+        owner TEXT NOT NULL,
+        secret TEXT NOT NULL,
+        body TEXT NOT NULL
+    );
+    INSERT INTO tenant_documents (tenant, owner, secret, body)
+    SELECT CASE WHEN n <= 3 THEN 'a' ELSE 'b' END, 'nobody', 'secret ' || n, 'hello world'
+    FROM generate_series(1, 8) n;
+
+    CREATE INDEX tenant_documents_idx ON tenant_documents
+    USING bm25 (id, tenant, body)
+    WITH (
+        key_field = 'id',
+        text_fields = '{
+            "tenant": {"tokenizer": {"type": "keyword"}, "fast": true},
+            "body": {}
+        }'
+    );
+
+    DO $$
+    BEGIN
+        IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'rls_tenant') THEN
+            CREATE ROLE rls_tenant;
+        END IF;
+    END
+    $$;
+    GRANT SELECT ON tenant_documents TO rls_tenant;
+    ALTER TABLE tenant_documents ENABLE ROW LEVEL SECURITY;
+    "#
+    .execute(conn);
+}
+
+/// The `ParadeDB Scan` node of `plan`, if it has one.
+fn find_custom_scan(plan: &Value) -> Option<&Value> {
+    if plan["Custom Plan Provider"] == "ParadeDB Scan" {
+        return Some(plan);
+    }
+    plan["Plans"].as_array()?.iter().find_map(find_custom_scan)
+}
+
+#[rstest]
+fn policy_is_solved_for_each_execution(mut conn: PgConnection) {
+    setup_tenant_documents(&mut conn);
+    r#"
+    CREATE POLICY tenant_isolation ON tenant_documents
+    USING (tenant = current_setting('app.tenant'));
+
+    SET paradedb.enable_aggregate_custom_scan TO on;
+    SET plan_cache_mode TO force_generic_plan;
+    SET ROLE rls_tenant;
+    PREPARE tenant_count AS SELECT count(*) FROM tenant_documents WHERE body @@@ 'hello';
+    PREPARE tenant_ids AS SELECT id FROM tenant_documents WHERE body @@@ 'hello' ORDER BY id;
+    "#
+    .execute(&mut conn);
+
+    // the cached plan must not hold on to the tenant that was current when it was planned
+    for (tenant, expected) in [("a", 3), ("b", 5), ("a", 3), ("c", 0)] {
+        format!("SET app.tenant = '{tenant}'").execute(&mut conn);
+        let (count,) = "EXECUTE tenant_count".fetch_one::<(i64,)>(&mut conn);
+        assert_eq!(count, expected, "count for tenant {tenant}");
+        let ids = "EXECUTE tenant_ids".fetch::<(i32,)>(&mut conn);
+        assert_eq!(ids.len() as i64, expected, "rows for tenant {tenant}");
+    }
+
+    "RESET ROLE".execute(&mut conn);
+}
+
+#[rstest]
+fn leaky_filter_is_not_evaluated_before_policy(mut conn: PgConnection) {
+    setup_tenant_documents(&mut conn);
+    r#"
+    -- `owner` isn't indexed, so the policy has to be checked against the heap
+    CREATE POLICY owner_only ON tenant_documents USING (owner = current_user);
+
+    CREATE FUNCTION leaky_texteq(text, text) RETURNS bool
+    LANGUAGE plpgsql COST 0.0000001 AS $$
+    BEGIN
+        RAISE NOTICE 'saw %', $1;
+        RETURN $1 = $2;
+    END
+    $$;
+    CREATE OPERATOR === (LEFTARG = text, RIGHTARG = text, FUNCTION = leaky_texteq);
+    GRANT EXECUTE ON FUNCTION leaky_texteq(text, text) TO rls_tenant;
+
+    SET ROLE rls_tenant;
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT id FROM tenant_documents WHERE body @@@ 'hello' AND secret === 'secret 1'";
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    assert!(find_custom_scan(&plan[0]["Plan"]).is_none(), "{plan:#}");
+    assert!(plan.to_string().contains("==="), "{plan:#}");
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), vec![]);
+
+    // a leakproof filter can still be searched alongside the policy
+    let query = "SELECT id FROM tenant_documents WHERE body @@@ 'hello' AND secret = 'secret 1'";
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    let scan = find_custom_scan(&plan[0]["Plan"]).unwrap_or_else(|| panic!("{plan:#}"));
+    // the policy's column isn't indexed, so it's still checked against each row
+    assert!(
+        scan["Filter"]
+            .as_str()
+            .is_some_and(|filter| filter.contains("owner")),
+        "{scan:#}"
+    );
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), vec![]);
+
+    "RESET ROLE".execute(&mut conn);
+}
+
+#[rstest]
+fn functions_that_read_the_index_alone_refuse_policies(mut conn: PgConnection) {
+    setup_tenant_documents(&mut conn);
+    r#"
+    CREATE POLICY tenant_isolation ON tenant_documents USING (tenant = 'a');
+    SET ROLE rls_tenant;
+    "#
+    .execute(&mut conn);
+
+    for query in [
+        "SELECT paradedb.aggregate('tenant_documents_idx', paradedb.all(), '{\"n\": {\"value_count\": {\"field\": \"id\"}}}')",
+        "SELECT * FROM paradedb.timeseries('tenant_documents_idx', paradedb.all(), 'id', '1d')",
+        "SELECT paradedb.es_search('tenant_documents_idx', '{\"size\": 0}')",
+        "SELECT * FROM paradedb.multi_search(ARRAY['tenant_documents_idx'::regclass], paradedb.all())",
+    ] {
+        let err = query.execute_result(&mut conn).unwrap_err();
+        assert!(err.to_string().contains("row-level security"), "{query}: {err}");
+    }
+
+    // the table's owner isn't subject to its policies
+    "RESET ROLE".execute(&mut conn);
+    let (result,) = "SELECT paradedb.aggregate('tenant_documents_idx', paradedb.all(), '{\"n\": {\"value_count\": {\"field\": \"id\"}}}')"
+        .fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(result["n"]["value"], 8.0);
+}
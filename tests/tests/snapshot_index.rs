@@ -0,0 +1,189 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod fixtures;
+
+use fixtures::*;
+use pretty_assertions::assert_eq;
+use rstest::*;
+use sqlx::PgConnection;
+
+/// A fresh directory under the server's data directory, which the server can always write to.
+fn snapshot_dir(conn: &mut PgConnection, name: &str) -> String {
+    let (data_directory,) = "SHOW data_directory".fetch_one::<(String,)>(conn);
+    format!(
+        "{data_directory}/pg_search_snapshots/{name}_{}",
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+#[rstest]
+fn snapshot_and_restore(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, description TEXT);
+    INSERT INTO products (description) VALUES ('red shoes'), ('blue hat'), ('green shoes');
+    CREATE INDEX products_idx ON products USING bm25 (id, description) WITH (key_field = 'id');
+    DELETE FROM products WHERE id = 3;
+    "#
+    .execute(&mut conn);
+
+    let path = snapshot_dir(&mut conn, "products");
+    let (nsegments,) = format!("SELECT paradedb.snapshot_index('products_idx', '{path}')")
+        .fetch_one::<(i64,)>(&mut conn);
+    assert!(nsegments > 0);
+
+    // a snapshot can't be taken into a directory that's already in use
+    let err = format!("SELECT paradedb.snapshot_index('products_idx', '{path}')")
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("is not empty"), "{err}");
+
+    // index a row the snapshot doesn't have, so that the restore has something to undo
+    "UPDATE products SET description = 'purple shoes' WHERE id = 2".execute(&mut conn);
+
+    let (name,) =
+        format!("SELECT paradedb.restore_index('{path}')").fetch_one::<(String,)>(&mut conn);
+    assert_eq!(name, "products_idx");
+
+    // only the live rows that were indexed when the snapshot was taken are found
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM products WHERE id @@@ paradedb.term('description', 'shoes') ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM products WHERE id @@@ paradedb.term('description', 'hat') ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![]);
+
+    // restoring the same snapshot again works too
+    let (name,) =
+        format!("SELECT paradedb.restore_index('{path}')").fetch_one::<(String,)>(&mut conn);
+    assert_eq!(name, "products_idx");
+}
+
+#[rstest]
+fn restore_into_mismatched_index(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, description TEXT, category TEXT);
+    INSERT INTO products (description, category) VALUES ('red shoes', 'footwear');
+    CREATE INDEX products_idx ON products USING bm25 (id, description) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let path = snapshot_dir(&mut conn, "mismatched");
+    format!("SELECT paradedb.snapshot_index('products_idx', '{path}')").execute(&mut conn);
+
+    r#"
+    DROP INDEX products_idx;
+    CREATE INDEX products_idx ON products USING bm25 (id, description, category) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+    let err = format!("SELECT paradedb.restore_index('{path}')")
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("don't match"), "{err}");
+
+    "DROP INDEX products_idx".execute(&mut conn);
+    let err = format!("SELECT paradedb.restore_index('{path}')")
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"), "{err}");
+}
+
+#[rstest]
+fn snapshot_and_restore_privileges(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, description TEXT);
+    INSERT INTO products (description) VALUES ('red shoes');
+    CREATE INDEX products_idx ON products USING bm25 (id, description) WITH (key_field = 'id');
+    DO $$
+    BEGIN
+        IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'snapshot_user') THEN
+            CREATE ROLE snapshot_user;
+        END IF;
+    END
+    $$;
+    GRANT pg_read_server_files, pg_write_server_files TO snapshot_user;
+    "#
+    .execute(&mut conn);
+
+    // server file access isn't enough to copy out a table the role can't read
+    let path = snapshot_dir(&mut conn, "privileges");
+    "SET ROLE snapshot_user".execute(&mut conn);
+    let err = format!("SELECT paradedb.snapshot_index('products_idx', '{path}')")
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("permission denied"), "{err}");
+
+    // nor to replace the segments of an index of a table it doesn't own
+    "RESET ROLE".execute(&mut conn);
+    format!("SELECT paradedb.snapshot_index('products_idx', '{path}')").execute(&mut conn);
+    "GRANT SELECT ON products TO snapshot_user; SET ROLE snapshot_user".execute(&mut conn);
+    let err = format!("SELECT paradedb.restore_index('{path}')")
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("must be owner"), "{err}");
+    "RESET ROLE".execute(&mut conn);
+}
+
+#[rstest]
+fn restore_rejects_paths_outside_the_snapshot(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, description TEXT);
+    INSERT INTO products (description) VALUES ('red shoes');
+    CREATE INDEX products_idx ON products USING bm25 (id, description) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let path = snapshot_dir(&mut conn, "paths");
+    format!("SELECT paradedb.snapshot_index('products_idx', '{path}')").execute(&mut conn);
+    let manifest = format!("{path}/manifest.json");
+    let (original,) = format!("SELECT pg_read_file('{manifest}')::jsonb")
+        .fetch_one::<(serde_json::Value,)>(&mut conn);
+    let escaped = original.to_string().replace('\'', "''");
+    let segment_id = original["segments"][0]["segment_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    for (key, value, expected) in [
+        (
+            "{segments,0,files,0}",
+            format!("{segment_id}/../../../../etc/passwd"),
+            "is not the name of a segment file",
+        ),
+        (
+            "{segments,0,files,0}",
+            format!("{segment_id}.idx/../../manifest.json"),
+            "is not the name of a segment file",
+        ),
+        (
+            "{segments,0,segment_id}",
+            "../..".to_string(),
+            "is not a segment id",
+        ),
+    ] {
+        format!(
+            "COPY (SELECT jsonb_set('{escaped}'::jsonb, '{key}', to_jsonb('{value}'::text))::text) TO '{manifest}'"
+        )
+        .execute(&mut conn);
+        let err = format!("SELECT paradedb.restore_index('{path}')")
+            .execute_result(&mut conn)
+            .unwrap_err();
+        assert!(err.to_string().contains(expected), "{err}");
+    }
+}
@@ -181,18 +181,19 @@ fn sort_by_row_return_scores(mut conn: PgConnection) {
         );
     "#.execute(&mut conn);
 
-    let (plan, ) = "EXPLAIN (ANALYZE, FORMAT JSON) SELECT paradedb.score(id), * FROM paradedb.bm25_search WHERE description @@@ 'keyboard OR shoes' ORDER BY category LIMIT 5".fetch_one::<(Value,)>(&mut conn);
+    let query = "SELECT paradedb.score(id), id FROM paradedb.bm25_search WHERE description @@@ 'keyboard OR shoes' ORDER BY category, id";
+    let (plan,) =
+        format!("EXPLAIN (ANALYZE, FORMAT JSON) {query} LIMIT 5").fetch_one::<(Value,)>(&mut conn);
     eprintln!("{plan:#?}");
 
-    // Get the first plan node in the plans array
-    let plan = plan
-        .pointer("/0/Plan/Plans/0/Plans/0")
-        .unwrap()
-        .as_object()
-        .unwrap();
+    // the ordering is pushed down, and the top n are scored after they're found
+    let plan = format!("{plan:?}");
+    assert!(plan.contains(r#""   TopN Order By": String("category asc, id asc")"#));
+    assert!(plan.contains(r#""Scores": Bool(true)"#));
 
-    assert_eq!(plan.get("   TopN Order By"), None);
-    assert_eq!(plan.get("Scores"), Some(&Value::Bool(true)));
+    let expected = query.fetch::<(f32, i32)>(&mut conn);
+    let top_n = format!("{query} LIMIT 5").fetch::<(f32, i32)>(&mut conn);
+    assert_eq!(top_n, expected[..5]);
 }
 
 #[rstest]
@@ -211,3 +211,88 @@ mod string_fast_field_exec {
         assert_eq!(count, vec![(0,)])
     }
 }
+
+mod normalized_fast_fields {
+    use super::*;
+    use serde_json::Value;
+
+    #[fixture]
+    fn setup_normalized_table(mut conn: PgConnection) -> PgConnection {
+        r#"
+        CREATE TABLE products (
+            id SERIAL8 NOT NULL PRIMARY KEY,
+            lower_brand text,
+            raw_brand text,
+            sku text
+        );
+        INSERT INTO products (lower_brand, raw_brand, sku) VALUES
+            ('Acme', 'Acme', 'A-1'),
+            ('ACME', 'ACME', 'A-2'),
+            ('Zenith', 'Zenith', 'Z-1');
+
+        CREATE INDEX products_idx ON products USING bm25 (id, lower_brand, raw_brand, sku)
+        WITH (
+            key_field = 'id',
+            text_fields = '{
+                "lower_brand": {"fast": true, "normalizer": "lowercase"},
+                "raw_brand": {"fast": true, "normalizer": "raw"},
+                "sku": {"tokenizer": {"type": "raw"}}
+            }'
+        );
+
+        SET enable_indexscan TO off;
+        SET enable_bitmapscan TO off;
+        SET max_parallel_workers TO 0;
+        "#
+        .execute(&mut conn);
+        conn
+    }
+
+    #[rstest]
+    fn raw_tokenizer_is_fast(#[from(setup_normalized_table)] mut conn: PgConnection) {
+        let (fast,) = "SELECT fast FROM paradedb.schema('products_idx') WHERE name = 'sku'"
+            .fetch_one::<(bool,)>(&mut conn);
+        assert!(fast);
+    }
+
+    #[rstest]
+    fn lowercase_values_come_from_the_heap(#[from(setup_normalized_table)] mut conn: PgConnection) {
+        // the lowercase fast field only holds `acme`, so it can't be used to return the values
+        let query = "SELECT lower_brand FROM products WHERE id @@@ paradedb.all() ORDER BY id";
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+        assert!(
+            !plan.to_string().contains("FastFieldExecState"),
+            "{plan:#?}"
+        );
+        assert_eq!(
+            query.fetch::<(String,)>(&mut conn),
+            vec![("Acme".into(),), ("ACME".into(),), ("Zenith".into(),)]
+        );
+
+        // while the raw one can
+        let query = "SELECT raw_brand FROM products WHERE id @@@ paradedb.all() ORDER BY id";
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+        assert!(plan.to_string().contains("FastFieldExecState"), "{plan:#?}");
+        assert_eq!(
+            query.fetch::<(String,)>(&mut conn),
+            vec![("Acme".into(),), ("ACME".into(),), ("Zenith".into(),)]
+        );
+    }
+
+    #[rstest]
+    fn group_by_keeps_original_values(#[from(setup_normalized_table)] mut conn: PgConnection) {
+        "SET paradedb.enable_aggregate_custom_scan TO on".execute(&mut conn);
+
+        for column in ["lower_brand", "raw_brand"] {
+            let rows = format!(
+                "SELECT {column}, COUNT(*) FROM products WHERE id @@@ paradedb.all() GROUP BY {column} ORDER BY {column} COLLATE \"C\""
+            )
+            .fetch::<(String, i64)>(&mut conn);
+            assert_eq!(
+                rows,
+                vec![("ACME".into(), 1), ("Acme".into(), 1), ("Zenith".into(), 1)],
+                "{column}"
+            );
+        }
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod fixtures;
+
+use fixtures::*;
+use pretty_assertions::assert_eq;
+use rstest::*;
+use sqlx::PgConnection;
+use std::os::unix::fs::PermissionsExt;
+use tantivy::schema::{Schema, FAST, INDEXED, STORED, TEXT};
+use tantivy::{doc, Index};
+use tempfile::TempDir;
+
+/// Write a tantivy index of some products to a new directory that the server can read.
+fn products_index() -> TempDir {
+    let directory = tempfile::tempdir().unwrap();
+    std::fs::set_permissions(directory.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut builder = Schema::builder();
+    let id = builder.add_u64_field("id", INDEXED | STORED | FAST);
+    let description = builder.add_text_field("description", TEXT | STORED);
+    let tags = builder.add_text_field("tags", TEXT | STORED);
+    let index = Index::create_in_dir(directory.path(), builder.build()).unwrap();
+    let mut writer = index.writer(15_000_000).unwrap();
+    writer
+        .add_document(doc!(id => 1u64, description => "red running shoes", tags => "sale"))
+        .unwrap();
+    writer
+        .add_document(doc!(id => 2u64, description => "blue shoes", tags => "sale", tags => "new"))
+        .unwrap();
+    writer
+        .add_document(doc!(id => 3u64, description => "green hat"))
+        .unwrap();
+    writer.commit().unwrap();
+    writer.wait_merging_threads().unwrap();
+
+    for entry in std::fs::read_dir(directory.path()).unwrap() {
+        std::fs::set_permissions(
+            entry.unwrap().path(),
+            std::fs::Permissions::from_mode(0o644),
+        )
+        .unwrap();
+    }
+    directory
+}
+
+#[rstest]
+fn tantivy_fdw_search(mut conn: PgConnection) {
+    let index = products_index();
+    format!(
+        r#"
+        CREATE SERVER tantivy_server FOREIGN DATA WRAPPER tantivy_fdw;
+        CREATE FOREIGN TABLE products (id INT, description TEXT, tags TEXT[], query TEXT, score REAL)
+        SERVER tantivy_server OPTIONS (path '{}');
+        "#,
+        index.path().display()
+    )
+    .execute(&mut conn);
+
+    let rows: Vec<(i32, String)> =
+        "SELECT id, description FROM products ORDER BY id".fetch(&mut conn);
+    assert_eq!(
+        rows,
+        vec![
+            (1, "red running shoes".into()),
+            (2, "blue shoes".into()),
+            (3, "green hat".into())
+        ]
+    );
+
+    // the query is searched for by the index, so it isn't checked against the rows
+    let rows: Vec<(i32, Option<Vec<String>>)> =
+        "SELECT id, tags FROM products WHERE query = 'description:shoes AND tags:new'"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(2, Some(vec!["sale".into(), "new".into()]))]);
+
+    let (plan,) = "EXPLAIN (FORMAT JSON) SELECT * FROM products WHERE query = 'shoes' LIMIT 1"
+        .fetch_one::<(serde_json::Value,)>(&mut conn);
+    let scan = &plan[0]["Plan"]["Plans"][0];
+    assert_eq!(scan["Tantivy Query"], "shoes");
+    assert_eq!(scan["Limit"], 1);
+    assert!(scan.get("Filter").is_none(), "{scan}");
+
+    // the top document is the one whose description is shortest
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM products WHERE query = 'shoes' LIMIT 1".fetch(&mut conn);
+    assert_eq!(rows, vec![(2,)]);
+
+    let (scored,) = "SELECT COUNT(*) FROM products WHERE query = 'shoes' AND score > 0"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(scored, 2);
+}
+
+#[rstest]
+fn tantivy_fdw_options(mut conn: PgConnection) {
+    "CREATE SERVER tantivy_server FOREIGN DATA WRAPPER tantivy_fdw".execute(&mut conn);
+
+    let err = "CREATE FOREIGN TABLE products (id INT) SERVER tantivy_server"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("is required"), "{err}");
+
+    let err =
+        "CREATE FOREIGN TABLE products (id INT) SERVER tantivy_server OPTIONS (path 'relative')"
+            .execute_result(&mut conn)
+            .unwrap_err();
+    assert!(err.to_string().contains("is not an absolute path"), "{err}");
+
+    let err = "CREATE FOREIGN TABLE products (id INT) SERVER tantivy_server OPTIONS (path '/tmp', color 'red')"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("`color` is not an option"),
+        "{err}"
+    );
+}
+
+#[rstest]
+fn tantivy_fdw_path_requires_pg_read_server_files(mut conn: PgConnection) {
+    r#"
+    CREATE SERVER tantivy_server FOREIGN DATA WRAPPER tantivy_fdw;
+    DO $$
+    BEGIN
+        IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'tantivy_fdw_user') THEN
+            CREATE ROLE tantivy_fdw_user;
+        END IF;
+    END
+    $$;
+    REVOKE pg_read_server_files FROM tantivy_fdw_user;
+    GRANT USAGE ON FOREIGN SERVER tantivy_server TO tantivy_fdw_user;
+    GRANT CREATE ON SCHEMA public TO tantivy_fdw_user;
+    SET ROLE tantivy_fdw_user;
+    "#
+    .execute(&mut conn);
+
+    let err = "CREATE FOREIGN TABLE products (id INT) SERVER tantivy_server OPTIONS (path '/tmp')"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(err.to_string().contains("pg_read_server_files"), "{err}");
+
+    "RESET ROLE; GRANT pg_read_server_files TO tantivy_fdw_user; SET ROLE tantivy_fdw_user;"
+        .execute(&mut conn);
+    "CREATE FOREIGN TABLE products (id INT) SERVER tantivy_server OPTIONS (path '/tmp')"
+        .execute(&mut conn);
+    "RESET ROLE".execute(&mut conn);
+}
+
+#[rstest]
+fn tantivy_fdw_missing_index(mut conn: PgConnection) {
+    r#"
+    CREATE SERVER tantivy_server FOREIGN DATA WRAPPER tantivy_fdw;
+    CREATE FOREIGN TABLE products (id INT, query TEXT, score REAL)
+    SERVER tantivy_server OPTIONS (path '/nonexistent/tantivy_index');
+    "#
+    .execute(&mut conn);
+
+    let err = "SELECT * FROM products"
+        .fetch_result::<(i32,)>(&mut conn)
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("could not open the tantivy index at `/nonexistent/tantivy_index`"),
+        "{err}"
+    );
+}
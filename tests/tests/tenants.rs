@@ -0,0 +1,98 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+// Tests for indexes whose segments are partitioned by a `tenant_field`
+mod fixtures;
+
+use fixtures::*;
+use pretty_assertions::assert_eq;
+use rstest::*;
+use sqlx::PgConnection;
+
+#[rstest]
+fn tenant_segments_are_searched_alone(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE tenant_docs (id SERIAL PRIMARY KEY, tenant_id INT, body TEXT);
+    CREATE INDEX tenant_docs_idx ON tenant_docs
+    USING bm25 (id, tenant_id, body)
+    WITH (key_field = 'id', tenant_field = 'tenant_id', layer_sizes = '0');
+
+    INSERT INTO tenant_docs (tenant_id, body)
+    SELECT n % 3, 'hello world' FROM generate_series(1, 30) n;
+    "#
+    .execute(&mut conn);
+
+    // one statement that writes the rows of three tenants writes a segment for each of them
+    let (nsegments,) = "SELECT count(*) FROM paradedb.index_info('tenant_docs_idx')"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(nsegments, 3);
+
+    "INSERT INTO tenant_docs (tenant_id, body) VALUES (1, 'hello again')".execute(&mut conn);
+
+    for (tenant_id, expected) in [(0, 10), (1, 11), (2, 10), (3, 0)] {
+        let (count,) = format!(
+            "SELECT count(*) FROM tenant_docs WHERE body @@@ 'hello' AND tenant_id = {tenant_id}"
+        )
+        .fetch_one::<(i64,)>(&mut conn);
+        assert_eq!(count, expected, "count for tenant {tenant_id}");
+    }
+
+    let (count,) =
+        "SELECT count(*) FROM tenant_docs WHERE body @@@ 'hello' AND tenant_id IN (0, 1)"
+            .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 21);
+
+    // queries that aren't restricted to a tenant still search every segment
+    let (count,) =
+        "SELECT count(*) FROM tenant_docs WHERE body @@@ 'hello'".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 31);
+    let (count,) = "SELECT count(*) FROM tenant_docs WHERE body @@@ 'hello' AND tenant_id <> 1"
+        .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 20);
+}
+
+#[rstest]
+fn tenant_field_must_be_exact(mut conn: PgConnection) {
+    "CREATE TABLE tenant_docs (id SERIAL PRIMARY KEY, tenant TEXT, score REAL)".execute(&mut conn);
+
+    let result = r#"
+    CREATE INDEX tenant_docs_idx ON tenant_docs USING bm25 (id, tenant)
+    WITH (key_field = 'id', tenant_field = 'tenant')
+    "#
+    .execute_result(&mut conn);
+    assert!(
+        result.is_err(),
+        "a tokenized text field can't name a tenant"
+    );
+
+    let result = r#"
+    CREATE INDEX tenant_docs_idx ON tenant_docs USING bm25 (id, score)
+    WITH (key_field = 'id', tenant_field = 'score')
+    "#
+    .execute_result(&mut conn);
+    assert!(result.is_err(), "a float field can't name a tenant");
+
+    r#"
+    CREATE INDEX tenant_docs_idx ON tenant_docs USING bm25 (id, tenant)
+    WITH (
+        key_field = 'id',
+        tenant_field = 'tenant',
+        text_fields = '{"tenant": {"tokenizer": {"type": "keyword"}}}'
+    )
+    "#
+    .execute(&mut conn);
+}
@@ -320,3 +320,129 @@ fn datetime_term(mut conn: PgConnection) {
     .fetch_collect(&mut conn);
     assert_eq!(rows, vec![(2,)]);
 }
+
+#[rstest]
+fn binary_uuid_term(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE test_table (
+        id SERIAL PRIMARY KEY,
+        value_uuid UUID,
+        value_uuids UUID[]
+    );
+
+    INSERT INTO test_table (value_uuid, value_uuids) VALUES
+        ('a99e7330-37e6-4f14-8c95-985052ee74f3', '{2fe779f1-2a74-4035-9f1a-9477bae0364c}'),
+        ('2fe779f1-2a74-4035-9f1a-9477bae0364c', '{}'),
+        ('b9592b87-82ea-4d7b-8865-f6be819d4f0f', '{a99e7330-37e6-4f14-8c95-985052ee74f3}');
+
+    CREATE INDEX test_index ON test_table
+    USING bm25 (id, value_uuid, value_uuids)
+    WITH (key_field = 'id', uuid_fields = '{"value_uuid": {}, "value_uuids": {}}');
+    "#
+    .execute(&mut conn);
+
+    let rows: Vec<(i32, String)> = r#"
+    SELECT id, value_uuid::text FROM test_table WHERE test_table @@@
+    paradedb.term(field => 'value_uuid', value => '2fe779f1-2a74-4035-9f1a-9477bae0364c'::uuid)
+    ORDER BY id
+    "#
+    .fetch_collect(&mut conn);
+    assert_eq!(
+        rows,
+        vec![(2, "2fe779f1-2a74-4035-9f1a-9477bae0364c".into())]
+    );
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM test_table WHERE test_table @@@
+    paradedb.term(field => 'value_uuids', value => 'a99e7330-37e6-4f14-8c95-985052ee74f3'::uuid)
+    ORDER BY id
+    "#
+    .fetch_collect(&mut conn);
+    assert_eq!(rows, vec![(3,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM test_table WHERE id @@@ paradedb.all()
+    AND value_uuid IN ('a99e7330-37e6-4f14-8c95-985052ee74f3', 'b9592b87-82ea-4d7b-8865-f6be819d4f0f')
+    ORDER BY id
+    "#
+    .fetch_collect(&mut conn);
+    assert_eq!(rows, vec![(1,), (3,)]);
+
+    let result = r#"
+    SELECT id FROM test_table WHERE test_table @@@
+    paradedb.term(field => 'value_uuid', value => 'not-a-uuid')
+    "#
+    .execute_result(&mut conn);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("is not a valid UUID"));
+}
+
+#[rstest]
+fn bytes_term(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE test_table (
+        id SERIAL PRIMARY KEY,
+        body TEXT,
+        hash BYTEA,
+        hashes BYTEA[]
+    );
+
+    INSERT INTO test_table (body, hash, hashes) VALUES
+        ('red shoes', '\xdeadbeef', '{"\\x0102"}'),
+        ('blue shoes', '\xcafebabe', '{}'),
+        ('red hat', '\xdeadbeef', '{"\\x0304"}');
+
+    CREATE INDEX test_index ON test_table
+    USING bm25 (id, body, hash, hashes)
+    WITH (key_field = 'id', bytes_fields = '{"hash": {}, "hashes": {}}');
+    "#
+    .execute(&mut conn);
+
+    let rows: Vec<(i32, Vec<u8>)> = r#"
+    SELECT id, hash FROM test_table WHERE test_table @@@
+    paradedb.term(field => 'hash', value => '\xdeadbeef'::bytea)
+    ORDER BY id
+    "#
+    .fetch_collect(&mut conn);
+    assert_eq!(
+        rows,
+        vec![
+            (1, vec![0xde, 0xad, 0xbe, 0xef]),
+            (3, vec![0xde, 0xad, 0xbe, 0xef])
+        ]
+    );
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM test_table WHERE test_table @@@
+    paradedb.term(field => 'hashes', value => '\x0304'::bytea)
+    ORDER BY id
+    "#
+    .fetch_collect(&mut conn);
+    assert_eq!(rows, vec![(3,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM test_table
+    WHERE body @@@ 'shoes' AND hash = '\xdeadbeef'::bytea
+    ORDER BY id
+    "#
+    .fetch_collect(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM test_table WHERE id @@@ paradedb.all()
+    AND hash IN ('\xcafebabe'::bytea, '\x00'::bytea)
+    ORDER BY id
+    "#
+    .fetch_collect(&mut conn);
+    assert_eq!(rows, vec![(2,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM test_table WHERE id @@@
+    '{"term": {"field": "hash", "value": "yv66vg=="}}'::jsonb
+    ORDER BY id
+    "#
+    .fetch_collect(&mut conn);
+    assert_eq!(rows, vec![(2,)]);
+}
@@ -0,0 +1,113 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod fixtures;
+
+use fixtures::*;
+use pretty_assertions::assert_eq;
+use rstest::*;
+use serde_json::Value;
+use sqlx::PgConnection;
+
+fn products_with_tsvector(conn: &mut PgConnection) {
+    r#"
+    CREATE TABLE products (
+        id SERIAL PRIMARY KEY,
+        title TEXT,
+        body TEXT,
+        tsv tsvector GENERATED ALWAYS AS (
+            setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+            setweight(to_tsvector('english', coalesce(body, '')), 'B')
+        ) STORED
+    );
+    INSERT INTO products (title, body) VALUES
+        ('Running Shoes', 'light and fast'),
+        ('Hiking Boots', 'made for running trails'),
+        ('Rain Jacket', 'keeps you dry while hiking');
+    CREATE INDEX products_tsv_idx ON products USING gin (tsv);
+    CREATE INDEX products_idx ON products USING bm25 (id, title, body)
+    WITH (
+        key_field = 'id',
+        text_fields = '{
+            "title": {"tokenizer": {"type": "default", "stemmer": "English"}},
+            "body": {"tokenizer": {"type": "default", "stemmer": "English"}}
+        }'
+    );
+    "#
+    .execute(conn);
+}
+
+#[rstest]
+fn tsvector_column_searches_bm25_index(mut conn: PgConnection) {
+    products_with_tsvector(&mut conn);
+
+    // the same queries of the `tsvector` column match the same rows with either index
+    for query in [
+        "running",
+        "running:A",
+        "hiking & !boots",
+        "running <-> trails",
+        "jack:*",
+        "dry | shoes",
+    ] {
+        let gin: Vec<(i32,)> = format!(
+            "SELECT id FROM products WHERE tsv @@ to_tsquery('english', '{query}') ORDER BY id"
+        )
+        .fetch(&mut conn);
+        let bm25: Vec<(i32,)> = format!(
+            "SELECT id FROM products WHERE tsv @@@ to_tsquery('english', '{query}') ORDER BY id"
+        )
+        .fetch(&mut conn);
+        assert_eq!(gin, bm25, "{query}");
+        assert!(!bm25.is_empty(), "{query}");
+    }
+
+    // which is planned as a search of the BM25 index, and not of the `tsvector`
+    let (plan,) = "EXPLAIN (FORMAT JSON) SELECT id FROM products WHERE tsv @@@ to_tsquery('english', 'running')"
+        .fetch_one::<(Value,)>(&mut conn);
+    assert!(
+        plan.to_string().contains(r#""Index":"products_idx""#),
+        "{plan}"
+    );
+
+    // a text field can be searched with a `tsquery` too
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM products WHERE body @@@ to_tsquery('english', 'running') ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(2,)]);
+    let rows: Vec<(i32,)> = "SELECT id FROM products WHERE id @@@ paradedb.tsquery(ARRAY['title', 'body'], to_tsquery('english', 'running:B'), 'AB') ORDER BY id"
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(2,)]);
+}
+
+#[rstest]
+fn tsvector_column_not_generated(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE documents (id SERIAL PRIMARY KEY, body TEXT, tsv tsvector);
+    INSERT INTO documents (body, tsv) VALUES ('running shoes', to_tsvector('english', 'running shoes'));
+    CREATE INDEX documents_idx ON documents USING bm25 (id, body) WITH (key_field = 'id');
+    "#
+    .execute(&mut conn);
+
+    let err = "SELECT id FROM documents WHERE tsv @@@ to_tsquery('english', 'shoes')"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("is not a generated column"),
+        "{err}"
+    );
+}
@@ -0,0 +1,86 @@
+// Copyright (c) 2023-2025 ParadeDB, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+// Tests for indexes whose documents expire after a `ttl`
+mod fixtures;
+
+use fixtures::*;
+use pretty_assertions::assert_eq;
+use rstest::*;
+use sqlx::PgConnection;
+
+#[rstest]
+fn expired_documents_are_hidden_then_deleted(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE ttl_logs (id SERIAL PRIMARY KEY, message TEXT, created_at TIMESTAMPTZ);
+    INSERT INTO ttl_logs (message, created_at)
+    SELECT 'disk full', now() - n * interval '1 day' FROM generate_series(0, 9) n;
+    INSERT INTO ttl_logs (message, created_at) VALUES ('disk full', NULL);
+
+    CREATE INDEX ttl_logs_idx ON ttl_logs
+    USING bm25 (id, message, created_at)
+    WITH (key_field = 'id', ttl_field = 'created_at', ttl = '3 days 12 hours');
+    "#
+    .execute(&mut conn);
+
+    // the four newest rows are young enough, and the row without a timestamp never expires
+    let (count,) =
+        "SELECT count(*) FROM ttl_logs WHERE message @@@ 'disk'".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 5);
+
+    // lengthening the ttl brings back documents that haven't been deleted yet, but not the ones
+    // that VACUUM has
+    "ALTER INDEX ttl_logs_idx SET (ttl = '5 days 12 hours')".execute(&mut conn);
+    let (count,) =
+        "SELECT count(*) FROM ttl_logs WHERE message @@@ 'disk'".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 7);
+
+    "VACUUM ttl_logs".execute(&mut conn);
+    "ALTER INDEX ttl_logs_idx SET (ttl = '30 days')".execute(&mut conn);
+    let (count,) =
+        "SELECT count(*) FROM ttl_logs WHERE message @@@ 'disk'".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 7);
+    let (count,) = "SELECT count(*) FROM ttl_logs".fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 11);
+}
+
+#[rstest]
+fn ttl_field_must_be_a_point_in_time(mut conn: PgConnection) {
+    "CREATE TABLE ttl_logs (id SERIAL PRIMARY KEY, message TEXT, created_at TIMESTAMPTZ, at_time TIME)"
+        .execute(&mut conn);
+
+    let result = r#"
+    CREATE INDEX ttl_logs_idx ON ttl_logs USING bm25 (id, message, at_time)
+    WITH (key_field = 'id', ttl_field = 'at_time', ttl = '1 day')
+    "#
+    .execute_result(&mut conn);
+    assert!(result.is_err(), "a time of day can't expire");
+
+    let result = r#"
+    CREATE INDEX ttl_logs_idx ON ttl_logs USING bm25 (id, message, created_at)
+    WITH (key_field = 'id', ttl_field = 'created_at')
+    "#
+    .execute_result(&mut conn);
+    assert!(result.is_err(), "a ttl_field needs a ttl");
+
+    let result = r#"
+    CREATE INDEX ttl_logs_idx ON ttl_logs USING bm25 (id, message, created_at)
+    WITH (key_field = 'id', ttl_field = 'created_at', ttl = 'forever')
+    "#
+    .execute_result(&mut conn);
+    assert!(result.is_err(), "a ttl must be an interval");
+}
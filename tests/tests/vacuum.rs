@@ -63,3 +63,59 @@ fn manual_vacuum(mut conn: PgConnection) {
     "update sadvac set id = id;".execute(&mut conn);
     assert_eq!(count_func(&mut conn), ROW_COUNT, "post update after vacuum");
 }
+
+#[rstest]
+fn index_segments_reports_deleted_docs(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE segstats (id bigint, data text);
+        ALTER TABLE segstats SET (autovacuum_enabled = 'off');
+        CREATE INDEX idxsegstats ON segstats USING bm25 (id, data) WITH (key_field = 'id', layer_sizes = '0', background_layer_sizes = '0');
+        INSERT INTO segstats SELECT x, 'row ' || x FROM generate_series(1, 100) x;
+        DELETE FROM segstats WHERE id <= 25;
+        VACUUM segstats;
+    "#
+    .execute(&mut conn);
+
+    let (nsegments, num_docs, deleted_docs, has_postings, has_created_at) = r#"
+        SELECT count(*),
+               sum(num_docs)::bigint,
+               sum(deleted_docs)::bigint,
+               bool_and(postings_bytes > 0 AND deletes_bytes > 0 AND byte_size >= postings_bytes + deletes_bytes),
+               bool_and(created_at > now() - interval '1 hour')
+        FROM paradedb.index_segments('idxsegstats');
+    "#
+    .fetch_one::<(i64, i64, i64, bool, bool)>(&mut conn);
+    assert_eq!(nsegments, 1);
+    assert_eq!(num_docs, 75);
+    assert_eq!(deleted_docs, 25);
+    assert!(has_postings);
+    assert!(has_created_at);
+}
+
+#[rstest]
+fn prewarm_segments(mut conn: PgConnection) {
+    r#"
+        CREATE TABLE prewarm (id bigint, data text);
+        CREATE INDEX idxprewarm ON prewarm USING bm25 (id, data) WITH (key_field = 'id', layer_sizes = '0', background_layer_sizes = '0');
+        INSERT INTO prewarm SELECT x, 'row ' || x FROM generate_series(1, 100) x;
+        INSERT INTO prewarm SELECT x, 'row ' || x FROM generate_series(101, 200) x;
+    "#
+    .execute(&mut conn);
+
+    let segments = "SELECT segno FROM paradedb.index_segments('idxprewarm') ORDER BY segno"
+        .fetch::<(String,)>(&mut conn);
+    assert_eq!(segments.len(), 2);
+
+    let (all,) = "SELECT paradedb.prewarm_segments('idxprewarm')".fetch_one::<(i64,)>(&mut conn);
+    let (one,) = format!(
+        "SELECT paradedb.prewarm_segments('idxprewarm', ARRAY['{}'])",
+        segments[0].0
+    )
+    .fetch_one::<(i64,)>(&mut conn);
+    assert!(0 < one && one < all);
+
+    let error = "SELECT paradedb.prewarm_segments('idxprewarm', ARRAY['nonesuch'])"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(error.to_string().contains("segment(s) not found: nonesuch"));
+}
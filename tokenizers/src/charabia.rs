@@ -20,8 +20,8 @@ use unicode_normalization::UnicodeNormalization;
 use once_cell::sync::Lazy;
 use std::sync::Arc;
 
-use charabia::normalizer::NormalizedTokenIter;
-use charabia::{Tokenizer, TokenizerBuilder};
+use charabia::{Script, Tokenizer, TokenizerBuilder};
+use rust_stemmers::{Algorithm, Stemmer};
 use tantivy::tokenizer::{Token, TokenStream, Tokenizer as TantivyTokenizer};
 
 static CHARABIA_TOKENIZER: Lazy<Arc<Tokenizer>> = Lazy::new(|| {
@@ -29,47 +29,127 @@ static CHARABIA_TOKENIZER: Lazy<Arc<Tokenizer>> = Lazy::new(|| {
 });
 
 #[derive(Clone, Default)]
-pub struct CharabiaTokenizer;
+pub struct CharabiaTokenizer {
+    auto_stemmer: bool,
+}
+
+impl CharabiaTokenizer {
+    /// With `auto_stemmer`, each run of tokens of the same script is stemmed in the language it's
+    /// detected to be in, if there's a Snowball stemmer for it.
+    pub fn new(auto_stemmer: bool) -> Self {
+        Self { auto_stemmer }
+    }
+}
 
 impl TantivyTokenizer for CharabiaTokenizer {
-    type TokenStream<'a> = CharabiaTokenStream<'a>;
+    type TokenStream<'a> = CharabiaTokenStream;
 
     fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
-        CharabiaTokenStream::new(text)
+        CharabiaTokenStream::new(text, self.auto_stemmer)
     }
 }
 
-pub struct CharabiaTokenStream<'a> {
-    tokens: NormalizedTokenIter<'a, 'static, 'static, 'static>,
+pub struct CharabiaTokenStream {
+    tokens: std::vec::IntoIter<Token>,
     token: Token,
-    position: usize,
 }
 
-impl<'a> CharabiaTokenStream<'a> {
-    pub fn new(text: &'a str) -> Self {
-        let tokens = CHARABIA_TOKENIZER.tokenize(text);
+/// A word of the text, with the script and language Charabia detected it to be in.
+struct Word {
+    token: Token,
+    script: Script,
+    language: Option<&'static str>,
+}
+
+impl CharabiaTokenStream {
+    pub fn new(text: &str, auto_stemmer: bool) -> Self {
+        let mut words = Vec::new();
+        for token in CHARABIA_TOKENIZER.tokenize(text) {
+            if token.is_word() {
+                words.push(Word {
+                    token: Token {
+                        text: token.lemma().nfc().collect::<String>(),
+                        offset_from: token.byte_start,
+                        offset_to: token.byte_end,
+                        position: words.len(),
+                        ..Token::default()
+                    },
+                    script: token.script,
+                    language: token.language.map(|language| language.code()),
+                });
+            }
+        }
+        if auto_stemmer {
+            stem_runs(text, &mut words);
+        }
 
         Self {
-            tokens,
+            tokens: words
+                .into_iter()
+                .map(|word| word.token)
+                .collect::<Vec<_>>()
+                .into_iter(),
             token: Token::default(),
-            position: 0,
         }
     }
 }
 
-impl<'a> TokenStream for CharabiaTokenStream<'a> {
+/// Stem each run of words of the same script in the language Charabia detected it to be in, or
+/// else the language the run's text reliably looks like.  A run whose language is unknown, or has
+/// no stemmer, is left as it is.
+fn stem_runs(text: &str, words: &mut [Word]) {
+    for run in words.chunk_by_mut(|a, b| a.script == b.script) {
+        let language = run.iter().find_map(|word| word.language).or_else(|| {
+            let from = run.first()?.token.offset_from;
+            let to = run.last()?.token.offset_to;
+            whatlang::detect(&text[from..to])
+                .filter(|info| info.is_reliable())
+                .map(|info| info.lang().code())
+        });
+        let Some(algorithm) = language.and_then(stemmer_algorithm) else {
+            continue;
+        };
+        let stemmer = Stemmer::create(algorithm);
+        for word in run {
+            word.token.text = stemmer.stem(&word.token.text).into_owned();
+        }
+    }
+}
+
+/// The Snowball stemmer of the language with the ISO 639-3 code `code`.
+fn stemmer_algorithm(code: &str) -> Option<Algorithm> {
+    Some(match code {
+        "ara" => Algorithm::Arabic,
+        "dan" => Algorithm::Danish,
+        "nld" => Algorithm::Dutch,
+        "eng" => Algorithm::English,
+        "fin" => Algorithm::Finnish,
+        "fra" => Algorithm::French,
+        "deu" => Algorithm::German,
+        "ell" => Algorithm::Greek,
+        "hun" => Algorithm::Hungarian,
+        "ita" => Algorithm::Italian,
+        "nob" => Algorithm::Norwegian,
+        "por" => Algorithm::Portuguese,
+        "ron" => Algorithm::Romanian,
+        "rus" => Algorithm::Russian,
+        "spa" => Algorithm::Spanish,
+        "swe" => Algorithm::Swedish,
+        "tam" => Algorithm::Tamil,
+        "tur" => Algorithm::Turkish,
+        _ => return None,
+    })
+}
+
+impl TokenStream for CharabiaTokenStream {
     fn advance(&mut self) -> bool {
-        while let Some(token) = self.tokens.next() {
-            if token.is_word() {
-                self.token.text = token.lemma().nfc().collect::<String>();
-                self.token.offset_from = token.byte_start;
-                self.token.offset_to = token.byte_end;
-                self.token.position = self.position;
-                self.position += 1;
-                return true;
+        match self.tokens.next() {
+            Some(token) => {
+                self.token = token;
+                true
             }
+            None => false,
         }
-        false
     }
 
     fn token(&self) -> &Token {
@@ -216,4 +296,55 @@ mod tests {
         let actual_tokens: Vec<String> = tokens.iter().map(|t| t.text.clone()).collect();
         assert_eq!(actual_tokens, expected_tokens);
     }
-}
\ No newline at end of file
+
+    #[rstest]
+    fn test_charabia_tokenizer_with_auto_stemmer() {
+        let search_tokenizer = SearchTokenizer::from_json_value(&serde_json::json!({
+            "type": "charabia",
+            "stemmer": "auto"
+        }))
+        .unwrap();
+        assert_eq!(search_tokenizer.name(), "charabia[stemmer=auto]");
+        let mut tokenizer = search_tokenizer.to_tantivy_tokenizer().unwrap();
+
+        let english = "the runners were running quickly through the beautiful mountains";
+        let russian = "бегуны быстро бежали через красивые горы и зеленые долины";
+        let mut tokens = Vec::new();
+        let mut token_stream = tokenizer.token_stream(&format!("{english} {russian}"));
+        while let Some(token) = token_stream.next() {
+            tokens.push(token.text.clone());
+        }
+
+        // each script's run of words is stemmed in its own language
+        let english_stemmer = Stemmer::create(Algorithm::English);
+        let russian_stemmer = Stemmer::create(Algorithm::Russian);
+        let expected_tokens: Vec<String> = english
+            .split(' ')
+            .map(|word| english_stemmer.stem(word).into_owned())
+            .chain(
+                russian
+                    .split(' ')
+                    .map(|word| russian_stemmer.stem(word).into_owned()),
+            )
+            .collect();
+        assert_eq!(tokens, expected_tokens);
+        assert!(tokens.contains(&"mountain".to_string()));
+    }
+
+    #[rstest]
+    fn test_charabia_tokenizer_without_auto_stemmer() {
+        let mut tokenizer = CharabiaTokenizer::default();
+        let tokens = test_helper(&mut tokenizer, "the runners were running");
+        let actual_tokens: Vec<String> = tokens.iter().map(|t| t.text.clone()).collect();
+        assert_eq!(actual_tokens, vec!["the", "runners", "were", "running"]);
+    }
+
+    #[rstest]
+    fn test_auto_stemmer_requires_charabia() {
+        let result = SearchTokenizer::from_json_value(&serde_json::json!({
+            "type": "default",
+            "stemmer": "auto"
+        }));
+        assert!(result.is_err());
+    }
+}
@@ -45,6 +45,9 @@ pub struct SearchTokenizerFilters {
     pub stemmer: Option<Language>,
     pub stopwords_language: Option<Language>,
     pub stopwords: Option<Vec<String>>,
+    /// Stem each run of tokens in the language it's detected to be in, which is given as a
+    /// `stemmer` of `"auto"`.
+    pub auto_stemmer: Option<bool>,
 }
 
 impl SearchTokenizerFilters {
@@ -60,6 +63,7 @@ impl SearchTokenizerFilters {
             stemmer: None,
             stopwords_language: None,
             stopwords: None,
+            auto_stemmer: None,
         }
     }
 
@@ -83,9 +87,13 @@ impl SearchTokenizerFilters {
             })?);
         };
         if let Some(stemmer) = value.get("stemmer") {
-            filters.stemmer = Some(serde_json::from_value(stemmer.clone()).map_err(|_| {
-                anyhow::anyhow!("stemmer tokenizer requires a valid 'stemmer' field")
-            })?);
+            if stemmer.as_str() == Some("auto") {
+                filters.auto_stemmer = Some(true);
+            } else {
+                filters.stemmer = Some(serde_json::from_value(stemmer.clone()).map_err(|_| {
+                    anyhow::anyhow!("stemmer tokenizer requires a valid 'stemmer' field")
+                })?);
+            }
         }
         if let Some(stopwords_language) = value.get("stopwords_language") {
             filters.stopwords_language = Some(
@@ -115,6 +123,10 @@ impl SearchTokenizerFilters {
             let v = serde_json::Value::Bool(value);
             enclosing.insert("lowercase".to_string(), v);
         }
+        if self.auto_stemmer == Some(true) {
+            let v = serde_json::Value::String("auto".to_string());
+            enclosing.insert("stemmer".to_string(), v);
+        }
 
         if let Some(stopwords) = self.stopwords.as_ref() {
             let v = serde_json::Value::Array(
@@ -153,6 +165,10 @@ impl SearchTokenizerFilters {
             write!(buffer, "{}stemmer={value:?}", sep(is_empty)).unwrap();
             is_empty = false;
         }
+        if self.auto_stemmer == Some(true) {
+            write!(buffer, "{}stemmer=auto", sep(is_empty)).unwrap();
+            is_empty = false;
+        }
         if let Some(value) = self.stopwords_language.as_ref() {
             write!(buffer, "{}stopwords_language={value:?}", sep(is_empty)).unwrap();
             is_empty = false;
@@ -313,6 +329,12 @@ impl SearchTokenizer {
             .ok_or_else(|| anyhow::anyhow!("a 'type' must be passed in pg_search tokenizer configuration, not found in: {value:#?}"))?;
 
         let filters = SearchTokenizerFilters::from_json_value(value)?;
+        // only Charabia's tokens carry the script and language that they were detected to be in
+        if filters.auto_stemmer == Some(true) && tokenizer_type != "charabia" {
+            return Err(anyhow::anyhow!(
+                "a 'stemmer' of 'auto' requires the 'charabia' tokenizer, found: {tokenizer_type}"
+            ));
+        }
 
         match tokenizer_type {
             "default" => Ok(SearchTokenizer::Default(filters)),
@@ -372,6 +394,16 @@ impl SearchTokenizer {
         }
     }
 
+    /// This tokenizer without its `stopwords_language` and `stopwords` filters, to analyze a query
+    /// whose stopwords matter, like "to be or not to be".
+    pub fn without_stopwords(mut self) -> Self {
+        if let Some(filters) = self.filters_mut() {
+            filters.stopwords_language = None;
+            filters.stopwords = None;
+        }
+        self
+    }
+
     pub fn to_tantivy_tokenizer(&self) -> Option<tantivy::tokenizer::TextAnalyzer> {
         match self {
             SearchTokenizer::Default(filters) => Some(
@@ -535,13 +567,15 @@ impl SearchTokenizer {
                     .build(),
             ),
             SearchTokenizer::Charabia(filters) => Some(
-                TextAnalyzer::builder(CharabiaTokenizer::default())
-                    .filter(filters.remove_long_filter())
-                    .filter(filters.lower_caser())
-                    .filter(filters.stemmer())
-                    .filter(filters.stopwords_language())
-                    .filter(filters.stopwords())
-                    .build(),
+                TextAnalyzer::builder(CharabiaTokenizer::new(
+                    filters.auto_stemmer.unwrap_or(false),
+                ))
+                .filter(filters.remove_long_filter())
+                .filter(filters.lower_caser())
+                .filter(filters.stemmer())
+                .filter(filters.stopwords_language())
+                .filter(filters.stopwords())
+                .build(),
             ),
         }
     }
@@ -569,6 +603,30 @@ impl SearchTokenizer {
             SearchTokenizer::Charabia(filters) => filters,
         }
     }
+
+    fn filters_mut(&mut self) -> Option<&mut SearchTokenizerFilters> {
+        match self {
+            SearchTokenizer::Default(filters) => Some(filters),
+            SearchTokenizer::Keyword => None,
+            #[allow(deprecated)]
+            SearchTokenizer::Raw(filters) => Some(filters),
+            SearchTokenizer::EnStem(filters) => Some(filters),
+            SearchTokenizer::Stem { filters, .. } => Some(filters),
+            SearchTokenizer::Lowercase(filters) => Some(filters),
+            SearchTokenizer::WhiteSpace(filters) => Some(filters),
+            SearchTokenizer::RegexTokenizer { filters, .. } => Some(filters),
+            SearchTokenizer::ChineseCompatible(filters) => Some(filters),
+            SearchTokenizer::SourceCode(filters) => Some(filters),
+            SearchTokenizer::Ngram { filters, .. } => Some(filters),
+            SearchTokenizer::ChineseLindera(filters) => Some(filters),
+            SearchTokenizer::JapaneseLindera(filters) => Some(filters),
+            SearchTokenizer::KoreanLindera(filters) => Some(filters),
+            #[cfg(feature = "icu")]
+            SearchTokenizer::ICUTokenizer(filters) => Some(filters),
+            SearchTokenizer::Jieba(filters) => Some(filters),
+            SearchTokenizer::Charabia(filters) => Some(filters),
+        }
+    }
 }
 
 pub fn language_to_str(lang: &Language) -> &str {
@@ -681,6 +739,7 @@ mod tests {
             stemmer: None,
             stopwords_language: None,
             stopwords: None,
+            auto_stemmer: None,
         });
         assert_eq!(
             tokenizer.name(),
@@ -711,6 +770,7 @@ mod tests {
                     stemmer: None,
                     stopwords_language: None,
                     stopwords: None,
+                    auto_stemmer: None,
                 }
             }
         );
@@ -731,6 +791,7 @@ mod tests {
                 stemmer: None,
                 stopwords_language: None,
                 stopwords: None,
+                auto_stemmer: None,
             },
         };
 
@@ -771,6 +832,7 @@ mod tests {
                     "花朵".to_string(),
                     "公园".to_string()
                 ]),
+                auto_stemmer: None,
             })
         );
 
@@ -819,6 +881,7 @@ mod tests {
                 stemmer: None,
                 stopwords_language: Some(Language::English),
                 stopwords: None,
+                auto_stemmer: None,
             })
         );
 
@@ -844,4 +907,38 @@ mod tests {
         assert!(tokens.contains(&"读书".to_string()));
         assert!(tokens.contains(&"learning".to_string()));
     }
+
+    #[rstest]
+    fn test_tokenizer_without_stopwords() {
+        use tantivy::tokenizer::TokenStream;
+
+        let json = r#"{
+            "type": "default",
+            "stopwords_language": "English",
+            "stopwords": ["question"]
+        }"#;
+        let tokenizer =
+            SearchTokenizer::from_json_value(&serde_json::from_str(json).unwrap()).unwrap();
+
+        let tokens = |tokenizer: &SearchTokenizer| {
+            let mut analyzer = tokenizer.to_tantivy_tokenizer().unwrap();
+            let mut token_stream =
+                analyzer.token_stream("to be or not to be, that is the question");
+            let mut tokens = Vec::new();
+            while token_stream.advance() {
+                tokens.push(token_stream.token().text.clone());
+            }
+            tokens
+        };
+
+        assert!(tokens(&tokenizer).is_empty());
+        assert_eq!(
+            tokens(&tokenizer.without_stopwords()),
+            vec!["to", "be", "or", "not", "to", "be", "that", "is", "the", "question"]
+        );
+        assert_eq!(
+            SearchTokenizer::Keyword.without_stopwords(),
+            SearchTokenizer::Keyword
+        );
+    }
 }